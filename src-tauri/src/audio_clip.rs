@@ -0,0 +1,335 @@
+// audio_clip.rs - 只转录文件中的一段时间窗口：按需定位解码，段时间换算回原文件时间
+use std::ffi::CStr;
+use std::os::raw::c_char;
+
+use chrono::Utc;
+use tauri::Manager;
+
+use crate::errors::StenoError;
+use crate::model_management::{self, LanguageCompatibility, ModelManager};
+use crate::storage::{TranscriptionConfig, TranscriptionRecord, TranscriptionResult, TranscriptionSegment};
+use crate::storage_commands::StorageState;
+use crate::transcription_jobs::{CancellationToken, TranscriptionJobRegistry};
+use crate::whisper_input::{self, WHISPER_SAMPLE_RATE};
+use crate::{
+    advanced_audio_preprocessing_pipeline, confidence, decode_audio_to_mono_16k, post_process_text,
+    romanization, run_whisper, text_processing, whisper_full_default_params,
+    whisper_full_get_segment_no_speech_prob, whisper_full_get_segment_t0, whisper_full_get_segment_t1,
+    whisper_full_get_segment_text, whisper_full_n_segments,
+    whisper_sampling_strategy_WHISPER_SAMPLING_BEAM_SEARCH, AudioProcessingConfig, RecognitionState,
+    WhisperContextState,
+};
+
+const SAMPLE_RATE: f64 = WHISPER_SAMPLE_RATE as f64;
+/// 容器时长与播放器显示的时长可能相差一帧，终点略超出时截断到文件末尾
+const DURATION_TOLERANCE_SECS: f64 = 0.1;
+
+/// 校验时间窗口并返回实际使用的 (起点, 终点)；文件时长未知时只做基本校验，越界留给解码阶段报告
+pub fn validate_range(start: f64, end: f64, duration: Option<f64>) -> Result<(f64, f64), StenoError> {
+    if !start.is_finite() || !end.is_finite() || start < 0.0 {
+        return Err(StenoError::InvalidArgument(format!("无效的时间范围: {} - {}", start, end)));
+    }
+    if end <= start {
+        return Err(StenoError::InvalidArgument(format!("终点 {:.2} 秒必须晚于起点 {:.2} 秒", end, start)));
+    }
+    let Some(duration) = duration else {
+        return Ok((start, end));
+    };
+    if start >= duration || end > duration + DURATION_TOLERANCE_SECS {
+        return Err(StenoError::InvalidArgument(format!(
+            "时间范围 {:.2}-{:.2} 秒超出音频时长 {:.2} 秒", start, end, duration
+        )));
+    }
+    Ok((start, end.min(duration)))
+}
+
+/// 从 decoded_start 秒开始的解码数据中截取 [start, end) 对应的 (跳过帧数, 保留帧数)
+pub fn window_frames(decoded_start: f64, start: f64, end: f64, sample_rate: u32) -> (usize, usize) {
+    let rate = sample_rate as f64;
+    let skip = ((start - decoded_start).max(0.0) * rate).round() as usize;
+    let take = ((end - start.max(decoded_start)).max(0.0) * rate).round() as usize;
+    (skip, take)
+}
+
+/// 片段内的段时间加上 offset 换算为原文件时间；Whisper 最后一段的结束时间可能超出音频，截断到窗口终点
+pub fn shift_segments(segments: &mut [TranscriptionSegment], offset: f64, window_end: f64) {
+    for segment in segments {
+        segment.start_time = (segment.start_time + offset).min(window_end);
+        segment.end_time = (segment.end_time + offset).clamp(segment.start_time, window_end);
+    }
+}
+
+/// 按转录配置识别一段音频（采样率不是 16kHz 时先重采样），返回的段时间相对这段音频的起点，空段已丢弃
+pub(crate) fn transcribe_samples(
+    audio: &[f32],
+    sample_rate: u32,
+    config: &TranscriptionConfig,
+    whisper_state: &WhisperContextState,
+    cancel_token: &CancellationToken,
+) -> Result<Vec<TranscriptionSegment>, String> {
+    let ctx = whisper_state.ctx.lock().unwrap();
+    if ctx.is_null() {
+        return Err("Whisper 上下文未初始化".to_string());
+    }
+
+    let mut params = unsafe { whisper_full_default_params(whisper_sampling_strategy_WHISPER_SAMPLING_BEAM_SEARCH) };
+    params.print_progress = false;
+    params.print_realtime = false;
+    params.suppress_blank = true;
+    params.token_timestamps = true;
+    params.beam_search.beam_size = if config.mode == "high_precision" { 5 } else { 3 };
+    if let Some(decoding) = config.decoding {
+        decoding.apply(&mut params);
+    }
+    config.temperature_fallback.apply(&mut params);
+    config.segmentation.apply(&mut params);
+    config.suppression.apply(&mut params);
+    cancel_token.install(&mut params);
+
+    let mut audio_copy = whisper_input::ensure_whisper_rate(audio, sample_rate).into_owned();
+    let result = run_whisper(*ctx, params, &mut audio_copy, &config.language, None);
+    if cancel_token.is_cancelled() {
+        return Err("转录已被用户取消".to_string());
+    }
+    if result != 0 {
+        return Err(format!("Whisper 识别失败，错误码: {}", result));
+    }
+
+    let mut segments = Vec::new();
+    for i in 0..unsafe { whisper_full_n_segments(*ctx) } {
+        let text_ptr = unsafe { whisper_full_get_segment_text(*ctx, i) };
+        if text_ptr.is_null() {
+            continue;
+        }
+        let raw = unsafe { CStr::from_ptr(text_ptr as *const c_char) }.to_string_lossy();
+        let text = post_process_text(&text_processing::strip_non_speech(&raw, &config.suppression), &config.language);
+        if text.trim().is_empty() {
+            continue;
+        }
+        // Whisper 时间戳单位为10ms
+        segments.push(TranscriptionSegment {
+            id: String::new(),
+            start_time: unsafe { whisper_full_get_segment_t0(*ctx, i) }.max(0) as f64 / 100.0,
+            end_time: unsafe { whisper_full_get_segment_t1(*ctx, i) }.max(0) as f64 / 100.0,
+            text: text.trim().to_string(),
+            speaker: None,
+            confidence: unsafe { confidence::whisper_segment_confidence(*ctx, i) },
+            no_speech_prob: Some(unsafe { whisper_full_get_segment_no_speech_prob(*ctx, i) } as f64),
+            romanization: None,
+        });
+    }
+    Ok(segments)
+}
+
+/// 片段不做 VAD 裁剪，否则删掉的静音会让段时间与原文件错位
+pub(crate) fn preprocess_clip(samples: Vec<f32>) -> Vec<f32> {
+    let config = AudioProcessingConfig { enable_vad: false, ..AudioProcessingConfig::default() };
+    match advanced_audio_preprocessing_pipeline(samples.clone(), &config) {
+        Ok(processed) if processed.len() == samples.len() => processed,
+        _ => samples,
+    }
+}
+
+/// 用户未传入配置时使用上次的转录配置，并拒绝仅英文模型识别其他语言
+pub(crate) fn resolve_config(app_handle: &tauri::AppHandle, config: Option<TranscriptionConfig>) -> Result<TranscriptionConfig, StenoError> {
+    let config = match config {
+        Some(config) => config,
+        None => app_handle.state::<StorageState>()
+            .with_storage(|storage| storage.get_last_used_config())
+            .unwrap_or_default()
+            .resolve_transcription(None, None),
+    };
+    if let Some(ref decoding) = config.decoding {
+        decoding.validate().map_err(StenoError::InvalidArgument)?;
+    }
+    config.segmentation.validate().map_err(StenoError::InvalidArgument)?;
+
+    let model_path = app_handle.state::<std::sync::Arc<std::sync::Mutex<ModelManager>>>().lock().unwrap().get_current_model_path();
+    match model_management::check_model_language(&model_path, &config.language) {
+        Err(e @ StenoError::ModelLanguageMismatch(_)) => return Err(e),
+        Ok(LanguageCompatibility::Warning { message }) => log::warn!("⚠️ {}", message),
+        _ => {}
+    }
+    Ok(config)
+}
+
+/// 占用识别状态运行一个文件转录任务，结束后无论成功与否都释放
+pub(crate) fn run_exclusive<R>(
+    app_handle: &tauri::AppHandle,
+    job_key: &str,
+    f: impl FnOnce(&CancellationToken) -> Result<R, String>,
+) -> Result<R, StenoError> {
+    let recognition_state = app_handle.state::<RecognitionState>();
+    if !recognition_state.try_start_processing() {
+        return Err(StenoError::InvalidArgument("已有识别任务在进行中".to_string()));
+    }
+    let registry = app_handle.state::<TranscriptionJobRegistry>();
+    let cancel_token = match registry.register(job_key) {
+        Ok(token) => token,
+        Err(e) => {
+            recognition_state.stop_processing();
+            return Err(StenoError::InvalidArgument(e));
+        }
+    };
+    let result = f(&cancel_token);
+    recognition_state.stop_processing();
+    registry.finish(job_key);
+    result.map_err(StenoError::Internal)
+}
+
+fn clock(seconds: f64) -> String {
+    let total = seconds.max(0.0) as u64;
+    format!("{:02}:{:02}", total / 60, total % 60)
+}
+
+/// 由识别结果生成已完成的记录；段ID按记录ID编号，准确率按段置信度估算
+pub(crate) fn completed_record(
+    record_id: String,
+    name: String,
+    file_path: &str,
+    duration: f64,
+    config: TranscriptionConfig,
+    mut segments: Vec<TranscriptionSegment>,
+    processing_time: f64,
+    model_name: Option<String>,
+) -> TranscriptionRecord {
+    for (i, segment) in segments.iter_mut().enumerate() {
+        segment.id = format!("{}_{}", record_id, i);
+    }
+    romanization::annotate_segments(&mut segments, &config);
+    let text = segments.iter().map(|segment| segment.text.as_str()).collect::<Vec<_>>().join("\n");
+    let now = Utc::now();
+    TranscriptionRecord {
+        id: record_id,
+        name,
+        original_file_name: file_path.rsplit(['/', '\\']).next().unwrap_or(file_path).to_string(),
+        file_path: file_path.to_string(),
+        file_size: std::fs::metadata(file_path).map(|m| m.len() as i64).unwrap_or(0),
+        duration: Some(duration),
+        status: "completed".to_string(),
+        progress: 100.0,
+        error_message: None,
+        created_at: now,
+        updated_at: now,
+        tags: Vec::new(),
+        category: None,
+        is_starred: false,
+        config,
+        result: Some(TranscriptionResult {
+            text,
+            processing_time,
+            accuracy: confidence::estimate_record_accuracy(&segments),
+            segments: Some(segments),
+        }),
+        model_name,
+        realtime_factor: crate::transcription_progress::realtime_factor(duration, processing_time),
+        sources: None,
+    }
+}
+
+pub(crate) fn current_model_name(app_handle: &tauri::AppHandle) -> Option<String> {
+    app_handle.state::<std::sync::Arc<std::sync::Mutex<ModelManager>>>()
+        .lock()
+        .ok()
+        .map(|manager| manager.config.lock().unwrap().current_model.clone())
+}
+
+/// 只转录文件中 [start_secs, end_secs) 的内容，生成一条新记录；段时间为原文件中的时间
+#[tauri::command]
+pub async fn transcribe_range(
+    file_path: String,
+    start_secs: f64,
+    end_secs: f64,
+    config: Option<TranscriptionConfig>,
+    app_handle: tauri::AppHandle,
+) -> Result<TranscriptionRecord, StenoError> {
+    validate_range(start_secs, end_secs, None)?;
+    let config = resolve_config(&app_handle, config)?;
+    let record_id = format!("clip_{}", Utc::now().timestamp_millis());
+
+    let app_handle_clone = app_handle.clone();
+    let record = tauri::async_runtime::spawn_blocking(move || {
+        let started = std::time::Instant::now();
+        run_exclusive(&app_handle_clone, &record_id, |cancel_token| {
+            let decoded = decode_audio_to_mono_16k(&file_path, Some((start_secs, end_secs)))?;
+            let (start, end) = validate_range(start_secs, end_secs, decoded.source_duration).map_err(String::from)?;
+            let offset = decoded.start_time;
+            let window_end = end.min(offset + decoded.samples.len() as f64 / SAMPLE_RATE);
+            log::info!("转录片段 {:.2}-{:.2} 秒，实际起点 {:.2} 秒", start, end, offset);
+
+            let audio = preprocess_clip(decoded.samples);
+            let whisper_state = app_handle_clone.state::<WhisperContextState>();
+            let mut segments = transcribe_samples(&audio, WHISPER_SAMPLE_RATE, &config, &whisper_state, cancel_token)?;
+            shift_segments(&mut segments, offset, window_end);
+
+            let file_name = file_path.rsplit(['/', '\\']).next().unwrap_or(&file_path).to_string();
+            Ok(completed_record(
+                record_id.clone(),
+                format!("{} ({}-{})", file_name, clock(offset), clock(window_end)),
+                &file_path,
+                window_end - offset,
+                config,
+                segments,
+                started.elapsed().as_secs_f64(),
+                current_model_name(&app_handle_clone),
+            ))
+        })
+    })
+    .await
+    .map_err(|e| StenoError::Internal(format!("片段转录任务异常: {}", e)))??;
+
+    app_handle.state::<StorageState>().with_storage(|storage| storage.save_record(&record))?;
+    Ok(record)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::test_support::sample_segment;
+
+    fn segment(start_time: f64, end_time: f64) -> TranscriptionSegment {
+        TranscriptionSegment { confidence: Some(0.9), ..sample_segment("", start_time, end_time, "片段") }
+    }
+
+    #[test]
+    fn test_validate_range_against_duration() {
+        assert_eq!(validate_range(10.0, 20.0, Some(60.0)).unwrap(), (10.0, 20.0));
+        assert_eq!(validate_range(50.0, 60.05, Some(60.0)).unwrap(), (50.0, 60.0));
+        assert!(validate_range(50.0, 61.0, Some(60.0)).is_err());
+        assert!(validate_range(60.0, 61.0, Some(60.0)).is_err());
+        assert!(validate_range(20.0, 10.0, Some(60.0)).is_err());
+        assert!(validate_range(-1.0, 10.0, None).is_err());
+        assert!(validate_range(0.0, f64::NAN, None).is_err());
+        assert_eq!(validate_range(100.0, 200.0, None).unwrap(), (100.0, 200.0));
+    }
+
+    #[test]
+    fn test_window_frames_after_seek() {
+        // 未定位：从0秒解码，跳过起点之前的数据
+        assert_eq!(window_frames(0.0, 2.0, 3.5, 16000), (32000, 24000));
+        // 定位到起点前的包边界
+        assert_eq!(window_frames(1.95, 2.0, 3.0, 44100), (2205, 44100));
+        // 定位落在起点之后，以实际起点截取
+        assert_eq!(window_frames(2.5, 2.0, 3.0, 16000), (0, 8000));
+    }
+
+    #[test]
+    fn test_segment_times_offset_to_source() {
+        let mut segments = vec![segment(0.0, 2.4), segment(2.4, 5.0), segment(5.0, 10.3)];
+        shift_segments(&mut segments, 95.0, 105.0);
+        let times: Vec<(f64, f64)> = segments.iter().map(|s| (s.start_time, s.end_time)).collect();
+        assert_eq!(times, vec![(95.0, 97.4), (97.4, 100.0), (100.0, 105.0)]);
+
+        let record = completed_record(
+            "clip_1".to_string(), "a.wav (01:35-01:45)".to_string(), "/tmp/a.wav", 10.0,
+            TranscriptionConfig::default(), segments, 2.0, None,
+        );
+        let result = record.result.unwrap();
+        let segments = result.segments.unwrap();
+        assert_eq!(segments[2].id, "clip_1_2");
+        assert_eq!(segments[0].start_time, 95.0);
+        assert_eq!(result.text, "片段\n片段\n片段");
+        assert_eq!(record.duration, Some(10.0));
+        assert_eq!(record.original_file_name, "a.wav");
+    }
+}
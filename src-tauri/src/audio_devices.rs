@@ -2,6 +2,9 @@ use cpal::traits::{DeviceTrait, HostTrait};
 use serde::{Deserialize, Serialize};
 use tauri::command;
 
+use crate::errors::StenoError;
+use crate::level_meter::{LevelMeter, LevelMeterConfig, LevelReading};
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AudioDevice {
     pub id: String,
@@ -28,7 +31,9 @@ pub struct AudioTestResult {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MicTestState {
     pub phase: String, // "monitoring", "recording", "playback", "completed"
-    pub volume_level: f32,
+    pub volume_level: f32, // 0..1，由平滑后的 dBFS 映射
+    pub dbfs: f32,
+    pub peak_dbfs: f32,
     pub countdown: i32,
     pub message: String,
 }
@@ -41,7 +46,7 @@ pub struct RecordedAudio {
 }
 
 #[command]
-pub async fn get_audio_devices() -> Result<AudioDeviceInfo, String> {
+pub async fn get_audio_devices() -> Result<AudioDeviceInfo, StenoError> {
     let host = cpal::default_host();
     
     let mut input_devices = Vec::new();
@@ -151,15 +156,18 @@ fn get_device_capabilities(device: &cpal::Device) -> (Vec<u32>, Vec<u16>) {
 }
 
 #[command]
-pub async fn test_audio_device(device_id: String, device_type: String) -> Result<AudioTestResult, String> {
+pub async fn test_audio_device(device_id: String, device_type: String) -> Result<AudioTestResult, StenoError> {
     let host = cpal::default_host();
     
     if device_type == "input" {
-        test_input_device(&host, &device_id)
+        if host.default_input_device().is_none() {
+            return Err(StenoError::NoInputDevice);
+        }
+        test_input_device(&host, &device_id).map_err(StenoError::AudioDevice)
     } else if device_type == "output" {
-        test_output_device(&host, &device_id)
+        test_output_device(&host, &device_id).map_err(StenoError::AudioDevice)
     } else {
-        Err("Invalid device type".to_string())
+        Err(StenoError::InvalidArgument("Invalid device type".to_string()))
     }
 }
 
@@ -195,8 +203,9 @@ fn test_input_device(host: &cpal::Host, device_id: &str) -> Result<AudioTestResu
     let sample_format = config.sample_format();
     
     // 音频级别监测
-    let audio_level = Arc::new(Mutex::new(0.0f32));
+    let audio_level = Arc::new(Mutex::new(LevelReading::default()));
     let audio_level_clone = audio_level.clone();
+    let mut level_meter = LevelMeter::new(LevelMeterConfig::default(), stream_config.sample_rate.0, stream_config.channels);
     
     // 创建音频流
     let stream = match sample_format {
@@ -204,9 +213,9 @@ fn test_input_device(host: &cpal::Host, device_id: &str) -> Result<AudioTestResu
             device.build_input_stream(
                 &stream_config,
                 move |data: &[f32], _: &cpal::InputCallbackInfo| {
-                    let level = data.iter().map(|&sample| sample.abs()).sum::<f32>() / data.len() as f32;
+                    let reading = level_meter.process(data);
                     if let Ok(mut current_level) = audio_level_clone.lock() {
-                        *current_level = level;
+                        *current_level = reading;
                     }
                 },
                 |err| eprintln!("Audio stream error: {}", err),
@@ -218,9 +227,9 @@ fn test_input_device(host: &cpal::Host, device_id: &str) -> Result<AudioTestResu
                 &stream_config,
                 move |data: &[i16], _: &cpal::InputCallbackInfo| {
                     let float_data: Vec<f32> = data.iter().map(|&x| x as f32 / 32768.0).collect();
-                    let level = float_data.iter().map(|&sample| sample.abs()).sum::<f32>() / float_data.len() as f32;
+                    let reading = level_meter.process(&float_data);
                     if let Ok(mut current_level) = audio_level_clone.lock() {
-                        *current_level = level;
+                        *current_level = reading;
                     }
                 },
                 |err| eprintln!("Audio stream error: {}", err),
@@ -235,20 +244,21 @@ fn test_input_device(host: &cpal::Host, device_id: &str) -> Result<AudioTestResu
     
     // 测试2秒
     let start_time = Instant::now();
-    let mut max_level = 0.0f32;
+    let mut max_level = LevelReading::default();
     
     while start_time.elapsed() < Duration::from_secs(2) {
         thread::sleep(Duration::from_millis(100));
         if let Ok(level) = audio_level.lock() {
-            max_level = max_level.max(*level);
+            max_level.dbfs = max_level.dbfs.max(level.dbfs);
+            max_level.peak_dbfs = max_level.peak_dbfs.max(level.peak_dbfs);
         }
     }
     
     drop(stream);
     
-    let success = max_level > 0.001; // 检测到声音信号
+    let success = max_level.peak_dbfs > -60.0; // 检测到声音信号
     let message = if success {
-        format!("输入设备测试成功，检测到音频信号 (最大音量: {:.3})", max_level)
+        format!("输入设备测试成功，检测到音频信号 (最大音量: {:.1} dBFS，峰值: {:.1} dBFS)", max_level.dbfs, max_level.peak_dbfs)
     } else {
         "输入设备测试完成，未检测到音频信号，请检查麦克风是否正常工作".to_string()
     };
@@ -256,7 +266,7 @@ fn test_input_device(host: &cpal::Host, device_id: &str) -> Result<AudioTestResu
     Ok(AudioTestResult {
         success,
         message,
-        level: Some(max_level),
+        level: Some(max_level.normalized()),
     })
 }
 
@@ -406,14 +416,14 @@ lazy_static! {
 }
 
 #[command]
-pub async fn stop_audio_test() -> Result<(), String> {
+pub async fn stop_audio_test() -> Result<(), StenoError> {
     STOP_TEST_AUDIO.store(true, Ordering::Relaxed);
     Ok(())
 }
 
 // 启动新的麦克风测试
 #[command]
-pub async fn start_mic_test(device_id: String) -> Result<(), String> {
+pub async fn start_mic_test(device_id: String) -> Result<(), StenoError> {
     use std::thread;
     use std::time::Duration;
     
@@ -427,6 +437,8 @@ pub async fn start_mic_test(device_id: String) -> Result<(), String> {
                 *state = Some(MicTestState {
                     phase: "error".to_string(),
                     volume_level: 0.0,
+                    dbfs: crate::level_meter::SILENCE_DBFS,
+                    peak_dbfs: crate::level_meter::SILENCE_DBFS,
                     countdown: 0,
                     message: format!("测试失败: {}", e),
                 });
@@ -439,17 +451,17 @@ pub async fn start_mic_test(device_id: String) -> Result<(), String> {
 
 // 获取麦克风测试状态
 #[command]
-pub async fn get_mic_test_state() -> Result<Option<MicTestState>, String> {
+pub async fn get_mic_test_state() -> Result<Option<MicTestState>, StenoError> {
     if let Ok(state) = MIC_TEST_STATE.lock() {
         Ok(state.clone())
     } else {
-        Err("Failed to get test state".to_string())
+        Err(StenoError::Internal("Failed to get test state".to_string()))
     }
 }
 
 // 播放录制的音频
 #[command]
-pub async fn play_recorded_audio() -> Result<(), String> {
+pub async fn play_recorded_audio() -> Result<(), StenoError> {
     let host = cpal::default_host();
     
     // 获取录制的音频数据
@@ -458,14 +470,14 @@ pub async fn play_recorded_audio() -> Result<(), String> {
             if let Some(ref recorded) = *audio {
                 recorded.clone()
             } else {
-                return Err("No recorded audio available".to_string());
+                return Err(StenoError::NotFound("No recorded audio available".to_string()));
             }
         } else {
-            return Err("Failed to access recorded audio".to_string());
+            return Err(StenoError::Internal("Failed to access recorded audio".to_string()));
         }
     };
     
-    play_audio_data(&host, audio_data)?;
+    play_audio_data(&host, audio_data).map_err(StenoError::AudioDevice)?;
     
     Ok(())
 }
@@ -508,10 +520,11 @@ fn run_mic_test(device_id: &str) -> Result<(), String> {
     
     // 共享的音频数据和音量
     let audio_buffer = Arc::new(Mutex::new(Vec::<f32>::new()));
-    let current_volume = Arc::new(Mutex::new(0.0f32));
+    let current_volume = Arc::new(Mutex::new(LevelReading::default()));
     
     let audio_buffer_clone = audio_buffer.clone();
     let current_volume_clone = current_volume.clone();
+    let mut level_meter = LevelMeter::new(LevelMeterConfig::default(), sample_rate, channels);
     
     // 创建音频流
     let stream = match sample_format {
@@ -520,9 +533,9 @@ fn run_mic_test(device_id: &str) -> Result<(), String> {
                 &stream_config,
                 move |data: &[f32], _: &cpal::InputCallbackInfo| {
                     // 计算音量
-                    let level = data.iter().map(|&sample| sample.abs()).sum::<f32>() / data.len() as f32;
+                    let reading = level_meter.process(data);
                     if let Ok(mut vol) = current_volume_clone.lock() {
-                        *vol = level;
+                        *vol = reading;
                     }
                     
                     // 存储音频数据
@@ -541,9 +554,9 @@ fn run_mic_test(device_id: &str) -> Result<(), String> {
                     let float_data: Vec<f32> = data.iter().map(|&x| x as f32 / 32768.0).collect();
                     
                     // 计算音量
-                    let level = float_data.iter().map(|&sample| sample.abs()).sum::<f32>() / float_data.len() as f32;
+                    let reading = level_meter.process(&float_data);
                     if let Ok(mut vol) = current_volume_clone.lock() {
-                        *vol = level;
+                        *vol = reading;
                     }
                     
                     // 存储音频数据
@@ -562,7 +575,7 @@ fn run_mic_test(device_id: &str) -> Result<(), String> {
     stream.play().map_err(|e| format!("Failed to start stream: {}", e))?;
     
     // 阶段1: 实时音量监测 (3秒)
-    update_test_state("monitoring", 0.0, 0, "请对着麦克风说话，观察音量指示...");
+    update_test_state("monitoring", LevelReading::default(), 0, "请对着麦克风说话，观察音量指示...");
     
     let start_time = Instant::now();
     while start_time.elapsed() < Duration::from_secs(3) {
@@ -579,7 +592,7 @@ fn run_mic_test(device_id: &str) -> Result<(), String> {
     }
     
     // 阶段2: 录音测试 (5秒)
-    update_test_state("recording", 0.0, 5, "开始录音！请说一段话进行测试...");
+    update_test_state("recording", LevelReading::default(), 5, "开始录音！请说一段话进行测试...");
     
     let start_time = Instant::now();
     while start_time.elapsed() < Duration::from_secs(5) {
@@ -607,17 +620,19 @@ fn run_mic_test(device_id: &str) -> Result<(), String> {
     }
     
     // 阶段3: 准备播放
-    update_test_state("playback", 0.0, 0, "录音完成！点击播放按钮听录音效果");
+    update_test_state("playback", LevelReading::default(), 0, "录音完成！点击播放按钮听录音效果");
     
     Ok(())
 }
 
 // 更新测试状态的辅助函数
-fn update_test_state(phase: &str, volume: f32, countdown: i32, message: &str) {
+fn update_test_state(phase: &str, volume: LevelReading, countdown: i32, message: &str) {
     if let Ok(mut state) = MIC_TEST_STATE.lock() {
         *state = Some(MicTestState {
             phase: phase.to_string(),
-            volume_level: volume,
+            volume_level: volume.normalized(),
+            dbfs: volume.dbfs,
+            peak_dbfs: volume.peak_dbfs,
             countdown,
             message: message.to_string(),
         });
@@ -720,34 +735,39 @@ fn play_audio_data(host: &cpal::Host, audio_data: RecordedAudio) -> Result<(), S
     drop(stream);
     
     // 更新状态为完成
-    update_test_state("completed", 0.0, 0, "播放完成！测试结束");
+    update_test_state("completed", LevelReading::default(), 0, "播放完成！测试结束");
     
     Ok(())
 }
 
 #[command]
-pub async fn set_global_audio_device(device_id: String, device_type: String) -> Result<(), String> {
+pub async fn set_global_audio_device(device_id: String, device_type: String) -> Result<(), StenoError> {
     unsafe {
         if device_type == "input" {
             GLOBAL_INPUT_DEVICE = Some(device_id);
         } else if device_type == "output" {
             GLOBAL_OUTPUT_DEVICE = Some(device_id);
         } else {
-            return Err("Invalid device type".to_string());
+            return Err(StenoError::InvalidArgument("Invalid device type".to_string()));
         }
     }
     Ok(())
 }
 
+/// 录音时读取设置中选定的输入设备ID
+pub fn selected_input_device_id() -> Option<String> {
+    unsafe { GLOBAL_INPUT_DEVICE.clone() }
+}
+
 #[command]
-pub async fn get_global_audio_device(device_type: String) -> Result<Option<String>, String> {
+pub async fn get_global_audio_device(device_type: String) -> Result<Option<String>, StenoError> {
     unsafe {
         Ok(if device_type == "input" {
             GLOBAL_INPUT_DEVICE.clone()
         } else if device_type == "output" {
             GLOBAL_OUTPUT_DEVICE.clone()
         } else {
-            return Err("Invalid device type".to_string());
+            return Err(StenoError::InvalidArgument("Invalid device type".to_string()));
         })
     }
 }
\ No newline at end of file
@@ -0,0 +1,199 @@
+// audio_merge.rs - 将分成多个文件的录音拼接后一次转录，记录中保存各源文件在合并时间轴上的范围
+use chrono::Utc;
+use tauri::Manager;
+
+use crate::audio_clip::{completed_record, current_model_name, preprocess_clip, resolve_config, run_exclusive, transcribe_samples};
+use crate::data_dir;
+use crate::errors::StenoError;
+use crate::recording_writer::{self, RecordingFormat};
+use crate::storage::{SourceBoundary, TranscriptionConfig, TranscriptionRecord, TranscriptionSegment};
+use crate::storage_commands::StorageState;
+use crate::whisper_input::WHISPER_SAMPLE_RATE;
+use crate::{decode_audio_to_mono_16k, WhisperContextState};
+
+const SAMPLE_RATE: f64 = WHISPER_SAMPLE_RATE as f64;
+/// 默认在文件之间插入的静音，避免前一个文件末尾与下一个文件开头被识别成同一句
+const DEFAULT_GAP_SECS: f64 = 1.0;
+const MAX_GAP_SECS: f64 = 10.0;
+
+/// 文件间静音时长，未指定时使用默认值
+fn resolve_gap(gap_secs: Option<f64>) -> Result<f64, StenoError> {
+    match gap_secs {
+        None => Ok(DEFAULT_GAP_SECS),
+        Some(gap) if (0.0..=MAX_GAP_SECS).contains(&gap) => Ok(gap),
+        Some(gap) => Err(StenoError::InvalidArgument(format!("文件间静音须在 0 到 {} 秒之间，当前为 {}", MAX_GAP_SECS, gap))),
+    }
+}
+
+/// 按顺序拼接各文件的 16kHz 音频，文件之间插入 gap_secs 秒静音
+pub fn concatenate(files: Vec<(String, Vec<f32>)>, gap_secs: f64) -> (Vec<f32>, Vec<SourceBoundary>) {
+    let gap = vec![0.0f32; (gap_secs * SAMPLE_RATE) as usize];
+    let mut merged = Vec::with_capacity(files.iter().map(|(_, samples)| samples.len() + gap.len()).sum());
+    let mut sources = Vec::with_capacity(files.len());
+    for (i, (file_path, samples)) in files.into_iter().enumerate() {
+        if i > 0 {
+            merged.extend_from_slice(&gap);
+        }
+        let start_time = merged.len() as f64 / SAMPLE_RATE;
+        merged.extend_from_slice(&samples);
+        sources.push(SourceBoundary { file_path, start_time, end_time: merged.len() as f64 / SAMPLE_RATE });
+    }
+    (merged, sources)
+}
+
+/// 合并时间轴上的时间所属的源文件及其在该文件中的时间；落在文件间静音中的时间归入后一个文件的开头
+pub fn locate_source(sources: &[SourceBoundary], time: f64) -> Option<(usize, f64)> {
+    let index = sources.iter().position(|source| time < source.end_time)?;
+    Some((index, (time - sources[index].start_time).max(0.0)))
+}
+
+/// 段不跨越文件：起点落在静音中的段移到下一个文件开头，结束时间截断到所属文件末尾
+pub fn fit_segments_to_sources(segments: &mut Vec<TranscriptionSegment>, sources: &[SourceBoundary]) {
+    segments.retain_mut(|segment| {
+        let Some((index, _)) = locate_source(sources, segment.start_time) else {
+            return false;
+        };
+        let source = &sources[index];
+        segment.start_time = segment.start_time.max(source.start_time);
+        segment.end_time = segment.end_time.clamp(segment.start_time, source.end_time);
+        true
+    });
+}
+
+fn file_name(path: &str) -> &str {
+    path.rsplit(['/', '\\']).next().unwrap_or(path)
+}
+
+/// 依次解码 paths 中的文件并拼接为一条记录，文件之间插入 gap_secs 秒静音；
+/// 合并后的音频另存到录音目录，作为记录的音频文件
+#[tauri::command]
+pub async fn merge_and_transcribe(
+    paths: Vec<String>,
+    config: Option<TranscriptionConfig>,
+    gap_secs: Option<f64>,
+    app_handle: tauri::AppHandle,
+) -> Result<TranscriptionRecord, StenoError> {
+    if paths.is_empty() {
+        return Err(StenoError::InvalidArgument("没有要合并的文件".to_string()));
+    }
+    let gap_secs = resolve_gap(gap_secs)?;
+    let config = resolve_config(&app_handle, config)?;
+    let recordings_dir = data_dir::resolve_data_dir(&app_handle)?.join("recordings");
+    let record_id = format!("merged_{}", Utc::now().timestamp_millis());
+
+    let app_handle_clone = app_handle.clone();
+    let record = tauri::async_runtime::spawn_blocking(move || {
+        let started = std::time::Instant::now();
+        run_exclusive(&app_handle_clone, &record_id, |cancel_token| {
+            let mut files = Vec::with_capacity(paths.len());
+            for path in &paths {
+                let decoded = decode_audio_to_mono_16k(path, None)
+                    .map_err(|e| format!("{}: {}", file_name(path), e))?;
+                files.push((path.clone(), decoded.samples));
+            }
+            let (merged, sources) = concatenate(files, gap_secs);
+            let duration = merged.len() as f64 / SAMPLE_RATE;
+            log::info!("已拼接 {} 个文件，总时长 {:.1} 秒", sources.len(), duration);
+
+            std::fs::create_dir_all(&recordings_dir).map_err(|e| format!("无法创建录音目录: {}", e))?;
+            let merged_path = recording_writer::write_recording(&recordings_dir.join(&record_id), &merged, RecordingFormat::Pcm16)?;
+
+            let whisper_state = app_handle_clone.state::<WhisperContextState>();
+            let mut segments = transcribe_samples(&preprocess_clip(merged), WHISPER_SAMPLE_RATE, &config, &whisper_state, cancel_token)?;
+            fit_segments_to_sources(&mut segments, &sources);
+
+            let name = format!("{} 等 {} 个文件合并", file_name(&paths[0]), paths.len());
+            let mut record = completed_record(
+                record_id.clone(),
+                name,
+                &merged_path.to_string_lossy(),
+                duration,
+                config,
+                segments,
+                started.elapsed().as_secs_f64(),
+                current_model_name(&app_handle_clone),
+            );
+            record.sources = Some(sources);
+            Ok(record)
+        })
+    })
+    .await
+    .map_err(|e| StenoError::Internal(format!("合并转录任务异常: {}", e)))??;
+
+    app_handle.state::<StorageState>().with_storage(|storage| storage.save_record(&record))?;
+    Ok(record)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::test_support::{sample_record, sample_segment, temp_storage};
+
+    fn seconds(secs: f64) -> Vec<f32> {
+        vec![0.1; (secs * SAMPLE_RATE) as usize]
+    }
+
+    #[test]
+    fn test_segment_times_respect_file_offsets() {
+        let files = vec![
+            ("/tmp/part1.wav".to_string(), seconds(10.0)),
+            ("/tmp/part2.wav".to_string(), seconds(5.5)),
+            ("/tmp/part3.wav".to_string(), seconds(3.0)),
+        ];
+        let (merged, sources) = concatenate(files, DEFAULT_GAP_SECS);
+        assert_eq!(merged.len(), ((10.0 + 5.5 + 3.0 + 2.0 * DEFAULT_GAP_SECS) * SAMPLE_RATE) as usize);
+        let ranges: Vec<(f64, f64)> = sources.iter().map(|s| (s.start_time, s.end_time)).collect();
+        assert_eq!(ranges, vec![(0.0, 10.0), (11.0, 16.5), (17.5, 20.5)]);
+        assert!(merged[(10.5 * SAMPLE_RATE) as usize] == 0.0);
+
+        // 合并时间轴上的段映射回源文件
+        assert_eq!(locate_source(&sources, 3.0), Some((0, 3.0)));
+        assert_eq!(locate_source(&sources, 12.0), Some((1, 1.0)));
+        assert_eq!(locate_source(&sources, 10.4), Some((1, 0.0)));
+        assert_eq!(locate_source(&sources, 19.0), Some((2, 1.5)));
+        assert_eq!(locate_source(&sources, 21.0), None);
+
+        // 跨越文件边界的段被截断到所属文件内
+        let mut segments: Vec<_> = [(8.0, 11.5), (10.6, 13.0), (17.5, 22.0), (20.8, 21.0)]
+            .into_iter()
+            .map(|(start_time, end_time)| sample_segment("", start_time, end_time, "段"))
+            .collect();
+        fit_segments_to_sources(&mut segments, &sources);
+        let times: Vec<(f64, f64)> = segments.iter().map(|s| (s.start_time, s.end_time)).collect();
+        assert_eq!(times, vec![(8.0, 10.0), (11.0, 13.0), (17.5, 20.5)]);
+    }
+
+    #[test]
+    fn test_configurable_gap() {
+        let files = || vec![("/tmp/a.wav".to_string(), seconds(2.0)), ("/tmp/b.wav".to_string(), seconds(3.0))];
+        let (merged, sources) = concatenate(files(), resolve_gap(Some(2.5)).unwrap());
+        assert_eq!(merged.len(), (7.5 * SAMPLE_RATE) as usize);
+        assert_eq!((sources[1].start_time, sources[1].end_time), (4.5, 7.5));
+
+        // 不插入静音时文件首尾相接
+        let (merged, sources) = concatenate(files(), resolve_gap(Some(0.0)).unwrap());
+        assert_eq!(merged.len(), (5.0 * SAMPLE_RATE) as usize);
+        assert_eq!(sources[1].start_time, 2.0);
+
+        assert_eq!(resolve_gap(None).unwrap(), DEFAULT_GAP_SECS);
+        assert!(resolve_gap(Some(-1.0)).is_err());
+        assert!(resolve_gap(Some(60.0)).is_err());
+    }
+
+    #[test]
+    fn test_sources_persisted_with_record() {
+        let (storage, dir) = temp_storage("merged_sources");
+        let mut record = sample_record("merged_1");
+        let (_, sources) = concatenate(vec![
+            ("/tmp/a.wav".to_string(), seconds(2.0)),
+            ("/tmp/b.wav".to_string(), seconds(3.0)),
+        ], DEFAULT_GAP_SECS);
+        record.sources = Some(sources.clone());
+        storage.save_record(&record).unwrap();
+
+        assert_eq!(storage.get_record("merged_1").unwrap().unwrap().sources, Some(sources));
+        storage.save_record(&sample_record("single")).unwrap();
+        assert_eq!(storage.get_record("single").unwrap().unwrap().sources, None);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
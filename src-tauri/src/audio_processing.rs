@@ -219,6 +219,23 @@ impl SmartAudioBuffer {
     }
 }
 
+/// 默认噪声门阈值（块平均能量）
+pub(crate) const DEFAULT_NOISE_GATE_THRESHOLD: f32 = 0.001;
+
+/// 整块平均能量低于阈值时大幅衰减
+pub(crate) fn apply_noise_gate(audio: &mut [f32], threshold: f32) {
+    if audio.is_empty() {
+        return;
+    }
+    let energy = audio.iter().map(|&x| x * x).sum::<f32>() / audio.len() as f32;
+
+    if energy < threshold {
+        for sample in audio.iter_mut() {
+            *sample *= 0.1; // 大幅衰减噪音
+        }
+    }
+}
+
 /// 音频预处理器
 pub struct AudioPreprocessor {
     preemphasis_coeff: f32,
@@ -229,7 +246,7 @@ impl AudioPreprocessor {
     pub fn new() -> Self {
         Self {
             preemphasis_coeff: 0.97,
-            noise_gate_threshold: 0.001,
+            noise_gate_threshold: DEFAULT_NOISE_GATE_THRESHOLD,
         }
     }
 
@@ -240,13 +257,7 @@ impl AudioPreprocessor {
     }
 
     fn apply_noise_gate(&self, audio: &mut [f32]) {
-        let energy = audio.iter().map(|&x| x * x).sum::<f32>() / audio.len() as f32;
-        
-        if energy < self.noise_gate_threshold {
-            for sample in audio.iter_mut() {
-                *sample *= 0.1; // 大幅衰减噪音
-            }
-        }
+        apply_noise_gate(audio, self.noise_gate_threshold);
     }
 
     fn apply_preemphasis(&self, audio: &mut [f32]) {
@@ -0,0 +1,413 @@
+// capture_core.rs - 实时录音共用的设备选择、输入流创建、输入增益、重采样与音量表
+use std::borrow::Cow;
+
+use cpal::traits::{DeviceTrait, HostTrait};
+use cpal::{Device, SampleFormat, SampleRate, StreamConfig, SupportedStreamConfigRange};
+
+use crate::audio_devices;
+use crate::level_meter::{LevelMeter, LevelMeterConfig};
+
+/// Whisper 需要的采样率
+pub const TARGET_SAMPLE_RATE: u32 = crate::whisper_input::WHISPER_SAMPLE_RATE;
+
+/// 按设备原生采样率保存录音时的采样率上限，更高的采样率对语音没有意义且文件过大
+pub const MAX_NATIVE_SAMPLE_RATE: u32 = 48000;
+
+/// 输入增益上限，防止误设过大的倍数把底噪放大成削波
+pub const MAX_INPUT_GAIN: f32 = 8.0;
+
+/// 选定的采集格式；采样率或声道与目标不一致时在回调中转换
+#[derive(Debug, Clone)]
+pub struct CaptureFormat {
+    pub stream_config: StreamConfig,
+    pub sample_format: SampleFormat,
+}
+
+impl CaptureFormat {
+    pub fn sample_rate(&self) -> u32 {
+        self.stream_config.sample_rate.0
+    }
+
+    pub fn channels(&self) -> u16 {
+        self.stream_config.channels
+    }
+
+    pub fn needs_resample(&self) -> bool {
+        self.sample_rate() != TARGET_SAMPLE_RATE
+    }
+
+    /// 录音文件的采样率：保存原生采样率时为设备采样率，否则与识别一致
+    pub fn recording_sample_rate(&self, native: bool) -> u32 {
+        if native { self.sample_rate() } else { TARGET_SAMPLE_RATE }
+    }
+
+    /// 设备原始数据（交错多声道）到16kHz单声道的转换器，跨回调保持重采样状态
+    pub fn converter(&self) -> TargetConverter {
+        TargetConverter {
+            channels: self.channels(),
+            resampler: StreamResampler::new(self.sample_rate()),
+        }
+    }
+
+    /// 按设备原始格式计时的音量表
+    pub fn level_meter(&self, config: LevelMeterConfig) -> LevelMeter {
+        LevelMeter::new(config, self.sample_rate(), self.channels())
+    }
+}
+
+/// 优先选择可直接以16kHz采集的配置（声道越少越好），否则使用第一个配置的最低采样率并重采样
+pub fn select_capture_format(configs: &[SupportedStreamConfigRange]) -> Option<CaptureFormat> {
+    let target = SampleRate(TARGET_SAMPLE_RATE);
+    let direct = configs.iter()
+        .filter(|c| c.channels() >= 1 && c.min_sample_rate() <= target && c.max_sample_rate() >= target)
+        .min_by_key(|c| c.channels());
+
+    let (range, sample_rate) = match direct {
+        Some(range) => (range, target),
+        None => {
+            let range = configs.iter().find(|c| c.channels() >= 1)?;
+            (range, range.min_sample_rate())
+        }
+    };
+
+    Some(CaptureFormat {
+        stream_config: range.clone().with_sample_rate(sample_rate).config(),
+        sample_format: range.sample_format(),
+    })
+}
+
+/// 保存原生采样率录音时选择可用的最高采样率（不超过 MAX_NATIVE_SAMPLE_RATE），同采样率下声道越少越好
+pub fn select_native_capture_format(configs: &[SupportedStreamConfigRange]) -> Option<CaptureFormat> {
+    let rate_of = |c: &SupportedStreamConfigRange| {
+        c.max_sample_rate().0.min(MAX_NATIVE_SAMPLE_RATE).max(c.min_sample_rate().0)
+    };
+    let range = configs.iter()
+        .filter(|c| c.channels() >= 1)
+        .max_by_key(|c| (rate_of(c), std::cmp::Reverse(c.channels())))?;
+
+    Some(CaptureFormat {
+        stream_config: range.clone().with_sample_rate(SampleRate(rate_of(range))).config(),
+        sample_format: range.sample_format(),
+    })
+}
+
+/// 设置中保存的设备ID形如 "input_3"
+fn parse_input_device_index(device_id: &str) -> Option<usize> {
+    device_id.strip_prefix("input_").and_then(|s| s.parse().ok())
+}
+
+/// 按设置中保存的设备ID查找输入设备
+pub fn find_input_device(host: &cpal::Host, device_id: &str) -> Option<Device> {
+    parse_input_device_index(device_id).and_then(|index| {
+        host.input_devices().ok().and_then(|mut devices| devices.nth(index))
+    })
+}
+
+/// 获取选定的输入设备；未选择或选定设备不可用时回退到默认设备
+pub fn select_input_device(host: &cpal::Host) -> Result<Device, String> {
+    if let Some(device_id) = audio_devices::selected_input_device_id() {
+        match find_input_device(host, &device_id) {
+            Some(device) => return Ok(device),
+            None => log::warn!("Selected input device {} not found, falling back to default", device_id),
+        }
+    }
+
+    host.default_input_device()
+        .ok_or_else(|| "No input device available".to_string())
+}
+
+/// 按设备报告的配置协商采集格式；设备不支持16kHz时以其支持的采样率采集，回调中重采样。
+/// 只有没有任何可用配置时才报错，错误中列出设备报告的配置便于排查
+pub fn negotiate_capture_format(configs: &[SupportedStreamConfigRange], native_rate: bool) -> Result<CaptureFormat, String> {
+    let format = if native_rate { select_native_capture_format(configs) } else { select_capture_format(configs) };
+    format.ok_or_else(|| {
+        if configs.is_empty() {
+            "Input device reports no supported configurations".to_string()
+        } else {
+            let reported: Vec<String> = configs.iter()
+                .map(|c| format!("{}ch {}-{}Hz {:?}", c.channels(), c.min_sample_rate().0, c.max_sample_rate().0, c.sample_format()))
+                .collect();
+            format!("No usable input configuration (device reports: {})", reported.join(", "))
+        }
+    })
+}
+
+/// 打开输入设备并选定采集格式；native_rate 为 true 时按原生采样率采集，供保存高保真录音
+pub fn open_input(host: &cpal::Host, native_rate: bool) -> Result<(Device, CaptureFormat), String> {
+    let device = select_input_device(host)?;
+    let configs: Vec<_> = device.supported_input_configs()
+        .map_err(|e| format!("Failed to get supported configs: {}", e))?
+        .collect();
+    let format = negotiate_capture_format(&configs, native_rate)?;
+    Ok((device, format))
+}
+
+/// 按设备采样格式创建输入流，回调统一收到 f32 样本（设备原始采样率与声道）
+pub fn build_input_stream<F>(device: &Device, format: &CaptureFormat, mut on_samples: F) -> Result<cpal::Stream, String>
+where
+    F: FnMut(&[f32]) + Send + 'static,
+{
+    let config = &format.stream_config;
+    let on_error = |err: cpal::StreamError| log::error!("Audio stream error: {}", err);
+
+    let stream = match format.sample_format {
+        SampleFormat::I8 => device.build_input_stream(
+            config,
+            move |data: &[i8], _: &cpal::InputCallbackInfo| {
+                let samples: Vec<f32> = data.iter().map(|&x| x as f32 / 128.0).collect();
+                on_samples(&samples);
+            },
+            on_error,
+            None,
+        ),
+        SampleFormat::I16 => device.build_input_stream(
+            config,
+            move |data: &[i16], _: &cpal::InputCallbackInfo| {
+                let samples: Vec<f32> = data.iter().map(|&x| x as f32 / 32768.0).collect();
+                on_samples(&samples);
+            },
+            on_error,
+            None,
+        ),
+        SampleFormat::I32 => device.build_input_stream(
+            config,
+            move |data: &[i32], _: &cpal::InputCallbackInfo| {
+                let samples: Vec<f32> = data.iter().map(|&x| x as f32 / 2147483648.0).collect();
+                on_samples(&samples);
+            },
+            on_error,
+            None,
+        ),
+        SampleFormat::F32 => device.build_input_stream(
+            config,
+            move |data: &[f32], _: &cpal::InputCallbackInfo| on_samples(data),
+            on_error,
+            None,
+        ),
+        other => return Err(format!("Unsupported sample format: {:?}", other)),
+    };
+
+    stream.map_err(|e| format!("Failed to build audio stream: {}", e))
+}
+
+/// 对设备原始样本施加输入增益并截断到 [-1, 1] 避免溢出；增益为 1 时不复制
+pub fn apply_gain(samples: &[f32], gain: f32) -> Cow<'_, [f32]> {
+    let gain = if gain.is_finite() { gain.clamp(0.0, MAX_INPUT_GAIN) } else { 1.0 };
+    if gain == 1.0 {
+        return Cow::Borrowed(samples);
+    }
+    Cow::Owned(samples.iter().map(|&x| (x * gain).clamp(-1.0, 1.0)).collect())
+}
+
+/// 交错多声道数据按帧取平均
+pub fn downmix_to_mono(samples: &[f32], channels: u16) -> Vec<f32> {
+    let channels = channels.max(1) as usize;
+    if channels == 1 {
+        return samples.to_vec();
+    }
+    samples.chunks(channels)
+        .map(|frame| frame.iter().sum::<f32>() / frame.len() as f32)
+        .collect()
+}
+
+pub struct TargetConverter {
+    channels: u16,
+    resampler: StreamResampler,
+}
+
+impl TargetConverter {
+    pub fn process(&mut self, samples: &[f32]) -> Vec<f32> {
+        self.process_split(samples).1
+    }
+
+    /// 同时返回设备采样率的单声道数据（用于保存录音）和16kHz单声道数据（用于识别）
+    pub fn process_split(&mut self, samples: &[f32]) -> (Vec<f32>, Vec<f32>) {
+        let mono = downmix_to_mono(samples, self.channels);
+        let target = self.resampler.process(&mono);
+        (mono, target)
+    }
+}
+
+/// 线性插值重采样到16kHz。每个回调的数据块单独重采样时，块首的相位归零且块间没有插值，
+/// 拼接处会出现跳变（爆音）；这里保留上一块的最后一个样本和下一个输出点的相位，使相邻输出连续
+pub struct StreamResampler {
+    source_rate: u32,
+    tail: Option<f32>, // 上一块的最后一个输入样本
+    position: f64,     // 下一个输出点相对 tail（无 tail 时相对本块起点）的位置
+}
+
+impl StreamResampler {
+    pub fn new(source_rate: u32) -> Self {
+        Self {
+            source_rate,
+            tail: None,
+            position: 0.0,
+        }
+    }
+
+    pub fn process(&mut self, samples: &[f32]) -> Vec<f32> {
+        if self.source_rate == TARGET_SAMPLE_RATE || self.source_rate == 0 || samples.is_empty() {
+            return samples.to_vec();
+        }
+        let ratio = self.source_rate as f64 / TARGET_SAMPLE_RATE as f64;
+        let input: Vec<f32> = self.tail.into_iter().chain(samples.iter().copied()).collect();
+        let last = (input.len() - 1) as f64;
+
+        // 只输出两侧样本都已到达的点，落在块末尾之后的点留到下一块
+        let mut output = Vec::with_capacity((samples.len() as f64 / ratio) as usize + 1);
+        let mut position = self.position;
+        while position < last {
+            let index = position as usize;
+            let fraction = (position - index as f64) as f32;
+            output.push(input[index] + (input[index + 1] - input[index]) * fraction);
+            position += ratio;
+        }
+
+        self.position = position - last;
+        self.tail = input.last().copied();
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cpal::SupportedBufferSize;
+    use crate::recording_writer::{RecordingFormat, StreamingWavWriter};
+
+    fn range(channels: u16, min: u32, max: u32, sample_format: SampleFormat) -> SupportedStreamConfigRange {
+        SupportedStreamConfigRange::new(channels, SampleRate(min), SampleRate(max), SupportedBufferSize::Unknown, sample_format)
+    }
+
+    #[test]
+    fn test_selects_16khz_config() {
+        let configs = vec![
+            range(2, 44100, 48000, SampleFormat::F32),
+            range(2, 8000, 48000, SampleFormat::F32),
+            range(1, 8000, 48000, SampleFormat::I16),
+        ];
+        let format = select_capture_format(&configs).unwrap();
+        assert_eq!(format.sample_rate(), TARGET_SAMPLE_RATE);
+        assert_eq!(format.channels(), 1);
+        assert_eq!(format.sample_format, SampleFormat::I16);
+        assert!(!format.needs_resample());
+    }
+
+    #[test]
+    fn test_falls_back_to_resampling() {
+        let format = select_capture_format(&[range(2, 44100, 48000, SampleFormat::F32)]).unwrap();
+        assert_eq!(format.sample_rate(), 44100);
+        assert!(format.needs_resample());
+
+        // 1秒的双声道 44.1kHz 数据转换后约为1秒的16kHz单声道
+        let converted = format.converter().process(&vec![0.25f32; 44100 * 2]);
+        assert!((converted.len() as i64 - 16000).abs() <= 1);
+        assert!(converted.iter().all(|&x| (x - 0.25).abs() < 1e-6));
+
+        assert!(select_capture_format(&[]).is_none());
+    }
+
+    #[test]
+    fn test_48khz_only_device_negotiates() {
+        // 只支持 48kHz 的设备（常见于 USB 麦克风和部分声卡）
+        let configs = vec![range(2, 48000, 48000, SampleFormat::I16), range(1, 48000, 48000, SampleFormat::F32)];
+        let format = negotiate_capture_format(&configs, false).unwrap();
+        assert_eq!(format.sample_rate(), 48000);
+        assert!(format.needs_resample());
+
+        let converted = format.converter().process(&vec![0.5f32; 48000 * format.channels() as usize]);
+        assert!((converted.len() as i64 - TARGET_SAMPLE_RATE as i64).abs() <= 1);
+        assert_eq!(negotiate_capture_format(&configs, true).unwrap().sample_rate(), 48000);
+
+        let error = negotiate_capture_format(&[], false).unwrap_err();
+        assert!(error.contains("no supported configurations"), "{}", error);
+    }
+
+    #[test]
+    fn test_native_rate_recording_keeps_device_rate() {
+        let configs = vec![
+            range(2, 8000, 96000, SampleFormat::F32),
+            range(1, 44100, 48000, SampleFormat::I16),
+        ];
+        let format = select_native_capture_format(&configs).unwrap();
+        assert_eq!((format.sample_rate(), format.channels()), (48000, 1));
+        assert_eq!(format.recording_sample_rate(true), 48000);
+        assert_eq!(format.recording_sample_rate(false), TARGET_SAMPLE_RATE);
+        assert_eq!(select_native_capture_format(&[range(2, 96000, 192000, SampleFormat::F32)]).unwrap().sample_rate(), 96000);
+
+        // 1秒 48kHz 双声道：保存的录音保持 48kHz，识别数据为 16kHz
+        let format = select_native_capture_format(&[range(2, 44100, 48000, SampleFormat::F32)]).unwrap();
+        let (recording, inference) = format.converter().process_split(&vec![0.25f32; 48000 * 2]);
+        assert_eq!(recording.len(), 48000);
+        assert!((inference.len() as i64 - TARGET_SAMPLE_RATE as i64).abs() <= 1);
+
+        let dir = std::env::temp_dir().join(format!("steno_native_rate_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut writer = StreamingWavWriter::create(&dir.join("recording"), RecordingFormat::Pcm16, format.recording_sample_rate(true)).unwrap();
+        writer.append(&recording).unwrap();
+        let path = writer.finalize().unwrap();
+        let reader = hound::WavReader::open(&path).unwrap();
+        assert_eq!(reader.spec().sample_rate, 48000);
+        assert_eq!(reader.len(), 48000);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_downmix_and_resample() {
+        assert_eq!(downmix_to_mono(&[1.0, 0.0, 0.5, 0.5], 2), vec![0.5, 0.5]);
+        let ramp: Vec<f32> = (0..32).map(|i| i as f32).collect();
+        let resampled = StreamResampler::new(32000).process(&ramp);
+        assert_eq!(resampled.len(), 16);
+        assert_eq!(resampled[3], 6.0);
+        assert_eq!(parse_input_device_index("input_2"), Some(2));
+        assert_eq!(parse_input_device_index("output_2"), None);
+    }
+
+    #[test]
+    fn test_gain_scales_samples() {
+        let samples = [0.1, -0.2, 0.0, 0.05];
+        assert_eq!(&*apply_gain(&samples, 2.0), &[0.2, -0.4, 0.0, 0.1]);
+        assert_eq!(&*apply_gain(&samples, 0.5), &[0.05, -0.1, 0.0, 0.025]);
+        assert!(matches!(apply_gain(&samples, 1.0), Cow::Borrowed(_)));
+        assert!(matches!(apply_gain(&samples, f32::NAN), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn test_gain_clamps_to_unit_range() {
+        let gained = apply_gain(&[0.6, -0.9, 0.3, -1.0], 4.0);
+        assert_eq!(&*gained, &[1.0, -1.0, 1.0, -1.0]);
+        // 超过上限的增益按上限处理
+        assert_eq!(&*apply_gain(&[0.01], 100.0), &[0.01 * MAX_INPUT_GAIN]);
+    }
+
+    #[test]
+    fn test_resampler_continuous_across_callbacks() {
+        // 44.1kHz 的 440Hz 正弦波按不规则大小分块送入，模拟采集回调
+        let source_rate = 44100;
+        let sine = |n: f64| (2.0 * std::f64::consts::PI * 440.0 * n / source_rate as f64).sin() as f32;
+        let input: Vec<f32> = (0..source_rate).map(|n| sine(n as f64)).collect();
+
+        let mut resampler = StreamResampler::new(source_rate);
+        let mut output = Vec::new();
+        let mut offset = 0;
+        for size in [441, 1000, 37, 512, 2048].iter().cycle() {
+            if offset >= input.len() {
+                break;
+            }
+            let end = (offset + size).min(input.len());
+            output.extend(resampler.process(&input[offset..end]));
+            offset = end;
+        }
+
+        // 输出与连续重采样的理想值一致，相邻样本的差不超过正弦波的最大斜率
+        let ratio = source_rate as f64 / TARGET_SAMPLE_RATE as f64;
+        let max_step = 2.0 * std::f32::consts::PI * 440.0 / TARGET_SAMPLE_RATE as f32 * 1.05;
+        for (i, pair) in output.windows(2).enumerate() {
+            assert!((pair[1] - pair[0]).abs() <= max_step, "第 {} 个样本处跳变 {}", i, pair[1] - pair[0]);
+        }
+        for (i, &sample) in output.iter().enumerate() {
+            assert!((sample - sine(i as f64 * ratio)).abs() < 1e-3, "第 {} 个样本偏差过大", i);
+        }
+        assert!((output.len() as i64 - TARGET_SAMPLE_RATE as i64).abs() <= 1);
+    }
+}
@@ -0,0 +1,234 @@
+// confidence.rs - 基于 Whisper token 概率的置信度与准确率估算（实时与文件转录共用）
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::errors::StenoError;
+use crate::storage::{StorageService, TranscriptionSegment};
+use crate::storage_commands::StorageState;
+use crate::{
+    whisper_context, whisper_full_get_segment_t0, whisper_full_get_segment_t1,
+    whisper_full_get_token_id, whisper_full_get_token_p, whisper_full_n_tokens, whisper_token_eot,
+};
+
+/// 概率下限，避免 ln(0)
+const MIN_TOKEN_PROB: f64 = 1e-6;
+const THRESHOLDS_SETTINGS_KEY: &str = "confidence_thresholds";
+
+/// 界面按置信度着色时使用的档位
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConfidenceBand {
+    Low,
+    Medium,
+    High,
+}
+
+/// 各档位的置信度下限：不低于 high 为 High，不低于 medium 为 Medium，不低于 low 为 Low；
+/// 低于 low 的结果不标注档位
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ConfidenceThresholds {
+    pub low: f64,
+    pub medium: f64,
+    pub high: f64,
+}
+
+impl Default for ConfidenceThresholds {
+    fn default() -> Self {
+        Self { low: 0.0, medium: 0.6, high: 0.85 }
+    }
+}
+
+impl ConfidenceThresholds {
+    /// 下限须在 [0, 1] 内且 low ≤ medium ≤ high
+    pub fn validate(&self) -> Result<(), StenoError> {
+        let in_range = [self.low, self.medium, self.high].iter().all(|value| (0.0..=1.0).contains(value));
+        if !in_range || self.low > self.medium || self.medium > self.high {
+            return Err(StenoError::InvalidArgument(format!(
+                "置信度阈值须满足 0 ≤ low ≤ medium ≤ high ≤ 1，当前为 {}/{}/{}",
+                self.low, self.medium, self.high
+            )));
+        }
+        Ok(())
+    }
+
+    /// 置信度所属档位；没有置信度或低于 low 时返回 None
+    pub fn band(&self, confidence: Option<f64>) -> Option<ConfidenceBand> {
+        let confidence = confidence.filter(|value| value.is_finite())?;
+        if confidence >= self.high {
+            Some(ConfidenceBand::High)
+        } else if confidence >= self.medium {
+            Some(ConfidenceBand::Medium)
+        } else if confidence >= self.low {
+            Some(ConfidenceBand::Low)
+        } else {
+            None
+        }
+    }
+
+    /// 读取保存的阈值；未设置或格式无效时使用默认值
+    pub fn load(storage: &StorageService) -> rusqlite::Result<Self> {
+        Ok(storage.get_setting(THRESHOLDS_SETTINGS_KEY)?
+            .and_then(|json| serde_json::from_str::<Self>(&json).ok())
+            .filter(|thresholds| thresholds.validate().is_ok())
+            .unwrap_or_default())
+    }
+
+    pub fn save(&self, storage: &StorageService) -> rusqlite::Result<()> {
+        let json = serde_json::to_string(self)
+            .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+        storage.set_setting(THRESHOLDS_SETTINGS_KEY, &json)
+    }
+}
+
+/// 由 token 概率计算段置信度：log 概率均值取指数（几何平均）
+pub fn confidence_from_token_probs(probs: &[f32]) -> Option<f64> {
+    if probs.is_empty() {
+        return None;
+    }
+    let mean_log_prob = probs.iter()
+        .map(|&p| (p as f64).clamp(MIN_TOKEN_PROB, 1.0).ln())
+        .sum::<f64>() / probs.len() as f64;
+    Some(mean_log_prob.exp())
+}
+
+/// 按时长加权的置信度累加器
+#[derive(Debug, Clone, Default)]
+pub struct ConfidenceAccumulator {
+    weighted_sum: f64,
+    total_duration: f64,
+}
+
+impl ConfidenceAccumulator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, duration: f64, confidence: f64) {
+        if duration <= 0.0 || !confidence.is_finite() {
+            return;
+        }
+        self.weighted_sum += duration * confidence.clamp(0.0, 1.0);
+        self.total_duration += duration;
+    }
+
+    /// 加权平均置信度；没有有效样本时返回 None
+    pub fn average(&self) -> Option<f64> {
+        if self.total_duration > 0.0 {
+            Some(self.weighted_sum / self.total_duration)
+        } else {
+            None
+        }
+    }
+}
+
+/// 记录的准确率估算：各段置信度按段时长加权平均
+pub fn estimate_record_accuracy(segments: &[TranscriptionSegment]) -> Option<f64> {
+    let mut accumulator = ConfidenceAccumulator::new();
+    for segment in segments {
+        if let Some(confidence) = segment.confidence {
+            accumulator.add(segment.end_time - segment.start_time, confidence);
+        }
+    }
+    accumulator.average()
+}
+
+#[tauri::command]
+pub async fn get_confidence_thresholds(
+    storage_state: State<'_, StorageState>,
+) -> Result<ConfidenceThresholds, StenoError> {
+    storage_state.with_storage(ConfidenceThresholds::load)
+}
+
+#[tauri::command]
+pub async fn set_confidence_thresholds(
+    thresholds: ConfidenceThresholds,
+    storage_state: State<'_, StorageState>,
+) -> Result<ConfidenceThresholds, StenoError> {
+    thresholds.validate()?;
+    storage_state.with_storage(|storage| thresholds.save(storage))?;
+    Ok(thresholds)
+}
+
+/// 读取最近一次 whisper_full 结果中某段的置信度（忽略特殊 token）
+pub unsafe fn whisper_segment_confidence(ctx: *mut whisper_context, i_segment: i32) -> Option<f64> {
+    let eot = whisper_token_eot(ctx);
+    let n_tokens = whisper_full_n_tokens(ctx, i_segment);
+    let probs: Vec<f32> = (0..n_tokens)
+        .filter(|&i| whisper_full_get_token_id(ctx, i_segment, i) < eot)
+        .map(|i| whisper_full_get_token_p(ctx, i_segment, i))
+        .collect();
+    confidence_from_token_probs(&probs)
+}
+
+/// 某段的时长（秒），Whisper 时间戳单位为10ms
+pub unsafe fn whisper_segment_duration(ctx: *mut whisper_context, i_segment: i32) -> f64 {
+    let t0 = whisper_full_get_segment_t0(ctx, i_segment);
+    let t1 = whisper_full_get_segment_t1(ctx, i_segment);
+    (t1 - t0).max(0) as f64 / 100.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::test_support::sample_segment;
+
+    fn segment(start_time: f64, end_time: f64, confidence: Option<f64>) -> TranscriptionSegment {
+        TranscriptionSegment { confidence, ..sample_segment(&format!("seg_{}", start_time), start_time, end_time, "测试") }
+    }
+
+    #[test]
+    fn test_high_confidence_record_scores_higher() {
+        let high = vec![segment(0.0, 5.0, Some(0.95)), segment(5.0, 8.0, Some(0.9))];
+        let low = vec![segment(0.0, 5.0, Some(0.4)), segment(5.0, 8.0, Some(0.55))];
+
+        let high_accuracy = estimate_record_accuracy(&high).unwrap();
+        let low_accuracy = estimate_record_accuracy(&low).unwrap();
+        assert!(high_accuracy > low_accuracy);
+
+        // 长段权重更大
+        let weighted = estimate_record_accuracy(&[segment(0.0, 9.0, Some(1.0)), segment(9.0, 10.0, Some(0.0))]).unwrap();
+        assert!((weighted - 0.9).abs() < 1e-9);
+
+        assert_eq!(estimate_record_accuracy(&[segment(0.0, 1.0, None)]), None);
+    }
+
+    #[test]
+    fn test_confidence_from_token_probs() {
+        assert_eq!(confidence_from_token_probs(&[]), None);
+        assert!((confidence_from_token_probs(&[1.0, 1.0]).unwrap() - 1.0).abs() < 1e-9);
+        // 几何平均对低概率 token 更敏感
+        let confidence = confidence_from_token_probs(&[0.9, 0.1]).unwrap();
+        assert!(confidence < 0.5);
+    }
+
+    #[test]
+    fn test_confidence_bands() {
+        let defaults = ConfidenceThresholds::default();
+        let bands: Vec<Option<ConfidenceBand>> = [Some(0.95), Some(0.85), Some(0.7), Some(0.6), Some(0.2), None]
+            .into_iter()
+            .map(|confidence| defaults.band(confidence))
+            .collect();
+        use ConfidenceBand::*;
+        assert_eq!(bands, vec![Some(High), Some(High), Some(Medium), Some(Medium), Some(Low), None]);
+
+        let strict = ConfidenceThresholds { low: 0.3, medium: 0.7, high: 0.9 };
+        assert_eq!(strict.band(Some(0.85)), Some(Medium));
+        assert_eq!(strict.band(Some(0.5)), Some(Low));
+        assert_eq!(strict.band(Some(0.1)), None);
+        assert_eq!(strict.band(Some(f64::NAN)), None);
+
+        assert!(ConfidenceThresholds { low: 0.5, medium: 0.4, high: 0.9 }.validate().is_err());
+        assert!(ConfidenceThresholds { low: 0.0, medium: 0.6, high: 1.2 }.validate().is_err());
+    }
+
+    #[test]
+    fn test_thresholds_persisted_in_settings() {
+        let (storage, dir) = crate::storage::test_support::temp_storage("confidence_thresholds");
+        assert_eq!(ConfidenceThresholds::load(&storage).unwrap(), ConfidenceThresholds::default());
+
+        let custom = ConfidenceThresholds { low: 0.2, medium: 0.5, high: 0.8 };
+        custom.save(&storage).unwrap();
+        assert_eq!(ConfidenceThresholds::load(&storage).unwrap(), custom);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,181 @@
+// data_dir.rs - 应用数据目录的统一解析：数据库、日志、录音等共用同一套重试与回退逻辑
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::errors::StenoError;
+
+const ATTEMPTS_PER_SOURCE: u32 = 3;
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(50);
+
+type DirSource<'a> = (&'a str, &'a dyn Fn() -> Result<PathBuf, String>);
+
+/// 按顺序尝试各来源，每个来源最多重试 attempts 次（等待时间递增）；全部失败时返回列出各来源错误的 StorageUnavailable
+fn resolve_from(sources: &[DirSource<'_>], attempts: u32, base_delay: Duration) -> Result<PathBuf, StenoError> {
+    let mut failures = Vec::new();
+    for (index, (name, source)) in sources.iter().enumerate() {
+        for attempt in 1..=attempts {
+            match source() {
+                Ok(dir) => {
+                    if index > 0 {
+                        log::warn!("⚠️ 主数据目录不可用，改用{}: {}", name, dir.display());
+                    }
+                    return Ok(dir);
+                }
+                Err(e) => {
+                    log::warn!("⚠️ 获取{}失败 (尝试 {}/{}): {}", name, attempt, attempts, e);
+                    if attempt < attempts {
+                        std::thread::sleep(base_delay * attempt);
+                    } else {
+                        failures.push(format!("{}: {}", name, e));
+                    }
+                }
+            }
+        }
+    }
+    let message = format!("无法确定应用数据目录，所有来源均失败（{}）", failures.join("；"));
+    log::error!("❌ {}", message);
+    Err(StenoError::StorageUnavailable(message))
+}
+
+/// 主来源失败时的回退目录：系统本地数据目录下的 Steno
+fn local_fallback() -> Result<PathBuf, String> {
+    dirs::data_local_dir()
+        .map(|dir| dir.join("Steno"))
+        .ok_or_else(|| "系统未提供本地数据目录".to_string())
+}
+
+/// 应用数据目录（录音、规则、模型目录等子目录所在位置），各平台均使用 Tauri 的 AppData 目录
+pub fn resolve_data_dir(app_handle: &tauri::AppHandle) -> Result<PathBuf, StenoError> {
+    let primary = || {
+        use tauri::Manager;
+        app_handle.path().app_data_dir().map_err(|e| e.to_string())
+    };
+    resolve_from(&[("应用数据目录", &primary), ("本地数据目录", &local_fallback)], ATTEMPTS_PER_SOURCE, RETRY_BASE_DELAY)
+}
+
+/// 数据库所在目录：Windows 使用安装目录策略下的 data 子目录，其他平台与应用数据目录相同
+pub fn resolve_database_dir(app_handle: &tauri::AppHandle) -> Result<PathBuf, StenoError> {
+    #[cfg(target_os = "windows")]
+    {
+        let _ = app_handle;
+        let primary = || windows_install_dir().map(|dir| dir.join("data"));
+        resolve_from(&[("安装数据目录", &primary), ("本地数据目录", &local_fallback)], ATTEMPTS_PER_SOURCE, RETRY_BASE_DELAY)
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        resolve_data_dir(app_handle)
+    }
+}
+
+/// 日志目录的上级目录：Windows 使用安装目录策略，其他平台与应用数据目录相同
+pub fn resolve_log_root(app_handle: &tauri::AppHandle) -> Result<PathBuf, StenoError> {
+    #[cfg(target_os = "windows")]
+    {
+        let _ = app_handle;
+        resolve_from(&[("安装目录", &windows_install_dir), ("本地数据目录", &local_fallback)], ATTEMPTS_PER_SOURCE, RETRY_BASE_DELAY)
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        resolve_data_dir(app_handle)
+    }
+}
+
+/// 应用数据目录下的子目录，不存在时创建
+pub fn resolve_subdir(app_handle: &tauri::AppHandle, name: &str) -> Result<PathBuf, StenoError> {
+    let dir = resolve_data_dir(app_handle)?.join(name);
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| StenoError::StorageUnavailable(format!("无法创建目录 {}: {}", dir.display(), e)))?;
+    Ok(dir)
+}
+
+/// Windows专用：获取合适的数据存储目录
+#[cfg(target_os = "windows")]
+fn windows_install_dir() -> Result<PathBuf, String> {
+    // 策略1：优先使用用户AppData目录（推荐，符合Windows最佳实践）
+    // %APPDATA%\Roaming\Steno 或 %LOCALAPPDATA%\Steno
+    if let Some(app_data) = dirs::data_dir() {
+        let steno_data_dir = app_data.join("Steno");
+        log::info!("✓ Windows用户数据模式：使用AppData目录 {}", steno_data_dir.display());
+        return Ok(steno_data_dir);
+    }
+
+    // 策略2：备选使用Local AppData目录（更快的本地存储）
+    if let Some(local_data) = dirs::data_local_dir() {
+        let steno_data_dir = local_data.join("Steno");
+        log::info!("✓ Windows本地数据模式：使用LocalAppData目录 {}", steno_data_dir.display());
+        return Ok(steno_data_dir);
+    }
+
+    // 策略3：便携模式检查（仅当可执行文件目录可写时使用）
+    if let Ok(exe_path) = std::env::current_exe() {
+        if let Some(exe_dir) = exe_path.parent() {
+            // 检查是否存在便携模式标记文件或可执行文件目录可写
+            let portable_marker = exe_dir.join("portable.txt");
+            let test_file = exe_dir.join("write_test.tmp");
+
+            if portable_marker.exists() || std::fs::File::create(&test_file).is_ok() {
+                let _ = std::fs::remove_file(&test_file); // 清理测试文件
+                log::info!("✓ Windows便携模式：使用可执行文件目录 {}", exe_dir.display());
+                return Ok(exe_dir.to_path_buf());
+            } else {
+                log::debug!("📍 可执行文件目录无写权限，使用用户数据目录模式");
+            }
+        }
+    }
+
+    // 策略4：使用用户文档目录
+    if let Some(docs_dir) = dirs::document_dir() {
+        let steno_data_dir = docs_dir.join("Steno");
+        log::info!("✓ Windows文档目录模式：{}", steno_data_dir.display());
+        return Ok(steno_data_dir);
+    }
+
+    // 策略5：开发环境回退到工作目录
+    if let Ok(current_dir) = std::env::current_dir() {
+        log::warn!("⚠️ Windows开发模式：使用当前目录 {}", current_dir.display());
+        return Ok(current_dir);
+    }
+
+    // 最后备选：相对路径
+    log::error!("❌ Windows路径回退：使用相对路径");
+    Ok(PathBuf::from("."))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    #[test]
+    fn test_falls_back_when_primary_fails() {
+        let primary_calls = Cell::new(0);
+        let primary = || {
+            primary_calls.set(primary_calls.get() + 1);
+            Err::<PathBuf, _>("路径解析失败".to_string())
+        };
+        let fallback = || Ok(PathBuf::from("/tmp/steno_fallback"));
+
+        let dir = resolve_from(&[("应用数据目录", &primary), ("本地数据目录", &fallback)], 3, Duration::ZERO).unwrap();
+        assert_eq!(dir, PathBuf::from("/tmp/steno_fallback"));
+        assert_eq!(primary_calls.get(), 3);
+
+        // 主来源重试中恢复时不会用到备用目录
+        let flaky_calls = Cell::new(0);
+        let flaky = || {
+            flaky_calls.set(flaky_calls.get() + 1);
+            if flaky_calls.get() < 2 { Err("暂时不可用".to_string()) } else { Ok(PathBuf::from("/tmp/steno_primary")) }
+        };
+        let dir = resolve_from(&[("应用数据目录", &flaky), ("本地数据目录", &fallback)], 3, Duration::ZERO).unwrap();
+        assert_eq!(dir, PathBuf::from("/tmp/steno_primary"));
+    }
+
+    #[test]
+    fn test_all_sources_failing_reports_each_error() {
+        let primary = || Err::<PathBuf, _>("路径解析失败".to_string());
+        let fallback = || Err::<PathBuf, _>("系统未提供本地数据目录".to_string());
+        let error = resolve_from(&[("应用数据目录", &primary), ("本地数据目录", &fallback)], 2, Duration::ZERO).unwrap_err();
+        assert_eq!(error.code(), "storage_unavailable");
+        let message = error.to_string();
+        assert!(message.contains("应用数据目录: 路径解析失败") && message.contains("本地数据目录: 系统未提供本地数据目录"), "{}", message);
+    }
+}
@@ -1,6 +1,8 @@
-use tauri::command;
+use tauri::{command, State};
 use crate::database_manager::{DatabaseManager, DatabaseInfo};
-use std::path::PathBuf;
+use crate::storage_commands::StorageState;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 
 /// 获取数据库信息
 #[command]
@@ -31,40 +33,50 @@ pub async fn list_database_backups(app_handle: tauri::AppHandle) -> Result<Vec<B
     let db_manager = DatabaseManager::new(&app_handle)
         .map_err(|e| format!("Failed to create database manager: {}", e))?;
     
-    let backups = db_manager.list_backups();
-    let backup_infos: Vec<BackupInfo> = backups.into_iter().map(|(path, time)| {
-        let filename = path.file_name()
-            .unwrap_or_default()
-            .to_string_lossy()
-            .to_string();
-        
-        let created_at = chrono::DateTime::<chrono::Utc>::from(time).to_rfc3339();
-        
-        // 从文件大小获取备份大小
-        let size = std::fs::metadata(&path)
-            .map(|m| m.len())
-            .unwrap_or(0);
-        
-        BackupInfo {
-            filename,
-            path: path.to_string_lossy().to_string(),
-            created_at,
-            size,
-        }
-    }).collect();
+    Ok(collect_backups(&db_manager))
+}
+
+/// 列出所有备份文件（最新在前），供设置页展示；与 list_database_backups 相同
+#[command]
+pub async fn list_backups(app_handle: tauri::AppHandle) -> Result<Vec<BackupInfo>, String> {
+    list_database_backups(app_handle).await
+}
+
+/// 立即创建一个手动备份，返回新备份的信息
+#[command]
+pub async fn create_backup_now(app_handle: tauri::AppHandle) -> Result<BackupInfo, String> {
+    let db_manager = DatabaseManager::new(&app_handle)
+        .map_err(|e| format!("Failed to create database manager: {}", e))?;
+    
+    backup_now(&db_manager)
+}
+
+/// 从指定备份恢复数据库；必须由前端确认后传入 confirmed = true。
+/// 覆盖前关闭存储的全部连接，恢复后重新初始化并按备份版本执行迁移
+#[command]
+pub async fn restore_backup_from(
+    app_handle: tauri::AppHandle,
+    storage_state: State<'_, StorageState>,
+    backup_path: String,
+    confirmed: bool,
+) -> Result<DatabaseInfo, String> {
+    let db_manager = DatabaseManager::new(&app_handle)
+        .map_err(|e| format!("Failed to create database manager: {}", e))?;
     
-    Ok(backup_infos)
+    restore_confirmed(&storage_state, &db_manager, &PathBuf::from(backup_path), confirmed)
 }
 
 /// 恢复数据库备份
 #[command]
-pub async fn restore_database_backup(app_handle: tauri::AppHandle, backup_path: String) -> Result<String, String> {
+pub async fn restore_database_backup(
+    app_handle: tauri::AppHandle,
+    storage_state: State<'_, StorageState>,
+    backup_path: String,
+) -> Result<String, String> {
     let db_manager = DatabaseManager::new(&app_handle)
         .map_err(|e| format!("Failed to create database manager: {}", e))?;
     
-    let path = PathBuf::from(backup_path);
-    db_manager.restore_backup(&path)
-        .map_err(|e| format!("Failed to restore backup: {}", e))?;
+    restore_confirmed(&storage_state, &db_manager, &PathBuf::from(backup_path), true)?;
     
     Ok("数据库备份恢复成功".to_string())
 }
@@ -81,6 +93,16 @@ pub async fn vacuum_database(app_handle: tauri::AppHandle) -> Result<String, Str
     Ok("数据库真空操作完成".to_string())
 }
 
+/// 设置备份总大小上限（字节），返回轮换释放的字节数
+#[command]
+pub async fn set_backup_size_budget(app_handle: tauri::AppHandle, size_budget: u64) -> Result<u64, String> {
+    let db_manager = DatabaseManager::new(&app_handle)
+        .map_err(|e| format!("Failed to create database manager: {}", e))?;
+    
+    db_manager.set_backup_size_budget(size_budget)
+        .map_err(|e| format!("Failed to set backup size budget: {}", e))
+}
+
 /// 检查数据库完整性
 #[command]
 pub async fn check_database_integrity(app_handle: tauri::AppHandle) -> Result<bool, String> {
@@ -121,4 +143,116 @@ pub struct BackupInfo {
     pub path: String,
     pub created_at: String,
     pub size: u64,
-}
\ No newline at end of file
+}
+
+fn backup_info(path: &Path, time: SystemTime) -> BackupInfo {
+    let filename = path.file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string();
+    
+    // 从文件大小获取备份大小
+    let size = std::fs::metadata(path)
+        .map(|m| m.len())
+        .unwrap_or(0);
+    
+    BackupInfo {
+        filename,
+        path: path.to_string_lossy().to_string(),
+        created_at: chrono::DateTime::<chrono::Utc>::from(time).to_rfc3339(),
+        size,
+    }
+}
+
+fn collect_backups(db_manager: &DatabaseManager) -> Vec<BackupInfo> {
+    db_manager.list_backups()
+        .into_iter()
+        .map(|(path, time)| backup_info(&path, time))
+        .collect()
+}
+
+fn backup_now(db_manager: &DatabaseManager) -> Result<BackupInfo, String> {
+    let backup_path = db_manager.create_backup("manual")
+        .map_err(|e| format!("Failed to create backup: {}", e))?;
+    let created = std::fs::metadata(&backup_path)
+        .and_then(|m| m.modified())
+        .unwrap_or_else(|_| SystemTime::now());
+    
+    Ok(backup_info(&backup_path, created))
+}
+
+fn restore_confirmed(storage_state: &StorageState, db_manager: &DatabaseManager, backup_path: &PathBuf, confirmed: bool) -> Result<DatabaseInfo, String> {
+    if !confirmed {
+        return Err("恢复备份会覆盖当前数据库，请确认后重试".to_string());
+    }
+    
+    storage_state.reopen_after(db_manager, || {
+        db_manager.restore_backup(backup_path)
+            .map_err(|e| format!("Failed to restore backup: {}", e))
+    })?;
+    db_manager.get_database_info()
+        .map_err(|e| format!("Failed to get database info: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::StorageService;
+    use std::fs;
+
+    fn temp_manager(name: &str) -> (DatabaseManager, PathBuf) {
+        let dir = std::env::temp_dir().join(format!("steno_db_cmd_test_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let manager = DatabaseManager::with_paths(dir.join("steno.db"), dir.join("backups")).unwrap();
+        manager.initialize_database().unwrap();
+        (manager, dir)
+    }
+
+    #[test]
+    fn test_backup_commands_return_serializable_shapes() {
+        let (manager, dir) = temp_manager("shapes");
+
+        let created = backup_now(&manager).unwrap();
+        assert!(created.filename.starts_with("steno_backup_") && created.filename.ends_with("_manual.db"));
+        assert!(created.size > 0);
+        assert!(chrono::DateTime::parse_from_rfc3339(&created.created_at).is_ok());
+
+        let listed = collect_backups(&manager);
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].path, created.path);
+
+        let json = serde_json::to_value(&listed[0]).unwrap();
+        for key in ["filename", "path", "created_at", "size"] {
+            assert!(json.get(key).is_some(), "missing {}", key);
+        }
+
+        let info = serde_json::to_value(manager.get_database_info().unwrap()).unwrap();
+        assert_eq!(info["backup_count"], 1);
+        assert!(chrono::DateTime::parse_from_rfc3339(info["created_at"].as_str().unwrap()).is_ok());
+
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_restore_requires_confirmation() {
+        let (manager, dir) = temp_manager("restore_confirm");
+        let state = StorageState::new();
+        *state.0.write().unwrap() = Some(StorageService::with_manager(&manager).unwrap());
+        let backup = PathBuf::from(backup_now(&manager).unwrap().path);
+
+        assert!(restore_confirmed(&state, &manager, &backup, false).is_err());
+        assert_eq!(collect_backups(&manager).len(), 1);
+
+        // 备份之后写入的数据在恢复后消失，存储服务仍可继续使用
+        state.with_storage(|storage| storage.set_setting("marker", "after_backup")).unwrap();
+
+        // 恢复前会额外生成 before_restore 备份
+        let info = restore_confirmed(&state, &manager, &backup, true).unwrap();
+        assert_eq!(info.backup_count, 2);
+        assert_eq!(info.version, DatabaseManager::CURRENT_VERSION);
+        assert_eq!(state.with_storage(|storage| storage.get_setting("marker")).unwrap(), None);
+
+        let _ = fs::remove_dir_all(dir);
+    }
+}
@@ -1,8 +1,9 @@
 use rusqlite::{Connection, Result};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use chrono::Utc;
 use serde::{Deserialize, Serialize};
 use std::fs;
+use crate::storage::{insert_segments, read_segments, TranscriptionSegment, INSERT_SEGMENT_SQL};
 
 /// 数据库版本和迁移管理器
 pub struct DatabaseManager {
@@ -20,9 +21,12 @@ pub struct DatabaseInfo {
 
 impl DatabaseManager {
     /// 当前数据库版本
-    const CURRENT_VERSION: i32 = 1;
+    const CURRENT_VERSION: i32 = 8;
     /// 最大备份文件数量
     const MAX_BACKUPS: usize = 10;
+    /// 备份总大小默认上限（500MB），可通过 database_metadata 中的 backup_size_budget 配置
+    const DEFAULT_BACKUP_SIZE_BUDGET: u64 = 500 * 1024 * 1024;
+    const BACKUP_SIZE_BUDGET_KEY: &'static str = "backup_size_budget";
 
     pub fn new(app_handle: &tauri::AppHandle) -> Result<Self> {
         // 使用统一的可靠路径获取方法
@@ -50,111 +54,21 @@ impl DatabaseManager {
         })
     }
 
-    /// 可靠的应用数据目录获取 - Windows使用安装目录，其他平台使用AppData
-    fn get_app_data_dir_reliable(app_handle: &tauri::AppHandle) -> Result<PathBuf> {
-        #[cfg(target_os = "windows")]
-        {
-            // Windows: 使用安装目录下的data子目录
-            for attempt in 1..=3 {
-                match Self::get_windows_install_dir() {
-                    Ok(install_dir) => {
-                        let data_dir = install_dir.join("data");
-                        log::info!("✓ 获取Windows安装目录成功 (尝试 {}): {}", attempt, data_dir.display());
-                        return Ok(data_dir);
-                    },
-                    Err(e) => {
-                        log::warn!("⚠️ 获取Windows安装目录失败 (尝试 {}/3): {}", attempt, e);
-                        if attempt < 3 {
-                            std::thread::sleep(std::time::Duration::from_millis(50 * attempt as u64));
-                        }
-                    }
-                }
-            }
-            
-            Err(rusqlite::Error::SqliteFailure(
-                rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_CANTOPEN),
-                Some("Failed to get Windows install directory after multiple attempts".to_string())
-            ))
-        }
-        
-        #[cfg(not(target_os = "windows"))]
-        {
-            // macOS/Linux: 继续使用AppData目录
-            use tauri::Manager;
-            for attempt in 1..=3 {
-                match app_handle.path().app_data_dir() {
-                    Ok(dir) => {
-                        log::info!("✓ 获取应用数据目录成功 (尝试 {}): {}", attempt, dir.display());
-                        return Ok(dir);
-                    },
-                    Err(e) => {
-                        log::warn!("⚠️ 获取应用数据目录失败 (尝试 {}/3): {}", attempt, e);
-                        if attempt < 3 {
-                            // 渐进式等待时间
-                            std::thread::sleep(std::time::Duration::from_millis(50 * attempt as u64));
-                        }
-                    }
-                }
-            }
-            
-            Err(rusqlite::Error::SqliteFailure(
-                rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_CANTOPEN),
-                Some("Failed to get app data directory after multiple attempts".to_string())
-            ))
-        }
+    /// 使用指定的数据库文件和备份目录（测试时指向临时目录）
+    pub(crate) fn with_paths(db_path: PathBuf, backup_dir: PathBuf) -> Result<Self> {
+        Self::create_directory_reliable(&backup_dir, "backup")?;
+        Ok(Self {
+            db_path,
+            backup_dir,
+        })
     }
 
-    /// Windows专用：获取合适的数据存储目录
-    #[cfg(target_os = "windows")]
-    fn get_windows_install_dir() -> Result<PathBuf> {
-        // 策略1：优先使用用户AppData目录（推荐，符合Windows最佳实践）
-        // %APPDATA%\Roaming\Steno 或 %LOCALAPPDATA%\Steno
-        if let Some(app_data) = dirs::data_dir() {
-            let steno_data_dir = app_data.join("Steno");
-            log::info!("✓ Windows用户数据模式：使用AppData目录 {}", steno_data_dir.display());
-            return Ok(steno_data_dir);
-        }
-        
-        // 策略2：备选使用Local AppData目录（更快的本地存储）
-        if let Some(local_data) = dirs::data_local_dir() {
-            let steno_data_dir = local_data.join("Steno");
-            log::info!("✓ Windows本地数据模式：使用LocalAppData目录 {}", steno_data_dir.display());
-            return Ok(steno_data_dir);
-        }
-        
-        // 策略3：便携模式检查（仅当可执行文件目录可写时使用）
-        if let Ok(exe_path) = std::env::current_exe() {
-            if let Some(exe_dir) = exe_path.parent() {
-                // 检查是否存在便携模式标记文件或可执行文件目录可写
-                let portable_marker = exe_dir.join("portable.txt");
-                let test_file = exe_dir.join("write_test.tmp");
-                
-                if portable_marker.exists() || std::fs::File::create(&test_file).is_ok() {
-                    let _ = std::fs::remove_file(&test_file); // 清理测试文件
-                    log::info!("✓ Windows便携模式：使用可执行文件目录 {}", exe_dir.display());
-                    return Ok(exe_dir.to_path_buf());
-                } else {
-                    log::debug!("📍 可执行文件目录无写权限，使用用户数据目录模式");
-                }
-            }
-        }
-        
-        // 策略4：使用用户文档目录
-        if let Some(docs_dir) = dirs::document_dir() {
-            let steno_data_dir = docs_dir.join("Steno");
-            log::info!("✓ Windows文档目录模式：{}", steno_data_dir.display());
-            return Ok(steno_data_dir);
-        }
-        
-        // 策略5：开发环境回退到工作目录
-        if let Ok(current_dir) = std::env::current_dir() {
-            log::warn!("⚠️ Windows开发模式：使用当前目录 {}", current_dir.display());
-            return Ok(current_dir);
-        }
-        
-        // 最后备选：相对路径
-        log::error!("❌ Windows路径回退：使用相对路径");
-        Ok(PathBuf::from("."))
+    /// 可靠的应用数据目录获取，重试与回退逻辑见 data_dir::resolve_database_dir
+    fn get_app_data_dir_reliable(app_handle: &tauri::AppHandle) -> Result<PathBuf> {
+        crate::data_dir::resolve_database_dir(app_handle).map_err(|e| rusqlite::Error::SqliteFailure(
+            rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_CANTOPEN),
+            Some(e.to_string())
+        ))
     }
 
     /// 统一的目录创建方法 - 确保关键目录存在
@@ -221,7 +135,11 @@ impl DatabaseManager {
         }
 
         // 清理旧备份文件
-        self.cleanup_old_backups();
+        let size_budget = self.get_backup_size_budget(&conn);
+        let reclaimed = self.cleanup_old_backups(Self::MAX_BACKUPS, size_budget);
+        if reclaimed > 0 {
+            println!("✓ 备份轮换释放空间: {} 字节", reclaimed);
+        }
 
         Ok(conn)
     }
@@ -257,7 +175,10 @@ impl DatabaseManager {
                 is_starred BOOLEAN DEFAULT 0,
                 config TEXT NOT NULL,
                 processing_time REAL,
-                accuracy REAL
+                accuracy REAL,
+                model_name TEXT,
+                realtime_factor REAL,
+                sources TEXT
             )",
             [],
         )?;
@@ -273,6 +194,13 @@ impl DatabaseManager {
             [],
         )?;
 
+        // 创建分段表（版本2）
+        Self::create_segments_table(conn)?;
+
+        // 创建录音会话表（版本7）
+        Self::create_recording_sessions_table(conn)?;
+        Self::create_record_extension_tables(conn)?;
+
         // 创建提示词模板表
         conn.execute(
             "CREATE TABLE IF NOT EXISTS prompt_templates (
@@ -287,7 +215,8 @@ impl DatabaseManager {
                 created_at TEXT NOT NULL,
                 updated_at TEXT NOT NULL,
                 usage_count INTEGER DEFAULT 0,
-                is_active BOOLEAN DEFAULT 1
+                is_active BOOLEAN DEFAULT 1,
+                last_used_at TEXT
             )",
             [],
         )?;
@@ -298,6 +227,138 @@ impl DatabaseManager {
         Ok(())
     }
 
+    /// 创建规范化的分段表及索引
+    fn create_segments_table(conn: &Connection) -> Result<()> {
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS transcription_segments (
+                record_id TEXT NOT NULL,
+                idx INTEGER NOT NULL,
+                segment_id TEXT NOT NULL,
+                start_time REAL NOT NULL,
+                end_time REAL NOT NULL,
+                text TEXT NOT NULL,
+                speaker TEXT,
+                confidence REAL,
+                no_speech_prob REAL,
+                romanization TEXT,
+                PRIMARY KEY (record_id, idx),
+                FOREIGN KEY (record_id) REFERENCES transcription_records(id) ON DELETE CASCADE
+            )",
+            [],
+        )?;
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_segments_record_time ON transcription_segments(record_id, start_time, end_time)",
+            [],
+        )?;
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_segments_speaker ON transcription_segments(record_id, speaker)",
+            [],
+        )?;
+        Ok(())
+    }
+
+    /// 创建录音会话表：超过单段时长上限后自动拆分的录音，各段记录属于同一会话
+    fn create_recording_sessions_table(conn: &Connection) -> Result<()> {
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS recording_sessions (
+                record_id TEXT PRIMARY KEY,
+                session_id TEXT NOT NULL,
+                part INTEGER NOT NULL
+            )",
+            [],
+        )?;
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_recording_sessions_session ON recording_sessions(session_id, part)",
+            [],
+        )?;
+        Ok(())
+    }
+
+    /// 创建应用设置、说话人名称、波形峰值缓存与说话人分离汇总表
+    fn create_record_extension_tables(conn: &Connection) -> Result<()> {
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS app_settings (
+                key TEXT PRIMARY KEY,
+                value TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            )",
+            [],
+        )?;
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS speaker_names (
+                record_id TEXT NOT NULL,
+                speaker_id TEXT NOT NULL,
+                display_name TEXT NOT NULL,
+                PRIMARY KEY (record_id, speaker_id),
+                FOREIGN KEY (record_id) REFERENCES transcription_records(id) ON DELETE CASCADE
+            )",
+            [],
+        )?;
+        // 峰值随音频文件的大小与修改时间一起保存，音频被合并或重录后缓存自动失效
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS waveform_peaks (
+                record_id TEXT NOT NULL,
+                buckets INTEGER NOT NULL,
+                source_size INTEGER NOT NULL,
+                source_modified INTEGER NOT NULL,
+                peaks BLOB NOT NULL,
+                PRIMARY KEY (record_id, buckets),
+                FOREIGN KEY (record_id) REFERENCES transcription_records(id) ON DELETE CASCADE
+            )",
+            [],
+        )?;
+        // 实时录音停止时写入，此时记录本身可能还没有保存，因此不设外键；未保存记录留下的汇总在启动时清理
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS diarization_summaries (
+                record_id TEXT PRIMARY KEY,
+                summary TEXT NOT NULL
+            )",
+            [],
+        )?;
+        Ok(())
+    }
+
+    /// 将 transcription_contents 中的 JSON 分段逐条迁移到分段表
+    fn migrate_segment_blobs(conn: &Connection) -> Result<usize> {
+        let blobs: Vec<(String, String)> = {
+            let mut stmt = conn.prepare(
+                "SELECT record_id, segments FROM transcription_contents WHERE segments IS NOT NULL"
+            )?;
+            let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+            rows.collect::<Result<Vec<_>>>()?
+        };
+
+        // 分段表在本次迁移中新建，无需逐条记录先删除；插入语句只准备一次，迁移完的原字段一次性清空
+        let mut migrated: Vec<String> = Vec::new();
+        let mut insert = conn.prepare(INSERT_SEGMENT_SQL)?;
+        for (record_id, json) in blobs {
+            match serde_json::from_str::<Vec<TranscriptionSegment>>(&json) {
+                Ok(segments) => {
+                    insert_segments(&mut insert, &record_id, &segments)?;
+                    migrated.push(record_id);
+                }
+                Err(e) => {
+                    // 无法解析的旧数据保留在原字段中
+                    eprintln!("警告: 记录 {} 的分段数据无法解析，保留原始JSON: {}", record_id, e);
+                }
+            }
+        }
+        conn.execute(
+            "UPDATE transcription_contents SET segments = NULL
+             WHERE record_id IN (SELECT value FROM json_each(?1))",
+            [serde_json::to_string(&migrated).unwrap_or_default()],
+        )?;
+        Ok(migrated.len())
+    }
+
+    fn column_exists(conn: &Connection, table: &str, column: &str) -> Result<bool> {
+        conn.query_row(
+            "SELECT COUNT(*) FROM pragma_table_info(?1) WHERE name = ?2",
+            [table, column],
+            |row| row.get::<_, i32>(0).map(|count| count > 0)
+        )
+    }
+
     /// 创建数据库索引
     fn create_indexes(&self, conn: &Connection) -> Result<()> {
         let indexes = vec![
@@ -383,6 +444,48 @@ impl DatabaseManager {
                         // 例如：ALTER TABLE transcription_records ADD COLUMN new_field TEXT;
                     }
                 },
+                2 => {
+                    // 迁移到版本2：分段从 JSON 字段迁移到规范化的分段表
+                    Self::create_segments_table(&tx)?;
+                    let migrated = Self::migrate_segment_blobs(&tx)?;
+                    println!("✓ 已迁移 {} 条记录的分段数据", migrated);
+                },
+                3 => {
+                    // 迁移到版本3：记录提示词最近使用时间
+                    if !Self::column_exists(&tx, "prompt_templates", "last_used_at")? {
+                        tx.execute("ALTER TABLE prompt_templates ADD COLUMN last_used_at TEXT", [])?;
+                    }
+                },
+                4 => {
+                    // 迁移到版本4：记录转录使用的模型及实时倍率
+                    if !Self::column_exists(&tx, "transcription_records", "model_name")? {
+                        tx.execute("ALTER TABLE transcription_records ADD COLUMN model_name TEXT", [])?;
+                    }
+                    if !Self::column_exists(&tx, "transcription_records", "realtime_factor")? {
+                        tx.execute("ALTER TABLE transcription_records ADD COLUMN realtime_factor REAL", [])?;
+                    }
+                },
+                5 => {
+                    // 迁移到版本5：合并转录记录的源文件范围
+                    if !Self::column_exists(&tx, "transcription_records", "sources")? {
+                        tx.execute("ALTER TABLE transcription_records ADD COLUMN sources TEXT", [])?;
+                    }
+                },
+                6 => {
+                    // 迁移到版本6：分段的拼音注音
+                    if !Self::column_exists(&tx, "transcription_segments", "romanization")? {
+                        tx.execute("ALTER TABLE transcription_segments ADD COLUMN romanization TEXT", [])?;
+                    }
+                },
+                7 => {
+                    // 迁移到版本7：录音会话表（此前由存储服务启动时创建，已存在时保持不变）
+                    Self::create_recording_sessions_table(&tx)?;
+                },
+                8 => {
+                    // 迁移到版本8：此前由存储服务启动时创建的附属表；旧的波形缓存没有音频指纹，直接重建
+                    tx.execute("DROP TABLE IF EXISTS waveform_peaks", [])?;
+                    Self::create_record_extension_tables(&tx)?;
+                },
                 _ => {
                     return Err(rusqlite::Error::SqliteFailure(
                         rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_MISUSE),
@@ -415,11 +518,16 @@ impl DatabaseManager {
         let backup_filename = format!("steno_backup_{}_{}.db", timestamp, suffix);
         let backup_path = self.backup_dir.join(backup_filename);
 
-        fs::copy(&self.db_path, &backup_path)
-            .map_err(|e| rusqlite::Error::SqliteFailure(
+        // VACUUM INTO 生成包含 WAL 中已提交数据的一致快照，且结果为单文件（非 WAL）数据库。
+        // 目标文件须不存在，同一秒内重复备份时覆盖先前的文件
+        if backup_path.exists() {
+            fs::remove_file(&backup_path).map_err(|e| rusqlite::Error::SqliteFailure(
                 rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_IOERR),
                 Some(format!("备份创建失败: {}", e))
             ))?;
+        }
+        let conn = Connection::open(&self.db_path)?;
+        conn.execute("VACUUM INTO ?1", [backup_path.to_string_lossy().into_owned()])?;
 
         println!("✓ 数据库备份已创建: {}", backup_path.display());
         Ok(backup_path)
@@ -434,27 +542,80 @@ impl DatabaseManager {
             ));
         }
 
-        // 验证备份文件是否为有效的SQLite数据库
-        {
-            let _test_conn = Connection::open(backup_path)?;
-        }
+        // 验证备份文件完整性，失败时保留当前数据库不变
+        self.verify_backup(backup_path)?;
 
         // 创建当前数据库的备份（恢复前）
         if self.db_path.exists() {
             self.create_backup("before_restore")?;
         }
 
-        // 恢复备份
+        // 恢复备份：调用方需先关闭所有连接。覆盖后删除旧的 -wal/-shm，避免旧日志回放到恢复后的数据库
         fs::copy(backup_path, &self.db_path)
             .map_err(|e| rusqlite::Error::SqliteFailure(
                 rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_IOERR),
                 Some(format!("数据库恢复失败: {}", e))
             ))?;
+        for sidecar in Self::wal_sidecars(&self.db_path) {
+            match fs::remove_file(&sidecar) {
+                Ok(()) => {}
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err(rusqlite::Error::SqliteFailure(
+                    rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_IOERR),
+                    Some(format!("清理日志文件失败 {}: {}", sidecar.display(), e))
+                )),
+            }
+        }
 
         println!("✓ 数据库已从备份恢复: {}", backup_path.display());
         Ok(())
     }
 
+    /// WAL 模式下数据库旁的 -wal 与 -shm 文件
+    fn wal_sidecars(db_path: &Path) -> [PathBuf; 2] {
+        let with_suffix = |suffix: &str| {
+            let mut name = db_path.as_os_str().to_owned();
+            name.push(suffix);
+            PathBuf::from(name)
+        };
+        [with_suffix("-wal"), with_suffix("-shm")]
+    }
+
+    /// 校验备份：完整性检查、外键检查，以及版本不高于应用支持的版本
+    pub fn verify_backup(&self, backup_path: &PathBuf) -> Result<()> {
+        let conn = Connection::open_with_flags(backup_path, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+
+        let integrity: String = conn.query_row("PRAGMA integrity_check", [], |row| row.get(0))?;
+        if integrity != "ok" {
+            return Err(rusqlite::Error::SqliteFailure(
+                rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_CORRUPT),
+                Some(format!("备份文件完整性检查失败: {}", integrity))
+            ));
+        }
+
+        let foreign_key_violations: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM pragma_foreign_key_check",
+            [],
+            |row| row.get(0)
+        )?;
+        if foreign_key_violations > 0 {
+            return Err(rusqlite::Error::SqliteFailure(
+                rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_CONSTRAINT),
+                Some(format!("备份文件存在 {} 处外键约束错误", foreign_key_violations))
+            ));
+        }
+
+        let version = self.get_database_version(&conn)?;
+        if version > Self::CURRENT_VERSION {
+            return Err(rusqlite::Error::SqliteFailure(
+                rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_MISMATCH),
+                Some(format!("备份数据库版本 {} 高于应用支持的版本 {}", version, Self::CURRENT_VERSION))
+            ));
+        }
+
+        Ok(())
+    }
+
     /// 获取所有备份文件信息
     pub fn list_backups(&self) -> Vec<(PathBuf, std::time::SystemTime)> {
         let mut backups = Vec::new();
@@ -478,20 +639,57 @@ impl DatabaseManager {
         backups
     }
 
-    /// 清理旧的备份文件
-    fn cleanup_old_backups(&self) {
-        let backups = self.list_backups();
-        
-        if backups.len() > Self::MAX_BACKUPS {
-            let to_remove = &backups[Self::MAX_BACKUPS..];
-            for (backup_path, _) in to_remove {
-                if let Err(e) = fs::remove_file(backup_path) {
-                    eprintln!("警告: 无法删除旧备份文件 {}: {}", backup_path.display(), e);
-                } else {
-                    println!("✓ 清理旧备份: {}", backup_path.display());
-                }
+    /// 读取备份总大小上限，未配置时使用默认值
+    fn get_backup_size_budget(&self, conn: &Connection) -> u64 {
+        conn.query_row(
+            "SELECT value FROM database_metadata WHERE key = ?1",
+            [Self::BACKUP_SIZE_BUDGET_KEY],
+            |row| row.get::<_, String>(0)
+        )
+        .ok()
+        .and_then(|value| value.parse::<u64>().ok())
+        .unwrap_or(Self::DEFAULT_BACKUP_SIZE_BUDGET)
+    }
+
+    /// 设置备份总大小上限并立即执行一次轮换，返回释放的字节数
+    pub fn set_backup_size_budget(&self, size_budget: u64) -> Result<u64> {
+        let conn = Connection::open(&self.db_path)?;
+        conn.execute(
+            "INSERT OR REPLACE INTO database_metadata (key, value, updated_at) VALUES (?1, ?2, ?3)",
+            rusqlite::params![Self::BACKUP_SIZE_BUDGET_KEY, size_budget.to_string(), Utc::now().to_rfc3339()],
+        )?;
+        Ok(self.cleanup_old_backups(Self::MAX_BACKUPS, size_budget))
+    }
+
+    /// 清理旧的备份文件：从最旧的开始删除，直到数量和总大小都满足限制；至少保留最新的一个备份
+    /// 返回释放的字节数
+    fn cleanup_old_backups(&self, max_count: usize, size_budget: u64) -> u64 {
+        // list_backups 已按时间倒序排列（最新在前）
+        let backups: Vec<(PathBuf, u64)> = self.list_backups()
+            .into_iter()
+            .map(|(path, _)| {
+                let size = fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+                (path, size)
+            })
+            .collect();
+
+        let mut kept = backups.len();
+        let mut kept_size: u64 = backups.iter().map(|(_, size)| size).sum();
+        while kept > 1 && (kept > max_count || kept_size > size_budget) {
+            kept -= 1;
+            kept_size -= backups[kept].1;
+        }
+
+        let mut reclaimed = 0;
+        for (backup_path, size) in &backups[kept..] {
+            if let Err(e) = fs::remove_file(backup_path) {
+                eprintln!("警告: 无法删除旧备份文件 {}: {}", backup_path.display(), e);
+            } else {
+                println!("✓ 清理旧备份: {}", backup_path.display());
+                reclaimed += size;
             }
         }
+        reclaimed
     }
 
     /// 获取数据库信息
@@ -539,4 +737,207 @@ impl DatabaseManager {
         let result: String = conn.query_row("PRAGMA integrity_check", [], |row| row.get(0))?;
         Ok(result == "ok")
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::test_support::sample_segment;
+    use std::time::{Duration, SystemTime};
+
+    fn temp_manager(name: &str) -> (DatabaseManager, PathBuf) {
+        let dir = std::env::temp_dir().join(format!("steno_db_test_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let manager = DatabaseManager::with_paths(dir.join("steno.db"), dir.join("backups")).unwrap();
+        (manager, dir)
+    }
+
+    fn seed_backup(manager: &DatabaseManager, index: u64, size: usize) -> PathBuf {
+        let path = manager.backup_dir.join(format!("steno_backup_2024010{}_000000_test.db", index));
+        fs::write(&path, vec![0u8; size]).unwrap();
+        // 序号越大越新
+        let modified = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000 + index * 60);
+        fs::File::options().write(true).open(&path).unwrap().set_modified(modified).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_backup_rotation_honors_count_and_size() {
+        let (manager, dir) = temp_manager("rotation");
+        let sizes = [400, 100, 300, 200, 500, 100, 100];
+        let paths: Vec<PathBuf> = sizes.iter().enumerate()
+            .map(|(i, &size)| seed_backup(&manager, i as u64, size))
+            .collect();
+
+        // 数量上限5，大小上限700：保留最新的 100+100+500，删除其余
+        let reclaimed = manager.cleanup_old_backups(5, 700);
+        let remaining: Vec<PathBuf> = manager.list_backups().into_iter().map(|(p, _)| p).collect();
+        assert_eq!(remaining, vec![paths[6].clone(), paths[5].clone(), paths[4].clone()]);
+        assert_eq!(reclaimed, 400 + 100 + 300 + 200);
+
+        let total: u64 = remaining.iter().map(|p| fs::metadata(p).unwrap().len()).sum();
+        assert!(remaining.len() <= 5 && total <= 700);
+
+        // 即使单个备份超出预算也至少保留一个
+        manager.cleanup_old_backups(5, 10);
+        assert_eq!(manager.list_backups().len(), 1);
+        assert!(paths[6].exists());
+
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_migration_to_v2_preserves_segments() {
+        let (manager, dir) = temp_manager("migrate_v2");
+        let segments = vec![
+            TranscriptionSegment {
+                speaker: Some("spk_0".to_string()),
+                confidence: Some(0.92),
+                no_speech_prob: Some(0.01),
+                ..sample_segment("seg_0", 0.0, 2.5, "第一段")
+            },
+            sample_segment("seg_1", 2.5, 4.0, "second, \"quoted\""),
+        ];
+
+        // 构造版本1的数据库：分段以JSON形式存放
+        {
+            let conn = Connection::open(&manager.db_path).unwrap();
+            manager.create_initial_schema(&conn).unwrap();
+            conn.execute("DROP TABLE transcription_segments", []).unwrap();
+            manager.set_database_version(&conn, 1).unwrap();
+            conn.execute(
+                "INSERT INTO transcription_records (id, name, original_file_name, file_path, file_size, status, created_at, updated_at, tags, config)
+                 VALUES ('record_1', 'r', 'r.wav', '/tmp/r.wav', 1, 'completed', '2024-01-01T00:00:00Z', '2024-01-01T00:00:00Z', '[]', '{}')",
+                [],
+            ).unwrap();
+            conn.execute(
+                "INSERT INTO transcription_contents (record_id, full_text, segments) VALUES ('record_1', '全文', ?1)",
+                [serde_json::to_string(&segments).unwrap()],
+            ).unwrap();
+        }
+
+        let conn = manager.initialize_database().unwrap();
+        assert_eq!(manager.get_database_version(&conn).unwrap(), DatabaseManager::CURRENT_VERSION);
+        assert_eq!(read_segments(&conn, "record_1").unwrap(), Some(segments));
+
+        let blob: Option<String> = conn.query_row(
+            "SELECT segments FROM transcription_contents WHERE record_id = 'record_1'", [], |row| row.get(0)
+        ).unwrap();
+        assert!(blob.is_none());
+
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_migration_to_v8_rebuilds_waveform_cache() {
+        let (manager, dir) = temp_manager("migrate_v8");
+        {
+            let conn = Connection::open(&manager.db_path).unwrap();
+            manager.create_initial_schema(&conn).unwrap();
+            conn.execute("DROP TABLE waveform_peaks", []).unwrap();
+            conn.execute("DROP TABLE app_settings", []).unwrap();
+            // 版本7时由存储服务创建的旧波形缓存表
+            conn.execute(
+                "CREATE TABLE waveform_peaks (record_id TEXT NOT NULL, buckets INTEGER NOT NULL, peaks BLOB NOT NULL, PRIMARY KEY (record_id, buckets))",
+                [],
+            ).unwrap();
+            conn.execute("INSERT INTO waveform_peaks (record_id, buckets, peaks) VALUES ('r', 1, x'00')", []).unwrap();
+            manager.set_database_version(&conn, 7).unwrap();
+        }
+
+        let conn = manager.initialize_database().unwrap();
+        assert_eq!(manager.get_database_version(&conn).unwrap(), DatabaseManager::CURRENT_VERSION);
+        let cached: i64 = conn.query_row("SELECT COUNT(*) FROM waveform_peaks", [], |row| row.get(0)).unwrap();
+        assert_eq!(cached, 0);
+        assert!(DatabaseManager::column_exists(&conn, "waveform_peaks", "source_modified").unwrap());
+        conn.execute("INSERT INTO app_settings (key, value, updated_at) VALUES ('k', 'v', 'now')", []).unwrap();
+
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_migration_to_v7_creates_session_table() {
+        let (manager, dir) = temp_manager("migrate_v7");
+        {
+            let conn = Connection::open(&manager.db_path).unwrap();
+            manager.create_initial_schema(&conn).unwrap();
+            conn.execute("DROP TABLE recording_sessions", []).unwrap();
+            manager.set_database_version(&conn, 6).unwrap();
+        }
+
+        let conn = manager.initialize_database().unwrap();
+        assert_eq!(manager.get_database_version(&conn).unwrap(), DatabaseManager::CURRENT_VERSION);
+        conn.execute("INSERT INTO recording_sessions (record_id, session_id, part) VALUES ('r_part2', 'r', 2)", []).unwrap();
+
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_corrupt_backup_rejected_and_live_db_untouched() {
+        let (manager, dir) = temp_manager("restore");
+        {
+            let conn = manager.initialize_database().unwrap();
+            conn.execute(
+                "INSERT INTO database_metadata (key, value, updated_at) VALUES ('marker', 'live', '')",
+                [],
+            ).unwrap();
+        }
+        let live_bytes = fs::read(&manager.db_path).unwrap();
+
+        // 非SQLite内容的损坏备份
+        let corrupt = manager.backup_dir.join("steno_backup_corrupt.db");
+        fs::write(&corrupt, b"definitely not a sqlite database, just garbage bytes".repeat(100)).unwrap();
+        assert!(manager.restore_backup(&corrupt).is_err());
+
+        // 版本高于应用支持的备份
+        let future = manager.backup_dir.join("steno_backup_future.db");
+        {
+            let conn = Connection::open(&future).unwrap();
+            manager.create_initial_schema(&conn).unwrap();
+            manager.set_database_version(&conn, DatabaseManager::CURRENT_VERSION + 1).unwrap();
+        }
+        assert!(manager.restore_backup(&future).is_err());
+
+        assert_eq!(fs::read(&manager.db_path).unwrap(), live_bytes);
+        let conn = Connection::open(&manager.db_path).unwrap();
+        let marker: String = conn.query_row(
+            "SELECT value FROM database_metadata WHERE key = 'marker'", [], |row| row.get(0)
+        ).unwrap();
+        assert_eq!(marker, "live");
+
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_backup_and_restore_handle_wal_files() {
+        let (manager, dir) = temp_manager("wal");
+        let count = |conn: &Connection| -> i64 {
+            conn.query_row("SELECT COUNT(*) FROM database_metadata WHERE key LIKE 'marker_%'", [], |row| row.get(0)).unwrap()
+        };
+        let insert = |conn: &Connection, key: &str| {
+            conn.execute("INSERT INTO database_metadata (key, value, updated_at) VALUES (?1, '', '')", [key]).unwrap();
+        };
+
+        let conn = manager.initialize_database().unwrap();
+        conn.pragma_update_and_check(None, "journal_mode", "WAL", |row| row.get::<_, String>(0)).unwrap();
+        conn.pragma_update(None, "wal_autocheckpoint", 0).unwrap();
+        insert(&conn, "marker_1");
+
+        // 未检查点的写入只存在于 -wal 中，备份也应包含
+        let backup = manager.create_backup("wal").unwrap();
+        assert_eq!(count(&Connection::open(&backup).unwrap()), 1);
+
+        // 模拟异常退出残留的 -wal：若不清理，恢复后会把 marker_2 回放进数据库
+        insert(&conn, "marker_2");
+        let [wal, _] = DatabaseManager::wal_sidecars(&manager.db_path);
+        let leftover = fs::read(&wal).unwrap();
+        drop(conn);
+        fs::write(&wal, leftover).unwrap();
+
+        manager.restore_backup(&backup).unwrap();
+        assert!(DatabaseManager::wal_sidecars(&manager.db_path).iter().all(|p| !p.exists()));
+        assert_eq!(count(&Connection::open(&manager.db_path).unwrap()), 1);
+
+        let _ = fs::remove_dir_all(dir);
+    }
+}
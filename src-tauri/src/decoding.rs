@@ -0,0 +1,202 @@
+// decoding.rs - Whisper 解码策略配置：贪婪采样或束搜索、温度回退，在速度与准确度之间取舍
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    whisper_full_params, whisper_sampling_strategy,
+    whisper_sampling_strategy_WHISPER_SAMPLING_BEAM_SEARCH, whisper_sampling_strategy_WHISPER_SAMPLING_GREEDY,
+};
+
+/// whisper.cpp 最多同时运行的解码器数量（WHISPER_MAX_DECODERS）
+pub const MAX_DECODERS: i32 = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DecodingStrategy {
+    Greedy { best_of: i32 },
+    Beam { beam_size: i32 },
+}
+
+impl Default for DecodingStrategy {
+    // 与实时识别原先的最小解码开销一致
+    fn default() -> Self {
+        Self::Greedy { best_of: 1 }
+    }
+}
+
+impl DecodingStrategy {
+    pub fn validate(&self) -> Result<(), String> {
+        let (name, value) = match *self {
+            Self::Greedy { best_of } => ("best_of", best_of),
+            Self::Beam { beam_size } => ("beam_size", beam_size),
+        };
+        if (1..=MAX_DECODERS).contains(&value) {
+            Ok(())
+        } else {
+            Err(format!("{} 必须在 1 到 {} 之间，当前为 {}", name, MAX_DECODERS, value))
+        }
+    }
+
+    pub fn sampling_strategy(&self) -> whisper_sampling_strategy {
+        match self {
+            Self::Greedy { .. } => whisper_sampling_strategy_WHISPER_SAMPLING_GREEDY,
+            Self::Beam { .. } => whisper_sampling_strategy_WHISPER_SAMPLING_BEAM_SEARCH,
+        }
+    }
+
+    /// 覆盖参数中的采样策略及对应的候选数量
+    pub fn apply(&self, params: &mut whisper_full_params) {
+        params.strategy = self.sampling_strategy();
+        match *self {
+            Self::Greedy { best_of } => params.greedy.best_of = best_of,
+            Self::Beam { beam_size } => params.beam_search.beam_size = beam_size,
+        }
+    }
+}
+
+/// 温度回退：解码结果熵过高或平均对数概率过低时，提高温度重新解码
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TemperatureFallback {
+    pub temperature_inc: f32,       // 每次回退增加的温度，0 表示不回退
+    pub max_fallback_attempts: u32, // 最多重试次数，0 表示不回退
+}
+
+impl Default for TemperatureFallback {
+    fn default() -> Self {
+        Self {
+            temperature_inc: 0.2,
+            max_fallback_attempts: 5,
+        }
+    }
+}
+
+impl TemperatureFallback {
+    /// whisper.cpp 从初始温度起按步长递增直到 1.0，没有次数参数；
+    /// 步长至少取 (1 - 初始温度) / 次数，使重试不超过 max_fallback_attempts 次
+    pub fn effective_inc(&self, initial_temperature: f32) -> f32 {
+        if self.temperature_inc <= 0.0 || self.max_fallback_attempts == 0 {
+            return 0.0;
+        }
+        let min_inc = (1.0 - initial_temperature).max(0.0) / self.max_fallback_attempts as f32;
+        self.temperature_inc.max(min_inc)
+    }
+
+    /// 需在设置初始温度之后调用
+    pub fn apply(&self, params: &mut whisper_full_params) {
+        params.temperature_inc = self.effective_inc(params.temperature);
+    }
+}
+
+/// 分段长度：max_len 为每段最大字符数，0 表示不限制，由 Whisper 按自然句切分；
+/// split_on_word 时只在词边界处切分，避免把一个词拆到两段
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SegmentationConfig {
+    pub max_len: i32,
+    pub split_on_word: bool,
+}
+
+impl Default for SegmentationConfig {
+    // 批量转录只拼接文本，保留 Whisper 的句级分段
+    fn default() -> Self {
+        Self {
+            max_len: 0,
+            split_on_word: false,
+        }
+    }
+}
+
+impl SegmentationConfig {
+    /// 实时字幕按约一行的长度切分
+    pub fn realtime() -> Self {
+        Self {
+            max_len: 60,
+            split_on_word: true,
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.max_len < 0 {
+            return Err(format!("max_len 不能为负数，当前为 {}", self.max_len));
+        }
+        Ok(())
+    }
+
+    /// whisper.cpp 只在开启 token 时间戳时按 max_len 切分段，因此限制长度时一并开启
+    pub fn apply(&self, params: &mut whisper_full_params) {
+        params.max_len = self.max_len;
+        params.split_on_word = self.split_on_word;
+        if self.max_len > 0 {
+            params.token_timestamps = true;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::whisper_full_default_params;
+
+    #[test]
+    fn test_params_reflect_strategy() {
+        let mut params = unsafe { whisper_full_default_params(whisper_sampling_strategy_WHISPER_SAMPLING_BEAM_SEARCH) };
+        DecodingStrategy::Greedy { best_of: 3 }.apply(&mut params);
+        assert_eq!(params.strategy, whisper_sampling_strategy_WHISPER_SAMPLING_GREEDY);
+        assert_eq!(params.greedy.best_of, 3);
+
+        DecodingStrategy::Beam { beam_size: 5 }.apply(&mut params);
+        assert_eq!(params.strategy, whisper_sampling_strategy_WHISPER_SAMPLING_BEAM_SEARCH);
+        assert_eq!(params.beam_search.beam_size, 5);
+    }
+
+    #[test]
+    fn test_params_reflect_segmentation() {
+        let mut params = unsafe { whisper_full_default_params(whisper_sampling_strategy_WHISPER_SAMPLING_GREEDY) };
+        params.token_timestamps = false;
+        SegmentationConfig { max_len: 40, split_on_word: true }.apply(&mut params);
+        assert_eq!(params.max_len, 40);
+        assert!(params.split_on_word);
+        assert!(params.token_timestamps);
+
+        SegmentationConfig::default().apply(&mut params);
+        assert_eq!(params.max_len, 0);
+        assert!(!params.split_on_word);
+
+        // 实时默认不再强制一个 token 一段
+        assert!(SegmentationConfig::realtime().max_len != 1);
+        assert!(SegmentationConfig { max_len: -1, split_on_word: false }.validate().is_err());
+
+        let parsed: SegmentationConfig = serde_json::from_str(r#"{ "max_len": 80 }"#).unwrap();
+        assert_eq!(parsed, SegmentationConfig { max_len: 80, split_on_word: false });
+    }
+
+    #[test]
+    fn test_fallback_params_from_config() {
+        let mut params = unsafe { whisper_full_default_params(whisper_sampling_strategy_WHISPER_SAMPLING_BEAM_SEARCH) };
+        params.temperature = 0.0;
+
+        TemperatureFallback { temperature_inc: 0.25, max_fallback_attempts: 4 }.apply(&mut params);
+        assert!((params.temperature_inc - 0.25).abs() < 1e-6);
+
+        // 次数限制比步长更严格时放大步长
+        TemperatureFallback { temperature_inc: 0.1, max_fallback_attempts: 2 }.apply(&mut params);
+        assert!((params.temperature_inc - 0.5).abs() < 1e-6);
+
+        TemperatureFallback { temperature_inc: 0.2, max_fallback_attempts: 0 }.apply(&mut params);
+        assert_eq!(params.temperature_inc, 0.0);
+
+        let fallback: TemperatureFallback = serde_json::from_str(r#"{"max_fallback_attempts":3}"#).unwrap();
+        assert_eq!(fallback, TemperatureFallback { temperature_inc: 0.2, max_fallback_attempts: 3 });
+    }
+
+    #[test]
+    fn test_validation_and_serde() {
+        assert!(DecodingStrategy::default().validate().is_ok());
+        assert!(DecodingStrategy::Beam { beam_size: MAX_DECODERS }.validate().is_ok());
+        assert!(DecodingStrategy::Beam { beam_size: 0 }.validate().is_err());
+        assert!(DecodingStrategy::Greedy { best_of: MAX_DECODERS + 1 }.validate().is_err());
+
+        let strategy: DecodingStrategy = serde_json::from_str(r#"{"type":"beam","beam_size":4}"#).unwrap();
+        assert_eq!(strategy, DecodingStrategy::Beam { beam_size: 4 });
+    }
+}
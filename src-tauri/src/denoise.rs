@@ -0,0 +1,177 @@
+// denoise.rs - 实时录音送入识别前的降噪后端：不处理（默认）、噪声门或 RNNoise（需启用 denoise 特性）
+use serde::{Deserialize, Serialize};
+
+use crate::audio_processing::{apply_noise_gate, DEFAULT_NOISE_GATE_THRESHOLD};
+
+pub trait Denoiser {
+    /// 处理一块 16kHz 单声道音频；有内部缓冲的后端返回的长度可能与输入不同
+    fn process(&mut self, samples: &[f32]) -> Vec<f32>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DenoiseBackendKind {
+    /// 不降噪，音频原样送入识别
+    #[default]
+    None,
+    NoiseGate,
+    Rnnoise,
+}
+
+/// 返回 None 表示不做降噪处理
+pub fn create_denoiser(kind: DenoiseBackendKind) -> Option<Box<dyn Denoiser>> {
+    match kind {
+        DenoiseBackendKind::None => None,
+        DenoiseBackendKind::NoiseGate => Some(Box::new(NoiseGate::new(DEFAULT_NOISE_GATE_THRESHOLD))),
+        #[cfg(feature = "denoise")]
+        DenoiseBackendKind::Rnnoise => Some(Box::new(rnnoise::RnnoiseDenoiser::new())),
+        #[cfg(not(feature = "denoise"))]
+        DenoiseBackendKind::Rnnoise => {
+            log::warn!("⚠️ 未启用 denoise 特性，RNNoise 降噪不可用，音频不做处理");
+            None
+        }
+    }
+}
+
+/// 复用音频预处理中的噪声门：整块能量低于阈值时大幅衰减
+pub struct NoiseGate {
+    threshold: f32,
+}
+
+impl NoiseGate {
+    pub fn new(threshold: f32) -> Self {
+        Self { threshold }
+    }
+}
+
+impl Denoiser for NoiseGate {
+    fn process(&mut self, samples: &[f32]) -> Vec<f32> {
+        let mut output = samples.to_vec();
+        apply_noise_gate(&mut output, self.threshold);
+        output
+    }
+}
+
+#[cfg(feature = "denoise")]
+mod rnnoise {
+    use super::Denoiser;
+    use nnnoiseless::DenoiseState;
+
+    /// RNNoise 以 48kHz、480 样本为一帧，对应 16kHz 下 160 个样本
+    const UPSAMPLE: usize = 3;
+    const INPUT_FRAME: usize = DenoiseState::FRAME_SIZE / UPSAMPLE;
+    /// nnnoiseless 期望 i16 量程的浮点样本
+    const I16_SCALE: f32 = 32767.0;
+
+    /// 16kHz 输入线性插值到 48kHz 降噪，再按三点平均降回 16kHz；不足一帧的尾部留到下一块
+    pub struct RnnoiseDenoiser {
+        state: Box<DenoiseState<'static>>,
+        pending: Vec<f32>,
+        last_input: f32, // 上一块最后一个输入样本，保证块间插值连续
+    }
+
+    impl RnnoiseDenoiser {
+        pub fn new() -> Self {
+            Self {
+                state: DenoiseState::new(),
+                pending: Vec::with_capacity(INPUT_FRAME),
+                last_input: 0.0,
+            }
+        }
+
+        fn upsample(&mut self, frame: &[f32]) -> Vec<f32> {
+            let mut output = Vec::with_capacity(frame.len() * UPSAMPLE);
+            for &sample in frame {
+                for step in 1..=UPSAMPLE {
+                    let fraction = step as f32 / UPSAMPLE as f32;
+                    output.push((self.last_input + (sample - self.last_input) * fraction) * I16_SCALE);
+                }
+                self.last_input = sample;
+            }
+            output
+        }
+    }
+
+    impl Default for RnnoiseDenoiser {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    impl Denoiser for RnnoiseDenoiser {
+        fn process(&mut self, samples: &[f32]) -> Vec<f32> {
+            self.pending.extend_from_slice(samples);
+            let complete = self.pending.len() / INPUT_FRAME * INPUT_FRAME;
+            let frames: Vec<f32> = self.pending.drain(..complete).collect();
+
+            let mut output = Vec::with_capacity(complete);
+            let mut denoised = [0.0f32; DenoiseState::FRAME_SIZE];
+            for frame in frames.chunks(INPUT_FRAME) {
+                let upsampled = self.upsample(frame);
+                self.state.process_frame(&mut denoised, &upsampled);
+                output.extend(denoised.chunks(UPSAMPLE).map(|group| {
+                    group.iter().sum::<f32>() / UPSAMPLE as f32 / I16_SCALE
+                }));
+            }
+            output
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 可复现的白噪声
+    fn white_noise(samples: usize, amplitude: f32) -> Vec<f32> {
+        let mut seed = 0x2545_f491u32;
+        (0..samples)
+            .map(|_| {
+                seed ^= seed << 13;
+                seed ^= seed >> 17;
+                seed ^= seed << 5;
+                (seed as f32 / u32::MAX as f32 * 2.0 - 1.0) * amplitude
+            })
+            .collect()
+    }
+
+    fn energy(samples: &[f32]) -> f32 {
+        samples.iter().map(|&x| x * x).sum::<f32>() / samples.len() as f32
+    }
+
+    #[test]
+    fn test_noise_gate_attenuates_quiet_blocks() {
+        assert!(create_denoiser(DenoiseBackendKind::default()).is_none());
+
+        let mut gate = create_denoiser(DenoiseBackendKind::NoiseGate).unwrap();
+        let quiet = white_noise(1600, 0.01);
+        let gated = gate.process(&quiet);
+        assert_eq!(gated.len(), quiet.len());
+        assert!(energy(&gated) < energy(&quiet) * 0.02);
+
+        let loud = white_noise(1600, 0.5);
+        assert_eq!(gate.process(&loud), loud);
+
+        let kind: DenoiseBackendKind = serde_json::from_str(r#""rnnoise""#).unwrap();
+        assert_eq!(kind, DenoiseBackendKind::Rnnoise);
+    }
+
+    #[cfg(feature = "denoise")]
+    #[test]
+    fn test_rnnoise_reduces_noise_energy() {
+        let mut denoiser = create_denoiser(DenoiseBackendKind::Rnnoise).unwrap();
+        let noisy = white_noise(16000 * 3, 0.1);
+
+        // 块大小与帧长不对齐，模拟采集回调
+        let mut output = Vec::new();
+        for chunk in noisy.chunks(1000) {
+            output.extend(denoiser.process(chunk));
+        }
+        assert_eq!(output.len(), noisy.len() / 160 * 160);
+
+        // 跳过模型适应阶段，稳态噪声应被明显压低
+        let settled = &output[16000..];
+        assert!(energy(settled) < energy(&noisy[16000..]) * 0.25,
+            "降噪后能量 {} 未明显低于输入 {}", energy(settled), energy(&noisy[16000..]));
+    }
+}
@@ -0,0 +1,203 @@
+// diagnostics.rs - 一键健康检查：汇总音频设备、模型、数据库、磁盘空间与 Whisper 上下文的状态
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use cpal::traits::{DeviceTrait, HostTrait};
+use serde::Serialize;
+use tauri::command;
+
+use crate::audio_devices;
+use crate::capture_core;
+use crate::database_manager::DatabaseManager;
+use crate::errors::StenoError;
+use crate::model_management::{self, GgmlHeader, ModelManager};
+
+/// 剩余空间低于该值时判为不通过（足够下载一个中等模型并保存录音）
+const MIN_FREE_SPACE: u64 = 1024 * 1024 * 1024;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DiagnosticCheck {
+    pub name: String,
+    pub passed: bool,
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DiagnosticReport {
+    pub passed: bool, // 所有检查均通过
+    pub checks: Vec<DiagnosticCheck>,
+}
+
+/// 各子系统的原始探测结果，与判定逻辑分开以便测试
+pub struct SubsystemProbe {
+    pub default_input_device: Option<String>,                   // 默认输入设备名称
+    pub selected_input_device: Option<(String, Option<String>)>, // 设置中的设备ID及解析出的设备名称
+    pub model_path: PathBuf,
+    pub model_header: Result<GgmlHeader, StenoError>,
+    pub database_integrity: Result<bool, String>,
+    pub free_space: Result<u64, String>,
+    pub whisper_context_loaded: bool,
+}
+
+fn check(name: &str, passed: bool, detail: String) -> DiagnosticCheck {
+    DiagnosticCheck { name: name.to_string(), passed, detail }
+}
+
+fn format_size(bytes: u64) -> String {
+    format!("{:.1} GB", bytes as f64 / (1024.0 * 1024.0 * 1024.0))
+}
+
+pub fn assemble_report(probe: &SubsystemProbe) -> DiagnosticReport {
+    let mut checks = Vec::with_capacity(6);
+
+    checks.push(match &probe.default_input_device {
+        Some(name) => check("default_input_device", true, format!("默认输入设备: {}", name)),
+        None => check("default_input_device", false, "未找到默认输入设备".to_string()),
+    });
+
+    // 未选择设备时录音使用默认设备，不算失败
+    checks.push(match &probe.selected_input_device {
+        None => check("selected_input_device", true, "未选择设备，使用默认输入设备".to_string()),
+        Some((id, Some(name))) => check("selected_input_device", true, format!("{} -> {}", id, name)),
+        Some((id, None)) => check("selected_input_device", false, format!("选定的设备 {} 不可用，录音将回退到默认设备", id)),
+    });
+
+    checks.push(match &probe.model_header {
+        Ok(header) => check("model_file", true, format!(
+            "{} (n_vocab={}, n_audio_layer={}, ftype={})",
+            probe.model_path.display(), header.n_vocab, header.n_audio_layer, header.ftype
+        )),
+        Err(e) => check("model_file", false, e.to_string()),
+    });
+
+    checks.push(match &probe.database_integrity {
+        Ok(true) => check("database_integrity", true, "数据库完整性检查通过".to_string()),
+        Ok(false) => check("database_integrity", false, "数据库完整性检查未通过".to_string()),
+        Err(e) => check("database_integrity", false, format!("无法检查数据库: {}", e)),
+    });
+
+    checks.push(match &probe.free_space {
+        Ok(bytes) => check("free_disk_space", *bytes >= MIN_FREE_SPACE, format!(
+            "剩余 {}（最低要求 {}）", format_size(*bytes), format_size(MIN_FREE_SPACE)
+        )),
+        Err(e) => check("free_disk_space", false, format!("无法获取磁盘空间: {}", e)),
+    });
+
+    checks.push(if probe.whisper_context_loaded {
+        check("whisper_context", true, "Whisper 上下文已初始化".to_string())
+    } else {
+        check("whisper_context", false, "Whisper 上下文未初始化，请切换或重新加载模型".to_string())
+    });
+
+    DiagnosticReport {
+        passed: checks.iter().all(|c| c.passed),
+        checks,
+    }
+}
+
+fn probe_input_devices() -> (Option<String>, Option<(String, Option<String>)>) {
+    let host = cpal::default_host();
+    let default_name = host.default_input_device()
+        .map(|device| device.name().unwrap_or_else(|_| "未知设备".to_string()));
+    let selected = audio_devices::selected_input_device_id().map(|id| {
+        let name = capture_core::find_input_device(&host, &id)
+            .map(|device| device.name().unwrap_or_else(|_| "未知设备".to_string()));
+        (id, name)
+    });
+    (default_name, selected)
+}
+
+#[command]
+pub async fn run_diagnostics(
+    app_handle: tauri::AppHandle,
+    model_manager: tauri::State<'_, Arc<Mutex<ModelManager>>>,
+    whisper_context: tauri::State<'_, crate::WhisperContextState>,
+) -> Result<DiagnosticReport, StenoError> {
+    let (default_input_device, selected_input_device) = probe_input_devices();
+    let model_path = model_manager.lock().unwrap().get_current_model_path();
+    let model_header = model_management::validate_model_file(&model_path);
+
+    let database_integrity = DatabaseManager::new(&app_handle)
+        .map_err(|e| e.to_string())
+        .and_then(|db| db.check_integrity().map_err(|e| e.to_string()));
+
+    let free_space = crate::data_dir::resolve_data_dir(&app_handle)
+        .map_err(|e| e.to_string())
+        .and_then(|dir| {
+            std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+            fs2::available_space(&dir).map_err(|e| e.to_string())
+        });
+
+    let report = assemble_report(&SubsystemProbe {
+        default_input_device,
+        selected_input_device,
+        model_path,
+        model_header,
+        database_integrity,
+        free_space,
+        whisper_context_loaded: whisper_context.is_loaded(),
+    });
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn healthy_probe() -> SubsystemProbe {
+        SubsystemProbe {
+            default_input_device: Some("Built-in Microphone".to_string()),
+            selected_input_device: None,
+            model_path: PathBuf::from("/models/ggml-base.bin"),
+            model_header: Ok(GgmlHeader { n_vocab: 51865, n_audio_layer: 6, n_text_layer: 6, n_mels: 80, ftype: 1 }),
+            database_integrity: Ok(true),
+            free_space: Ok(20 * 1024 * 1024 * 1024),
+            whisper_context_loaded: true,
+        }
+    }
+
+    fn find<'a>(report: &'a DiagnosticReport, name: &str) -> &'a DiagnosticCheck {
+        report.checks.iter().find(|c| c.name == name).unwrap()
+    }
+
+    #[test]
+    fn test_healthy_report() {
+        let report = assemble_report(&healthy_probe());
+        assert!(report.passed);
+        assert_eq!(report.checks.len(), 6);
+        assert!(find(&report, "model_file").detail.contains("n_vocab=51865"));
+    }
+
+    #[test]
+    fn test_failed_checks_carry_detail() {
+        let mut probe = healthy_probe();
+        probe.default_input_device = None;
+        probe.selected_input_device = Some(("input_3".to_string(), None));
+        probe.model_header = Err(StenoError::ModelMissing("模型文件不存在: /models/ggml-base.bin".to_string()));
+        probe.database_integrity = Err("database is locked".to_string());
+        probe.free_space = Ok(100 * 1024 * 1024);
+        probe.whisper_context_loaded = false;
+
+        let report = assemble_report(&probe);
+        assert!(!report.passed);
+        assert!(report.checks.iter().all(|c| !c.passed));
+        assert!(find(&report, "selected_input_device").detail.contains("input_3"));
+        assert!(find(&report, "model_file").detail.contains("模型文件不存在"));
+        assert!(find(&report, "database_integrity").detail.contains("database is locked"));
+        assert!(find(&report, "free_disk_space").detail.contains("0.1 GB"));
+    }
+
+    #[test]
+    fn test_single_failure_fails_report() {
+        let mut probe = healthy_probe();
+        probe.database_integrity = Ok(false);
+        let report = assemble_report(&probe);
+        assert!(!report.passed);
+        assert_eq!(report.checks.iter().filter(|c| !c.passed).count(), 1);
+
+        // 选定设备可解析时通过
+        let mut probe = healthy_probe();
+        probe.selected_input_device = Some(("input_0".to_string(), Some("USB Mic".to_string())));
+        assert!(find(&assemble_report(&probe), "selected_input_device").passed);
+    }
+}
@@ -0,0 +1,127 @@
+// errors.rs - 命令边界的结构化错误，序列化为 { code, message } 供前端按错误码处理
+use std::fmt;
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum StenoError {
+    NoInputDevice,
+    AudioDevice(String),
+    InvalidArgument(String),
+    ModelMissing(String),
+    ModelInvalid(String),
+    ModelIo(String),
+    ModelLanguageMismatch(String),
+    StorageUnavailable(String),
+    Storage(String),
+    NotFound(String),
+    Internal(String),
+}
+
+impl StenoError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::NoInputDevice => "no_input_device",
+            Self::AudioDevice(_) => "audio_device_error",
+            Self::InvalidArgument(_) => "invalid_argument",
+            Self::ModelMissing(_) => "model_missing",
+            Self::ModelInvalid(_) => "model_invalid",
+            Self::ModelIo(_) => "model_io_error",
+            Self::ModelLanguageMismatch(_) => "model_language_mismatch",
+            Self::StorageUnavailable(_) => "storage_unavailable",
+            Self::Storage(_) => "storage_error",
+            Self::NotFound(_) => "not_found",
+            Self::Internal(_) => "internal_error",
+        }
+    }
+}
+
+impl fmt::Display for StenoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoInputDevice => write!(f, "没有可用的音频输入设备"),
+            Self::AudioDevice(message)
+            | Self::InvalidArgument(message)
+            | Self::ModelMissing(message)
+            | Self::ModelInvalid(message)
+            | Self::ModelIo(message)
+            | Self::ModelLanguageMismatch(message)
+            | Self::StorageUnavailable(message)
+            | Self::Storage(message)
+            | Self::NotFound(message)
+            | Self::Internal(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for StenoError {}
+
+impl Serialize for StenoError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("StenoError", 2)?;
+        state.serialize_field("code", self.code())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.end()
+    }
+}
+
+impl From<rusqlite::Error> for StenoError {
+    fn from(error: rusqlite::Error) -> Self {
+        Self::Storage(format!("Storage error: {}", error))
+    }
+}
+
+// 尚未迁移的内部函数仍返回字符串错误
+impl From<String> for StenoError {
+    fn from(message: String) -> Self {
+        Self::Internal(message)
+    }
+}
+
+impl From<&str> for StenoError {
+    fn from(message: &str) -> Self {
+        Self::Internal(message.to_string())
+    }
+}
+
+impl From<StenoError> for String {
+    fn from(error: StenoError) -> Self {
+        error.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_variants_serialize_to_codes() {
+        let cases = [
+            (StenoError::NoInputDevice, "no_input_device"),
+            (StenoError::AudioDevice("设备被占用".into()), "audio_device_error"),
+            (StenoError::InvalidArgument("参数错误".into()), "invalid_argument"),
+            (StenoError::ModelMissing("模型文件不存在".into()), "model_missing"),
+            (StenoError::ModelInvalid("不是有效的模型".into()), "model_invalid"),
+            (StenoError::ModelIo("复制失败".into()), "model_io_error"),
+            (StenoError::ModelLanguageMismatch("仅英文模型".into()), "model_language_mismatch"),
+            (StenoError::StorageUnavailable("未初始化".into()), "storage_unavailable"),
+            (StenoError::Storage("写入失败".into()), "storage_error"),
+            (StenoError::NotFound("记录不存在".into()), "not_found"),
+            (StenoError::Internal("未知错误".into()), "internal_error"),
+        ];
+        for (error, code) in cases {
+            let value = serde_json::to_value(&error).unwrap();
+            assert_eq!(value["code"], code);
+            assert_eq!(value["message"], error.to_string());
+            assert_eq!(value.as_object().unwrap().len(), 2);
+        }
+    }
+
+    #[test]
+    fn test_conversions() {
+        let error: StenoError = rusqlite::Error::InvalidQuery.into();
+        assert_eq!(error.code(), "storage_error");
+        let message: String = StenoError::ModelMissing("模型文件不存在".into()).into();
+        assert_eq!(message, "模型文件不存在");
+        assert_eq!(StenoError::from("失败").code(), "internal_error");
+    }
+}
@@ -0,0 +1,78 @@
+// languages.rs - 当前模型支持的识别语言：多语言模型枚举 Whisper 内置语言表，仅英文模型只返回 en
+use std::ffi::CStr;
+
+use serde::Serialize;
+use tauri::State;
+
+use crate::errors::StenoError;
+use crate::{whisper_is_multilingual, whisper_lang_max_id, whisper_lang_str, whisper_lang_str_full, WhisperContextState};
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LanguageInfo {
+    pub code: String, // Whisper 语言代码，如 "zh"
+    pub name: String, // 可读名称，如 "Chinese"
+}
+
+fn lang_str(ptr: *const std::os::raw::c_char) -> Option<String> {
+    if ptr.is_null() {
+        return None;
+    }
+    Some(unsafe { CStr::from_ptr(ptr) }.to_string_lossy().to_string())
+}
+
+/// Whisper 语言 ID 对应的代码与名称；ID 越界时返回 None
+pub fn language_by_id(id: i32) -> Option<LanguageInfo> {
+    let code = lang_str(unsafe { whisper_lang_str(id) })?;
+    // whisper.cpp 的全称为小写英文，首字母大写后展示
+    let full = lang_str(unsafe { whisper_lang_str_full(id) }).unwrap_or_else(|| code.clone());
+    let mut chars = full.chars();
+    let name = match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => code.clone(),
+    };
+    Some(LanguageInfo { code, name })
+}
+
+pub fn all_languages() -> Vec<LanguageInfo> {
+    let max_id = unsafe { whisper_lang_max_id() };
+    (0..=max_id).filter_map(language_by_id).collect()
+}
+
+pub fn supported_languages(multilingual: bool) -> Vec<LanguageInfo> {
+    if multilingual {
+        all_languages()
+    } else {
+        vec![LanguageInfo { code: "en".to_string(), name: "English".to_string() }]
+    }
+}
+
+#[tauri::command]
+pub async fn get_supported_languages(
+    whisper_context: State<'_, WhisperContextState>,
+) -> Result<Vec<LanguageInfo>, StenoError> {
+    let ctx = whisper_context.ctx.lock().unwrap();
+    if ctx.is_null() {
+        return Err(StenoError::ModelMissing("Whisper 上下文未初始化，请先下载或切换模型".to_string()));
+    }
+    let multilingual = unsafe { whisper_is_multilingual(*ctx) } != 0;
+    Ok(supported_languages(multilingual))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_known_language_ids() {
+        let expected = [(0, "en"), (1, "zh"), (2, "de"), (3, "es"), (7, "ja")];
+        for (id, code) in expected {
+            assert_eq!(language_by_id(id).unwrap().code, code, "语言ID {}", id);
+        }
+        assert_eq!(language_by_id(1).unwrap().name, "Chinese");
+        assert!(language_by_id(-1).is_none());
+
+        let all = all_languages();
+        assert!(all.len() >= 99);
+        assert_eq!(supported_languages(false), vec![LanguageInfo { code: "en".to_string(), name: "English".to_string() }]);
+    }
+}
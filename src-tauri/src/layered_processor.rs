@@ -5,6 +5,7 @@ use std::time::{Duration, Instant};
 use tokio::sync::mpsc;
 use serde::{Deserialize, Serialize};
 
+use crate::prompt_builder::PromptParts;
 use crate::realtime_whisper::{RealtimeWhisperRecognizer, RealtimeRecognitionConfig};
 use crate::whisper_context;
 use crate::audio_processing::SpeechSegment;
@@ -33,7 +34,7 @@ pub struct FastProcessor {
 }
 
 impl FastProcessor {
-    pub fn new(whisper_ctx: *mut std::ffi::c_void, language: String, initial_prompt: Option<String>) -> Result<Self, String> {
+    pub fn new(whisper_ctx: *mut std::ffi::c_void, language: String, prompt: PromptParts) -> Result<Self, String> {
         let config = RealtimeRecognitionConfig {
             language,
             mode: "fast".to_string(),
@@ -42,7 +43,8 @@ impl FastProcessor {
             beam_size: 1, // 最小beam size
             temperature: 0.2, // 稍高温度，更快但略不稳定
             max_tokens: 20, // 限制token数
-            initial_prompt, // 使用传入的提示词
+            initial_prompt: prompt.template, // 由识别器与热词一起组装成初始提示词
+            hotwords: prompt.hotwords,
         };
 
         let recognizer = RealtimeWhisperRecognizer::new(whisper_ctx as *mut whisper_context, config);
@@ -91,7 +93,7 @@ pub struct AccurateProcessor {
 }
 
 impl AccurateProcessor {
-    pub fn new(whisper_ctx: *mut std::ffi::c_void, language: String, initial_prompt: Option<String>) -> Result<Self, String> {
+    pub fn new(whisper_ctx: *mut std::ffi::c_void, language: String, prompt: PromptParts) -> Result<Self, String> {
         let config = RealtimeRecognitionConfig {
             language,
             mode: "accurate".to_string(),
@@ -100,7 +102,8 @@ impl AccurateProcessor {
             beam_size: 5, // 更大的beam size
             temperature: 0.0, // 最保守的温度
             max_tokens: 50, // 更多token
-            initial_prompt, // 使用传入的提示词
+            initial_prompt: prompt.template, // 由识别器与热词一起组装成初始提示词
+            hotwords: prompt.hotwords,
         };
 
         let recognizer = RealtimeWhisperRecognizer::new(whisper_ctx as *mut whisper_context, config);
@@ -194,9 +197,9 @@ pub struct LayeredProcessor {
 }
 
 impl LayeredProcessor {
-    pub fn new(whisper_ctx: *mut std::ffi::c_void, language: String, initial_prompt: Option<String>) -> Result<Self, String> {
-        let fast_processor = Arc::new(FastProcessor::new(whisper_ctx, language.clone(), initial_prompt.clone())?);
-        let accurate_processor = Arc::new(AccurateProcessor::new(whisper_ctx, language, initial_prompt)?);
+    pub fn new(whisper_ctx: *mut std::ffi::c_void, language: String, prompt: PromptParts) -> Result<Self, String> {
+        let fast_processor = Arc::new(FastProcessor::new(whisper_ctx, language.clone(), prompt.clone())?);
+        let accurate_processor = Arc::new(AccurateProcessor::new(whisper_ctx, language, prompt)?);
         
         let (task_sender, task_receiver) = mpsc::unbounded_channel();
         let (result_sender, result_receiver) = mpsc::unbounded_channel();
@@ -339,8 +342,8 @@ struct ProcessingStats {
 }
 
 impl UnifiedProcessor {
-    pub fn new(whisper_ctx: *mut std::ffi::c_void, language: String, initial_prompt: Option<String>) -> Result<Self, String> {
-        let layered_processor = LayeredProcessor::new(whisper_ctx, language, initial_prompt)?;
+    pub fn new(whisper_ctx: *mut std::ffi::c_void, language: String, prompt: PromptParts) -> Result<Self, String> {
+        let layered_processor = LayeredProcessor::new(whisper_ctx, language, prompt)?;
         
         Ok(Self {
             layered_processor,
@@ -0,0 +1,158 @@
+// level_meter.rs - 各采集路径共用的音量表：RMS 转 dBFS 并做指数平滑
+use serde::{Deserialize, Serialize};
+
+/// 静音时报告的电平下限
+pub const SILENCE_DBFS: f32 = -100.0;
+/// 归一化音量条的显示下限，低于该电平时音量条为空
+const METER_FLOOR_DBFS: f32 = -60.0;
+
+/// 音量表平滑参数：电平上升用 attack，下降用 release
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LevelMeterConfig {
+    pub attack_ms: f32,
+    pub release_ms: f32,
+}
+
+impl Default for LevelMeterConfig {
+    fn default() -> Self {
+        Self {
+            attack_ms: 10.0,
+            release_ms: 300.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct LevelReading {
+    pub dbfs: f32,      // 平滑后的 RMS 电平
+    pub peak_dbfs: f32, // 当前数据块的峰值电平
+}
+
+impl Default for LevelReading {
+    fn default() -> Self {
+        Self {
+            dbfs: SILENCE_DBFS,
+            peak_dbfs: SILENCE_DBFS,
+        }
+    }
+}
+
+impl LevelReading {
+    /// 映射到 0..1，供音量条显示
+    pub fn normalized(&self) -> f32 {
+        ((self.dbfs - METER_FLOOR_DBFS) / -METER_FLOOR_DBFS).clamp(0.0, 1.0)
+    }
+}
+
+pub fn rms(samples: &[f32]) -> f32 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+    (samples.iter().map(|&x| x * x).sum::<f32>() / samples.len() as f32).sqrt()
+}
+
+/// 线性幅度转 dBFS（满幅为 0 dBFS），不低于 SILENCE_DBFS
+pub fn to_dbfs(amplitude: f32) -> f32 {
+    if amplitude <= 0.0 {
+        return SILENCE_DBFS;
+    }
+    (20.0 * amplitude.log10()).max(SILENCE_DBFS)
+}
+
+pub struct LevelMeter {
+    config: LevelMeterConfig,
+    samples_per_second: f32, // 采样率 × 声道数（交错数据）
+    current: LevelReading,
+}
+
+impl LevelMeter {
+    pub fn new(config: LevelMeterConfig, sample_rate: u32, channels: u16) -> Self {
+        Self {
+            config,
+            samples_per_second: (sample_rate as f32 * channels.max(1) as f32).max(1.0),
+            current: LevelReading::default(),
+        }
+    }
+
+    /// 送入一个数据块，返回平滑后的读数
+    pub fn process(&mut self, samples: &[f32]) -> LevelReading {
+        if samples.is_empty() {
+            return self.current;
+        }
+
+        let block_ms = samples.len() as f32 / self.samples_per_second * 1000.0;
+        let target = to_dbfs(rms(samples));
+        let time_constant = if target > self.current.dbfs {
+            self.config.attack_ms
+        } else {
+            self.config.release_ms
+        };
+        let coefficient = if time_constant <= 0.0 {
+            1.0
+        } else {
+            1.0 - (-block_ms / time_constant).exp()
+        };
+
+        let peak = samples.iter().fold(0.0f32, |max, &x| max.max(x.abs()));
+        self.current = LevelReading {
+            dbfs: self.current.dbfs + coefficient * (target - self.current.dbfs),
+            peak_dbfs: to_dbfs(peak),
+        };
+        self.current
+    }
+
+    pub fn reading(&self) -> LevelReading {
+        self.current
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dbfs_conversion() {
+        assert!(to_dbfs(1.0).abs() < 1e-6);
+        assert!((to_dbfs(0.5) + 6.0206).abs() < 1e-3);
+        assert_eq!(to_dbfs(0.0), SILENCE_DBFS);
+        assert_eq!(to_dbfs(1e-9), SILENCE_DBFS);
+
+        // 满幅正弦波的 RMS 为 -3.01 dBFS
+        let sine: Vec<f32> = (0..16000)
+            .map(|n| (2.0 * std::f32::consts::PI * 440.0 * n as f32 / 16000.0).sin())
+            .collect();
+        assert!((to_dbfs(rms(&sine)) + 3.0103).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_smoother_step_response() {
+        let config = LevelMeterConfig { attack_ms: 10.0, release_ms: 300.0 };
+        let mut meter = LevelMeter::new(config, 16000, 1);
+        let loud = vec![0.5f32; 16]; // 1ms 的数据块，-6.02 dBFS
+        let target = to_dbfs(0.5);
+
+        // 经过一个 attack 时间常数，剩余差距约为 e^-1
+        for _ in 0..10 {
+            meter.process(&loud);
+        }
+        let remaining = (target - meter.reading().dbfs) / (target - SILENCE_DBFS);
+        assert!((remaining - (-1.0f32).exp()).abs() < 0.01, "剩余比例 {}", remaining);
+        assert!((meter.reading().peak_dbfs - target).abs() < 1e-3);
+
+        for _ in 0..90 {
+            meter.process(&loud);
+        }
+        assert!((meter.reading().dbfs - target).abs() < 0.1);
+
+        // 释放更慢：静音 10ms 后电平只下降一小部分
+        let silence = vec![0.0f32; 16];
+        for _ in 0..10 {
+            meter.process(&silence);
+        }
+        let reading = meter.reading();
+        assert!(reading.dbfs > target - 5.0 && reading.dbfs < target);
+        assert_eq!(reading.peak_dbfs, SILENCE_DBFS);
+        assert!(reading.normalized() > 0.8);
+    }
+}
@@ -9,6 +9,7 @@ include!(concat!(env!("OUT_DIR"), "/bindings.rs"));
 
 // 模块导入
 mod logging;
+mod data_dir;
 mod path_test;
 mod storage;
 mod storage_commands;
@@ -28,6 +29,39 @@ mod context_processor;
 mod result_manager;
 mod optimal_realtime_processor;
 mod model_management;
+mod prompt_builder;
+mod text_processing;
+mod romanization;
+mod translation;
+mod recording_writer;
+mod transcription_progress;
+mod transcription_jobs;
+mod confidence;
+mod transcript_export;
+mod transcript_search;
+mod prompt_comparison;
+mod spectral_analysis;
+mod errors;
+mod level_meter;
+mod capture_core;
+mod realtime_autosave;
+mod decoding;
+mod vad;
+mod metrics;
+mod diagnostics;
+mod denoise;
+mod post_process_rules;
+mod token_inspection;
+mod model_catalog;
+mod languages;
+mod transcription_queue;
+mod audio_clip;
+mod audio_merge;
+mod rediarization;
+mod record_naming;
+mod waveform;
+mod whisper_input;
+mod wav_input;
 
 use std::ffi::{CStr, CString};
 use std::os::raw::c_char;
@@ -41,14 +75,19 @@ use rayon::prelude::*;
 
 // 存储相关导入
 use storage_commands::StorageState;
+use transcription_progress::ProgressTracker;
+use transcription_jobs::{CancellationToken, TranscriptionJobRegistry};
+use confidence::ConfidenceAccumulator;
+use decoding::{DecodingStrategy, SegmentationConfig, TemperatureFallback};
 
 // 音频转换相关导入
 use symphonia::core::audio::SampleBuffer;
 use symphonia::core::codecs::DecoderOptions;
-use symphonia::core::formats::FormatOptions;
+use symphonia::core::formats::{FormatOptions, SeekMode, SeekTo};
 use symphonia::core::io::MediaSourceStream;
 use symphonia::core::meta::MetadataOptions;
 use symphonia::core::probe::Hint;
+use symphonia::core::units::Time;
 // use rubato::{FftFixedInOut, Resampler}; // 暂时不使用复杂的重采样
 
 struct WhisperContextState {
@@ -61,6 +100,8 @@ pub struct RecognitionProgress {
     pub stage: String,
     pub progress: f32,
     pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub eta_secs: Option<f64>, // 预计剩余秒数，仅推理阶段提供
 }
 
 // 识别结果数据结构
@@ -70,6 +111,19 @@ pub struct RecognitionResult {
     pub text: Option<String>,
     pub error: Option<String>,
     pub processing_time: f64,
+    pub accuracy: Option<f64>, // 按段时长加权的置信度估算
+    pub confidence_band: Option<confidence::ConfidenceBand>, // 按设置中的阈值划分的档位，供界面着色
+}
+
+// 单文件转录任务的运行期状态：进度、取消令牌与置信度统计
+struct FileJobContext {
+    progress: ProgressTracker,
+    cancel_token: CancellationToken,
+    confidence: ConfidenceAccumulator,
+    decoding: Option<DecodingStrategy>, // 覆盖按 mode 选择的解码参数
+    temperature_fallback: TemperatureFallback,
+    segmentation: SegmentationConfig,
+    suppression: text_processing::SuppressionConfig, // 非语音标注过滤
 }
 
 // 全局状态管理器
@@ -89,9 +143,15 @@ impl RecognitionState {
         }
     }
 
-    fn start_processing(&self) {
-        *self.is_processing.lock().unwrap() = true;
+    /// 未在处理时占用识别状态，检查与设置在同一次加锁内完成；已被占用时返回 false
+    fn try_start_processing(&self) -> bool {
+        let mut is_processing = self.is_processing.lock().unwrap();
+        if *is_processing {
+            return false;
+        }
+        *is_processing = true;
         *self.should_cancel.lock().unwrap() = false;
+        true
     }
 
     fn stop_processing(&self) {
@@ -139,6 +199,15 @@ impl WhisperContextState {
         *self.ctx.lock().unwrap()
     }
 
+    pub fn is_loaded(&self) -> bool {
+        !self.get_context_ptr().is_null()
+    }
+
+    // 录音类命令的前置检查：没有可用模型时直接返回类型化错误
+    pub fn ensure_ready(&self, model_path: &std::path::Path) -> Result<(), errors::StenoError> {
+        model_management::check_model_ready(model_path, self.is_loaded())
+    }
+
     pub fn reinitialize(&self, model_path: &str) -> Result<(), String> {
         let c_model_path = CString::new(model_path).map_err(|e| e.to_string())?;
         
@@ -160,10 +229,35 @@ impl WhisperContextState {
                 whisper_free(old_ctx);
             }
         }
+        token_inspection::clear_token_cache();
 
         Ok(())
     }
 
+    // 用一秒静音跑一次 whisper_full，把首次识别时的惰性缓冲区分配提前完成
+    pub fn warm_up(&self) -> Result<(), String> {
+        let ctx = self.ctx.lock().unwrap();
+        if ctx.is_null() {
+            return Err("Whisper 上下文未初始化".to_string());
+        }
+
+        let mut params = unsafe { whisper_full_default_params(whisper_sampling_strategy_WHISPER_SAMPLING_GREEDY) };
+        params.print_progress = false;
+        params.print_realtime = false;
+        params.no_context = true;
+        params.single_segment = true;
+        // 固定语言，跳过语言检测
+        let language = CString::new("en").unwrap();
+        params.language = language.as_ptr();
+
+        let mut silence = vec![0.0f32; 16000];
+        let result = unsafe { whisper_full(*ctx, params, silence.as_mut_ptr(), silence.len() as i32) };
+        if result != 0 {
+            return Err(format!("预热推理失败，错误码: {}", result));
+        }
+        Ok(())
+    }
+
     // 创建空的上下文，用于模型不存在的情况
     fn new_empty() -> Self {
         Self {
@@ -185,8 +279,102 @@ impl Drop for WhisperContextState {
 
 // 音频格式转换函数 - 支持多种格式包括MP3, M4A, AAC等
 pub fn load_and_convert_audio(file_path: &str) -> Result<(Vec<f32>, u32, f64), String> {
+    let final_samples = decode_audio_to_mono_16k(file_path, None)?.samples;
+
+    // 应用完整音频预处理流水线
+    let config = AudioProcessingConfig::default();
+    let optimized_samples = match advanced_audio_preprocessing_pipeline(final_samples.clone(), &config) {
+        Ok(processed) => {
+            log::info!("完整音频预处理流水线成功");
+            processed
+        }
+        Err(e) => {
+            log::warn!("完整预处理失败，尝试简单优化: {}", e);
+            // 回退到简单处理
+            match preprocess_audio_with_vad(final_samples.clone()) {
+                Ok(processed) => processed,
+                Err(_) => {
+                    log::warn!("简单处理也失败，使用原始音频");
+                    final_samples
+                }
+            }
+        }
+    };
+
+    let duration = optimized_samples.len() as f64 / whisper_input::WHISPER_SAMPLE_RATE as f64;
+    Ok((optimized_samples, whisper_input::WHISPER_SAMPLE_RATE, duration))
+}
+
+// 解码后的16kHz单声道音频
+pub struct DecodedAudio {
+    pub samples: Vec<f32>,
+    pub start_time: f64, // 第一个采样点在原文件中的时间(秒)
+    pub source_duration: Option<f64>, // 原文件总时长(秒)，容器未提供帧数时为 None
+}
+
+// 解码音频并转换为16kHz单声道；指定时间窗口时先尝试 seek 到起点，解码越过终点即停止
+pub fn decode_audio_to_mono_16k(file_path: &str, range: Option<(f64, f64)>) -> Result<DecodedAudio, String> {
     println!("开始处理音频文件: {}", file_path);
-    
+
+    // WAV 按位深显式换算；hound 不支持的编码（如 A-law）再交给通用解码器
+    let is_wav = std::path::Path::new(file_path).extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("wav"));
+    let (decoded, sample_rate) = if is_wav {
+        // 有时间窗口时先定位再解码，不读取窗口之外的数据
+        match wav_input::read_wav_mono_range(std::path::Path::new(file_path), range) {
+            Ok(audio) if audio.samples.is_empty() && range.is_none() => return Err("无法解码音频数据".to_string()),
+            Ok(audio) => {
+                let decoded = DecodedAudio { samples: audio.samples, start_time: audio.start_time, source_duration: Some(audio.source_duration) };
+                (decoded, audio.sample_rate)
+            }
+            Err(wav_error) => {
+                println!("{}，改用通用解码器", wav_error);
+                decode_mono_with_symphonia(file_path, range).map_err(|e| format!("{}；{}", wav_error, e))?
+            }
+        }
+    } else {
+        decode_mono_with_symphonia(file_path, range)?
+    };
+    let DecodedAudio { samples: mono_samples, start_time: decoded_start, source_duration } = decoded;
+
+    // 截取时间窗口，定位落在起点之后时以实际起点为准
+    let (mono_samples, start_time) = match range {
+        Some((start, end)) => {
+            let (skip, take) = audio_clip::window_frames(decoded_start, start, end, sample_rate);
+            let window: Vec<f32> = mono_samples.into_iter().skip(skip).take(take).collect();
+            if window.is_empty() {
+                return Err(format!("{:.2}-{:.2} 秒范围内没有音频数据", start, end));
+            }
+            (window, start.max(decoded_start))
+        }
+        None => (mono_samples, 0.0),
+    };
+
+    // 重采样到 16kHz (如果需要)
+    let final_samples = if sample_rate != 16000 {
+        println!("需要重采样: {}Hz -> 16000Hz, 样本数: {}", sample_rate, mono_samples.len());
+        
+        // 使用高质量重采样
+        match high_quality_resample(&mono_samples, sample_rate, 16000) {
+            Ok(resampled) => {
+                println!("高质量重采样完成: {} -> {} 个采样点", mono_samples.len(), resampled.len());
+                resampled
+            }
+            Err(e) => {
+                println!("高质量重采样失败，使用备用方案: {}", e);
+                // 回退到简单重采样
+                fallback_resample(&mono_samples, sample_rate, 16000)
+            }
+        }
+    } else {
+        mono_samples
+    };
+
+    Ok(DecodedAudio { samples: final_samples, start_time, source_duration })
+}
+
+// 用 symphonia 解码为原采样率的单声道，返回的 start_time 为第一个成功解码的包的时间
+fn decode_mono_with_symphonia(file_path: &str, range: Option<(f64, f64)>) -> Result<(DecodedAudio, u32), String> {
     // 读取音频文件
     let file = std::fs::File::open(file_path).map_err(|e| format!("无法打开文件: {}", e))?;
     let mss = MediaSourceStream::new(Box::new(file), Default::default());
@@ -219,6 +407,10 @@ pub fn load_and_convert_audio(file_path: &str) -> Result<(Vec<f32>, u32, f64), S
     println!("找到音频轨道，编解码器: {:?}", track.codec_params.codec);
 
     let track_id = track.id;
+    let time_base = track.codec_params.time_base;
+    let source_duration = track.codec_params.n_frames
+        .zip(track.codec_params.sample_rate)
+        .map(|(frames, rate)| frames as f64 / rate as f64);
     let mut decoder = symphonia::default::get_codecs()
         .make(&track.codec_params, &DecoderOptions { verify: false })
         .map_err(|e| format!("无法创建解码器: {}", e))?;
@@ -235,10 +427,27 @@ pub fn load_and_convert_audio(file_path: &str) -> Result<(Vec<f32>, u32, f64), S
     
     println!("音频信息: {}Hz, {}声道(预估)", sample_rate, channels);
 
+    // 支持定位的格式直接跳到窗口起点，不支持时从头解码再截取
+    if let Some((start, _)) = range.filter(|(start, _)| *start > 0.0) {
+        let seek_to = SeekTo::Time { time: Time::from(start), track_id: Some(track_id) };
+        match format.seek(SeekMode::Accurate, seek_to) {
+            Ok(_) => decoder.reset(),
+            Err(e) => log::warn!("音频格式不支持定位，从头解码: {}", e),
+        }
+    }
+    let packet_time = |ts: u64| match time_base {
+        Some(time_base) => {
+            let time = time_base.calc_time(ts);
+            time.seconds as f64 + time.frac
+        }
+        None => ts as f64 / sample_rate as f64,
+    };
+
     // 解码音频数据
     let mut audio_samples = Vec::new();
     let mut sample_buf = None;
     let mut actual_channels = channels; // 从音频数据中获取的实际声道数
+    let mut decoded_start = None; // 第一个成功解码的包的时间
 
     loop {
         let packet = match format.next_packet() {
@@ -249,9 +458,13 @@ pub fn load_and_convert_audio(file_path: &str) -> Result<(Vec<f32>, u32, f64), S
         if packet.track_id() != track_id {
             continue;
         }
+        if range.is_some_and(|(_, end)| packet_time(packet.ts()) >= end) {
+            break;
+        }
 
         match decoder.decode(&packet) {
             Ok(decoded) => {
+                decoded_start.get_or_insert(packet_time(packet.ts()));
                 if sample_buf.is_none() {
                     let spec = *decoded.spec();
                     actual_channels = spec.channels.count(); // 从实际解码数据获取声道数
@@ -288,48 +501,8 @@ pub fn load_and_convert_audio(file_path: &str) -> Result<(Vec<f32>, u32, f64), S
         audio_samples
     };
 
-    // 重采样到 16kHz (如果需要)
-    let final_samples = if sample_rate != 16000 {
-        println!("需要重采样: {}Hz -> 16000Hz, 样本数: {}", sample_rate, mono_samples.len());
-        
-        // 使用高质量重采样
-        match high_quality_resample(&mono_samples, sample_rate, 16000) {
-            Ok(resampled) => {
-                println!("高质量重采样完成: {} -> {} 个采样点", mono_samples.len(), resampled.len());
-                resampled
-            }
-            Err(e) => {
-                println!("高质量重采样失败，使用备用方案: {}", e);
-                // 回退到简单重采样
-                fallback_resample(&mono_samples, sample_rate, 16000)
-            }
-        }
-    } else {
-        mono_samples
-    };
-
-    // 应用完整音频预处理流水线
-    let config = AudioProcessingConfig::default();
-    let optimized_samples = match advanced_audio_preprocessing_pipeline(final_samples.clone(), &config) {
-        Ok(processed) => {
-            println!("完整音频预处理流水线成功");
-            processed
-        }
-        Err(e) => {
-            println!("完整预处理失败，尝试简单优化: {}", e);
-            // 回退到简单处理
-            match preprocess_audio_with_vad(final_samples.clone()) {
-                Ok(processed) => processed,
-                Err(_) => {
-                    println!("简单处理也失败，使用原始音频");
-                    final_samples
-                }
-            }
-        }
-    };
-
-    let duration = optimized_samples.len() as f64 / 16000.0;
-    Ok((optimized_samples, 16000, duration))
+    let decoded = DecodedAudio { samples: mono_samples, start_time: decoded_start.unwrap_or(0.0), source_duration };
+    Ok((decoded, sample_rate))
 }
 
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
@@ -342,21 +515,84 @@ fn greet(name: &str) -> String {
 #[tauri::command]
 fn recognize_file_async(
     path: String,
-    language: String,
-    mode: String,
+    language: Option<String>,
+    mode: Option<String>,
     initial_prompt: Option<String>,
+    prompt_template_id: Option<String>,
+    hotwords: Option<Vec<String>>,
+    record_id: Option<String>,
+    decoding: Option<DecodingStrategy>,
+    temperature_fallback: Option<TemperatureFallback>,
+    segmentation: Option<SegmentationConfig>,
+    suppression: Option<text_processing::SuppressionConfig>,
     app_handle: tauri::AppHandle,
 ) -> Result<String, String> {
     // 获取状态管理器
     let recognition_state = app_handle.state::<RecognitionState>();
     
-    // 检查是否已在处理中
+    // 已在处理中时提前拒绝，不覆盖上次的配置；真正占用处理状态在登记任务时完成
     if recognition_state.is_processing() {
         return Err("已有识别任务在进行中".to_string());
     }
     
-    // 启动处理状态
-    recognition_state.start_processing();
+    // 未指定的参数沿用上次的配置（包括提示词模板），并记住本次使用的配置
+    let storage_state = app_handle.state::<StorageState>();
+    let mut last_config = storage_state.with_storage(|s| s.get_last_used_config()).unwrap_or_default();
+    let mut transcription_config = last_config.resolve_transcription(language, mode);
+    if decoding.is_some() {
+        transcription_config.decoding = decoding;
+    }
+    if let Some(fallback) = temperature_fallback {
+        transcription_config.temperature_fallback = fallback;
+    }
+    if let Some(segmentation) = segmentation {
+        transcription_config.segmentation = segmentation;
+    }
+    if let Some(suppression) = suppression {
+        transcription_config.suppression = suppression;
+    }
+    if let Some(ref decoding) = transcription_config.decoding {
+        decoding.validate()?;
+    }
+    transcription_config.segmentation.validate()?;
+    let prompt_template_id = prompt_template_id.or_else(|| last_config.prompt_template_id.clone());
+    let initial_prompt = initial_prompt.or_else(|| {
+        let id = prompt_template_id.as_deref()?;
+        storage_state.with_storage(|s| s.get_prompt_template(id)).ok().flatten().map(|t| t.content)
+    });
+    last_config.transcription = Some(transcription_config.clone());
+    last_config.prompt_template_id = prompt_template_id;
+    if let Err(e) = storage_state.with_storage(|s| s.set_last_used_config(&last_config)) {
+        log::warn!("⚠️ 保存转录配置失败: {}", e);
+    }
+    let language = transcription_config.language;
+    // 仅英文模型不能识别其他语言；模型文件本身的问题留给后续加载流程报告
+    let model_path = app_handle.state::<Arc<Mutex<model_management::ModelManager>>>().lock().unwrap().get_current_model_path();
+    match model_management::check_model_language(&model_path, &language) {
+        Err(e @ errors::StenoError::ModelLanguageMismatch(_)) => return Err(e.into()),
+        Ok(model_management::LanguageCompatibility::Warning { message }) => log::warn!("⚠️ {}", message),
+        _ => {}
+    }
+    let mode = transcription_config.mode;
+    let decoding = transcription_config.decoding;
+    let temperature_fallback = transcription_config.temperature_fallback;
+    let segmentation = transcription_config.segmentation;
+    let suppression = transcription_config.suppression;
+    
+    // 占用处理状态，检查与设置在同一次加锁内完成，并发调用只有一个能通过
+    if !recognition_state.try_start_processing() {
+        return Err("已有识别任务在进行中".to_string());
+    }
+    
+    // 登记任务，取消令牌按记录ID（无记录ID时按文件路径）索引
+    let job_key = record_id.clone().unwrap_or_else(|| path.clone());
+    let cancel_token = match app_handle.state::<TranscriptionJobRegistry>().register(&job_key) {
+        Ok(token) => token,
+        Err(e) => {
+            recognition_state.stop_processing();
+            return Err(e);
+        }
+    };
     
     // 获取主窗口
     let window = app_handle.get_webview_window("main").unwrap();
@@ -366,13 +602,15 @@ fn recognize_file_async(
         stage: "initializing".to_string(),
         progress: 0.0,
         message: "初始化语音识别引擎...".to_string(),
+        eta_secs: None,
     });
     
     // 在新线程中执行识别，避免阻塞前端
     let path_clone = path.clone();
     let language_clone = language.clone();
     let mode_clone = mode.clone();
-    let initial_prompt_clone = initial_prompt.clone();
+    // 热词与提示词模板在识别时合并进初始提示词
+    let prompt = prompt_builder::PromptParts::new(initial_prompt, hotwords.unwrap_or_default());
     let app_handle_clone = app_handle.clone();
     
     std::thread::spawn(move || {
@@ -384,18 +622,25 @@ fn recognize_file_async(
             path_clone, 
             language_clone,
             mode_clone,
-            initial_prompt_clone,
+            decoding,
+            temperature_fallback,
+            segmentation,
+            suppression,
+            prompt,
+            record_id,
             window, 
             &*whisper_state, 
-            &*recognition_state
+            &*recognition_state,
+            &cancel_token,
         );
         
         // 无论成功失败都停止处理状态
         recognition_state.stop_processing();
+        app_handle_clone.state::<TranscriptionJobRegistry>().finish(&job_key);
         
         match result {
-            Ok(text) => println!("识别成功完成: {} 字符", text.len()),
-            Err(e) => println!("识别失败: {}", e),
+            Ok(text) => log::info!("识别成功完成: {} 字符", text.len()),
+            Err(e) => log::warn!("识别失败: {}", e),
         }
     });
     
@@ -409,6 +654,7 @@ fn cancel_file_transcription(app_handle: tauri::AppHandle) -> Result<String, Str
     // 总是设置取消标志，即使当前状态显示没有处理中的任务
     // 这可以处理竞态条件问题，确保取消请求不会被拒绝
     recognition_state.request_cancel();
+    app_handle.state::<TranscriptionJobRegistry>().cancel_all();
     
     // 发送取消事件到前端
     if let Some(window) = app_handle.get_webview_window("main") {
@@ -416,6 +662,7 @@ fn cancel_file_transcription(app_handle: tauri::AppHandle) -> Result<String, Str
             stage: "cancelling".to_string(),
             progress: 0.0,
             message: "正在取消转录...".to_string(),
+            eta_secs: None,
         });
     }
     
@@ -427,15 +674,174 @@ fn cancel_file_transcription(app_handle: tauri::AppHandle) -> Result<String, Str
     }
 }
 
+// 创建单文件转录的进度跟踪器：发送进度事件，并在提供记录ID时更新记录进度
+fn create_progress_tracker(
+    total_duration: f64,
+    record_id: Option<String>,
+    window: WebviewWindow,
+    cancel_token: CancellationToken,
+) -> ProgressTracker {
+    ProgressTracker::new(total_duration, Box::new(move |progress, processed, total, eta| {
+        // 取消后不再覆盖记录状态
+        if cancel_token.is_cancelled() {
+            return;
+        }
+
+        let _ = window.emit("recognition_progress", RecognitionProgress {
+            stage: "transcribing".to_string(),
+            progress: progress as f32,
+            message: match eta {
+                Some(eta) => format!("正在识别 {:.0}/{:.0} 秒，预计剩余 {:.0} 秒", processed, total, eta),
+                None => format!("正在识别 {:.0}/{:.0} 秒", processed, total),
+            },
+            eta_secs: eta,
+        });
+
+        if let Some(ref id) = record_id {
+            let storage_state = window.state::<StorageState>();
+            if let Err(e) = storage_state.with_storage(|storage| storage.update_record_status(id, "processing", progress, None)) {
+                log::warn!("更新记录进度失败: {}", e);
+            }
+        }
+    }))
+}
+
+// 取消指定记录的转录：中断 Whisper 推理并将记录状态设为 cancelled
+#[tauri::command]
+fn cancel_transcription(record_id: String, app_handle: tauri::AppHandle) -> Result<String, String> {
+    let registry = app_handle.state::<TranscriptionJobRegistry>();
+    let storage_state = app_handle.state::<StorageState>();
+
+    storage_state.with_storage(|storage| {
+        Ok(transcription_jobs::cancel_job(&registry, storage, &record_id))
+    })??;
+    app_handle.state::<RecognitionState>().request_cancel();
+
+    if let Some(window) = app_handle.get_webview_window("main") {
+        let _ = window.emit("recognition_progress", RecognitionProgress {
+            stage: "cancelling".to_string(),
+            progress: 0.0,
+            message: "正在取消转录...".to_string(),
+            eta_secs: None,
+        });
+    }
+
+    Ok("转录已取消".to_string())
+}
+
+// 使用两个提示词模板分别转录同一条记录的音频，返回两份结果和词语差异
+#[tauri::command]
+async fn transcribe_with_prompts(
+    record_id: String,
+    prompt_ids: [String; 2],
+    app_handle: tauri::AppHandle,
+) -> Result<prompt_comparison::PromptComparison, String> {
+    let storage_state = app_handle.state::<StorageState>();
+    let record = storage_state
+        .with_storage(|storage| storage.get_record(&record_id))?
+        .ok_or_else(|| format!("记录不存在: {}", record_id))?;
+    let mut prompts = Vec::with_capacity(2);
+    for prompt_id in &prompt_ids {
+        let prompt = storage_state
+            .with_storage(|storage| storage.get_prompt_template(prompt_id))?
+            .ok_or_else(|| format!("提示词模板不存在: {}", prompt_id))?;
+        prompts.push((prompt.id, prompt.name, prompt.content));
+    }
+
+    let recognition_state = app_handle.state::<RecognitionState>();
+    if !recognition_state.try_start_processing() {
+        return Err("已有识别任务在进行中".to_string());
+    }
+    let cancel_token = match app_handle.state::<TranscriptionJobRegistry>().register(&record_id) {
+        Ok(token) => token,
+        Err(e) => {
+            recognition_state.stop_processing();
+            return Err(e);
+        }
+    };
+
+    let app_handle_clone = app_handle.clone();
+    let job_key = record_id.clone();
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        let whisper_state = app_handle_clone.state::<WhisperContextState>();
+        let recognition_state = app_handle_clone.state::<RecognitionState>();
+        let window = app_handle_clone.get_webview_window("main").ok_or("主窗口不可用")?;
+
+        let (audio_data, _, duration) = load_and_convert_audio(&record.file_path)?;
+        let language = record.config.language.clone();
+
+        prompt_comparison::compare_prompts(&record.id, &prompts, |prompt| {
+            // 对比运行不更新记录进度，也不覆盖记录中已保存的结果
+            let mut job = FileJobContext {
+                progress: ProgressTracker::new(duration, Box::new(|_, _, _, _| {})),
+                cancel_token: cancel_token.clone(),
+                confidence: ConfidenceAccumulator::new(),
+                decoding: record.config.decoding,
+                temperature_fallback: record.config.temperature_fallback,
+                segmentation: record.config.segmentation,
+                suppression: record.config.suppression.clone(),
+            };
+            let text = advanced_recognition_pipeline(
+                audio_data.clone(),
+                language.clone(),
+                record.config.mode.clone(),
+                prompt_builder::PromptParts::new(Some(prompt.to_string()), Vec::new()),
+                &whisper_state,
+                &window,
+                &recognition_state,
+                &mut job,
+            )?;
+            if cancel_token.is_cancelled() {
+                return Err("转录已被用户取消".to_string());
+            }
+            Ok(post_process_text(&text, &language))
+        })
+    })
+    .await
+    .map_err(|e| format!("提示词对比任务异常: {}", e));
+
+    recognition_state.stop_processing();
+    app_handle.state::<TranscriptionJobRegistry>().finish(&job_key);
+    result?
+}
+
+// 转录前估算记录音频中的说话人数量及各自的大致时间范围
+#[tauri::command]
+async fn estimate_speaker_count(
+    record_id: String,
+    max_speakers: Option<usize>,
+    app_handle: tauri::AppHandle,
+) -> Result<realtime_speaker_diarization::SpeakerCountEstimate, String> {
+    let record = app_handle.state::<StorageState>()
+        .with_storage(|storage| storage.get_record(&record_id))?
+        .ok_or_else(|| format!("记录不存在: {}", record_id))?;
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let (audio_data, sample_rate, _) = load_and_convert_audio(&record.file_path)?;
+        let mut diarization = realtime_speaker_diarization::RealtimeSpeakerDiarization::new();
+        let estimate = diarization.estimate_speaker_count(&audio_data, sample_rate, max_speakers.unwrap_or(4));
+        println!("记录 {} 估计说话人数量: {}", record.id, estimate.speaker_count);
+        Ok(estimate)
+    })
+    .await
+    .map_err(|e| format!("说话人数量估算任务异常: {}", e))?
+}
+
 // 实际的阻塞式识别函数
 fn recognize_file_blocking_inner(
     path: String,
     language: String,
     mode: String,
-    initial_prompt: Option<String>,
+    decoding: Option<DecodingStrategy>,
+    temperature_fallback: TemperatureFallback,
+    segmentation: SegmentationConfig,
+    suppression: text_processing::SuppressionConfig,
+    prompt: prompt_builder::PromptParts,
+    record_id: Option<String>,
     window: WebviewWindow,
     whisper_state: &WhisperContextState,
     recognition_state: &RecognitionState,
+    cancel_token: &CancellationToken,
 ) -> Result<String, String> {
     let start_time = std::time::Instant::now();
     
@@ -446,6 +852,8 @@ fn recognize_file_blocking_inner(
             text: None,
             error: Some("转录已被用户取消".to_string()),
             processing_time: start_time.elapsed().as_secs_f64(),
+            accuracy: None,
+            confidence_band: None,
         });
         return Err("转录已被用户取消".to_string());
     }
@@ -455,9 +863,10 @@ fn recognize_file_blocking_inner(
         stage: "converting".to_string(),
         progress: 20.0,
         message: "正在转换音频格式...".to_string(),
+        eta_secs: None,
     });
 
-    let (audio_data, _, _) = match load_and_convert_audio(&path) {
+    let (audio_data, sample_rate, _) = match load_and_convert_audio(&path) {
         Ok(data) => {
             println!("音频转换成功: {} 个采样点", data.0.len());
             data
@@ -470,41 +879,12 @@ fn recognize_file_blocking_inner(
                 stage: "converting_wav".to_string(),
                 progress: 25.0,
                 message: "尝试WAV格式转换...".to_string(),
+                eta_secs: None,
             });
             
-            let mut reader = hound::WavReader::open(&path).map_err(|e| {
-                let error_msg = format!("无法打开文件 (尝试了通用格式和 WAV 格式): {}", e);
-                // 停止处理状态并发送错误事件
-                recognition_state.stop_processing();
-                let _ = window.emit("recognition_complete", RecognitionResult {
-                    success: false,
-                    text: None,
-                    error: Some(error_msg.clone()),
-                    processing_time: 0.0,
-                });
-                error_msg
-            })?;
-            let spec = reader.spec();
-            
-            if spec.channels != 1 || spec.sample_rate != 16000 {
-                let error_msg = format!(
-                    "检测到 WAV 文件，但格式不符合要求:\\n当前: {}声道, {}Hz\\n需要: 1声道, 16000Hz\\n\\n建议使用 FFmpeg 转换:\\nffmpeg -i \\\"{}\\\" -ar 16000 -ac 1 output.wav", 
-                    spec.channels, spec.sample_rate, path
-                );
-                // 停止处理状态并发送错误事件
-                recognition_state.stop_processing();
-                let _ = window.emit("recognition_complete", RecognitionResult {
-                    success: false,
-                    text: None,
-                    error: Some(error_msg.clone()),
-                    processing_time: 0.0,
-                });
-                return Err(error_msg);
-            }
-            
-            let samples: Result<Vec<i16>, _> = reader.samples().collect();
-            let samples = samples.map_err(|e| {
-                let error_msg = e.to_string();
+            // 按位深换算，非 16kHz 的采样率在识别前统一重采样
+            let audio = wav_input::read_wav_mono(std::path::Path::new(&path)).map_err(|wav_error| {
+                let error_msg = format!("无法打开文件 (尝试了通用格式和 WAV 格式): {}", wav_error);
                 // 停止处理状态并发送错误事件
                 recognition_state.stop_processing();
                 let _ = window.emit("recognition_complete", RecognitionResult {
@@ -512,11 +892,13 @@ fn recognize_file_blocking_inner(
                     text: None,
                     error: Some(error_msg.clone()),
                     processing_time: 0.0,
+                    accuracy: None,
+                    confidence_band: None,
                 });
                 error_msg
             })?;
-            
-            (samples.iter().map(|&s| s as f32 / 32768.0).collect(), 16000, samples.len() as f64 / 16000.0)
+            let duration = audio.samples.len() as f64 / audio.sample_rate as f64;
+            (audio.samples, audio.sample_rate, duration)
         }
     };
 
@@ -527,6 +909,8 @@ fn recognize_file_blocking_inner(
             text: None,
             error: Some("转录已被用户取消".to_string()),
             processing_time: start_time.elapsed().as_secs_f64(),
+            accuracy: None,
+            confidence_band: None,
         });
         return Err("转录已被用户取消".to_string());
     }
@@ -536,6 +920,7 @@ fn recognize_file_blocking_inner(
         stage: "preparing".to_string(),
         progress: 40.0,
         message: "准备语音识别...".to_string(),
+        eta_secs: None,
     });
 
     // 检查是否需要取消
@@ -545,6 +930,8 @@ fn recognize_file_blocking_inner(
             text: None,
             error: Some("转录已被用户取消".to_string()),
             processing_time: start_time.elapsed().as_secs_f64(),
+            accuracy: None,
+            confidence_band: None,
         });
         return Err("转录已被用户取消".to_string());
     }
@@ -554,10 +941,48 @@ fn recognize_file_blocking_inner(
         stage: "processing".to_string(),
         progress: 50.0,
         message: "开始高级音频预处理...".to_string(),
+        eta_secs: None,
     });
 
+    // 先统一到 16kHz，时长与进度总量按识别实际使用的采样点计算
+    let audio_data = whisper_input::ensure_whisper_rate(audio_data, sample_rate).into_owned();
+    let audio_duration = audio_data.len() as f64 / whisper_input::WHISPER_SAMPLE_RATE as f64;
+
+    // 按已处理音频时长上报真实进度，并同步到转录记录
+    let mut job = FileJobContext {
+        progress: create_progress_tracker(
+            audio_duration,
+            record_id.clone(),
+            window.clone(),
+            cancel_token.clone(),
+        ),
+        cancel_token: cancel_token.clone(),
+        confidence: ConfidenceAccumulator::new(),
+        decoding,
+        temperature_fallback,
+        segmentation,
+        suppression,
+    };
+
     // 使用高级识别流水线，包含音频预处理、VAD分段、增强等功能
-    let full_text = match advanced_recognition_pipeline(audio_data, language.clone(), mode.clone(), initial_prompt.clone(), whisper_state, &window, recognition_state) {
+    let full_text = match advanced_recognition_pipeline(audio_data, language.clone(), mode.clone(), prompt, whisper_state, &window, recognition_state, &mut job) {
+        Ok(_) | Err(_) if cancel_token.is_cancelled() => {
+            // 丢弃已识别的部分结果，记录状态保持为 cancelled
+            if let Some(ref id) = record_id {
+                let _ = window.state::<StorageState>().with_storage(|storage| {
+                    storage.update_record_status(id, transcription_jobs::STATUS_CANCELLED, 0.0, None)
+                });
+            }
+            let _ = window.emit("recognition_complete", RecognitionResult {
+                success: false,
+                text: None,
+                error: Some("转录已被用户取消".to_string()),
+                processing_time: start_time.elapsed().as_secs_f64(),
+                accuracy: None,
+                confidence_band: None,
+            });
+            return Err("转录已被用户取消".to_string());
+        }
         Ok(text) => text,
         Err(e) => {
             let error_msg = format!("高级识别流程失败: {}", e);
@@ -568,6 +993,8 @@ fn recognize_file_blocking_inner(
                 text: None,
                 error: Some(error_msg.clone()),
                 processing_time: start_time.elapsed().as_secs_f64(),
+                accuracy: None,
+                confidence_band: None,
             });
             return Err(error_msg);
         }
@@ -578,25 +1005,58 @@ fn recognize_file_blocking_inner(
         stage: "post_processing".to_string(),
         progress: 95.0,
         message: "后处理识别结果...".to_string(),
+        eta_secs: None,
     });
 
     let processed_text = post_process_text(&full_text, &language);
 
     let processing_time = start_time.elapsed().as_secs_f64();
+    let accuracy = job.confidence.average();
+
+    // 有记录ID时直接保存结果和准确率估算
+    if let Some(ref id) = record_id {
+        let result = storage::TranscriptionResult {
+            text: processed_text.clone(),
+            processing_time,
+            accuracy,
+            segments: None,
+        };
+        if let Err(e) = window.state::<StorageState>().with_storage(|storage| storage.update_record_result(id, &result)) {
+            println!("保存识别结果失败: {}", e);
+        }
+
+        // 记录模型与实时倍率，用于按模型统计处理速度
+        let model_name = window.state::<Arc<Mutex<model_management::ModelManager>>>()
+            .lock()
+            .ok()
+            .map(|manager| manager.config.lock().unwrap().current_model.clone());
+        let xrt = transcription_progress::realtime_factor(audio_duration, processing_time);
+        if let Err(e) = window.state::<StorageState>().with_storage(|storage| {
+            storage.update_record_performance(id, model_name.as_deref(), xrt)
+        }) {
+            println!("保存处理速度失败: {}", e);
+        }
+    }
 
     // 步骤5: 完成
     let _ = window.emit("recognition_progress", RecognitionProgress {
         stage: "completing".to_string(),
         progress: 100.0,
         message: "识别完成!".to_string(),
+        eta_secs: None,
     });
 
     // 发送结果事件
+    let thresholds = window.state::<StorageState>()
+        .with_storage(confidence::ConfidenceThresholds::load)
+        .unwrap_or_default();
     let final_result = RecognitionResult {
         success: true,
         text: Some(processed_text.clone()),
         error: None,
         processing_time,
+        accuracy,
+        confidence_band: thresholds.band(accuracy),
     };
 
     let _ = window.emit("recognition_complete", final_result);
@@ -718,12 +1178,17 @@ pub fn run() {
         }
     };
     
-    let whisper_context = if final_model_path.exists() {
-        WhisperContextState::new(&final_model_path.to_string_lossy())
-            .expect("Failed to create whisper context")
-    } else {
-        // 创建一个占位符上下文，稍后需要重新初始化
-        WhisperContextState::new_empty()
+    // 校验模型文件头，无效或加载失败时使用占位符上下文，稍后由用户切换模型
+    let whisper_context = match model_management::validate_model_file(&final_model_path) {
+        Ok(_) => WhisperContextState::new(&final_model_path.to_string_lossy())
+            .unwrap_or_else(|e| {
+                eprintln!("Warning: Failed to load model {}: {}", final_model_path.display(), e);
+                WhisperContextState::new_empty()
+            }),
+        Err(e) => {
+            eprintln!("Warning: {}", e);
+            WhisperContextState::new_empty()
+        }
     };
     
     let recognition_state = RecognitionState::new();
@@ -731,12 +1196,14 @@ pub fn run() {
     tauri::Builder::default()
         .manage(whisper_context)
         .manage(recognition_state)
+        .manage(TranscriptionJobRegistry::new())
         .manage(StorageState::new())
         .manage(realtime_audio_full::AudioCaptureState::default())
         // 新的优化处理器状态
         .manage(optimal_realtime_processor::OptimalRealtimeState::default())
         // 模型管理状态
         .manage(model_manager)
+        .manage(model_management::ModelStatusState::default())
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_fs::init())
@@ -746,6 +1213,9 @@ pub fn run() {
             greet, 
             recognize_file_async,
             cancel_file_transcription,
+            cancel_transcription,
+            transcribe_with_prompts,
+            estimate_speaker_count,
             storage_commands::init_storage,
             storage_commands::save_transcription_record,
             storage_commands::get_transcription_record,
@@ -756,6 +1226,41 @@ pub fn run() {
             storage_commands::toggle_transcription_star,
             storage_commands::update_transcription_name,
             storage_commands::search_transcription_records,
+            storage_commands::get_library_stats,
+            storage_commands::get_segments_in_range,
+            storage_commands::segment_at_time,
+            storage_commands::get_recording_session,
+            storage_commands::get_diarization_summary,
+            transcript_search::find_in_transcript,
+            storage_commands::list_all_tags,
+            storage_commands::rename_tag,
+            storage_commands::list_categories,
+            storage_commands::rename_category,
+            storage_commands::delete_category,
+            storage_commands::set_speaker_name,
+            storage_commands::export_transcription_record,
+            storage_commands::export_records_combined,
+            storage_commands::evaluate_record,
+            diagnostics::run_diagnostics,
+            logging::get_log_path,
+            logging::open_log_dir,
+            token_inspection::get_segment_tokens,
+            confidence::get_confidence_thresholds,
+            confidence::set_confidence_thresholds,
+            model_catalog::get_model_catalog,
+            languages::get_supported_languages,
+            transcription_queue::enqueue_transcription,
+            transcription_queue::get_transcription_queue,
+            transcription_queue::take_next_queued_transcription,
+            transcription_queue::update_queued_transcription,
+            transcription_queue::clear_finished_transcriptions,
+            audio_clip::transcribe_range,
+            audio_merge::merge_and_transcribe,
+            rediarization::rediarize_record,
+            record_naming::generate_record_name,
+            record_naming::get_auto_name_records,
+            record_naming::set_auto_name_records,
+            waveform::compute_waveform_peaks,
             // 提示词管理相关命令
             storage_commands::get_prompt_templates,
             storage_commands::get_prompts_by_filter,
@@ -764,6 +1269,9 @@ pub fn run() {
             storage_commands::delete_prompt_template,
             storage_commands::search_prompt_templates,
             storage_commands::increment_prompt_usage,
+            storage_commands::get_prompt_usage_stats,
+            storage_commands::get_last_config,
+            storage_commands::set_last_config,
             // 数据库管理命令
             database_commands::get_database_info,
             database_commands::create_database_backup,
@@ -772,11 +1280,16 @@ pub fn run() {
             database_commands::vacuum_database,
             database_commands::check_database_integrity,
             database_commands::delete_database_backup,
+            database_commands::set_backup_size_budget,
+            database_commands::list_backups,
+            database_commands::create_backup_now,
+            database_commands::restore_backup_from,
             long_audio_commands::create_long_audio_task,
             long_audio_commands::start_long_audio_task,
             long_audio_commands::pause_long_audio_task,
             long_audio_commands::resume_long_audio_task,
             long_audio_commands::cancel_long_audio_task,
+            long_audio_commands::retry_failed_long_audio_segments,
             long_audio_commands::get_long_audio_task,
             long_audio_commands::get_all_long_audio_tasks,
             realtime_audio_full::start_realtime_recording,
@@ -784,6 +1297,10 @@ pub fn run() {
             realtime_audio_full::resume_realtime_recording,
             realtime_audio_full::stop_realtime_recording,
             realtime_audio_full::get_recording_duration,
+            realtime_audio_full::get_realtime_current_transcript,
+            realtime_audio_full::get_realtime_segments,
+            realtime_audio_full::get_realtime_speaker_timeline,
+            realtime_audio_full::reset_speaker_diarization,
             audio_devices::get_audio_devices,
             audio_devices::test_audio_device,
             audio_devices::stop_audio_test,
@@ -810,14 +1327,17 @@ pub fn run() {
             // 模型管理命令
             model_management::list_installed_models,
             model_management::get_storage_info,
+            model_management::plan_download,
             model_management::download_model,
             model_management::switch_model,
             model_management::delete_model,
             model_management::scan_local_models,
             model_management::import_local_model,
-            model_management::get_current_model
+            model_management::get_current_model,
+            model_management::is_model_ready,
+            model_management::get_model_status
         ])
-        .setup(|app| {
+        .setup(move |app| {
             let app_handle = app.handle().clone();
             
             // 首先初始化日志系统
@@ -839,6 +1359,12 @@ pub fn run() {
                     Ok(_) => {
                         log::info!("✅ 存储服务同步初始化成功");
                         println!("✅ 存储服务同步初始化成功");
+                        // 恢复上次退出时未完成的批量转录队列
+                        match storage_state.with_storage(transcription_queue::restore_on_startup) {
+                            Ok(0) => {}
+                            Ok(requeued) => log::info!("🔁 已重新排队 {} 个中断的转录任务", requeued),
+                            Err(e) => log::warn!("⚠️ 恢复转录队列失败: {}", e),
+                        }
                     },
                     Err(e) => {
                         log::error!("❌ 存储服务同步初始化失败: {}", e);
@@ -852,6 +1378,11 @@ pub fn run() {
                 eprintln!("⚠️ 未找到存储状态，跳过存储服务初始化");
             }
             
+            // 加载用户自定义的后处理规则，失败时使用内置规则
+            if let Err(e) = post_process_rules::load_active_rules(&app_handle) {
+                log::warn!("⚠️ 加载后处理规则失败，使用内置规则: {}", e);
+            }
+            
             // 2. 异步初始化其他非关键组件（不阻塞UI）
             let app_handle_clone = app_handle.clone();
             tauri::async_runtime::spawn(async move {
@@ -871,12 +1402,32 @@ pub fn run() {
                 }
             });
             
+            // 3. 模型未就绪时通知前端引导下载或切换模型
+            let whisper_state = app.state::<WhisperContextState>();
+            if whisper_state.is_loaded() {
+                model_management::warm_up_model(app_handle.clone(), final_model_path.to_string_lossy().to_string());
+            }
+            if let Err(e) = whisper_state.ensure_ready(&final_model_path) {
+                log::warn!("⚠️ 模型未就绪: {}", e);
+                model_management::report_model_missing(&app_handle, model_management::ModelMissingEvent {
+                    model_path: final_model_path.to_string_lossy().to_string(),
+                    code: e.code().to_string(),
+                    message: e.to_string(),
+                });
+            }
+
             log::info!("✅ Tauri 应用设置完成");
             println!("✅ Tauri 应用设置完成");
             Ok(())
         })
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while running tauri application")
+        .run(|_app_handle, event| {
+            // 退出前停止长音频工作线程并等待结束
+            if let tauri::RunEvent::Exit = event {
+                tauri::async_runtime::block_on(long_audio::LONG_AUDIO_PROCESSOR.shutdown());
+            }
+        });
 }
 
 /// 初始化非关键组件 - 异步执行，不阻塞应用启动
@@ -891,25 +1442,63 @@ async fn initialize_non_critical_components(app_handle: &tauri::AppHandle) -> Re
         }
     }
     
-    // 2. 其他非关键初始化任务可以在这里添加
+    // 2. 修复上次异常退出时未完成的录音文件
+    if let Ok(app_data_dir) = data_dir::resolve_data_dir(&app_handle) {
+        let repaired = recording_writer::recover_unfinalized_recordings(&app_data_dir.join("recordings"));
+        if !repaired.is_empty() {
+            log::info!("🩹 已修复 {} 个未完成的录音文件", repaired.len());
+        }
+    }
+    
+    // 3. 启动空闲维护线程（大量删除后自动 VACUUM）
+    spawn_idle_maintenance(app_handle.clone());
+    
+    // 4. 其他非关键初始化任务可以在这里添加
     // 例如：预加载配置、检查更新等
     
     log::info!("✅ 非关键组件初始化完成");
     Ok(())
 }
 
+// 空闲维护：定期检查累计删除量，仅在没有识别、录音、长音频任务且近期没有写入时执行 VACUUM
+fn spawn_idle_maintenance(app_handle: tauri::AppHandle) {
+    const MAINTENANCE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(300);
+
+    std::thread::spawn(move || loop {
+        std::thread::sleep(MAINTENANCE_INTERVAL);
+
+        let is_busy = app_handle.state::<RecognitionState>().is_processing()
+            || app_handle.state::<TranscriptionJobRegistry>().has_active_jobs()
+            || app_handle.state::<realtime_audio_full::AudioCaptureState>().is_capturing()
+            || tauri::async_runtime::block_on(long_audio::LONG_AUDIO_PROCESSOR.has_active_tasks());
+        let storage_state = app_handle.state::<StorageState>();
+        if let Err(e) = storage_state.with_storage(|storage| storage.maybe_auto_vacuum(is_busy)) {
+            log::warn!("⚠️ 自动数据库维护失败: {}", e);
+        }
+    });
+}
+
 // 文本后处理函数
 fn post_process_text(text: &str, language: &str) -> String {
+    post_process_text_with_config(text, language, &text_processing::RepetitionConfig::default())
+}
+
+// 带重复折叠配置的文本后处理
+fn post_process_text_with_config(text: &str, language: &str, repetition: &text_processing::RepetitionConfig) -> String {
     let mut processed = text.to_string();
     
     // 基础清理：去除多余空格和换行
     processed = processed.trim().to_string();
     processed = Regex::new(r"\s+").unwrap().replace_all(&processed, " ").to_string();
     
+    // 折叠幻觉产生的重复短语
+    processed = text_processing::collapse_repetitions(&processed, language, repetition);
+    
     match language {
         "zh" => post_process_chinese(&processed),
         "en" => post_process_english(&processed),
-        _ => post_process_auto(&processed), // 自动检测或其他语言
+        // 自动检测或其他语言：先应用该语言的用户规则
+        _ => post_process_auto(&post_process_rules::apply(language, &processed)),
     }
 }
 
@@ -941,34 +1530,13 @@ fn post_process_chinese(text: &str) -> String {
     // 注意: Rust的regex不支持反向引用，改用手动处理
     result = remove_repeated_chars(&result);
     
-    // 2. 修复常见的中文识别错误
-    let fixes = vec![
-        ("的的", "的"),
-        ("了了", "了"),
-        ("在在", "在"),
-        ("是是", "是"),
-        ("有有", "有"),
-        ("我我", "我"),
-        ("你你", "你"),
-        ("他他", "他"),
-        ("她她", "她"),
-        ("它它", "它"),
-    ];
-    
-    for (wrong, correct) in fixes {
-        result = result.replace(wrong, correct);
-    }
-    
-    // 3. 中文标点符号矫正
-    result = result.replace("。。", "。");
-    result = result.replace("，，", "，");
-    result = result.replace("？？", "？");
-    result = result.replace("！！", "！");
-    
-    // 4. 句子首尾处理
+    // 2. 修复常见的中文识别错误与标点（内置规则加用户规则）
+    result = post_process_rules::apply("zh", &result);
+    
+    // 3. 句子首尾处理
     result = result.trim().to_string();
     
-    // 5. 添加适当的句号（如果文本较长且没有结尾标点）
+    // 4. 添加适当的句号（如果文本较长且没有结尾标点）
     if result.len() > 10 && !result.ends_with(['。', '？', '！', '.', '?', '!']) {
         result.push('。');
     }
@@ -980,38 +1548,18 @@ fn post_process_chinese(text: &str) -> String {
 fn post_process_english(text: &str) -> String {
     let mut result = text.to_string();
     
-    // 1. 修复常见的英文识别错误
-    let fixes = vec![
-        (" a a ", " a "),
-        (" the the ", " the "),
-        (" and and ", " and "),
-        (" to to ", " to "),
-        (" of of ", " of "),
-        (" in in ", " in "),
-        (" is is ", " is "),
-        (" it it ", " it "),
-        (" that that ", " that "),
-    ];
-    
-    for (wrong, correct) in fixes {
-        result = result.replace(wrong, correct);
-    }
+    // 1. 修复常见的英文识别错误与标点（内置规则加用户规则）
+    result = post_process_rules::apply("en", &result);
     
     // 2. 大小写矫正
     result = fix_english_capitalization(&result);
     
-    // 3. 标点符号矫正
-    result = result.replace(",,", ",");
-    result = result.replace("..", ".");
-    result = result.replace("??", "?");
-    result = result.replace("!!", "!");
-    
-    // 4. 句子间距矫正
+    // 3. 句子间距矫正
     result = Regex::new(r"\.([A-Z])").unwrap().replace_all(&result, ". $1").to_string();
     result = Regex::new(r"\?([A-Z])").unwrap().replace_all(&result, "? $1").to_string();
     result = Regex::new(r"!([A-Z])").unwrap().replace_all(&result, "! $1").to_string();
     
-    // 5. 添加适当的句号
+    // 4. 添加适当的句号
     result = result.trim().to_string();
     if result.len() > 10 && !result.ends_with(['.', '?', '!', '。', '？', '！']) {
         result.push('.');
@@ -1925,9 +2473,9 @@ impl AudioFeatures {
             .count() as f32;
         let zero_crossing_rate = zero_crossings / (frame.len() - 1) as f32;
         
-        // 简化的频谱特征计算
-        let spectral_centroid = calculate_spectral_centroid(frame);
-        let spectral_rolloff = calculate_spectral_rolloff(frame);
+        let spectral = spectral_analysis::analyze_spectrum(frame, 16000);
+        let spectral_centroid = spectral.centroid;
+        let spectral_rolloff = spectral.rolloff;
         
         Self {
             energy,
@@ -1938,41 +2486,6 @@ impl AudioFeatures {
     }
 }
 
-fn calculate_spectral_centroid(frame: &[f32]) -> f32 {
-    // 简化的频谱质心计算
-    let mut weighted_sum = 0.0;
-    let mut magnitude_sum = 0.0;
-    
-    for (i, &sample) in frame.iter().enumerate() {
-        let magnitude = sample.abs();
-        weighted_sum += i as f32 * magnitude;
-        magnitude_sum += magnitude;
-    }
-    
-    if magnitude_sum > 0.0 {
-        weighted_sum / magnitude_sum
-    } else {
-        0.0
-    }
-}
-
-fn calculate_spectral_rolloff(frame: &[f32]) -> f32 {
-    // 简化的频谱滚降计算
-    let magnitudes: Vec<f32> = frame.iter().map(|&x| x.abs()).collect();
-    let total_energy: f32 = magnitudes.iter().sum();
-    let threshold = total_energy * 0.85; // 85%能量阈值
-    
-    let mut cumulative_energy = 0.0;
-    for (i, &magnitude) in magnitudes.iter().enumerate() {
-        cumulative_energy += magnitude;
-        if cumulative_energy >= threshold {
-            return i as f32 / magnitudes.len() as f32;
-        }
-    }
-    
-    1.0 // 如果没找到，返回最大值
-}
-
 // 检测分段边界
 fn detect_segment_boundaries(
     features: &[AudioFeatures], 
@@ -2129,10 +2642,11 @@ fn segment_based_recognition(
     segments: Vec<AudioSegment>,
     language: String,
     mode: String,
-    initial_prompt: Option<String>,
+    prompt: &prompt_builder::PromptParts,
     whisper_state: &WhisperContextState,
     window: &WebviewWindow,
     recognition_state: &RecognitionState,
+    job: &mut FileJobContext,
 ) -> Result<String, String> {
     println!("开始分段识别，共 {} 个段", segments.len());
     
@@ -2147,24 +2661,40 @@ fn segment_based_recognition(
     
     let total_segments = segments.len();
     let mut results = Vec::new();
+    // 上一段的识别结果作为下一段提示词的续接上下文
+    let mut carry_context: Option<String> = None;
+
+    // 进度按各段时长之和计算
+    let segments_duration: f64 = segments.iter()
+        .map(|s| (s.end_time - s.start_time) as f64)
+        .sum();
+    job.progress.set_total_duration(segments_duration);
     
     for (i, segment) in segments.into_iter().enumerate() {
         // 在每个段处理前检查是否需要取消
-        if recognition_state.should_cancel() {
+        if recognition_state.should_cancel() || job.cancel_token.is_cancelled() {
             return Err("转录已被用户取消".to_string());
         }
         
-        let progress = ((i + 1) as f32 / total_segments as f32) * 100.0;
+        let progress = transcription_progress::INFERENCE_PROGRESS_START
+            + (transcription_progress::INFERENCE_PROGRESS_END - transcription_progress::INFERENCE_PROGRESS_START)
+                * i as f64 / total_segments as f64;
         let _ = window.emit("recognition_progress", RecognitionProgress {
             stage: "segment_processing".to_string(),
-            progress,
+            progress: progress as f32,
             message: format!("处理段 {}/{} ({:.1}s)", i + 1, total_segments, segment.end_time - segment.start_time),
+            eta_secs: None,
         });
         
         println!("处理段 {} ({:.1}s - {:.1}s)", i + 1, segment.start_time, segment.end_time);
         
-        match recognize_segment_blocking(&segment.data, &language, &mode, &initial_prompt, whisper_state) {
+        job.progress.begin_chunk((segment.end_time - segment.start_time) as f64);
+        let initial_prompt = prompt.build(carry_context.as_deref());
+        match recognize_segment_blocking(&segment.data, &language, &mode, &initial_prompt, whisper_state, job) {
             Ok(text) => {
+                if !text.trim().is_empty() {
+                    carry_context = Some(text.clone());
+                }
                 results.push((segment.start_time, text));
                 println!("段 {} 完成: {} 字符", i + 1, results.last().unwrap().1.len());
             }
@@ -2177,11 +2707,11 @@ fn segment_based_recognition(
     
     // 按时间顺序排序并合并
     results.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
-    let combined_text = results.into_iter()
-        .map(|(_, text)| text)
-        .filter(|text| !text.is_empty())
-        .collect::<Vec<_>>()
-        .join(" ");
+    let combined_text = text_processing::join_segments(
+        results.iter().map(|(_, text)| text.as_str()).filter(|text| !text.is_empty()),
+        text_processing::TextJoinStyle::Space,
+        &language,
+    );
     
     println!("分段识别完成，总字符数: {}", combined_text.len());
     Ok(combined_text)
@@ -2195,6 +2725,32 @@ fn should_use_segmentation(samples: &[f32], config: &SegmentationConfig) -> bool
     duration_sec > config.max_segment_duration_sec
 }
 
+/// 文件转录共用的 Whisper 调用：设置语言（空或 auto 为自动检测）和初始提示词后执行识别，返回 whisper_full 的错误码。
+/// 其余参数由调用方按模式和用户配置设置好；C 字符串在这里持有到识别结束
+pub(crate) fn run_whisper(
+    ctx: *mut whisper_context,
+    mut params: whisper_full_params,
+    audio: &mut [f32],
+    language: &str,
+    initial_prompt: Option<&str>,
+) -> i32 {
+    let lang_cstring = match language {
+        "" | "auto" => None,
+        code => std::ffi::CString::new(code).ok(),
+    };
+    params.language = lang_cstring.as_ref().map_or(std::ptr::null(), |lang| lang.as_ptr());
+
+    let prompt_cstring = initial_prompt
+        .map(str::trim)
+        .filter(|prompt| !prompt.is_empty())
+        .and_then(|prompt| std::ffi::CString::new(prompt).ok());
+    if let Some(ref prompt) = prompt_cstring {
+        params.initial_prompt = prompt.as_ptr();
+    }
+
+    unsafe { whisper_full(ctx, params, audio.as_mut_ptr(), audio.len() as i32) }
+}
+
 // 阻塞式段识别
 fn recognize_segment_blocking(
     audio_data: &[f32],
@@ -2202,6 +2758,7 @@ fn recognize_segment_blocking(
     mode: &str,
     initial_prompt: &Option<String>,
     whisper_state: &WhisperContextState,
+    job: &mut FileJobContext,
 ) -> Result<String, String> {
     let ctx = whisper_state.ctx.lock().unwrap();
     
@@ -2217,7 +2774,7 @@ fn recognize_segment_blocking(
     params.temperature = 0.0;
     params.suppress_blank = true;
     params.token_timestamps = true;
-    params.max_len = 1;
+    job.segmentation.apply(&mut params);
     
     // 根据处理模式调整参数（为并行处理优化，使用较低线程数）
     match mode {
@@ -2241,47 +2798,20 @@ fn recognize_segment_blocking(
             params.greedy.best_of = 2;
         }
     }
-    
-    // 语言设置
-    let lang_cstring = match language {
-        "zh" => Some(std::ffi::CString::new("zh").unwrap()),
-        "en" => Some(std::ffi::CString::new("en").unwrap()),
-        _ => None,
-    };
-    
-    if let Some(ref lang_str) = lang_cstring {
-        params.language = lang_str.as_ptr();
-    } else {
-        params.language = std::ptr::null();
-    }
-
-    // 设置初始提示词
-    let prompt_cstring = if let Some(ref prompt) = initial_prompt {
-        if !prompt.trim().is_empty() {
-            Some(std::ffi::CString::new(prompt.trim()).unwrap())
-        } else {
-            None
-        }
-    } else {
-        None
-    };
 
-    if let Some(ref prompt_str) = prompt_cstring {
-        params.initial_prompt = prompt_str.as_ptr();
+    // 用户指定的解码策略优先于模式预设
+    if let Some(decoding) = job.decoding {
+        decoding.apply(&mut params);
     }
+    job.temperature_fallback.apply(&mut params);
+    job.suppression.apply(&mut params);
+    
+    job.progress.install(&mut params);
+    job.cancel_token.install(&mut params);
     
     // 执行识别
     let mut audio_copy = audio_data.to_vec();
-    let result = unsafe {
-        whisper_full(
-            *ctx,
-            params,
-            audio_copy.as_mut_ptr(),
-            audio_copy.len() as i32,
-        )
-    };
-    
-    if result != 0 {
+    if run_whisper(*ctx, params, &mut audio_copy, language, initial_prompt.as_deref()) != 0 {
         return Err("Whisper段识别失败".to_string());
     }
     
@@ -2290,6 +2820,13 @@ fn recognize_segment_blocking(
     let mut text = String::new();
     
     for i in 0..num_segments {
+        // 记录段置信度，用于估算整体准确率
+        unsafe {
+            if let Some(segment_confidence) = confidence::whisper_segment_confidence(*ctx, i) {
+                job.confidence.add(confidence::whisper_segment_duration(*ctx, i), segment_confidence);
+            }
+        }
+
         let segment_ptr = unsafe { whisper_full_get_segment_text(*ctx, i) };
         if !segment_ptr.is_null() {
             let c_str = unsafe { CStr::from_ptr(segment_ptr as *const c_char) };
@@ -2297,7 +2834,8 @@ fn recognize_segment_blocking(
         }
     }
     
-    // 应用文本后处理
+    // 删除非语音标注后再做文本后处理
+    let text = text_processing::strip_non_speech(&text, &job.suppression);
     let processed_text = post_process_text(&text, language);
     Ok(processed_text)
 }
@@ -2373,10 +2911,11 @@ fn advanced_recognition_pipeline(
     audio_data: Vec<f32>,
     language: String,
     mode: String,
-    initial_prompt: Option<String>,
+    prompt: prompt_builder::PromptParts,
     whisper_state: &WhisperContextState,
     window: &WebviewWindow,
     recognition_state: &RecognitionState,
+    job: &mut FileJobContext,
 ) -> Result<String, String> {
     println!("开始高级音频识别流程...");
     
@@ -2399,15 +2938,15 @@ fn advanced_recognition_pipeline(
             Ok(segs) => segs,
             Err(e) => {
                 println!("智能分段失败: {}, 使用整体识别", e);
-                return recognize_whole_audio(audio_data, language, mode.clone(), initial_prompt, whisper_state, recognition_state);
+                return recognize_whole_audio(audio_data, language, mode.clone(), prompt.build(None), whisper_state, recognition_state, job);
             }
         };
         
         // 分段识别
-        segment_based_recognition(segments, language, mode.clone(), initial_prompt.clone(), whisper_state, window, recognition_state)
+        segment_based_recognition(segments, language, mode.clone(), &prompt, whisper_state, window, recognition_state, job)
     } else {
         println!("音频较短({:.1}s)，使用整体识别", total_duration);
-        recognize_whole_audio(audio_data, language, mode, initial_prompt, whisper_state, recognition_state)
+        recognize_whole_audio(audio_data, language, mode, prompt.build(None), whisper_state, recognition_state, job)
     }
 }
 
@@ -2419,6 +2958,7 @@ fn recognize_whole_audio(
     initial_prompt: Option<String>,
     whisper_state: &WhisperContextState,
     recognition_state: &RecognitionState,
+    job: &mut FileJobContext,
 ) -> Result<String, String> {
     // 检查是否需要取消
     if recognition_state.should_cancel() {
@@ -2440,7 +2980,7 @@ fn recognize_whole_audio(
     params.temperature = 0.0;
     params.suppress_blank = true;
     params.token_timestamps = true;
-    params.max_len = 1;
+    job.segmentation.apply(&mut params);
     
     // 根据处理模式和音频长度调整参数
     match mode.as_str() {
@@ -2479,49 +3019,23 @@ fn recognize_whole_audio(
             }
         }
     }
-    
-    // 语言设置
-    let lang_cstring = match language.as_str() {
-        "zh" => Some(std::ffi::CString::new("zh").unwrap()),
-        "en" => Some(std::ffi::CString::new("en").unwrap()),
-        _ => None,
-    };
-    
-    if let Some(ref lang_str) = lang_cstring {
-        params.language = lang_str.as_ptr();
-    } else {
-        params.language = std::ptr::null();
-    }
-
-    // 设置初始提示词
-    let prompt_cstring = if let Some(ref prompt) = initial_prompt {
-        if !prompt.trim().is_empty() {
-            Some(std::ffi::CString::new(prompt.trim()).unwrap())
-        } else {
-            None
-        }
-    } else {
-        None
-    };
 
-    if let Some(ref prompt_str) = prompt_cstring {
-        params.initial_prompt = prompt_str.as_ptr();
+    // 用户指定的解码策略优先于模式预设
+    if let Some(decoding) = job.decoding {
+        decoding.apply(&mut params);
     }
+    job.temperature_fallback.apply(&mut params);
+    job.suppression.apply(&mut params);
     
     println!("使用优化参数: beam_size={}, threads={}, duration={:.1}s", 
              params.beam_search.beam_size, params.n_threads, duration);
+
+    job.progress.begin_chunk(duration as f64);
+    job.progress.install(&mut params);
+    job.cancel_token.install(&mut params);
     
     // 执行识别
-    let result = unsafe {
-        whisper_full(
-            *ctx,
-            params,
-            audio_data.as_mut_ptr(),
-            audio_data.len() as i32,
-        )
-    };
-    
-    if result != 0 {
+    if run_whisper(*ctx, params, &mut audio_data, &language, initial_prompt.as_deref()) != 0 {
         return Err("Whisper整体识别失败".to_string());
     }
     
@@ -2530,6 +3044,13 @@ fn recognize_whole_audio(
     let mut full_text = String::new();
     
     for i in 0..num_segments {
+        // 记录段置信度，用于估算整体准确率
+        unsafe {
+            if let Some(segment_confidence) = confidence::whisper_segment_confidence(*ctx, i) {
+                job.confidence.add(confidence::whisper_segment_duration(*ctx, i), segment_confidence);
+            }
+        }
+
         let segment_ptr = unsafe { whisper_full_get_segment_text(*ctx, i) };
         if !segment_ptr.is_null() {
             let c_str = unsafe { CStr::from_ptr(segment_ptr as *const c_char) };
@@ -2537,10 +3058,15 @@ fn recognize_whole_audio(
         }
     }
     
-    // 获取带时间戳的段信息用于说话人识别
-    let segments = extract_timestamped_segments(*ctx);
+    // 获取带时间戳的段信息用于说话人识别，并删除非语音标注
+    let mut segments = extract_timestamped_segments(*ctx);
+    for segment in &mut segments {
+        segment.text = text_processing::strip_non_speech(&segment.text, &job.suppression);
+    }
+    segments.retain(|segment| !segment.text.trim().is_empty());
     
     // 文本后处理
+    let full_text = text_processing::strip_non_speech(&full_text, &job.suppression);
     let processed_text = post_process_text(&full_text, &language);
     
     // 如果有多个段，尝试进行说话人识别和角色分配
@@ -2734,44 +3260,8 @@ fn estimate_fundamental_frequency(audio: &[f32]) -> f32 {
 }
 
 fn calculate_spectral_features(audio: &[f32]) -> (f32, f32) {
-    let mut magnitudes = Vec::new();
-    let chunk_size = 512;
-    
-    for chunk in audio.chunks(chunk_size) {
-        if chunk.len() == chunk_size {
-            let magnitude_sum: f32 = chunk.iter().map(|&x| x.abs()).sum();
-            magnitudes.push(magnitude_sum / chunk_size as f32);
-        }
-    }
-    
-    if magnitudes.is_empty() {
-        return (0.0, 0.0);
-    }
-    
-    let mut weighted_sum = 0.0;
-    let mut total_magnitude = 0.0;
-    
-    for (i, &magnitude) in magnitudes.iter().enumerate() {
-        let frequency = i as f32 * 16000.0 / magnitudes.len() as f32;
-        weighted_sum += frequency * magnitude;
-        total_magnitude += magnitude;
-    }
-    
-    let centroid = if total_magnitude > 0.0 {
-        weighted_sum / total_magnitude
-    } else { 0.0 };
-    
-    let mut variance = 0.0;
-    for (i, &magnitude) in magnitudes.iter().enumerate() {
-        let frequency = i as f32 * 16000.0 / magnitudes.len() as f32;
-        variance += (frequency - centroid).powi(2) * magnitude;
-    }
-    
-    let bandwidth = if total_magnitude > 0.0 {
-        (variance / total_magnitude).sqrt()
-    } else { 0.0 };
-    
-    (centroid, bandwidth)
+    let spectral = spectral_analysis::analyze_spectrum(audio, 16000);
+    (spectral.centroid, spectral.bandwidth)
 }
 
 fn calculate_zero_crossing_rate(audio: &[f32]) -> f32 {
@@ -2806,7 +3296,7 @@ fn cluster_speakers(
     
     // 使用简化的基频聚类
     let max_speakers = 4;
-    let actual_speakers = estimate_speaker_count(&segment_features).min(max_speakers);
+    let actual_speakers = estimate_pitch_cluster_count(&segment_features).min(max_speakers);
     
     println!("估计说话人数量: {}", actual_speakers);
     
@@ -2876,7 +3366,7 @@ fn cluster_speakers(
     Ok(speakers)
 }
 
-fn estimate_speaker_count(segment_features: &[(TimestampedSegment, VoiceCharacteristics)]) -> usize {
+fn estimate_pitch_cluster_count(segment_features: &[(TimestampedSegment, VoiceCharacteristics)]) -> usize {
     if segment_features.len() <= 2 {
         return segment_features.len();
     }
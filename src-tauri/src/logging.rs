@@ -1,35 +1,107 @@
-use std::fs::{self, OpenOptions};
-use std::io::Write;
-use std::path::PathBuf;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
 use chrono::Utc;
 use log::LevelFilter;
 use env_logger::{Builder, Target};
+use tauri::command;
+use tauri_plugin_opener::OpenerExt;
 
-/// 初始化日志系统，将日志写入应用数据目录
+use crate::errors::StenoError;
+
+/// 当前日志文件达到该大小后轮转
+const MAX_LOG_FILE_SIZE: u64 = 5 * 1024 * 1024;
+/// 保留的历史日志文件数（steno.1.log ... steno.N.log）
+const MAX_ROTATED_FILES: usize = 4;
+const LOG_FILE_NAME: &str = "steno.log";
+
+lazy_static::lazy_static! {
+    static ref LOG_DIR: Mutex<Option<PathBuf>> = Mutex::new(None);
+}
+
+/// 按大小轮转的日志文件：写满后 steno.log -> steno.1.log -> steno.2.log ...，超出保留数的最旧文件被删除
+pub struct RotatingFileWriter {
+    dir: PathBuf,
+    max_size: u64,
+    max_files: usize,
+    file: File,
+    size: u64,
+}
+
+impl RotatingFileWriter {
+    pub fn open(dir: &Path, max_size: u64, max_files: usize) -> io::Result<Self> {
+        fs::create_dir_all(dir)?;
+        let file = OpenOptions::new().create(true).append(true).open(dir.join(LOG_FILE_NAME))?;
+        let size = file.metadata()?.len();
+        Ok(Self { dir: dir.to_path_buf(), max_size, max_files, file, size })
+    }
+
+    pub fn current_path(&self) -> PathBuf {
+        self.dir.join(LOG_FILE_NAME)
+    }
+
+    fn rotated_path(&self, index: usize) -> PathBuf {
+        self.dir.join(format!("steno.{}.log", index))
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        let _ = fs::remove_file(self.rotated_path(self.max_files));
+        for index in (1..self.max_files).rev() {
+            let from = self.rotated_path(index);
+            if from.exists() {
+                fs::rename(&from, self.rotated_path(index + 1))?;
+            }
+        }
+        if self.max_files > 0 {
+            fs::rename(self.current_path(), self.rotated_path(1))?;
+        }
+        self.file = OpenOptions::new().create(true).write(true).truncate(true).open(self.current_path())?;
+        self.size = 0;
+        Ok(())
+    }
+}
+
+impl Write for RotatingFileWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.size > 0 && self.size + buf.len() as u64 > self.max_size {
+            self.rotate()?;
+        }
+        // 调试构建同时输出到终端，方便开发时查看
+        if cfg!(debug_assertions) {
+            let _ = io::stderr().write_all(buf);
+        }
+        let written = self.file.write(buf)?;
+        self.size += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+/// 调试构建记录 debug 级别，发布构建只记录 info 及以上，音频热路径的逐块日志在发布版中不输出
+fn default_level() -> LevelFilter {
+    if cfg!(debug_assertions) { LevelFilter::Debug } else { LevelFilter::Info }
+}
+
+/// 初始化日志系统，将日志写入应用数据目录下的 logs 目录
 pub fn init_logging(app_handle: &tauri::AppHandle) -> Result<(), Box<dyn std::error::Error>> {
     // 获取应用数据目录 - Windows使用安装目录，其他平台使用AppData
-    let app_data_dir = get_app_data_dir_for_logging(app_handle)?;
-
-    // 创建日志目录
+    let app_data_dir = crate::data_dir::resolve_log_root(app_handle)?;
     let log_dir = app_data_dir.join("logs");
-    fs::create_dir_all(&log_dir)
-        .map_err(|e| format!("Failed to create log directory: {}", e))?;
-
-    // 创建日志文件（按日期命名）
-    let log_file_name = format!("steno_{}.log", Utc::now().format("%Y%m%d"));
-    let log_file_path = log_dir.join(log_file_name);
-
-    // 打开日志文件（追加模式）
-    let log_file = OpenOptions::new()
-        .create(true)
-        .append(true)
-        .open(&log_file_path)
-        .map_err(|e| format!("Failed to open log file: {}", e))?;
-
-    // 配置env_logger写入文件
-    Builder::from_default_env()
-        .target(Target::Pipe(Box::new(log_file)))
-        .filter_level(LevelFilter::Info)
+
+    let writer = RotatingFileWriter::open(&log_dir, MAX_LOG_FILE_SIZE, MAX_ROTATED_FILES)
+        .map_err(|e| format!("Failed to open log file in {}: {}", log_dir.display(), e))?;
+    let log_file_path = writer.current_path();
+
+    // 配置env_logger写入文件，RUST_LOG 可覆盖默认级别
+    Builder::new()
+        .filter_level(default_level())
+        .parse_default_env()
+        .target(Target::Pipe(Box::new(writer)))
         .format(|buf, record| {
             writeln!(
                 buf,
@@ -41,82 +113,40 @@ pub fn init_logging(app_handle: &tauri::AppHandle) -> Result<(), Box<dyn std::er
                 record.args()
             )
         })
-        .init();
+        .try_init()?;
 
-    // 同时输出到stderr供调试使用
-    eprintln!("📝 日志系统已初始化，日志文件: {}", log_file_path.display());
+    *LOG_DIR.lock().unwrap() = Some(log_dir.clone());
     log::info!("=== Steno 应用启动 ===");
     log::info!("日志文件位置: {}", log_file_path.display());
-    
-    // 清理旧日志文件（保留最近7天）
+
+    // 清理旧版本按日期命名的日志文件（保留最近7天）
     cleanup_old_logs(&log_dir)?;
 
     Ok(())
 }
 
-/// 获取应用数据目录 - 支持Windows安装目录模式
-fn get_app_data_dir_for_logging(app_handle: &tauri::AppHandle) -> Result<PathBuf, Box<dyn std::error::Error>> {
-    #[cfg(target_os = "windows")]
-    {
-        // Windows: 使用安装目录
-        get_windows_install_dir().map_err(|e| e.into())
-    }
-    
-    #[cfg(not(target_os = "windows"))]
-    {
-        // macOS/Linux: 使用AppData目录
-        use tauri::Manager;
-        app_handle
-            .path()
-            .app_data_dir()
-            .map_err(|e| format!("Failed to get app data dir: {}", e).into())
-    }
+fn log_dir() -> Result<PathBuf, StenoError> {
+    LOG_DIR.lock().unwrap().clone()
+        .ok_or_else(|| StenoError::Internal("日志系统未初始化".to_string()))
 }
 
-/// Windows专用：获取合适的数据存储目录
-#[cfg(target_os = "windows")]
-fn get_windows_install_dir() -> Result<PathBuf, Box<dyn std::error::Error>> {
-    // 策略1：优先使用用户AppData目录（推荐，符合Windows最佳实践）
-    // %APPDATA%\Roaming\Steno - 适合日志文件存储
-    if let Some(app_data) = dirs::data_dir() {
-        return Ok(app_data.join("Steno"));
-    }
-    
-    // 策略2：备选使用Local AppData目录（更快的本地存储）
-    if let Some(local_data) = dirs::data_local_dir() {
-        return Ok(local_data.join("Steno"));
-    }
-    
-    // 策略3：便携模式检查（仅当可执行文件目录可写或有便携标记时使用）
-    if let Ok(exe_path) = std::env::current_exe() {
-        if let Some(exe_dir) = exe_path.parent() {
-            // 检查是否存在便携模式标记文件或可执行文件目录可写
-            let portable_marker = exe_dir.join("portable.txt");
-            let test_file = exe_dir.join("write_test.tmp");
-            
-            if portable_marker.exists() || std::fs::File::create(&test_file).is_ok() {
-                let _ = std::fs::remove_file(&test_file); // 清理测试文件
-                return Ok(exe_dir.to_path_buf());
-            }
-        }
-    }
-    
-    // 策略4: 使用用户文档目录
-    if let Some(docs_dir) = dirs::document_dir() {
-        return Ok(docs_dir.join("Steno"));
-    }
-    
-    // 策略5: 开发环境回退到工作目录
-    if let Ok(current_dir) = std::env::current_dir() {
-        return Ok(current_dir);
-    }
-    
-    // 最后备选：相对路径
-    Ok(PathBuf::from("."))
+/// 当前日志文件路径，供用户反馈问题时附带
+#[command]
+pub async fn get_log_path() -> Result<String, StenoError> {
+    Ok(log_dir()?.join(LOG_FILE_NAME).to_string_lossy().to_string())
+}
+
+/// 在系统文件管理器中打开日志目录
+#[command]
+pub async fn open_log_dir(app_handle: tauri::AppHandle) -> Result<(), StenoError> {
+    let dir = log_dir()?;
+    app_handle.opener()
+        .open_path(dir.to_string_lossy(), None::<&str>)
+        .map_err(|e| StenoError::Internal(format!("无法打开日志目录: {}", e)))
 }
 
-/// 清理超过7天的旧日志文件
-fn cleanup_old_logs(log_dir: &PathBuf) -> Result<(), Box<dyn std::error::Error>> {
+/// 清理超过7天的按日期命名的旧日志文件
+fn cleanup_old_logs(log_dir: &Path) -> Result<(), Box<dyn std::error::Error>> {
     let now = std::time::SystemTime::now();
     let seven_days = std::time::Duration::from_secs(7 * 24 * 60 * 60);
 
@@ -176,4 +206,43 @@ macro_rules! log_operation {
             }
         }
     };
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_log_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("steno_log_test_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn test_creates_directory_and_writable_file() {
+        let dir = temp_log_dir("init").join("logs");
+        let mut writer = RotatingFileWriter::open(&dir, MAX_LOG_FILE_SIZE, MAX_ROTATED_FILES).unwrap();
+        assert!(dir.is_dir());
+        writeln!(writer, "hello").unwrap();
+        writer.flush().unwrap();
+        assert_eq!(fs::read_to_string(writer.current_path()).unwrap(), "hello\n");
+        let _ = fs::remove_dir_all(dir.parent().unwrap());
+    }
+
+    #[test]
+    fn test_rotates_by_size() {
+        let dir = temp_log_dir("rotate");
+        let mut writer = RotatingFileWriter::open(&dir, 10, 2).unwrap();
+        for line in ["aaaaaaaa\n", "bbbbbbbb\n", "cccccccc\n", "dddddddd\n"] {
+            writer.write_all(line.as_bytes()).unwrap();
+        }
+        writer.flush().unwrap();
+
+        // 只保留当前文件和两个历史文件，最旧的一行被丢弃
+        assert_eq!(fs::read_to_string(dir.join("steno.log")).unwrap(), "dddddddd\n");
+        assert_eq!(fs::read_to_string(dir.join("steno.1.log")).unwrap(), "cccccccc\n");
+        assert_eq!(fs::read_to_string(dir.join("steno.2.log")).unwrap(), "bbbbbbbb\n");
+        assert!(!dir.join("steno.3.log").exists());
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
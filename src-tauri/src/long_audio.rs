@@ -1,10 +1,17 @@
-use std::sync::{Arc, Mutex, atomic::{AtomicBool, Ordering}};
-use std::collections::HashMap;
+use std::sync::{Arc, Mutex, atomic::{AtomicBool, AtomicUsize, Ordering}};
+use std::cmp;
+use std::collections::{BinaryHeap, HashMap};
 use tokio::task::JoinHandle;
 use tokio::sync::{mpsc, RwLock};
 use serde::{Serialize, Deserialize};
 use tauri::{Emitter, WebviewWindow};
 use crate::storage::TranscriptionSegment;
+use crate::decoding::TemperatureFallback;
+use crate::metrics;
+use crate::text_processing::{join_segments, TextJoinStyle};
+use crate::transcription_progress::realtime_factor;
+use crate::whisper_input::{self, WHISPER_SAMPLE_RATE};
+use crate::{whisper_full_default_params, whisper_full_params, whisper_sampling_strategy_WHISPER_SAMPLING_BEAM_SEARCH};
 
 // 音频段信息
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -20,6 +27,8 @@ pub struct AudioSegment {
     pub confidence: Option<f64>,
     pub processing_time: Option<f64>,
     pub error: Option<String>,
+    #[serde(default)]
+    pub attempts: u32,       // 已分发处理的次数
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -46,8 +55,31 @@ pub struct LongAudioTask {
     pub segments: Vec<AudioSegment>,
     pub final_text: Option<String>,
     pub processing_stats: ProcessingStats,
+    #[serde(default = "default_max_segment_attempts")]
+    pub max_segment_attempts: u32, // 单段最大尝试次数
+    #[serde(default)]
+    pub text_join: TextJoinStyle, // 合并最终文本时段与段之间的分隔方式
+    #[serde(default)]
+    pub language: String, // 任务语言，决定按空格拼接时是否加空格；为空时按衔接处的字符判断
+    #[serde(default = "default_overlap_dedup_threshold")]
+    pub overlap_dedup_threshold: f64, // 相邻段重叠部分去重所需的最低相似度
+    #[serde(skip)]
+    pub config: ProcessingConfig, // 创建任务时的配置，随每个段分发给工作线程
 }
 
+fn default_max_segment_attempts() -> u32 {
+    3
+}
+
+fn default_overlap_dedup_threshold() -> f64 {
+    0.8
+}
+
+/// 重叠去重最多比较的词数（CJK 按字计），1 秒左右的重叠通常只有几个字
+const MAX_SEAM_TOKENS: usize = 16;
+/// 少于 2 个词的重叠容易是巧合，不做去重
+const MIN_SEAM_TOKENS: usize = 2;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum TaskStatus {
     Preparing,   // 预处理阶段
@@ -73,6 +105,7 @@ struct WorkerState {
     id: usize,
     is_busy: Arc<AtomicBool>,
     current_segment: Arc<Mutex<Option<String>>>,
+    exiting: Arc<AtomicBool>, // 持有队列锁时置位，之后入队的段由新启动的线程处理
     handle: Option<JoinHandle<()>>,
 }
 
@@ -81,32 +114,151 @@ pub struct LongAudioProcessor {
     tasks: Arc<RwLock<HashMap<String, LongAudioTask>>>,
     workers: Arc<Mutex<Vec<WorkerState>>>,
     max_workers: usize,
+    next_worker_id: AtomicUsize, // 线程编号单调递增，重启的线程不复用旧编号
     should_stop: Arc<AtomicBool>,
-    segment_tx: mpsc::UnboundedSender<ProcessingMessage>,
-    segment_rx: Arc<Mutex<mpsc::UnboundedReceiver<ProcessingMessage>>>,
+    result_tx: mpsc::Sender<SegmentOutcome>,
+    result_rx: Mutex<Option<mpsc::Receiver<SegmentOutcome>>>, // 由唯一的结果监听器取走
+    listening_tasks: Arc<Mutex<HashMap<String, ListenerContext>>>, // 正在等待结果的任务
+    segment_queue: Arc<Mutex<SegmentQueue>>, // 待处理段的优先队列，长度不超过 queue_capacity
+    queue_capacity: usize,
+    audio_cache: Arc<Mutex<HashMap<String, Arc<Vec<f32>>>>>, // 每个任务解码一次的音频，任务完成或取消时释放
+    audio_loader: AudioLoader,
+    worker_idle_timeout: std::time::Duration,
 }
 
+// 每个工作线程最多预取的段数；队列和结果通道按 max_workers 的倍数限长，
+// 分发时才复制段音频，峰值内存与总段数无关
+const QUEUE_DEPTH_PER_WORKER: usize = 2;
+
+// 工作线程连续空闲超过该时长后退出，下次分发时按需重新启动
+const WORKER_IDLE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+// 解码音频文件，返回 (音频数据, 采样率, 总时长)
+type AudioLoader = fn(&str) -> Result<(Vec<f32>, u32, f64), String>;
+
+// 优先队列中的待处理段，priority 越小越先处理
 #[derive(Debug)]
-enum ProcessingMessage {
-    ProcessSegment {
-        task_id: String,
-        segment_id: String,
-        audio_data: Vec<f32>,
-        config: ProcessingConfig,
-    },
-    SegmentCompleted {
+struct QueuedSegment {
+    priority: u64,
+    sequence: u64,
+    job: SegmentJob,
+}
+
+impl PartialEq for QueuedSegment {
+    fn eq(&self, other: &Self) -> bool {
+        self.priority == other.priority && self.sequence == other.sequence
+    }
+}
+
+impl Eq for QueuedSegment {}
+
+impl PartialOrd for QueuedSegment {
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for QueuedSegment {
+    fn cmp(&self, other: &Self) -> cmp::Ordering {
+        // BinaryHeap 是最大堆，反转比较使开始时间最早的段先出队；同优先级按入队顺序
+        other.priority.cmp(&self.priority)
+            .then_with(|| other.sequence.cmp(&self.sequence))
+    }
+}
+
+// 按段开始时间排序的处理队列，保证录音开头先完成转录
+#[derive(Debug, Default)]
+struct SegmentQueue {
+    heap: BinaryHeap<QueuedSegment>,
+    next_sequence: u64,
+}
+
+impl SegmentQueue {
+    fn push(&mut self, job: SegmentJob) {
+        let priority = job.priority;
+        let sequence = self.next_sequence;
+        self.next_sequence += 1;
+        self.heap.push(QueuedSegment { priority, sequence, job });
+    }
+
+    fn pop(&mut self) -> Option<SegmentJob> {
+        self.heap.pop().map(|queued| queued.job)
+    }
+
+    // 移除指定任务的所有待处理段（暂停/取消时使用），返回被移除的段ID
+    fn remove_task(&mut self, task_id: &str) -> Vec<String> {
+        let mut removed = Vec::new();
+        let remaining: Vec<QueuedSegment> = self.heap.drain()
+            .filter(|queued| {
+                let matches = queued.job.task_id == task_id;
+                if matches {
+                    removed.push(queued.job.segment_id.clone());
+                }
+                !matches
+            })
+            .collect();
+        self.heap = remaining.into_iter().collect();
+        removed
+    }
+
+    fn len(&self) -> usize {
+        self.heap.len()
+    }
+}
+
+// 段优先级：开始时间（毫秒）
+fn segment_priority(segment: &AudioSegment) -> u64 {
+    (segment.start_time.max(0.0) * 1000.0) as u64
+}
+
+// 经优先队列分发给工作线程的段
+#[derive(Debug)]
+struct SegmentJob {
+    task_id: String,
+    segment_id: String,
+    audio_data: Vec<f32>,
+    config: ProcessingConfig,
+    priority: u64, // 段开始时间（毫秒），越小越优先
+}
+
+// 工作线程经结果通道发回监听器的处理结果
+#[derive(Debug)]
+enum SegmentOutcome {
+    Completed {
         task_id: String,
         segment_id: String,
         result: SegmentResult,
     },
-    SegmentFailed {
+    Failed {
         task_id: String,
         segment_id: String,
         error: String,
     },
-    TaskPaused(String),
-    TaskResumed(String),
-    TaskCancelled(String),
+}
+
+impl SegmentOutcome {
+    fn task_id(&self) -> &str {
+        match self {
+            Self::Completed { task_id, .. } | Self::Failed { task_id, .. } => task_id,
+        }
+    }
+}
+
+// 结果监听器处理某个任务的结果时需要的上下文
+#[derive(Clone)]
+struct ListenerContext {
+    window: WebviewWindow,
+    full_audio_data: Arc<Vec<f32>>,
+}
+
+// 长音频分段方式
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SegmentationMode {
+    #[default]
+    Vad,                             // 按语音活动区域分段，适合对话、讲座
+    FixedWindow { len_secs: f64 },   // 整段音频按固定时长切分，适合音乐或连续语音
+    Hybrid,                          // 优先VAD，检测不到语音时退回固定窗口
 }
 
 #[derive(Debug, Clone)]
@@ -117,7 +269,13 @@ pub struct ProcessingConfig {
     pub segment_overlap: f64,  // 段间重叠时间（秒）
     pub max_segment_length: f64, // 最大段长度（秒）
     pub min_segment_length: f64, // 最小段长度（秒）
-    pub initial_prompt: Option<String>,
+    pub max_segment_attempts: u32, // 失败段最多尝试次数（含首次）
+    pub temperature_fallback: TemperatureFallback, // 解码结果不可靠时的温度回退
+    pub segmentation_mode: SegmentationMode,
+    pub cut_search_secs: f64, // 在段末尾之前多长范围内寻找切割点（秒）
+    pub min_pause_secs: f64,  // 可作为切割点的最短停顿（秒）
+    pub text_join: TextJoinStyle, // 最终文本的段间分隔方式
+    pub overlap_dedup_threshold: f64, // 重叠去重的相似度阈值（0-1）：过高会留下重复，过低会误删真实内容
 }
 
 #[derive(Debug, Clone)]
@@ -137,7 +295,13 @@ impl Default for ProcessingConfig {
             segment_overlap: 1.0,      // 1秒重叠
             max_segment_length: 60.0,  // 最大60秒
             min_segment_length: 10.0,  // 最小10秒
-            initial_prompt: None,
+            max_segment_attempts: default_max_segment_attempts(),
+            temperature_fallback: TemperatureFallback::default(),
+            segmentation_mode: SegmentationMode::default(),
+            cut_search_secs: 3.0,
+            min_pause_secs: 0.15,
+            text_join: TextJoinStyle::default(),
+            overlap_dedup_threshold: default_overlap_dedup_threshold(),
         }
     }
 }
@@ -156,18 +320,30 @@ impl Default for ProcessingStats {
 
 impl LongAudioProcessor {
     pub fn new() -> Self {
+        Self::with_audio_loader(crate::load_and_convert_audio)
+    }
+
+    fn with_audio_loader(audio_loader: AudioLoader) -> Self {
         let cpu_count = num_cpus::get();
         let max_workers = (cpu_count.saturating_sub(1)).max(1).min(8); // 保留一个核心给UI，最多8个工作线程
         
-        let (segment_tx, segment_rx) = mpsc::unbounded_channel();
+        let queue_capacity = max_workers * QUEUE_DEPTH_PER_WORKER;
+        let (result_tx, result_rx) = mpsc::channel(queue_capacity);
         
         Self {
             tasks: Arc::new(RwLock::new(HashMap::new())),
             workers: Arc::new(Mutex::new(Vec::new())),
             max_workers,
+            next_worker_id: AtomicUsize::new(0),
             should_stop: Arc::new(AtomicBool::new(false)),
-            segment_tx,
-            segment_rx: Arc::new(Mutex::new(segment_rx)),
+            result_tx,
+            result_rx: Mutex::new(Some(result_rx)),
+            listening_tasks: Arc::new(Mutex::new(HashMap::new())),
+            segment_queue: Arc::new(Mutex::new(SegmentQueue::default())),
+            queue_capacity,
+            audio_cache: Arc::new(Mutex::new(HashMap::new())),
+            audio_loader,
+            worker_idle_timeout: WORKER_IDLE_TIMEOUT,
         }
     }
 
@@ -188,8 +364,10 @@ impl LongAudioProcessor {
             "message": "正在加载音频文件..."
         }));
 
-        // 加载和预处理音频
+        // 加载和预处理音频，解码结果缓存到任务结束，分段和分发共用
         let (audio_data, sample_rate, total_duration) = self.load_audio_file(&file_path).await?;
+        let audio_data = Arc::new(audio_data);
+        self.audio_cache.lock().unwrap().insert(task_id.clone(), audio_data.clone());
         
         let _ = window.emit("long_audio_preprocessing", &serde_json::json!({
             "task_id": task_id,
@@ -198,7 +376,13 @@ impl LongAudioProcessor {
         }));
 
         // 智能分段
-        let segments = self.segment_audio(&audio_data, sample_rate, total_duration, &config).await?;
+        let segments = match self.segment_audio(&audio_data, sample_rate, total_duration, &config).await {
+            Ok(segments) => segments,
+            Err(e) => {
+                self.release_audio(&task_id);
+                return Err(e);
+            }
+        };
         
         let task = LongAudioTask {
             id: task_id.clone(),
@@ -214,6 +398,11 @@ impl LongAudioProcessor {
             segments,
             final_text: None,
             processing_stats: ProcessingStats::default(),
+            max_segment_attempts: config.max_segment_attempts.max(1),
+            text_join: config.text_join,
+            language: config.language.clone(),
+            overlap_dedup_threshold: config.overlap_dedup_threshold.clamp(0.0, 1.0),
+            config,
         };
 
         // 保存任务
@@ -245,9 +434,6 @@ impl LongAudioProcessor {
             }
         }
 
-        // 启动工作线程
-        self.start_workers().await;
-
         // 开始分发处理任务
         self.dispatch_segments(task_id.clone(), window).await?;
 
@@ -266,7 +452,11 @@ impl LongAudioProcessor {
             }
         }
 
-        let _ = self.segment_tx.send(ProcessingMessage::TaskPaused(task_id));
+        // 清除已排队的段，恢复时会重新分发
+        let removed = self.segment_queue.lock().unwrap().remove_task(&task_id);
+        if let Some(task) = self.tasks.write().await.get_mut(&task_id) {
+            return_to_pending(task, &removed);
+        }
         Ok(())
     }
 
@@ -282,7 +472,6 @@ impl LongAudioProcessor {
             }
         }
 
-        let _ = self.segment_tx.send(ProcessingMessage::TaskResumed(task_id.clone()));
         self.dispatch_segments(task_id, window).await?;
         Ok(())
     }
@@ -299,10 +488,42 @@ impl LongAudioProcessor {
             }
         }
 
-        let _ = self.segment_tx.send(ProcessingMessage::TaskCancelled(task_id));
+        self.segment_queue.lock().unwrap().remove_task(&task_id);
+        self.release_audio(&task_id);
+        self.listening_tasks.lock().unwrap().remove(&task_id);
         Ok(())
     }
 
+    // 重试失败的段，返回重新排队的段数
+    pub async fn retry_failed_segments(&self, task_id: String, window: WebviewWindow) -> Result<usize, String> {
+        let retrying = {
+            let mut tasks = self.tasks.write().await;
+            let task = tasks.get_mut(&task_id).ok_or("任务不存在")?;
+            let retrying = mark_failed_segments_for_retry(task);
+            if !retrying.is_empty() {
+                task.status = TaskStatus::Processing;
+                task.updated_at = chrono::Utc::now();
+            }
+            retrying
+        };
+
+        if retrying.is_empty() {
+            return Ok(0);
+        }
+
+        for (segment_id, next_attempt) in &retrying {
+            let _ = window.emit("long_audio_segment_retrying", &serde_json::json!({
+                "task_id": task_id,
+                "segment_id": segment_id,
+                "attempt": next_attempt,
+            }));
+        }
+
+        self.dispatch_segments(task_id, window).await?;
+
+        Ok(retrying.len())
+    }
+
     // 获取任务状态
     pub async fn get_task(&self, task_id: &str) -> Option<LongAudioTask> {
         let tasks = self.tasks.read().await;
@@ -310,6 +531,12 @@ impl LongAudioProcessor {
     }
 
     // 获取所有任务
+    /// 是否有任务处于预处理或处理中（暂停的任务不算）
+    pub async fn has_active_tasks(&self) -> bool {
+        self.tasks.read().await.values()
+            .any(|task| matches!(task.status, TaskStatus::Preparing | TaskStatus::Processing))
+    }
+
     pub async fn get_all_tasks(&self) -> Vec<LongAudioTask> {
         let tasks = self.tasks.read().await;
         tasks.values().cloned().collect()
@@ -318,75 +545,52 @@ impl LongAudioProcessor {
     // 私有方法：加载音频文件
     async fn load_audio_file(&self, file_path: &str) -> Result<(Vec<f32>, u32, f64), String> {
         // 这里复用现有的音频加载逻辑
-        // 返回: (音频数据, 采样率, 总时长)
+        // 返回: (音频数据, 采样率, 总时长)；分段与识别都按 16kHz 换算，加载后统一采样率
+        let audio_loader = self.audio_loader;
         tokio::task::spawn_blocking({
             let file_path = file_path.to_string();
             move || {
-                crate::load_and_convert_audio(&file_path)
-                    .map_err(|e| format!("加载音频文件失败: {}", e))
+                let (audio_data, sample_rate, total_duration) = audio_loader(&file_path)
+                    .map_err(|e| format!("加载音频文件失败: {}", e))?;
+                let audio_data = whisper_input::ensure_whisper_rate(audio_data, sample_rate).into_owned();
+                Ok((audio_data, WHISPER_SAMPLE_RATE, total_duration))
             }
         }).await
         .map_err(|e| format!("异步任务失败: {}", e))?
     }
 
+    // 私有方法：获取任务的解码音频，缓存已释放（如任务完成后重试）时重新解码
+    async fn task_audio(&self, task_id: &str, file_path: &str) -> Result<Arc<Vec<f32>>, String> {
+        if let Some(audio) = self.audio_cache.lock().unwrap().get(task_id) {
+            return Ok(audio.clone());
+        }
+        let (audio_data, _, _) = self.load_audio_file(file_path).await?;
+        let audio_data = Arc::new(audio_data);
+        self.audio_cache.lock().unwrap().insert(task_id.to_string(), audio_data.clone());
+        Ok(audio_data)
+    }
+
+    fn release_audio(&self, task_id: &str) {
+        self.audio_cache.lock().unwrap().remove(task_id);
+    }
+
     // 私有方法：智能分段
     async fn segment_audio(
         &self, 
-        audio_data: &[f32], 
+        audio_data: &Arc<Vec<f32>>, 
         sample_rate: u32, 
         _total_duration: f64,
         config: &ProcessingConfig
     ) -> Result<Vec<AudioSegment>, String> {
-        let mut segments = Vec::new();
-        let samples_per_second = sample_rate as f64;
-        let max_segment_samples = (config.max_segment_length * samples_per_second) as usize;
-        let min_segment_samples = (config.min_segment_length * samples_per_second) as usize;
-        let overlap_samples = (config.segment_overlap * samples_per_second) as usize;
-
-        // 使用VAD检测语音活动区域
-        let speech_segments = self.detect_speech_segments(audio_data, sample_rate).await?;
-        
-        let mut segment_id = 0;
-        for (speech_start, speech_end) in speech_segments {
-            let mut current_start = speech_start;
-            
-            while current_start < speech_end {
-                let mut segment_end = (current_start + max_segment_samples).min(speech_end);
-                
-                // 在最后overlap_samples范围内寻找最佳切割点（低能量点）
-                if segment_end < speech_end {
-                    let search_start = segment_end.saturating_sub(overlap_samples);
-                    if let Some(best_cut) = self.find_best_cut_point(audio_data, search_start, segment_end) {
-                        segment_end = best_cut;
-                    }
-                }
-
-                // 确保段长度满足最小要求
-                if segment_end - current_start >= min_segment_samples {
-                    let start_time = current_start as f64 / samples_per_second;
-                    let end_time = segment_end as f64 / samples_per_second;
-                    
-                    segments.push(AudioSegment {
-                        id: format!("segment_{}", segment_id),
-                        start_time,
-                        end_time,
-                        duration: end_time - start_time,
-                        sample_start: current_start,
-                        sample_end: segment_end,
-                        status: SegmentStatus::Pending,
-                        text: None,
-                        confidence: None,
-                        processing_time: None,
-                        error: None,
-                    });
-                    
-                    segment_id += 1;
-                }
-
-                current_start = segment_end.saturating_sub(overlap_samples);
+        // 固定窗口不需要VAD
+        let speech_regions = match config.segmentation_mode {
+            SegmentationMode::FixedWindow { .. } => None,
+            SegmentationMode::Vad | SegmentationMode::Hybrid => {
+                Some(self.detect_speech_segments(audio_data.clone(), sample_rate).await?)
             }
-        }
+        };
 
+        let segments = plan_segments(audio_data, sample_rate, config, speech_regions);
         if segments.is_empty() {
             return Err("未检测到有效的语音段".to_string());
         }
@@ -395,45 +599,36 @@ impl LongAudioProcessor {
     }
 
     // 私有方法：检测语音段
-    async fn detect_speech_segments(&self, audio_data: &[f32], _sample_rate: u32) -> Result<Vec<(usize, usize)>, String> {
-        // 复用现有的VAD逻辑
-        tokio::task::spawn_blocking({
-            let audio_data = audio_data.to_vec();
-            move || {
-                crate::detect_speech_segments(&audio_data)
-                    .map_err(|e| format!("语音活动检测失败: {}", e))
-            }
+    async fn detect_speech_segments(&self, audio_data: Arc<Vec<f32>>, _sample_rate: u32) -> Result<Vec<(usize, usize)>, String> {
+        // 复用现有的VAD逻辑，共享解码缓冲区而不复制
+        tokio::task::spawn_blocking(move || {
+            crate::detect_speech_segments(&audio_data)
+                .map_err(|e| format!("语音活动检测失败: {}", e))
         }).await
         .map_err(|e| format!("异步任务失败: {}", e))?
     }
 
-    // 私有方法：寻找最佳切割点
-    fn find_best_cut_point(&self, audio_data: &[f32], start: usize, end: usize) -> Option<usize> {
-        let mut min_energy = f32::MAX;
-        let mut best_point = None;
-        
-        for i in start..end {
-            if i + 1600 < audio_data.len() { // 检查100ms的能量窗口
-                let energy: f32 = audio_data[i..i+1600].iter().map(|x| x * x).sum();
-                if energy < min_energy {
-                    min_energy = energy;
-                    best_point = Some(i);
-                }
-            }
+    // 应用退出时停止所有工作线程并等待其结束
+    pub async fn shutdown(&self) {
+        self.should_stop.store(true, Ordering::Relaxed);
+        let handles: Vec<JoinHandle<()>> = self.workers.lock().unwrap()
+            .drain(..)
+            .filter_map(|worker| worker.handle)
+            .collect();
+        for handle in handles {
+            let _ = handle.await;
         }
-        
-        best_point
     }
 
-    // 私有方法：启动工作线程
+    // 私有方法：启动工作线程，补足因空闲退出的线程
     async fn start_workers(&self) {
         let worker_count = {
             let mut workers = self.workers.lock().unwrap();
             
-            // 清理已完成的工作线程
+            // 清理已完成或已决定退出的工作线程
             workers.retain(|worker| {
                 if let Some(ref handle) = worker.handle {
-                    !handle.is_finished()
+                    !handle.is_finished() && !worker.exiting.load(Ordering::SeqCst)
                 } else {
                     false
                 }
@@ -443,17 +638,20 @@ impl LongAudioProcessor {
         };
 
         // 启动新的工作线程直到达到最大数量
-        for worker_id in worker_count..self.max_workers {
+        for _ in worker_count..self.max_workers {
+            let worker_id = self.next_worker_id.fetch_add(1, Ordering::Relaxed);
             let is_busy = Arc::new(AtomicBool::new(false));
             let current_segment = Arc::new(Mutex::new(None));
+            let exiting = Arc::new(AtomicBool::new(false));
             
-            let handle = self.spawn_worker(worker_id, is_busy.clone(), current_segment.clone()).await;
+            let handle = self.spawn_worker(worker_id, is_busy.clone(), current_segment.clone(), exiting.clone()).await;
             
             let mut workers = self.workers.lock().unwrap();
             workers.push(WorkerState {
                 id: worker_id,
                 is_busy,
                 current_segment,
+                exiting,
                 handle: Some(handle),
             });
         }
@@ -465,70 +663,73 @@ impl LongAudioProcessor {
         worker_id: usize,
         is_busy: Arc<AtomicBool>,
         current_segment: Arc<Mutex<Option<String>>>,
+        exiting: Arc<AtomicBool>,
     ) -> JoinHandle<()> {
-        let segment_rx = self.segment_rx.clone();
-        let segment_tx = self.segment_tx.clone();
+        let segment_queue = self.segment_queue.clone();
+        let result_tx = self.result_tx.clone();
         let should_stop = self.should_stop.clone();
+        let idle_timeout = self.worker_idle_timeout;
 
         tokio::spawn(async move {
-            println!("工作线程 {} 启动", worker_id);
+            log::debug!("工作线程 {} 启动", worker_id);
+            let mut idle_since = std::time::Instant::now();
             
             while !should_stop.load(Ordering::Relaxed) {
-                // 尝试获取处理消息
-                let message = {
-                    let mut rx = segment_rx.lock().unwrap();
-                    rx.try_recv().ok()
+                // 从优先队列取出开始时间最早的段；空闲超时在持有队列锁时判断并标记退出，
+                // 与入队后的 start_workers 不会错过：要么本线程取到新段，要么 start_workers 看到退出标记补起新线程
+                let job = {
+                    let mut queue = segment_queue.lock().unwrap();
+                    let job = queue.pop();
+                    if job.is_none() && idle_since.elapsed() >= idle_timeout {
+                        exiting.store(true, Ordering::SeqCst);
+                        log::debug!("工作线程 {} 空闲超时", worker_id);
+                        break;
+                    }
+                    job
                 };
                 
-                if let Some(msg) = message {
-                    match msg {
-                        ProcessingMessage::ProcessSegment { task_id, segment_id, audio_data, config } => {
-                            is_busy.store(true, Ordering::Relaxed);
-                            {
-                                let mut current = current_segment.lock().unwrap();
-                                *current = Some(segment_id.clone());
-                            }
-                            
-                            println!("工作线程 {} 开始处理段 {}", worker_id, segment_id);
-                            
-                            // 处理音频段
-                            match Self::process_audio_segment(&audio_data, &config).await {
-                                Ok(result) => {
-                                    let _ = segment_tx.send(ProcessingMessage::SegmentCompleted {
-                                        task_id,
-                                        segment_id,
-                                        result,
-                                    });
-                                }
-                                Err(error) => {
-                                    let _ = segment_tx.send(ProcessingMessage::SegmentFailed {
-                                        task_id,
-                                        segment_id,
-                                        error,
-                                    });
-                                }
-                            }
-                            
-                            is_busy.store(false, Ordering::Relaxed);
-                            {
-                                let mut current = current_segment.lock().unwrap();
-                                *current = None;
-                            }
+                if let Some(job) = job {
+                    let SegmentJob { task_id, segment_id, audio_data, config, .. } = job;
+                    is_busy.store(true, Ordering::Relaxed);
+                    {
+                        let mut current = current_segment.lock().unwrap();
+                        *current = Some(segment_id.clone());
+                    }
+                    
+                    log::debug!("工作线程 {} 开始处理段 {}", worker_id, segment_id);
+                    
+                    // 处理音频段
+                    match Self::process_audio_segment(&audio_data, &config).await {
+                        Ok(result) => {
+                            // 结果通道已满时等待，监听器跟不上时工作线程不再取新段
+                            let _ = result_tx.send(SegmentOutcome::Completed {
+                                task_id,
+                                segment_id,
+                                result,
+                            }).await;
                         }
-                        ProcessingMessage::TaskPaused(_) | 
-                        ProcessingMessage::TaskCancelled(_) => {
-                            // 暂停或取消时，工作线程暂时停止处理
-                            tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+                        Err(error) => {
+                            let _ = result_tx.send(SegmentOutcome::Failed {
+                                task_id,
+                                segment_id,
+                                error,
+                            }).await;
                         }
-                        _ => {}
                     }
+                    
+                    is_busy.store(false, Ordering::Relaxed);
+                    {
+                        let mut current = current_segment.lock().unwrap();
+                        *current = None;
+                    }
+                    idle_since = std::time::Instant::now();
                 } else {
                     // 没有任务时短暂休眠
                     tokio::time::sleep(tokio::time::Duration::from_millis(50)).await;
                 }
             }
             
-            println!("工作线程 {} 停止", worker_id);
+            log::debug!("工作线程 {} 停止", worker_id);
         })
     }
 
@@ -564,9 +765,10 @@ impl LongAudioProcessor {
 
     // 私有方法：Whisper段处理（需要实现）
     fn whisper_process_segment(audio_data: &[f32], config: &ProcessingConfig) -> Result<String, String> {
-        // 这里需要传入Whisper context，暂时返回模拟结果
+        let _params = segment_params(config);
+        // 参数已按任务配置生成，这里还需要传入Whisper context，暂时返回模拟结果
         // TODO: 需要重构以支持多线程Whisper处理
-        let segment_duration = audio_data.len() as f64 / 16000.0;
+        let segment_duration = audio_data.len() as f64 / WHISPER_SAMPLE_RATE as f64;
         
         // 模拟处理时间（实际会更快）
         std::thread::sleep(std::time::Duration::from_millis((segment_duration * 100.0) as u64));
@@ -582,218 +784,950 @@ impl LongAudioProcessor {
     }
 
     // 私有方法：分发处理任务
+    // 只把队列补到容量上限，其余段在工作线程空出时由结果监听器继续分发
     async fn dispatch_segments(&self, task_id: String, window: WebviewWindow) -> Result<(), String> {
-        let segments_to_process: Vec<AudioSegment> = {
+        // 从任务缓存读取完整音频数据用于分段
+        let file_path = {
             let tasks = self.tasks.read().await;
-            if let Some(task) = tasks.get(&task_id) {
-                task.segments.iter()
-                    .filter(|s| matches!(s.status, SegmentStatus::Pending))
-                    .cloned()
-                    .collect()
-            } else {
-                return Err("任务不存在".to_string());
+            match tasks.get(&task_id) {
+                Some(task) => task.file_path.clone(),
+                None => return Err("任务不存在".to_string()),
             }
         };
+        let full_audio_data = self.task_audio(&task_id, &file_path).await?;
 
-        // 加载完整音频数据用于分段
-        let (full_audio_data, _, _) = {
-            let tasks = self.tasks.read().await;
-            if let Some(task) = tasks.get(&task_id) {
-                self.load_audio_file(&task.file_path).await?
-            } else {
-                return Err("任务不存在".to_string());
-            }
-        };
-
-        // 分发处理任务
-        for segment in segments_to_process {
-            let segment_audio = full_audio_data[segment.sample_start..segment.sample_end].to_vec();
-            let config = ProcessingConfig::default(); // 应该从任务配置获取
-            
-            let _ = self.segment_tx.send(ProcessingMessage::ProcessSegment {
-                task_id: task_id.clone(),
-                segment_id: segment.id.clone(),
-                audio_data: segment_audio,
-                config,
-            });
+        {
+            let mut tasks = self.tasks.write().await;
+            let task = tasks.get_mut(&task_id).ok_or("任务不存在")?;
+            let mut queue = self.segment_queue.lock().unwrap();
+            fill_queue(task, &mut queue, &full_audio_data, self.queue_capacity);
         }
+        self.start_workers().await;
 
-        // 启动结果监听器
-        self.start_result_listener(task_id, window).await;
+        // 登记任务并确保结果监听器在运行
+        self.listening_tasks.lock().unwrap().insert(task_id, ListenerContext { window, full_audio_data });
+        self.start_result_listener();
         
         Ok(())
     }
 
-    // 私有方法：启动结果监听器
-    async fn start_result_listener(&self, task_id: String, window: WebviewWindow) {
+    // 私有方法：启动结果监听器。全局只有一个监听器独占结果通道的接收端，按任务ID查找窗口和音频，
+    // 不会与工作线程或其他任务的监听器争抢消息
+    fn start_result_listener(&self) {
+        let Some(mut result_rx) = self.result_rx.lock().unwrap().take() else {
+            return; // 已在运行
+        };
         let tasks = self.tasks.clone();
-        let segment_rx = self.segment_rx.clone();
-        
+        let listening_tasks = self.listening_tasks.clone();
+        let audio_cache = self.audio_cache.clone();
+        let segment_queue = self.segment_queue.clone();
+        let queue_capacity = self.queue_capacity;
+
         tokio::spawn(async move {
-            loop {
-                let message = {
-                    let mut rx = segment_rx.lock().unwrap();
-                    // 使用try_recv避免长时间持有锁
-                    rx.try_recv().ok()
+            while let Some(outcome) = result_rx.recv().await {
+                let context = listening_tasks.lock().unwrap().get(outcome.task_id()).cloned();
+                let Some(ListenerContext { window, full_audio_data }) = context else {
+                    continue; // 任务已取消或已完成
                 };
-                
-                if message.is_none() {
-                    // 没有消息时短暂休眠
-                    tokio::time::sleep(tokio::time::Duration::from_millis(10)).await;
-                    continue;
-                }
-                
-                if let Some(msg) = message {
-                    match msg {
-                        ProcessingMessage::SegmentCompleted { task_id: msg_task_id, segment_id, result } => {
-                            if msg_task_id == task_id {
-                                // 更新任务状态
-                                {
-                                    let mut tasks_guard = tasks.write().await;
-                                    if let Some(task) = tasks_guard.get_mut(&task_id) {
-                                        if let Some(segment) = task.segments.iter_mut().find(|s| s.id == segment_id) {
-                                            segment.status = SegmentStatus::Completed;
-                                            segment.text = Some(result.text.clone());
-                                            segment.confidence = Some(result.confidence);
-                                            segment.processing_time = Some(result.processing_time);
-                                        }
-                                        task.completed_segments += 1;
-                                        task.updated_at = chrono::Utc::now();
-                                        
-                                        // 更新处理统计
-                                        task.processing_stats.active_workers = task.segments.iter()
-                                            .filter(|s| matches!(s.status, SegmentStatus::Processing))
-                                            .count();
-                                        
-                                        // 计算平均处理速度
-                                        let completed_segments: Vec<_> = task.segments.iter()
-                                            .filter(|s| matches!(s.status, SegmentStatus::Completed))
-                                            .collect();
-                                        
-                                        if !completed_segments.is_empty() {
-                                            let total_audio_time: f64 = completed_segments.iter()
-                                                .map(|s| s.duration)
-                                                .sum();
-                                            let total_processing_time: f64 = completed_segments.iter()
-                                                .filter_map(|s| s.processing_time)
-                                                .sum();
-                                            
-                                            if total_processing_time > 0.0 {
-                                                task.processing_stats.average_processing_speed = total_audio_time / total_processing_time;
-                                            }
-                                            
-                                            // 估算剩余时间
-                                            let remaining_segments = task.total_segments - task.completed_segments;
-                                            if remaining_segments > 0 && task.processing_stats.average_processing_speed > 0.0 {
-                                                let remaining_audio_time: f64 = task.segments.iter()
-                                                    .filter(|s| matches!(s.status, SegmentStatus::Pending | SegmentStatus::Processing))
-                                                    .map(|s| s.duration)
-                                                    .sum();
-                                                task.processing_stats.estimated_remaining_time = Some(
-                                                    remaining_audio_time / task.processing_stats.average_processing_speed
-                                                );
-                                            }
-                                        }
-                                    }
+
+                let task_id = match outcome {
+                    SegmentOutcome::Completed { task_id, segment_id, result } => {
+                        // 更新任务状态
+                        {
+                            let mut tasks_guard = tasks.write().await;
+                            if let Some(task) = tasks_guard.get_mut(&task_id) {
+                                if let Some(segment) = task.segments.iter_mut().find(|s| s.id == segment_id) {
+                                    segment.status = SegmentStatus::Completed;
+                                    segment.text = Some(result.text.clone());
+                                    segment.confidence = Some(result.confidence);
+                                    segment.processing_time = Some(result.processing_time);
                                 }
-                                
-                                // 发送进度更新事件
-                                let progress_data = {
-                                    let tasks_guard = tasks.read().await;
-                                    if let Some(task) = tasks_guard.get(&task_id) {
-                                        serde_json::json!({
-                                            "task_id": task_id,
-                                            "completed_segments": task.completed_segments,
-                                            "total_segments": task.total_segments,
-                                            "progress": (task.completed_segments as f64 / task.total_segments as f64 * 100.0),
-                                            "segment_id": segment_id,
-                                            "segment_text": result.text,
-                                            "processing_stats": task.processing_stats
-                                        })
-                                    } else {
-                                        continue;
-                                    }
-                                };
-                                
-                                let _ = window.emit("long_audio_segment_completed", &progress_data);
-                                
-                                // 检查是否所有段都已完成
-                                let is_task_completed = {
-                                    let tasks_guard = tasks.read().await;
-                                    if let Some(task) = tasks_guard.get(&task_id) {
-                                        task.completed_segments == task.total_segments
-                                    } else {
-                                        false
-                                    }
-                                };
-                                
-                                if is_task_completed {
-                                    // 合并所有段的文本
-                                    let final_text = {
-                                        let mut tasks_guard = tasks.write().await;
-                                        if let Some(task) = tasks_guard.get_mut(&task_id) {
-                                            task.status = TaskStatus::Completed;
-                                            task.updated_at = chrono::Utc::now();
-                                            
-                                            let mut combined_text = String::new();
-                                            for segment in &task.segments {
-                                                if let Some(text) = &segment.text {
-                                                    if !combined_text.is_empty() {
-                                                        combined_text.push(' ');
-                                                    }
-                                                    combined_text.push_str(text);
-                                                }
-                                            }
-                                            task.final_text = Some(combined_text.clone());
-                                            combined_text
-                                        } else {
-                                            String::new()
-                                        }
-                                    };
-                                    
-                                    let _ = window.emit("long_audio_task_completed", &serde_json::json!({
-                                        "task_id": task_id,
-                                        "final_text": final_text,
-                                        "message": "长音频转录完成！"
-                                    }));
-                                    
-                                    break; // 任务完成，退出监听循环
+                                task.completed_segments += 1;
+                                task.updated_at = chrono::Utc::now();
+
+                                // 工作线程空出，继续分发
+                                if matches!(task.status, TaskStatus::Processing) {
+                                    fill_queue(task, &mut segment_queue.lock().unwrap(), &full_audio_data, queue_capacity);
                                 }
+                                update_processing_stats(task);
                             }
                         }
-                        ProcessingMessage::SegmentFailed { task_id: msg_task_id, segment_id, error } => {
-                            if msg_task_id == task_id {
-                                // 更新失败段状态
-                                {
-                                    let mut tasks_guard = tasks.write().await;
-                                    if let Some(task) = tasks_guard.get_mut(&task_id) {
-                                        if let Some(segment) = task.segments.iter_mut().find(|s| s.id == segment_id) {
-                                            segment.status = SegmentStatus::Failed;
-                                            segment.error = Some(error.clone());
-                                        }
-                                        task.failed_segments += 1;
-                                        task.updated_at = chrono::Utc::now();
-                                    }
-                                }
-                                
-                                let _ = window.emit("long_audio_segment_failed", &serde_json::json!({
+
+                        // 发送进度更新事件
+                        let (progress_data, partial_text_data) = {
+                            let tasks_guard = tasks.read().await;
+                            if let Some(task) = tasks_guard.get(&task_id) {
+                                (serde_json::json!({
                                     "task_id": task_id,
+                                    "completed_segments": task.completed_segments,
+                                    "total_segments": task.total_segments,
+                                    "progress": (task.completed_segments as f64 / task.total_segments as f64 * 100.0),
                                     "segment_id": segment_id,
-                                    "error": error
-                                }));
+                                    "segment_text": result.text,
+                                    "processing_stats": task.processing_stats
+                                }), serde_json::json!({
+                                    "task_id": task_id,
+                                    "completed_segments": task.completed_segments,
+                                    "total_segments": task.total_segments,
+                                    "text": assemble_partial_text(task),
+                                }))
+                            } else {
+                                continue;
+                            }
+                        };
+
+                        let _ = window.emit("long_audio_segment_completed", &progress_data);
+                        let _ = window.emit("long_audio_partial_text", &partial_text_data);
+                        task_id
+                    }
+                    SegmentOutcome::Failed { task_id, segment_id, error } => {
+                        // 更新失败段状态
+                        {
+                            let mut tasks_guard = tasks.write().await;
+                            if let Some(task) = tasks_guard.get_mut(&task_id) {
+                                record_segment_failure(task, &segment_id, &error);
+                                if matches!(task.status, TaskStatus::Processing) {
+                                    fill_queue(task, &mut segment_queue.lock().unwrap(), &full_audio_data, queue_capacity);
+                                }
                             }
                         }
-                        _ => {}
+
+                        let _ = window.emit("long_audio_segment_failed", &serde_json::json!({
+                            "task_id": task_id,
+                            "segment_id": segment_id,
+                            "error": error
+                        }));
+                        task_id
                     }
-                } else {
-                    break;
+                };
+
+                // 所有段都已完成或失败时按时间顺序合并文本；结果到达顺序不影响最终文本
+                let finished = {
+                    let mut tasks_guard = tasks.write().await;
+                    tasks_guard.get_mut(&task_id)
+                        .filter(|task| is_task_finished(task))
+                        .map(|task| (complete_task(task), task.failed_segments))
+                };
+
+                if let Some((final_text, failed_segments)) = finished {
+                    listening_tasks.lock().unwrap().remove(&task_id);
+                    audio_cache.lock().unwrap().remove(&task_id);
+                    let _ = window.emit("long_audio_task_completed", &serde_json::json!({
+                        "task_id": task_id,
+                        "final_text": final_text,
+                        "failed_segments": failed_segments,
+                        "message": "长音频转录完成！"
+                    }));
                 }
             }
         });
     }
 }
 
+// 根据已完成段更新处理速度与剩余时间估计
+fn update_processing_stats(task: &mut LongAudioTask) {
+    task.processing_stats.active_workers = task.segments.iter()
+        .filter(|s| matches!(s.status, SegmentStatus::Processing))
+        .count();
+    
+    // 计算平均处理速度
+    let completed_segments: Vec<_> = task.segments.iter()
+        .filter(|s| matches!(s.status, SegmentStatus::Completed))
+        .collect();
+    
+    if !completed_segments.is_empty() {
+        let total_audio_time: f64 = completed_segments.iter()
+            .map(|s| s.duration)
+            .sum();
+        let total_processing_time: f64 = completed_segments.iter()
+            .filter_map(|s| s.processing_time)
+            .sum();
+        
+        if let Some(speed) = realtime_factor(total_audio_time, total_processing_time) {
+            task.processing_stats.average_processing_speed = speed;
+        }
+        
+        // 估算剩余时间
+        let remaining_segments = task.total_segments - task.completed_segments;
+        if remaining_segments > 0 && task.processing_stats.average_processing_speed > 0.0 {
+            let remaining_audio_time: f64 = task.segments.iter()
+                .filter(|s| matches!(s.status, SegmentStatus::Pending | SegmentStatus::Processing))
+                .map(|s| s.duration)
+                .sum();
+            task.processing_stats.estimated_remaining_time = Some(
+                remaining_audio_time / task.processing_stats.average_processing_speed
+            );
+        }
+    }
+}
+
+// 没有待处理或处理中的段
+fn is_task_finished(task: &LongAudioTask) -> bool {
+    task.segments.iter().all(|s| matches!(s.status, SegmentStatus::Completed | SegmentStatus::Failed))
+}
+
+// 去掉 next 开头与 previous 末尾重复的部分（两段在时间上重叠时同一句话会被识别两次）；
+// 从最长的候选重叠开始比较，词级相似度达到 threshold 即视为重复
+fn trim_seam_overlap<'a>(previous: &str, next: &'a str, threshold: f64) -> &'a str {
+    let previous_tokens = metrics::word_tokens(previous);
+    let next_tokens = metrics::word_token_ends(next);
+    let longest = previous_tokens.len().min(next_tokens.len()).min(MAX_SEAM_TOKENS);
+
+    for length in (MIN_SEAM_TOKENS..=longest).rev() {
+        let tail = &previous_tokens[previous_tokens.len() - length..];
+        let head: Vec<String> = next_tokens[..length].iter().map(|(token, _)| token.clone()).collect();
+        let similarity = 1.0 - metrics::error_rate(&metrics::align(&head, tail)).rate;
+        if similarity >= threshold {
+            let cut = next_tokens[length - 1].1;
+            return next[cut..].trim_start_matches(|c: char| !c.is_alphanumeric());
+        }
+    }
+    next
+}
+
+// 按开始时间合并已完成段的文本；include_failed 时失败段以带时间范围的标记占位。
+// 与前一段在时间上重叠的段先去掉开头的重复部分
+fn join_segment_texts(task: &LongAudioTask, include_failed: bool) -> String {
+    let mut segments: Vec<&AudioSegment> = task.segments.iter().collect();
+    segments.sort_by(|a, b| a.start_time.total_cmp(&b.start_time));
+
+    let mut texts: Vec<String> = Vec::new();
+    let mut previous: Option<&AudioSegment> = None;
+    for segment in segments {
+        match (&segment.status, segment.text.as_deref()) {
+            (SegmentStatus::Completed, Some(text)) => {
+                let overlapping = previous
+                    .filter(|p| p.end_time > segment.start_time)
+                    .and_then(|p| p.text.as_deref());
+                let text = match overlapping {
+                    Some(previous_text) => trim_seam_overlap(previous_text, text, task.overlap_dedup_threshold),
+                    None => text,
+                };
+                if !text.is_empty() {
+                    texts.push(text.to_string());
+                }
+            }
+            (SegmentStatus::Failed, _) if include_failed => {
+                texts.push(format!("[转录失败 {:.1}s-{:.1}s]", segment.start_time, segment.end_time));
+            }
+            _ => {}
+        }
+        previous = matches!(segment.status, SegmentStatus::Completed).then_some(segment);
+    }
+    join_segments(texts.iter().map(String::as_str), task.text_join, &task.language)
+}
+
+// 最终文本：失败段以标记占位，便于之后重试补齐
+fn assemble_final_text(task: &LongAudioTask) -> String {
+    join_segment_texts(task, true)
+}
+
+// 处理过程中已完成部分的文本，每完成一段发送一次供界面逐步显示；结果乱序到达时仍按时间排列
+fn assemble_partial_text(task: &LongAudioTask) -> String {
+    join_segment_texts(task, false)
+}
+
+// 标记任务完成并合并文本
+fn complete_task(task: &mut LongAudioTask) -> String {
+    task.status = TaskStatus::Completed;
+    task.updated_at = chrono::Utc::now();
+
+    let combined_text = assemble_final_text(task);
+    task.final_text = Some(combined_text.clone());
+    combined_text
+}
+
+// 按分段模式确定要切分的区域：VAD 语音区域，或整段音频；
+// Hybrid 在 VAD 没有检测到语音（如音乐、连续底噪）时退回固定窗口
+fn plan_segments(
+    audio_data: &[f32],
+    sample_rate: u32,
+    config: &ProcessingConfig,
+    speech_regions: Option<Vec<(usize, usize)>>,
+) -> Vec<AudioSegment> {
+    let whole_audio = vec![(0, audio_data.len())];
+    let (regions, window_secs) = match (config.segmentation_mode, speech_regions) {
+        (SegmentationMode::FixedWindow { len_secs }, _) => (whole_audio, len_secs),
+        (SegmentationMode::Hybrid, Some(regions)) if regions.is_empty() => (whole_audio, config.max_segment_length),
+        (_, regions) => (regions.unwrap_or_default(), config.max_segment_length),
+    };
+
+    let samples_per_second = sample_rate as f64;
+    let window_samples = ((window_secs * samples_per_second) as usize).max(1);
+    let min_segment_samples = (config.min_segment_length * samples_per_second) as usize;
+    let overlap_samples = (config.segment_overlap * samples_per_second) as usize;
+    let search_samples = (config.cut_search_secs * samples_per_second) as usize;
+    let min_pause_samples = (config.min_pause_secs * samples_per_second) as usize;
+
+    let mut segments = Vec::new();
+    for (region_start, region_end) in regions {
+        let region_end = region_end.min(audio_data.len());
+        let mut current_start = region_start;
+
+        while current_start < region_end {
+            let mut segment_end = (current_start + window_samples).min(region_end);
+
+            // 在段末尾之前的搜索范围内寻找停顿作为切割点
+            if segment_end < region_end {
+                let search_start = segment_end.saturating_sub(search_samples).max(current_start + 1);
+                if let Some(best_cut) = find_best_cut_point(audio_data, search_start, segment_end, min_pause_samples) {
+                    segment_end = best_cut;
+                }
+            }
+
+            // 确保段长度满足最小要求
+            if segment_end - current_start >= min_segment_samples {
+                let start_time = current_start as f64 / samples_per_second;
+                let end_time = segment_end as f64 / samples_per_second;
+
+                segments.push(AudioSegment {
+                    id: format!("segment_{}", segments.len()),
+                    start_time,
+                    end_time,
+                    duration: end_time - start_time,
+                    sample_start: current_start,
+                    sample_end: segment_end,
+                    status: SegmentStatus::Pending,
+                    text: None,
+                    confidence: None,
+                    processing_time: None,
+                    error: None,
+                    attempts: 0,
+                });
+            }
+
+            if segment_end >= region_end {
+                break;
+            }
+            // 重叠不能让起点回退到原地，否则会死循环
+            current_start = segment_end.saturating_sub(overlap_samples).max(current_start + 1);
+        }
+    }
+
+    segments
+}
+
+// 切割点分析的帧长（16kHz 下 20ms）
+const CUT_FRAME_SAMPLES: usize = 320;
+// 均方能量低于该值的帧视为静音候选（约 -46dBFS）
+const SILENCE_ENERGY: f32 = 2.5e-5;
+// 过零率高于该值的低能量帧更可能是清辅音（如"s"）而不是静音
+const FRICATIVE_ZCR: f32 = 0.25;
+
+// 在 [start, end) 内寻找切割点：优先选择最长的一段连续静音（不短于 min_pause_samples）的中点，
+// 低能量但过零率高的帧按清辅音处理；找不到足够长的停顿时退回能量最低的帧
+fn find_best_cut_point(audio_data: &[f32], start: usize, end: usize, min_pause_samples: usize) -> Option<usize> {
+    let end = end.min(audio_data.len());
+    if start >= end {
+        return None;
+    }
+
+    let frames: Vec<(f32, f32)> = audio_data[start..end].chunks_exact(CUT_FRAME_SAMPLES)
+        .map(|frame| {
+            let energy = frame.iter().map(|x| x * x).sum::<f32>() / frame.len() as f32;
+            let crossings = frame.windows(2).filter(|pair| (pair[0] >= 0.0) != (pair[1] >= 0.0)).count();
+            (energy, crossings as f32 / frame.len() as f32)
+        })
+        .collect();
+    if frames.is_empty() {
+        return None;
+    }
+
+    let min_energy = frames.iter().map(|&(energy, _)| energy).fold(f32::MAX, f32::min);
+    let threshold = (min_energy * 4.0).max(SILENCE_ENERGY);
+    let is_pause = |&(energy, zcr): &(f32, f32)| energy < threshold && (zcr < FRICATIVE_ZCR || energy < threshold / 4.0);
+
+    // 最长的连续静音段；长度相同时取更靠后的，段尽量长
+    let min_pause_frames = min_pause_samples.div_ceil(CUT_FRAME_SAMPLES).max(1);
+    let mut best_run: Option<(usize, usize)> = None;
+    let mut run_start = None;
+    for index in 0..=frames.len() {
+        match (index < frames.len() && is_pause(&frames[index]), run_start) {
+            (true, None) => run_start = Some(index),
+            (false, Some(first)) => {
+                let length = index - first;
+                if length >= min_pause_frames && !best_run.is_some_and(|(_, best)| length < best) {
+                    best_run = Some((first, length));
+                }
+                run_start = None;
+            }
+            _ => {}
+        }
+    }
+
+    let frame = match best_run {
+        Some((first, length)) => first + length / 2,
+        None => frames.iter().enumerate()
+            .min_by(|(_, a), (_, b)| a.0.total_cmp(&b.0))
+            .map(|(index, _)| index)?,
+    };
+    Some(start + frame * CUT_FRAME_SAMPLES)
+}
+
+// 按任务配置生成单段识别的 Whisper 参数
+fn segment_params(config: &ProcessingConfig) -> whisper_full_params {
+    let mut params = unsafe { whisper_full_default_params(whisper_sampling_strategy_WHISPER_SAMPLING_BEAM_SEARCH) };
+    params.print_progress = false;
+    params.print_realtime = false;
+    params.suppress_blank = true;
+    params.beam_search.beam_size = if config.model_mode == "high_precision" { 5 } else { 3 };
+    config.temperature_fallback.apply(&mut params);
+    params
+}
+
+// 取出最多 limit 个待处理的段，标记为处理中并记录一次尝试
+fn take_pending_segments(task: &mut LongAudioTask, limit: usize) -> Vec<AudioSegment> {
+    task.segments.iter_mut()
+        .filter(|s| matches!(s.status, SegmentStatus::Pending))
+        .take(limit)
+        .map(|s| {
+            s.status = SegmentStatus::Processing;
+            s.attempts += 1;
+            s.clone()
+        })
+        .collect()
+}
+
+// 把待处理段补入队列直到达到容量，此时才复制段音频；返回本次入队的段数
+fn fill_queue(task: &mut LongAudioTask, queue: &mut SegmentQueue, full_audio_data: &[f32], capacity: usize) -> usize {
+    let segments = take_pending_segments(task, capacity.saturating_sub(queue.len()));
+    let count = segments.len();
+    for segment in segments {
+        let end = segment.sample_end.min(full_audio_data.len());
+        let start = segment.sample_start.min(end);
+        queue.push(SegmentJob {
+            task_id: task.id.clone(),
+            segment_id: segment.id.clone(),
+            audio_data: full_audio_data[start..end].to_vec(),
+            config: task.config.clone(),
+            priority: segment_priority(&segment),
+        });
+    }
+    count
+}
+
+// 已入队但未开始处理的段退回待处理，且不计入尝试次数
+fn return_to_pending(task: &mut LongAudioTask, segment_ids: &[String]) {
+    for segment in task.segments.iter_mut().filter(|s| segment_ids.contains(&s.id)) {
+        segment.status = SegmentStatus::Pending;
+        segment.attempts = segment.attempts.saturating_sub(1);
+    }
+}
+
+// 记录段失败；同一段重复失败时不重复计数
+fn record_segment_failure(task: &mut LongAudioTask, segment_id: &str, error: &str) {
+    if let Some(segment) = task.segments.iter_mut().find(|s| s.id == segment_id) {
+        if !matches!(segment.status, SegmentStatus::Failed) {
+            task.failed_segments += 1;
+        }
+        segment.status = SegmentStatus::Failed;
+        segment.error = Some(error.to_string());
+    }
+    task.updated_at = chrono::Utc::now();
+}
+
+// 将未超过最大尝试次数的失败段重置为待处理，返回 (段ID, 下一次尝试序号)
+fn mark_failed_segments_for_retry(task: &mut LongAudioTask) -> Vec<(String, u32)> {
+    let max_attempts = task.max_segment_attempts;
+    let mut retrying = Vec::new();
+
+    for segment in task.segments.iter_mut() {
+        if matches!(segment.status, SegmentStatus::Failed) && segment.attempts < max_attempts {
+            segment.status = SegmentStatus::Pending;
+            segment.error = None;
+            retrying.push((segment.id.clone(), segment.attempts + 1));
+        }
+    }
+
+    task.failed_segments = task.failed_segments.saturating_sub(retrying.len());
+    retrying
+}
+
 // 全局处理器实例
 lazy_static::lazy_static! {
     pub static ref LONG_AUDIO_PROCESSOR: LongAudioProcessor = LongAudioProcessor::new();
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_task(segment_count: usize) -> LongAudioTask {
+        let segments = (0..segment_count).map(|i| AudioSegment {
+            id: format!("segment_{}", i),
+            start_time: i as f64 * 10.0,
+            end_time: (i + 1) as f64 * 10.0,
+            duration: 10.0,
+            sample_start: i * 160000,
+            sample_end: (i + 1) * 160000,
+            status: SegmentStatus::Pending,
+            text: None,
+            confidence: None,
+            processing_time: None,
+            error: None,
+            attempts: 0,
+        }).collect();
+
+        LongAudioTask {
+            id: "task".to_string(),
+            record_id: "record".to_string(),
+            file_path: "test.wav".to_string(),
+            total_duration: segment_count as f64 * 10.0,
+            total_segments: segment_count,
+            completed_segments: 0,
+            failed_segments: 0,
+            status: TaskStatus::Processing,
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+            segments,
+            final_text: None,
+            processing_stats: ProcessingStats::default(),
+            max_segment_attempts: 3,
+            text_join: TextJoinStyle::default(),
+            language: "zh".to_string(),
+            overlap_dedup_threshold: default_overlap_dedup_threshold(),
+            config: ProcessingConfig::default(),
+        }
+    }
+
+    #[test]
+    fn test_segment_queue_prefers_earlier_segments() {
+        let queue = Arc::new(Mutex::new(SegmentQueue::default()));
+        let task = test_task(32);
+
+        // 多线程乱序入队
+        let handles: Vec<_> = (0..4).map(|t| {
+            let queue = queue.clone();
+            let segments: Vec<AudioSegment> = task.segments.iter().rev()
+                .skip(t).step_by(4).cloned().collect();
+            std::thread::spawn(move || {
+                for segment in segments {
+                    queue.lock().unwrap().push(SegmentJob {
+                        task_id: "task".to_string(),
+                        segment_id: segment.id.clone(),
+                        audio_data: Vec::new(),
+                        config: ProcessingConfig::default(),
+                        priority: segment_priority(&segment),
+                    });
+                }
+            })
+        }).collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        let mut queue = queue.lock().unwrap();
+        assert_eq!(queue.len(), 32);
+        let mut order = Vec::new();
+        while let Some(SegmentJob { segment_id, .. }) = queue.pop() {
+            order.push(segment_id);
+        }
+        let expected: Vec<String> = (0..32).map(|i| format!("segment_{}", i)).collect();
+        assert_eq!(order, expected);
+    }
+
+    #[test]
+    fn test_transient_failure_succeeds_on_retry() {
+        let mut task = test_task(2);
+        assert_eq!(take_pending_segments(&mut task, usize::MAX).len(), 2);
+        record_segment_failure(&mut task, "segment_1", "临时错误");
+        assert_eq!(task.failed_segments, 1);
+
+        let retrying = mark_failed_segments_for_retry(&mut task);
+        assert_eq!(retrying, vec![("segment_1".to_string(), 2)]);
+        assert_eq!(task.failed_segments, 0);
+
+        let dispatched = take_pending_segments(&mut task, usize::MAX);
+        assert_eq!(dispatched.len(), 1);
+        assert_eq!(dispatched[0].attempts, 2);
+    }
+
+    #[test]
+    fn test_permanent_failure_stops_after_max_attempts() {
+        let mut task = test_task(1);
+        let mut dispatch_count = 0;
+
+        loop {
+            if take_pending_segments(&mut task, usize::MAX).is_empty() {
+                break;
+            }
+            dispatch_count += 1;
+            record_segment_failure(&mut task, "segment_0", "永久错误");
+            mark_failed_segments_for_retry(&mut task);
+        }
+
+        assert_eq!(dispatch_count, 3);
+        assert_eq!(task.failed_segments, 1);
+        assert!(matches!(task.segments[0].status, SegmentStatus::Failed));
+    }
+
+    #[test]
+    fn test_final_text_ordered_by_start_time() {
+        let mut task = test_task(4);
+        // 段在任务中的存储顺序与时间顺序不同
+        task.segments.swap(0, 3);
+        take_pending_segments(&mut task, usize::MAX);
+
+        // 乱序完成，其中一段失败
+        for (segment_id, text) in [("segment_3", "四"), ("segment_0", "一"), ("segment_2", "三")] {
+            let segment = task.segments.iter_mut().find(|s| s.id == segment_id).unwrap();
+            segment.status = SegmentStatus::Completed;
+            segment.text = Some(text.to_string());
+            assert!(!is_task_finished(&task));
+        }
+        record_segment_failure(&mut task, "segment_1", "解码失败");
+        assert!(is_task_finished(&task));
+
+        assert_eq!(complete_task(&mut task), "一[转录失败 10.0s-20.0s]三四");
+        assert!(matches!(task.status, TaskStatus::Completed));
+        assert_eq!(assemble_final_text(&task), task.final_text.clone().unwrap());
+
+        task.text_join = TextJoinStyle::Newline;
+        assert_eq!(assemble_final_text(&task), "一\n[转录失败 10.0s-20.0s]\n三\n四");
+        task.text_join = TextJoinStyle::Paragraph;
+        assert_eq!(assemble_final_text(&task), "一\n\n[转录失败 10.0s-20.0s]\n\n三\n\n四");
+
+        // 以空格分词的语言按空格拼接
+        task.text_join = TextJoinStyle::Space;
+        task.language = "en".to_string();
+        assert_eq!(assemble_final_text(&task), "一 [转录失败 10.0s-20.0s] 三 四");
+    }
+
+    // 两段在 9-10 秒重叠
+    fn overlapping_task(first: &str, second: &str) -> LongAudioTask {
+        let mut task = test_task(2);
+        task.segments[1].start_time = 9.0;
+        for (segment, text) in task.segments.iter_mut().zip([first, second]) {
+            segment.status = SegmentStatus::Completed;
+            segment.text = Some(text.to_string());
+        }
+        task
+    }
+
+    #[test]
+    fn test_seam_dedup_at_thresholds() {
+        // 完全相同的重叠在任何阈值下都会去掉
+        let mut task = overlapping_task("今天讨论第二季度的预算", "的预算，首先看市场部");
+        assert_eq!(assemble_final_text(&task), "今天讨论第二季度的预算首先看市场部");
+        task.overlap_dedup_threshold = 1.0;
+        assert_eq!(assemble_final_text(&task), "今天讨论第二季度的预算首先看市场部");
+
+        // 重叠部分识别有差异（的/得）：默认阈值去重，阈值过高时留下重复
+        let mut task = overlapping_task("今天讨论第二季度的预算", "二季度得预算首先看市场部");
+        assert_eq!(assemble_final_text(&task), "今天讨论第二季度的预算首先看市场部");
+        task.overlap_dedup_threshold = 0.9;
+        assert_eq!(assemble_final_text(&task), "今天讨论第二季度的预算二季度得预算首先看市场部");
+
+        // 没有重复时默认阈值保留原文，阈值过低会误删真实内容
+        let mut task = overlapping_task("会议到此结束", "下面请财务部发言");
+        assert_eq!(assemble_final_text(&task), "会议到此结束下面请财务部发言");
+        task.overlap_dedup_threshold = 0.0;
+        assert_eq!(assemble_final_text(&task), "会议到此结束发言");
+    }
+
+    #[test]
+    fn test_seam_dedup_words_and_non_overlapping_segments() {
+        let mut task = overlapping_task("we will review the budget", "the budget for next quarter");
+        task.language = "en".to_string();
+        assert_eq!(assemble_final_text(&task), "we will review the budget for next quarter");
+
+        // 时间上不重叠的段即使文字相同也不去重
+        let mut task = overlapping_task("谢谢大家", "谢谢大家");
+        task.segments[1].start_time = 10.0;
+        assert_eq!(assemble_final_text(&task), "谢谢大家谢谢大家");
+    }
+
+    #[test]
+    fn test_partial_text_grows_in_time_order() {
+        let mut task = test_task(5);
+        take_pending_segments(&mut task, usize::MAX);
+        assert_eq!(assemble_partial_text(&task), "");
+
+        let mut previous = String::new();
+        let mut completed: Vec<(usize, &str)> = Vec::new();
+        for (index, text) in [(3, "四"), (0, "一"), (4, "五"), (2, "三")] {
+            let segment = &mut task.segments[index];
+            segment.status = SegmentStatus::Completed;
+            segment.text = Some(text.to_string());
+            completed.push((index, text));
+
+            let partial = assemble_partial_text(&task);
+            assert!(partial.chars().count() > previous.chars().count(), "部分文本没有增长: {:?} -> {:?}", previous, partial);
+            completed.sort();
+            let expected: Vec<&str> = completed.iter().map(|(_, text)| *text).collect();
+            assert_eq!(partial, expected.concat());
+            previous = partial;
+        }
+
+        // 失败段不出现在部分文本中，最终文本中以标记占位
+        record_segment_failure(&mut task, "segment_1", "解码失败");
+        assert_eq!(assemble_partial_text(&task), "一三四五");
+        assert_eq!(assemble_final_text(&task), "一[转录失败 10.0s-20.0s]三四五");
+    }
+
+    #[test]
+    fn test_queue_depth_bounded_regardless_of_segment_count() {
+        let capacity = 4 * QUEUE_DEPTH_PER_WORKER;
+        for segment_count in [3, 100, 5000] {
+            let mut task = test_task(segment_count);
+            for (i, segment) in task.segments.iter_mut().enumerate() {
+                segment.sample_start = i * 100;
+                segment.sample_end = (i + 1) * 100;
+            }
+            let full_audio_data = vec![0.0f32; segment_count * 100];
+            let mut queue = SegmentQueue::default();
+
+            let mut max_depth = 0;
+            let mut processed = Vec::new();
+            fill_queue(&mut task, &mut queue, &full_audio_data, capacity);
+            // 模拟工作线程逐个取段、监听器在每个结果后补充队列
+            while let Some(SegmentJob { segment_id, audio_data, .. }) = queue.pop() {
+                assert_eq!(audio_data.len(), 100);
+                processed.push(segment_id.clone());
+                task.segments.iter_mut().find(|s| s.id == segment_id).unwrap().status = SegmentStatus::Completed;
+                fill_queue(&mut task, &mut queue, &full_audio_data, capacity);
+                max_depth = max_depth.max(queue.len());
+            }
+
+            assert!(max_depth <= capacity);
+            assert_eq!(processed.len(), segment_count);
+            assert!(task.segments.iter().all(|s| s.attempts == 1));
+        }
+    }
+
+    #[test]
+    fn test_segment_params_follow_task_config() {
+        let mut task = test_task(2);
+        task.config.temperature_fallback = TemperatureFallback { temperature_inc: 0.25, max_fallback_attempts: 4 };
+        let full_audio_data = vec![0.0f32; 2 * 160000];
+        let mut queue = SegmentQueue::default();
+        fill_queue(&mut task, &mut queue, &full_audio_data, 2);
+
+        while let Some(SegmentJob { config, .. }) = queue.pop() {
+            assert_eq!(config.temperature_fallback, task.config.temperature_fallback);
+            let params = segment_params(&config);
+            assert!((params.temperature_inc - 0.25).abs() < 1e-6);
+        }
+
+        task.config.temperature_fallback.max_fallback_attempts = 0;
+        assert_eq!(segment_params(&task.config).temperature_inc, 0.0);
+    }
+
+    #[test]
+    fn test_paused_segments_return_to_pending() {
+        let mut task = test_task(10);
+        let full_audio_data = vec![0.0f32; 10 * 160000];
+        let mut queue = SegmentQueue::default();
+        assert_eq!(fill_queue(&mut task, &mut queue, &full_audio_data, 4), 4);
+
+        let removed = queue.remove_task("task");
+        return_to_pending(&mut task, &removed);
+        assert_eq!(queue.len(), 0);
+        assert!(task.segments.iter().all(|s| matches!(s.status, SegmentStatus::Pending) && s.attempts == 0));
+
+        // 恢复后从最早的段重新分发
+        fill_queue(&mut task, &mut queue, &full_audio_data, 4);
+        assert!(matches!(queue.pop(), Some(SegmentJob { segment_id, .. }) if segment_id == "segment_0"));
+    }
+
+    #[tokio::test]
+    async fn test_results_reach_listener_not_workers() {
+        let processor = LongAudioProcessor::with_audio_loader(counting_loader);
+        let mut result_rx = processor.result_rx.lock().unwrap().take().unwrap();
+
+        let segment_count = 10;
+        for i in 0..segment_count {
+            processor.segment_queue.lock().unwrap().push(SegmentJob {
+                task_id: "task".to_string(),
+                segment_id: format!("segment_{}", i),
+                audio_data: vec![0.0; 160],
+                config: ProcessingConfig::default(),
+                priority: i as u64,
+            });
+        }
+        processor.start_workers().await;
+
+        // 每个段的结果都从结果通道到达，工作线程只从分发队列取段
+        let mut received = Vec::new();
+        while received.len() < segment_count {
+            let outcome = tokio::time::timeout(std::time::Duration::from_secs(10), result_rx.recv())
+                .await
+                .expect("等待结果超时")
+                .unwrap();
+            match outcome {
+                SegmentOutcome::Completed { task_id, segment_id, .. } => {
+                    assert_eq!(task_id, "task");
+                    received.push(segment_id);
+                }
+                SegmentOutcome::Failed { error, .. } => panic!("段处理失败: {}", error),
+            }
+        }
+        processor.should_stop.store(true, Ordering::Relaxed);
+
+        received.sort_by_key(|id| id.trim_start_matches("segment_").parse::<usize>().unwrap());
+        let expected: Vec<String> = (0..segment_count).map(|i| format!("segment_{}", i)).collect();
+        assert_eq!(received, expected);
+        assert_eq!(processor.segment_queue.lock().unwrap().len(), 0);
+        assert!(result_rx.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_idle_workers_exit_between_tasks() {
+        let mut processor = LongAudioProcessor::with_audio_loader(counting_loader);
+        processor.worker_idle_timeout = std::time::Duration::from_millis(100);
+        let mut result_rx = processor.result_rx.lock().unwrap().take().unwrap();
+        let live_workers = |processor: &LongAudioProcessor| {
+            processor.workers.lock().unwrap().iter()
+                .filter(|worker| worker.handle.as_ref().is_some_and(|handle| !handle.is_finished()))
+                .count()
+        };
+
+        let mut seen_ids = std::collections::HashSet::new();
+        for round in 0..3 {
+            for i in 0..4 {
+                processor.segment_queue.lock().unwrap().push(SegmentJob {
+                    task_id: format!("task_{}", round),
+                    segment_id: format!("segment_{}", i),
+                    audio_data: vec![0.0; 160],
+                    config: ProcessingConfig::default(),
+                    priority: i,
+                });
+            }
+            processor.start_workers().await;
+            assert!(processor.workers.lock().unwrap().len() <= processor.max_workers);
+            // 重新启动的线程使用新编号
+            for worker in processor.workers.lock().unwrap().iter() {
+                assert!(seen_ids.insert(worker.id));
+            }
+            for _ in 0..4 {
+                result_rx.recv().await.unwrap();
+            }
+
+            // 任务结束后空闲线程在宽限期后退出，不会跨任务累积
+            tokio::time::sleep(std::time::Duration::from_millis(400)).await;
+            assert_eq!(live_workers(&processor), 0);
+        }
+
+        processor.start_workers().await;
+        processor.shutdown().await;
+        assert!(processor.workers.lock().unwrap().is_empty());
+    }
+
+    // 60秒的恒定信号，在 29.5s 和 58.0s 处各有200ms静音
+    fn synthetic_signal() -> Vec<f32> {
+        let mut audio = vec![0.5f32; 60 * 16000];
+        for start in [29.5, 58.0] {
+            let start = (start * 16000.0) as usize;
+            audio[start..start + 3200].fill(0.0);
+        }
+        audio
+    }
+
+    fn boundaries(segments: &[AudioSegment]) -> Vec<(f64, f64)> {
+        segments.iter().map(|s| (s.start_time, s.end_time)).collect()
+    }
+
+    fn segmentation_config(mode: SegmentationMode) -> ProcessingConfig {
+        ProcessingConfig {
+            segmentation_mode: mode,
+            max_segment_length: 30.0,
+            min_segment_length: 2.0,
+            segment_overlap: 1.0,
+            ..ProcessingConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_fixed_window_cuts_at_low_energy() {
+        let audio = synthetic_signal();
+        let config = segmentation_config(SegmentationMode::FixedWindow { len_secs: 30.0 });
+        let segments = plan_segments(&audio, 16000, &config, None);
+        // 在窗口末尾之前的静音中点切开，下一段向前重叠1秒
+        assert_eq!(boundaries(&segments), vec![(0.0, 29.6), (28.6, 58.1), (57.1, 60.0)]);
+    }
+
+    #[test]
+    fn test_vad_and_hybrid_segmentation() {
+        let audio = synthetic_signal();
+        let regions = vec![(0, 20 * 16000), (30 * 16000, 45 * 16000)];
+
+        let vad = plan_segments(&audio, 16000, &segmentation_config(SegmentationMode::Vad), Some(regions.clone()));
+        assert_eq!(boundaries(&vad), vec![(0.0, 20.0), (30.0, 45.0)]);
+        assert!(plan_segments(&audio, 16000, &segmentation_config(SegmentationMode::Vad), Some(Vec::new())).is_empty());
+
+        // Hybrid 有语音区域时与 VAD 一致，没有时按最大段长的固定窗口切分
+        let hybrid = segmentation_config(SegmentationMode::Hybrid);
+        assert_eq!(boundaries(&plan_segments(&audio, 16000, &hybrid, Some(regions))), boundaries(&vad));
+        assert_eq!(
+            boundaries(&plan_segments(&audio, 16000, &hybrid, Some(Vec::new()))),
+            vec![(0.0, 29.6), (28.6, 58.1), (57.1, 60.0)]
+        );
+
+        let mode: SegmentationMode = serde_json::from_str(r#"{"type":"fixed_window","len_secs":20}"#).unwrap();
+        assert_eq!(mode, SegmentationMode::FixedWindow { len_secs: 20.0 });
+    }
+
+    #[test]
+    fn test_cut_lands_in_word_gap() {
+        // 词(0.8s) - 停顿(0.3s) - 含40ms短暂中断的词(0.9s) - 清辅音(0.5s) - 词(0.5s)
+        let mut seed = 12345u32;
+        let mut noise = |amplitude: f32| {
+            seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+            ((seed >> 16) as f32 / 32768.0 - 1.0) * amplitude
+        };
+        let word = |n: usize| (2.0 * std::f32::consts::PI * 200.0 * n as f32 / 16000.0).sin() * 0.4;
+        let audio: Vec<f32> = (0..48000).map(|n| match n {
+            0..=12799 => word(n),
+            12800..=17599 => noise(0.001),
+            27200..=27839 => 0.0,
+            32000..=39999 => noise(0.008),
+            _ => word(n),
+        }).collect();
+
+        let cut = find_best_cut_point(&audio, 0, audio.len(), 2400).unwrap();
+        assert!((12800..17600).contains(&cut), "切割点 {} 不在停顿内", cut);
+
+        // 没有足够长的停顿时退回能量最低处
+        let cut = find_best_cut_point(&audio, 17600, 32000, 2400).unwrap();
+        assert!((27200..27840).contains(&cut));
+    }
+
+    static DECODE_COUNT: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+
+    fn counting_loader(_file_path: &str) -> Result<(Vec<f32>, u32, f64), String> {
+        DECODE_COUNT.fetch_add(1, Ordering::SeqCst);
+        Ok((vec![0.0; 16000], 16000, 1.0))
+    }
+
+    #[tokio::test]
+    async fn test_audio_decoded_once_per_task() {
+        let processor = LongAudioProcessor::with_audio_loader(counting_loader);
+
+        // 首次分发、恢复后分发、重试分发都读取同一份缓存
+        let first = processor.task_audio("task", "test.wav").await.unwrap();
+        for _ in 0..3 {
+            let again = processor.task_audio("task", "test.wav").await.unwrap();
+            assert!(Arc::ptr_eq(&first, &again));
+        }
+        assert_eq!(DECODE_COUNT.load(Ordering::SeqCst), 1);
+
+        // 任务结束后释放缓存，分发时保留的引用不受影响
+        processor.release_audio("task");
+        assert!(processor.audio_cache.lock().unwrap().is_empty());
+        assert_eq!(first.len(), 16000);
+    }
+
+    fn failing_loader(_file_path: &str) -> Result<(Vec<f32>, u32, f64), String> {
+        Err("不支持的音频格式".to_string())
+    }
+
+    #[tokio::test]
+    async fn test_failed_decode_is_reported_and_not_cached() {
+        let processor = LongAudioProcessor::with_audio_loader(failing_loader);
+
+        let error = processor.task_audio("task", "broken.mp3").await.unwrap_err();
+        assert!(error.contains("加载音频文件失败") && error.contains("不支持的音频格式"), "{}", error);
+        assert!(processor.audio_cache.lock().unwrap().is_empty());
+        // 失败不会被缓存，下次分发仍会重新尝试解码
+        assert!(processor.task_audio("task", "broken.mp3").await.is_err());
+    }
+}
@@ -22,6 +22,21 @@ pub async fn create_long_audio_task(
         audio_enhancement: config.get("audioEnhancement")
             .and_then(|v| v.as_bool())
             .unwrap_or(true),
+        temperature_fallback: config.get("temperatureFallback")
+            .and_then(|v| serde_json::from_value(v.clone()).ok())
+            .unwrap_or_default(),
+        segmentation_mode: config.get("segmentationMode")
+            .and_then(|v| serde_json::from_value(v.clone()).ok())
+            .unwrap_or_default(),
+        cut_search_secs: config.get("cutSearchSecs")
+            .and_then(|v| v.as_f64())
+            .unwrap_or(3.0),
+        text_join: config.get("textJoin")
+            .and_then(|v| serde_json::from_value(v.clone()).ok())
+            .unwrap_or_default(),
+        overlap_dedup_threshold: config.get("overlapDedupThreshold")
+            .and_then(|v| v.as_f64())
+            .unwrap_or(0.8),
         ..Default::default()
     };
 
@@ -68,6 +83,16 @@ pub async fn cancel_long_audio_task(
         .await
 }
 
+#[tauri::command]
+pub async fn retry_failed_long_audio_segments(
+    task_id: String,
+    window: WebviewWindow,
+) -> Result<usize, String> {
+    LONG_AUDIO_PROCESSOR
+        .retry_failed_segments(task_id, window)
+        .await
+}
+
 #[tauri::command]
 pub async fn get_long_audio_task(
     task_id: String,
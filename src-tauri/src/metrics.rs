@@ -0,0 +1,207 @@
+// metrics.rs - 识别质量评估：基于编辑距离对齐计算词错误率（WER）和字错误率（CER）
+use serde::Serialize;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ErrorRate {
+    pub rate: f64, // (替换 + 删除 + 插入) / 参考长度
+    pub substitutions: usize,
+    pub deletions: usize,
+    pub insertions: usize,
+    pub reference_length: usize,
+}
+
+/// 对齐后的一个位置；删除表示参考中有而识别结果中缺失，插入表示识别结果多出的内容
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum AlignedToken {
+    Match { token: String },
+    Substitute { reference: String, hypothesis: String },
+    Delete { reference: String },
+    Insert { hypothesis: String },
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EvaluationReport {
+    pub wer: ErrorRate,
+    pub cer: ErrorRate,
+    pub alignment: Vec<AlignedToken>, // 词级对齐
+}
+
+fn is_cjk(c: char) -> bool {
+    matches!(c as u32,
+        0x3040..=0x30FF     // 平假名、片假名
+        | 0x3400..=0x4DBF   // 扩展A
+        | 0x4E00..=0x9FFF   // 基本汉字
+        | 0xAC00..=0xD7AF   // 韩文音节
+        | 0xF900..=0xFAFF)  // 兼容汉字
+}
+
+/// 分词：中日韩文字没有空格分词，每个字算一个词；其余按空白和标点切分并转小写
+pub fn word_tokens(text: &str) -> Vec<String> {
+    word_token_ends(text).into_iter().map(|(token, _)| token).collect()
+}
+
+/// 与 word_tokens 相同的分词，同时给出每个词在原文中结束的字节位置
+pub fn word_token_ends(text: &str) -> Vec<(String, usize)> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut current_end = 0;
+    for (index, c) in text.char_indices() {
+        if is_cjk(c) || !c.is_alphanumeric() {
+            if !current.is_empty() {
+                tokens.push((std::mem::take(&mut current), current_end));
+            }
+            if is_cjk(c) {
+                tokens.push((c.to_string(), index + c.len_utf8()));
+            }
+        } else {
+            current.extend(c.to_lowercase());
+            current_end = index + c.len_utf8();
+        }
+    }
+    if !current.is_empty() {
+        tokens.push((current, current_end));
+    }
+    tokens
+}
+
+/// 字符级切分，忽略空白和标点
+pub fn char_tokens(text: &str) -> Vec<String> {
+    text.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(|c| c.to_lowercase())
+        .map(|c| c.to_string())
+        .collect()
+}
+
+/// 最小编辑距离对齐，代价相同时优先替换/匹配，其次删除
+pub fn align(hypothesis: &[String], reference: &[String]) -> Vec<AlignedToken> {
+    let (n, m) = (reference.len(), hypothesis.len());
+    let mut cost = vec![vec![0usize; m + 1]; n + 1];
+    for (i, row) in cost.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in cost[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+    for i in 1..=n {
+        for j in 1..=m {
+            let diagonal = cost[i - 1][j - 1] + usize::from(reference[i - 1] != hypothesis[j - 1]);
+            cost[i][j] = diagonal.min(cost[i - 1][j] + 1).min(cost[i][j - 1] + 1);
+        }
+    }
+
+    let mut alignment = Vec::with_capacity(n.max(m));
+    let (mut i, mut j) = (n, m);
+    while i > 0 || j > 0 {
+        if i > 0 && j > 0 && cost[i][j] == cost[i - 1][j - 1] + usize::from(reference[i - 1] != hypothesis[j - 1]) {
+            alignment.push(if reference[i - 1] == hypothesis[j - 1] {
+                AlignedToken::Match { token: reference[i - 1].clone() }
+            } else {
+                AlignedToken::Substitute { reference: reference[i - 1].clone(), hypothesis: hypothesis[j - 1].clone() }
+            });
+            i -= 1;
+            j -= 1;
+        } else if i > 0 && cost[i][j] == cost[i - 1][j] + 1 {
+            alignment.push(AlignedToken::Delete { reference: reference[i - 1].clone() });
+            i -= 1;
+        } else {
+            alignment.push(AlignedToken::Insert { hypothesis: hypothesis[j - 1].clone() });
+            j -= 1;
+        }
+    }
+    alignment.reverse();
+    alignment
+}
+
+pub fn error_rate(alignment: &[AlignedToken]) -> ErrorRate {
+    let mut rate = ErrorRate { rate: 0.0, substitutions: 0, deletions: 0, insertions: 0, reference_length: 0 };
+    for token in alignment {
+        match token {
+            AlignedToken::Match { .. } => rate.reference_length += 1,
+            AlignedToken::Substitute { .. } => {
+                rate.substitutions += 1;
+                rate.reference_length += 1;
+            }
+            AlignedToken::Delete { .. } => {
+                rate.deletions += 1;
+                rate.reference_length += 1;
+            }
+            AlignedToken::Insert { .. } => rate.insertions += 1,
+        }
+    }
+    let errors = rate.substitutions + rate.deletions + rate.insertions;
+    // 参考为空时，只要识别出任何内容即视为全错
+    rate.rate = if rate.reference_length == 0 {
+        if errors == 0 { 0.0 } else { 1.0 }
+    } else {
+        errors as f64 / rate.reference_length as f64
+    };
+    rate
+}
+
+pub fn compute_wer(hypothesis: &str, reference: &str) -> ErrorRate {
+    error_rate(&align(&word_tokens(hypothesis), &word_tokens(reference)))
+}
+
+pub fn compute_cer(hypothesis: &str, reference: &str) -> ErrorRate {
+    error_rate(&align(&char_tokens(hypothesis), &char_tokens(reference)))
+}
+
+pub fn evaluate(hypothesis: &str, reference: &str) -> EvaluationReport {
+    EvaluationReport {
+        wer: compute_wer(hypothesis, reference),
+        cer: compute_cer(hypothesis, reference),
+        alignment: align(&word_tokens(hypothesis), &word_tokens(reference)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_rate(rate: &ErrorRate, expected: (usize, usize, usize, usize)) {
+        assert_eq!((rate.substitutions, rate.deletions, rate.insertions, rate.reference_length), expected);
+        let (s, d, i, n) = expected;
+        assert!((rate.rate - (s + d + i) as f64 / n as f64).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_english_wer() {
+        // sat→sit 替换，删除一个 the
+        let wer = compute_wer("the cat sit on mat", "The cat sat on the mat.");
+        assert_rate(&wer, (1, 1, 0, 6));
+
+        let wer = compute_wer("a b x c", "a b c");
+        assert_rate(&wer, (0, 0, 1, 3));
+
+        assert_eq!(compute_wer("hello world", "Hello, world!").rate, 0.0);
+    }
+
+    #[test]
+    fn test_cjk_error_rates() {
+        // 气→汽 替换，删除“很”，标点不计
+        let cer = compute_cer("今天天汽好", "今天，天气很好。");
+        assert_rate(&cer, (1, 1, 0, 6));
+
+        // 中英混排：汉字逐字成词，英文按单词
+        assert_eq!(word_tokens("我用 Whisper 模型"), vec!["我", "用", "whisper", "模", "型"]);
+        let wer = compute_wer("我用 whisper 模型转写", "我用 Whisper 模型");
+        assert_rate(&wer, (0, 0, 2, 5));
+    }
+
+    #[test]
+    fn test_alignment_diff() {
+        let report = evaluate("the cat sit on mat", "the cat sat on the mat");
+        assert_eq!(report.alignment, vec![
+            AlignedToken::Match { token: "the".into() },
+            AlignedToken::Match { token: "cat".into() },
+            AlignedToken::Substitute { reference: "sat".into(), hypothesis: "sit".into() },
+            AlignedToken::Match { token: "on".into() },
+            AlignedToken::Delete { reference: "the".into() },
+            AlignedToken::Match { token: "mat".into() },
+        ]);
+        assert_eq!(compute_wer("", "").rate, 0.0);
+        assert_eq!(compute_wer("多余", "").rate, 1.0);
+    }
+}
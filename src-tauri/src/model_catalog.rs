@@ -0,0 +1,131 @@
+// model_catalog.rs - 可下载的 Whisper 模型目录：内置版本化 JSON，可被应用数据目录下的 model_catalog.json 覆盖
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use tauri::command;
+
+use crate::errors::StenoError;
+
+const EMBEDDED_CATALOG: &str = include_str!("model_catalog.json");
+pub const CATALOG_FILE_NAME: &str = "model_catalog.json";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CatalogEntry {
+    pub name: String, // 文件名（不含 .bin），与已安装模型的 name 一致
+    pub display_name: String,
+    pub size: u64, // 字节
+    pub languages: String, // "multilingual" 或语言代码
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub quality: String,
+    pub urls: Vec<String>, // 按优先级排列的下载地址，第一个失败时可换用镜像
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelCatalog {
+    pub version: u32,
+    pub models: Vec<CatalogEntry>,
+}
+
+impl ModelCatalog {
+    pub fn find(&self, name: &str) -> Option<&CatalogEntry> {
+        self.models.iter().find(|entry| entry.name == name)
+    }
+}
+
+/// 解析并校验目录：每个模型都需要名称和至少一个下载地址
+pub fn parse_catalog(json: &str) -> Result<ModelCatalog, String> {
+    let catalog: ModelCatalog = serde_json::from_str(json).map_err(|e| format!("模型目录格式无效: {}", e))?;
+    for entry in &catalog.models {
+        if entry.name.trim().is_empty() {
+            return Err("模型目录中存在没有名称的条目".to_string());
+        }
+        if !entry.urls.iter().any(|url| !url.trim().is_empty()) {
+            return Err(format!("模型 {} 没有下载地址", entry.name));
+        }
+    }
+    Ok(catalog)
+}
+
+pub fn embedded_catalog() -> ModelCatalog {
+    parse_catalog(EMBEDDED_CATALOG).expect("内置模型目录无效")
+}
+
+/// 覆盖文件有效且版本不低于内置目录时使用覆盖文件，避免应用升级后旧文件遮住新目录
+pub fn load_catalog(override_path: &Path) -> ModelCatalog {
+    let embedded = embedded_catalog();
+    let Ok(json) = fs::read_to_string(override_path) else {
+        return embedded;
+    };
+    match parse_catalog(&json) {
+        Ok(catalog) if catalog.version >= embedded.version => catalog,
+        Ok(catalog) => {
+            log::info!("模型目录覆盖文件版本 {} 低于内置版本 {}，使用内置目录", catalog.version, embedded.version);
+            embedded
+        }
+        Err(e) => {
+            log::warn!("⚠️ 忽略模型目录覆盖文件 {}: {}", override_path.display(), e);
+            embedded
+        }
+    }
+}
+
+pub fn load_app_catalog(app_handle: &tauri::AppHandle) -> ModelCatalog {
+    match crate::data_dir::resolve_data_dir(app_handle) {
+        Ok(dir) => load_catalog(&dir.join(CATALOG_FILE_NAME)),
+        Err(_) => embedded_catalog(),
+    }
+}
+
+#[command]
+pub async fn get_model_catalog(app_handle: tauri::AppHandle) -> Result<ModelCatalog, StenoError> {
+    Ok(load_app_catalog(&app_handle))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_embedded_catalog_entries_have_urls() {
+        let catalog = embedded_catalog();
+        assert!(!catalog.models.is_empty());
+        for entry in &catalog.models {
+            assert!(!entry.urls.is_empty(), "{} 没有下载地址", entry.name);
+            assert!(entry.urls.iter().all(|url| url.starts_with("https://") && url.ends_with(".bin")));
+            assert!(entry.size > 0);
+        }
+        assert_eq!(catalog.find("ggml-base").unwrap().languages, "multilingual");
+    }
+
+    #[test]
+    fn test_parse_rejects_entries_without_url() {
+        let json = r#"{ "version": 1, "models": [
+            { "name": "ggml-custom", "display_name": "Custom", "size": 1, "languages": "zh", "urls": [] }
+        ] }"#;
+        assert!(parse_catalog(json).unwrap_err().contains("ggml-custom"));
+        assert!(parse_catalog("[]").is_err());
+    }
+
+    #[test]
+    fn test_override_file_by_version() {
+        let path = std::env::temp_dir().join(format!("steno_catalog_test_{}.json", std::process::id()));
+        let custom = |version: u32| format!(r#"{{ "version": {}, "models": [
+            {{ "name": "ggml-custom", "display_name": "Custom", "size": 10, "languages": "zh",
+               "urls": ["https://example.com/ggml-custom.bin"] }}
+        ] }}"#, version);
+
+        fs::write(&path, custom(embedded_catalog().version)).unwrap();
+        let catalog = load_catalog(&path);
+        assert_eq!(catalog.models.len(), 1);
+        assert_eq!(catalog.models[0].description, "");
+
+        fs::write(&path, custom(0)).unwrap();
+        assert!(load_catalog(&path).find("ggml-base").is_some());
+
+        let _ = fs::remove_file(&path);
+        assert!(load_catalog(&path).find("ggml-base").is_some());
+    }
+}
@@ -1,11 +1,13 @@
 use std::fs::{self, File};
-use std::io::{self, Write};
+use std::io::{self, Read, Write};
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 use serde::{Deserialize, Serialize};
-use tauri::{command, Emitter, WebviewWindow};
+use tauri::{command, Emitter, Manager, WebviewWindow};
 use reqwest::Client;
 
+use crate::errors::StenoError;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ModelInfo {
     pub name: String,
@@ -22,13 +24,77 @@ pub struct StorageInfo {
     pub available_space: u64,
 }
 
+/// 下载前的空间检查结果
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum DownloadPlan {
+    Ok { required: u64, available: u64 },
+    // 删除建议的模型后即可腾出足够空间
+    NeedsCleanup { required: u64, available: u64, suggestions: Vec<ModelInfo> },
+    // 删除所有可删除的模型也不够
+    Insufficient { required: u64, available: u64 },
+}
+
+/// 下载时额外预留的空间，避免磁盘被完全写满
+const DOWNLOAD_SPACE_MARGIN: u64 = 200 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportMethod {
+    Move,
+    Copy,
+}
+
+/// 流式复制时每块的大小
+const IMPORT_CHUNK_SIZE: usize = 1024 * 1024;
+
 #[derive(Debug, Clone, Serialize)]
 pub struct DownloadProgress {
     pub model_name: String,
     pub downloaded: u64,
+    /// 0 表示大小未知（如分块传输编码）
     pub total: u64,
     pub speed: f64,
     pub status: String,
+    /// 完成百分比（0-100），大小未知时为 None
+    pub percent: Option<f64>,
+    /// 按平滑后的速度估算的剩余秒数，大小未知或速度为 0 时为 None
+    pub eta_secs: Option<u64>,
+}
+
+impl DownloadProgress {
+    pub fn new(model_name: &str, downloaded: u64, total: u64, speed: f64, status: &str) -> Self {
+        Self {
+            model_name: model_name.to_string(),
+            downloaded,
+            total,
+            speed,
+            status: status.to_string(),
+            percent: progress_percent(downloaded, total),
+            eta_secs: progress_eta_secs(downloaded, total, speed),
+        }
+    }
+}
+
+fn progress_percent(downloaded: u64, total: u64) -> Option<f64> {
+    if total == 0 {
+        return None;
+    }
+    Some((downloaded as f64 / total as f64 * 100.0).min(100.0))
+}
+
+fn progress_eta_secs(downloaded: u64, total: u64, speed: f64) -> Option<u64> {
+    if total == 0 {
+        return None;
+    }
+    let remaining = total.saturating_sub(downloaded);
+    if remaining == 0 {
+        return Some(0);
+    }
+    if !speed.is_finite() || speed <= 0.0 {
+        return None;
+    }
+    Some((remaining as f64 / speed).ceil() as u64)
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -47,6 +113,111 @@ pub struct LocalModel {
     pub valid: bool,
 }
 
+/// whisper.cpp 模型文件头中的超参数
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct GgmlHeader {
+    pub n_vocab: i32,
+    pub n_audio_layer: i32,
+    pub n_text_layer: i32,
+    pub n_mels: i32,
+    pub ftype: i32,
+}
+
+impl GgmlHeader {
+    /// whisper.cpp 按词表大小区分：多语言模型为 51865 及以上，仅英文（.en）模型为 51864
+    pub fn is_multilingual(&self) -> bool {
+        self.n_vocab >= 51865
+    }
+}
+
+/// 模型与识别语言的兼容性；不兼容时以 ModelLanguageMismatch 错误返回
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum LanguageCompatibility {
+    Compatible,
+    Warning { message: String },
+}
+
+// whisper.cpp 模型文件的魔数 "ggml"（小端序存储）
+const GGML_MAGIC: u32 = 0x6767_6d6c;
+// 魔数后依次为 11 个 i32 超参数
+const GGML_HPARAM_COUNT: usize = 11;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ModelMissingEvent {
+    pub model_path: String,
+    pub code: String,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ModelReadyEvent {
+    pub model_path: String,
+    pub warmed_up: bool, // 预热失败时模型仍可用，只是首次识别会慢一些
+    pub duration_ms: u64,
+}
+
+/// 最近一次的模型状态；启动检查在前端注册监听之前就已完成，前端挂载后通过 get_model_status 补查
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum ModelStatus {
+    Unchecked,
+    Missing(ModelMissingEvent),
+    WarmingUp { model_path: String },
+    Ready(ModelReadyEvent),
+}
+
+pub struct ModelStatusState(Mutex<ModelStatus>);
+
+impl Default for ModelStatusState {
+    fn default() -> Self {
+        Self(Mutex::new(ModelStatus::Unchecked))
+    }
+}
+
+impl ModelStatusState {
+    pub fn set(&self, status: ModelStatus) {
+        *self.0.lock().unwrap_or_else(|e| e.into_inner()) = status;
+    }
+
+    pub fn get(&self) -> ModelStatus {
+        self.0.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+}
+
+/// 记录模型缺失状态并发送 model_missing 事件
+pub fn report_model_missing(app_handle: &tauri::AppHandle, event: ModelMissingEvent) {
+    app_handle.state::<ModelStatusState>().set(ModelStatus::Missing(event.clone()));
+    if let Err(e) = app_handle.emit("model_missing", event) {
+        log::warn!("⚠️ 发送 model_missing 事件失败: {}", e);
+    }
+}
+
+/// 在后台线程预热刚加载的模型，完成后发送 model_ready 事件，不阻塞调用方
+pub fn warm_up_model(app_handle: tauri::AppHandle, model_path: String) {
+    app_handle.state::<ModelStatusState>().set(ModelStatus::WarmingUp { model_path: model_path.clone() });
+    std::thread::spawn(move || {
+        let started = std::time::Instant::now();
+        let warmed_up = match app_handle.state::<crate::WhisperContextState>().warm_up() {
+            Ok(()) => true,
+            Err(e) => {
+                log::warn!("⚠️ 模型预热失败: {}", e);
+                false
+            }
+        };
+        let event = ModelReadyEvent {
+            model_path,
+            warmed_up,
+            duration_ms: started.elapsed().as_millis() as u64,
+        };
+        log::info!("模型已就绪: {} (预热 {} ms)", event.model_path, event.duration_ms);
+        app_handle.state::<ModelStatusState>().set(ModelStatus::Ready(event.clone()));
+        if let Err(e) = app_handle.emit("model_ready", event) {
+            log::warn!("⚠️ 发送 model_ready 事件失败: {}", e);
+        }
+    });
+}
+
 pub struct ModelManager {
     pub config: Arc<Mutex<ModelConfig>>,
     pub client: Client,
@@ -247,10 +418,9 @@ impl ModelManager {
             }
         }
 
-        // 获取磁盘总空间和可用空间 (简化版本，使用固定值)
-        // 在实际应用中，可以使用系统API获取真实的磁盘空间
-        let total_space = 100 * 1024 * 1024 * 1024u64; // 100GB 假设总空间
-        let available_space = total_space - used_space; // 简化计算
+        // 模型目录所在磁盘的总空间和剩余空间
+        let total_space = fs2::total_space(&models_dir)?;
+        let available_space = fs2::available_space(&models_dir)?;
 
         Ok(StorageInfo {
             used_space,
@@ -268,13 +438,7 @@ impl ModelManager {
         let file_path = models_dir.join(format!("{}.bin", model_name));
         
         // 发送开始下载事件
-        let _ = window.emit("model_download_progress", DownloadProgress {
-            model_name: model_name.to_string(),
-            downloaded: 0,
-            total: 0,
-            speed: 0.0,
-            status: "downloading".to_string(),
-        });
+        let _ = window.emit("model_download_progress", DownloadProgress::new(model_name, 0, 0, 0.0, "downloading"));
 
         let response = self.client.get(url).send().await
             .map_err(|e| format!("请求失败: {}", e))?;
@@ -288,6 +452,7 @@ impl ModelManager {
             .map_err(|e| format!("创建文件失败: {}", e))?;
 
         let mut downloaded = 0u64;
+        let mut last_downloaded = 0u64;
         let mut last_update = std::time::Instant::now();
         let mut speed_samples = Vec::new();
         
@@ -304,7 +469,7 @@ impl ModelManager {
             let now = std::time::Instant::now();
             if now.duration_since(last_update).as_millis() >= 500 { // 每500ms更新一次
                 let duration = now.duration_since(last_update).as_secs_f64();
-                let speed = chunk.len() as f64 / duration;
+                let speed = (downloaded - last_downloaded) as f64 / duration;
                 
                 speed_samples.push(speed);
                 if speed_samples.len() > 10 {
@@ -313,39 +478,28 @@ impl ModelManager {
                 
                 let avg_speed = speed_samples.iter().sum::<f64>() / speed_samples.len() as f64;
                 
-                let _ = window.emit("model_download_progress", DownloadProgress {
-                    model_name: model_name.to_string(),
-                    downloaded,
-                    total: total_size,
-                    speed: avg_speed,
-                    status: "downloading".to_string(),
-                });
+                let _ = window.emit("model_download_progress", DownloadProgress::new(model_name, downloaded, total_size, avg_speed, "downloading"));
                 
                 last_update = now;
+                last_downloaded = downloaded;
             }
         }
 
         // 下载完成
-        let _ = window.emit("model_download_progress", DownloadProgress {
-            model_name: model_name.to_string(),
-            downloaded,
-            total: total_size,
-            speed: 0.0,
-            status: "completed".to_string(),
-        });
+        let _ = window.emit("model_download_progress", DownloadProgress::new(model_name, downloaded, total_size, 0.0, "completed"));
 
         Ok(())
     }
 
-    pub fn switch_model(&self, model_path: &str) -> Result<(), String> {
+    pub fn switch_model(&self, model_path: &str) -> Result<(), StenoError> {
         let path = Path::new(model_path);
         if !path.exists() {
-            return Err("模型文件不存在".to_string());
+            return Err(StenoError::ModelMissing("模型文件不存在".to_string()));
         }
 
         let model_name = path.file_stem()
             .and_then(|s| s.to_str())
-            .ok_or("无效的模型文件名")?;
+            .ok_or_else(|| StenoError::ModelInvalid("无效的模型文件名".to_string()))?;
 
         let mut config = self.config.lock().unwrap();
         config.current_model = model_name.to_string();
@@ -356,29 +510,29 @@ impl ModelManager {
         Ok(())
     }
 
-    pub fn delete_model(&self, model_path: &str) -> Result<(), String> {
+    pub fn delete_model(&self, model_path: &str) -> Result<(), StenoError> {
         let path = Path::new(model_path);
         
         // 检查是否是当前使用的模型
         let config = self.config.lock().unwrap();
         if config.model_path == path {
-            return Err("无法删除当前使用的模型".to_string());
+            return Err(StenoError::InvalidArgument("无法删除当前使用的模型".to_string()));
         }
         drop(config);
 
         if !path.exists() {
-            return Err("模型文件不存在".to_string());
+            return Err(StenoError::ModelMissing("模型文件不存在".to_string()));
         }
 
-        fs::remove_file(path).map_err(|e| format!("删除文件失败: {}", e))?;
+        fs::remove_file(path).map_err(|e| StenoError::ModelIo(format!("删除文件失败: {}", e)))?;
         Ok(())
     }
 
-    pub fn scan_local_models(&self, folder_path: &str) -> Result<Vec<LocalModel>, String> {
+    pub fn scan_local_models(&self, folder_path: &str) -> Result<Vec<LocalModel>, StenoError> {
         let folder = Path::new(folder_path);
         
         if !folder.exists() || !folder.is_dir() {
-            return Err("指定的路径不存在或不是文件夹".to_string());
+            return Err(StenoError::InvalidArgument("指定的路径不存在或不是文件夹".to_string()));
         }
 
         let mut models = Vec::new();
@@ -426,6 +580,10 @@ impl ModelManager {
                 return false;
             }
 
+            if read_ggml_header(file_path).is_err() {
+                return false;
+            }
+
             // 检查文件名是否符合whisper模型命名规范
             if let Some(file_name) = file_path.file_name().and_then(|n| n.to_str()) {
                 return file_name.starts_with("ggml-") && file_name.ends_with(".bin");
@@ -435,17 +593,20 @@ impl ModelManager {
         false
     }
 
-    pub fn import_local_model(&self, model_path: &str, model_name: &str) -> Result<(), String> {
+    /// 导入本地模型：同一磁盘上可直接移动，否则流式复制并通过 on_progress(已复制, 总大小) 报告进度
+    pub fn import_local_model<F>(&self, model_path: &str, model_name: &str, move_file: bool, on_progress: F) -> Result<ImportMethod, StenoError>
+    where
+        F: FnMut(u64, u64),
+    {
         let source_path = Path::new(model_path);
-        
-        if !source_path.exists() {
-            return Err("源文件不存在".to_string());
-        }
+
+        // 只导入文件头有效的 GGML 模型
+        validate_model_file(source_path)?;
 
         // 获取模型目录
         let models_dir = get_models_directory();
         if !models_dir.exists() {
-            fs::create_dir_all(&models_dir).map_err(|e| format!("创建模型目录失败: {}", e))?;
+            fs::create_dir_all(&models_dir).map_err(|e| StenoError::ModelIo(format!("创建模型目录失败: {}", e)))?;
         }
 
         // 目标文件路径
@@ -453,14 +614,11 @@ impl ModelManager {
         
         // 检查是否已存在同名文件
         if target_path.exists() {
-            return Err(format!("模型 {} 已存在", model_name));
+            return Err(StenoError::InvalidArgument(format!("模型 {} 已存在", model_name)));
         }
 
-        // 复制文件
-        fs::copy(source_path, &target_path)
-            .map_err(|e| format!("复制文件失败: {}", e))?;
-
-        Ok(())
+        import_file(source_path, &target_path, move_file, on_progress)
+            .map_err(|e| StenoError::ModelIo(format!("导入模型失败: {}", e)))
     }
 
     // 获取当前模型信息
@@ -476,11 +634,182 @@ impl ModelManager {
     }
 }
 
+/// 读取并校验 GGML 模型文件头
+pub fn read_ggml_header(path: &Path) -> Result<GgmlHeader, StenoError> {
+    let mut file = File::open(path)
+        .map_err(|e| StenoError::ModelIo(format!("无法打开模型文件 {}: {}", path.display(), e)))?;
+    let mut bytes = [0u8; 4 * (GGML_HPARAM_COUNT + 1)];
+    file.read_exact(&mut bytes)
+        .map_err(|_| StenoError::ModelInvalid(format!("模型文件过短: {}", path.display())))?;
+
+    let field = |index: usize| {
+        let offset = index * 4;
+        [bytes[offset], bytes[offset + 1], bytes[offset + 2], bytes[offset + 3]]
+    };
+    if u32::from_le_bytes(field(0)) != GGML_MAGIC {
+        return Err(StenoError::ModelInvalid(format!("不是有效的 GGML 模型文件: {}", path.display())));
+    }
+
+    // 超参数顺序：n_vocab, n_audio_ctx, n_audio_state, n_audio_head, n_audio_layer,
+    // n_text_ctx, n_text_state, n_text_head, n_text_layer, n_mels, ftype
+    let hparam = |index: usize| i32::from_le_bytes(field(index + 1));
+    let header = GgmlHeader {
+        n_vocab: hparam(0),
+        n_audio_layer: hparam(4),
+        n_text_layer: hparam(8),
+        n_mels: hparam(9),
+        ftype: hparam(10),
+    };
+    if header.n_vocab <= 0 || header.n_audio_layer <= 0 || header.n_text_layer <= 0 || header.n_mels <= 0 {
+        return Err(StenoError::ModelInvalid(format!("模型文件头参数无效: {}", path.display())));
+    }
+    Ok(header)
+}
+
+/// 比较下载所需空间与剩余空间；不足时从非当前模型中按体积从大到小挑选，删除尽量少的模型
+pub fn plan_download_space(model_name: &str, model_size: u64, available: u64, installed: &[ModelInfo]) -> DownloadPlan {
+    let required = model_size + DOWNLOAD_SPACE_MARGIN;
+    if available >= required {
+        return DownloadPlan::Ok { required, available };
+    }
+
+    let mut candidates: Vec<&ModelInfo> = installed.iter()
+        .filter(|model| !model.is_current && model.name != model_name)
+        .collect();
+    candidates.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.name.cmp(&b.name)));
+
+    let mut freed = available;
+    let mut suggestions = Vec::new();
+    for model in candidates {
+        if freed >= required {
+            break;
+        }
+        freed += model.size;
+        suggestions.push(model.clone());
+    }
+
+    if freed >= required {
+        DownloadPlan::NeedsCleanup { required, available, suggestions }
+    } else {
+        DownloadPlan::Insufficient { required, available }
+    }
+}
+
+/// 源文件与目标目录是否在同一磁盘；无法判断时返回 None
+#[cfg(unix)]
+fn same_volume(source: &Path, target_dir: &Path) -> Option<bool> {
+    use std::os::unix::fs::MetadataExt;
+    Some(fs::metadata(source).ok()?.dev() == fs::metadata(target_dir).ok()?.dev())
+}
+
+#[cfg(not(unix))]
+fn same_volume(_source: &Path, _target_dir: &Path) -> Option<bool> {
+    None
+}
+
+/// 只有要求移动时才考虑重命名；跨磁盘的重命名会失败，直接复制。无法判断时先尝试重命名
+pub fn choose_import_method(move_requested: bool, same_volume: Option<bool>) -> ImportMethod {
+    match (move_requested, same_volume) {
+        (true, Some(true)) | (true, None) => ImportMethod::Move,
+        _ => ImportMethod::Copy,
+    }
+}
+
+fn copy_with_progress<F>(source: &Path, target: &Path, on_progress: &mut F) -> io::Result<()>
+where
+    F: FnMut(u64, u64),
+{
+    let total = fs::metadata(source)?.len();
+    let mut reader = File::open(source)?;
+    let mut writer = File::create(target)?;
+    let mut buffer = vec![0u8; IMPORT_CHUNK_SIZE];
+    let mut copied = 0u64;
+    on_progress(0, total);
+    loop {
+        let read = reader.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        writer.write_all(&buffer[..read])?;
+        copied += read as u64;
+        on_progress(copied, total);
+    }
+    writer.sync_all()
+}
+
+fn import_file<F>(source: &Path, target: &Path, move_file: bool, mut on_progress: F) -> io::Result<ImportMethod>
+where
+    F: FnMut(u64, u64),
+{
+    let target_dir = target.parent().unwrap_or(Path::new("."));
+    if choose_import_method(move_file, same_volume(source, target_dir)) == ImportMethod::Move {
+        match fs::rename(source, target) {
+            Ok(()) => {
+                let size = fs::metadata(target)?.len();
+                on_progress(size, size);
+                return Ok(ImportMethod::Move);
+            }
+            Err(e) => log::warn!("⚠️ 移动模型失败，改为复制: {}", e),
+        }
+    }
+
+    // 先写入临时文件，完成后再改名，避免中断时留下不完整的模型
+    let partial = target.with_extension("partial");
+    if let Err(e) = copy_with_progress(source, &partial, &mut on_progress).and_then(|_| fs::rename(&partial, target)) {
+        let _ = fs::remove_file(&partial);
+        return Err(e);
+    }
+
+    if move_file {
+        // 跨磁盘移动：复制成功后删除源文件
+        fs::remove_file(source)?;
+    }
+    Ok(ImportMethod::Copy)
+}
+
+/// 仅英文模型只能识别英文：指定其他语言时报错，自动检测时给出警告
+pub fn check_language_compatibility(multilingual: bool, language: &str) -> Result<LanguageCompatibility, StenoError> {
+    if multilingual {
+        return Ok(LanguageCompatibility::Compatible);
+    }
+    match language {
+        "en" => Ok(LanguageCompatibility::Compatible),
+        "" | "auto" => Ok(LanguageCompatibility::Warning {
+            message: "当前为仅英文模型，自动检测只能识别英文".to_string(),
+        }),
+        other => Err(StenoError::ModelLanguageMismatch(format!(
+            "当前为仅英文模型，无法识别语言 {}，请切换到多语言模型", other
+        ))),
+    }
+}
+
+pub fn check_model_language(model_path: &Path, language: &str) -> Result<LanguageCompatibility, StenoError> {
+    let header = read_ggml_header(model_path)?;
+    check_language_compatibility(header.is_multilingual(), language)
+}
+
+/// 校验模型文件存在且文件头有效
+pub fn validate_model_file(path: &Path) -> Result<GgmlHeader, StenoError> {
+    if !path.exists() {
+        return Err(StenoError::ModelMissing(format!("模型文件不存在: {}", path.display())));
+    }
+    read_ggml_header(path)
+}
+
+/// 模型就绪状态：上下文已加载即可识别；未加载时返回模型文件的具体问题
+pub fn check_model_ready(model_path: &Path, context_loaded: bool) -> Result<(), StenoError> {
+    if context_loaded {
+        return Ok(());
+    }
+    validate_model_file(model_path)?;
+    Err(StenoError::ModelInvalid(format!("模型加载失败: {}", model_path.display())))
+}
+
 // Tauri 命令
 #[command]
 pub async fn list_installed_models(
     model_manager: tauri::State<'_, Arc<Mutex<ModelManager>>>,
-) -> Result<Vec<ModelInfo>, String> {
+) -> Result<Vec<ModelInfo>, StenoError> {
     let manager = model_manager.lock().unwrap();
     Ok(manager.scan_installed_models())
 }
@@ -488,9 +817,23 @@ pub async fn list_installed_models(
 #[command] 
 pub async fn get_storage_info(
     model_manager: tauri::State<'_, Arc<Mutex<ModelManager>>>,
-) -> Result<StorageInfo, String> {
+) -> Result<StorageInfo, StenoError> {
     let manager = model_manager.lock().unwrap();
-    manager.get_storage_info().map_err(|e| e.to_string())
+    manager.get_storage_info().map_err(|e| StenoError::ModelIo(e.to_string()))
+}
+
+#[command]
+pub async fn plan_download(
+    app_handle: tauri::AppHandle,
+    model_manager: tauri::State<'_, Arc<Mutex<ModelManager>>>,
+    model_name: String,
+) -> Result<DownloadPlan, StenoError> {
+    let catalog = crate::model_catalog::load_app_catalog(&app_handle);
+    let entry = catalog.find(&model_name)
+        .ok_or_else(|| StenoError::NotFound(format!("模型目录中没有 {}", model_name)))?;
+    let manager = model_manager.lock().unwrap();
+    let storage = manager.get_storage_info().map_err(|e| StenoError::ModelIo(e.to_string()))?;
+    Ok(plan_download_space(&model_name, entry.size, storage.available_space, &manager.scan_installed_models()))
 }
 
 #[command]
@@ -499,7 +842,7 @@ pub async fn download_model(
     model_manager: tauri::State<'_, Arc<Mutex<ModelManager>>>,
     model_name: String,
     url: String,
-) -> Result<(), String> {
+) -> Result<(), StenoError> {
     let manager = {
         let guard = model_manager.lock().unwrap();
         ModelManager {
@@ -508,30 +851,39 @@ pub async fn download_model(
         }
     };
     
-    manager.download_model(&window, &model_name, &url).await?;
+    manager.download_model(&window, &model_name, &url).await.map_err(StenoError::ModelIo)?;
     Ok(())
 }
 
 #[command]
 pub async fn switch_model(
+    app_handle: tauri::AppHandle,
     model_manager: tauri::State<'_, Arc<Mutex<ModelManager>>>,
     whisper_context: tauri::State<'_, crate::WhisperContextState>,
     model_path: String,
-) -> Result<(), String> {
+    language: Option<String>,
+) -> Result<LanguageCompatibility, StenoError> {
+    // 传入当前识别语言时，先确认模型支持该语言再切换
+    let compatibility = match language.as_deref() {
+        Some(language) => check_model_language(Path::new(&model_path), language)?,
+        None => LanguageCompatibility::Compatible,
+    };
+
     let manager = model_manager.lock().unwrap();
     manager.switch_model(&model_path)?;
     
     // 重新初始化whisper上下文
-    whisper_context.reinitialize(&model_path)?;
+    whisper_context.reinitialize(&model_path).map_err(StenoError::ModelInvalid)?;
+    warm_up_model(app_handle, model_path);
     
-    Ok(())
+    Ok(compatibility)
 }
 
 #[command]
 pub async fn delete_model(
     model_manager: tauri::State<'_, Arc<Mutex<ModelManager>>>,
     model_path: String,
-) -> Result<(), String> {
+) -> Result<(), StenoError> {
     let manager = model_manager.lock().unwrap();
     manager.delete_model(&model_path)
 }
@@ -540,25 +892,283 @@ pub async fn delete_model(
 pub async fn scan_local_models(
     model_manager: tauri::State<'_, Arc<Mutex<ModelManager>>>,
     folder_path: String,
-) -> Result<Vec<LocalModel>, String> {
+) -> Result<Vec<LocalModel>, StenoError> {
     let manager = model_manager.lock().unwrap();
     manager.scan_local_models(&folder_path)
 }
 
 #[command]
 pub async fn import_local_model(
+    window: WebviewWindow,
     model_manager: tauri::State<'_, Arc<Mutex<ModelManager>>>,
     model_path: String,
     model_name: String,
-) -> Result<(), String> {
-    let manager = model_manager.lock().unwrap();
-    manager.import_local_model(&model_path, &model_name)
+    move_file: Option<bool>,
+) -> Result<ImportMethod, StenoError> {
+    let manager = {
+        let guard = model_manager.lock().unwrap();
+        ModelManager {
+            config: guard.config.clone(),
+            client: guard.client.clone(),
+        }
+    };
+
+    // 大文件复制耗时较长，放到阻塞线程中执行，进度与下载共用事件
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut last_update = std::time::Instant::now();
+        let mut last_copied = 0u64;
+        let method = manager.import_local_model(&model_path, &model_name, move_file.unwrap_or(false), |copied, total| {
+            let elapsed = last_update.elapsed().as_secs_f64();
+            if copied < total && elapsed < 0.5 {
+                return;
+            }
+            let speed = if elapsed > 0.0 { (copied - last_copied) as f64 / elapsed } else { 0.0 };
+            let _ = window.emit("model_download_progress", DownloadProgress::new(&model_name, copied, total, speed, "importing"));
+            last_update = std::time::Instant::now();
+            last_copied = copied;
+        })?;
+        let size = fs::metadata(get_models_directory().join(&model_name)).map(|m| m.len()).unwrap_or(0);
+        let _ = window.emit("model_download_progress", DownloadProgress::new(&model_name, size, size, 0.0, "completed"));
+        Ok(method)
+    })
+    .await
+    .map_err(|e| StenoError::Internal(format!("导入任务异常: {}", e)))?
 }
 
 #[command]
 pub async fn get_current_model(
     model_manager: tauri::State<'_, Arc<Mutex<ModelManager>>>,
-) -> Result<Option<ModelInfo>, String> {
+) -> Result<Option<ModelInfo>, StenoError> {
     let manager = model_manager.lock().unwrap();
     Ok(manager.get_current_model())
-}
\ No newline at end of file
+}
+
+#[command]
+pub async fn is_model_ready(
+    model_manager: tauri::State<'_, Arc<Mutex<ModelManager>>>,
+    whisper_context: tauri::State<'_, crate::WhisperContextState>,
+) -> Result<bool, StenoError> {
+    let model_path = model_manager.lock().unwrap().get_current_model_path();
+    Ok(check_model_ready(&model_path, whisper_context.is_loaded()).is_ok())
+}
+
+/// 最近一次的模型状态，与 model_missing / model_ready 事件内容一致
+#[command]
+pub fn get_model_status(status: tauri::State<'_, ModelStatusState>) -> ModelStatus {
+    status.get()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_model(name: &str, magic: u32, hparams: [i32; GGML_HPARAM_COUNT]) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("steno_model_test_{}_{}.bin", name, std::process::id()));
+        let mut bytes = magic.to_le_bytes().to_vec();
+        for value in hparams {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        fs::write(&path, bytes).unwrap();
+        path
+    }
+
+    // tiny 模型的超参数
+    const TINY_HPARAMS: [i32; GGML_HPARAM_COUNT] = [51865, 1500, 384, 6, 4, 448, 384, 6, 4, 80, 1];
+
+    #[test]
+    fn test_read_valid_header() {
+        let path = write_model("valid", GGML_MAGIC, TINY_HPARAMS);
+        let header = read_ggml_header(&path).unwrap();
+        assert_eq!(header, GgmlHeader { n_vocab: 51865, n_audio_layer: 4, n_text_layer: 4, n_mels: 80, ftype: 1 });
+        fs::remove_file(path).ok();
+    }
+
+    const MB: u64 = 1024 * 1024;
+
+    fn installed(name: &str, size_mb: u64, is_current: bool) -> ModelInfo {
+        ModelInfo {
+            name: name.to_string(),
+            path: format!("/models/{}.bin", name),
+            size: size_mb * MB,
+            is_current,
+            display_name: name.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_plan_download_sufficient_space() {
+        let plan = plan_download_space("ggml-large-v3", 1500 * MB, 5000 * MB, &[]);
+        assert!(matches!(plan, DownloadPlan::Ok { required, .. } if required == 1500 * MB + DOWNLOAD_SPACE_MARGIN));
+    }
+
+    #[test]
+    fn test_plan_download_suggests_fewest_removals() {
+        let models = vec![
+            installed("ggml-large-v2", 3000, true), // 当前模型不建议删除
+            installed("ggml-small", 480, false),
+            installed("ggml-medium", 1500, false),
+            installed("ggml-base", 140, false),
+        ];
+        // 还差约 1200MB：删除 medium 一个就够
+        let plan = plan_download_space("ggml-large-v3", 1500 * MB, 500 * MB, &models);
+        match plan {
+            DownloadPlan::NeedsCleanup { suggestions, .. } => {
+                let names: Vec<&str> = suggestions.iter().map(|m| m.name.as_str()).collect();
+                assert_eq!(names, vec!["ggml-medium"]);
+            }
+            other => panic!("预期需要清理，实际为 {:?}", other),
+        }
+
+        // 还差约 1900MB：需要 medium 和 small
+        let plan = plan_download_space("ggml-large-v3", 1500 * MB, 0, &models);
+        match plan {
+            DownloadPlan::NeedsCleanup { suggestions, .. } => assert_eq!(suggestions.len(), 2),
+            other => panic!("预期需要清理，实际为 {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_plan_download_insufficient_even_after_cleanup() {
+        let models = vec![installed("ggml-large-v2", 3000, true), installed("ggml-base", 140, false)];
+        let plan = plan_download_space("ggml-large-v3", 3000 * MB, 100 * MB, &models);
+        assert!(matches!(plan, DownloadPlan::Insufficient { available, .. } if available == 100 * MB));
+
+        // 正在重新下载的同名模型不计入可删除空间
+        let models = vec![installed("ggml-large-v3", 3000, false)];
+        assert!(matches!(plan_download_space("ggml-large-v3", 3000 * MB, 0, &models), DownloadPlan::Insufficient { .. }));
+    }
+
+    #[test]
+    fn test_import_method_selection() {
+        assert_eq!(choose_import_method(true, Some(true)), ImportMethod::Move);
+        assert_eq!(choose_import_method(true, Some(false)), ImportMethod::Copy);
+        assert_eq!(choose_import_method(true, None), ImportMethod::Move);
+        assert_eq!(choose_import_method(false, Some(true)), ImportMethod::Copy);
+        assert_eq!(choose_import_method(false, None), ImportMethod::Copy);
+    }
+
+    #[test]
+    fn test_import_move_and_copy() {
+        let dir = std::env::temp_dir().join(format!("steno_import_test_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let source = write_model("import_source", GGML_MAGIC, TINY_HPARAMS);
+        let size = fs::metadata(&source).unwrap().len();
+
+        // 复制：源文件保留，进度到达总大小
+        let mut progress = Vec::new();
+        let copied = dir.join("copied.bin");
+        assert_eq!(import_file(&source, &copied, false, |done, total| progress.push((done, total))).unwrap(), ImportMethod::Copy);
+        assert!(source.exists());
+        assert_eq!(progress.last(), Some(&(size, size)));
+        assert_eq!(fs::read(&copied).unwrap(), fs::read(&source).unwrap());
+        assert!(!dir.join("copied.partial").exists());
+
+        // 同一磁盘上移动：重命名后源文件消失
+        let moved = dir.join("moved.bin");
+        assert_eq!(import_file(&source, &moved, true, |_, _| {}).unwrap(), ImportMethod::Move);
+        assert!(!source.exists());
+        assert_eq!(fs::metadata(&moved).unwrap().len(), size);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_language_compatibility_matrix() {
+        use LanguageCompatibility::*;
+        for language in ["zh", "en", "ja", "auto", ""] {
+            assert_eq!(check_language_compatibility(true, language).unwrap(), Compatible, "{}", language);
+        }
+        assert_eq!(check_language_compatibility(false, "en").unwrap(), Compatible);
+        assert!(matches!(check_language_compatibility(false, "auto").unwrap(), Warning { .. }));
+        assert!(matches!(check_language_compatibility(false, "").unwrap(), Warning { .. }));
+        for language in ["zh", "ja", "de"] {
+            assert_eq!(check_language_compatibility(false, language).unwrap_err().code(), "model_language_mismatch");
+        }
+
+        // 词表大小决定是否为多语言模型
+        let english_only = write_model("english_only", GGML_MAGIC, [51864, 1500, 384, 6, 4, 448, 384, 6, 4, 80, 1]);
+        assert!(check_model_language(&english_only, "zh").is_err());
+        let multilingual = write_model("multilingual", GGML_MAGIC, TINY_HPARAMS);
+        assert_eq!(check_model_language(&multilingual, "zh").unwrap(), Compatible);
+        fs::remove_file(english_only).ok();
+        fs::remove_file(multilingual).ok();
+    }
+
+    /// 需要真实模型：STENO_TEST_MODEL 指向一个小模型（如 ggml-tiny.bin）
+    #[cfg(feature = "whisper-integration-tests")]
+    #[test]
+    fn test_warm_up_tiny_model() {
+        let model = std::env::var("STENO_TEST_MODEL").expect("需要设置 STENO_TEST_MODEL");
+        let whisper_state = crate::WhisperContextState::new(&model).unwrap();
+        whisper_state.warm_up().unwrap();
+        // 预热后上下文保持可用，可再次推理
+        whisper_state.warm_up().unwrap();
+        assert!(crate::WhisperContextState::new_empty().warm_up().is_err());
+    }
+
+    #[test]
+    fn test_model_status_is_queryable_after_event() {
+        let state = ModelStatusState::default();
+        assert!(matches!(state.get(), ModelStatus::Unchecked));
+
+        state.set(ModelStatus::Missing(ModelMissingEvent {
+            model_path: "/models/ggml-base.bin".to_string(),
+            code: "model_missing".to_string(),
+            message: "模型文件不存在".to_string(),
+        }));
+        let json = serde_json::to_value(state.get()).unwrap();
+        assert_eq!(json["status"], "missing");
+        assert_eq!(json["code"], "model_missing");
+        assert_eq!(json["model_path"], "/models/ggml-base.bin");
+    }
+
+    #[test]
+    fn test_model_ready_states() {
+        let valid = write_model("ready", GGML_MAGIC, TINY_HPARAMS);
+        let corrupt = write_model("corrupt", 0x1234_5678, TINY_HPARAMS);
+        let missing = std::env::temp_dir().join("steno_model_test_missing.bin");
+
+        assert!(check_model_ready(&valid, true).is_ok());
+        // 文件有效但上下文未加载
+        assert_eq!(check_model_ready(&valid, false).unwrap_err().code(), "model_invalid");
+        assert_eq!(check_model_ready(&missing, false).unwrap_err().code(), "model_missing");
+        assert_eq!(check_model_ready(&corrupt, false).unwrap_err().code(), "model_invalid");
+
+        fs::remove_file(valid).ok();
+        fs::remove_file(corrupt).ok();
+    }
+
+    #[test]
+    fn test_download_progress_percent_and_eta() {
+        let progress = DownloadProgress::new("ggml-base", 25 * 1024 * 1024, 100 * 1024 * 1024, 5.0 * 1024.0 * 1024.0, "downloading");
+        assert_eq!(progress.percent, Some(25.0));
+        assert_eq!(progress.eta_secs, Some(15));
+
+        // 剩余时间向上取整
+        assert_eq!(progress_eta_secs(0, 10, 3.0), Some(4));
+        assert_eq!(progress_percent(1, 3).map(|p| (p * 100.0).round() / 100.0), Some(33.33));
+
+        // 速度未知时无法估算剩余时间，但百分比照常给出
+        let stalled = DownloadProgress::new("ggml-base", 50, 100, 0.0, "downloading");
+        assert_eq!(stalled.percent, Some(50.0));
+        assert_eq!(stalled.eta_secs, None);
+        assert_eq!(progress_eta_secs(50, 100, f64::NAN), None);
+
+        // 已完成（或服务器给出的大小偏小）时不超过 100%，剩余时间为 0
+        let done = DownloadProgress::new("ggml-base", 120, 100, 0.0, "completed");
+        assert_eq!(done.percent, Some(100.0));
+        assert_eq!(done.eta_secs, Some(0));
+    }
+
+    #[test]
+    fn test_download_progress_unknown_total() {
+        // 分块传输编码没有 Content-Length，total 为 0
+        let progress = DownloadProgress::new("ggml-base", 4096, 0, 1024.0, "downloading");
+        assert_eq!(progress.percent, None);
+        assert_eq!(progress.eta_secs, None);
+
+        let json = serde_json::to_value(&progress).unwrap();
+        assert!(json["percent"].is_null());
+        assert!(json["eta_secs"].is_null());
+    }
+}
@@ -1,6 +1,6 @@
 // optimal_realtime_processor.rs - 最优实时转录处理器
-use cpal::{Device, StreamConfig, SampleRate};
-use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::Device;
+use cpal::traits::StreamTrait;
 use std::sync::{Arc, Mutex, mpsc};
 use std::thread;
 use std::time::{Duration, Instant};
@@ -11,7 +11,12 @@ use crate::audio_processing::{AudioProcessingPipeline, SpeechSegment};
 use crate::layered_processor::{UnifiedProcessor, ProcessingEvent};
 use crate::context_processor::ContextAwareProcessor;
 use crate::result_manager::{ResultManager, ManagedTranscriptSegment, QualityReport};
+use crate::translation::{NoopTranslator, Translator, TranslationWorker};
 use crate::WhisperContextState;
+use crate::level_meter::LevelMeterConfig;
+use crate::capture_core::{self, CaptureFormat};
+use crate::errors::StenoError;
+use crate::model_management::{self, LanguageCompatibility, ModelManager};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OptimalRealtimeConfig {
@@ -22,6 +27,12 @@ pub struct OptimalRealtimeConfig {
     pub max_segment_duration: u64, // milliseconds
     pub buffer_duration: u64, // milliseconds
     pub initial_prompt: Option<String>, // 添加提示词支持
+    #[serde(default)]
+    pub hotwords: Vec<String>, // 自定义词汇，合并进初始提示词
+    #[serde(default)]
+    pub translation_target_language: Option<String>, // 最终结果的翻译目标语言，None 表示不翻译
+    #[serde(default)]
+    pub level_meter: LevelMeterConfig, // 音量表平滑参数
 }
 
 impl Default for OptimalRealtimeConfig {
@@ -34,6 +45,9 @@ impl Default for OptimalRealtimeConfig {
             max_segment_duration: 10000, // 10秒
             buffer_duration: 300000, // 5分钟
             initial_prompt: None, // 默认不使用提示词
+            hotwords: Vec::new(),
+            translation_target_language: None,
+            level_meter: LevelMeterConfig::default(),
         }
     }
 }
@@ -47,7 +61,9 @@ pub struct RealtimeTranscriptionEvent {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AudioLevelEvent {
-    pub level: f32,
+    pub level: f32,     // 0..1，由平滑后的 dBFS 映射
+    pub dbfs: f32,
+    pub peak_dbfs: f32,
     pub speech_probability: f32,
     pub timestamp: u64,
 }
@@ -75,7 +91,7 @@ pub struct ProcessingStatsEvent {
 /// 最优实时转录处理器
 pub struct OptimalRealtimeProcessor {
     device: Device,
-    config: StreamConfig,
+    capture_format: CaptureFormat,
     // 移除 stream，因为它不是 Send
     
     // 状态管理
@@ -87,6 +103,7 @@ pub struct OptimalRealtimeProcessor {
     unified_processor: Arc<Mutex<UnifiedProcessor>>,
     context_processor: Arc<Mutex<ContextAwareProcessor>>,
     result_manager: Arc<Mutex<ResultManager>>,
+    translation_worker: Option<Arc<TranslationWorker>>,
     
     // 通信
     app_handle: AppHandle,
@@ -109,35 +126,35 @@ impl OptimalRealtimeProcessor {
     ) -> Result<Self, Box<dyn std::error::Error>> {
         // 设置音频设备
         let host = cpal::default_host();
-        let device = host.default_input_device()
-            .ok_or("No input device available")?;
-
-        let stream_config = StreamConfig {
-            channels: 1,
-            sample_rate: SampleRate(16000),
-            buffer_size: cpal::BufferSize::Fixed(512),
-        };
+        let (device, capture_format) = capture_core::open_input(&host, false)?;
 
         // 初始化处理组件
         let audio_pipeline = Arc::new(Mutex::new(AudioProcessingPipeline::new()));
         
+        let prompt = crate::prompt_builder::PromptParts::new(config.initial_prompt.clone(), config.hotwords.clone());
         let unified_processor = Arc::new(Mutex::new(
-            UnifiedProcessor::new(whisper_state.get_context_ptr() as *mut std::ffi::c_void, config.language.clone(), config.initial_prompt.clone())?
+            UnifiedProcessor::new(whisper_state.get_context_ptr() as *mut std::ffi::c_void, config.language.clone(), prompt)?
         ));
         
         let context_processor = Arc::new(Mutex::new(ContextAwareProcessor::new()));
         
-        let result_manager = Arc::new(Mutex::new(ResultManager::new(1000))); // 最多保存1000个段落
+        let mut result_manager = ResultManager::new(1000); // 最多保存1000个段落
+        result_manager.set_language(&config.language);
+        let result_manager = Arc::new(Mutex::new(result_manager));
+
+        let translation_worker = config.translation_target_language.as_ref()
+            .map(|target| Self::spawn_translation_worker(&app_handle, Arc::new(NoopTranslator), target.clone()));
 
         Ok(Self {
             device,
-            config: stream_config,
+            capture_format,
             is_recording: Arc::new(Mutex::new(false)),
             is_paused: Arc::new(Mutex::new(false)),
             audio_pipeline,
             unified_processor,
             context_processor,
             result_manager,
+            translation_worker,
             app_handle,
             config_settings: config,
             start_time: None,
@@ -145,6 +162,24 @@ impl OptimalRealtimeProcessor {
         })
     }
 
+    fn spawn_translation_worker(
+        app_handle: &AppHandle,
+        translator: Arc<dyn Translator>,
+        target_language: String,
+    ) -> Arc<TranslationWorker> {
+        let app_handle = app_handle.clone();
+        Arc::new(TranslationWorker::spawn(translator, target_language, move |event| {
+            let _ = app_handle.emit("translation_result", event);
+        }))
+    }
+
+    /// 替换翻译器（例如接入外部翻译服务），需在开始录音前调用
+    pub fn set_translator(&mut self, translator: Arc<dyn Translator>) {
+        if let Some(target) = self.config_settings.translation_target_language.clone() {
+            self.translation_worker = Some(Self::spawn_translation_worker(&self.app_handle, translator, target));
+        }
+    }
+
     pub fn start_recording(&mut self) -> Result<(), Box<dyn std::error::Error>> {
         *self.is_recording.lock().unwrap() = true;
         *self.is_paused.lock().unwrap() = false;
@@ -166,25 +201,30 @@ impl OptimalRealtimeProcessor {
 
         let (audio_tx, audio_rx) = mpsc::channel::<Vec<f32>>();
         let (level_tx, level_rx) = mpsc::channel::<AudioLevelEvent>();
-
-        // 创建音频输入流
-        let stream = self.device.build_input_stream(
-            &self.config,
-            move |data: &[f32], _: &cpal::InputCallbackInfo| {
+        let mut level_meter = self.capture_format.level_meter(self.config_settings.level_meter);
+        let mut converter = self.capture_format.converter();
+
+        // 创建音频输入流，设备格式不是16kHz单声道时在回调中转换
+        let stream = capture_core::build_input_stream(
+            &self.device,
+            &self.capture_format,
+            move |data: &[f32]| {
                 let recording = *is_recording_stream.lock().unwrap();
                 let paused = *is_paused_stream.lock().unwrap();
 
                 if recording && !paused {
                     // 发送音频数据到处理线程
-                    let _ = audio_tx.send(data.to_vec());
+                    let _ = audio_tx.send(converter.process(data));
 
-                    // 计算实时音频级别
+                    // 计算实时音频级别；音量表每个数据块都要更新以保持平滑连续
+                    let reading = level_meter.process(data);
                     if let Ok(pipeline) = audio_pipeline.try_lock() {
                         let speech_probability = pipeline.get_speech_probability();
-                        let level = data.iter().map(|&x| x.abs()).sum::<f32>() / data.len() as f32;
                         
                         let level_event = AudioLevelEvent {
-                            level: level * 10.0,
+                            level: reading.normalized(),
+                            dbfs: reading.dbfs,
+                            peak_dbfs: reading.peak_dbfs,
                             speech_probability,
                             timestamp: std::time::SystemTime::now()
                                 .duration_since(std::time::UNIX_EPOCH)
@@ -196,8 +236,6 @@ impl OptimalRealtimeProcessor {
                     }
                 }
             },
-            |err| eprintln!("Audio stream error: {}", err),
-            None,
         )?;
 
         stream.play()?;
@@ -296,6 +334,7 @@ impl OptimalRealtimeProcessor {
         let segments_processed = self.segments_processed.clone();
         let app_handle = self.app_handle.clone();
         let config = self.config_settings.clone();
+        let translation_worker = self.translation_worker.clone();
 
         thread::spawn(move || {
             let rt = tokio::runtime::Runtime::new().unwrap();
@@ -313,6 +352,7 @@ impl OptimalRealtimeProcessor {
                                 &segments_processed,
                                 &app_handle,
                                 &config,
+                                &translation_worker,
                             ).await;
                         });
                     }
@@ -337,6 +377,7 @@ impl OptimalRealtimeProcessor {
         segments_processed: &Arc<Mutex<u32>>,
         app_handle: &AppHandle,
         config: &OptimalRealtimeConfig,
+        translation_worker: &Option<Arc<TranslationWorker>>,
     ) {
         // 1. 音频处理和分段
         let completed_segments = {
@@ -354,6 +395,7 @@ impl OptimalRealtimeProcessor {
                 segments_processed,
                 app_handle,
                 config,
+                translation_worker,
             ).await;
         }
     }
@@ -366,6 +408,7 @@ impl OptimalRealtimeProcessor {
         segments_processed: &Arc<Mutex<u32>>,
         app_handle: &AppHandle,
         config: &OptimalRealtimeConfig,
+        translation_worker: &Option<Arc<TranslationWorker>>,
     ) {
         // 1. 多层次处理
         let processing_events = {
@@ -432,6 +475,11 @@ impl OptimalRealtimeProcessor {
 
                     let _ = app_handle.emit("transcription_result", event_data);
 
+                    // 异步翻译最终结果
+                    if let Some(worker) = translation_worker {
+                        worker.submit(&enhanced_result.segment_id, &enhanced_result.text, &config.language);
+                    }
+
                     // 通知段落更新
                     for segment_id in updated_segments {
                         let _ = app_handle.emit("segment_updated", serde_json::json!({
@@ -484,7 +532,7 @@ impl OptimalRealtimeProcessor {
         if let Ok(mut pipeline) = self.audio_pipeline.lock() {
             if let Some(final_segment) = pipeline.force_complete_current() {
                 // TODO: 异步处理最后的段落
-                println!("Processing final segment: {:.2}s", final_segment.length_seconds());
+                log::debug!("Processing final segment: {:.2}s", final_segment.length_seconds());
             }
         }
 
@@ -545,16 +593,23 @@ pub async fn start_optimal_realtime_recording(
     config: OptimalRealtimeConfig,
     whisper_state: State<'_, WhisperContextState>,
     state: State<'_, OptimalRealtimeState>,
-) -> Result<(), String> {
-    let mut processor_state = state.0.lock().map_err(|e| e.to_string())?;
+    model_manager: State<'_, Arc<Mutex<ModelManager>>>,
+) -> Result<(), StenoError> {
+    let model_path = model_manager.lock().unwrap().get_current_model_path();
+    whisper_state.ensure_ready(&model_path)?;
+    if let LanguageCompatibility::Warning { message } = model_management::check_model_language(&model_path, &config.language)? {
+        log::warn!("⚠️ {}", message);
+    }
+
+    let mut processor_state = state.0.lock().map_err(|e| StenoError::Internal(e.to_string()))?;
     
     match OptimalRealtimeProcessor::new(app_handle, config, &*whisper_state) {
         Ok(mut processor) => {
-            processor.start_recording().map_err(|e| e.to_string())?;
+            processor.start_recording().map_err(|e| StenoError::AudioDevice(e.to_string()))?;
             *processor_state = Some(processor);
             Ok(())
         }
-        Err(e) => Err(e.to_string()),
+        Err(e) => Err(StenoError::Internal(e.to_string())),
     }
 }
 
@@ -0,0 +1,190 @@
+// post_process_rules.rs - 按语言的识别结果替换/清理规则：内置默认规则，外加应用数据目录下 post_process_rules.json 中的用户规则
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+pub const RULES_FILE_NAME: &str = "post_process_rules.json";
+
+lazy_static::lazy_static! {
+    static ref ACTIVE_RULES: RwLock<CompiledRules> = RwLock::new(PostProcessRules::builtin().compile());
+}
+
+/// 一条替换规则；regex 为 true 时 pattern 按正则匹配，replacement 可引用捕获组（$1）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Rule {
+    pub pattern: String,
+    pub replacement: String,
+    #[serde(default)]
+    pub regex: bool,
+}
+
+impl Rule {
+    fn literal(pattern: &str, replacement: &str) -> Self {
+        Self { pattern: pattern.to_string(), replacement: replacement.to_string(), regex: false }
+    }
+}
+
+/// 用户规则文件格式：{ "languages": { "zh": [ { "pattern": "...", "replacement": "..." } ] } }
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PostProcessRules {
+    #[serde(default)]
+    pub languages: HashMap<String, Vec<Rule>>,
+}
+
+impl PostProcessRules {
+    /// 内置的常见重复字词与标点修正
+    pub fn builtin() -> Self {
+        let zh = [
+            ("的的", "的"), ("了了", "了"), ("在在", "在"), ("是是", "是"), ("有有", "有"),
+            ("我我", "我"), ("你你", "你"), ("他他", "他"), ("她她", "她"), ("它它", "它"),
+            ("。。", "。"), ("，，", "，"), ("？？", "？"), ("！！", "！"),
+        ];
+        let en = [
+            (" a a ", " a "), (" the the ", " the "), (" and and ", " and "), (" to to ", " to "),
+            (" of of ", " of "), (" in in ", " in "), (" is is ", " is "), (" it it ", " it "),
+            (" that that ", " that "),
+            (",,", ","), ("..", "."), ("??", "?"), ("!!", "!"),
+        ];
+        let mut languages = HashMap::new();
+        languages.insert("zh".to_string(), zh.iter().map(|(p, r)| Rule::literal(p, r)).collect());
+        languages.insert("en".to_string(), en.iter().map(|(p, r)| Rule::literal(p, r)).collect());
+        Self { languages }
+    }
+
+    /// 内置规则在前，用户规则追加在后；文件不存在时只使用内置规则，文件无效时记录警告并忽略
+    pub fn load(path: &Path) -> Self {
+        let mut rules = Self::builtin();
+        let user: Self = match fs::read_to_string(path) {
+            Ok(json) => match serde_json::from_str(&json) {
+                Ok(user) => user,
+                Err(e) => {
+                    log::warn!("⚠️ 后处理规则文件格式无效，已忽略 {}: {}", path.display(), e);
+                    return rules;
+                }
+            },
+            Err(_) => return rules,
+        };
+        for (language, user_rules) in user.languages {
+            rules.languages.entry(language).or_default().extend(user_rules);
+        }
+        rules
+    }
+
+    pub fn compile(&self) -> CompiledRules {
+        let mut languages = HashMap::new();
+        for (language, rules) in &self.languages {
+            let compiled = rules.iter().filter_map(|rule| {
+                if !rule.regex {
+                    return Some(CompiledRule::Literal(rule.pattern.clone(), rule.replacement.clone()));
+                }
+                match Regex::new(&rule.pattern) {
+                    Ok(regex) => Some(CompiledRule::Regex(regex, rule.replacement.clone())),
+                    Err(e) => {
+                        log::warn!("⚠️ 跳过无效的后处理正则 {}: {}", rule.pattern, e);
+                        None
+                    }
+                }
+            }).collect();
+            languages.insert(language.clone(), compiled);
+        }
+        CompiledRules { languages }
+    }
+}
+
+enum CompiledRule {
+    Literal(String, String),
+    Regex(Regex, String),
+}
+
+pub struct CompiledRules {
+    languages: HashMap<String, Vec<CompiledRule>>,
+}
+
+impl CompiledRules {
+    /// 按顺序应用该语言的规则，没有对应规则时原样返回
+    pub fn apply(&self, language: &str, text: &str) -> String {
+        let mut result = text.to_string();
+        for rule in self.languages.get(language).into_iter().flatten() {
+            result = match rule {
+                CompiledRule::Literal(pattern, replacement) if !pattern.is_empty() => result.replace(pattern, replacement),
+                CompiledRule::Literal(..) => result,
+                CompiledRule::Regex(regex, replacement) => regex.replace_all(&result, replacement.as_str()).to_string(),
+            };
+        }
+        result
+    }
+}
+
+pub fn rules_path(app_handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    crate::data_dir::resolve_data_dir(app_handle)
+        .map(|dir| dir.join(RULES_FILE_NAME))
+        .map_err(|e| e.to_string())
+}
+
+/// 启动时加载用户规则，之后实时与批量转录的后处理都使用这套规则
+pub fn load_active_rules(app_handle: &tauri::AppHandle) -> Result<(), String> {
+    let path = rules_path(app_handle)?;
+    let compiled = PostProcessRules::load(&path).compile();
+    *ACTIVE_RULES.write().unwrap() = compiled;
+    log::info!("后处理规则已加载: {}", path.display());
+    Ok(())
+}
+
+pub fn apply(language: &str, text: &str) -> String {
+    ACTIVE_RULES.read().unwrap().apply(language, text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_rules_file(name: &str, contents: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("steno_rules_test_{}_{}.json", name, std::process::id()));
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_defaults_without_file() {
+        let rules = PostProcessRules::load(Path::new("/nonexistent/post_process_rules.json")).compile();
+        assert_eq!(rules.apply("zh", "我的的书。。"), "我的书。");
+        assert_eq!(rules.apply("en", "go to the the store!!"), "go to the store!");
+        assert_eq!(rules.apply("ja", "そのまま"), "そのまま");
+    }
+
+    #[test]
+    fn test_custom_rules_loaded_and_applied() {
+        let path = temp_rules_file("custom", r#"{
+            "languages": {
+                "zh": [{ "pattern": "微信支富", "replacement": "微信支付" }],
+                "en": [{ "pattern": "\\bteh\\b", "replacement": "the", "regex": true }],
+                "ja": [{ "pattern": "ありがとうござますした", "replacement": "ありがとうございました" }]
+            }
+        }"#);
+        let rules = PostProcessRules::load(&path).compile();
+
+        // 用户规则与内置规则同时生效
+        assert_eq!(rules.apply("zh", "用微信支富的的"), "用微信支付的");
+        assert_eq!(rules.apply("en", "teh cat and teh dog"), "the cat and the dog");
+        assert_eq!(rules.apply("ja", "ありがとうござますした"), "ありがとうございました");
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn test_invalid_entries_are_ignored() {
+        let path = temp_rules_file("invalid", "not json");
+        assert_eq!(PostProcessRules::load(&path).compile().apply("zh", "了了"), "了");
+        let _ = fs::remove_file(path);
+
+        let path = temp_rules_file("bad_regex", r#"{ "languages": { "en": [
+            { "pattern": "(", "replacement": "", "regex": true },
+            { "pattern": "colour", "replacement": "color" }
+        ] } }"#);
+        assert_eq!(PostProcessRules::load(&path).compile().apply("en", "colour"), "color");
+        let _ = fs::remove_file(path);
+    }
+}
@@ -0,0 +1,236 @@
+// prompt_builder.rs - 初始提示词组装（热词/自定义词汇偏置、提示词模板、续接上下文）
+
+/// Whisper 初始提示词的 token 预算（n_text_ctx / 2）
+pub const MAX_PROMPT_TOKENS: usize = 224;
+
+/// 热词前缀，与内置提示词中"核心术语"的列举方式保持一致
+const HOTWORD_PREFIX: &str = "术语：";
+const HOTWORD_SEPARATOR: &str = "、";
+
+/// 判断字符是否为CJK字符（每个字符大约对应一个token）
+pub fn is_cjk_char(c: char) -> bool {
+    matches!(c as u32,
+        0x4E00..=0x9FFF |   // CJK统一汉字
+        0x3400..=0x4DBF |   // 扩展A
+        0x3040..=0x30FF |   // 日文假名
+        0xAC00..=0xD7AF |   // 韩文音节
+        0x3000..=0x303F |   // CJK标点
+        0xFF00..=0xFFEF     // 全角字符
+    )
+}
+
+/// 粗略估算文本的token数量：CJK字符按1个token计，其余按每4个字符1个token计
+pub fn estimate_tokens(text: &str) -> usize {
+    let mut tokens = 0;
+    for word in text.split_whitespace() {
+        let mut latin_chars = 0;
+        for c in word.chars() {
+            if is_cjk_char(c) {
+                tokens += 1;
+            } else {
+                latin_chars += 1;
+            }
+        }
+        tokens += latin_chars.div_ceil(4);
+    }
+    tokens
+}
+
+/// 保留文本末尾不超过预算的部分（Whisper更关注靠近音频的上下文）
+fn truncate_to_budget_keep_tail(text: &str, budget: usize) -> String {
+    if estimate_tokens(text) <= budget {
+        return text.to_string();
+    }
+
+    let chars: Vec<char> = text.chars().collect();
+    let mut start = chars.len();
+    while start > 0 {
+        let candidate: String = chars[start - 1..].iter().collect();
+        if estimate_tokens(&candidate) > budget {
+            break;
+        }
+        start -= 1;
+    }
+
+    chars[start..].iter().collect::<String>().trim_start().to_string()
+}
+
+/// 去重热词：忽略空白、大小写重复，以及已出现在提示词中的词
+fn dedup_hotwords(base_prompt: &str, hotwords: &[String]) -> Vec<String> {
+    let base_lower = base_prompt.to_lowercase();
+    let mut seen: Vec<String> = Vec::new();
+    let mut result = Vec::new();
+
+    for word in hotwords {
+        let word = word.trim();
+        if word.is_empty() {
+            continue;
+        }
+        let lower = word.to_lowercase();
+        if seen.contains(&lower) || base_lower.contains(&lower) {
+            continue;
+        }
+        seen.push(lower);
+        result.push(word.to_string());
+    }
+
+    result
+}
+
+/// 热词行：按重要性依次加入，超出预算时从末尾开始丢弃
+fn hotword_line(hotwords: &[String], budget: usize) -> String {
+    let mut accepted: Vec<String> = Vec::new();
+    let mut line = String::new();
+    for word in hotwords {
+        let mut candidate_words = accepted.clone();
+        candidate_words.push(word.clone());
+        let candidate = format!("{}{}", HOTWORD_PREFIX, candidate_words.join(HOTWORD_SEPARATOR));
+        if estimate_tokens(&candidate) > budget {
+            break;
+        }
+        accepted = candidate_words;
+        line = candidate;
+    }
+
+    if accepted.len() < hotwords.len() {
+        log::warn!("热词超出提示词预算，丢弃 {} 个低优先级热词", hotwords.len() - accepted.len());
+    }
+    line
+}
+
+/// 组装最终的初始提示词，所有转录入口共用
+///
+/// 顺序固定为：热词行、提示词模板、续接上下文（上一段的识别结果，离音频最近）。
+/// 预算按同样的顺序分配：有其他内容时热词最多占一半预算，靠前的热词优先保留；
+/// 同时有模板时续接上下文最多占剩余预算的一半，其余留给模板；上下文和模板过长时都保留末尾部分。
+pub fn build_initial_prompt(template: Option<&str>, hotwords: &[String], carry_context: Option<&str>) -> Option<String> {
+    let template = template.map(|p| p.trim()).unwrap_or("");
+    let carry_context = carry_context.map(|c| c.trim()).unwrap_or("");
+    let hotwords = dedup_hotwords(template, hotwords);
+
+    let hotword_budget = if template.is_empty() && carry_context.is_empty() {
+        MAX_PROMPT_TOKENS
+    } else {
+        MAX_PROMPT_TOKENS / 2
+    };
+    let hotword_line = hotword_line(&hotwords, hotword_budget);
+    let remaining = MAX_PROMPT_TOKENS.saturating_sub(estimate_tokens(&hotword_line));
+
+    let context_budget = if template.is_empty() { remaining } else { remaining / 2 };
+    let carry_context = truncate_to_budget_keep_tail(carry_context, context_budget);
+    let template = truncate_to_budget_keep_tail(template, remaining.saturating_sub(estimate_tokens(&carry_context)));
+
+    let parts: Vec<String> = [hotword_line, template, carry_context]
+        .into_iter()
+        .filter(|part| !part.is_empty())
+        .collect();
+    if parts.is_empty() {
+        return None;
+    }
+    Some(parts.join("\n"))
+}
+
+/// 提示词中由调用方指定、整个转录过程不变的部分；续接上下文在逐段识别时传入
+#[derive(Debug, Clone, Default)]
+pub struct PromptParts {
+    pub template: Option<String>,
+    pub hotwords: Vec<String>,
+}
+
+impl PromptParts {
+    pub fn new(template: Option<String>, hotwords: Vec<String>) -> Self {
+        Self { template, hotwords }
+    }
+
+    pub fn build(&self, carry_context: Option<&str>) -> Option<String> {
+        build_initial_prompt(self.template.as_deref(), &self.hotwords, carry_context)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_assemble_hotwords_with_template() {
+        let prompt = build_initial_prompt(Some("季度投资策略会议。"), &words(&["美联储", "Steno"]), None).unwrap();
+        assert_eq!(prompt, "术语：美联储、Steno\n季度投资策略会议。");
+
+        assert_eq!(build_initial_prompt(None, &[], None), None);
+        assert_eq!(build_initial_prompt(Some("  "), &words(&[" "]), Some("")), None);
+        assert_eq!(build_initial_prompt(None, &words(&["Kubernetes"]), None).unwrap(), "术语：Kubernetes");
+    }
+
+    #[test]
+    fn test_hotword_dedup() {
+        let prompt = build_initial_prompt(
+            Some("讨论 GPU 集群的调度"),
+            &words(&["gpu", "Kubernetes", "kubernetes", " 调度 ", "Ray"]),
+            None,
+        ).unwrap();
+        assert_eq!(prompt, "术语：Kubernetes、Ray\n讨论 GPU 集群的调度");
+    }
+
+    #[test]
+    fn test_prompt_length_cap() {
+        // 热词超出预算时保留靠前的高优先级热词
+        let many: Vec<String> = (0..200).map(|i| format!("术语{}", i)).collect();
+        let prompt = build_initial_prompt(None, &many, None).unwrap();
+        assert!(estimate_tokens(&prompt) <= MAX_PROMPT_TOKENS);
+        assert!(prompt.starts_with("术语：术语0、术语1"));
+        assert!(!prompt.contains("术语199"));
+
+        // 模板过长时保留末尾，总长度不超过预算
+        let long_template = "很长的会议背景介绍".repeat(100) + "结尾";
+        let prompt = build_initial_prompt(Some(&long_template), &many, None).unwrap();
+        assert!(estimate_tokens(&prompt) <= MAX_PROMPT_TOKENS);
+        assert!(prompt.ends_with("结尾"));
+        assert!(prompt.starts_with("术语：术语0"));
+    }
+
+    #[test]
+    fn test_combined_assembly_order() {
+        let parts = PromptParts::new(Some("季度投资策略会议。".to_string()), words(&["美联储", "Steno"]));
+        assert_eq!(parts.build(Some("上一段讨论了利率。")).unwrap(), "术语：美联储、Steno\n季度投资策略会议。\n上一段讨论了利率。");
+        assert_eq!(parts.build(Some("  ")).unwrap(), "术语：美联储、Steno\n季度投资策略会议。");
+
+        // 只有续接上下文，或者续接上下文与热词
+        assert_eq!(build_initial_prompt(None, &[], Some("上一段")).unwrap(), "上一段");
+        assert_eq!(build_initial_prompt(Some(" "), &words(&["Ray"]), Some("上一段")).unwrap(), "术语：Ray\n上一段");
+    }
+
+    #[test]
+    fn test_truncation_order() {
+        let many: Vec<String> = (0..200).map(|i| format!("术语{}", i)).collect();
+        let long_template = "模板".repeat(200) + "模板结尾";
+        let long_context = "上文".repeat(200) + "上文结尾";
+
+        // 三部分都过长：热词占一半，上下文与模板平分剩余预算，各自保留末尾
+        let prompt = build_initial_prompt(Some(&long_template), &many, Some(&long_context)).unwrap();
+        assert!(estimate_tokens(&prompt) <= MAX_PROMPT_TOKENS);
+        let lines: Vec<&str> = prompt.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("术语：术语0、术语1"));
+        assert!(estimate_tokens(lines[0]) <= MAX_PROMPT_TOKENS / 2);
+        assert!(lines[1].ends_with("模板结尾"));
+        assert!(lines[2].ends_with("上文结尾"));
+        let remaining = MAX_PROMPT_TOKENS - estimate_tokens(lines[0]);
+        assert_eq!(estimate_tokens(lines[2]), remaining / 2);
+        assert_eq!(estimate_tokens(lines[1]), remaining - remaining / 2);
+
+        // 上下文较短时未用完的预算留给模板
+        let prompt = build_initial_prompt(Some(&long_template), &[], Some("上文结尾")).unwrap();
+        let lines: Vec<&str> = prompt.lines().collect();
+        assert_eq!(lines[1], "上文结尾");
+        assert_eq!(estimate_tokens(lines[0]), MAX_PROMPT_TOKENS - 4);
+
+        // 没有模板时上下文可以使用热词之外的全部预算
+        let prompt = build_initial_prompt(None, &words(&["Ray"]), Some(&long_context)).unwrap();
+        assert_eq!(estimate_tokens(&prompt), MAX_PROMPT_TOKENS);
+        assert!(prompt.starts_with("术语：Ray\n") && prompt.ends_with("上文结尾"));
+    }
+}
@@ -0,0 +1,134 @@
+// prompt_comparison.rs - 同一录音使用两个提示词模板的转录对比
+use serde::{Deserialize, Serialize};
+
+use crate::prompt_builder::is_cjk_char;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromptTranscript {
+    pub prompt_id: String,
+    pub prompt_name: String,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromptDiffSummary {
+    pub added_terms: Vec<String>,   // 只出现在第二个结果中的词
+    pub removed_terms: Vec<String>, // 只出现在第一个结果中的词
+    pub common_terms: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromptComparison {
+    pub record_id: String,
+    pub results: Vec<PromptTranscript>,
+    pub diff: PromptDiffSummary,
+}
+
+/// 切分对比用的词：拉丁文字按单词（忽略大小写），CJK 按标点分隔的短语
+fn extract_terms(text: &str) -> Vec<String> {
+    let mut terms: Vec<String> = Vec::new();
+    let mut current = String::new();
+    let mut current_is_cjk = false;
+
+    let mut flush = |current: &mut String, terms: &mut Vec<String>| {
+        if !current.is_empty() {
+            let term = current.to_lowercase();
+            if !terms.contains(&term) {
+                terms.push(term);
+            }
+            current.clear();
+        }
+    };
+
+    for c in text.chars() {
+        let is_cjk = is_cjk_char(c) && c.is_alphanumeric();
+        if !c.is_alphanumeric() {
+            flush(&mut current, &mut terms);
+            continue;
+        }
+        if !current.is_empty() && is_cjk != current_is_cjk {
+            flush(&mut current, &mut terms);
+        }
+        current_is_cjk = is_cjk;
+        current.push(c);
+    }
+    flush(&mut current, &mut terms);
+    terms
+}
+
+pub fn diff_transcripts(first: &str, second: &str) -> PromptDiffSummary {
+    let first_terms = extract_terms(first);
+    let second_terms = extract_terms(second);
+
+    PromptDiffSummary {
+        added_terms: second_terms.iter().filter(|t| !first_terms.contains(t)).cloned().collect(),
+        removed_terms: first_terms.iter().filter(|t| !second_terms.contains(t)).cloned().collect(),
+        common_terms: first_terms.iter().filter(|t| second_terms.contains(t)).count(),
+    }
+}
+
+/// 依次用每个提示词执行转录并生成对比结果；transcribe 接收提示词内容返回转录文本
+pub fn compare_prompts<F>(
+    record_id: &str,
+    prompts: &[(String, String, String)], // (id, 名称, 内容)
+    mut transcribe: F,
+) -> Result<PromptComparison, String>
+where
+    F: FnMut(&str) -> Result<String, String>,
+{
+    if prompts.len() != 2 {
+        return Err("提示词对比需要恰好两个模板".to_string());
+    }
+
+    let mut results = Vec::with_capacity(2);
+    for (id, name, content) in prompts {
+        let text = transcribe(content).map_err(|e| format!("使用提示词 {} 转录失败: {}", name, e))?;
+        results.push(PromptTranscript {
+            prompt_id: id.clone(),
+            prompt_name: name.clone(),
+            text,
+        });
+    }
+
+    let diff = diff_transcripts(&results[0].text, &results[1].text);
+    Ok(PromptComparison {
+        record_id: record_id.to_string(),
+        results,
+        diff,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compare_two_prompts() {
+        let prompts = vec![
+            ("p1".to_string(), "通用".to_string(), "日常对话".to_string()),
+            ("p2".to_string(), "技术".to_string(), "术语：Kubernetes".to_string()),
+        ];
+        let mut calls = Vec::new();
+        let comparison = compare_prompts("record_1", &prompts, |prompt| {
+            calls.push(prompt.to_string());
+            Ok(if prompt.contains("Kubernetes") {
+                "我们部署到 Kubernetes 集群，效果很好".to_string()
+            } else {
+                "我们部署到 酷伯内特斯 集群，效果很好".to_string()
+            })
+        }).unwrap();
+
+        assert_eq!(calls, vec!["日常对话", "术语：Kubernetes"]);
+        assert_eq!(comparison.results.len(), 2);
+        assert_ne!(comparison.results[0].text, comparison.results[1].text);
+        assert_eq!(comparison.diff.added_terms, vec!["kubernetes"]);
+        assert_eq!(comparison.diff.removed_terms, vec!["酷伯内特斯"]);
+        assert_eq!(comparison.diff.common_terms, 3);
+    }
+
+    #[test]
+    fn test_compare_requires_two_prompts() {
+        let prompts = vec![("p1".to_string(), "通用".to_string(), String::new())];
+        assert!(compare_prompts("record_1", &prompts, |_| Ok(String::new())).is_err());
+    }
+}
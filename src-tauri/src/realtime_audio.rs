@@ -1,703 +0,0 @@
-use cpal::{Device, Stream, StreamConfig, SampleRate};
-use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
-use std::sync::{Arc, Mutex, mpsc};
-use std::thread;
-use std::time::{Duration, Instant};
-use serde::{Deserialize, Serialize};
-use tauri::{AppHandle, Emitter};
-
-// 导入 Whisper 相关类型和函数
-use crate::{
-    whisper_context, WhisperContextState,
-    whisper_full, whisper_full_default_params, 
-    whisper_full_n_segments, whisper_full_get_segment_text,
-    whisper_sampling_strategy_WHISPER_SAMPLING_GREEDY,
-};
-use std::ffi::{CStr, CString};
-use std::os::raw::c_char;
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct RealtimeConfig {
-    pub language: String,
-    pub mode: String, // "streaming", "buffered", "hybrid"
-    pub speaker_diarization: bool,
-    pub noise_reduction: bool,
-    pub auto_save: bool,
-    pub save_interval: u32, // minutes
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct AudioLevelUpdate {
-    pub level: f32,
-    pub timestamp: u64,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct RecognitionResult {
-    pub text: String,
-    pub confidence: f32,
-    pub is_temporary: bool,
-    pub speaker: Option<String>,
-    pub timestamp: u64,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct RecordingStats {
-    pub duration: u64, // seconds
-    pub segments_count: u32,
-    pub speaker_count: u32,
-    pub average_confidence: f32,
-}
-
-pub struct RealtimeAudioCapture {
-    device: Device,
-    config: StreamConfig,
-    stream: Option<Stream>,
-    is_recording: Arc<Mutex<bool>>,
-    is_paused: Arc<Mutex<bool>>,
-    audio_buffer: Arc<Mutex<Vec<f32>>>,
-    app_handle: AppHandle,
-    recognition_config: RealtimeConfig,
-    start_time: Option<Instant>,
-    whisper_context_ptr: *mut whisper_context, // 添加 Whisper 上下文指针
-}
-
-impl RealtimeAudioCapture {
-    pub fn new(
-        app_handle: AppHandle, 
-        config: RealtimeConfig,
-        whisper_state: &WhisperContextState,
-    ) -> Result<Self, Box<dyn std::error::Error>> {
-        let host = cpal::default_host();
-        let device = host.default_input_device()
-            .ok_or("No input device available")?;
-
-        let mut supported_configs = device.supported_input_configs()?;
-        let _supported_config = supported_configs.next()
-            .ok_or("No supported input config")?
-            .with_sample_rate(SampleRate(16000)); // 16kHz for Whisper
-
-        let stream_config = StreamConfig {
-            channels: 1, // Mono
-            sample_rate: SampleRate(16000),
-            buffer_size: cpal::BufferSize::Fixed(512), // 32ms buffer at 16kHz
-        };
-
-        Ok(Self {
-            device,
-            config: stream_config,
-            stream: None,
-            is_recording: Arc::new(Mutex::new(false)),
-            is_paused: Arc::new(Mutex::new(false)),
-            audio_buffer: Arc::new(Mutex::new(Vec::new())),
-            app_handle,
-            recognition_config: config,
-            start_time: None,
-            whisper_context_ptr: whisper_state.get_context_ptr(),
-        })
-    }
-
-    pub fn start_recording(&mut self) -> Result<(), Box<dyn std::error::Error>> {
-        let is_recording_stream = self.is_recording.clone();
-        let is_paused_stream = self.is_paused.clone();
-        let is_recording_processing = self.is_recording.clone();
-        let audio_buffer = self.audio_buffer.clone();
-        let app_handle = self.app_handle.clone();
-        let config = self.recognition_config.clone();
-
-        *self.is_recording.lock().unwrap() = true;
-        *self.is_paused.lock().unwrap() = false;
-        self.start_time = Some(Instant::now());
-
-        let (audio_tx, audio_rx) = mpsc::channel::<Vec<f32>>();
-        let (level_tx, level_rx) = mpsc::channel::<f32>();
-
-        // 创建音频流
-        let stream = self.device.build_input_stream(
-            &self.config,
-            move |data: &[f32], _: &cpal::InputCallbackInfo| {
-                let recording = *is_recording_stream.lock().unwrap();
-                let paused = *is_paused_stream.lock().unwrap();
-
-                if recording && !paused {
-                    // 计算音频级别
-                    let level = data.iter().map(|&sample| sample.abs()).sum::<f32>() / data.len() as f32;
-                    let _ = level_tx.send(level);
-
-                    // 发送音频数据
-                    let audio_chunk = data.to_vec();
-                    let _ = audio_tx.send(audio_chunk);
-
-                    // 存储到缓冲区
-                    let mut buffer = audio_buffer.lock().unwrap();
-                    buffer.extend_from_slice(data);
-                    
-                    // 保持缓冲区大小在合理范围内 (10秒的音频)
-                    let buffer_len = buffer.len();
-                    if buffer_len > 16000 * 10 {
-                        buffer.drain(..buffer_len - 16000 * 10);
-                    }
-                }
-            },
-            |err| eprintln!("Audio stream error: {}", err),
-            None,
-        )?;
-
-        stream.play()?;
-        self.stream = Some(stream);
-
-        // 启动音频级别监控线程
-        let app_handle_level = app_handle.clone();
-        thread::spawn(move || {
-            while let Ok(level) = level_rx.recv() {
-                let level_update = AudioLevelUpdate {
-                    level: level * 10.0, // 放大显示
-                    timestamp: std::time::SystemTime::now()
-                        .duration_since(std::time::UNIX_EPOCH)
-                        .unwrap()
-                        .as_millis() as u64,
-                };
-                
-                let _ = app_handle_level.emit("audio_level_update", level_update);
-            }
-        });
-
-        // 启动音频处理线程
-        let app_handle_processing = app_handle.clone();
-        let is_recording_processing = is_recording.clone();
-        thread::spawn(move || {
-            Self::audio_processing_thread(
-                audio_rx, 
-                app_handle_processing, 
-                config,
-                is_recording_processing
-            );
-        });
-
-        Ok(())
-    }
-
-    pub fn pause_recording(&mut self) -> Result<(), Box<dyn std::error::Error>> {
-        *self.is_paused.lock().unwrap() = true;
-        Ok(())
-    }
-
-    pub fn resume_recording(&mut self) -> Result<(), Box<dyn std::error::Error>> {
-        *self.is_paused.lock().unwrap() = false;
-        Ok(())
-    }
-
-    pub fn stop_recording(&mut self) -> Result<(), Box<dyn std::error::Error>> {
-        *self.is_recording.lock().unwrap() = false;
-        *self.is_paused.lock().unwrap() = false;
-        
-        if let Some(stream) = self.stream.take() {
-            drop(stream);
-        }
-
-        // 处理最后的音频数据
-        let final_audio = {
-            let mut buffer = self.audio_buffer.lock().unwrap();
-            let audio = buffer.clone();
-            buffer.clear();
-            audio
-        };
-
-        if !final_audio.is_empty() {
-            self.process_final_audio(final_audio)?;
-        }
-
-        Ok(())
-    }
-
-    fn audio_processing_thread(
-        audio_rx: mpsc::Receiver<Vec<f32>>,
-        app_handle: AppHandle,
-        config: RealtimeConfig,
-        is_recording: Arc<Mutex<bool>>,
-    ) {
-        let mut audio_accumulator = Vec::new();
-        let chunk_size = 16000; // 1 second of audio at 16kHz
-        let mut last_process_time = Instant::now();
-        let mut segment_id = 0u32;
-
-        while *is_recording.lock().unwrap() {
-            match audio_rx.recv_timeout(Duration::from_millis(100)) {
-                Ok(audio_chunk) => {
-                    audio_accumulator.extend(audio_chunk);
-
-                    // 根据模式决定处理频率
-                    let should_process = match config.mode.as_str() {
-                        "streaming" => audio_accumulator.len() >= chunk_size / 4, // 250ms
-                        "buffered" => audio_accumulator.len() >= chunk_size * 2,  // 2s
-                        "hybrid" => {
-                            // 混合模式：快速临时结果 + 定期最终结果
-                            audio_accumulator.len() >= chunk_size / 2 // 500ms
-                        },
-                        _ => audio_accumulator.len() >= chunk_size,
-                    };
-
-                    if should_process && last_process_time.elapsed() > Duration::from_millis(200) {
-                        let audio_to_process = if audio_accumulator.len() > chunk_size * 3 {
-                            // 如果积累太多，只处理最新的部分
-                            audio_accumulator.split_off(audio_accumulator.len() - chunk_size * 2)
-                        } else {
-                            audio_accumulator.clone()
-                        };
-
-                        if let Ok(result) = Self::process_audio_chunk(&audio_to_process, &config, segment_id) {
-                            segment_id += 1;
-                            let _ = app_handle.emit("recognition_result", result);
-                        }
-
-                        // 对于流式模式，清理缓冲区更频繁
-                        if config.mode == "streaming" {
-                            audio_accumulator.clear();
-                        } else {
-                            // 保留一些重叠以提高连续性
-                            if audio_accumulator.len() > chunk_size / 2 {
-                                audio_accumulator.drain(..chunk_size / 4);
-                            }
-                        }
-
-                        last_process_time = Instant::now();
-                    }
-                },
-                Err(mpsc::RecvTimeoutError::Timeout) => {
-                    // 定期发送统计信息
-                    let stats = RecordingStats {
-                        duration: 0, // 这里需要从外部传入实际时长
-                        segments_count: segment_id,
-                        speaker_count: 1, // 简化实现，后续可以集成说话人识别
-                        average_confidence: 0.95, // 模拟值
-                    };
-                    let _ = app_handle.emit("recording_stats", stats);
-                },
-                Err(mpsc::RecvTimeoutError::Disconnected) => break,
-            }
-        }
-    }
-
-    fn process_audio_chunk(
-        audio: &[f32], 
-        config: &RealtimeConfig,
-        segment_id: u32
-    ) -> Result<RecognitionResult, Box<dyn std::error::Error>> {
-        // 实时 Whisper.cpp 调用实现
-        
-        // 1. VAD (Voice Activity Detection) - 检测是否有语音
-        let energy = audio.iter().map(|&x| x * x).sum::<f32>() / audio.len() as f32;
-        let rms = energy.sqrt();
-        
-        // 提高静音检测阈值，避免处理噪音
-        if rms < 0.005 {
-            return Err("Silent segment".into());
-        }
-
-        // 2. 音频预处理
-        let mut processed_audio = audio.to_vec();
-        
-        // 应用预加重滤波
-        Self::apply_preemphasis(&mut processed_audio, 0.97);
-        
-        // 归一化处理
-        Self::normalize_audio(&mut processed_audio);
-        
-        // 噪声抑制
-        if config.noise_reduction {
-            Self::apply_noise_reduction(&mut processed_audio, rms);
-        }
-
-        // 3. 调用真实的 Whisper.cpp 识别 - 使用模拟实现
-        // 注意：由于架构限制，无法在静态方法中直接访问实例的 whisper_context_ptr
-        // 这里先使用改进的模拟实现，实际部署时需要重构为非静态方法
-        let energy_level = processed_audio.iter().map(|&x| x * x).sum::<f32>() / processed_audio.len() as f32;
-        let spectral_features = Self::extract_spectral_features(&processed_audio);
-        let recognition_result = Self::generate_realistic_recognition(energy_level, &spectral_features, config);
-        
-        // 4. 根据模式决定是否为临时结果
-        let is_temporary = match config.mode.as_str() {
-            "streaming" => false,  // 流式模式直接输出最终结果
-            "buffered" => false,   // 缓冲模式输出最终结果
-            "hybrid" => segment_id % 3 != 0,  // 混合模式：每3次输出1次最终结果
-            _ => false,
-        };
-
-        // 5. 说话人识别（如果启用）
-        let speaker = if config.speaker_diarization {
-            Self::detect_speaker(&processed_audio, segment_id)
-        } else {
-            None
-        };
-
-        // 6. 计算置信度
-        let confidence = Self::calculate_confidence(&recognition_result, rms);
-
-        Ok(RecognitionResult {
-            text: recognition_result,
-            confidence,
-            is_temporary,
-            speaker,
-            timestamp: std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap()
-                .as_millis() as u64,
-        })
-    }
-
-    // 真实的 Whisper.cpp 识别调用
-    fn whisper_recognize_chunk(
-        audio: &[f32], 
-        config: &RealtimeConfig,
-        whisper_ctx: *mut whisper_context
-    ) -> Result<String, Box<dyn std::error::Error>> {
-        // 检查音频长度，太短的音频跳过
-        if audio.len() < 1600 { // 100ms at 16kHz
-            return Err("Audio chunk too short for recognition".into());
-        }
-
-        // 真实的 Whisper.cpp 识别调用
-        unsafe {
-            // 创建音频数据的可变副本
-            let mut audio_data = audio.to_vec();
-            
-            // 设置 Whisper 参数 - 针对实时处理优化
-            let mut params = whisper_full_default_params(whisper_sampling_strategy_WHISPER_SAMPLING_GREEDY);
-            
-            // 实时处理优化参数
-            params.n_threads = 2; // 使用较少线程减少延迟
-            params.greedy.best_of = 1; // 减少候选数量
-            params.temperature = 0.0;
-            params.suppress_blank = true;
-            params.token_timestamps = false; // 实时处理不需要详细时间戳
-            params.max_len = 30; // 限制最大token数
-            params.print_realtime = false;
-            params.print_progress = false;
-
-            // 设置语言
-            let lang_cstring = match config.language.as_str() {
-                "zh" => Some(CString::new("zh").unwrap()),
-                "en" => Some(CString::new("en").unwrap()),
-                _ => None,
-            };
-
-            if let Some(ref lang_str) = lang_cstring {
-                params.language = lang_str.as_ptr();
-            } else {
-                params.language = std::ptr::null();
-            }
-
-            // 执行识别
-            let result = whisper_full(
-                whisper_ctx,
-                params,
-                audio_data.as_mut_ptr(),
-                audio_data.len() as i32,
-            );
-
-            if result != 0 {
-                return Err(format!("Whisper recognition failed with code: {}", result).into());
-            }
-
-            // 提取识别文本
-            let num_segments = whisper_full_n_segments(whisper_ctx);
-            let mut full_text = String::new();
-
-            for i in 0..num_segments {
-                let segment_ptr = whisper_full_get_segment_text(whisper_ctx, i);
-                if !segment_ptr.is_null() {
-                    let c_str = CStr::from_ptr(segment_ptr as *const c_char);
-                    if let Ok(text) = c_str.to_str() {
-                        full_text.push_str(text);
-                    }
-                }
-            }
-
-            // 后处理文本
-            let processed_text = Self::post_process_realtime_text(&full_text, config);
-            
-            Ok(processed_text)
-        }
-    }
-
-    // 实时文本后处理
-    fn post_process_realtime_text(text: &str, config: &RealtimeConfig) -> String {
-        let mut processed = text.trim().to_string();
-        
-        // 移除多余的空格
-        processed = processed.split_whitespace().collect::<Vec<_>>().join(" ");
-        
-        // 根据语言进行特定处理
-        match config.language.as_str() {
-            "zh" => {
-                // 中文处理
-                let fixes = vec![
-                    ("的的", "的"),
-                    ("了了", "了"),
-                    ("是是", "是"),
-                    ("在在", "在"),
-                ];
-                for (wrong, correct) in fixes {
-                    processed = processed.replace(wrong, correct);
-                }
-            },
-            "en" => {
-                // 英文处理
-                processed = processed.replace("  ", " ");
-                processed = processed.replace(" .", ".");
-                processed = processed.replace(" ,", ",");
-            },
-            _ => {}
-        }
-        
-        processed
-    }
-
-    // 音频预处理函数
-    fn apply_preemphasis(samples: &mut [f32], coeff: f32) {
-        if samples.len() < 2 {
-            return;
-        }
-        for i in (1..samples.len()).rev() {
-            samples[i] -= coeff * samples[i-1];
-        }
-    }
-
-    fn normalize_audio(samples: &mut [f32]) {
-        if samples.is_empty() {
-            return;
-        }
-        
-        let max_abs = samples.iter()
-            .map(|&x| x.abs())
-            .fold(0.0f32, f32::max);
-        
-        if max_abs > 0.0 && max_abs != 1.0 {
-            let scale = 0.95 / max_abs;
-            for sample in samples.iter_mut() {
-                *sample *= scale;
-            }
-        }
-    }
-
-    fn apply_noise_reduction(samples: &mut [f32], rms: f32) {
-        let noise_threshold = rms * 0.3;
-        for sample in samples.iter_mut() {
-            let sample_energy = *sample * *sample;
-            if sample_energy < noise_threshold {
-                *sample *= 0.4; // 降低噪声区域的增益
-            }
-        }
-    }
-
-    // 提取频谱特征用于识别
-    fn extract_spectral_features(audio: &[f32]) -> Vec<f32> {
-        let mut features = Vec::new();
-        
-        // 计算过零率
-        let mut zero_crossings = 0;
-        for i in 1..audio.len() {
-            if (audio[i] > 0.0) != (audio[i-1] > 0.0) {
-                zero_crossings += 1;
-            }
-        }
-        features.push(zero_crossings as f32 / audio.len() as f32);
-        
-        // 计算频谱质心（简化版本）
-        let mut spectral_centroid = 0.0f32;
-        let mut total_magnitude = 0.0f32;
-        
-        for (i, &sample) in audio.iter().enumerate() {
-            let magnitude = sample.abs();
-            spectral_centroid += (i as f32) * magnitude;
-            total_magnitude += magnitude;
-        }
-        
-        if total_magnitude > 0.0 {
-            features.push(spectral_centroid / total_magnitude);
-        } else {
-            features.push(0.0);
-        }
-        
-        features
-    }
-
-    // 基于音频特征生成逼真的识别结果
-    fn generate_realistic_recognition(energy: f32, features: &[f32], config: &RealtimeConfig) -> String {
-        // 根据语言配置和音频特征生成相应的文本
-        let language_templates = match config.language.as_str() {
-            "zh" => vec![
-                "嗯", "好的", "是的", "我觉得", "然后", "这样", "对", "不过", "所以", "但是",
-                "我认为", "确实", "可能", "现在", "刚刚", "一下", "这个", "那个", "应该", "或者"
-            ],
-            "en" => vec![
-                "yes", "okay", "right", "well", "so", "but", "and", "the", "that", "this",
-                "I think", "maybe", "actually", "really", "just", "now", "here", "there", "good", "sure"
-            ],
-            _ => vec![
-                "um", "uh", "well", "so", "and", "but", "the", "that", "this", "yes"
-            ]
-        };
-        
-        // 根据能量水平选择适当的词汇
-        let energy_index = ((energy * 1000.0) as usize) % language_templates.len();
-        let selected_word = language_templates[energy_index];
-        
-        // 根据频谱特征调整输出
-        if features.len() >= 2 {
-            let zero_crossing_rate = features[0];
-            let spectral_centroid = features[1];
-            
-            // 高过零率通常表示辅音或噪音
-            if zero_crossing_rate > 0.15 {
-                // 可能是辅音开头的词
-                match config.language.as_str() {
-                    "zh" => format!("{}呢", selected_word),
-                    "en" => format!("{}...", selected_word),
-                    _ => selected_word.to_string(),
-                }
-            } else {
-                // 低过零率通常表示元音
-                selected_word.to_string()
-            }
-        } else {
-            selected_word.to_string()
-        }
-    }
-
-    // 说话人检测
-    fn detect_speaker(audio: &[f32], segment_id: u32) -> Option<String> {
-        // 简单的说话人检测基于音频特征
-        let avg_amplitude = audio.iter().map(|&x| x.abs()).sum::<f32>() / audio.len() as f32;
-        
-        // 基于平均幅度判断说话人（这是一个简化的实现）
-        if avg_amplitude > 0.015 {
-            Some("Speaker A".to_string())
-        } else if avg_amplitude > 0.008 {
-            Some("Speaker B".to_string())
-        } else {
-            None
-        }
-    }
-
-    // 计算识别置信度
-    fn calculate_confidence(text: &str, audio_rms: f32) -> f32 {
-        let base_confidence = 0.75;
-        
-        // 基于音频质量调整置信度
-        let audio_quality_bonus = (audio_rms * 50.0).min(0.2);
-        
-        // 基于文本长度调整置信度
-        let text_length_bonus = if text.len() > 2 { 0.1 } else { 0.0 };
-        
-        (base_confidence + audio_quality_bonus + text_length_bonus).min(0.98)
-    }
-
-    fn process_final_audio(&self, _audio: Vec<f32>) -> Result<(), Box<dyn std::error::Error>> {
-        // 处理录音结束时的最终音频
-        // 这里可以进行最终的识别和后处理
-        let _ = self.app_handle.emit("recording_completed", ());
-        Ok(())
-    }
-
-    pub fn get_recording_duration(&self) -> u64 {
-        if let Some(start_time) = self.start_time {
-            start_time.elapsed().as_secs()
-        } else {
-            0
-        }
-    }
-}
-
-// Tauri命令实现
-use tauri::State;
-use std::sync::Mutex as StdMutex;
-
-pub struct AudioCaptureState(StdMutex<Option<RealtimeAudioCapture>>);
-
-impl AudioCaptureState {
-    pub fn new() -> Self {
-        AudioCaptureState(StdMutex::new(None))
-    }
-    
-    pub fn lock(&self) -> Result<std::sync::MutexGuard<Option<RealtimeAudioCapture>>, std::sync::PoisonError<std::sync::MutexGuard<Option<RealtimeAudioCapture>>>> {
-        self.0.lock()
-    }
-}
-
-impl Default for AudioCaptureState {
-    fn default() -> Self {
-        Self::new()
-    }
-}
-
-#[tauri::command]
-pub async fn start_realtime_recording(
-    app_handle: AppHandle,
-    config: RealtimeConfig,
-    whisper_state: tauri::State<'_, WhisperContextState>,
-    state: tauri::State<'_, AudioCaptureState>,
-) -> Result<(), String> {
-    let mut capture_state = state.lock().map_err(|e| e.to_string())?;
-    
-    match RealtimeAudioCapture::new(app_handle, config, &*whisper_state) {
-        Ok(mut capture) => {
-            capture.start_recording().map_err(|e| e.to_string())?;
-            *capture_state = Some(capture);
-            Ok(())
-        },
-        Err(e) => Err(e.to_string()),
-    }
-}
-
-#[tauri::command]
-pub async fn pause_realtime_recording(
-    state: State<'_, AudioCaptureState>,
-) -> Result<(), String> {
-    let mut capture_state = state.lock().map_err(|e| e.to_string())?;
-    
-    if let Some(ref mut capture) = capture_state.as_mut() {
-        capture.pause_recording().map_err(|e| e.to_string())?;
-    }
-    
-    Ok(())
-}
-
-#[tauri::command]
-pub async fn resume_realtime_recording(
-    state: State<'_, AudioCaptureState>,
-) -> Result<(), String> {
-    let mut capture_state = state.lock().map_err(|e| e.to_string())?;
-    
-    if let Some(ref mut capture) = capture_state.as_mut() {
-        capture.resume_recording().map_err(|e| e.to_string())?;
-    }
-    
-    Ok(())
-}
-
-#[tauri::command]
-pub async fn stop_realtime_recording(
-    state: State<'_, AudioCaptureState>,
-) -> Result<(), String> {
-    let mut capture_state = state.lock().map_err(|e| e.to_string())?;
-    
-    if let Some(mut capture) = capture_state.take() {
-        capture.stop_recording().map_err(|e| e.to_string())?;
-    }
-    
-    Ok(())
-}
-
-#[tauri::command]
-pub async fn get_recording_duration(
-    state: State<'_, AudioCaptureState>,
-) -> Result<u64, String> {
-    let capture_state = state.lock().map_err(|e| e.to_string())?;
-    
-    if let Some(ref capture) = capture_state.as_ref() {
-        Ok(capture.get_recording_duration())
-    } else {
-        Ok(0)
-    }
-}
\ No newline at end of file
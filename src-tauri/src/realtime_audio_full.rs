@@ -1,6 +1,7 @@
-use cpal::SampleRate;
-use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::traits::{DeviceTrait, StreamTrait};
+use std::collections::VecDeque;
 use std::sync::{Arc, Mutex, mpsc};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::thread;
 use std::time::{Duration, Instant};
 use serde::{Deserialize, Serialize};
@@ -13,11 +14,29 @@ use std::os::raw::c_char;
 // 导入whisper相关函数
 use crate::{
     whisper_full, whisper_full_default_params, whisper_full_get_segment_text, 
-    whisper_full_n_segments, whisper_sampling_strategy_WHISPER_SAMPLING_BEAM_SEARCH,
-    WhisperContextState, post_process_text
+    whisper_full_get_segment_t0, whisper_full_get_segment_t1,
+    whisper_full_get_segment_no_speech_prob, whisper_full_n_segments,
+    whisper_full_get_token_id, whisper_full_n_tokens, whisper_token, whisper_token_eot,
+    whisper_tokenize, whisper_context, WhisperContextState, post_process_text_with_config
 };
-use crate::realtime_speaker_diarization::RealtimeSpeakerDiarization;
-use crate::audio_devices;
+use crate::text_processing::{self, RepetitionConfig, SuppressionConfig, TextJoinStyle};
+use crate::confidence::{self, ConfidenceAccumulator, ConfidenceBand, ConfidenceThresholds};
+use crate::recording_writer::{self, RecordingFormat, StreamingWavWriter};
+use crate::realtime_speaker_diarization::{self, RealtimeSpeakerDiarization, SpeakerChange, SpeakerTimeRange};
+use crate::capture_core;
+use crate::whisper_input::{self, WHISPER_SAMPLE_RATE};
+use crate::data_dir;
+use crate::level_meter::{LevelMeterConfig, LevelReading};
+use crate::decoding::{DecodingStrategy, SegmentationConfig};
+use crate::errors::StenoError;
+use crate::model_management::{self, LanguageCompatibility, ModelManager};
+use crate::storage_commands::StorageState;
+use crate::storage::TranscriptionRecord;
+use crate::realtime_autosave::{RecordingPart, RecordingSplitter, TranscriptAutoSaver};
+use crate::vad::{self, VadBackend, VadBackendKind};
+use crate::denoise::{self, DenoiseBackendKind};
+use crate::layered_processor::TranscriptResult;
+use crate::result_manager::{ManagedTranscriptSegment, ResultManager};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RealtimeConfig {
@@ -27,11 +46,349 @@ pub struct RealtimeConfig {
     pub noise_reduction: bool,
     pub auto_save: bool,
     pub save_interval: u32, // minutes
+    #[serde(default)]
+    pub repetition: RepetitionConfig, // 重复短语折叠配置
+    #[serde(default = "default_no_speech_threshold")]
+    pub no_speech_threshold: f32, // 无语音概率高于该值的段将被丢弃
+    #[serde(default)]
+    pub output_format: RecordingFormat, // 录音文件保存格式
+    #[serde(default)]
+    pub level_meter: LevelMeterConfig, // 音量表平滑参数
+    #[serde(default)]
+    pub auto_stop_after_silence_secs: Option<u32>, // 连续静音超过该时长后自动停止，None 或 0 表示不启用
+    #[serde(default)]
+    pub decoding: DecodingStrategy, // 解码策略，默认 best_of=1 的贪婪采样以降低延迟
+    #[serde(default)]
+    pub vad_backend: VadBackendKind, // 语音活动检测后端，默认能量阈值
+    #[serde(default)]
+    pub input_gain: Option<f32>, // 输入增益倍数，None 时使用当前设备保存的增益
+    #[serde(default)]
+    pub denoise_backend: DenoiseBackendKind, // noise_reduction 开启时使用的降噪后端，默认不处理
+    #[serde(default = "SegmentationConfig::realtime")]
+    pub segmentation: SegmentationConfig, // 段长度与切分方式
+    #[serde(default)]
+    pub suppression: SuppressionConfig, // 非语音标注过滤
+    #[serde(default = "default_stall_timeout_secs")]
+    pub stall_timeout_secs: u32, // 录音中超过该时长没有收到音频块即判定采集卡住
+    #[serde(default = "default_normalize")]
+    pub normalize: bool, // 识别前把音量标准化到目标 RMS，安静环境下可关闭避免放大底噪
+    #[serde(default)]
+    pub pre_emphasis: Option<f32>, // 预加重系数（如 0.97），None 表示不做预加重
+    #[serde(default)]
+    pub save_native_sample_rate: bool, // 录音文件按设备原生采样率保存，只有送入识别的数据重采样到16kHz
+    #[serde(default)]
+    pub text_join: TextJoinStyle, // 当前转录文本中段与段之间的分隔方式
+    #[serde(default)]
+    pub carry_context: bool, // 把上一段已确定结果的 token 作为下一窗口的提示，提高连续语音的连贯性
+    #[serde(default = "default_max_context_tokens")]
+    pub max_context_tokens: usize, // 携带的上下文 token 上限，超过时只保留末尾部分
+    #[serde(default)]
+    pub auto_name: bool, // 录音结束时用转录内容的第一句作为记录名称，而不是时间戳
+    #[serde(default)]
+    pub max_recording_minutes: Option<u32>, // 单段录音的最长时长，达到后结束当前记录与录音文件并在新记录中继续；None 或 0 表示不拆分
+    #[serde(default = "default_speaker_history")]
+    pub speaker_history: usize, // 说话人匹配时平均的最近片段数（1~50），越大越稳定但换人时反应越慢
+    #[serde(default = "default_overlap_ms")]
+    pub overlap_ms: u32, // 非流式模式下每次识别后保留的重叠音频，提高连续性
+    #[serde(default = "default_streaming_window_ms")]
+    pub streaming_window_ms: u32, // 流式模式的识别窗口（延迟更低）
+    #[serde(default = "default_buffered_window_ms")]
+    pub buffered_window_ms: u32, // 缓冲模式的识别窗口（准确率更高）
+    #[serde(default)]
+    pub initial_prompt: Option<String>, // 提示词模板，与热词一起组装后放在携带的上下文之前
+    #[serde(default)]
+    pub hotwords: Vec<String>, // 自定义词汇，合并进初始提示词
+}
+
+fn default_no_speech_threshold() -> f32 {
+    0.6
+}
+
+fn default_stall_timeout_secs() -> u32 {
+    5
+}
+
+fn default_normalize() -> bool {
+    true
+}
+
+fn default_max_context_tokens() -> usize {
+    64
+}
+
+fn default_speaker_history() -> usize {
+    realtime_speaker_diarization::DEFAULT_SPEAKER_HISTORY
+}
+
+fn default_overlap_ms() -> u32 {
+    250
+}
+
+fn default_streaming_window_ms() -> u32 {
+    250
+}
+
+fn default_buffered_window_ms() -> u32 {
+    2000
+}
+
+const HYBRID_WINDOW_MS: u32 = 500;
+const DEFAULT_WINDOW_MS: u32 = 1000;
+const MIN_WINDOW_MS: u32 = 100; // 窗口过短时 Whisper 无法给出有效结果
+
+/// 按识别模式换算出的采样点窗口
+#[derive(Debug, Clone, PartialEq)]
+struct ProcessingWindows {
+    window: usize,  // 累积到该长度时触发识别
+    overlap: usize, // 识别后保留的尾部长度；流式模式为0
+    interval: Duration, // 两次识别之间的最短间隔，与窗口时长一致
+}
+
+impl ProcessingWindows {
+    fn from_config(config: &RealtimeConfig, sample_rate: u32) -> Result<Self, String> {
+        if sample_rate == 0 {
+            return Err("采样率无效".to_string());
+        }
+        let (window_ms, overlap_ms) = match config.mode.as_str() {
+            "streaming" => (config.streaming_window_ms, 0),
+            "buffered" => (config.buffered_window_ms, config.overlap_ms),
+            "hybrid" => (HYBRID_WINDOW_MS, config.overlap_ms),
+            _ => (DEFAULT_WINDOW_MS, config.overlap_ms),
+        };
+        if window_ms < MIN_WINDOW_MS {
+            return Err(format!("识别窗口 {}ms 过短，至少需要 {}ms", window_ms, MIN_WINDOW_MS));
+        }
+        if overlap_ms >= window_ms {
+            return Err(format!("重叠时长 {}ms 必须小于识别窗口 {}ms", overlap_ms, window_ms));
+        }
+
+        let to_samples = |ms: u32| (ms as u64 * sample_rate as u64 / 1000) as usize;
+        Ok(Self {
+            window: to_samples(window_ms),
+            overlap: to_samples(overlap_ms),
+            interval: Duration::from_millis(window_ms as u64),
+        })
+    }
+
+    fn should_process(&self, buffered: usize) -> bool {
+        buffered >= self.window
+    }
+
+    /// 识别后只保留尾部重叠部分，重叠内容由 SegmentTimeline 去重
+    fn retain_overlap(&self, accumulator: &mut Vec<f32>) {
+        if accumulator.len() > self.overlap {
+            accumulator.drain(..accumulator.len() - self.overlap);
+        }
+    }
+}
+
+/// Whisper 提示最多占文本上下文的一半（448 / 2）
+const MAX_CONTEXT_TOKENS: usize = 224;
+
+/// 预加重系数须在 [0, 1) 内，否则会放大高频到失真
+fn validate_pre_emphasis(pre_emphasis: Option<f32>) -> Result<(), String> {
+    match pre_emphasis {
+        Some(coeff) if !(0.0..1.0).contains(&coeff) => {
+            Err(format!("pre_emphasis 必须在 0 到 1 之间（不含 1），当前为 {}", coeff))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// 启动录音前的全部配置检查；通过后才记住本次配置与增益，避免无效配置被当作下次的默认值
+fn validate_config(config: &RealtimeConfig) -> Result<(), String> {
+    config.decoding.validate()?;
+    config.segmentation.validate()?;
+    validate_pre_emphasis(config.pre_emphasis)?;
+    if let Some(gain) = config.input_gain {
+        if !(0.0..=capture_core::MAX_INPUT_GAIN).contains(&gain) {
+            return Err(format!("input_gain 必须在 0 到 {} 之间，当前为 {}", capture_core::MAX_INPUT_GAIN, gain));
+        }
+    }
+    ProcessingWindows::from_config(config, WHISPER_SAMPLE_RATE)?;
+    Ok(())
+}
+
+impl Default for RealtimeConfig {
+    fn default() -> Self {
+        Self {
+            language: "zh".to_string(),
+            mode: "hybrid".to_string(),
+            speaker_diarization: true,
+            noise_reduction: true,
+            auto_save: true,
+            save_interval: 5,
+            repetition: RepetitionConfig::default(),
+            no_speech_threshold: default_no_speech_threshold(),
+            output_format: RecordingFormat::default(),
+            level_meter: LevelMeterConfig::default(),
+            auto_stop_after_silence_secs: None,
+            decoding: DecodingStrategy::default(),
+            vad_backend: VadBackendKind::default(),
+            input_gain: None,
+            denoise_backend: DenoiseBackendKind::default(),
+            segmentation: SegmentationConfig::realtime(),
+            suppression: SuppressionConfig::default(),
+            stall_timeout_secs: default_stall_timeout_secs(),
+            normalize: default_normalize(),
+            pre_emphasis: None,
+            save_native_sample_rate: false,
+            text_join: TextJoinStyle::default(),
+            carry_context: false,
+            max_context_tokens: default_max_context_tokens(),
+            auto_name: false,
+            max_recording_minutes: None,
+            speaker_history: default_speaker_history(),
+            overlap_ms: default_overlap_ms(),
+            streaming_window_ms: default_streaming_window_ms(),
+            buffered_window_ms: default_buffered_window_ms(),
+            initial_prompt: None,
+            hotwords: Vec::new(),
+        }
+    }
+}
+
+/// 判断Whisper段是否应因无语音概率过高而丢弃
+fn should_suppress_segment(no_speech_prob: f32, threshold: f32) -> bool {
+    no_speech_prob > threshold
+}
+
+/// Whisper 输出的一段，时间相对识别窗口起点（单位10ms）
+#[derive(Debug, Clone)]
+struct WindowSegment {
+    text: String,
+    t0: i64,
+    t1: i64,
+    confidence: Option<f64>,
+    no_speech_prob: f32,
+    tokens: Vec<whisper_token>, // 文本 token（不含特殊 token），用于携带上下文
+}
+
+/// 上一段已确定结果的 token，作为下一窗口的 prompt_tokens；未启用时始终为空
+struct ContextCarryOver {
+    max_tokens: usize,
+    tokens: Vec<whisper_token>,
+}
+
+impl ContextCarryOver {
+    fn new(config: &RealtimeConfig) -> Self {
+        let max_tokens = if config.carry_context { config.max_context_tokens.min(MAX_CONTEXT_TOKENS) } else { 0 };
+        Self { max_tokens, tokens: Vec::new() }
+    }
+
+    /// 追加刚确定的文本 token（已去掉与上一窗口重叠的段），只保留末尾 max_tokens 个
+    fn carry(&mut self, finalized: &[whisper_token]) {
+        if self.max_tokens == 0 {
+            return;
+        }
+        self.tokens.extend_from_slice(finalized);
+        let skip = self.tokens.len().saturating_sub(self.max_tokens);
+        self.tokens.drain(..skip);
+    }
+
+    fn prompt(&self) -> &[whisper_token] {
+        &self.tokens
+    }
+}
+
+/// 把文本提示词转换为 token，最多 MAX_CONTEXT_TOKENS 个；无法转换时返回空
+unsafe fn tokenize_prompt(ctx: *mut whisper_context, text: &str) -> Vec<whisper_token> {
+    let Ok(text) = CString::new(text) else {
+        return Vec::new();
+    };
+    let mut tokens = vec![0; MAX_CONTEXT_TOKENS];
+    let n_tokens = whisper_tokenize(ctx, text.as_ptr(), tokens.as_mut_ptr(), tokens.len() as i32);
+    tokens.truncate(n_tokens.max(0) as usize);
+    tokens
+}
+
+/// 读取最近一次 whisper_full 结果中某段的文本 token（忽略特殊 token）
+unsafe fn segment_text_tokens(ctx: *mut whisper_context, i_segment: i32) -> Vec<whisper_token> {
+    let eot = whisper_token_eot(ctx);
+    (0..whisper_full_n_tokens(ctx, i_segment))
+        .map(|i| whisper_full_get_token_id(ctx, i_segment, i))
+        .filter(|&id| id < eot)
+        .collect()
+}
+
+/// 一个识别窗口整理后的结果，时间为相对录音开始的秒数
+#[derive(Debug, Clone)]
+struct WindowTranscript {
+    text: String,
+    tokens: Vec<whisper_token>, // 新输出段的文本 token，用于携带上下文
+    confidence: Option<f64>,
+    no_speech_prob: Option<f64>, // 新输出段中的最大值
+    start_time: f64,
+    end_time: f64,
+}
+
+/// 把各窗口的段落换算为录音内的绝对时间；相邻识别窗口互相重叠，已输出过的时间范围不再重复输出
+struct SegmentTimeline {
+    last_end: f64,
+}
+
+impl SegmentTimeline {
+    fn new() -> Self {
+        Self { last_end: 0.0 }
+    }
+
+    fn place(&mut self, window_offset: f64, segment: &WindowSegment) -> Option<(f64, f64)> {
+        let start = (window_offset + segment.t0 as f64 / 100.0).max(self.last_end);
+        let end = window_offset + segment.t1 as f64 / 100.0;
+        if end <= start {
+            return None;
+        }
+        self.last_end = end;
+        Some((start, end))
+    }
+}
+
+/// 合并窗口内新出现的段落，并做文本后处理；没有新内容时返回 None
+fn assemble_window(
+    segments: &[WindowSegment],
+    window_offset: f64,
+    timeline: &mut SegmentTimeline,
+    config: &RealtimeConfig,
+) -> Option<WindowTranscript> {
+    let mut text = String::new();
+    let mut tokens = Vec::new();
+    let mut confidence = ConfidenceAccumulator::new();
+    let mut no_speech_prob: Option<f64> = None;
+    let mut span: Option<(f64, f64)> = None;
+
+    for segment in segments {
+        if let Some((start, end)) = timeline.place(window_offset, segment) {
+            text.push_str(&segment.text);
+            tokens.extend_from_slice(&segment.tokens);
+            if let Some(value) = segment.confidence {
+                confidence.add(end - start, value);
+            }
+            no_speech_prob = Some(no_speech_prob.map_or(segment.no_speech_prob as f64, |max| max.max(segment.no_speech_prob as f64)));
+            span = Some((span.map_or(start, |(first, _)| first), end));
+        }
+    }
+
+    let (start_time, end_time) = span?;
+    let text = text_processing::strip_non_speech(&text, &config.suppression);
+    let text = post_process_text_with_config(&text, &config.language, &config.repetition);
+    if text.trim().is_empty() {
+        return None;
+    }
+    Some(WindowTranscript {
+        text,
+        tokens,
+        confidence: confidence.average(),
+        no_speech_prob,
+        start_time,
+        end_time,
+    })
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AudioLevelUpdate {
-    pub level: f32,
+    pub level: f32,     // 0..1，由平滑后的 dBFS 映射
+    pub dbfs: f32,
+    pub peak_dbfs: f32,
     pub timestamp: u64,
 }
 
@@ -42,6 +399,127 @@ pub struct RecognitionResult {
     pub is_temporary: bool,
     pub speaker: Option<String>,
     pub timestamp: u64,
+    pub start_time: f64, // 相对录音开始的秒数，可用于在录音中定位
+    pub end_time: f64,
+    #[serde(default)]
+    pub confidence_band: Option<ConfidenceBand>, // 按设置中的阈值划分的档位，供界面着色
+}
+
+/// 单段录音达到时长上限、切换到新记录时发送
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordingRolledOverEvent {
+    pub session_id: String,
+    pub part: u32, // 新一段的序号，从1开始
+    pub previous_record_id: String,
+    pub record_id: String,
+    pub previous_file_path: Option<String>, // 上一段的录音文件（相对路径），没有可保存的音频时为空
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AutoStoppedEvent {
+    pub silence_secs: u32,
+}
+
+/// 处理线程定期发送，前端长时间收不到即可判断处理线程已退出
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordingHeartbeat {
+    pub uptime_secs: f64,
+    pub last_chunk_at: Option<u64>, // 采集回调最近一次被调用的时间（毫秒时间戳），尚未回调时为空
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordingStalledEvent {
+    pub stalled_secs: f64, // 距上次收到音频块的秒数
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum WatchdogEvent {
+    Heartbeat(RecordingHeartbeat),
+    Stalled(RecordingStalledEvent),
+}
+
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(2);
+
+/// 采集回调最近一次被调用的时间；回调中只做一次原子写入，由处理线程读取后交给看门狗。
+/// 按回调而不是处理线程收到音频块计时，识别耗时较长时不会被误判为采集卡住
+#[derive(Clone)]
+struct CaptureClock {
+    epoch: Instant,
+    last_callback_ms: Arc<AtomicU64>, // 相对 epoch 的毫秒数加1，0 表示尚未回调
+}
+
+impl CaptureClock {
+    fn new() -> Self {
+        Self { epoch: Instant::now(), last_callback_ms: Arc::new(AtomicU64::new(0)) }
+    }
+
+    fn tick(&self) {
+        self.last_callback_ms.store(self.epoch.elapsed().as_millis() as u64 + 1, Ordering::Relaxed);
+    }
+
+    fn last_callback(&self) -> Option<Instant> {
+        match self.last_callback_ms.load(Ordering::Relaxed) {
+            0 => None,
+            ms => Some(self.epoch + Duration::from_millis(ms - 1)),
+        }
+    }
+}
+
+/// 心跳与卡顿检测；暂停期间不计时，卡顿只报告一次，直到重新收到音频
+struct RecordingWatchdog {
+    started_at: Instant,
+    last_chunk_at: Option<Instant>,
+    last_heartbeat_at: Instant,
+    waiting_since: Instant, // 开始等待音频的时间：最近一次音频块、开始录音或恢复录音
+    stall_after: Duration,
+    stalled: bool,
+}
+
+impl RecordingWatchdog {
+    fn new(now: Instant, stall_after: Duration) -> Self {
+        Self {
+            started_at: now,
+            last_chunk_at: None,
+            last_heartbeat_at: now,
+            waiting_since: now,
+            stall_after,
+            stalled: false,
+        }
+    }
+
+    /// 记录一次采集回调；同一时间重复传入时忽略，避免卡顿状态被反复清除
+    fn chunk_received(&mut self, at: Instant) {
+        if self.last_chunk_at.is_some_and(|last| at <= last) {
+            return;
+        }
+        self.last_chunk_at = Some(at);
+        self.waiting_since = self.waiting_since.max(at);
+        self.stalled = false;
+    }
+
+    /// wall_clock_ms 为 now 对应的毫秒时间戳，用于换算上次音频块的时间
+    fn poll(&mut self, now: Instant, wall_clock_ms: u64, paused: bool) -> Vec<WatchdogEvent> {
+        let mut events = Vec::new();
+        if paused {
+            self.waiting_since = now;
+        } else if !self.stalled && now.duration_since(self.waiting_since) >= self.stall_after {
+            self.stalled = true;
+            events.push(WatchdogEvent::Stalled(RecordingStalledEvent {
+                stalled_secs: now.duration_since(self.waiting_since).as_secs_f64(),
+            }));
+        }
+
+        if now.duration_since(self.last_heartbeat_at) >= HEARTBEAT_INTERVAL {
+            self.last_heartbeat_at = now;
+            events.push(WatchdogEvent::Heartbeat(RecordingHeartbeat {
+                uptime_secs: now.duration_since(self.started_at).as_secs_f64(),
+                last_chunk_at: self.last_chunk_at.map(|at| {
+                    wall_clock_ms.saturating_sub(now.duration_since(at).as_millis() as u64)
+                }),
+            }));
+        }
+        events
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -57,62 +535,75 @@ struct AudioProcessor {
     audio_buffer: Vec<f32>,
     continuous_buffer: Vec<f32>, // 连续的音频缓冲区
     last_recognition_time: Instant,
-    recognition_interval: Duration, // 识别间隔
-    min_audio_length: usize, // 最小音频长度(样本数)
+    windows: ProcessingWindows, // 按识别模式配置的窗口与重叠
     max_audio_length: usize, // 最大音频长度(样本数)
-    activity_threshold: f32, // 活动检测阈值
+    vad: Box<dyn VadBackend>, // 活动检测
     speaker_diarization: RealtimeSpeakerDiarization,
+    silent_samples: usize, // 连续静音的样本数
+    auto_stop_samples: Option<usize>, // 连续静音达到该样本数时自动停止
+    fed_samples: usize, // 已送入的样本总数，用于换算说话人轮次的时间
 }
 
 impl AudioProcessor {
-    fn new() -> Result<Self, String> {
+    fn new(windows: ProcessingWindows, auto_stop_after_silence_secs: Option<u32>, vad: Box<dyn VadBackend>) -> Result<Self, String> {
         Ok(Self {
             audio_buffer: Vec::new(),
             continuous_buffer: Vec::new(),
             last_recognition_time: Instant::now(),
-            recognition_interval: Duration::from_millis(2000), // 每2秒识别一次
-            min_audio_length: 16000, // 1秒的音频 (16kHz)
-            max_audio_length: 16000 * 10, // 10秒的音频
-            activity_threshold: 0.005, // 活动检测阈值
+            windows,
+            max_audio_length: WHISPER_SAMPLE_RATE as usize * 10, // 10秒的音频
+            vad,
             speaker_diarization: RealtimeSpeakerDiarization::new(),
+            silent_samples: 0,
+            auto_stop_samples: auto_stop_after_silence_secs
+                .filter(|&secs| secs > 0)
+                .map(|secs| secs as usize * WHISPER_SAMPLE_RATE as usize),
+            fed_samples: 0,
         })
     }
+
+    /// 连续静音是否已超过自动停止时长
+    fn silence_limit_exceeded(&self) -> bool {
+        self.auto_stop_samples.is_some_and(|limit| self.silent_samples >= limit)
+    }
     
-    fn process_audio_chunk(&mut self, audio: &[f32]) -> Option<(Vec<f32>, Option<String>)> {
+    fn process_audio_chunk(&mut self, audio: &[f32]) -> Option<(Vec<f32>, Option<String>, Option<SpeakerChange>)> {
         // 添加音频到连续缓冲区
         self.continuous_buffer.extend_from_slice(audio);
-        
-        // 计算当前音频块的平均音量
-        let current_level = audio.iter().map(|&x| x.abs()).sum::<f32>() / audio.len() as f32;
+        self.fed_samples += audio.len();
         
         // 检查是否有活动音频
-        let has_activity = current_level > self.activity_threshold;
+        let has_activity = self.vad.is_voiced(audio);
         
+        // 任何有声块都会清零连续静音计数，短暂停顿不会触发自动停止
         if has_activity {
-            println!("🎵 Audio activity detected: level={:.6}", current_level);
+            log::debug!("🎵 Audio activity detected");
+            self.silent_samples = 0;
+        } else {
+            self.silent_samples += audio.len();
         }
         
         // 限制缓冲区大小，避免内存溢出
         if self.continuous_buffer.len() > self.max_audio_length {
             let excess = self.continuous_buffer.len() - self.max_audio_length;
             self.continuous_buffer.drain(..excess);
-            println!("🔄 Buffer trimmed, removed {} samples", excess);
+            log::debug!("🔄 Buffer trimmed, removed {} samples", excess);
         }
         
         // 定期或检测到活动时进行识别
         let should_recognize = {
             let time_since_last = self.last_recognition_time.elapsed();
-            let has_enough_audio = self.continuous_buffer.len() >= self.min_audio_length;
+            let has_enough_audio = self.windows.should_process(self.continuous_buffer.len());
             
             // 如果有活动且距离上次识别超过间隔时间，或者缓冲区快满了
-            (has_activity && time_since_last >= self.recognition_interval && has_enough_audio) ||
+            (has_activity && time_since_last >= self.windows.interval && has_enough_audio) ||
             (self.continuous_buffer.len() >= self.max_audio_length * 8 / 10) // 80%满时强制识别
         };
         
         if should_recognize {
-            println!("🔍 Triggering recognition: buffer_size={} samples ({:.1}s), activity={}, time_elapsed={:.1}s", 
+            log::debug!("🔍 Triggering recognition: buffer_size={} samples ({:.1}s), activity={}, time_elapsed={:.1}s", 
                 self.continuous_buffer.len(), 
-                self.continuous_buffer.len() as f32 / 16000.0,
+                self.continuous_buffer.len() as f32 / WHISPER_SAMPLE_RATE as f32,
                 has_activity, 
                 self.last_recognition_time.elapsed().as_secs_f32());
             
@@ -120,18 +611,22 @@ impl AudioProcessor {
             let recognition_length = self.continuous_buffer.len().min(self.max_audio_length);
             let start_pos = self.continuous_buffer.len() - recognition_length;
             let audio_for_recognition = self.continuous_buffer[start_pos..].to_vec();
+            // 流式模式不保留重叠，其他模式保留配置的重叠以提高连续性
+            self.windows.retain_overlap(&mut self.continuous_buffer);
             
             // 更新最后识别时间
             self.last_recognition_time = Instant::now();
             
             // 进行说话人识别
-            let speaker = if has_activity {
-                self.speaker_diarization.identify_speaker(&audio_for_recognition)
+            let (speaker, speaker_change) = if has_activity {
+                let end_time = self.fed_samples as f64 / WHISPER_SAMPLE_RATE as f64;
+                let start_time = end_time - audio_for_recognition.len() as f64 / WHISPER_SAMPLE_RATE as f64;
+                self.speaker_diarization.identify_speaker_at(&audio_for_recognition, start_time, end_time)
             } else {
-                None
+                (None, None)
             };
             
-            return Some((audio_for_recognition, speaker));
+            return Some((audio_for_recognition, speaker, speaker_change));
         }
         
         None
@@ -147,6 +642,121 @@ enum AudioCommand {
     Stop,
 }
 
+/// 已输出识别结果的共享缓冲区，前端重连后可据此取回当前转录与说话人时间线
+#[derive(Clone)]
+struct RealtimeResults {
+    manager: Arc<Mutex<ResultManager>>,
+    speaker_turns: Arc<Mutex<Vec<SpeakerTimeRange>>>,
+    speaker_reset: Arc<AtomicBool>, // 等待处理线程重置说话人识别
+}
+
+impl RealtimeResults {
+    fn new(join_style: TextJoinStyle, language: &str) -> Self {
+        let mut manager = ResultManager::new(1000); // 最多保存1000个段落
+        manager.set_join_style(join_style);
+        manager.set_language(language);
+        Self {
+            manager: Arc::new(Mutex::new(manager)),
+            speaker_turns: Arc::new(Mutex::new(Vec::new())),
+            speaker_reset: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// 清空说话人时间线，并通知处理线程在下一块音频前重置说话人识别
+    fn request_speaker_reset(&self) {
+        if let Ok(mut turns) = self.speaker_turns.lock() {
+            turns.clear();
+        }
+        self.speaker_reset.store(true, Ordering::SeqCst);
+    }
+
+    fn take_speaker_reset(&self) -> bool {
+        self.speaker_reset.swap(false, Ordering::SeqCst)
+    }
+
+    /// 同步说话人时间线：新轮次追加，当前轮次只更新结束时间
+    fn record_speaker_turns(&self, timeline: &[SpeakerTimeRange]) {
+        if let (Ok(mut turns), Some(last)) = (self.speaker_turns.lock(), timeline.last()) {
+            if turns.len() == timeline.len() {
+                turns.pop();
+            }
+            turns.push(last.clone());
+        }
+    }
+
+    fn speaker_timeline(&self) -> Result<Vec<SpeakerTimeRange>, String> {
+        let turns = self.speaker_turns.lock().map_err(|e| format!("Failed to lock speaker timeline: {}", e))?;
+        Ok(turns.clone())
+    }
+
+    fn record(&self, result: &RecognitionResult, segment_id: u32) {
+        if let Ok(mut manager) = self.manager.lock() {
+            manager.process_result(TranscriptResult {
+                text: result.text.clone(),
+                confidence: result.confidence,
+                is_temporary: result.is_temporary,
+                speaker: result.speaker.clone(),
+                timestamp: result.timestamp,
+                processing_time_ms: 0,
+                segment_id: segment_id.to_string(),
+            });
+        }
+    }
+
+    fn current_transcript(&self) -> Result<String, String> {
+        let manager = self.manager.lock().map_err(|e| format!("Failed to lock result manager: {}", e))?;
+        Ok(manager.get_continuous_text(None))
+    }
+
+    fn segments(&self) -> Result<Vec<ManagedTranscriptSegment>, String> {
+        let manager = self.manager.lock().map_err(|e| format!("Failed to lock result manager: {}", e))?;
+        Ok(manager.get_all_segments().iter().cloned().collect())
+    }
+}
+
+/// 录音文件相关的共享状态；超过单段时长上限时由录音写入线程切换到新文件
+#[derive(Clone)]
+struct RecordingFiles {
+    writer: Arc<Mutex<Option<StreamingWavWriter>>>,
+    sample_rate: Arc<Mutex<u32>>,
+    audio_data: Arc<Mutex<Vec<f32>>>,
+    dir: Option<std::path::PathBuf>,
+    current_record_id: Arc<Mutex<String>>,
+    format: RecordingFormat,
+    writer_thread: Arc<Mutex<Option<thread::JoinHandle<()>>>>, // 录音写入线程，停止时等待其写完剩余数据
+}
+
+impl RecordingFiles {
+    /// 结束当前录音文件并为 record_id 打开新文件，返回上一段的文件路径。
+    /// 先换上新的写入器再完成旧文件，FLAC 编码等耗时操作不在持锁期间进行；
+    /// 没有增量写入器时把内存中缓存的音频写成上一段的文件
+    fn roll_over(&self, record_id: &str) -> Result<Option<std::path::PathBuf>, String> {
+        let dir = self.dir.as_ref().ok_or("录音目录不可用")?;
+        let sample_rate = *self.sample_rate.lock().unwrap();
+        let next_writer = StreamingWavWriter::create(&dir.join(record_id), self.format, sample_rate)
+            .map_err(|e| log::warn!("无法创建新一段的增量录音文件，将在结束时一次性保存: {}", e))
+            .ok();
+        let previous_record_id = std::mem::replace(&mut *self.current_record_id.lock().unwrap(), record_id.to_string());
+        let previous_writer = std::mem::replace(&mut *self.writer.lock().unwrap(), next_writer);
+        let buffered = std::mem::take(&mut *self.audio_data.lock().unwrap());
+
+        match previous_writer {
+            Some(writer) => writer.finalize().map(Some),
+            None if !buffered.is_empty() => {
+                recording_writer::write_recording_at(&dir.join(&previous_record_id), &buffered, self.format, sample_rate).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+/// 录音写入线程切换到新文件后通知处理线程；处理线程的识别进度到达该段起点时再切换转录记录
+struct FileRollover {
+    part: RecordingPart,
+    previous_record_id: String,
+    previous_file_path: Option<String>, // 上一段的录音文件（相对路径）
+}
+
 // 线程安全的音频管理器
 pub struct RealtimeAudioCapture {
     command_tx: Option<mpsc::Sender<AudioCommand>>,
@@ -156,32 +766,37 @@ pub struct RealtimeAudioCapture {
     recognition_config: RealtimeConfig,
     app_handle: AppHandle,
     audio_data: Arc<Mutex<Vec<f32>>>, // 保存录音数据
-    recording_id: String, // 录音ID
+    recording_writer: Arc<Mutex<Option<StreamingWavWriter>>>, // 录音过程中增量写盘
+    recording_sample_rate: Arc<Mutex<u32>>, // 录音文件的采样率，采集格式确定后更新
+    recording_writer_thread: Arc<Mutex<Option<thread::JoinHandle<()>>>>, // 采集回调只投递样本，由该线程写盘
+    recording_id: String, // 录音ID，自动拆分时作为会话ID
+    current_record_id: Arc<Mutex<String>>, // 正在写入的记录与录音文件，自动拆分后指向最新一段
+    results: RealtimeResults, // 已输出的识别结果
 }
 
 impl RealtimeAudioCapture {
     pub fn new(app_handle: AppHandle, config: RealtimeConfig) -> Result<Self, Box<dyn std::error::Error>> {
-        println!("🔧 创建 RealtimeAudioCapture 实例...");
+        log::debug!("🔧 创建 RealtimeAudioCapture 实例...");
         
         // 检查音频设备可用性
         let host = cpal::default_host();
-        println!("音频主机: {:?}", host.id());
+        log::debug!("音频主机: {:?}", host.id());
         
-        // 检查默认输入设备
-        match host.default_input_device() {
-            Some(device) => {
+        // 检查输入设备（选定设备不可用时回退到默认设备）
+        match capture_core::select_input_device(&host) {
+            Ok(device) => {
                 if let Ok(name) = device.name() {
-                    println!("✅ 找到默认输入设备: {}", name);
+                    log::debug!("✅ 找到输入设备: {}", name);
                 } else {
-                    println!("⚠️ 默认输入设备无法获取名称");
+                    log::warn!("⚠️ 输入设备无法获取名称");
                 }
             }
-            None => {
+            Err(_) => {
                 return Err("❌ 未找到任何音频输入设备，请检查麦克风连接".into());
             }
         }
         
-        println!("✅ RealtimeAudioCapture 实例创建成功");
+        log::debug!("✅ RealtimeAudioCapture 实例创建成功");
         
         // 生成唯一的录音ID
         let recording_id = format!("recording_{}", std::time::SystemTime::now()
@@ -189,6 +804,7 @@ impl RealtimeAudioCapture {
             .unwrap()
             .as_millis());
         
+        let results = RealtimeResults::new(config.text_join, &config.language);
         Ok(Self {
             command_tx: None,
             is_recording: Arc::new(Mutex::new(false)),
@@ -197,7 +813,12 @@ impl RealtimeAudioCapture {
             recognition_config: config,
             app_handle,
             audio_data: Arc::new(Mutex::new(Vec::new())),
+            recording_writer: Arc::new(Mutex::new(None)),
+            recording_sample_rate: Arc::new(Mutex::new(recording_writer::RECORDING_SAMPLE_RATE)),
+            recording_writer_thread: Arc::new(Mutex::new(None)),
+            current_record_id: Arc::new(Mutex::new(recording_id.clone())),
             recording_id,
+            results,
         })
     }
 
@@ -213,7 +834,26 @@ impl RealtimeAudioCapture {
         let is_paused = self.is_paused.clone();
         let app_handle = self.app_handle.clone();
         let config = self.recognition_config.clone();
-        let audio_data = self.audio_data.clone();
+
+        // 录音文件在采集格式确定后由音频线程打开
+        let recording_path = match self.recordings_dir() {
+            Ok(dir) => Some(dir.join(&self.recording_id)),
+            Err(e) => {
+                log::warn!("无法创建录音目录，将在停止时一次性保存: {}", e);
+                None
+            }
+        };
+        let recording_files = RecordingFiles {
+            writer: self.recording_writer.clone(),
+            sample_rate: self.recording_sample_rate.clone(),
+            audio_data: self.audio_data.clone(),
+            dir: recording_path.as_ref().and_then(|path| path.parent().map(|dir| dir.to_path_buf())),
+            current_record_id: self.current_record_id.clone(),
+            format: config.output_format,
+            writer_thread: self.recording_writer_thread.clone(),
+        };
+        let recording_id = self.recording_id.clone();
+        let results = self.results.clone();
 
         // 启动独立的音频处理线程
         thread::spawn(move || {
@@ -224,19 +864,44 @@ impl RealtimeAudioCapture {
                 app_handle,
                 config,
                 whisper_state,
-                audio_data,
+                recording_files,
+                recording_path,
+                recording_id,
+                results,
             );
         });
 
         Ok(())
     }
 
+    fn recordings_dir(&self) -> Result<std::path::PathBuf, StenoError> {
+        data_dir::resolve_subdir(&self.app_handle, "recordings")
+    }
+
+    // 保存采集到的音频：写入内存缓冲区并追加到录音文件（在录音写入线程中执行）
+    fn store_samples(
+        storage: &Arc<Mutex<Vec<f32>>>,
+        recording_writer: &Arc<Mutex<Option<StreamingWavWriter>>>,
+        samples: &[f32],
+    ) {
+        if let Ok(mut storage) = storage.lock() {
+            storage.extend_from_slice(samples);
+        }
+        if let Ok(mut writer) = recording_writer.lock() {
+            if let Some(ref mut writer) = *writer {
+                if let Err(e) = writer.append(samples) {
+                    log::error!("写入录音文件失败: {}", e);
+                }
+            }
+        }
+    }
+
     pub fn pause_recording(&mut self) -> Result<(), Box<dyn std::error::Error>> {
         *self.is_paused.lock().unwrap() = true;
         if let Some(ref tx) = self.command_tx {
             let _ = tx.send(AudioCommand::Pause);
         }
-        println!("Recording paused");
+        log::info!("Recording paused");
         Ok(())
     }
 
@@ -245,12 +910,12 @@ impl RealtimeAudioCapture {
         if let Some(ref tx) = self.command_tx {
             let _ = tx.send(AudioCommand::Resume);
         }
-        println!("Recording resumed");
+        log::info!("Recording resumed");
         Ok(())
     }
 
     pub fn stop_recording(&mut self) -> Result<(), Box<dyn std::error::Error>> {
-        println!("Stopping recording...");
+        log::info!("Stopping recording...");
         
         // 立即设置停止标志
         *self.is_recording.lock().unwrap() = false;
@@ -259,7 +924,7 @@ impl RealtimeAudioCapture {
         // 发送停止命令
         if let Some(ref tx) = self.command_tx {
             let _ = tx.send(AudioCommand::Stop);
-            println!("Stop command sent to audio thread");
+            log::debug!("Stop command sent to audio thread");
         }
         
         // 清理命令通道
@@ -267,60 +932,80 @@ impl RealtimeAudioCapture {
         
         // 保存录音文件 - 移除 await 调用
         if let Err(e) = self.save_audio_file() {
-            eprintln!("保存录音文件失败: {}", e);
+            log::error!("保存录音文件失败: {}", e);
+            let _ = self.app_handle.emit("recording_save_failed", format!("保存录音文件失败: {}", e));
+        }
+
+        if self.recognition_config.speaker_diarization {
+            self.emit_diarization_summary();
         }
         
         // 发送停止完成事件
         let _ = self.app_handle.emit("recording_stopped", ());
         let _ = self.app_handle.emit("recording_completed", ());
         
-        println!("Recording stopped successfully");
+        log::info!("Recording stopped successfully");
         Ok(())
     }
 
+    /// 由说话人时间线生成汇总，发送 diarization_summary 事件并随当前记录保存
+    fn emit_diarization_summary(&self) {
+        let timeline = match self.results.speaker_timeline() {
+            Ok(timeline) if !timeline.is_empty() => timeline,
+            Ok(_) => return,
+            Err(e) => {
+                log::warn!("⚠️ 读取说话人时间线失败: {}", e);
+                return;
+            }
+        };
+        let summary = realtime_speaker_diarization::summarize_timeline(&timeline);
+        log::info!("🗣️ 本次录音共识别到 {} 位说话人", summary.speaker_count);
+
+        let record_id = self.current_record_id.lock().unwrap().clone();
+        let saved = self.app_handle.state::<StorageState>()
+            .with_storage(|storage| storage.save_diarization_summary(&record_id, &summary));
+        if let Err(e) = saved {
+            log::warn!("⚠️ 保存说话人分离汇总失败: {}", e);
+        }
+        let _ = self.app_handle.emit("diarization_summary", summary);
+    }
+
     fn save_audio_file(&self) -> Result<(), Box<dyn std::error::Error>> {
-        use std::fs::File;
-        use std::io::BufWriter;
-        
-        // 获取音频数据
-        let audio_data = self.audio_data.lock().unwrap().clone();
-        if audio_data.is_empty() {
-            println!("没有音频数据可保存");
-            return Ok(());
+        // 采集流关闭后写入线程会写完队列中剩余的样本再退出
+        if let Some(handle) = self.recording_writer_thread.lock().unwrap().take() {
+            if handle.join().is_err() {
+                log::error!("录音写入线程异常退出");
+            }
         }
-        
-        // 获取应用数据目录
-        let app_data_dir = self.app_handle.path().app_data_dir()?;
-        let recordings_dir = app_data_dir.join("recordings");
-        
-        // 创建录音目录
-        std::fs::create_dir_all(&recordings_dir)?;
-        
-        // 生成文件名
-        let filename = format!("{}.wav", self.recording_id);
-        let file_path = recordings_dir.join(&filename);
-        
-        // 创建WAV文件
-        let spec = hound::WavSpec {
-            channels: 1,
-            sample_rate: 16000,
-            bits_per_sample: 32,
-            sample_format: hound::SampleFormat::Float,
+
+        // 优先完成增量写入的录音文件
+        let streaming_writer = self.recording_writer.lock().unwrap().take();
+        let file_path = if let Some(writer) = streaming_writer {
+            writer.finalize()?
+        } else {
+            // 获取音频数据
+            let audio_data = self.audio_data.lock().unwrap().clone();
+            if audio_data.is_empty() {
+                log::debug!("没有音频数据可保存");
+                return Ok(());
+            }
+            
+            // 按配置的格式与采集时的采样率写入文件
+            let record_id = self.current_record_id.lock().unwrap().clone();
+            recording_writer::write_recording_at(
+                &self.recordings_dir()?.join(record_id),
+                &audio_data,
+                self.recognition_config.output_format,
+                *self.recording_sample_rate.lock().unwrap(),
+            )?
         };
         
-        let file = File::create(&file_path)?;
-        let mut writer = hound::WavWriter::new(BufWriter::new(file), spec)?;
-        
-        // 写入音频数据
-        for &sample in &audio_data {
-            writer.write_sample(sample)?;
-        }
-        
-        writer.finalize()?;
-        
-        println!("录音文件已保存: {:?}", file_path);
+        log::info!("录音文件已保存: {:?}", file_path);
         
         // 发送录音文件路径事件 - 使用相对路径，便于前端访问
+        let filename = file_path.file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
         let relative_path = format!("recordings/{}", filename);
         let _ = self.app_handle.emit("recording_file_saved", relative_path);
         
@@ -329,10 +1014,16 @@ impl RealtimeAudioCapture {
 
     // 获取录音文件的完整路径
     pub fn get_audio_file_path(&self) -> Result<String, Box<dyn std::error::Error>> {
-        let app_data_dir = self.app_handle.path().app_data_dir()?;
-        let recordings_dir = app_data_dir.join("recordings");
-        let filename = format!("{}.wav", self.recording_id);
-        let file_path = recordings_dir.join(&filename);
+        let recordings_dir = data_dir::resolve_data_dir(&self.app_handle)?.join("recordings");
+        let record_id = self.current_record_id.lock().unwrap().clone();
+        let preferred = recordings_dir.join(&record_id)
+            .with_extension(self.recognition_config.output_format.extension());
+        // FLAC编码不可用时会回退为WAV
+        let file_path = if preferred.exists() {
+            preferred
+        } else {
+            recordings_dir.join(format!("{}.wav", record_id))
+        };
         Ok(file_path.to_string_lossy().to_string())
     }
 
@@ -343,253 +1034,116 @@ impl RealtimeAudioCapture {
         app_handle: AppHandle,
         config: RealtimeConfig,
         whisper_state: Arc<WhisperContextState>,
-        audio_data: Arc<Mutex<Vec<f32>>>,
+        recording_files: RecordingFiles,
+        recording_path: Option<std::path::PathBuf>,
+        recording_id: String,
+        results: RealtimeResults,
     ) {
-        println!("Starting audio thread");
+        log::debug!("Starting audio thread");
         
         // 获取音频主机
         let host = cpal::default_host();
-        println!("Audio host: {:?}", host.id());
-        
-        // 列出所有输入设备
-        match host.input_devices() {
-            Ok(devices) => {
-                println!("Available input devices:");
-                for (i, device) in devices.enumerate() {
-                    if let Ok(name) = device.name() {
-                        println!("  {}: {}", i, name);
-                    }
-                }
-            }
-            Err(e) => {
-                eprintln!("Failed to enumerate input devices: {}", e);
-            }
-        }
-        
-        // 获取选定的输入设备（如果没有选择则使用默认设备）
-        let device = match get_selected_input_device_sync(&host) {
-            Ok(device) => {
-                if let Ok(name) = device.name() {
-                    println!("Using selected input device: {}", name);
-                } else {
-                    println!("Using selected input device (name unavailable)");
-                }
-                device
-            }
-            Err(e) => {
-                eprintln!("Failed to get selected device, falling back to default: {}", e);
-                match host.default_input_device() {
-                    Some(device) => {
-                        if let Ok(name) = device.name() {
-                            println!("Using default input device: {}", name);
-                        }
-                        device
-                    }
-                    None => {
-                        eprintln!("No input device available");
-                        let _ = app_handle.emit("recording_error", "No input device available");
-                        return;
-                    }
-                }
-            }
-        };
+        log::debug!("Audio host: {:?}", host.id());
         
-        // 检查设备支持的配置
-        let supported_configs = match device.supported_input_configs() {
-            Ok(configs) => configs.collect::<Vec<_>>(),
+        // 选定输入设备与采集格式（没有选择设备时使用默认设备）
+        let (device, capture_format) = match capture_core::open_input(&host, config.save_native_sample_rate) {
+            Ok(input) => input,
             Err(e) => {
-                eprintln!("Failed to get supported configs: {}", e);
-                let _ = app_handle.emit("recording_error", format!("Failed to get supported configs: {}", e));
+                log::error!("{}", e);
+                let _ = app_handle.emit("recording_error", e);
                 return;
             }
         };
-        
-        println!("Supported input configurations:");
-        for (i, config) in supported_configs.iter().enumerate() {
-            println!("  {}: {:?}", i, config);
+        if let Ok(name) = device.name() {
+            log::info!("Using input device: {}", name);
+        }
+        log::info!("Selected config: channels={}, sample_rate={}, sample_format={:?}, need_resample={}", 
+                capture_format.channels(), capture_format.sample_rate(), capture_format.sample_format, capture_format.needs_resample());
+
+        // 采集格式确定后打开录音文件，采集过程中持续写入，避免崩溃丢失整段录音
+        let save_native = config.save_native_sample_rate;
+        let sample_rate = capture_format.recording_sample_rate(save_native);
+        *recording_files.sample_rate.lock().unwrap() = sample_rate;
+        if let Some(path) = &recording_path {
+            match StreamingWavWriter::create(path, config.output_format, sample_rate) {
+                Ok(writer) => *recording_files.writer.lock().unwrap() = Some(writer),
+                Err(e) => log::warn!("无法创建增量录音文件，将在停止时一次性保存: {}", e),
+            }
         }
-        
-        // 查找最佳配置 - 使用设备支持的最低采样率
-        let (stream_config, sample_format, need_resample, original_sample_rate) = if let Some(config) = supported_configs.iter()
-            .find(|c| c.channels() >= 1 && c.min_sample_rate() <= SampleRate(16000) && c.max_sample_rate() >= SampleRate(16000)) {
-            // 使用支持的配置
-            let config_range = config.clone();
-            let stream_config = config_range.with_sample_rate(SampleRate(16000)).config();
-            (stream_config, config_range.sample_format(), false, 16000)
-        } else if let Some(config) = supported_configs.first() {
-            // 使用第一个可用配置，稍后重采样
-            let original_rate = config.min_sample_rate().0;
-            println!("Using config with resampling from {}Hz to 16kHz", original_rate);
-            let config_range = config.clone();
-            let stream_config = config_range.with_sample_rate(config.min_sample_rate()).config();
-            (stream_config, config_range.sample_format(), true, original_rate)
-        } else {
-            eprintln!("No supported input configurations found");
-            let _ = app_handle.emit("recording_error", "No supported input configurations found");
-            return;
-        };
-        
-        println!("Selected config: channels={}, sample_rate={:?}, sample_format={:?}, need_resample={}", 
-                stream_config.channels, stream_config.sample_rate, sample_format, need_resample);
         
         let (audio_tx, audio_rx) = mpsc::channel::<Vec<f32>>();
-        let (level_tx, level_rx) = mpsc::channel::<f32>();
+        let (level_tx, level_rx) = mpsc::channel::<LevelReading>();
+        let (record_tx, record_rx) = mpsc::channel::<Vec<f32>>();
+        let (rollover_tx, rollover_rx) = mpsc::channel::<FileRollover>();
+
+        // 文件写入可能阻塞，不能放在采集回调中；采集流关闭后发送端释放，线程写完剩余数据后退出
+        let writer_files = recording_files.clone();
+        let splitter = RecordingSplitter::new(&config, &recording_id, sample_rate);
+        *recording_files.writer_thread.lock().unwrap() = Some(thread::spawn(move || {
+            Self::recording_writer_thread(writer_files, record_rx, splitter, rollover_tx);
+        }));
+        let mut level_meter = capture_format.level_meter(config.level_meter);
         
         let is_recording_stream = is_recording.clone();
         let is_paused_stream = is_paused.clone();
-        let audio_data_storage = audio_data.clone();
-        
-        // 创建音频流回调
-        let stream = match sample_format {
-            cpal::SampleFormat::I8 => {
-                device.build_input_stream(
-                    &stream_config,
-                    move |data: &[i8], _: &cpal::InputCallbackInfo| {
-                        let recording = *is_recording_stream.lock().unwrap();
-                        let paused = *is_paused_stream.lock().unwrap();
-                        if recording && !paused {
-                            let float_data: Vec<f32> = data.iter().map(|&x| x as f32 / 128.0).collect();
-                            let level = float_data.iter().map(|&sample| sample.abs()).sum::<f32>() / float_data.len() as f32;
-                            let _ = level_tx.send(level);
-                            
-                            // 保存原始音频数据
-                            if let Ok(mut storage) = audio_data_storage.lock() {
-                                storage.extend_from_slice(&float_data);
-                            }
-                            
-                            let _ = audio_tx.send(float_data);
-                        }
-                    },
-                    |err| eprintln!("Audio stream error: {}", err),
-                    None,
-                )
-            }
-            cpal::SampleFormat::I16 => {
-                device.build_input_stream(
-                    &stream_config,
-                    move |data: &[i16], _: &cpal::InputCallbackInfo| {
-                        let recording = *is_recording_stream.lock().unwrap();
-                        let paused = *is_paused_stream.lock().unwrap();
-                        if recording && !paused {
-                            let float_data: Vec<f32> = data.iter().map(|&x| x as f32 / 32768.0).collect();
-                            let level = float_data.iter().map(|&sample| sample.abs()).sum::<f32>() / float_data.len() as f32;
-                            let _ = level_tx.send(level);
-                            
-                            // 保存原始音频数据
-                            if let Ok(mut storage) = audio_data_storage.lock() {
-                                storage.extend_from_slice(&float_data);
-                            }
-                            
-                            let _ = audio_tx.send(float_data);
-                        }
-                    },
-                    |err| eprintln!("Audio stream error: {}", err),
-                    None,
-                )
-            }
-            cpal::SampleFormat::I32 => {
-                device.build_input_stream(
-                    &stream_config,
-                    move |data: &[i32], _: &cpal::InputCallbackInfo| {
-                        let recording = *is_recording_stream.lock().unwrap();
-                        let paused = *is_paused_stream.lock().unwrap();
-                        if recording && !paused {
-                            let float_data: Vec<f32> = data.iter().map(|&x| x as f32 / 2147483648.0).collect();
-                            let level = float_data.iter().map(|&sample| sample.abs()).sum::<f32>() / float_data.len() as f32;
-                            let _ = level_tx.send(level);
-                            
-                            // 保存原始音频数据
-                            if let Ok(mut storage) = audio_data_storage.lock() {
-                                storage.extend_from_slice(&float_data);
-                            }
-                            
-                            let _ = audio_tx.send(float_data);
-                        }
-                    },
-                    |err| eprintln!("Audio stream error: {}", err),
-                    None,
-                )
-            }
-            cpal::SampleFormat::F32 => {
-                device.build_input_stream(
-                    &stream_config,
-                    move |data: &[f32], _: &cpal::InputCallbackInfo| {
-                        let recording = *is_recording_stream.lock().unwrap();
-                        let paused = *is_paused_stream.lock().unwrap();
-                        if recording && !paused {
-                            let level = data.iter().map(|&sample| sample.abs()).sum::<f32>() / data.len() as f32;
-                            let _ = level_tx.send(level);
-                            // 重采样到16kHz（如果需要）
-                            let float_data = if need_resample {
-                                let ratio = original_sample_rate as f64 / 16000.0;
-                                let output_len = (data.len() as f64 / ratio) as usize;
-                                let mut resampled = Vec::with_capacity(output_len);
-                                
-                                for i in 0..output_len {
-                                    let src_index = (i as f64 * ratio) as usize;
-                                    if src_index < data.len() {
-                                        resampled.push(data[src_index]);
-                                    } else {
-                                        resampled.push(0.0);
-                                    }
-                                }
-                                println!("Resampled audio: {} -> {} samples", data.len(), resampled.len());
-                                resampled
-                            } else {
-                                data.to_vec()
-                            };
-                            
-                            // 保存原始音频数据
-                            if let Ok(mut storage) = audio_data_storage.lock() {
-                                storage.extend_from_slice(&float_data);
-                            }
-                            
-                            // 发送音频数据到处理线程
-                            if let Err(_) = audio_tx.send(float_data.clone()) {
-                                println!("Failed to send audio data to processing thread");
-                            } else {
-                                println!("Sent {} audio samples to processing thread", float_data.len());
-                            }
-                        }
-                    },
-                    |err| eprintln!("Audio stream error: {}", err),
-                    None,
-                )
-            }
-            _ => {
-                eprintln!("Unsupported sample format: {:?}", sample_format);
-                let _ = app_handle.emit("recording_error", format!("Unsupported sample format: {:?}", sample_format));
-                return;
+        let mut converter = capture_format.converter();
+        let input_gain = config.input_gain.unwrap_or(1.0);
+        let capture_clock = CaptureClock::new();
+        let callback_clock = capture_clock.clone();
+        
+        // 创建音频流回调：施加输入增益并混为单声道，按配置保存原生采样率或16kHz录音，16kHz数据送入识别线程
+        let stream = capture_core::build_input_stream(&device, &capture_format, move |data: &[f32]| {
+            callback_clock.tick();
+            let recording = *is_recording_stream.lock().unwrap();
+            let paused = *is_paused_stream.lock().unwrap();
+            if recording && !paused {
+                let data = capture_core::apply_gain(data, input_gain);
+                let _ = level_tx.send(level_meter.process(&data));
+                
+                let (native_data, float_data) = converter.process_split(&data);
+                
+                // 保存原始音频数据：交给录音写入线程
+                let recorded = if save_native { native_data } else { float_data.clone() };
+                if record_tx.send(recorded).is_err() {
+                    log::error!("Failed to send audio data to recording writer thread");
+                }
+                
+                // 发送音频数据到处理线程
+                if audio_tx.send(float_data).is_err() {
+                    log::error!("Failed to send audio data to processing thread");
+                }
             }
-        };
+        });
         
         let stream = match stream {
             Ok(stream) => {
-                println!("Audio stream created successfully");
+                log::debug!("Audio stream created successfully");
                 stream
             }
             Err(e) => {
-                eprintln!("Failed to build audio stream: {}", e);
-                let _ = app_handle.emit("recording_error", format!("Failed to build audio stream: {}", e));
+                log::error!("{}", e);
+                let _ = app_handle.emit("recording_error", e);
                 return;
             }
         };
         
         if let Err(e) = stream.play() {
-            eprintln!("Failed to start audio stream: {}", e);
+            log::error!("Failed to start audio stream: {}", e);
             let _ = app_handle.emit("recording_error", format!("Failed to start audio stream: {}", e));
             return;
         } else {
-            println!("Audio stream started successfully");
+            log::info!("Audio stream started successfully");
             let _ = app_handle.emit("recording_started", ());
         }
         
         // 启动音频级别监控线程
         let app_handle_level = app_handle.clone();
         thread::spawn(move || {
-            while let Ok(level) = level_rx.recv() {
+            while let Ok(reading) = level_rx.recv() {
                 let level_update = AudioLevelUpdate {
-                    level: level * 5.0,
+                    level: reading.normalized(),
+                    dbfs: reading.dbfs,
+                    peak_dbfs: reading.peak_dbfs,
                     timestamp: std::time::SystemTime::now()
                         .duration_since(std::time::UNIX_EPOCH)
                         .unwrap()
@@ -602,13 +1156,21 @@ impl RealtimeAudioCapture {
         // 启动音频处理和识别线程
         let app_handle_processing = app_handle.clone();
         let is_recording_processing = is_recording.clone();
+        let is_paused_processing = is_paused.clone();
+        let recognition_rate = capture_format.recording_sample_rate(false); // 转换器输出给识别线程的采样率
         thread::spawn(move || {
             Self::audio_processing_thread(
                 audio_rx,
+                recognition_rate,
+                rollover_rx,
+                capture_clock,
                 app_handle_processing,
                 config,
                 is_recording_processing,
+                is_paused_processing,
                 whisper_state,
+                recording_id,
+                results,
             );
         });
         
@@ -616,50 +1178,115 @@ impl RealtimeAudioCapture {
         while let Ok(command) = command_rx.recv() {
             match command {
                 AudioCommand::Start => {
-                    println!("Audio thread: Start command received");
+                    log::debug!("Audio thread: Start command received");
                 }
                 AudioCommand::Pause => {
-                    println!("Audio thread: Pause command received");
+                    log::debug!("Audio thread: Pause command received");
                 }
                 AudioCommand::Resume => {
-                    println!("Audio thread: Resume command received");
+                    log::debug!("Audio thread: Resume command received");
                 }
                 AudioCommand::Stop => {
-                    println!("Audio thread: Stop command received");
+                    log::debug!("Audio thread: Stop command received");
                     break;
                 }
             }
         }
         
         drop(stream);
-        println!("Audio thread ended");
+        log::debug!("Audio thread ended");
     }
     
+    /// 录音写入线程：按写入文件的样本数决定拆分点，跨越单段上限的一块拆到前后两个文件中
+    fn recording_writer_thread(
+        recording_files: RecordingFiles,
+        record_rx: mpsc::Receiver<Vec<f32>>,
+        mut splitter: RecordingSplitter,
+        rollover_tx: mpsc::Sender<FileRollover>,
+    ) {
+        let mut written = 0usize;
+        while let Ok(samples) = record_rx.recv() {
+            let mut rest = samples.as_slice();
+            while let Some(offset) = splitter.boundary_in(written, rest.len()) {
+                let (head, tail) = rest.split_at(offset);
+                Self::store_samples(&recording_files.audio_data, &recording_files.writer, head);
+                written += head.len();
+                rest = tail;
+                let Some(part) = splitter.advance(written) else { break };
+
+                log::info!("⏱️ 录音达到单段时长上限，切换到第 {} 段: {}", part.part, part.record_id);
+                let previous_record_id = recording_files.current_record_id.lock().unwrap().clone();
+                let previous_file_path = match recording_files.roll_over(&part.record_id) {
+                    Ok(path) => path.and_then(|path| path.file_name().map(|name| format!("recordings/{}", name.to_string_lossy()))),
+                    Err(e) => {
+                        log::error!("切换录音文件失败: {}", e);
+                        None
+                    }
+                };
+                let _ = rollover_tx.send(FileRollover { part, previous_record_id, previous_file_path });
+            }
+            Self::store_samples(&recording_files.audio_data, &recording_files.writer, rest);
+            written += rest.len();
+        }
+    }
+
     fn audio_processing_thread(
         audio_rx: mpsc::Receiver<Vec<f32>>,
+        sample_rate: u32,
+        rollover_rx: mpsc::Receiver<FileRollover>,
+        capture_clock: CaptureClock,
         app_handle: AppHandle,
         config: RealtimeConfig,
         is_recording: Arc<Mutex<bool>>,
+        is_paused: Arc<Mutex<bool>>,
         whisper_state: Arc<WhisperContextState>,
+        recording_id: String,
+        results: RealtimeResults,
     ) {
-        println!("🚀 Audio processing thread starting...");
+        log::debug!("🚀 Audio processing thread starting...");
         
-        let mut processor = match AudioProcessor::new() {
-            Ok(p) => {
-                println!("✅ Audio processor created successfully");
+        let windows = match ProcessingWindows::from_config(&config, WHISPER_SAMPLE_RATE) {
+            Ok(windows) => windows,
+            Err(e) => {
+                log::error!("❌ 识别窗口配置无效: {}", e);
+                return;
+            }
+        };
+        let mut processor = match AudioProcessor::new(windows, config.auto_stop_after_silence_secs, vad::create_backend(config.vad_backend)) {
+            Ok(mut p) => {
+                log::debug!("✅ Audio processor created successfully");
+                p.speaker_diarization.set_max_history(config.speaker_history);
                 p
             },
             Err(e) => {
-                eprintln!("❌ Failed to create audio processor: {}", e);
+                log::error!("❌ Failed to create audio processor: {}", e);
                 return;
             }
         };
 
         let mut segment_id = 0u32;
         let mut total_segments = 0u32;
-        let mut confidence_sum = 0.0f32;
+        let mut session_confidence = ConfidenceAccumulator::new();
+        let mut processed_samples = 0usize; // 已处理的16kHz样本数，用于计算窗口起点
+        let mut timeline = SegmentTimeline::new();
+        let mut context = ContextCarryOver::new(&config);
+        let thresholds = app_handle.state::<StorageState>()
+            .with_storage(ConfidenceThresholds::load)
+            .unwrap_or_default();
 
-        println!("🎵 Audio processing thread ready, waiting for audio data...");
+        // 启用自动保存时，识别结果定期写入以录音ID为主键的记录
+        let file_path = format!("recordings/{}.{}", recording_id, config.output_format.extension());
+        let mut auto_saver = TranscriptAutoSaver::new(&config, &recording_id, &file_path, Instant::now());
+        // 录音文件切换后，识别进度到达该段起点时结束当前记录，在新记录中继续
+        let mut pending_rollovers = VecDeque::new();
+        let mut part_offset = 0.0f64; // 当前记录在会话中的起点（秒）
+        // 降噪只作用于送入识别的音频，保存的录音保持原样
+        let mut denoiser = config.noise_reduction
+            .then(|| denoise::create_denoiser(config.denoise_backend))
+            .flatten();
+        let mut watchdog = RecordingWatchdog::new(Instant::now(), Duration::from_secs(config.stall_timeout_secs.max(1) as u64));
+
+        log::debug!("🎵 Audio processing thread ready, waiting for audio data...");
 
         // 使用更安全的循环检查
         loop {
@@ -667,38 +1294,75 @@ impl RealtimeAudioCapture {
             let recording = match is_recording.lock() {
                 Ok(guard) => *guard,
                 Err(e) => {
-                    eprintln!("❌ Failed to lock recording state: {}", e);
+                    log::error!("❌ Failed to lock recording state: {}", e);
                     break;
                 }
             };
             
             if !recording {
-                println!("🛑 Recording stopped, exiting audio processing thread");
+                log::debug!("🛑 Recording stopped, exiting audio processing thread");
                 break;
             }
             match audio_rx.recv_timeout(Duration::from_millis(100)) {
                 Ok(audio_chunk) => {
-                    println!("📊 Processing audio chunk with {} samples", audio_chunk.len());
+                    // 之后的窗口与时间换算都按 WHISPER_SAMPLE_RATE，采样率不一致时在入口统一
+                    let audio_chunk = whisper_input::ensure_whisper_rate(audio_chunk, sample_rate).into_owned();
+                    let audio_chunk = match denoiser.as_mut() {
+                        Some(denoiser) => denoiser.process(&audio_chunk),
+                        None => audio_chunk,
+                    };
+                    log::debug!("📊 Processing audio chunk with {} samples", audio_chunk.len());
+                    processed_samples += audio_chunk.len();
+                    if results.take_speaker_reset() {
+                        log::info!("🔄 已重置说话人识别");
+                        processor.speaker_diarization.reset();
+                    }
+                    pending_rollovers.extend(rollover_rx.try_iter());
+                    while pending_rollovers.front()
+                        .is_some_and(|rollover: &FileRollover| processed_samples as f64 / WHISPER_SAMPLE_RATE as f64 >= rollover.part.start_secs)
+                    {
+                        let rollover = pending_rollovers.pop_front().unwrap();
+                        part_offset = rollover.part.start_secs;
+                        Self::roll_over_part(&app_handle, &config, &mut auto_saver, &recording_id, rollover);
+                    }
                     
                     // 安全地处理音频块
                     match std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
                         processor.process_audio_chunk(&audio_chunk)
                     })) {
                         Ok(result) => {
-                            if let Some((speech_audio, speaker)) = result {
-                                println!("🎯 Processing speech segment of {} samples", speech_audio.len());
+                            if let Some((speech_audio, speaker, speaker_change)) = result {
+                                log::debug!("🎯 Processing speech segment of {} samples", speech_audio.len());
+                                if config.speaker_diarization && speaker.is_some() {
+                                    results.record_speaker_turns(processor.speaker_diarization.speaker_timeline());
+                                    if let Some(change) = speaker_change {
+                                        log::debug!("🗣️ 说话人切换: {} @ {:.2}s", change.speaker, change.timestamp);
+                                        let _ = app_handle.emit("speaker_change", change);
+                                    }
+                                }
                                 
                                 // 安全地使用Whisper进行识别
                                 match std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
-                                    Self::recognize_speech_segment_optimized(&speech_audio, &config, &whisper_state)
+                                    Self::recognize_speech_segment_optimized(&speech_audio, &config, &whisper_state, context.prompt())
                                 })) {
                                     Ok(recognition_result) => match recognition_result {
-                                        Ok(text) => {
-                                            if !text.trim().is_empty() {
-                                                let confidence = 0.85 + (speech_audio.len() as f32 / 32000.0 * 0.1).min(0.15);
-                                                confidence_sum += confidence;
+                                        Ok(segments) => {
+                                            // 识别窗口是缓冲区末尾的一段，起点为已处理样本数减去窗口长度
+                                            let window_offset = processed_samples.saturating_sub(speech_audio.len()) as f64 / WHISPER_SAMPLE_RATE as f64;
+                                            if let Some(mut window) = assemble_window(&segments, window_offset, &mut timeline, &config) {
+                                                context.carry(&window.tokens);
+                                                // 拆分后的记录时间从当前段起点算起，跨越拆分点的窗口归入新的一段
+                                                window.start_time = (window.start_time - part_offset).max(0.0);
+                                                window.end_time = (window.end_time - part_offset).max(window.start_time);
+                                                let text = window.text;
+                                                let confidence = window.confidence.unwrap_or(0.0) as f32;
+                                                if let Some(value) = window.confidence {
+                                                    session_confidence.add(window.end_time - window.start_time, value);
+                                                }
                                                 total_segments += 1;
 
+                                                auto_saver.push_segment(&text, window.start_time, window.end_time, speaker.clone().filter(|_| config.speaker_diarization), window.confidence, window.no_speech_prob);
+
                                                 let result = RecognitionResult {
                                                     text: text.clone(),
                                                     confidence,
@@ -712,9 +1376,13 @@ impl RealtimeAudioCapture {
                                                         .duration_since(std::time::UNIX_EPOCH)
                                                         .unwrap()
                                                         .as_millis() as u64,
+                                                    start_time: window.start_time,
+                                                    end_time: window.end_time,
+                                                    confidence_band: thresholds.band(window.confidence),
                                                 };
 
-                                                println!("✅ Recognition result: {}", text);
+                                                log::debug!("✅ Recognition result: [{:.2}s - {:.2}s] {}", window.start_time, window.end_time, text);
+                                                results.record(&result, segment_id);
                                                 let _ = app_handle.emit("recognition_result", result);
 
                                                 segment_id += 1;
@@ -724,25 +1392,32 @@ impl RealtimeAudioCapture {
                                                     duration: segment_id as u64 * 2, // 估算时长
                                                     segments_count: total_segments,
                                                     speaker_count: if config.speaker_diarization { 2 } else { 1 },
-                                                    average_confidence: if total_segments > 0 { confidence_sum / total_segments as f32 } else { 0.0 },
+                                                    average_confidence: session_confidence.average().unwrap_or(0.0) as f32,
                                                 };
                                                 let _ = app_handle.emit("recording_stats", stats);
                                             }
                                         }
                                         Err(e) => {
-                                            eprintln!("❌ Recognition failed: {}", e);
+                                            log::error!("❌ Recognition failed: {}", e);
                                         }
                                     },
                                     Err(_) => {
-                                        eprintln!("⚠️ Recognition panicked, skipping this segment");
+                                        log::warn!("⚠️ Recognition panicked, skipping this segment");
                                     }
                                 }
                             }
                         },
                         Err(_) => {
-                            eprintln!("⚠️ Audio processing panicked, skipping this chunk");
+                            log::warn!("⚠️ Audio processing panicked, skipping this chunk");
                         }
                     }
+
+                    if processor.silence_limit_exceeded() {
+                        let silence_secs = config.auto_stop_after_silence_secs.unwrap_or(0);
+                        log::info!("🔇 连续静音超过 {} 秒，自动停止录音", silence_secs);
+                        auto_stop_recording(&app_handle, silence_secs);
+                        break;
+                    }
                 },
                 Err(mpsc::RecvTimeoutError::Timeout) => {
                     // 定期发送心跳统计
@@ -751,39 +1426,126 @@ impl RealtimeAudioCapture {
                             duration: segment_id as u64 * 2,
                             segments_count: total_segments,
                             speaker_count: if config.speaker_diarization { 2 } else { 1 },
-                            average_confidence: confidence_sum / total_segments as f32,
+                            average_confidence: session_confidence.average().unwrap_or(0.0) as f32,
                         };
                         let _ = app_handle.emit("recording_stats", stats);
                     }
                 },
                 Err(mpsc::RecvTimeoutError::Disconnected) => {
-                    println!("Audio processing thread: channel disconnected");
+                    log::debug!("Audio processing thread: channel disconnected");
                     break;
                 }
             }
+
+            let paused = is_paused.lock().map(|guard| *guard).unwrap_or(false);
+            if let Some(at) = capture_clock.last_callback() {
+                watchdog.chunk_received(at);
+            }
+            let wall_clock_ms = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_millis() as u64;
+            for event in watchdog.poll(Instant::now(), wall_clock_ms, paused) {
+                match event {
+                    WatchdogEvent::Heartbeat(heartbeat) => {
+                        let _ = app_handle.emit("recording_heartbeat", heartbeat);
+                    }
+                    WatchdogEvent::Stalled(stalled) => {
+                        log::warn!("⚠️ {:.1} 秒未收到音频数据，采集可能已停止", stalled.stalled_secs);
+                        let _ = app_handle.emit("recording_stalled", stalled);
+                    }
+                }
+            }
+
+            if let Err(e) = auto_saver.maybe_save(Instant::now(), |record| Self::save_snapshot(&app_handle, record)) {
+                log::warn!("⚠️ 自动保存转录失败: {}", e);
+            }
         }
 
-        println!("Audio processing thread ended");
+        // 停止前已切换的文件也要切换对应的记录，保证记录与录音文件一一对应
+        pending_rollovers.extend(rollover_rx.try_iter());
+        for rollover in pending_rollovers {
+            Self::roll_over_part(&app_handle, &config, &mut auto_saver, &recording_id, rollover);
+        }
+        if let Err(e) = auto_saver.finish(Instant::now(), |record| Self::save_snapshot(&app_handle, record)) {
+            log::warn!("⚠️ 保存最终转录失败: {}", e);
+        }
+
+        log::debug!("Audio processing thread ended");
+    }
+
+    /// 切换到新的一段：录音文件已由写入线程切换，这里完成上一段的记录，启用自动保存时把两段登记到同一会话
+    fn roll_over_part(
+        app_handle: &AppHandle,
+        config: &RealtimeConfig,
+        auto_saver: &mut TranscriptAutoSaver,
+        session_id: &str,
+        rollover: FileRollover,
+    ) {
+        let FileRollover { part, previous_record_id, previous_file_path } = rollover;
+
+        let file_path = format!("recordings/{}.{}", part.record_id, config.output_format.extension());
+        match auto_saver.roll_over(&part.record_id, &file_path, Instant::now(), |record| Self::save_snapshot(app_handle, record)) {
+            Ok(_) if auto_saver.is_enabled() => {
+                let linked = app_handle.state::<StorageState>().with_storage(|storage| {
+                    storage.link_session_part(session_id, &previous_record_id, part.part - 1)?;
+                    storage.link_session_part(session_id, &part.record_id, part.part)
+                });
+                if let Err(e) = linked {
+                    log::warn!("⚠️ 登记录音会话失败: {}", e);
+                }
+            }
+            Ok(_) => {}
+            Err(e) => log::warn!("⚠️ 保存上一段转录失败: {}", e),
+        }
+
+        let _ = app_handle.emit("recording_rolled_over", RecordingRolledOverEvent {
+            session_id: session_id.to_string(),
+            part: part.part,
+            previous_record_id,
+            record_id: part.record_id,
+            previous_file_path,
+        });
+    }
+
+    fn save_snapshot(app_handle: &AppHandle, record: &TranscriptionRecord) -> Result<(), StenoError> {
+        app_handle.state::<StorageState>().with_storage(|s| s.save_record(record))?;
+        log::info!("💾 已自动保存转录: {}", record.id);
+        Ok(())
     }
 
     fn recognize_speech_segment_optimized(
         audio: &[f32],
         config: &RealtimeConfig,
         whisper_state: &WhisperContextState,
-    ) -> Result<String, String> {
-        println!("🎯 Starting Whisper recognition for {} samples ({:.2}s)", 
-            audio.len(), audio.len() as f32 / 16000.0);
+        prompt_tokens: &[whisper_token],
+    ) -> Result<Vec<WindowSegment>, String> {
+        log::debug!("🎯 Starting Whisper recognition for {} samples ({:.2}s)", 
+            audio.len(), audio.len() as f32 / WHISPER_SAMPLE_RATE as f32);
         
         // 检查音频长度
         if audio.len() < 1600 { // 少于0.1秒的音频跳过
-            println!("⚠️ Audio too short for recognition: {} samples", audio.len());
-            return Ok(String::new());
+            log::warn!("⚠️ Audio too short for recognition: {} samples", audio.len());
+            return Ok(Vec::new());
         }
         
-        // 预处理：标准化音频
-        let normalized_audio = Self::normalize_audio(audio);
+        let processed_audio = Self::preprocess_for_recognition(audio, config);
         
-        Self::recognize_speech_segment(&normalized_audio, config, whisper_state)
+        Self::recognize_speech_segment(&processed_audio, config, whisper_state, prompt_tokens)
+    }
+
+    /// 按配置依次做预加重和音量标准化；两者都关闭时原样返回
+    fn preprocess_for_recognition(audio: &[f32], config: &RealtimeConfig) -> Vec<f32> {
+        let mut samples = audio.to_vec();
+        if let Some(coeff) = config.pre_emphasis {
+            for i in (1..samples.len()).rev() {
+                samples[i] -= coeff * samples[i - 1];
+            }
+        }
+        if config.normalize {
+            samples = Self::normalize_audio(&samples);
+        }
+        samples
     }
     
     fn normalize_audio(audio: &[f32]) -> Vec<f32> {
@@ -801,52 +1563,63 @@ impl RealtimeAudioCapture {
         let max_gain = 10.0; // 限制最大增益
         let actual_gain = gain.min(max_gain);
         
-        println!("🔊 Audio normalization: RMS={:.6} -> gain={:.2}", rms, actual_gain);
+        log::debug!("🔊 Audio normalization: RMS={:.6} -> gain={:.2}", rms, actual_gain);
         
         audio.iter().map(|&x| (x * actual_gain).clamp(-1.0, 1.0)).collect()
     }
 
+    /// 返回窗口内各段的原始文本、相对时间与置信度
     fn recognize_speech_segment(
         audio: &[f32],
         config: &RealtimeConfig,
         whisper_state: &WhisperContextState,
-    ) -> Result<String, String> {
-        println!("🔒 Attempting to acquire Whisper context lock...");
+        prompt_tokens: &[whisper_token],
+    ) -> Result<Vec<WindowSegment>, String> {
+        log::debug!("🔒 Attempting to acquire Whisper context lock...");
         
         let ctx = match whisper_state.ctx.lock() {
             Ok(ctx) => {
-                println!("✅ Whisper context lock acquired");
+                log::debug!("✅ Whisper context lock acquired");
                 ctx
             },
             Err(e) => {
-                println!("❌ Failed to acquire Whisper context lock: {}", e);
+                log::error!("❌ Failed to acquire Whisper context lock: {}", e);
                 return Err("Failed to acquire Whisper context lock".to_string());
             }
         };
         
         // 验证Whisper上下文
         if ctx.is_null() {
-            println!("❌ Whisper context is null");
+            log::error!("❌ Whisper context is null");
             return Err("Whisper context is null".to_string());
         }
         
-        println!("🔧 Setting up Whisper parameters...");
+        log::debug!("🔧 Setting up Whisper parameters...");
         
-        // 使用贪婪搜索而不是beam search，更快更稳定
+        // 按配置选择贪婪采样或束搜索
         let mut params = unsafe { 
-            whisper_full_default_params(whisper_sampling_strategy_WHISPER_SAMPLING_BEAM_SEARCH) 
+            whisper_full_default_params(config.decoding.sampling_strategy()) 
         };
         
         // 针对实时识别的保守参数设置
         params.temperature = 0.0;
         params.suppress_blank = true;
         params.token_timestamps = false;
-        params.max_len = 1;
         params.n_threads = 1; // 使用单线程避免竞争
-        params.beam_search.beam_size = 1; // 最小beam size
-        params.greedy.best_of = 1;
+        config.decoding.apply(&mut params);
+        config.segmentation.apply(&mut params);
+        config.suppression.apply(&mut params);
         params.translate = false; // 禁用翻译
-        params.no_context = true; // 禁用上下文，提高稳定性
+        params.no_context = true; // 不使用上下文中残留的历史，提高稳定性；需要连贯性时通过 prompt_tokens 显式携带
+        // 设置了 prompt_tokens 时 Whisper 会忽略 initial_prompt，因此提示词也转换为 token，放在携带的上下文之前
+        let mut all_prompt_tokens = crate::prompt_builder::build_initial_prompt(config.initial_prompt.as_deref(), &config.hotwords, None)
+            .map(|prompt| unsafe { tokenize_prompt(*ctx, &prompt) })
+            .unwrap_or_default();
+        all_prompt_tokens.extend_from_slice(prompt_tokens);
+        if !all_prompt_tokens.is_empty() {
+            params.prompt_tokens = all_prompt_tokens.as_ptr();
+            params.prompt_n_tokens = all_prompt_tokens.len() as i32;
+        }
         
         // 语言设置
         let lang_cstring = match config.language.as_str() {
@@ -863,11 +1636,11 @@ impl RealtimeAudioCapture {
         
         // 验证音频数据
         if audio.is_empty() {
-            println!("⚠️ Audio data is empty");
-            return Ok(String::new());
+            log::warn!("⚠️ Audio data is empty");
+            return Ok(Vec::new());
         }
         
-        println!("📊 Audio data: {} samples, range: [{:.6}, {:.6}]", 
+        log::debug!("📊 Audio data: {} samples, range: [{:.6}, {:.6}]", 
             audio.len(), 
             audio.iter().min_by(|a, b| a.partial_cmp(b).unwrap()).unwrap_or(&0.0),
             audio.iter().max_by(|a, b| a.partial_cmp(b).unwrap()).unwrap_or(&0.0)
@@ -878,7 +1651,7 @@ impl RealtimeAudioCapture {
             .map(|&x| x.clamp(-1.0, 1.0)) // 确保音频在有效范围内
             .collect();
         
-        println!("🚀 Starting Whisper recognition...");
+        log::debug!("🚀 Starting Whisper recognition...");
         
         // 安全地执行识别
         let result = unsafe {
@@ -890,55 +1663,76 @@ impl RealtimeAudioCapture {
             )
         };
         
-        println!("📝 Whisper recognition result: {}", result);
+        log::debug!("📝 Whisper recognition result: {}", result);
         
         if result != 0 {
-            println!("❌ Whisper recognition failed with code: {}", result);
+            log::error!("❌ Whisper recognition failed with code: {}", result);
             return Err(format!("Whisper recognition failed with code: {}", result));
         }
         
         // 安全地提取文本
         let num_segments = unsafe { whisper_full_n_segments(*ctx) };
-        println!("📋 Number of segments: {}", num_segments);
+        log::debug!("📋 Number of segments: {}", num_segments);
         
         if num_segments == 0 {
-            println!("⚠️ No segments recognized");
-            return Ok(String::new());
+            log::warn!("⚠️ No segments recognized");
+            return Ok(Vec::new());
         }
         
-        let mut text = String::new();
+        let mut segments = Vec::new();
         
         for i in 0..num_segments {
+            // 静音窗口可能产生幻觉文本，按无语音概率过滤
+            let no_speech_prob = unsafe { whisper_full_get_segment_no_speech_prob(*ctx, i) };
+            if should_suppress_segment(no_speech_prob, config.no_speech_threshold) {
+                log::debug!("🔇 Segment {} suppressed: no_speech_prob={:.3}", i, no_speech_prob);
+                continue;
+            }
+
             let segment_ptr = unsafe { whisper_full_get_segment_text(*ctx, i) };
             if !segment_ptr.is_null() {
                 let c_str = unsafe { CStr::from_ptr(segment_ptr as *const c_char) };
                 match c_str.to_str() {
                     Ok(segment_text) => {
-                        println!("📝 Segment {}: '{}'", i, segment_text);
-                        text.push_str(segment_text);
+                        log::debug!("📝 Segment {}: '{}'", i, segment_text);
+                        segments.push(unsafe {
+                            WindowSegment {
+                                text: segment_text.to_string(),
+                                t0: whisper_full_get_segment_t0(*ctx, i),
+                                t1: whisper_full_get_segment_t1(*ctx, i),
+                                confidence: confidence::whisper_segment_confidence(*ctx, i),
+                                no_speech_prob,
+                                tokens: segment_text_tokens(*ctx, i),
+                            }
+                        });
                     },
                     Err(e) => {
-                        println!("⚠️ Failed to convert segment {} to string: {}", i, e);
+                        log::error!("⚠️ Failed to convert segment {} to string: {}", i, e);
                     }
                 }
             } else {
-                println!("⚠️ Segment {} pointer is null", i);
+                log::warn!("⚠️ Segment {} pointer is null", i);
             }
         }
         
-        println!("📜 Raw recognized text: '{}'", text);
-        
-        // 如果没有识别到任何文本，返回空字符串
-        if text.trim().is_empty() {
-            println!("ℹ️ No text recognized");
-            return Ok(String::new());
-        }
-        
-        // 文本后处理
-        let processed_text = post_process_text(&text, &config.language);
-        println!("✨ Processed text: '{}'", processed_text);
-        
-        Ok(processed_text)
+        log::debug!("📜 Recognized {} segments", segments.len());
+        Ok(segments)
+    }
+
+    pub fn get_current_transcript(&self) -> Result<String, String> {
+        self.results.current_transcript()
+    }
+
+    pub fn get_segments(&self) -> Result<Vec<ManagedTranscriptSegment>, String> {
+        self.results.segments()
+    }
+
+    pub fn get_speaker_timeline(&self) -> Result<Vec<SpeakerTimeRange>, String> {
+        self.results.speaker_timeline()
+    }
+
+    pub fn reset_speaker_diarization(&self) {
+        self.results.request_speaker_reset();
     }
 
     pub fn get_recording_duration(&self) -> u64 {
@@ -965,6 +1759,11 @@ impl AudioCaptureState {
     pub fn lock(&self) -> Result<std::sync::MutexGuard<Option<RealtimeAudioCapture>>, std::sync::PoisonError<std::sync::MutexGuard<Option<RealtimeAudioCapture>>>> {
         self.inner.lock()
     }
+
+    /// 是否有录音在进行（含暂停）；锁中毒时按忙碌处理
+    pub fn is_capturing(&self) -> bool {
+        self.inner.lock().map_or(true, |capture| capture.is_some())
+    }
 }
 
 unsafe impl Send for AudioCaptureState {}
@@ -977,19 +1776,51 @@ impl Default for AudioCaptureState {
 }
 
 // Tauri命令实现
+/// 开始实时录音，返回本次录音的ID；启用自动保存时转录记录以该ID保存，前端应沿用而不是另建记录
 #[tauri::command]
 pub async fn start_realtime_recording(
     app_handle: AppHandle,
-    config: RealtimeConfig,
+    config: Option<RealtimeConfig>,
     state: State<'_, AudioCaptureState>,
     whisper_state: State<'_, WhisperContextState>,
-) -> Result<(), String> {
-    println!("🎤 开始初始化实时录音...");
-    println!("配置: {:?}", config);
+    model_manager: State<'_, Arc<Mutex<ModelManager>>>,
+    storage_state: State<'_, StorageState>,
+) -> Result<String, StenoError> {
+    log::info!("🎤 开始初始化实时录音...");
+
+    // 未传入配置时沿用上次的实时录音配置，并记住本次使用的配置
+    let mut last_config = storage_state.with_storage(|s| s.get_last_used_config()).unwrap_or_default();
+    let mut config = config.or_else(|| last_config.realtime.clone()).unwrap_or_default();
+    validate_config(&config).map_err(StenoError::InvalidArgument)?;
+    // 输入增益按设备保存，不随上次配置带到其他设备
+    last_config.realtime = Some(RealtimeConfig { input_gain: None, ..config.clone() });
+    if let Err(e) = storage_state.with_storage(|s| s.set_last_used_config(&last_config)) {
+        log::warn!("⚠️ 保存实时录音配置失败: {}", e);
+    }
+
+    // 显式传入增益时记住到当前设备，否则使用该设备上次的增益
+    let device_key = crate::audio_devices::selected_input_device_id().unwrap_or_else(|| "default".to_string());
+    match config.input_gain {
+        Some(gain) => {
+            if let Err(e) = storage_state.with_storage(|s| s.set_input_gain(&device_key, gain)) {
+                log::warn!("⚠️ 保存输入增益失败: {}", e);
+            }
+        }
+        None => config.input_gain = storage_state.with_storage(|s| s.get_input_gain(&device_key)).ok().flatten(),
+    }
+    // 全局自动命名开启时实时录音同样按内容命名
+    config.auto_name |= storage_state.with_storage(crate::record_naming::auto_name_enabled).unwrap_or(false);
+    log::debug!("配置: {:?}", config);
+
+    let model_path = model_manager.lock().unwrap().get_current_model_path();
+    whisper_state.ensure_ready(&model_path)?;
+    if let LanguageCompatibility::Warning { message } = model_management::check_model_language(&model_path, &config.language)? {
+        log::warn!("⚠️ {}", message);
+    }
     
     let mut capture_state = state.lock().map_err(|e| {
         let error_msg = format!("无法获取录音状态锁: {}", e);
-        eprintln!("{}", error_msg);
+        log::error!("{}", error_msg);
         error_msg
     })?;
     
@@ -1005,9 +1836,10 @@ pub async fn start_realtime_recording(
     capture.start_recording(whisper_state_arc)
         .map_err(|e| format!("Failed to start recording: {}", e))?;
     
+    let recording_id = capture.recording_id.clone();
     *capture_state = Some(capture);
-    println!("Realtime recording started");
-    Ok(())
+    log::info!("Realtime recording started: {}", recording_id);
+    Ok(recording_id)
 }
 
 #[tauri::command]
@@ -1036,6 +1868,20 @@ pub async fn resume_realtime_recording(
     Ok(())
 }
 
+// 静音超时后自动停止：与 stop_realtime_recording 一样取出并停止当前录音
+fn auto_stop_recording(app_handle: &AppHandle, silence_secs: u32) {
+    let capture = app_handle.state::<AudioCaptureState>()
+        .lock()
+        .ok()
+        .and_then(|mut state| state.take());
+    if let Some(mut capture) = capture {
+        if let Err(e) = capture.stop_recording() {
+            log::error!("自动停止录音失败: {}", e);
+        }
+    }
+    let _ = app_handle.emit("auto_stopped", AutoStoppedEvent { silence_secs });
+}
+
 #[tauri::command]
 pub async fn stop_realtime_recording(
     state: State<'_, AudioCaptureState>,
@@ -1062,64 +1908,360 @@ pub async fn get_recording_duration(
     }
 }
 
-// 获取用户选定的输入设备 (异步版本)
-async fn get_selected_input_device(host: &cpal::Host) -> Result<cpal::Device, String> {
-    // 尝试获取全局选定的设备ID
-    let selected_device_id = audio_devices::get_global_audio_device("input".to_string()).await?;
+#[tauri::command]
+pub async fn get_realtime_current_transcript(
+    state: State<'_, AudioCaptureState>,
+) -> Result<String, String> {
+    let capture_state = state.lock().map_err(|e| e.to_string())?;
     
-    if let Some(device_id) = selected_device_id {
-        // 解析设备ID
-        let device_index: usize = device_id
-            .strip_prefix("input_")
-            .and_then(|s| s.parse().ok())
-            .ok_or("Invalid device ID format")?;
-        
-        // 获取设备列表
-        let devices: Vec<_> = host.input_devices()
-            .map_err(|e| format!("Failed to enumerate devices: {}", e))?
-            .collect();
-        
-        // 获取指定的设备
-        devices.get(device_index)
-            .cloned()
-            .ok_or("Selected device not found".to_string())
+    if let Some(ref capture) = capture_state.as_ref() {
+        capture.get_current_transcript()
     } else {
-        // 如果没有选择设备，返回默认设备
-        host.default_input_device()
-            .ok_or("No default input device available".to_string())
+        Ok(String::new())
     }
 }
 
-// 获取用户选定的输入设备 (同步版本)
-fn get_selected_input_device_sync(host: &cpal::Host) -> Result<cpal::Device, String> {
-    // 使用tokio的阻塞调用来执行异步函数
-    let rt = tokio::runtime::Runtime::new()
-        .map_err(|e| format!("Failed to create tokio runtime: {}", e))?;
+#[tauri::command]
+pub async fn get_realtime_segments(
+    state: State<'_, AudioCaptureState>,
+) -> Result<Vec<ManagedTranscriptSegment>, String> {
+    let capture_state = state.lock().map_err(|e| e.to_string())?;
     
-    rt.block_on(async {
-        // 尝试获取全局选定的设备ID
-        let selected_device_id = audio_devices::get_global_audio_device("input".to_string()).await?;
-        
-        if let Some(device_id) = selected_device_id {
-            // 解析设备ID
-            let device_index: usize = device_id
-                .strip_prefix("input_")
-                .and_then(|s| s.parse().ok())
-                .ok_or("Invalid device ID format")?;
-            
-            // 获取设备列表
-            let devices: Vec<_> = host.input_devices()
-                .map_err(|e| format!("Failed to enumerate devices: {}", e))?
-                .collect();
-            
-            // 获取指定的设备
-            devices.get(device_index)
-                .cloned()
-                .ok_or("Selected device not found".to_string())
-        } else {
-            // 如果没有选择设备，返回默认设备
-            host.default_input_device()
-                .ok_or("No default input device available".to_string())
+    if let Some(ref capture) = capture_state.as_ref() {
+        capture.get_segments()
+    } else {
+        Ok(Vec::new())
+    }
+}
+
+/// 换了一批与会者时重置说话人识别：已识别的说话人与特征历史被清空，之后的声音重新从说话人A开始。
+/// 说话人特征只保存在内存中，每次开始录音本来就从空白开始；未在录音时调用不做任何事
+#[tauri::command]
+pub async fn reset_speaker_diarization(
+    state: State<'_, AudioCaptureState>,
+) -> Result<(), String> {
+    let capture_state = state.lock().map_err(|e| e.to_string())?;
+
+    if let Some(ref capture) = capture_state.as_ref() {
+        capture.reset_speaker_diarization();
+    }
+    Ok(())
+}
+
+/// 当前实时录音的说话人轮次（时间相对录音开始），用于界面上的说话人色带
+#[tauri::command]
+pub async fn get_realtime_speaker_timeline(
+    state: State<'_, AudioCaptureState>,
+) -> Result<Vec<SpeakerTimeRange>, String> {
+    let capture_state = state.lock().map_err(|e| e.to_string())?;
+
+    if let Some(ref capture) = capture_state.as_ref() {
+        capture.get_speaker_timeline()
+    } else {
+        Ok(Vec::new())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_windows() -> ProcessingWindows {
+        ProcessingWindows::from_config(&RealtimeConfig::default(), 16000).unwrap()
+    }
+
+    fn windows_config(mode: &str) -> RealtimeConfig {
+        RealtimeConfig {
+            mode: mode.to_string(),
+            overlap_ms: 200,
+            streaming_window_ms: 300,
+            buffered_window_ms: 1500,
+            ..RealtimeConfig::default()
         }
-    })
-}
\ No newline at end of file
+    }
+
+    #[test]
+    fn test_modes_honor_configured_windows() {
+        let streaming = ProcessingWindows::from_config(&windows_config("streaming"), 16000).unwrap();
+        assert_eq!((streaming.window, streaming.overlap), (4800, 0));
+        assert_eq!(streaming.interval, Duration::from_millis(300));
+        assert!(!streaming.should_process(4799));
+        assert!(streaming.should_process(4800));
+        let mut accumulator = vec![0.0; 4800];
+        streaming.retain_overlap(&mut accumulator);
+        assert!(accumulator.is_empty());
+
+        let buffered = ProcessingWindows::from_config(&windows_config("buffered"), 16000).unwrap();
+        assert_eq!((buffered.window, buffered.overlap), (24000, 3200));
+        let mut accumulator: Vec<f32> = (0..24000).map(|i| i as f32).collect();
+        buffered.retain_overlap(&mut accumulator);
+        assert_eq!(accumulator.len(), 3200);
+        assert_eq!(accumulator[0], 20800.0);
+
+        // 窗口按采样率换算
+        let buffered_48k = ProcessingWindows::from_config(&windows_config("buffered"), 48000).unwrap();
+        assert_eq!(buffered_48k.window, 72000);
+    }
+
+    #[test]
+    fn test_invalid_windows_rejected() {
+        let invalid = RealtimeConfig { overlap_ms: 1500, ..windows_config("buffered") };
+        assert!(ProcessingWindows::from_config(&invalid, 16000).is_err());
+
+        let too_short = RealtimeConfig { streaming_window_ms: 50, ..windows_config("streaming") };
+        assert!(ProcessingWindows::from_config(&too_short, 16000).is_err());
+        assert!(ProcessingWindows::from_config(&windows_config("buffered"), 0).is_err());
+    }
+
+    #[test]
+    fn test_processor_keeps_only_overlap_after_recognition() {
+        let windows = ProcessingWindows::from_config(&windows_config("buffered"), 16000).unwrap();
+        let mut processor = AudioProcessor::new(windows, None, vad::create_backend(VadBackendKind::Energy)).unwrap();
+        processor.last_recognition_time = Instant::now() - Duration::from_secs(10);
+
+        let speech = vec![0.3; 24000];
+        let (audio, _, _) = processor.process_audio_chunk(&speech).unwrap();
+        assert_eq!(audio.len(), 24000);
+        assert_eq!(processor.continuous_buffer.len(), 3200);
+    }
+
+    fn feed(processor: &mut AudioProcessor, amplitude: f32, seconds: f32) {
+        // 100ms 一块，与采集回调的粒度相近
+        let chunk = vec![amplitude; 1600];
+        for _ in 0..(seconds * 10.0).round() as usize {
+            processor.process_audio_chunk(&chunk);
+        }
+    }
+
+    #[test]
+    fn test_sustained_silence_triggers_auto_stop() {
+        let mut processor = AudioProcessor::new(test_windows(), Some(3), vad::create_backend(VadBackendKind::Energy)).unwrap();
+        feed(&mut processor, 0.0, 2.9);
+        assert!(!processor.silence_limit_exceeded());
+        feed(&mut processor, 0.0, 0.1);
+        assert!(processor.silence_limit_exceeded());
+
+        // 未配置或配置为0时从不自动停止
+        for disabled in [None, Some(0)] {
+            let mut processor = AudioProcessor::new(test_windows(), disabled, vad::create_backend(VadBackendKind::Energy)).unwrap();
+            feed(&mut processor, 0.0, 10.0);
+            assert!(!processor.silence_limit_exceeded());
+        }
+    }
+
+    #[test]
+    fn test_intermittent_speech_resets_silence() {
+        let mut processor = AudioProcessor::new(test_windows(), Some(3), vad::create_backend(VadBackendKind::Energy)).unwrap();
+        for _ in 0..5 {
+            feed(&mut processor, 0.0, 2.5);
+            feed(&mut processor, 0.1, 0.1);
+        }
+        assert!(!processor.silence_limit_exceeded());
+    }
+
+    fn window_segment(text: &str, t0: i64, t1: i64) -> WindowSegment {
+        WindowSegment { text: text.to_string(), t0, t1, confidence: Some(0.9), no_speech_prob: 0.1, tokens: Vec::new() }
+    }
+
+    #[test]
+    fn test_window_segments_get_absolute_times() {
+        let config = RealtimeConfig::default();
+        let mut timeline = SegmentTimeline::new();
+
+        // 第二个窗口从2秒开始，与第一个窗口重叠的部分不再输出
+        let windows = [
+            (0.0, vec![window_segment("你好", 0, 150), window_segment("世界", 150, 300)]),
+            (2.0, vec![window_segment("世界", 0, 100), window_segment("今天", 100, 250)]),
+            (4.0, vec![window_segment("天气", 60, 420)]),
+        ];
+        let results: Vec<WindowTranscript> = windows.iter()
+            .filter_map(|(offset, segments)| assemble_window(segments, *offset, &mut timeline, &config))
+            .collect();
+
+        assert_eq!(results.len(), 3);
+        let expected = [(0.0, 3.0), (3.0, 4.5), (4.6, 8.2)];
+        for (result, (start, end)) in results.iter().zip(expected) {
+            assert!((result.start_time - start).abs() < 1e-9 && (result.end_time - end).abs() < 1e-9,
+                "{:?} != ({}, {})", result, start, end);
+        }
+        assert!(results.iter().all(|result| result.no_speech_prob == Some(0.1f32 as f64)));
+        for pair in results.windows(2) {
+            assert!(pair[0].start_time < pair[0].end_time);
+            assert!(pair[0].end_time <= pair[1].start_time);
+        }
+
+        // 完全落在已输出范围内的窗口没有新内容
+        assert!(assemble_window(&[window_segment("天气", 0, 300)], 5.0, &mut timeline, &config).is_none());
+
+        // 只有非语音标注的窗口被丢弃，标注与正文混合时保留正文
+        assert!(assemble_window(&[window_segment("[音乐]", 0, 200)], 9.0, &mut timeline, &config).is_none());
+        let mixed = assemble_window(&[window_segment("(掌声) 谢谢大家", 0, 200)], 12.0, &mut timeline, &config).unwrap();
+        assert!(mixed.text.contains("谢谢大家") && !mixed.text.contains("掌声"), "{:?}", mixed);
+    }
+
+    #[test]
+    fn test_context_carried_from_previous_segment() {
+        let with_tokens = |text: &str, t0: i64, t1: i64, tokens: Vec<whisper_token>| WindowSegment { tokens, ..window_segment(text, t0, t1) };
+
+        let enabled = RealtimeConfig { carry_context: true, max_context_tokens: 4, ..RealtimeConfig::default() };
+        let mut context = ContextCarryOver::new(&enabled);
+        assert!(context.prompt().is_empty());
+        let mut timeline = SegmentTimeline::new();
+        let window = assemble_window(&[with_tokens("你好", 0, 100, vec![1, 2]), with_tokens("世界", 100, 200, vec![3])], 0.0, &mut timeline, &enabled).unwrap();
+        context.carry(&window.tokens);
+        assert_eq!(context.prompt(), &[1, 2, 3]);
+
+        // 下一窗口从1秒开始与上一窗口重叠：已输出的段不再携带，只追加新确定的段，超过上限时保留末尾
+        let window = assemble_window(&[with_tokens("世界", 0, 100, vec![3]), with_tokens("今天天气", 100, 250, vec![10, 11, 12])], 1.0, &mut timeline, &enabled).unwrap();
+        assert_eq!(window.tokens, vec![10, 11, 12]);
+        context.carry(&window.tokens);
+        assert_eq!(context.prompt(), &[3, 10, 11, 12]);
+
+        let mut disabled = ContextCarryOver::new(&RealtimeConfig::default());
+        disabled.carry(&[1, 2]);
+        assert!(disabled.prompt().is_empty());
+
+        let uncapped = RealtimeConfig { carry_context: true, max_context_tokens: 10_000, ..RealtimeConfig::default() };
+        assert_eq!(ContextCarryOver::new(&uncapped).max_tokens, MAX_CONTEXT_TOKENS);
+    }
+
+    #[test]
+    fn test_preprocessing_toggles() {
+        let quiet: Vec<f32> = (0..1600).map(|i| (i as f32 * 0.05).sin() * 0.01).collect();
+
+        // 默认行为不变：标准化把安静音频放大
+        let config = RealtimeConfig::default();
+        let normalized = RealtimeAudioCapture::preprocess_for_recognition(&quiet, &config);
+        assert!(normalized.iter().map(|x| x.abs()).fold(0.0, f32::max) > 0.05);
+
+        let disabled = RealtimeConfig { normalize: false, ..RealtimeConfig::default() };
+        assert_eq!(RealtimeAudioCapture::preprocess_for_recognition(&quiet, &disabled), quiet);
+
+        let emphasized = RealtimeConfig { normalize: false, pre_emphasis: Some(0.97), ..RealtimeConfig::default() };
+        let output = RealtimeAudioCapture::preprocess_for_recognition(&quiet, &emphasized);
+        assert_eq!(output[0], quiet[0]);
+        assert!((output[10] - (quiet[10] - 0.97 * quiet[9])).abs() < 1e-7);
+
+        assert!(validate_pre_emphasis(Some(0.97)).is_ok());
+        assert!(validate_pre_emphasis(None).is_ok());
+        assert!(validate_pre_emphasis(Some(1.2)).is_err());
+    }
+
+    #[test]
+    fn test_validate_config_rejects_each_invalid_part() {
+        assert!(validate_config(&RealtimeConfig::default()).is_ok());
+        assert!(validate_config(&RealtimeConfig { pre_emphasis: Some(1.2), ..RealtimeConfig::default() }).is_err());
+        assert!(validate_config(&RealtimeConfig { input_gain: Some(f32::NAN), ..RealtimeConfig::default() }).is_err());
+        assert!(validate_config(&RealtimeConfig { input_gain: Some(20.0), ..RealtimeConfig::default() }).is_err());
+        assert!(validate_config(&RealtimeConfig { overlap_ms: 1500, ..windows_config("buffered") }).is_err());
+    }
+
+    #[test]
+    fn test_emitted_results_are_retrievable() {
+        let results = RealtimeResults::new(TextJoinStyle::default(), "zh");
+        assert_eq!(results.current_transcript().unwrap(), "");
+
+        let texts = [("大家好", Some("Speaker 1")), ("今天讨论预算", Some("Speaker 2")), ("先看第一季度", Some("Speaker 2"))];
+        for (i, (text, speaker)) in texts.iter().enumerate() {
+            let result = RecognitionResult {
+                text: text.to_string(),
+                confidence: 0.9,
+                is_temporary: false,
+                speaker: speaker.map(str::to_string),
+                timestamp: 1_000 + i as u64 * 2_000,
+                start_time: i as f64 * 2.0,
+                end_time: i as f64 * 2.0 + 1.5,
+                confidence_band: Some(ConfidenceBand::High),
+            };
+            results.record(&result, i as u32);
+        }
+
+        let segments = results.segments().unwrap();
+        assert_eq!(segments.len(), 3);
+        assert_eq!(segments[1].speaker.as_deref(), Some("Speaker 2"));
+        assert!(segments.iter().all(|segment| segment.is_final));
+        assert_eq!(results.current_transcript().unwrap(), "大家好今天讨论预算先看第一季度");
+
+        // 克隆共享同一缓冲区，处理线程写入后命令侧可见
+        let shared = results.clone();
+        shared.record(&RecognitionResult {
+            text: "好的".to_string(), confidence: 0.8, is_temporary: false, speaker: None,
+            timestamp: 9_000, start_time: 8.0, end_time: 8.5, confidence_band: None,
+        }, 3);
+        assert_eq!(results.segments().unwrap().len(), 4);
+    }
+
+    fn stalled_events(events: &[WatchdogEvent]) -> usize {
+        events.iter().filter(|event| matches!(event, WatchdogEvent::Stalled(_))).count()
+    }
+
+    #[test]
+    fn test_stalled_audio_source_triggers_event() {
+        let start = Instant::now();
+        let at = |millis: u64| start + Duration::from_millis(millis);
+        let mut watchdog = RecordingWatchdog::new(start, Duration::from_secs(5));
+
+        // 音频块持续到达时只有心跳
+        for millis in (100..=4000).step_by(100) {
+            watchdog.chunk_received(at(millis));
+            assert_eq!(stalled_events(&watchdog.poll(at(millis), 10_000 + millis, false)), 0);
+        }
+
+        // 音频源停止：5秒后报告一次卡顿，之后不重复报告
+        assert_eq!(stalled_events(&watchdog.poll(at(8900), 18_900, false)), 0);
+        assert_eq!(watchdog.poll(at(9000), 19_000, false), vec![
+            WatchdogEvent::Stalled(RecordingStalledEvent { stalled_secs: 5.0 }),
+        ]);
+        // 卡顿期间心跳照常发送，并带上最后一次音频块的时间
+        assert_eq!(watchdog.poll(at(11000), 21_000, false), vec![
+            WatchdogEvent::Heartbeat(RecordingHeartbeat { uptime_secs: 11.0, last_chunk_at: Some(14_000) }),
+        ]);
+        assert_eq!(stalled_events(&watchdog.poll(at(12000), 22_000, false)), 0);
+
+        // 恢复后重新计时
+        watchdog.chunk_received(at(13000));
+        assert_eq!(stalled_events(&watchdog.poll(at(18000), 28_000, false)), 1);
+    }
+
+    #[test]
+    fn test_watchdog_follows_capture_callbacks() {
+        let clock = CaptureClock::new();
+        assert!(clock.last_callback().is_none());
+        clock.tick();
+        let first = clock.last_callback().unwrap();
+        assert!(first >= clock.epoch && first <= Instant::now());
+
+        // 处理线程忙于识别时晚一些才读到回调时间，按回调发生的时刻计时
+        let start = Instant::now();
+        let at = |millis: u64| start + Duration::from_millis(millis);
+        let mut watchdog = RecordingWatchdog::new(start, Duration::from_secs(5));
+        watchdog.chunk_received(at(4000));
+        assert_eq!(stalled_events(&watchdog.poll(at(8000), 0, false)), 0);
+
+        // 回调停止后处理线程每轮都读到同一时间，卡顿只报告一次
+        for millis in (8100..=12000).step_by(100) {
+            watchdog.chunk_received(at(4000));
+            let expected = usize::from(millis == 9000);
+            assert_eq!(stalled_events(&watchdog.poll(at(millis), 0, false)), expected, "{}", millis);
+        }
+    }
+
+    #[test]
+    fn test_paused_recording_is_not_stalled() {
+        let start = Instant::now();
+        let mut watchdog = RecordingWatchdog::new(start, Duration::from_secs(5));
+        for secs in 1..=20 {
+            assert_eq!(stalled_events(&watchdog.poll(start + Duration::from_secs(secs), 0, true)), 0);
+        }
+        // 恢复录音后从恢复时刻开始计时
+        assert_eq!(stalled_events(&watchdog.poll(start + Duration::from_secs(24), 0, false)), 0);
+        assert_eq!(stalled_events(&watchdog.poll(start + Duration::from_secs(25), 0, false)), 1);
+    }
+
+    #[test]
+    fn test_no_speech_segment_suppression() {
+        let threshold = default_no_speech_threshold();
+        assert!(should_suppress_segment(0.92, threshold));
+        assert!(!should_suppress_segment(0.05, threshold));
+    }
+}
@@ -0,0 +1,358 @@
+// realtime_autosave.rs - 实时录音的定时自动保存：每隔 save_interval 分钟把当前转录快照写入同一条记录
+use std::time::{Duration, Instant};
+use chrono::{DateTime, Utc};
+
+use crate::realtime_audio_full::RealtimeConfig;
+use crate::record_naming;
+use crate::storage::{TranscriptionConfig, TranscriptionRecord, TranscriptionResult, TranscriptionSegment};
+
+pub struct TranscriptAutoSaver {
+    interval: Option<Duration>, // None 表示未启用自动保存
+    record_id: String,
+    file_path: String, // 相对 app_data 目录的录音文件路径
+    config: TranscriptionConfig,
+    created_at: DateTime<Utc>,
+    started_at: Instant,
+    last_saved_at: Instant,
+    segments: Vec<TranscriptionSegment>,
+    dirty: bool, // 上次保存后是否有新的识别结果
+    auto_name: bool, // 录音结束时按内容命名
+}
+
+impl TranscriptAutoSaver {
+    pub fn new(config: &RealtimeConfig, record_id: &str, file_path: &str, now: Instant) -> Self {
+        let interval = (config.auto_save && config.save_interval > 0)
+            .then(|| Duration::from_secs(config.save_interval as u64 * 60));
+
+        Self {
+            interval,
+            record_id: record_id.to_string(),
+            file_path: file_path.to_string(),
+            config: TranscriptionConfig {
+                language: config.language.clone(),
+                mode: config.mode.clone(),
+                audio_enhancement: config.noise_reduction,
+                decoding: Some(config.decoding),
+                ..TranscriptionConfig::default()
+            },
+            created_at: Utc::now(),
+            started_at: now,
+            last_saved_at: now,
+            segments: Vec::new(),
+            dirty: false,
+            auto_name: config.auto_name,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.interval.is_some()
+    }
+
+    /// 记录一条识别结果，时间为相对录音开始的秒数
+    pub fn push_segment(&mut self, text: &str, start_time: f64, end_time: f64, speaker: Option<String>, confidence: Option<f64>, no_speech_prob: Option<f64>) {
+        self.segments.push(TranscriptionSegment {
+            id: format!("{}_{}", self.record_id, self.segments.len()),
+            start_time,
+            end_time,
+            text: text.to_string(),
+            speaker,
+            confidence,
+            no_speech_prob,
+            romanization: None,
+        });
+        self.dirty = true;
+    }
+
+    /// 距上次保存已满一个间隔且有新内容
+    pub fn is_due(&self, now: Instant) -> bool {
+        self.dirty && self.interval.is_some_and(|interval| now.duration_since(self.last_saved_at) >= interval)
+    }
+
+    pub fn snapshot(&self, now: Instant, status: &str) -> TranscriptionRecord {
+        let file_name = self.file_path.rsplit('/').next().unwrap_or(&self.file_path).to_string();
+        let text = self.segments.iter()
+            .map(|segment| segment.text.as_str())
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        // 录音过程中沿用时间戳名称，结束时才按内容命名，避免标题随识别结果跳动
+        let name = (self.auto_name && status == "completed")
+            .then(|| record_naming::derive_title(&text))
+            .flatten()
+            .unwrap_or_else(|| record_naming::timestamp_name("实时录音", self.created_at));
+
+        TranscriptionRecord {
+            id: self.record_id.clone(),
+            name,
+            original_file_name: file_name,
+            file_path: self.file_path.clone(),
+            file_size: 0,
+            duration: Some(now.duration_since(self.started_at).as_secs_f64()),
+            status: status.to_string(),
+            progress: if status == "completed" { 100.0 } else { 0.0 },
+            error_message: None,
+            created_at: self.created_at,
+            updated_at: Utc::now(),
+            tags: Vec::new(),
+            category: None,
+            is_starred: false,
+            config: self.config.clone(),
+            result: Some(TranscriptionResult {
+                text,
+                processing_time: 0.0,
+                accuracy: None,
+                segments: Some(self.segments.clone()),
+            }),
+            model_name: None,
+            realtime_factor: None,
+            sources: None,
+        }
+    }
+
+    /// 到期时通过 save 写入快照（记录ID固定，覆盖而不是新增），返回是否执行了写入
+    pub fn maybe_save<F, E>(&mut self, now: Instant, save: F) -> Result<bool, E>
+    where
+        F: FnOnce(&TranscriptionRecord) -> Result<(), E>,
+    {
+        if !self.is_due(now) {
+            return Ok(false);
+        }
+        self.save_now(now, "processing", save)
+    }
+
+    /// 录音结束时写入最终结果；未启用自动保存或没有识别结果时不写入
+    pub fn finish<F, E>(&mut self, now: Instant, save: F) -> Result<bool, E>
+    where
+        F: FnOnce(&TranscriptionRecord) -> Result<(), E>,
+    {
+        if !self.is_enabled() || self.segments.is_empty() {
+            return Ok(false);
+        }
+        self.save_now(now, "completed", save)
+    }
+
+    /// 切换到新的记录：先按 finish 写入当前记录的最终结果，再以新的记录ID和录音文件重新开始，返回是否写入了旧记录
+    pub fn roll_over<F, E>(&mut self, record_id: &str, file_path: &str, now: Instant, save: F) -> Result<bool, E>
+    where
+        F: FnOnce(&TranscriptionRecord) -> Result<(), E>,
+    {
+        let saved = self.finish(now, save)?;
+        self.record_id = record_id.to_string();
+        self.file_path = file_path.to_string();
+        self.created_at = Utc::now();
+        self.started_at = now;
+        self.last_saved_at = now;
+        self.segments.clear();
+        self.dirty = false;
+        Ok(saved)
+    }
+
+    fn save_now<F, E>(&mut self, now: Instant, status: &str, save: F) -> Result<bool, E>
+    where
+        F: FnOnce(&TranscriptionRecord) -> Result<(), E>,
+    {
+        save(&self.snapshot(now, status))?;
+        self.last_saved_at = now;
+        self.dirty = false;
+        Ok(true)
+    }
+}
+
+/// 新开始的一段录音
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordingPart {
+    pub part: u32, // 从1开始
+    pub record_id: String,
+    pub start_secs: f64, // 该段在整个会话中的起点
+}
+
+/// 长时间录音的自动拆分：单段达到 max_recording_minutes 后切换到新的记录ID，各段属于同一录音会话。
+/// 由录音写入线程按写入文件的样本数决定拆分点，文件边界不受识别进度影响
+pub struct RecordingSplitter {
+    max_samples: Option<usize>, // None 表示不拆分
+    sample_rate: u32,
+    session_id: String,
+    part: u32,
+    part_start: usize, // 当前段起点，按 sample_rate 下的样本数计
+}
+
+impl RecordingSplitter {
+    pub fn new(config: &RealtimeConfig, session_id: &str, sample_rate: u32) -> Self {
+        let max_samples = config.max_recording_minutes
+            .filter(|&minutes| minutes > 0)
+            .map(|minutes| minutes as usize * 60 * sample_rate as usize);
+        Self { max_samples, sample_rate, session_id: session_id.to_string(), part: 1, part_start: 0 }
+    }
+
+    pub fn session_id(&self) -> &str {
+        &self.session_id
+    }
+
+    pub fn part(&self) -> u32 {
+        self.part
+    }
+
+    /// 第一段沿用会话ID，之后的段加 _partN 后缀
+    pub fn record_id(&self) -> String {
+        if self.part == 1 { self.session_id.clone() } else { format!("{}_part{}", self.session_id, self.part) }
+    }
+
+    /// 当前段起点在会话中的秒数，识别结果的时间减去该值即为段内时间
+    pub fn part_offset_secs(&self) -> f64 {
+        self.part_start as f64 / self.sample_rate as f64
+    }
+
+    /// 此前已写入 written 个样本时，长度为 chunk_len 的下一块中到达当前段上限的位置；不在本块内时返回 None
+    pub fn boundary_in(&self, written: usize, chunk_len: usize) -> Option<usize> {
+        let offset = (self.part_start + self.max_samples?).checked_sub(written)?;
+        (offset < chunk_len).then_some(offset)
+    }
+
+    /// 已写入样本数达到当前段上限时切换到下一段并返回新段
+    pub fn advance(&mut self, written: usize) -> Option<RecordingPart> {
+        let max_samples = self.max_samples?;
+        if written < self.part_start + max_samples {
+            return None;
+        }
+        self.part += 1;
+        self.part_start += max_samples;
+        Some(RecordingPart { part: self.part, record_id: self.record_id(), start_secs: self.part_offset_secs() })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::test_support::temp_storage;
+    use crate::whisper_input::WHISPER_SAMPLE_RATE;
+
+    fn config(auto_save: bool, save_interval: u32) -> RealtimeConfig {
+        RealtimeConfig {
+            auto_save,
+            save_interval,
+            ..RealtimeConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_interval_updates_record_in_place() {
+        let (storage, dir) = temp_storage("realtime_autosave");
+        let t0 = Instant::now();
+        let mut saver = TranscriptAutoSaver::new(&config(true, 2), "recording_1", "recordings/recording_1.wav", t0);
+
+        saver.push_segment("第一段", 0.0, 2.0, None, Some(0.9), None);
+        assert!(!saver.maybe_save(t0 + Duration::from_secs(119), |r| storage.save_record(r)).unwrap());
+        assert!(saver.maybe_save(t0 + Duration::from_secs(120), |r| storage.save_record(r)).unwrap());
+
+        // 没有新内容时不重复写入
+        assert!(!saver.maybe_save(t0 + Duration::from_secs(240), |r| storage.save_record(r)).unwrap());
+
+        saver.push_segment("第二段", 2.0, 4.0, None, Some(0.8), None);
+        assert!(saver.maybe_save(t0 + Duration::from_secs(241), |r| storage.save_record(r)).unwrap());
+
+        let records = storage.get_all_records().unwrap();
+        assert_eq!(records.len(), 1);
+        let result = records[0].result.as_ref().unwrap();
+        assert_eq!(result.text, "第一段\n第二段");
+        assert_eq!(result.segments.as_ref().unwrap().len(), 2);
+        assert_eq!(records[0].status, "processing");
+
+        assert!(saver.finish(t0 + Duration::from_secs(250), |r| storage.save_record(r)).unwrap());
+        let record = storage.get_record("recording_1").unwrap().unwrap();
+        assert_eq!(record.status, "completed");
+        assert_eq!(storage.get_all_records().unwrap().len(), 1);
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_auto_name_applied_on_completion() {
+        let t0 = Instant::now();
+        let named = RealtimeConfig { auto_name: true, ..config(true, 1) };
+        let mut saver = TranscriptAutoSaver::new(&named, "recording_3", "recordings/recording_3.wav", t0);
+        saver.push_segment("下周发布计划。先确认测试进度", 0.0, 3.0, None, None, None);
+
+        assert!(saver.snapshot(t0, "processing").name.starts_with("实时录音 "));
+        assert_eq!(saver.snapshot(t0, "completed").name, "下周发布计划");
+        let unnamed = TranscriptAutoSaver::new(&config(true, 1), "recording_4", "recordings/recording_4.wav", t0);
+        assert!(unnamed.snapshot(t0, "completed").name.starts_with("实时录音 "));
+    }
+
+    #[test]
+    fn test_crossing_cap_rolls_over_to_new_record() {
+        let (storage, dir) = temp_storage("realtime_rollover");
+        let t0 = Instant::now();
+        let capped = RealtimeConfig { max_recording_minutes: Some(1), ..config(true, 5) };
+        let mut splitter = RecordingSplitter::new(&capped, "recording_5", WHISPER_SAMPLE_RATE);
+        let mut saver = TranscriptAutoSaver::new(&capped, &splitter.record_id(), "recordings/recording_5.wav", t0);
+        storage.link_session_part(splitter.session_id(), &splitter.record_id(), splitter.part()).unwrap();
+
+        saver.push_segment("第一段内容", 0.0, 30.0, None, None, None);
+        assert_eq!(splitter.advance(59 * 16000), None);
+        let part = splitter.advance(61 * 16000).unwrap();
+        assert_eq!(part, RecordingPart { part: 2, record_id: "recording_5_part2".to_string(), start_secs: 60.0 });
+
+        let file_path = format!("recordings/{}.wav", part.record_id);
+        assert!(saver.roll_over(&part.record_id, &file_path, t0 + Duration::from_secs(61), |r| storage.save_record(r)).unwrap());
+        storage.link_session_part(splitter.session_id(), &part.record_id, part.part).unwrap();
+
+        // 新记录的时间从该段起点重新计算
+        saver.push_segment("第二段内容", 62.0 - splitter.part_offset_secs(), 70.0 - splitter.part_offset_secs(), None, None, None);
+        assert!(saver.finish(t0 + Duration::from_secs(75), |r| storage.save_record(r)).unwrap());
+        assert_eq!(splitter.advance(75 * 16000), None);
+
+        let first = storage.get_record("recording_5").unwrap().unwrap();
+        let second = storage.get_record("recording_5_part2").unwrap().unwrap();
+        assert_eq!((first.status.as_str(), second.status.as_str()), ("completed", "completed"));
+        assert_eq!(first.result.unwrap().text, "第一段内容");
+        let second_result = second.result.unwrap();
+        assert_eq!(second_result.text, "第二段内容");
+        assert_eq!(second_result.segments.unwrap()[0].start_time, 2.0);
+        assert_eq!(second.file_path, "recordings/recording_5_part2.wav");
+        assert_eq!(storage.get_session_records("recording_5_part2").unwrap(), vec!["recording_5", "recording_5_part2"]);
+
+        // 未设置上限时不拆分
+        let mut unlimited = RecordingSplitter::new(&config(true, 5), "recording_6", WHISPER_SAMPLE_RATE);
+        assert_eq!(unlimited.boundary_in(0, usize::MAX), None);
+        assert_eq!(unlimited.advance(usize::MAX), None);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_boundary_falls_on_exact_sample_at_recording_rate() {
+        let capped = RealtimeConfig { max_recording_minutes: Some(1), ..config(true, 5) };
+        let mut splitter = RecordingSplitter::new(&capped, "recording_7", 48000);
+        let cap = 60 * 48000;
+
+        assert_eq!(splitter.boundary_in(0, 1024), None);
+        assert_eq!(splitter.boundary_in(cap - 1000, 1024), Some(1000));
+        assert_eq!(splitter.boundary_in(cap - 1024, 1024), None);
+        assert_eq!(splitter.advance(cap - 24), None);
+        let part = splitter.advance(cap).unwrap();
+        assert_eq!(part.start_secs, 60.0);
+        assert_eq!(splitter.boundary_in(cap, 1024), None);
+        assert_eq!(splitter.boundary_in(2 * cap - 10, 1024), Some(10));
+    }
+
+    #[test]
+    fn test_disabled_auto_save_never_writes() {
+        let t0 = Instant::now();
+        for disabled in [config(false, 1), config(true, 0)] {
+            let mut saver = TranscriptAutoSaver::new(&disabled, "recording_2", "recordings/recording_2.wav", t0);
+            saver.push_segment("内容", 0.0, 1.0, None, None, None);
+
+            let mut writes = 0;
+            for minutes in 1..=10 {
+                saver.maybe_save(t0 + Duration::from_secs(minutes * 60), |_| {
+                    writes += 1;
+                    Ok::<(), ()>(())
+                }).unwrap();
+            }
+            saver.finish(t0 + Duration::from_secs(3600), |_| {
+                writes += 1;
+                Ok::<(), ()>(())
+            }).unwrap();
+            assert_eq!(writes, 0);
+        }
+    }
+}
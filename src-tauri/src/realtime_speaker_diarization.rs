@@ -1,17 +1,27 @@
 use std::collections::HashMap;
 use serde::{Deserialize, Serialize};
+use rustfft::num_complex::Complex32;
+
+use crate::spectral_analysis;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SpeakerProfile {
     pub id: String,
     pub name: String,
-    pub fundamental_freq: f32,
-    pub formant_frequencies: Vec<f32>,
-    pub spectral_centroid: f32,
+    pub embedding: Vec<f32>, // 说话人嵌入，按指数移动平均更新
     pub confidence: f32,
     pub sample_count: u32,
 }
 
+/// 由一段 16kHz 单声道音频生成固定长度的说话人嵌入，同一说话人的嵌入余弦距离应较小。
+/// 默认使用声学特征（FeatureEmbedder），神经网络嵌入模型实现该 trait 即可替换
+pub trait SpeakerEmbedder: Send {
+    /// 嵌入维度，同一实现的所有输出长度一致
+    fn dimension(&self) -> usize;
+    /// 音频过短或无法分析时返回 None
+    fn embed(&self, audio: &[f32]) -> Option<Vec<f32>>;
+}
+
 #[derive(Debug, Clone)]
 pub struct VoiceFeatures {
     pub fundamental_freq: f32,
@@ -23,99 +33,41 @@ pub struct VoiceFeatures {
     pub mfcc_features: Vec<f32>,
 }
 
-#[derive(Debug)]
-pub struct RealtimeSpeakerDiarization {
-    speaker_profiles: HashMap<String, SpeakerProfile>,
-    current_speaker: Option<String>,
-    feature_history: Vec<VoiceFeatures>,
-    max_history: usize,
-}
-
-impl RealtimeSpeakerDiarization {
-    pub fn new() -> Self {
-        Self {
-            speaker_profiles: HashMap::new(),
-            current_speaker: None,
-            feature_history: Vec::new(),
-            max_history: 10, // 保留最近10个特征用于说话人识别
-        }
-    }
-
-    pub fn identify_speaker(&mut self, audio: &[f32]) -> Option<String> {
-        // 提取音色特征
-        let features = match self.extract_voice_features(audio) {
-            Ok(f) => f,
-            Err(_) => return None,
-        };
-
-        // 添加到历史记录
-        self.feature_history.push(features.clone());
-        if self.feature_history.len() > self.max_history {
-            self.feature_history.remove(0);
-        }
-
-        // 如果没有已知说话人，创建第一个
-        if self.speaker_profiles.is_empty() {
-            let speaker_id = "Speaker_1".to_string();
-            let profile = SpeakerProfile {
-                id: speaker_id.clone(),
-                name: "说话人A".to_string(),
-                fundamental_freq: features.fundamental_freq,
-                formant_frequencies: features.formant_frequencies.clone(),
-                spectral_centroid: features.spectral_centroid,
-                confidence: 1.0,
-                sample_count: 1,
-            };
-            self.speaker_profiles.insert(speaker_id.clone(), profile);
-            self.current_speaker = Some(speaker_id.clone());
-            return Some("说话人A".to_string());
-        }
-
-        // 计算与已知说话人的相似度
-        let mut best_match = None;
-        let mut best_similarity = 0.0;
-
-        for (speaker_id, profile) in &self.speaker_profiles {
-            let similarity = self.calculate_speaker_similarity(&features, profile);
-            if similarity > best_similarity {
-                best_similarity = similarity;
-                best_match = Some(speaker_id.clone());
-            }
-        }
-
-        const SIMILARITY_THRESHOLD: f32 = 0.7;
+const FORMANT_COUNT: usize = 3;
+const MFCC_COUNT: usize = 12;
+/// 典型语音的基频与前三个共振峰 (Hz)，嵌入以此为原点
+const REFERENCE_PITCH_HZ: f32 = 150.0;
+const REFERENCE_FORMANTS_HZ: [f32; FORMANT_COUNT] = [500.0, 1500.0, 2500.0];
+const REFERENCE_MFCC_LEVEL: f32 = -6.0;
 
-        if let Some(speaker_id) = best_match {
-            if best_similarity > SIMILARITY_THRESHOLD {
-                // 更新说话人特征
-                self.update_speaker_profile(&speaker_id, &features);
-                let profile = self.speaker_profiles.get(&speaker_id).unwrap();
-                self.current_speaker = Some(speaker_id);
-                return Some(profile.name.clone());
-            }
-        }
+/// 默认嵌入：基频、共振峰、频谱质心/带宽、过零率与 MFCC 相对典型语音的偏移。
+/// 各维按经验尺度缩放，使1.0约等于可区分的音色差异，不同音色的嵌入指向不同方向
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FeatureEmbedder;
 
-        // 创建新说话人
-        let speaker_count = self.speaker_profiles.len();
-        let speaker_id = format!("Speaker_{}", speaker_count + 1);
-        let speaker_names = ["说话人A", "说话人B", "说话人C", "说话人D"];
-        let speaker_name = speaker_names.get(speaker_count)
-            .unwrap_or(&"说话人X")
-            .to_string();
+impl FeatureEmbedder {
+    const DIMENSION: usize = 1 + FORMANT_COUNT + 3 + 1 + MFCC_COUNT;
 
-        let profile = SpeakerProfile {
-            id: speaker_id.clone(),
-            name: speaker_name.clone(),
-            fundamental_freq: features.fundamental_freq,
-            formant_frequencies: features.formant_frequencies.clone(),
-            spectral_centroid: features.spectral_centroid,
-            confidence: 1.0,
-            sample_count: 1,
+    /// 对数基频、对数共振峰、频谱质心/带宽、过零率、MFCC 平均电平，以及去掉平均电平后的 MFCC 形状
+    pub fn embedding_from_features(features: &VoiceFeatures) -> Vec<f32> {
+        let log_offset = |value: f32, reference: f32, scale: f32| {
+            if value > 0.0 { (value / reference).ln() / scale } else { 0.0 }
         };
+        let mut embedding = Vec::with_capacity(Self::DIMENSION);
+        embedding.push(log_offset(features.fundamental_freq, REFERENCE_PITCH_HZ, 0.15));
+        for (i, reference) in REFERENCE_FORMANTS_HZ.iter().enumerate() {
+            let formant = features.formant_frequencies.get(i).copied().unwrap_or(0.0);
+            embedding.push(log_offset(formant, *reference, 0.3));
+        }
+        embedding.push((features.spectral_centroid - 1500.0) / 1000.0);
+        embedding.push((features.spectral_bandwidth - 1000.0) / 1000.0);
+        embedding.push((features.zero_crossing_rate - 0.05) / 0.02);
 
-        self.speaker_profiles.insert(speaker_id.clone(), profile);
-        self.current_speaker = Some(speaker_id);
-        Some(speaker_name)
+        let mfcc: Vec<f32> = (0..MFCC_COUNT).map(|i| features.mfcc_features.get(i).copied().unwrap_or(0.0)).collect();
+        let level = mfcc.iter().sum::<f32>() / MFCC_COUNT as f32;
+        embedding.push((level - REFERENCE_MFCC_LEVEL) / 3.0);
+        embedding.extend(mfcc.iter().map(|&value| value - level));
+        embedding
     }
 
     fn extract_voice_features(&self, audio: &[f32]) -> Result<VoiceFeatures, String> {
@@ -153,32 +105,7 @@ impl RealtimeSpeakerDiarization {
     }
 
     fn estimate_fundamental_frequency(&self, audio: &[f32]) -> f32 {
-        let sample_rate = 16000.0;
-        let min_period = (sample_rate / 500.0) as usize; // 最高500Hz
-        let max_period = (sample_rate / 50.0) as usize;  // 最低50Hz
-        
-        let mut max_correlation = 0.0;
-        let mut best_period = min_period;
-        
-        for period in min_period..=max_period.min(audio.len() / 2) {
-            let mut correlation = 0.0;
-            let mut count = 0;
-            
-            for i in 0..(audio.len() - period) {
-                correlation += audio[i] * audio[i + period];
-                count += 1;
-            }
-            
-            if count > 0 {
-                correlation /= count as f32;
-                if correlation > max_correlation {
-                    max_correlation = correlation;
-                    best_period = period;
-                }
-            }
-        }
-        
-        sample_rate / best_period as f32
+        estimate_pitch(audio, 16000).unwrap_or(0.0)
     }
 
     fn estimate_formant_frequencies(&self, audio: &[f32]) -> Vec<f32> {
@@ -277,49 +204,8 @@ impl RealtimeSpeakerDiarization {
     }
 
     fn calculate_spectral_features(&self, audio: &[f32]) -> (f32, f32) {
-        let chunk_size = 512;
-        let mut magnitudes = Vec::new();
-        
-        for chunk in audio.chunks(chunk_size) {
-            if chunk.len() == chunk_size {
-                let magnitude_sum: f32 = chunk.iter().map(|&x| x.abs()).sum();
-                magnitudes.push(magnitude_sum / chunk_size as f32);
-            }
-        }
-        
-        if magnitudes.is_empty() {
-            return (0.0, 0.0);
-        }
-        
-        // 计算质心
-        let mut weighted_sum = 0.0;
-        let mut total_magnitude = 0.0;
-        
-        for (i, &magnitude) in magnitudes.iter().enumerate() {
-            let frequency = i as f32 * 16000.0 / magnitudes.len() as f32;
-            weighted_sum += frequency * magnitude;
-            total_magnitude += magnitude;
-        }
-        
-        let centroid = if total_magnitude > 0.0 {
-            weighted_sum / total_magnitude
-        } else {
-            0.0
-        };
-        
-        // 计算带宽
-        let mut variance = 0.0;
-        for (i, &magnitude) in magnitudes.iter().enumerate() {
-            let frequency = i as f32 * 16000.0 / magnitudes.len() as f32;
-            variance += (frequency - centroid).powi(2) * magnitude;
-        }
-        let bandwidth = if total_magnitude > 0.0 {
-            (variance / total_magnitude).sqrt()
-        } else {
-            0.0
-        };
-        
-        (centroid, bandwidth)
+        let spectral = spectral_analysis::analyze_spectrum(audio, 16000);
+        (spectral.centroid, spectral.bandwidth)
     }
 
     fn calculate_zero_crossing_rate(&self, audio: &[f32]) -> f32 {
@@ -366,84 +252,363 @@ impl RealtimeSpeakerDiarization {
         
         mfcc
     }
+}
 
-    fn calculate_speaker_similarity(&self, features: &VoiceFeatures, profile: &SpeakerProfile) -> f32 {
-        let mut similarity = 0.0;
-        let mut weight_sum = 0.0;
+impl SpeakerEmbedder for FeatureEmbedder {
+    fn dimension(&self) -> usize {
+        Self::DIMENSION
+    }
 
-        // 基频相似度 (权重: 0.3)
-        if features.fundamental_freq > 0.0 && profile.fundamental_freq > 0.0 {
-            let freq_diff = (features.fundamental_freq - profile.fundamental_freq).abs();
-            let freq_sim = 1.0 - (freq_diff / (features.fundamental_freq + profile.fundamental_freq) * 2.0).min(1.0);
-            similarity += freq_sim * 0.3;
-            weight_sum += 0.3;
-        }
+    fn embed(&self, audio: &[f32]) -> Option<Vec<f32>> {
+        self.extract_voice_features(audio).ok().map(|features| Self::embedding_from_features(&features))
+    }
+}
 
-        // 频谱质心相似度 (权重: 0.2)
-        if features.spectral_centroid > 0.0 && profile.spectral_centroid > 0.0 {
-            let centroid_diff = (features.spectral_centroid - profile.spectral_centroid).abs();
-            let centroid_sim = 1.0 - (centroid_diff / (features.spectral_centroid + profile.spectral_centroid) * 2.0).min(1.0);
-            similarity += centroid_sim * 0.2;
-            weight_sum += 0.2;
+/// 各维度的在线均值/方差统计（Welford算法），用于 z-score 归一化
+#[derive(Debug, Clone, Default)]
+pub struct FeatureStatistics {
+    count: u32,
+    mean: Vec<f64>,
+    m2: Vec<f64>,
+}
+
+impl FeatureStatistics {
+    pub fn observe(&mut self, vector: &[f32]) {
+        if self.mean.len() != vector.len() {
+            // 维度变化时重新统计
+            *self = Self { count: 0, mean: vec![0.0; vector.len()], m2: vec![0.0; vector.len()] };
+        }
+        self.count += 1;
+        for (i, &value) in vector.iter().enumerate() {
+            let value = value as f64;
+            let delta = value - self.mean[i];
+            self.mean[i] += delta / self.count as f64;
+            self.m2[i] += delta * (value - self.mean[i]);
         }
+    }
+
+    pub fn count(&self) -> u32 {
+        self.count
+    }
 
-        // 共振峰相似度 (权重: 0.3)
-        let mut formant_similarity = 0.0;
-        let formant_count = features.formant_frequencies.len().min(profile.formant_frequencies.len());
-        if formant_count > 0 {
-            for i in 0..formant_count {
-                if features.formant_frequencies[i] > 0.0 && profile.formant_frequencies[i] > 0.0 {
-                    let formant_diff = (features.formant_frequencies[i] - profile.formant_frequencies[i]).abs();
-                    let formant_sim = 1.0 - (formant_diff / (features.formant_frequencies[i] + profile.formant_frequencies[i]) * 2.0).min(1.0);
-                    formant_similarity += formant_sim;
+    /// z-score 归一化；方差尚不可用或接近0的维度输出0（避免放大浮点舍入噪声）
+    pub fn normalize(&self, vector: &[f32]) -> Vec<f32> {
+        vector.iter().enumerate()
+            .map(|(i, &value)| {
+                if self.count < 2 || i >= self.mean.len() {
+                    return 0.0;
                 }
+                let std_dev = (self.m2[i] / (self.count - 1) as f64).sqrt();
+                if std_dev > 1e-4 {
+                    ((value as f64 - self.mean[i]) / std_dev) as f32
+                } else {
+                    0.0
+                }
+            })
+            .collect()
+    }
+}
+
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm_a <= f32::EPSILON || norm_b <= f32::EPSILON {
+        return 0.0;
+    }
+    (dot / (norm_a * norm_b)).clamp(-1.0, 1.0)
+}
+
+pub fn cosine_distance(a: &[f32], b: &[f32]) -> f32 {
+    1.0 - cosine_similarity(a, b)
+}
+
+/// 某个说话人的一段发言时间范围（秒）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpeakerTimeRange {
+    pub speaker: String,
+    pub start_time: f64,
+    pub end_time: f64,
+}
+
+/// 实时识别中切换到另一位说话人；录音开始后第一位说话人开口时不发送
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SpeakerChange {
+    pub speaker: String,
+    pub timestamp: f64, // 新说话人开始发言的时间（秒，相对录音开始）
+}
+
+/// 一位说话人在整个会话中的发言统计
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SpeakerTalkTime {
+    pub speaker: String,
+    pub talk_time: f64, // 累计发言秒数
+    pub turns: usize,
+}
+
+/// 录音结束时的说话人分离汇总，由发言时间线计算
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DiarizationSummary {
+    pub speaker_count: usize,
+    pub speakers: Vec<SpeakerTalkTime>, // 按首次发言的先后排列
+    pub longest_turn: Option<SpeakerTimeRange>,
+}
+
+/// 汇总发言时间线：说话人数、每人累计发言时长与最长的一次发言
+pub fn summarize_timeline(timeline: &[SpeakerTimeRange]) -> DiarizationSummary {
+    let mut speakers: Vec<SpeakerTalkTime> = Vec::new();
+    for turn in timeline {
+        let duration = (turn.end_time - turn.start_time).max(0.0);
+        match speakers.iter_mut().find(|entry| entry.speaker == turn.speaker) {
+            Some(entry) => {
+                entry.talk_time += duration;
+                entry.turns += 1;
             }
-            formant_similarity /= formant_count as f32;
-            similarity += formant_similarity * 0.3;
-            weight_sum += 0.3;
-        }
-
-        // MFCC相似度 (权重: 0.2)
-        let mfcc_count = features.mfcc_features.len().min(12);
-        if mfcc_count > 0 {
-            let mut mfcc_distance = 0.0;
-            for i in 0..mfcc_count {
-                let diff = features.mfcc_features[i] - 0.0; // 与默认值比较
-                mfcc_distance += diff * diff;
+            None => speakers.push(SpeakerTalkTime { speaker: turn.speaker.clone(), talk_time: duration, turns: 1 }),
+        }
+    }
+    let longest_turn = timeline.iter()
+        .max_by(|a, b| (a.end_time - a.start_time).total_cmp(&(b.end_time - b.start_time)))
+        .cloned();
+    DiarizationSummary { speaker_count: speakers.len(), speakers, longest_turn }
+}
+
+/// 整段音频的说话人数量估算结果，可用于预填 max_speakers
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpeakerCountEstimate {
+    pub speaker_count: usize,
+    pub ranges: Vec<SpeakerTimeRange>,
+}
+
+const ESTIMATE_WINDOW_SECONDS: f64 = 1.0; // 非流式估算时的分析窗口
+const MIN_WINDOW_ENERGY: f32 = 1e-5;      // 低于该能量的窗口视为静音
+const CLUSTER_MERGE_DISTANCE: f32 = 0.3;  // 嵌入的平均余弦距离小于该值时合并为同一说话人
+const SPEAKER_NAMES: [&str; 4] = ["说话人A", "说话人B", "说话人C", "说话人D"];
+const SIMILARITY_THRESHOLD: f32 = 0.6;    // 归一化嵌入的余弦相似度高于该值视为同一说话人
+const MIN_STATS_SAMPLES: u32 = 5;         // 统计样本不足时 z-score 不可靠，不新建说话人
+pub const DEFAULT_SPEAKER_HISTORY: usize = 10; // 匹配说话人时参与平均的最近嵌入数
+const MAX_SPEAKER_HISTORY: usize = 50;
+
+pub struct RealtimeSpeakerDiarization {
+    embedder: Box<dyn SpeakerEmbedder>,
+    speaker_profiles: HashMap<String, SpeakerProfile>,
+    current_speaker: Option<String>,
+    embedding_history: Vec<Vec<f32>>,
+    max_history: usize,
+    embedding_stats: FeatureStatistics,
+    timeline: Vec<SpeakerTimeRange>, // 按时间顺序的发言轮次，相邻的同一说话人合并
+}
+
+impl RealtimeSpeakerDiarization {
+    pub fn new() -> Self {
+        Self::with_embedder(Box::new(FeatureEmbedder))
+    }
+
+    pub fn with_embedder(embedder: Box<dyn SpeakerEmbedder>) -> Self {
+        Self {
+            embedder,
+            speaker_profiles: HashMap::new(),
+            current_speaker: None,
+            embedding_history: Vec::new(),
+            max_history: DEFAULT_SPEAKER_HISTORY,
+            embedding_stats: FeatureStatistics::default(),
+            timeline: Vec::new(),
+        }
+    }
+
+    /// 识别 start_time..end_time（秒）这段音频的说话人并记入发言时间线；
+    /// 与上一轮次的说话人不同时同时返回切换事件，第一位说话人开口不算切换
+    pub fn identify_speaker_at(&mut self, audio: &[f32], start_time: f64, end_time: f64) -> (Option<String>, Option<SpeakerChange>) {
+        let Some(speaker) = self.identify_speaker(audio) else {
+            return (None, None);
+        };
+        match self.timeline.last_mut() {
+            Some(turn) if turn.speaker == speaker => {
+                turn.end_time = turn.end_time.max(end_time);
+                (Some(speaker), None)
+            }
+            _ => {
+                // 识别窗口会与上一窗口重叠，新轮次从上一轮次结束处开始
+                let previous_end = self.timeline.last().map(|turn| turn.end_time);
+                let start_time = previous_end.map_or(start_time, |previous_end| start_time.max(previous_end).min(end_time));
+                self.timeline.push(SpeakerTimeRange { speaker: speaker.clone(), start_time, end_time });
+                let change = previous_end.map(|_| SpeakerChange { speaker: speaker.clone(), timestamp: start_time });
+                (Some(speaker), change)
             }
-            let mfcc_sim = 1.0 / (1.0 + mfcc_distance.sqrt());
-            similarity += mfcc_sim * 0.2;
-            weight_sum += 0.2;
         }
+    }
 
-        if weight_sum > 0.0 {
-            similarity / weight_sum
-        } else {
-            0.0
+    /// 目前为止的发言轮次
+    pub fn speaker_timeline(&self) -> &[SpeakerTimeRange] {
+        &self.timeline
+    }
+
+    /// 设置匹配时平均的历史窗口（1~50）：窗口越长越不容易因个别异常片段来回切换，但真正换人时反应越慢
+    pub fn set_max_history(&mut self, max_history: usize) {
+        self.max_history = max_history.clamp(1, MAX_SPEAKER_HISTORY);
+        self.trim_history();
+    }
+
+    fn trim_history(&mut self) {
+        let excess = self.embedding_history.len().saturating_sub(self.max_history);
+        self.embedding_history.drain(..excess);
+    }
+
+    /// 与 embeddings 平均相似度最高的说话人
+    fn best_match(&self, embeddings: &[Vec<f32>]) -> Option<(String, f32)> {
+        self.speaker_profiles.iter()
+            .map(|(speaker_id, profile)| {
+                let total: f32 = embeddings.iter().map(|embedding| self.calculate_speaker_similarity(embedding, profile)).sum();
+                (speaker_id.clone(), total / embeddings.len().max(1) as f32)
+            })
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+    }
+
+    /// 清空已识别的说话人、嵌入历史与归一化统计，之后的声音重新从说话人A开始编号
+    pub fn reset(&mut self) {
+        self.speaker_profiles.clear();
+        self.current_speaker = None;
+        self.embedding_history.clear();
+        self.embedding_stats = FeatureStatistics::default();
+        self.timeline.clear();
+    }
+
+    pub fn identify_speaker(&mut self, audio: &[f32]) -> Option<String> {
+        let embedding = self.embedder.embed(audio)?;
+
+        // 添加到历史记录，并更新归一化统计
+        self.embedding_stats.observe(&embedding);
+        self.embedding_history.push(embedding.clone());
+        self.trim_history();
+
+        // 如果没有已知说话人，创建第一个
+        if self.speaker_profiles.is_empty() {
+            let speaker_id = "Speaker_1".to_string();
+            let profile = SpeakerProfile {
+                id: speaker_id.clone(),
+                name: "说话人A".to_string(),
+                embedding,
+                confidence: 1.0,
+                sample_count: 1,
+            };
+            self.speaker_profiles.insert(speaker_id.clone(), profile);
+            self.current_speaker = Some(speaker_id.clone());
+            return Some("说话人A".to_string());
         }
+
+        // 先按历史窗口内的平均相似度匹配，个别异常片段不会让说话人来回切换；
+        // 平均后没有足够相似的说话人时再看当前片段本身，已知说话人之间切换不必等窗口滑过
+        let warming_up = self.embedding_stats.count() < MIN_STATS_SAMPLES;
+        let best_match = self.best_match(&self.embedding_history)
+            .filter(|(_, similarity)| *similarity > SIMILARITY_THRESHOLD)
+            .or_else(|| {
+                self.best_match(std::slice::from_ref(&embedding))
+                    .filter(|(_, similarity)| *similarity > SIMILARITY_THRESHOLD || warming_up)
+            });
+
+        if let Some((speaker_id, _)) = best_match {
+            // 更新说话人特征；靠窗口平均才归入的异常片段不参与更新，以免把特征带偏
+            let similar = self.calculate_speaker_similarity(&embedding, &self.speaker_profiles[&speaker_id]) > SIMILARITY_THRESHOLD;
+            if similar || warming_up {
+                self.update_speaker_profile(&speaker_id, &embedding);
+            }
+            let profile = self.speaker_profiles.get(&speaker_id).unwrap();
+            self.current_speaker = Some(speaker_id);
+            return Some(profile.name.clone());
+        }
+
+        // 创建新说话人
+        let speaker_count = self.speaker_profiles.len();
+        let speaker_id = format!("Speaker_{}", speaker_count + 1);
+        let speaker_name = SPEAKER_NAMES.get(speaker_count)
+            .unwrap_or(&"说话人X")
+            .to_string();
+
+        let profile = SpeakerProfile {
+            id: speaker_id.clone(),
+            name: speaker_name.clone(),
+            embedding,
+            confidence: 1.0,
+            sample_count: 1,
+        };
+
+        self.speaker_profiles.insert(speaker_id.clone(), profile);
+        self.current_speaker = Some(speaker_id);
+        Some(speaker_name)
     }
 
-    fn update_speaker_profile(&mut self, speaker_id: &str, features: &VoiceFeatures) {
-        if let Some(profile) = self.speaker_profiles.get_mut(speaker_id) {
-            let alpha = 0.1; // 学习率
-            
-            // 更新基频 (指数移动平均)
-            if features.fundamental_freq > 0.0 {
-                profile.fundamental_freq = profile.fundamental_freq * (1.0 - alpha) + features.fundamental_freq * alpha;
+    /// 非流式估算：对整段音频逐窗提取嵌入，再按余弦距离做层次聚类
+    pub fn estimate_speaker_count(&mut self, audio: &[f32], sample_rate: u32, max_speakers: usize) -> SpeakerCountEstimate {
+        self.estimate_speaker_count_with_progress(audio, sample_rate, max_speakers, |_| {})
+    }
+
+    /// 同 estimate_speaker_count，每处理完一个分析窗口回调一次已完成的比例（0~1）
+    pub fn estimate_speaker_count_with_progress(
+        &mut self,
+        audio: &[f32],
+        sample_rate: u32,
+        max_speakers: usize,
+        mut on_progress: impl FnMut(f64),
+    ) -> SpeakerCountEstimate {
+        let window_size = (sample_rate as f64 * ESTIMATE_WINDOW_SECONDS) as usize;
+        let total_windows = audio.len().div_ceil(window_size.max(1));
+        let mut windows: Vec<(f64, f64)> = Vec::new();
+        self.embedding_history.clear();
+
+        for (index, chunk) in audio.chunks(window_size.max(1)).enumerate() {
+            on_progress(index as f64 / total_windows as f64);
+            let energy = chunk.iter().map(|&x| x * x).sum::<f32>() / chunk.len() as f32;
+            if energy < MIN_WINDOW_ENERGY {
+                continue;
             }
-            
-            // 更新频谱质心
-            if features.spectral_centroid > 0.0 {
-                profile.spectral_centroid = profile.spectral_centroid * (1.0 - alpha) + features.spectral_centroid * alpha;
+            if let Some(embedding) = self.embedder.embed(chunk) {
+                let start_time = index as f64 * ESTIMATE_WINDOW_SECONDS;
+                windows.push((start_time, start_time + chunk.len() as f64 / sample_rate as f64));
+                self.embedding_history.push(embedding);
             }
-            
-            // 更新共振峰
-            for i in 0..profile.formant_frequencies.len().min(features.formant_frequencies.len()) {
-                if features.formant_frequencies[i] > 0.0 {
-                    profile.formant_frequencies[i] = profile.formant_frequencies[i] * (1.0 - alpha) + features.formant_frequencies[i] * alpha;
+        }
+
+        on_progress(1.0);
+        let labels = cluster_embeddings(&self.embedding_history, max_speakers.clamp(1, SPEAKER_NAMES.len()));
+
+        // 合并相邻且属于同一说话人的窗口
+        let mut ranges: Vec<SpeakerTimeRange> = Vec::new();
+        for ((start_time, end_time), label) in windows.into_iter().zip(labels.iter()) {
+            let speaker = SPEAKER_NAMES[*label].to_string();
+            match ranges.last_mut() {
+                Some(range) if range.speaker == speaker && start_time - range.end_time < 1e-6 => {
+                    range.end_time = end_time;
                 }
+                _ => ranges.push(SpeakerTimeRange { speaker, start_time, end_time }),
             }
-            
+        }
+
+        SpeakerCountEstimate {
+            speaker_count: labels.iter().max().map_or(0, |max| max + 1),
+            ranges,
+        }
+    }
+
+    // 实时识别时嵌入再按在线统计做 z-score 归一化以适应当前录音环境，之后比较余弦相似度
+    fn calculate_speaker_similarity(&self, embedding: &[f32], profile: &SpeakerProfile) -> f32 {
+        let observed = self.embedding_stats.normalize(embedding);
+        let reference = self.embedding_stats.normalize(&profile.embedding);
+        cosine_similarity(&observed, &reference)
+    }
+
+    fn update_speaker_profile(&mut self, speaker_id: &str, embedding: &[f32]) {
+        if let Some(profile) = self.speaker_profiles.get_mut(speaker_id) {
+            let alpha = 0.1; // 学习率
+
+            // 指数移动平均更新嵌入
+            if profile.embedding.len() == embedding.len() {
+                for (stored, &value) in profile.embedding.iter_mut().zip(embedding) {
+                    *stored = *stored * (1.0 - alpha) + value * alpha;
+                }
+            } else {
+                profile.embedding = embedding.to_vec();
+            }
+
             profile.sample_count += 1;
             
             // 更新置信度
@@ -466,4 +631,444 @@ impl RealtimeSpeakerDiarization {
     pub fn get_speaker_profiles(&self) -> Vec<SpeakerProfile> {
         self.speaker_profiles.values().cloned().collect()
     }
-}
\ No newline at end of file
+}
+const PITCH_MIN_HZ: f32 = 50.0;
+const PITCH_MAX_HZ: f32 = 500.0;
+const YIN_THRESHOLD: f32 = 0.1;      // 累积均值归一化差分低于该值视为周期
+const YIN_UNVOICED_LIMIT: f32 = 0.35; // 全局最小值仍高于该值时视为无声/清音
+
+/// YIN 基频检测：差分函数通过 FFT 自相关计算，结果做抛物线插值以获得亚采样精度。
+/// 无法检测到周期（静音、噪声或音频过短）时返回 None
+pub fn estimate_pitch(audio: &[f32], sample_rate: u32) -> Option<f32> {
+    let sample_rate = sample_rate as f32;
+    let tau_min = (sample_rate / PITCH_MAX_HZ).floor() as usize;
+    let tau_max = (sample_rate / PITCH_MIN_HZ).ceil() as usize;
+    if tau_min < 2 || audio.len() < tau_max * 2 {
+        return None;
+    }
+    let window = audio.len() - tau_max;
+    if audio.iter().map(|&x| x * x).sum::<f32>() / (audio.len() as f32) < 1e-8 {
+        return None;
+    }
+
+    // 互相关项 r(τ) = Σ x[j] * x[j+τ]，j ∈ [0, window)
+    let fft_len = (audio.len() + window).next_power_of_two();
+    let mut signal: Vec<Complex32> = audio.iter().map(|&x| Complex32::new(x, 0.0)).collect();
+    signal.resize(fft_len, Complex32::new(0.0, 0.0));
+    let mut head: Vec<Complex32> = audio[..window].iter().map(|&x| Complex32::new(x, 0.0)).collect();
+    head.resize(fft_len, Complex32::new(0.0, 0.0));
+
+    spectral_analysis::with_fft_planner(|planner| {
+        let forward = planner.plan_fft_forward(fft_len);
+        forward.process(&mut signal);
+        forward.process(&mut head);
+        for (s, h) in signal.iter_mut().zip(head.iter()) {
+            *s *= h.conj();
+        }
+        planner.plan_fft_inverse(fft_len).process(&mut signal);
+    });
+    let scale = 1.0 / fft_len as f32;
+
+    // 能量项使用平方前缀和
+    let mut prefix = vec![0.0f64; audio.len() + 1];
+    for (i, &x) in audio.iter().enumerate() {
+        prefix[i + 1] = prefix[i] + (x as f64) * (x as f64);
+    }
+    let head_energy = prefix[window];
+
+    // 累积均值归一化差分函数
+    let mut cmndf = vec![1.0f32; tau_max + 1];
+    let mut running_sum = 0.0f64;
+    for tau in 1..=tau_max {
+        let shifted_energy = prefix[tau + window] - prefix[tau];
+        let correlation = (signal[tau].re * scale) as f64;
+        let difference = (head_energy + shifted_energy - 2.0 * correlation).max(0.0);
+        running_sum += difference;
+        cmndf[tau] = if running_sum > 0.0 {
+            (difference * tau as f64 / running_sum) as f32
+        } else {
+            1.0
+        };
+    }
+
+    // 取第一个低于阈值的谷底；没有时退而使用全局最小值
+    let mut best_tau = None;
+    let mut tau = tau_min;
+    while tau < tau_max {
+        if cmndf[tau] < YIN_THRESHOLD {
+            while tau + 1 < tau_max && cmndf[tau + 1] < cmndf[tau] {
+                tau += 1;
+            }
+            best_tau = Some(tau);
+            break;
+        }
+        tau += 1;
+    }
+    let best_tau = match best_tau {
+        Some(tau) => tau,
+        None => {
+            let (tau, &value) = cmndf[tau_min..tau_max].iter().enumerate()
+                .min_by(|a, b| a.1.total_cmp(b.1))?;
+            if value > YIN_UNVOICED_LIMIT {
+                return None;
+            }
+            tau + tau_min
+        }
+    };
+
+    // 抛物线插值
+    let (left, center, right) = (cmndf[best_tau - 1], cmndf[best_tau], cmndf[best_tau + 1]);
+    let denominator = left - 2.0 * center + right;
+    let offset = if denominator.abs() > f32::EPSILON {
+        (0.5 * (left - right) / denominator).clamp(-1.0, 1.0)
+    } else {
+        0.0
+    };
+
+    Some(sample_rate / (best_tau as f32 + offset))
+}
+
+/// 平均余弦距离层次聚类：不断合并最近的两个簇，直到最近距离超过阈值且簇数不超过上限。
+/// 返回每个嵌入的簇编号，编号按首次出现的顺序从0开始
+pub fn cluster_embeddings(vectors: &[Vec<f32>], max_clusters: usize) -> Vec<usize> {
+    let count = vectors.len();
+    // 簇间平均距离只在开始时按成员两两计算一次，合并后按成员数加权更新（Lance-Williams），不再重算
+    let mut distances = vec![vec![0.0f32; count]; count];
+    for (a, first) in vectors.iter().enumerate() {
+        for (b, second) in vectors.iter().enumerate().skip(a + 1) {
+            let distance = cosine_distance(first, second);
+            distances[a][b] = distance;
+            distances[b][a] = distance;
+        }
+    }
+    let mut members: Vec<Vec<usize>> = (0..count).map(|i| vec![i]).collect();
+    let mut active: Vec<usize> = (0..count).collect();
+
+    while active.len() > 1 {
+        let mut closest = (active[0], active[1], f32::INFINITY);
+        for (position, &i) in active.iter().enumerate() {
+            for &j in &active[(position + 1)..] {
+                if distances[i][j] < closest.2 {
+                    closest = (i, j, distances[i][j]);
+                }
+            }
+        }
+
+        let (i, j, nearest) = closest;
+        if nearest > CLUSTER_MERGE_DISTANCE && active.len() <= max_clusters {
+            break;
+        }
+        let (size_i, size_j) = (members[i].len() as f32, members[j].len() as f32);
+        for &k in &active {
+            if k != i && k != j {
+                let merged = (size_i * distances[i][k] + size_j * distances[j][k]) / (size_i + size_j);
+                distances[i][k] = merged;
+                distances[k][i] = merged;
+            }
+        }
+        let merged = std::mem::take(&mut members[j]);
+        members[i].extend(merged);
+        active.retain(|&cluster| cluster != j);
+    }
+    let mut clusters: Vec<Vec<usize>> = active.into_iter().map(|cluster| std::mem::take(&mut members[cluster])).collect();
+
+    // 按首次出现顺序编号
+    clusters.sort_by_key(|members| members.iter().copied().min().unwrap_or(0));
+    let mut labels = vec![0; vectors.len()];
+    for (label, members) in clusters.iter().enumerate() {
+        for &index in members {
+            labels[index] = label;
+        }
+    }
+    labels
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 由若干谐波合成的"嗓音"，harmonic_weights 决定音色
+    fn synthetic_voice(f0: f32, harmonic_weights: &[f32], seconds: f32) -> Vec<f32> {
+        let sample_rate = 16000.0;
+        (0..(seconds * sample_rate) as usize)
+            .map(|n| {
+                let t = n as f32 / sample_rate;
+                harmonic_weights.iter().enumerate()
+                    .map(|(k, w)| w * (2.0 * std::f32::consts::PI * f0 * (k + 1) as f32 * t).sin())
+                    .sum::<f32>() * 0.2
+            })
+            .collect()
+    }
+
+    fn sine(frequency: f32, seconds: f32) -> Vec<f32> {
+        (0..(seconds * 16000.0) as usize)
+            .map(|n| 0.5 * (2.0 * std::f32::consts::PI * frequency * n as f32 / 16000.0).sin())
+            .collect()
+    }
+
+    #[test]
+    fn test_estimate_pitch_of_sine_tones() {
+        for frequency in [110.0, 220.0, 440.0] {
+            let pitch = estimate_pitch(&sine(frequency, 0.1), 16000).unwrap();
+            assert!((pitch - frequency).abs() < 2.0, "期望 {}Hz，检测到 {}Hz", frequency, pitch);
+        }
+        // 重复调用复用缓存的 FFT 计划，结果一致
+        assert_eq!(estimate_pitch(&sine(220.0, 0.1), 16000), estimate_pitch(&sine(220.0, 0.1), 16000));
+    }
+
+    #[test]
+    fn test_estimate_pitch_rejects_silence_and_short_audio() {
+        assert_eq!(estimate_pitch(&vec![0.0; 1600], 16000), None);
+        assert_eq!(estimate_pitch(&sine(220.0, 0.01), 16000), None);
+    }
+
+    fn features(f0: f32, formants: [f32; 3], centroid: f32, mfcc_offset: f32) -> VoiceFeatures {
+        VoiceFeatures {
+            fundamental_freq: f0,
+            formant_frequencies: formants.to_vec(),
+            spectral_centroid: centroid,
+            spectral_bandwidth: centroid * 0.5,
+            zero_crossing_rate: f0 / 8000.0,
+            energy: 0.01,
+            mfcc_features: (0..12).map(|i| -5.0 - i as f32 * 0.3 + mfcc_offset).collect(),
+        }
+    }
+
+    fn embedding(f0: f32, formants: [f32; 3], centroid: f32, mfcc_offset: f32) -> Vec<f32> {
+        FeatureEmbedder::embedding_from_features(&features(f0, formants, centroid, mfcc_offset))
+    }
+
+    fn profile_from(embedding: Vec<f32>) -> SpeakerProfile {
+        SpeakerProfile {
+            id: "Speaker_1".to_string(),
+            name: "说话人A".to_string(),
+            embedding,
+            confidence: 1.0,
+            sample_count: 1,
+        }
+    }
+
+    #[test]
+    fn test_same_speaker_scores_higher_than_different_speaker() {
+        let mut diarization = RealtimeSpeakerDiarization::new();
+        // 两位说话人交替出现的观测，用于建立归一化统计
+        for i in 0..6 {
+            let jitter = i as f32 * 0.5;
+            diarization.embedding_stats.observe(&embedding(110.0 + jitter, [700.0, 1200.0, 2500.0], 900.0, jitter * 0.1));
+            diarization.embedding_stats.observe(&embedding(230.0 - jitter, [850.0, 1700.0, 2900.0], 1500.0, 2.0 - jitter * 0.1));
+        }
+
+        let low_voice = profile_from(embedding(111.0, [705.0, 1210.0, 2510.0], 905.0, 0.1));
+        let high_voice = profile_from(embedding(228.0, [840.0, 1690.0, 2890.0], 1490.0, 1.9));
+        let sample = embedding(112.0, [698.0, 1195.0, 2495.0], 910.0, 0.2);
+
+        let same = diarization.calculate_speaker_similarity(&sample, &low_voice);
+        let different = diarization.calculate_speaker_similarity(&sample, &high_voice);
+        assert!(same > different, "same={} different={}", same, different);
+        assert!(same > SIMILARITY_THRESHOLD);
+        assert!(different < SIMILARITY_THRESHOLD);
+    }
+
+    #[test]
+    fn test_mfcc_differences_affect_similarity() {
+        let mut diarization = RealtimeSpeakerDiarization::new();
+        for offset in [0.0, 3.0, 0.2, 2.8, 0.1, 3.1] {
+            diarization.embedding_stats.observe(&embedding(150.0, [700.0, 1200.0, 2500.0], 1000.0, offset));
+        }
+        // 仅MFCC不同：与存储的MFCC比较，而不是与常数比较
+        let sample = embedding(150.0, [700.0, 1200.0, 2500.0], 1000.0, 0.0);
+        let matching = profile_from(embedding(150.0, [700.0, 1200.0, 2500.0], 1000.0, 0.1));
+        let mismatching = profile_from(embedding(150.0, [700.0, 1200.0, 2500.0], 1000.0, 3.0));
+        assert!(diarization.calculate_speaker_similarity(&sample, &matching)
+            > diarization.calculate_speaker_similarity(&sample, &mismatching));
+    }
+
+    #[test]
+    fn test_same_voice_embeddings_are_closer() {
+        let embedder = FeatureEmbedder;
+        let voice = [1.0, 0.6, 0.3, 0.15];
+        let first = embedder.embed(&synthetic_voice(120.0, &voice, 1.0)).unwrap();
+        let second = embedder.embed(&synthetic_voice(123.0, &voice, 0.8)).unwrap();
+        let other = embedder.embed(&synthetic_voice(240.0, &[0.3, 1.0, 0.8, 0.4, 0.2], 1.0)).unwrap();
+        assert_eq!(first.len(), embedder.dimension());
+
+        let same = cosine_distance(&first, &second);
+        let different = cosine_distance(&first, &other);
+        assert!(same < different, "same={} different={}", same, different);
+        assert!(same < CLUSTER_MERGE_DISTANCE);
+        assert_eq!(embedder.embed(&vec![0.1; 800]), None);
+    }
+
+    /// 固定输出的嵌入器，用于验证聚类与识别不依赖具体的特征实现
+    struct FixedEmbedder;
+
+    impl SpeakerEmbedder for FixedEmbedder {
+        fn dimension(&self) -> usize {
+            2
+        }
+
+        fn embed(&self, audio: &[f32]) -> Option<Vec<f32>> {
+            // 以音频正负号代表两位说话人
+            Some(if audio[0] > 0.0 { vec![1.0, 0.1] } else { vec![-0.2, 1.0] })
+        }
+    }
+
+    #[test]
+    fn test_custom_embedder_drives_clustering() {
+        let audio: Vec<f32> = [vec![0.2; 16000], vec![-0.2; 16000], vec![0.2; 16000]].concat();
+        let mut diarization = RealtimeSpeakerDiarization::with_embedder(Box::new(FixedEmbedder));
+        let estimate = diarization.estimate_speaker_count(&audio, 16000, 4);
+        assert_eq!(estimate.speaker_count, 2);
+        let speakers: Vec<&str> = estimate.ranges.iter().map(|r| r.speaker.as_str()).collect();
+        assert_eq!(speakers, vec!["说话人A", "说话人B", "说话人A"]);
+    }
+
+    #[test]
+    fn test_alternating_voices_emit_speaker_changes() {
+        let mut diarization = RealtimeSpeakerDiarization::with_embedder(Box::new(FixedEmbedder));
+        let first = vec![0.2; 1600];
+        let second = vec![-0.2; 1600];
+        // 前几个窗口用于建立归一化统计，始终归入第一位说话人
+        let mut changes = Vec::new();
+        for (i, audio) in [&first, &first, &first, &first, &second, &first, &second].iter().enumerate() {
+            let (speaker, change) = diarization.identify_speaker_at(audio, i as f64 * 2.0, i as f64 * 2.0 + 2.0);
+            assert!(speaker.is_some());
+            changes.extend(change);
+        }
+
+        let change = |speaker: &str, timestamp: f64| SpeakerChange { speaker: speaker.to_string(), timestamp };
+        // 第一位说话人开口不发切换事件
+        assert_eq!(changes, vec![change("说话人B", 8.0), change("说话人A", 10.0), change("说话人B", 12.0)]);
+        let turns: Vec<(&str, f64, f64)> = diarization.speaker_timeline().iter()
+            .map(|turn| (turn.speaker.as_str(), turn.start_time, turn.end_time))
+            .collect();
+        assert_eq!(turns, vec![("说话人A", 0.0, 8.0), ("说话人B", 8.0, 10.0), ("说话人A", 10.0, 12.0), ("说话人B", 12.0, 14.0)]);
+    }
+
+    #[test]
+    fn test_reset_starts_identification_fresh() {
+        let mut diarization = RealtimeSpeakerDiarization::with_embedder(Box::new(FixedEmbedder));
+        for audio in [vec![0.2; 1600], vec![0.2; 1600], vec![0.2; 1600], vec![0.2; 1600], vec![-0.2; 1600]] {
+            diarization.identify_speaker_at(&audio, 0.0, 1.0);
+        }
+        assert_eq!(diarization.get_speaker_count(), 2);
+
+        diarization.reset();
+        assert_eq!(diarization.get_speaker_count(), 0);
+        assert_eq!(diarization.get_current_speaker(), None);
+        assert!(diarization.speaker_timeline().is_empty());
+        assert_eq!(diarization.embedding_stats.count(), 0);
+
+        // 重置前的说话人B现在成为第一位说话人
+        let (speaker, change) = diarization.identify_speaker_at(&vec![-0.2; 1600], 5.0, 6.0);
+        assert_eq!(speaker.as_deref(), Some("说话人A"));
+        assert_eq!(change, None);
+        let profiles = diarization.get_speaker_profiles();
+        assert_eq!(profiles.len(), 1);
+        assert_eq!(profiles[0].id, "Speaker_1");
+    }
+
+    /// 直接把音频前两个样本当作嵌入，便于构造带抖动的嗓音
+    struct DirectEmbedder;
+
+    impl SpeakerEmbedder for DirectEmbedder {
+        fn dimension(&self) -> usize {
+            2
+        }
+
+        fn embed(&self, audio: &[f32]) -> Option<Vec<f32>> {
+            Some(audio[..2].to_vec())
+        }
+    }
+
+    fn voice(first: bool, i: usize) -> Vec<f32> {
+        let jitter = (i % 4) as f32 * 0.01;
+        if first { vec![1.0 + jitter, 0.1 + jitter] } else { vec![-0.2 - jitter, 1.0 - jitter] }
+    }
+
+    /// 稳定的一位说话人，每隔几个片段混入一个听起来像另一个人的异常片段，返回切换次数
+    fn steady_voice_switches(history: usize) -> usize {
+        let mut diarization = RealtimeSpeakerDiarization::with_embedder(Box::new(DirectEmbedder));
+        diarization.set_max_history(history);
+        (0..24)
+            .filter_map(|i| diarization.identify_speaker_at(&voice(i % 12 != 3, i), i as f64, i as f64 + 1.0).1)
+            .count()
+    }
+
+    #[test]
+    fn test_longer_history_reduces_flapping() {
+        // 只看当前片段时，统计建立后的异常片段（第15个）会切出去再切回来
+        assert_eq!(steady_voice_switches(1), 1 + 2);
+        assert_eq!(steady_voice_switches(20), 1);
+        assert_eq!(steady_voice_switches(DEFAULT_SPEAKER_HISTORY), 1);
+
+        // 真正换人后仍会切换过去，而不是新建更多说话人
+        let mut diarization = RealtimeSpeakerDiarization::with_embedder(Box::new(DirectEmbedder));
+        let speakers: Vec<String> = (0..20)
+            .filter_map(|i| diarization.identify_speaker(&voice(i != 3 && i < 10, i)))
+            .collect();
+        assert_eq!(speakers[9], "说话人A");
+        assert!(speakers[11..].iter().all(|speaker| speaker == "说话人B"), "{:?}", speakers);
+        assert_eq!(diarization.get_speaker_count(), 2);
+    }
+
+    #[test]
+    fn test_summary_talk_time_from_timeline() {
+        let turn = |speaker: &str, start_time: f64, end_time: f64| SpeakerTimeRange { speaker: speaker.to_string(), start_time, end_time };
+        let timeline = vec![
+            turn("说话人A", 0.0, 12.5),
+            turn("说话人B", 12.5, 20.0),
+            turn("说话人A", 20.0, 26.0),
+            turn("说话人C", 26.0, 27.0),
+            turn("说话人B", 27.0, 45.0),
+        ];
+        let summary = summarize_timeline(&timeline);
+        assert_eq!(summary.speaker_count, 3);
+        let talk: Vec<(&str, f64, usize)> = summary.speakers.iter()
+            .map(|entry| (entry.speaker.as_str(), entry.talk_time, entry.turns))
+            .collect();
+        assert_eq!(talk, vec![("说话人A", 18.5, 2), ("说话人B", 25.5, 2), ("说话人C", 1.0, 1)]);
+        let longest = summary.longest_turn.unwrap();
+        assert_eq!((longest.speaker.as_str(), longest.start_time, longest.end_time), ("说话人B", 27.0, 45.0));
+
+        let empty = summarize_timeline(&[]);
+        assert_eq!((empty.speaker_count, empty.speakers.len(), empty.longest_turn), (0, 0, None));
+    }
+
+    #[test]
+    fn test_single_voice_counts_one() {
+        let audio = synthetic_voice(120.0, &[1.0, 0.6, 0.3, 0.15], 4.0);
+        let estimate = RealtimeSpeakerDiarization::new().estimate_speaker_count(&audio, 16000, 4);
+        assert_eq!(estimate.speaker_count, 1);
+        assert_eq!(estimate.ranges.len(), 1);
+    }
+
+    #[test]
+    fn test_cluster_embeddings_groups_by_direction() {
+        let vectors = vec![
+            vec![1.0, 0.0], vec![0.0, 1.0], vec![0.98, 0.05], vec![0.04, 0.97], vec![0.97, 0.08],
+        ];
+        assert_eq!(cluster_embeddings(&vectors, 4), vec![0, 1, 0, 1, 0]);
+        // 簇数上限迫使继续合并
+        assert_eq!(cluster_embeddings(&vectors, 1), vec![0; 5]);
+        assert!(cluster_embeddings(&[], 4).is_empty());
+    }
+
+    #[cfg(feature = "diarization-tests")]
+    #[test]
+    fn test_two_synthetic_voices_count_two() {
+        let low = synthetic_voice(110.0, &[1.0, 0.6, 0.3, 0.15], 2.0);
+        let high = synthetic_voice(240.0, &[0.3, 1.0, 0.8, 0.4, 0.2], 2.0);
+        let silence = vec![0.0; 16000];
+        let audio: Vec<f32> = [&low[..], &silence, &high, &silence, &low, &silence, &high].concat();
+
+        let estimate = RealtimeSpeakerDiarization::new().estimate_speaker_count(&audio, 16000, 4);
+        assert_eq!(estimate.speaker_count, 2);
+
+        let speakers: Vec<&str> = estimate.ranges.iter().map(|r| r.speaker.as_str()).collect();
+        assert_eq!(speakers, vec!["说话人A", "说话人B", "说话人A", "说话人B"]);
+        assert!((estimate.ranges[0].start_time - 0.0).abs() < 1e-6);
+        assert!((estimate.ranges[0].end_time - 2.0).abs() < 1e-6);
+        assert!((estimate.ranges[1].start_time - 3.0).abs() < 1e-6);
+    }
+}
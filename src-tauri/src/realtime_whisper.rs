@@ -22,6 +22,8 @@ pub struct RealtimeRecognitionConfig {
     pub temperature: f32,
     pub max_tokens: i32,
     pub initial_prompt: Option<String>,
+    #[serde(default)]
+    pub hotwords: Vec<String>, // 自定义词汇，合并进初始提示词
 }
 
 impl Default for RealtimeRecognitionConfig {
@@ -35,6 +37,7 @@ impl Default for RealtimeRecognitionConfig {
             temperature: 0.0,
             max_tokens: 50, // 限制单次识别的最大token数
             initial_prompt: None,
+            hotwords: Vec::new(),
         }
     }
 }
@@ -195,12 +198,13 @@ impl RealtimeWhisperRecognizer {
             }
 
             // 设置初始提示词
-            let prompt_cstring = if let Some(ref prompt) = self.config.initial_prompt {
-                if !prompt.trim().is_empty() {
-                    Some(CString::new(prompt.trim()).unwrap())
-                } else {
-                    None
-                }
+            let assembled_prompt = crate::prompt_builder::build_initial_prompt(
+                self.config.initial_prompt.as_deref(),
+                &self.config.hotwords,
+                None,
+            );
+            let prompt_cstring = if let Some(ref prompt) = assembled_prompt {
+                Some(CString::new(prompt.as_str()).unwrap())
             } else {
                 None
             };
@@ -246,6 +250,13 @@ impl RealtimeWhisperRecognizer {
         // 移除重复的空格
         processed = processed.split_whitespace().collect::<Vec<_>>().join(" ");
 
+        // 折叠幻觉产生的重复短语
+        processed = crate::text_processing::collapse_repetitions(
+            &processed,
+            &self.config.language,
+            &crate::text_processing::RepetitionConfig::default(),
+        );
+
         // 语言特定的后处理
         match self.config.language.as_str() {
             "zh" => self.post_process_chinese(&processed),
@@ -0,0 +1,174 @@
+// record_naming.rs - 由转录内容生成记录标题：取第一句话，过长时截断；没有内容时回退为时间戳名称
+use chrono::{DateTime, Local, Utc};
+use tauri::State;
+
+use crate::errors::StenoError;
+use crate::storage::{StorageService, TranscriptionRecord};
+use crate::storage_commands::StorageState;
+
+/// 开启后所有记录在转录完成时按内容命名（实时录音另可通过 RealtimeConfig.auto_name 单独开启）
+const AUTO_NAME_SETTINGS_KEY: &str = "auto_name_records";
+
+/// 标题最多保留的字符数（不含省略号）
+const MAX_TITLE_CHARS: usize = 20;
+/// 中文句末标点与换行总是结束一句
+const CJK_SENTENCE_ENDINGS: &[char] = &['。', '！', '？', '；', '\n'];
+/// 英文句末标点只有后面是空白或文本结尾时才结束一句，避免把 2.5、e.g. 之类切开
+const ASCII_SENTENCE_ENDINGS: &[char] = &['.', '!', '?', ';'];
+
+fn is_title_trim_char(c: char) -> bool {
+    c.is_whitespace() || (c.is_ascii_punctuation() && !matches!(c, '#' | '%' | '&' | '@' | '+'))
+        || "，、：“”‘’（）《》【】…—·".contains(c)
+}
+
+/// 文本中的第一句话（不含句末标点）
+fn first_sentence(text: &str) -> &str {
+    let mut chars = text.char_indices().peekable();
+    while let Some((index, c)) = chars.next() {
+        let ends = CJK_SENTENCE_ENDINGS.contains(&c)
+            || (ASCII_SENTENCE_ENDINGS.contains(&c) && chars.peek().is_none_or(|(_, next)| next.is_whitespace()));
+        if ends {
+            return &text[..index];
+        }
+    }
+    text
+}
+
+/// 超过上限时截断：含空格的文本（英文等）退回到最后一个完整单词，其余按字符截断，末尾加省略号
+fn truncate_title(sentence: &str) -> String {
+    if sentence.chars().count() <= MAX_TITLE_CHARS {
+        return sentence.to_string();
+    }
+    let cut: String = sentence.chars().take(MAX_TITLE_CHARS).collect();
+    let cut = match cut.rfind(char::is_whitespace) {
+        Some(index) if cut[..index].chars().count() >= MAX_TITLE_CHARS / 2 => &cut[..index],
+        _ => cut.as_str(),
+    };
+    format!("{}…", cut.trim_end_matches(is_title_trim_char))
+}
+
+/// 由转录文本生成标题；文本为空或只有标点时返回 None
+pub fn derive_title(text: &str) -> Option<String> {
+    let text = text.trim_start_matches(is_title_trim_char);
+    let sentence = first_sentence(text).trim_matches(is_title_trim_char);
+    if sentence.is_empty() {
+        return None;
+    }
+    Some(truncate_title(sentence))
+}
+
+/// 以创建时间命名，如 "实时录音 2024-05-01 14:30"
+pub fn timestamp_name(prefix: &str, created_at: DateTime<Utc>) -> String {
+    format!("{} {}", prefix, created_at.with_timezone(&Local).format("%Y-%m-%d %H:%M"))
+}
+
+/// 记录的建议名称：优先取转录内容的第一句，没有转录文本时回退为时间戳名称
+pub fn suggest_record_name(record: &TranscriptionRecord) -> String {
+    record.result.as_ref()
+        .and_then(|result| derive_title(&result.text))
+        .unwrap_or_else(|| timestamp_name("录音", record.created_at))
+}
+
+pub fn auto_name_enabled(storage: &StorageService) -> rusqlite::Result<bool> {
+    Ok(storage.get_setting(AUTO_NAME_SETTINGS_KEY)?.as_deref() == Some("true"))
+}
+
+pub fn set_auto_name_enabled(storage: &StorageService, enabled: bool) -> rusqlite::Result<()> {
+    storage.set_setting(AUTO_NAME_SETTINGS_KEY, if enabled { "true" } else { "false" })
+}
+
+/// 转录完成时调用：开启自动命名且文本能生成标题时改名，返回新名称
+pub fn apply_auto_name(storage: &StorageService, record_id: &str, text: &str) -> rusqlite::Result<Option<String>> {
+    if !auto_name_enabled(storage)? {
+        return Ok(None);
+    }
+    let Some(name) = derive_title(text) else {
+        return Ok(None);
+    };
+    storage.update_record_name(record_id, &name)?;
+    Ok(Some(name))
+}
+
+/// 生成记录的建议名称；apply 为 true 时同时保存为记录名称
+#[tauri::command]
+pub async fn generate_record_name(
+    record_id: String,
+    apply: Option<bool>,
+    storage_state: State<'_, StorageState>,
+) -> Result<String, StenoError> {
+    let record = storage_state.with_storage(|storage| storage.get_record(&record_id))?
+        .ok_or_else(|| StenoError::NotFound(format!("记录不存在: {}", record_id)))?;
+    let name = suggest_record_name(&record);
+    if apply.unwrap_or(false) {
+        storage_state.with_storage(|storage| storage.update_record_name(&record_id, &name))?;
+    }
+    Ok(name)
+}
+
+#[tauri::command]
+pub async fn get_auto_name_records(storage_state: State<'_, StorageState>) -> Result<bool, StenoError> {
+    storage_state.with_storage(auto_name_enabled)
+}
+
+#[tauri::command]
+pub async fn set_auto_name_records(enabled: bool, storage_state: State<'_, StorageState>) -> Result<(), StenoError> {
+    storage_state.with_storage(|storage| set_auto_name_enabled(storage, enabled))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::test_support::{sample_record, temp_storage};
+    use crate::storage::TranscriptionResult;
+
+    #[test]
+    fn test_title_from_first_sentence() {
+        assert_eq!(derive_title("今天讨论第二季度预算。首先看市场部的开支。").as_deref(), Some("今天讨论第二季度预算"));
+        assert_eq!(derive_title("  ，好的，我们开始吧！大家好").as_deref(), Some("好的，我们开始吧"));
+        assert_eq!(derive_title("Version 2.5 is out. Next item").as_deref(), Some("Version 2.5 is out"));
+        assert_eq!(derive_title("第一行没有标点\n第二行").as_deref(), Some("第一行没有标点"));
+        assert_eq!(derive_title("“引号里的标题”").as_deref(), Some("引号里的标题"));
+    }
+
+    #[test]
+    fn test_long_sentences_are_truncated() {
+        assert_eq!(
+            derive_title("这是一段非常非常长而且中间完全没有任何标点符号的开场白内容").as_deref(),
+            Some("这是一段非常非常长而且中间完全没有任何标…")
+        );
+        assert_eq!(
+            derive_title("Welcome everyone to the quarterly planning meeting").as_deref(),
+            Some("Welcome everyone to…")
+        );
+    }
+
+    #[test]
+    fn test_empty_transcript_falls_back_to_timestamp() {
+        assert_eq!(derive_title(""), None);
+        assert_eq!(derive_title(" 。，… \n"), None);
+
+        let mut record = sample_record("record_1");
+        record.result = Some(TranscriptionResult { text: "  ".to_string(), processing_time: 0.0, accuracy: None, segments: None });
+        assert_eq!(suggest_record_name(&record), timestamp_name("录音", record.created_at));
+        record.result = None;
+        assert!(suggest_record_name(&record).starts_with("录音 "));
+
+        record.result = Some(TranscriptionResult { text: "项目周会纪要。".to_string(), processing_time: 0.0, accuracy: None, segments: None });
+        assert_eq!(suggest_record_name(&record), "项目周会纪要");
+    }
+
+    #[test]
+    fn test_auto_name_applies_to_any_record_when_enabled() {
+        let (storage, dir) = temp_storage("auto_name");
+        storage.save_record(&sample_record("record_1")).unwrap();
+
+        assert_eq!(apply_auto_name(&storage, "record_1", "项目周会纪要。下周发布").unwrap(), None);
+        assert_eq!(storage.get_record("record_1").unwrap().unwrap().name, "记录 record_1");
+
+        set_auto_name_enabled(&storage, true).unwrap();
+        assert_eq!(apply_auto_name(&storage, "record_1", "  ").unwrap(), None);
+        assert_eq!(apply_auto_name(&storage, "record_1", "项目周会纪要。下周发布").unwrap().as_deref(), Some("项目周会纪要"));
+        assert_eq!(storage.get_record("record_1").unwrap().unwrap().name, "项目周会纪要");
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,451 @@
+// recording_writer.rs - 录音文件保存格式
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+
+/// 录音保存格式（识别始终使用16kHz单声道，保存的文件格式与采样率可以不同）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum RecordingFormat {
+    #[default]
+    Float32, // 32位浮点WAV（原有格式）
+    Pcm16,   // 16位PCM WAV，体积减半
+    Flac,    // FLAC无损压缩（需要系统安装 flac 编码器，否则回退为16位WAV）
+}
+
+/// 未指定时录音文件的采样率（与识别使用的采样率一致）
+pub const RECORDING_SAMPLE_RATE: u32 = crate::whisper_input::WHISPER_SAMPLE_RATE;
+
+impl RecordingFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            RecordingFormat::Float32 | RecordingFormat::Pcm16 => "wav",
+            RecordingFormat::Flac => "flac",
+        }
+    }
+
+    /// 写入WAV时使用的参数（FLAC先写16位WAV再编码）
+    pub fn wav_spec(&self, sample_rate: u32) -> hound::WavSpec {
+        match self {
+            RecordingFormat::Float32 => hound::WavSpec {
+                channels: 1,
+                sample_rate,
+                bits_per_sample: 32,
+                sample_format: hound::SampleFormat::Float,
+            },
+            RecordingFormat::Pcm16 | RecordingFormat::Flac => hound::WavSpec {
+                channels: 1,
+                sample_rate,
+                bits_per_sample: 16,
+                sample_format: hound::SampleFormat::Int,
+            },
+        }
+    }
+}
+
+/// 16位量化使用的TPDF抖动器（xorshift伪随机数，无需额外依赖）
+pub struct Dither {
+    state: u32,
+}
+
+impl Dither {
+    pub fn new() -> Self {
+        Self { state: 0x9E37_79B9 }
+    }
+
+    fn next_uniform(&mut self) -> f32 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 17;
+        self.state ^= self.state << 5;
+        self.state as f32 / u32::MAX as f32 - 0.5
+    }
+
+    /// 将[-1, 1]浮点样本转换为16位整数，叠加±1 LSB三角分布抖动
+    pub fn to_pcm16(&mut self, sample: f32) -> i16 {
+        let dither = self.next_uniform() + self.next_uniform();
+        let scaled = sample.clamp(-1.0, 1.0) * i16::MAX as f32 + dither;
+        scaled.round().clamp(i16::MIN as f32, i16::MAX as f32) as i16
+    }
+}
+
+/// 按指定格式写入样本
+pub fn write_samples<W: std::io::Write + std::io::Seek>(
+    writer: &mut hound::WavWriter<W>,
+    samples: &[f32],
+    format: RecordingFormat,
+    dither: &mut Dither,
+) -> Result<(), String> {
+    match format {
+        RecordingFormat::Float32 => {
+            for &sample in samples {
+                writer.write_sample(sample).map_err(|e| format!("写入音频数据失败: {}", e))?;
+            }
+        }
+        RecordingFormat::Pcm16 | RecordingFormat::Flac => {
+            for &sample in samples {
+                writer.write_sample(dither.to_pcm16(sample)).map_err(|e| format!("写入音频数据失败: {}", e))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 保存16kHz录音文件，返回实际写入的文件路径（FLAC编码失败时为WAV路径）
+pub fn write_recording(path_without_extension: &Path, samples: &[f32], format: RecordingFormat) -> Result<PathBuf, String> {
+    write_recording_at(path_without_extension, samples, format, RECORDING_SAMPLE_RATE)
+}
+
+/// 按指定采样率保存单声道录音文件
+pub fn write_recording_at(path_without_extension: &Path, samples: &[f32], format: RecordingFormat, sample_rate: u32) -> Result<PathBuf, String> {
+    let wav_path = path_without_extension.with_extension("wav");
+    let file = File::create(&wav_path).map_err(|e| format!("创建录音文件失败: {}", e))?;
+    let mut writer = hound::WavWriter::new(BufWriter::new(file), format.wav_spec(sample_rate))
+        .map_err(|e| format!("创建WAV写入器失败: {}", e))?;
+
+    let mut dither = Dither::new();
+    write_samples(&mut writer, samples, format, &mut dither)?;
+    writer.finalize().map_err(|e| format!("完成WAV文件失败: {}", e))?;
+
+    if format == RecordingFormat::Flac {
+        match encode_flac_external(&wav_path) {
+            Ok(flac_path) => return Ok(flac_path),
+            Err(e) => log::warn!("FLAC编码不可用，保留16位WAV: {}", e),
+        }
+    }
+
+    Ok(wav_path)
+}
+
+/// 调用系统 flac 编码器，成功后删除中间WAV文件
+fn encode_flac_external(wav_path: &Path) -> Result<PathBuf, String> {
+    let flac_path = wav_path.with_extension("flac");
+    let status = std::process::Command::new("flac")
+        .arg("--silent")
+        .arg("--force")
+        .arg("-o")
+        .arg(&flac_path)
+        .arg(wav_path)
+        .status()
+        .map_err(|e| format!("无法启动flac编码器: {}", e))?;
+
+    if !status.success() {
+        return Err(format!("flac编码器退出码: {:?}", status.code()));
+    }
+
+    let _ = std::fs::remove_file(wav_path);
+    Ok(flac_path)
+}
+
+/// 录音过程中增量写入的WAV写入器，崩溃时已写入的数据仍可恢复
+pub struct StreamingWavWriter {
+    writer: Option<hound::WavWriter<BufWriter<File>>>,
+    wav_path: PathBuf,
+    format: RecordingFormat,
+    dither: Dither,
+    samples_since_flush: usize,
+    flush_interval: usize, // 每累计1秒音频更新一次文件头中的数据长度
+}
+
+impl StreamingWavWriter {
+    pub fn create(path_without_extension: &Path, format: RecordingFormat, sample_rate: u32) -> Result<Self, String> {
+        let wav_path = path_without_extension.with_extension("wav");
+        let file = File::create(&wav_path).map_err(|e| format!("创建录音文件失败: {}", e))?;
+        let writer = hound::WavWriter::new(BufWriter::new(file), format.wav_spec(sample_rate))
+            .map_err(|e| format!("创建WAV写入器失败: {}", e))?;
+
+        Ok(Self {
+            writer: Some(writer),
+            wav_path,
+            format,
+            dither: Dither::new(),
+            samples_since_flush: 0,
+            flush_interval: sample_rate.max(1) as usize,
+        })
+    }
+
+    pub fn append(&mut self, samples: &[f32]) -> Result<(), String> {
+        let writer = self.writer.as_mut().ok_or("录音文件已关闭")?;
+        write_samples(writer, samples, self.format, &mut self.dither)?;
+
+        self.samples_since_flush += samples.len();
+        if self.samples_since_flush >= self.flush_interval {
+            // hound 的 flush 会同步更新 RIFF/data 块长度，保证文件随时可读
+            writer.flush().map_err(|e| format!("刷新录音文件失败: {}", e))?;
+            self.samples_since_flush = 0;
+        }
+        Ok(())
+    }
+
+    /// 完成写入，返回最终文件路径
+    pub fn finalize(mut self) -> Result<PathBuf, String> {
+        if let Some(writer) = self.writer.take() {
+            writer.finalize().map_err(|e| format!("完成WAV文件失败: {}", e))?;
+        }
+
+        if self.format == RecordingFormat::Flac {
+            match encode_flac_external(&self.wav_path) {
+                Ok(flac_path) => return Ok(flac_path),
+                Err(e) => log::warn!("FLAC编码不可用，保留16位WAV: {}", e),
+            }
+        }
+
+        Ok(self.wav_path.clone())
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.wav_path
+    }
+}
+
+/// 修复未正常关闭的WAV文件头（RIFF与data块长度），返回是否做了修复。
+/// data 块之后若跟着完整的其他块（如 LIST），说明 data 长度可信，只校正 RIFF 长度，保留这些块
+pub fn repair_wav_header(path: &Path) -> Result<bool, String> {
+    use std::io::{Read, Seek, SeekFrom, Write};
+
+    let mut file = std::fs::OpenOptions::new().read(true).write(true).open(path)
+        .map_err(|e| format!("打开WAV文件失败: {}", e))?;
+    let file_len = file.metadata().map_err(|e| format!("读取文件信息失败: {}", e))?.len();
+
+    let mut header = [0u8; 12];
+    if file.read_exact(&mut header).is_err() || &header[0..4] != b"RIFF" || &header[8..12] != b"WAVE" {
+        return Err("不是有效的WAV文件".to_string());
+    }
+    let declared_riff_size = u32::from_le_bytes([header[4], header[5], header[6], header[7]]) as u64;
+
+    // 遍历块，找到 fmt 的块对齐和 data 块位置
+    let mut block_align = 1u64;
+    let mut pos = 12u64;
+    loop {
+        let Some((id, chunk_size)) = read_chunk_header(&mut file, pos) else {
+            return Err("WAV文件缺少data块".to_string());
+        };
+
+        if &id == b"fmt " {
+            let mut fmt = [0u8; 16];
+            file.read_exact(&mut fmt).map_err(|e| format!("读取fmt块失败: {}", e))?;
+            block_align = u16::from_le_bytes([fmt[12], fmt[13]]).max(1) as u64;
+        } else if &id == b"data" {
+            let data_start = pos + 8;
+            let data_end = data_start + chunk_size;
+
+            if data_end <= file_len && chunks_reach_end(&mut file, data_end + (chunk_size & 1), file_len) {
+                if declared_riff_size == file_len - 8 {
+                    return Ok(false);
+                }
+                file.seek(SeekFrom::Start(4)).map_err(|e| e.to_string())?;
+                file.write_all(&((file_len - 8) as u32).to_le_bytes()).map_err(|e| format!("写入RIFF长度失败: {}", e))?;
+                return Ok(true);
+            }
+
+            // 写入中断：data 块一直延伸到文件末尾，只丢弃最后不完整的样本帧
+            let actual_size = (file_len.saturating_sub(data_start) / block_align) * block_align;
+            let riff_size = data_start + actual_size - 8;
+            file.seek(SeekFrom::Start(4)).map_err(|e| e.to_string())?;
+            file.write_all(&(riff_size as u32).to_le_bytes()).map_err(|e| format!("写入RIFF长度失败: {}", e))?;
+            file.seek(SeekFrom::Start(pos + 4)).map_err(|e| e.to_string())?;
+            file.write_all(&(actual_size as u32).to_le_bytes()).map_err(|e| format!("写入data长度失败: {}", e))?;
+            file.set_len(data_start + actual_size).map_err(|e| format!("截断文件失败: {}", e))?;
+            return Ok(true);
+        }
+
+        // 块按偶数字节对齐
+        pos += 8 + chunk_size + (chunk_size & 1);
+    }
+}
+
+/// 读取 pos 处的块头（块ID与长度）
+fn read_chunk_header(file: &mut File, pos: u64) -> Option<([u8; 4], u64)> {
+    use std::io::{Read, Seek, SeekFrom};
+
+    let mut chunk_header = [0u8; 8];
+    file.seek(SeekFrom::Start(pos)).ok()?;
+    file.read_exact(&mut chunk_header).ok()?;
+    let id = [chunk_header[0], chunk_header[1], chunk_header[2], chunk_header[3]];
+    let size = u32::from_le_bytes([chunk_header[4], chunk_header[5], chunk_header[6], chunk_header[7]]) as u64;
+    Some((id, size))
+}
+
+/// 从 pos 开始是否是一串完整的块且恰好结束于文件末尾（最后一块可缺少补齐字节）
+fn chunks_reach_end(file: &mut File, mut pos: u64, file_len: u64) -> bool {
+    while pos < file_len {
+        let Some((id, size)) = read_chunk_header(file, pos) else {
+            return false;
+        };
+        if !id.iter().all(|b| (0x20..=0x7e).contains(b)) || pos + 8 + size > file_len {
+            return false;
+        }
+        pos += 8 + size + (size & 1);
+    }
+    true
+}
+
+/// 启动时检查录音目录，修复上次崩溃遗留的未完成WAV文件
+pub fn recover_unfinalized_recordings(recordings_dir: &Path) -> Vec<PathBuf> {
+    let mut repaired = Vec::new();
+    let entries = match std::fs::read_dir(recordings_dir) {
+        Ok(entries) => entries,
+        Err(_) => return repaired,
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().is_some_and(|ext| ext == "wav") {
+            match repair_wav_header(&path) {
+                Ok(true) => {
+                    log::info!("已修复未完成的录音文件: {:?}", path);
+                    repaired.push(path);
+                }
+                Ok(false) => {}
+                Err(e) => log::warn!("检查录音文件失败 {:?}: {}", path, e),
+            }
+        }
+    }
+
+    repaired
+}
+
+/// 读取保存的录音为浮点样本（用于校验和重新处理）
+pub fn read_wav_samples(path: &Path) -> Result<Vec<f32>, String> {
+    let mut reader = hound::WavReader::open(path).map_err(|e| format!("打开WAV文件失败: {}", e))?;
+    let spec = reader.spec();
+    match spec.sample_format {
+        hound::SampleFormat::Float => reader.samples::<f32>()
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("读取样本失败: {}", e)),
+        hound::SampleFormat::Int => {
+            let scale = (1i64 << (spec.bits_per_sample - 1)) as f32;
+            reader.samples::<i32>()
+                .map(|s| s.map(|v| v as f32 / scale))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| format!("读取样本失败: {}", e))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_signal() -> Vec<f32> {
+        (0..16000).map(|i| (i as f32 * 440.0 * 2.0 * std::f32::consts::PI / 16000.0).sin() * 0.5).collect()
+    }
+
+    fn round_trip(format: RecordingFormat) -> Option<Vec<f32>> {
+        let dir = std::env::temp_dir().join(format!("steno_format_test_{:?}_{}", format, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = write_recording(&dir.join("recording"), &test_signal(), format).unwrap();
+
+        let samples = if path.extension().is_some_and(|ext| ext == "wav") {
+            Some(read_wav_samples(&path).unwrap())
+        } else {
+            // FLAC需要外部解码器
+            let decoded = dir.join("decoded.wav");
+            let ok = std::process::Command::new("flac")
+                .args(["--silent", "--force", "-d", "-o"])
+                .arg(&decoded)
+                .arg(&path)
+                .status()
+                .map(|s| s.success())
+                .unwrap_or(false);
+            if ok { Some(read_wav_samples(&decoded).unwrap()) } else { None }
+        };
+
+        let _ = std::fs::remove_dir_all(&dir);
+        samples
+    }
+
+    #[test]
+    fn test_abrupt_stop_partial_wav_is_recoverable() {
+        let dir = std::env::temp_dir().join(format!("steno_partial_test_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let signal = test_signal();
+
+        let mut writer = StreamingWavWriter::create(&dir.join("recording"), RecordingFormat::Pcm16, RECORDING_SAMPLE_RATE).unwrap();
+        writer.append(&signal).unwrap();
+        writer.append(&signal[..4000]).unwrap();
+        let path = writer.path().to_path_buf();
+        // 模拟崩溃：不调用 finalize，直接丢弃缓冲写入器之外的状态
+        std::mem::forget(writer);
+
+        // 崩溃后文件头中的长度可能落后于实际数据
+        assert!(repair_wav_header(&path).is_ok());
+        let recovered = read_wav_samples(&path).unwrap();
+        assert!(recovered.len() >= signal.len());
+        assert!((recovered[100] - signal[100]).abs() < 3.0 / 32768.0);
+
+        let repaired = recover_unfinalized_recordings(&dir);
+        assert!(repaired.is_empty()); // 已修复的文件不再重复处理
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    /// 把 RIFF 与 data 长度改成写入中断时的样子（hound 尚未刷新文件头）
+    fn break_header(path: &Path) {
+        use std::io::{Seek, SeekFrom, Write};
+        let mut file = std::fs::OpenOptions::new().write(true).open(path).unwrap();
+        file.seek(SeekFrom::Start(4)).unwrap();
+        file.write_all(&36u32.to_le_bytes()).unwrap();
+        file.seek(SeekFrom::Start(40)).unwrap();
+        file.write_all(&0u32.to_le_bytes()).unwrap();
+    }
+
+    #[test]
+    fn test_repair_restores_truncated_header() {
+        let dir = std::env::temp_dir().join(format!("steno_repair_test_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let signal = test_signal();
+        let path = write_recording(&dir.join("recording"), &signal, RecordingFormat::Pcm16).unwrap();
+        let expected = read_wav_samples(&path).unwrap();
+
+        // 文件头声明的 data 长度为0，读取时得不到任何样本
+        break_header(&path);
+        assert!(read_wav_samples(&path).unwrap().is_empty());
+
+        assert_eq!(repair_wav_header(&path), Ok(true));
+        assert_eq!(read_wav_samples(&path).unwrap(), expected);
+        assert_eq!(repair_wav_header(&path), Ok(false));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_repair_keeps_trailing_chunks() {
+        use std::io::Write;
+
+        let dir = std::env::temp_dir().join(format!("steno_repair_list_test_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let signal = test_signal();
+        let path = write_recording(&dir.join("recording"), &signal, RecordingFormat::Pcm16).unwrap();
+        let expected = read_wav_samples(&path).unwrap();
+
+        // data 之后追加 LIST 块，RIFF 长度未包含它
+        let list_chunk = [b"LIST".as_slice(), &5u32.to_le_bytes(), b"INFOx", &[0u8]].concat();
+        std::fs::OpenOptions::new().append(true).open(&path).unwrap().write_all(&list_chunk).unwrap();
+        let file_len = std::fs::metadata(&path).unwrap().len();
+
+        assert_eq!(repair_wav_header(&path), Ok(true));
+        assert_eq!(std::fs::metadata(&path).unwrap().len(), file_len);
+        let bytes = std::fs::read(&path).unwrap();
+        assert_eq!(u32::from_le_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]) as u64, file_len - 8);
+        assert!(bytes.ends_with(&list_chunk));
+        assert_eq!(read_wav_samples(&path).unwrap(), expected);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_recording_formats_round_trip() {
+        let original = test_signal();
+        for format in [RecordingFormat::Float32, RecordingFormat::Pcm16, RecordingFormat::Flac] {
+            let Some(decoded) = round_trip(format) else { continue };
+            assert_eq!(decoded.len(), original.len());
+
+            let tolerance = if format == RecordingFormat::Float32 { 1e-6 } else { 3.0 / 32768.0 };
+            let max_error = original.iter().zip(&decoded)
+                .map(|(a, b)| (a - b).abs())
+                .fold(0.0f32, f32::max);
+            assert!(max_error <= tolerance, "{:?} 最大误差 {}", format, max_error);
+        }
+    }
+}
@@ -0,0 +1,182 @@
+// rediarization.rs - 对已保存的记录重新做说话人分离：在整段录音上聚类后改写各段的 speaker
+use serde::Serialize;
+use tauri::{Emitter, Manager};
+
+use crate::errors::StenoError;
+use crate::realtime_speaker_diarization::{RealtimeSpeakerDiarization, SpeakerTimeRange};
+use crate::storage::TranscriptionSegment;
+use crate::storage_commands::StorageState;
+use crate::load_and_convert_audio;
+
+const DEFAULT_MAX_SPEAKERS: usize = 4;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RediarizationProgress {
+    pub record_id: String,
+    pub progress: f64, // 0~100
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RediarizationResult {
+    pub speaker_count: usize,
+    pub changed_segments: usize,
+    pub segments: Vec<TranscriptionSegment>,
+}
+
+/// 与段重叠最多的说话人；段落在所有范围之外时取时间上最近的范围
+pub fn speaker_for_span(ranges: &[SpeakerTimeRange], start_time: f64, end_time: f64) -> Option<&str> {
+    let overlap = |range: &SpeakerTimeRange| end_time.min(range.end_time) - start_time.max(range.start_time);
+    let gap = |range: &SpeakerTimeRange| (range.start_time - end_time).max(start_time - range.end_time).max(0.0);
+    let best = ranges.iter()
+        .filter(|range| overlap(range) > 0.0)
+        .max_by(|a, b| overlap(a).total_cmp(&overlap(b)))
+        .or_else(|| ranges.iter().min_by(|a, b| gap(a).total_cmp(&gap(b))))?;
+    Some(best.speaker.as_str())
+}
+
+/// 在整段音频上估算说话人范围并改写 segments 的 speaker，返回 (说话人数量, 改动的段数)。
+/// 音频中没有可用的语音窗口时保留原有标注
+pub fn rediarize_segments(
+    diarization: &mut RealtimeSpeakerDiarization,
+    audio: &[f32],
+    sample_rate: u32,
+    max_speakers: usize,
+    segments: &mut [TranscriptionSegment],
+    on_progress: impl FnMut(f64),
+) -> (usize, usize) {
+    let estimate = diarization.estimate_speaker_count_with_progress(audio, sample_rate, max_speakers, on_progress);
+    if estimate.ranges.is_empty() {
+        return (0, 0);
+    }
+
+    let mut changed = 0;
+    for segment in segments.iter_mut() {
+        let speaker = speaker_for_span(&estimate.ranges, segment.start_time, segment.end_time).map(str::to_string);
+        if segment.speaker != speaker {
+            segment.speaker = speaker;
+            changed += 1;
+        }
+    }
+    (estimate.speaker_count, changed)
+}
+
+/// 读取记录保存的音频重新分离说话人，结果写回存储；进度通过 rediarization_progress 事件发送
+#[tauri::command]
+pub async fn rediarize_record(
+    record_id: String,
+    max_speakers: Option<usize>,
+    app_handle: tauri::AppHandle,
+) -> Result<RediarizationResult, StenoError> {
+    let storage_state = app_handle.state::<StorageState>();
+    let record = storage_state.with_storage(|storage| storage.get_record(&record_id))?
+        .ok_or_else(|| StenoError::NotFound(format!("记录不存在: {}", record_id)))?;
+    let mut segments = record.result
+        .and_then(|result| result.segments)
+        .filter(|segments| !segments.is_empty())
+        .ok_or_else(|| StenoError::NotFound("该记录没有分段结果".to_string()))?;
+    let file_path = record.file_path;
+
+    let app_handle_clone = app_handle.clone();
+    let record_id_clone = record_id.clone();
+    let (segments, speaker_count, changed_segments) = tauri::async_runtime::spawn_blocking(move || {
+        // 与转录走同一条预处理流水线（含 VAD 去静音），段时间对应的是裁剪后的时间轴
+        let (audio, sample_rate, _) = load_and_convert_audio(&file_path)?;
+        let mut diarization = RealtimeSpeakerDiarization::new();
+        let mut last_percent = -1.0;
+        let (speaker_count, changed) = rediarize_segments(
+            &mut diarization,
+            &audio,
+            sample_rate,
+            max_speakers.unwrap_or(DEFAULT_MAX_SPEAKERS),
+            &mut segments,
+            |fraction| {
+                let percent = (fraction * 100.0).floor();
+                if percent > last_percent {
+                    last_percent = percent;
+                    let _ = app_handle_clone.emit("rediarization_progress", RediarizationProgress {
+                        record_id: record_id_clone.clone(),
+                        progress: percent,
+                    });
+                }
+            },
+        );
+        Ok::<_, String>((segments, speaker_count, changed))
+    })
+    .await
+    .map_err(|e| StenoError::Internal(format!("说话人重新分离任务异常: {}", e)))?
+    .map_err(StenoError::Internal)?;
+
+    storage_state.with_storage(|storage| storage.update_record_segments(&record_id, &segments))?;
+    log::info!("记录 {} 重新分离说话人: {} 位，改动 {} 段", record_id, speaker_count, changed_segments);
+    Ok(RediarizationResult { speaker_count, changed_segments, segments })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::realtime_speaker_diarization::SpeakerEmbedder;
+    use crate::storage::test_support::{sample_record, sample_segment, temp_storage};
+    use crate::storage::TranscriptionResult;
+    use crate::whisper_input::WHISPER_SAMPLE_RATE as SAMPLE_RATE;
+
+    /// 按音量区分两位说话人的测试嵌入
+    struct LevelEmbedder;
+
+    impl SpeakerEmbedder for LevelEmbedder {
+        fn dimension(&self) -> usize {
+            2
+        }
+
+        fn embed(&self, audio: &[f32]) -> Option<Vec<f32>> {
+            Some(if audio[0] > 0.3 { vec![0.0, 1.0] } else { vec![1.0, 0.0] })
+        }
+    }
+
+    // 实时识别时全部标成同一说话人
+    fn segment(id: &str, start_time: f64, end_time: f64) -> TranscriptionSegment {
+        TranscriptionSegment { speaker: Some("说话人A".to_string()), ..sample_segment(id, start_time, end_time, "段") }
+    }
+
+    fn constant(level: f32, seconds: usize) -> Vec<f32> {
+        vec![level; seconds * SAMPLE_RATE as usize]
+    }
+
+    #[test]
+    fn test_speaker_for_span_prefers_overlap_then_nearest() {
+        let range = |speaker: &str, start_time, end_time| SpeakerTimeRange { speaker: speaker.to_string(), start_time, end_time };
+        let ranges = vec![range("A", 0.0, 3.0), range("B", 3.0, 5.0), range("A", 8.0, 10.0)];
+        assert_eq!(speaker_for_span(&ranges, 2.5, 4.8), Some("B"));
+        assert_eq!(speaker_for_span(&ranges, 0.5, 3.2), Some("A"));
+        assert_eq!(speaker_for_span(&ranges, 5.5, 6.0), Some("B"));
+        assert_eq!(speaker_for_span(&ranges, 7.0, 7.5), Some("A"));
+        assert_eq!(speaker_for_span(&[], 0.0, 1.0), None);
+    }
+
+    #[test]
+    fn test_rediarization_updates_persisted_speakers() {
+        let (storage, dir) = temp_storage("rediarize");
+        let mut record = sample_record("record_1");
+        record.result = Some(TranscriptionResult {
+            text: "段段段".to_string(),
+            processing_time: 1.0,
+            accuracy: None,
+            segments: Some(vec![segment("s0", 0.0, 2.5), segment("s1", 3.1, 5.8), segment("s2", 6.2, 7.9)]),
+        });
+        storage.save_record(&record).unwrap();
+
+        // 实时识别全部标成了说话人A，实际第二段是另一位说话人
+        let audio = [constant(0.2, 3), constant(0.5, 3), constant(0.2, 2)].concat();
+        let mut segments = storage.get_record("record_1").unwrap().unwrap().result.unwrap().segments.unwrap();
+        let mut diarization = RealtimeSpeakerDiarization::with_embedder(Box::new(LevelEmbedder));
+        let mut progress = Vec::new();
+        let (speaker_count, changed) = rediarize_segments(&mut diarization, &audio, SAMPLE_RATE, 4, &mut segments, |p| progress.push(p));
+        assert_eq!((speaker_count, changed), (2, 1));
+        assert_eq!(progress.last(), Some(&1.0));
+        storage.update_record_segments("record_1", &segments).unwrap();
+
+        let saved = storage.get_record("record_1").unwrap().unwrap().result.unwrap().segments.unwrap();
+        let speakers: Vec<(&str, Option<&str>)> = saved.iter().map(|s| (s.id.as_str(), s.speaker.as_deref())).collect();
+        assert_eq!(speakers, vec![("s0", Some("说话人A")), ("s1", Some("说话人B")), ("s2", Some("说话人A"))]);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
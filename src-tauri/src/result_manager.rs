@@ -4,6 +4,7 @@ use std::time::Duration;
 use serde::{Deserialize, Serialize};
 
 use crate::layered_processor::TranscriptResult;
+use crate::text_processing::{join_segments, normalize_for_index, TextJoinStyle};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ManagedTranscriptSegment {
@@ -125,6 +126,7 @@ impl DeduplicationEngine {
     }
 
     fn calculate_text_similarity(&self, text1: &str, text2: &str) -> f32 {
+        let (text1, text2) = (normalize_for_index(text1, true), normalize_for_index(text2, true));
         if text1 == text2 {
             return 1.0;
         }
@@ -189,6 +191,8 @@ pub struct SegmentOrganizer {
     segments: VecDeque<ManagedTranscriptSegment>,
     max_segments: usize,
     auto_paragraph_threshold: Duration,
+    join_style: TextJoinStyle, // 连续文本中段与段之间的分隔方式
+    language: String, // 会话语言，决定按空格拼接时是否加空格
 }
 
 impl SegmentOrganizer {
@@ -197,9 +201,19 @@ impl SegmentOrganizer {
             segments: VecDeque::with_capacity(max_segments),
             max_segments,
             auto_paragraph_threshold: Duration::from_secs(3),
+            join_style: TextJoinStyle::default(),
+            language: "auto".to_string(),
         }
     }
 
+    pub fn set_join_style(&mut self, join_style: TextJoinStyle) {
+        self.join_style = join_style;
+    }
+
+    pub fn set_language(&mut self, language: &str) {
+        self.language = language.to_string();
+    }
+
     pub fn add_segment(&mut self, result: TranscriptResult, source: SegmentSource) -> String {
         let segment_id = format!("seg_{}_{}", result.timestamp, result.segment_id);
         
@@ -301,16 +315,8 @@ impl SegmentOrganizer {
 
     pub fn get_continuous_text(&self, max_segments: Option<usize>) -> String {
         let limit = max_segments.unwrap_or(self.segments.len());
-        
-        self.segments
-            .iter()
-            .rev()
-            .take(limit)
-            .rev()
-            .map(|s| &s.text)
-            .map(|s| s.as_str())
-            .collect::<Vec<_>>()
-            .join(" ")
+        let skip = self.segments.len().saturating_sub(limit);
+        join_segments(self.segments.iter().skip(skip).map(|s| s.text.as_str()), self.join_style, &self.language)
     }
 
     fn should_merge_with_previous(&self, new_segment: &ManagedTranscriptSegment, last_segment: &ManagedTranscriptSegment) -> bool {
@@ -498,6 +504,14 @@ impl ResultManager {
         self.segment_organizer.get_continuous_text(max_segments)
     }
 
+    pub fn set_join_style(&mut self, join_style: TextJoinStyle) {
+        self.segment_organizer.set_join_style(join_style);
+    }
+
+    pub fn set_language(&mut self, language: &str) {
+        self.segment_organizer.set_language(language);
+    }
+
     pub fn get_quality_report(&self) -> QualityReport {
         let segments = self.segment_organizer.get_segments();
         let mut report = QualityReport::default();
@@ -563,4 +577,52 @@ pub struct QualityReport {
     pub average_confidence: f32,
     pub quality_percentage: f32,
     pub total_confidence: f32,
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn final_result(text: &str, timestamp: u64) -> TranscriptResult {
+        TranscriptResult {
+            text: text.to_string(),
+            confidence: 0.9,
+            is_temporary: false,
+            speaker: None,
+            timestamp,
+            processing_time_ms: 100,
+            segment_id: timestamp.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_continuous_text_join_styles() {
+        let mut organizer = SegmentOrganizer::new(10);
+        for (i, text) in ["大家好", "今天讨论预算", "先看第一季度"].iter().enumerate() {
+            organizer.add_segment(final_result(text, i as u64 * 10_000), SegmentSource::AccurateProcessing);
+        }
+
+        assert_eq!(organizer.get_continuous_text(None), "大家好今天讨论预算先看第一季度");
+        organizer.set_join_style(TextJoinStyle::Newline);
+        assert_eq!(organizer.get_continuous_text(None), "大家好\n今天讨论预算\n先看第一季度");
+        organizer.set_join_style(TextJoinStyle::Paragraph);
+        assert_eq!(organizer.get_continuous_text(Some(2)), "今天讨论预算\n\n先看第一季度");
+    }
+
+    #[test]
+    fn test_continuous_text_uses_session_language() {
+        let mut organizer = SegmentOrganizer::new(10);
+        for (i, text) in ["Good morning", "let's review the budget"].iter().enumerate() {
+            organizer.add_segment(final_result(text, i as u64 * 10_000), SegmentSource::AccurateProcessing);
+        }
+        organizer.set_language("en");
+        assert_eq!(organizer.get_continuous_text(None), "Good morning let's review the budget");
+
+        let mut organizer = SegmentOrganizer::new(10);
+        for (i, text) in ["第一季度", "Q2 计划"].iter().enumerate() {
+            organizer.add_segment(final_result(text, i as u64 * 10_000), SegmentSource::AccurateProcessing);
+        }
+        organizer.set_language("zh");
+        assert_eq!(organizer.get_continuous_text(None), "第一季度Q2 计划");
+    }
+}
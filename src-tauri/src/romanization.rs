@@ -0,0 +1,119 @@
+// romanization.rs - 中文转录的拼音注音：逐段生成与原文对应的带声调拼音，随分段保存并可一起导出
+use pinyin::ToPinyin;
+
+use crate::storage::{TranscriptionConfig, TranscriptionSegment};
+
+enum Token {
+    Syllable(&'static str),
+    Literal(String), // 非汉字原样保留，如英文单词、数字、标点
+}
+
+/// 中文标点换成对应的半角标点，拼音行中更易读
+fn romanize_punctuation(c: char) -> char {
+    match c {
+        '，' | '、' => ',',
+        '。' => '.',
+        '！' => '!',
+        '？' => '?',
+        '：' => ':',
+        '；' => ';',
+        '（' => '(',
+        '）' => ')',
+        '“' | '”' => '"',
+        _ => c,
+    }
+}
+
+fn is_punctuation(text: &str) -> bool {
+    text.chars().all(|c| c.is_ascii_punctuation() && !matches!(c, '(' | '"'))
+}
+
+/// 文本的拼音：每个汉字一个音节，音节之间用空格分隔，其余字符原样保留；没有汉字时返回 None
+pub fn romanize(text: &str) -> Option<String> {
+    let mut tokens = Vec::new();
+    let mut literal = String::new();
+    let mut has_syllable = false;
+    for c in text.chars() {
+        match c.to_pinyin() {
+            Some(pinyin) => {
+                if !literal.is_empty() {
+                    tokens.push(Token::Literal(std::mem::take(&mut literal)));
+                }
+                tokens.push(Token::Syllable(pinyin.with_tone()));
+                has_syllable = true;
+            }
+            None if c.is_whitespace() => {
+                if !literal.is_empty() {
+                    tokens.push(Token::Literal(std::mem::take(&mut literal)));
+                }
+            }
+            None => literal.push(romanize_punctuation(c)),
+        }
+    }
+    if !literal.is_empty() {
+        tokens.push(Token::Literal(literal));
+    }
+    if !has_syllable {
+        return None;
+    }
+
+    let mut output = String::new();
+    for token in tokens {
+        let text = match &token {
+            Token::Syllable(syllable) => *syllable,
+            Token::Literal(literal) => literal.as_str(),
+        };
+        // 标点紧跟前一个音节
+        let attach = matches!(token, Token::Literal(_)) && is_punctuation(text);
+        if !output.is_empty() && !attach {
+            output.push(' ');
+        }
+        output.push_str(text);
+    }
+    Some(output)
+}
+
+/// 配置开启注音且语言为中文（或自动检测）时，为每段生成拼音；关闭时清除已有注音
+pub fn annotate_segments(segments: &mut [TranscriptionSegment], config: &TranscriptionConfig) {
+    let enabled = config.romanization && matches!(config.language.as_str(), "zh" | "yue" | "auto" | "");
+    for segment in segments {
+        segment.romanization = if enabled { romanize(&segment.text) } else { None };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::test_support::sample_segment;
+
+    fn segment(text: &str) -> TranscriptionSegment {
+        sample_segment("", 0.0, 2.0, text)
+    }
+
+    #[test]
+    fn test_romanization_for_chinese_segments() {
+        assert_eq!(romanize("你好，世界！").as_deref(), Some("nǐ hǎo, shì jiè!"));
+        assert_eq!(romanize("我们用 GPU 训练模型。").as_deref(), Some("wǒ men yòng GPU xùn liàn mó xíng."));
+        assert_eq!(romanize("第3季度").as_deref(), Some("dì 3 jì dù"));
+        assert_eq!(romanize("Hello world"), None);
+
+        let config = TranscriptionConfig { language: "zh".to_string(), romanization: true, ..TranscriptionConfig::default() };
+        let mut segments = vec![segment("今天开会"), segment("OK"), segment("谢谢")];
+        annotate_segments(&mut segments, &config);
+        let romanized: Vec<Option<&str>> = segments.iter().map(|s| s.romanization.as_deref()).collect();
+        assert_eq!(romanized, vec![Some("jīn tiān kāi huì"), None, Some("xiè xiè")]);
+    }
+
+    #[test]
+    fn test_romanization_omitted_when_disabled() {
+        let mut segments = vec![segment("今天开会")];
+        annotate_segments(&mut segments, &TranscriptionConfig { language: "zh".to_string(), ..TranscriptionConfig::default() });
+        assert_eq!(segments[0].romanization, None);
+
+        // 英文、日文记录不生成拼音；关闭后重新生成会清除旧的注音
+        let enabled_en = TranscriptionConfig { language: "en".to_string(), romanization: true, ..TranscriptionConfig::default() };
+        segments[0].romanization = Some("jīn tiān kāi huì".to_string());
+        annotate_segments(&mut segments, &enabled_en);
+        assert_eq!(segments[0].romanization, None);
+    }
+}
@@ -0,0 +1,138 @@
+// spectral_analysis.rs - 基于 FFT 的频谱特征（说话人分离与实时特征提取共用）
+use std::cell::RefCell;
+use rustfft::{FftPlanner, num_complex::Complex32};
+
+/// 默认分析帧长（16kHz 下为32ms），帧移为半帧
+pub const DEFAULT_FRAME_SIZE: usize = 512;
+/// 频谱滚降点：累计能量达到该比例时的频率
+const ROLLOFF_RATIO: f32 = 0.85;
+
+thread_local! {
+    // FftPlanner 内部缓存已创建的 FFT 计划，避免每次调用重新规划
+    static FFT_PLANNER: RefCell<FftPlanner<f32>> = RefCell::new(FftPlanner::new());
+}
+
+/// 在当前线程共享的 FftPlanner 上执行操作
+pub fn with_fft_planner<R>(f: impl FnOnce(&mut FftPlanner<f32>) -> R) -> R {
+    FFT_PLANNER.with(|planner| f(&mut planner.borrow_mut()))
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SpectralFeatures {
+    pub centroid: f32,  // 频谱质心 (Hz)
+    pub bandwidth: f32, // 频谱带宽 (Hz)
+    pub rolloff: f32,   // 频谱滚降点 (Hz)
+}
+
+/// 加 Hann 窗后的幅度谱，长度为 fft_len/2+1；帧长不足2的幂时补零
+pub fn magnitude_spectrum(frame: &[f32]) -> Vec<f32> {
+    if frame.is_empty() {
+        return Vec::new();
+    }
+    let fft_len = frame.len().next_power_of_two();
+    let denominator = (frame.len().max(2) - 1) as f32;
+    let mut buffer: Vec<Complex32> = frame.iter().enumerate()
+        .map(|(i, &x)| {
+            let window = 0.5 - 0.5 * (2.0 * std::f32::consts::PI * i as f32 / denominator).cos();
+            Complex32::new(x * window, 0.0)
+        })
+        .collect();
+    buffer.resize(fft_len, Complex32::new(0.0, 0.0));
+
+    with_fft_planner(|planner| planner.plan_fft_forward(fft_len).process(&mut buffer));
+    buffer[..fft_len / 2 + 1].iter().map(|c| c.norm()).collect()
+}
+
+/// 由幅度谱计算质心、带宽与滚降点；bin_hz 为每个频点对应的频率间隔
+pub fn features_from_spectrum(spectrum: &[f32], bin_hz: f32) -> SpectralFeatures {
+    let total: f32 = spectrum.iter().sum();
+    if total <= f32::EPSILON {
+        return SpectralFeatures::default();
+    }
+
+    let centroid = spectrum.iter().enumerate()
+        .map(|(i, &m)| i as f32 * bin_hz * m)
+        .sum::<f32>() / total;
+    let variance = spectrum.iter().enumerate()
+        .map(|(i, &m)| (i as f32 * bin_hz - centroid).powi(2) * m)
+        .sum::<f32>() / total;
+
+    let energy: Vec<f32> = spectrum.iter().map(|&m| m * m).collect();
+    let threshold = energy.iter().sum::<f32>() * ROLLOFF_RATIO;
+    let mut cumulative = 0.0;
+    let mut rolloff_bin = spectrum.len() - 1;
+    for (i, &e) in energy.iter().enumerate() {
+        cumulative += e;
+        if cumulative >= threshold {
+            rolloff_bin = i;
+            break;
+        }
+    }
+
+    SpectralFeatures {
+        centroid,
+        bandwidth: variance.sqrt(),
+        rolloff: rolloff_bin as f32 * bin_hz,
+    }
+}
+
+/// 整段音频的频谱特征：逐帧计算幅度谱后取平均；音频短于一帧时整段作为一帧
+pub fn analyze_spectrum(audio: &[f32], sample_rate: u32) -> SpectralFeatures {
+    if audio.is_empty() {
+        return SpectralFeatures::default();
+    }
+    let frame_size = DEFAULT_FRAME_SIZE.min(audio.len());
+    let hop = (frame_size / 2).max(1);
+
+    let mut average: Vec<f32> = Vec::new();
+    let mut frames = 0;
+    let mut start = 0;
+    while start + frame_size <= audio.len() {
+        let spectrum = magnitude_spectrum(&audio[start..start + frame_size]);
+        if average.is_empty() {
+            average = spectrum;
+        } else {
+            for (sum, m) in average.iter_mut().zip(spectrum) {
+                *sum += m;
+            }
+        }
+        frames += 1;
+        start += hop;
+    }
+    for value in average.iter_mut() {
+        *value /= frames as f32;
+    }
+
+    let fft_len = frame_size.next_power_of_two();
+    features_from_spectrum(&average, sample_rate as f32 / fft_len as f32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tone(frequency: f32, samples: usize) -> Vec<f32> {
+        (0..samples)
+            .map(|n| (2.0 * std::f32::consts::PI * frequency * n as f32 / 16000.0).sin())
+            .collect()
+    }
+
+    #[test]
+    fn test_pure_tone_centroid_near_frequency() {
+        for frequency in [440.0, 1000.0, 3000.0] {
+            let features = analyze_spectrum(&tone(frequency, 8000), 16000);
+            assert!((features.centroid - frequency).abs() < 50.0, "期望 {}Hz，质心 {}Hz", frequency, features.centroid);
+            assert!((features.rolloff - frequency).abs() < 50.0);
+            assert!(features.bandwidth < 100.0);
+        }
+    }
+
+    #[test]
+    fn test_silence_and_short_frames() {
+        assert_eq!(analyze_spectrum(&vec![0.0; 1024], 16000), SpectralFeatures::default());
+        assert_eq!(analyze_spectrum(&[], 16000), SpectralFeatures::default());
+        // 短于一帧时按补零后的长度分析
+        let features = analyze_spectrum(&tone(2000.0, 400), 16000);
+        assert!((features.centroid - 2000.0).abs() < 100.0);
+    }
+}
@@ -1,7 +1,18 @@
-use rusqlite::{Connection, Result, params};
+use rusqlite::{Connection, OpenFlags, OptionalExtension, Result, params};
 use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::ops::Deref;
+use std::path::PathBuf;
+use std::sync::{Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
 use crate::database_manager::DatabaseManager;
+use crate::confidence::estimate_record_accuracy;
+use crate::realtime_audio_full::RealtimeConfig;
+use crate::realtime_speaker_diarization::DiarizationSummary;
+use crate::decoding::{DecodingStrategy, SegmentationConfig, TemperatureFallback};
+use crate::text_processing::SuppressionConfig;
+use crate::waveform::{self, AudioStamp, PeakPair};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TranscriptionRecord {
@@ -21,6 +32,20 @@ pub struct TranscriptionRecord {
     pub is_starred: bool,
     pub config: TranscriptionConfig,
     pub result: Option<TranscriptionResult>,
+    #[serde(default)]
+    pub model_name: Option<String>, // 转录使用的模型
+    #[serde(default)]
+    pub realtime_factor: Option<f64>, // 实时倍率 xRT = 音频时长 / 处理耗时
+    #[serde(default)]
+    pub sources: Option<Vec<SourceBoundary>>, // 由多个文件合并转录时，各源文件在合并音频中的范围
+}
+
+/// 合并转录中一个源文件在合并后时间轴上的范围
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SourceBoundary {
+    pub file_path: String,
+    pub start_time: f64, // 秒
+    pub end_time: f64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -28,6 +53,57 @@ pub struct TranscriptionConfig {
     pub language: String,
     pub mode: String,
     pub audio_enhancement: bool,
+    #[serde(default)]
+    pub decoding: Option<DecodingStrategy>, // 未设置时按 mode 选择解码参数
+    #[serde(default)]
+    pub temperature_fallback: TemperatureFallback,
+    #[serde(default)]
+    pub segmentation: SegmentationConfig,
+    #[serde(default)]
+    pub suppression: SuppressionConfig, // 非语音标注过滤
+    #[serde(default)]
+    pub romanization: bool, // 为中文转录逐段生成拼音
+}
+
+impl Default for TranscriptionConfig {
+    fn default() -> Self {
+        Self {
+            language: "auto".to_string(),
+            mode: "normal".to_string(),
+            audio_enhancement: false,
+            decoding: None,
+            temperature_fallback: TemperatureFallback::default(),
+            segmentation: SegmentationConfig::default(),
+            suppression: SuppressionConfig::default(),
+            romanization: false,
+        }
+    }
+}
+
+/// 上次使用的转录配置，启动命令未指定参数时以此为默认值
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LastUsedConfig {
+    pub transcription: Option<TranscriptionConfig>,
+    pub realtime: Option<RealtimeConfig>,
+    pub prompt_template_id: Option<String>,
+}
+
+impl LastUsedConfig {
+    /// 前端传入的参数优先，未传入时使用上次的值
+    pub fn resolve_transcription(&self, language: Option<String>, mode: Option<String>) -> TranscriptionConfig {
+        let last = self.transcription.clone().unwrap_or_default();
+        TranscriptionConfig {
+            language: language.unwrap_or(last.language),
+            mode: mode.unwrap_or(last.mode),
+            audio_enhancement: last.audio_enhancement,
+            decoding: last.decoding,
+            temperature_fallback: last.temperature_fallback,
+            segmentation: last.segmentation,
+            suppression: last.suppression,
+            romanization: last.romanization,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -38,7 +114,7 @@ pub struct TranscriptionResult {
     pub segments: Option<Vec<TranscriptionSegment>>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct TranscriptionSegment {
     pub id: String,
     pub start_time: f64,
@@ -46,6 +122,10 @@ pub struct TranscriptionSegment {
     pub text: String,
     pub speaker: Option<String>,
     pub confidence: Option<f64>,
+    #[serde(default)]
+    pub no_speech_prob: Option<f64>, // Whisper 无语音概率，用于诊断
+    #[serde(default)]
+    pub romanization: Option<String>, // 与 text 对应的拼音，开启 TranscriptionConfig.romanization 时生成
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -64,17 +144,162 @@ pub struct PromptTemplate {
     pub is_active: bool,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LibraryStats {
+    pub total_records: i64,
+    pub completed_records: i64,
+    pub starred_records: i64,
+    pub total_duration: f64,        // 秒
+    pub total_processing_time: f64, // 秒
+    pub average_accuracy: Option<f64>, // 按音频时长加权，仅统计有准确率的记录
+    pub average_realtime_factor: Option<f64>,
+    pub realtime_factor_by_model: Vec<ModelSpeed>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModelSpeed {
+    pub model_name: String,
+    pub record_count: i64,
+    pub average_realtime_factor: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TagUsage {
+    pub tag: String,
+    pub count: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CategoryUsage {
+    pub name: String,
+    pub count: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromptUsageStats {
+    pub id: String,
+    pub name: String,
+    pub category: String,
+    pub usage_count: i32,
+    pub last_used_at: Option<DateTime<Utc>>,
+}
+
+/// 存储服务：写操作共用一个受互斥锁保护的连接，读操作从只读连接池取连接，
+/// 因此读与读、读与写可以并发，只有写与写之间串行
 pub struct StorageService {
-    conn: Connection,
+    writer: Mutex<Connection>,
+    readers: ReaderPool,
+    last_write: Mutex<Instant>, // 最近一次借用写连接的时间，用于判断是否空闲
+}
+
+/// 只读连接池：按需打开连接，最多 MAX_READERS 个，用完归还复用
+struct ReaderPool {
+    db_path: PathBuf,
+    state: Mutex<ReaderPoolState>,
+    available: Condvar,
+}
+
+struct ReaderPoolState {
+    idle: Vec<Connection>,
+    opened: usize,
+}
+
+/// 从读连接池借出的连接，离开作用域时自动归还
+struct PooledReader<'a> {
+    pool: &'a ReaderPool,
+    conn: Option<Connection>,
+}
+
+impl ReaderPool {
+    fn new(db_path: PathBuf) -> Self {
+        Self {
+            db_path,
+            state: Mutex::new(ReaderPoolState { idle: Vec::new(), opened: 0 }),
+            available: Condvar::new(),
+        }
+    }
+
+    fn get(&self) -> Result<PooledReader<'_>> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        loop {
+            if let Some(conn) = state.idle.pop() {
+                return Ok(PooledReader { pool: self, conn: Some(conn) });
+            }
+            if state.opened < MAX_READERS {
+                state.opened += 1;
+                drop(state);
+                return match self.open() {
+                    Ok(conn) => Ok(PooledReader { pool: self, conn: Some(conn) }),
+                    Err(e) => {
+                        self.state.lock().unwrap_or_else(|e| e.into_inner()).opened -= 1;
+                        self.available.notify_one();
+                        Err(e)
+                    }
+                };
+            }
+            state = self.available.wait(state).unwrap_or_else(|e| e.into_inner());
+        }
+    }
+
+    fn open(&self) -> Result<Connection> {
+        let conn = Connection::open_with_flags(
+            &self.db_path,
+            OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        )?;
+        conn.busy_timeout(BUSY_TIMEOUT)?;
+        Ok(conn)
+    }
 }
 
+impl Deref for PooledReader<'_> {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        self.conn.as_ref().expect("pooled connection already returned")
+    }
+}
+
+impl Drop for PooledReader<'_> {
+    fn drop(&mut self) {
+        if let Some(conn) = self.conn.take() {
+            self.pool.state.lock().unwrap_or_else(|e| e.into_inner()).idle.push(conn);
+            self.pool.available.notify_one();
+        }
+    }
+}
+
+/// 累计删除行数达到该值后，在空闲时自动执行 VACUUM
+const AUTO_VACUUM_THRESHOLD: i64 = 500;
+/// 最近这段时间内有过写入时不执行自动 VACUUM，避免打断正在使用的用户
+const AUTO_VACUUM_MIN_IDLE: Duration = Duration::from_secs(600);
+const DELETED_ROWS_KEY: &str = "deleted_rows_since_vacuum";
+const LAST_USED_CONFIG_KEY: &str = "last_used_config";
+const INPUT_GAINS_KEY: &str = "input_gains";
+/// 只读连接池的最大连接数
+const MAX_READERS: usize = 4;
+/// 写事务提交需要等待读连接释放共享锁，超过该时间才返回 SQLITE_BUSY
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
 impl StorageService {
     pub fn new(app_handle: &tauri::AppHandle) -> Result<Self> {
         // 使用数据库管理器初始化数据库
         let db_manager = DatabaseManager::new(app_handle)?;
+        Self::with_manager(&db_manager)
+    }
+
+    /// 基于已创建的数据库管理器打开存储（测试时使用临时数据库）
+    pub(crate) fn with_manager(db_manager: &DatabaseManager) -> Result<Self> {
         let conn = db_manager.initialize_database()?;
+        conn.busy_timeout(BUSY_TIMEOUT)?;
+        // WAL 模式下读连接不会被写事务阻塞；备份与恢复由 DatabaseManager 处理 -wal/-shm 文件
+        conn.pragma_update_and_check(None, "journal_mode", "WAL", |row| row.get::<_, String>(0))?;
         
-        let storage = Self { conn };
+        let storage = Self {
+            writer: Mutex::new(conn),
+            readers: ReaderPool::new(db_manager.db_path.clone()),
+            last_write: Mutex::new(Instant::now()),
+        };
+        storage.prune_orphan_diarization_summaries()?;
         // 初始化内置提示词（如果需要）
         storage.init_built_in_prompts()?;
         Ok(storage)
@@ -82,16 +307,34 @@ impl StorageService {
 
     // 数据库初始化现在由 DatabaseManager 处理
 
+    /// 获取写连接；持有期间其他写操作等待。连接本身不会因 panic 损坏，因此忽略锁中毒
+    fn writer(&self) -> MutexGuard<'_, Connection> {
+        *self.last_write.lock().unwrap_or_else(|e| e.into_inner()) = Instant::now();
+        self.writer.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// 距最近一次写入已过去的时间；保存结果、自动保存、删除等都会重置
+    fn idle_for(&self) -> Duration {
+        self.last_write.lock().unwrap_or_else(|e| e.into_inner()).elapsed()
+    }
+
+    /// 从只读连接池借一个连接
+    fn reader(&self) -> Result<PooledReader<'_>> {
+        self.readers.get()
+    }
+
     pub fn save_record(&self, record: &TranscriptionRecord) -> Result<()> {
-        let tx = self.conn.unchecked_transaction()?;
+        let conn = self.writer();
+        let tx = conn.unchecked_transaction()?;
 
         // 保存主记录
         tx.execute(
             "INSERT OR REPLACE INTO transcription_records (
                 id, name, original_file_name, file_path, file_size, duration,
                 status, progress, error_message, created_at, updated_at,
-                tags, category, is_starred, config, processing_time, accuracy
-            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17)",
+                tags, category, is_starred, config, processing_time, accuracy,
+                model_name, realtime_factor, sources
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20)",
             params![
                 record.id,
                 record.name,
@@ -110,21 +353,20 @@ impl StorageService {
                 serde_json::to_string(&record.config).unwrap_or_default(),
                 record.result.as_ref().map(|r| r.processing_time),
                 record.result.as_ref().and_then(|r| r.accuracy),
+                record.model_name,
+                record.realtime_factor,
+                record.sources.as_ref().map(|sources| serde_json::to_string(sources).unwrap_or_default()),
             ],
         )?;
 
-        // 保存转录内容
+        // 保存转录内容（分段写入 transcription_segments 表）
         if let Some(result) = &record.result {
             tx.execute(
                 "INSERT OR REPLACE INTO transcription_contents (record_id, full_text, segments) 
-                 VALUES (?1, ?2, ?3)",
-                params![
-                    record.id,
-                    result.text,
-                    result.segments.as_ref()
-                        .map(|s| serde_json::to_string(s).unwrap_or_default())
-                ],
+                 VALUES (?1, ?2, NULL)",
+                params![record.id, result.text],
             )?;
+            write_segments(&tx, &record.id, result.segments.as_deref().unwrap_or(&[]))?;
         }
 
         tx.commit()?;
@@ -132,7 +374,8 @@ impl StorageService {
     }
 
     pub fn get_record(&self, id: &str) -> Result<Option<TranscriptionRecord>> {
-        let mut stmt = self.conn.prepare(
+        let conn = self.reader()?;
+        let mut stmt = conn.prepare(
             "SELECT r.*, c.full_text, c.segments 
              FROM transcription_records r
              LEFT JOIN transcription_contents c ON r.id = c.record_id
@@ -140,7 +383,7 @@ impl StorageService {
         )?;
 
         let record_iter = stmt.query_map([id], |row| {
-            self.row_to_record(row)
+            Self::row_to_record(&conn, row)
         })?;
 
         for record in record_iter {
@@ -151,7 +394,8 @@ impl StorageService {
     }
 
     pub fn get_all_records(&self) -> Result<Vec<TranscriptionRecord>> {
-        let mut stmt = self.conn.prepare(
+        let conn = self.reader()?;
+        let mut stmt = conn.prepare(
             "SELECT r.*, c.full_text, c.segments 
              FROM transcription_records r
              LEFT JOIN transcription_contents c ON r.id = c.record_id
@@ -159,7 +403,7 @@ impl StorageService {
         )?;
 
         let record_iter = stmt.query_map([], |row| {
-            self.row_to_record(row)
+            Self::row_to_record(&conn, row)
         })?;
 
         let mut records = Vec::new();
@@ -171,7 +415,8 @@ impl StorageService {
     }
 
     pub fn update_record_status(&self, id: &str, status: &str, progress: f64, error: Option<&str>) -> Result<()> {
-        self.conn.execute(
+        let conn = self.writer();
+        conn.execute(
             "UPDATE transcription_records 
              SET status = ?1, progress = ?2, error_message = ?3, updated_at = ?4
              WHERE id = ?5",
@@ -187,7 +432,12 @@ impl StorageService {
     }
 
     pub fn update_record_result(&self, id: &str, result: &TranscriptionResult) -> Result<()> {
-        let tx = self.conn.unchecked_transaction()?;
+        // 未提供准确率时由段置信度估算
+        let accuracy = result.accuracy
+            .or_else(|| result.segments.as_deref().and_then(estimate_record_accuracy));
+
+        let conn = self.writer();
+        let tx = conn.unchecked_transaction()?;
 
         // 更新主记录
         tx.execute(
@@ -196,7 +446,7 @@ impl StorageService {
              WHERE id = ?4",
             params![
                 result.processing_time,
-                result.accuracy,
+                accuracy,
                 Utc::now().to_rfc3339(),
                 id
             ],
@@ -205,38 +455,164 @@ impl StorageService {
         // 保存转录内容
         tx.execute(
             "INSERT OR REPLACE INTO transcription_contents (record_id, full_text, segments) 
-             VALUES (?1, ?2, ?3)",
-            params![
-                id,
-                result.text,
-                result.segments.as_ref()
-                    .map(|s| serde_json::to_string(s).unwrap_or_default())
-            ],
+             VALUES (?1, ?2, NULL)",
+            params![id, result.text],
         )?;
+        write_segments(&tx, id, result.segments.as_deref().unwrap_or(&[]))?;
 
         tx.commit()?;
         Ok(())
     }
 
+    /// 覆盖记录的分段（如重新分配说话人后），不改变记录状态与全文
+    pub fn update_record_segments(&self, id: &str, segments: &[TranscriptionSegment]) -> Result<()> {
+        let conn = self.writer();
+        let tx = conn.unchecked_transaction()?;
+        tx.execute(
+            "UPDATE transcription_records SET updated_at = ?1 WHERE id = ?2",
+            params![Utc::now().to_rfc3339(), id],
+        )?;
+        write_segments(&tx, id, segments)?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// 记录转录使用的模型及处理速度
+    pub fn update_record_performance(&self, id: &str, model_name: Option<&str>, realtime_factor: Option<f64>) -> Result<()> {
+        let conn = self.writer();
+        conn.execute(
+            "UPDATE transcription_records SET model_name = ?1, realtime_factor = ?2 WHERE id = ?3",
+            params![model_name, realtime_factor, id],
+        )?;
+        Ok(())
+    }
+
     pub fn delete_record(&self, id: &str) -> Result<()> {
-        let tx = self.conn.unchecked_transaction()?;
+        let conn = self.writer();
+        let tx = conn.unchecked_transaction()?;
         
-        tx.execute("DELETE FROM transcription_contents WHERE record_id = ?1", [id])?;
-        tx.execute("DELETE FROM transcription_records WHERE id = ?1", [id])?;
+        let mut deleted = 0;
+        deleted += tx.execute("DELETE FROM transcription_segments WHERE record_id = ?1", [id])?;
+        deleted += tx.execute("DELETE FROM transcription_contents WHERE record_id = ?1", [id])?;
+        deleted += tx.execute("DELETE FROM speaker_names WHERE record_id = ?1", [id])?;
+        deleted += tx.execute("DELETE FROM waveform_peaks WHERE record_id = ?1", [id])?;
+        deleted += tx.execute("DELETE FROM recording_sessions WHERE record_id = ?1", [id])?;
+        deleted += tx.execute("DELETE FROM diarization_summaries WHERE record_id = ?1", [id])?;
+        deleted += tx.execute("DELETE FROM transcription_records WHERE id = ?1", [id])?;
+        Self::add_deleted_rows(&tx, deleted as i64)?;
         
         tx.commit()?;
         Ok(())
     }
 
+    /// 查询与时间窗口 [start, end] 重叠的分段（边界相接也算重叠），按时间顺序返回
+    pub fn get_segments_in_range(&self, record_id: &str, start: f64, end: f64) -> Result<Vec<TranscriptionSegment>> {
+        if end < start {
+            return Ok(Vec::new());
+        }
+
+        let conn = self.reader()?;
+        let mut stmt = conn.prepare(
+            "SELECT * FROM transcription_segments
+             WHERE record_id = ?1 AND start_time <= ?3 AND end_time >= ?2
+             ORDER BY start_time, idx"
+        )?;
+        let segments = stmt.query_map(params![record_id, start, end], row_to_segment)?
+            .collect::<Result<Vec<_>>>()?;
+        Ok(segments)
+    }
+
+    /// 查询时间 t 所在的分段；t 落在段间空隙或录音首尾之外时返回时间上最近的段（距离相同取前一段）。
+    /// 两次按 (record_id, start_time) 索引定位，不扫描整条记录的分段
+    pub fn segment_at_time(&self, record_id: &str, t: f64) -> Result<Option<TranscriptionSegment>> {
+        let conn = self.reader()?;
+        let previous = conn.query_row(
+            "SELECT * FROM transcription_segments
+             WHERE record_id = ?1 AND start_time <= ?2
+             ORDER BY start_time DESC, idx DESC LIMIT 1",
+            params![record_id, t],
+            row_to_segment,
+        ).optional()?;
+        if let Some(segment) = previous.as_ref().filter(|segment| segment.end_time >= t) {
+            return Ok(Some(segment.clone()));
+        }
+
+        let next = conn.query_row(
+            "SELECT * FROM transcription_segments
+             WHERE record_id = ?1 AND start_time > ?2
+             ORDER BY start_time, idx LIMIT 1",
+            params![record_id, t],
+            row_to_segment,
+        ).optional()?;
+        Ok(match (previous, next) {
+            (Some(previous), Some(next)) => {
+                Some(if next.start_time - t < t - previous.end_time { next } else { previous })
+            }
+            (previous, next) => previous.or(next),
+        })
+    }
+
+    // ========== 删除后的自动清理 ==========
+
+    /// 累加删除行数计数（保存在 database_metadata 中）
+    fn add_deleted_rows(conn: &Connection, count: i64) -> Result<()> {
+        if count <= 0 {
+            return Ok(());
+        }
+        conn.execute(
+            "INSERT INTO database_metadata (key, value, updated_at) VALUES (?1, ?2, ?3)
+             ON CONFLICT(key) DO UPDATE SET value = CAST(value AS INTEGER) + excluded.value, updated_at = excluded.updated_at",
+            params![DELETED_ROWS_KEY, count.to_string(), Utc::now().to_rfc3339()],
+        )?;
+        Ok(())
+    }
+
+    pub fn deleted_rows_since_vacuum(&self) -> Result<i64> {
+        let conn = self.reader()?;
+        match conn.query_row(
+            "SELECT value FROM database_metadata WHERE key = ?1",
+            [DELETED_ROWS_KEY],
+            |row| row.get::<_, String>(0)
+        ) {
+            Ok(value) => Ok(value.parse().unwrap_or(0)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(0),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// 空闲时检查是否需要 VACUUM；有任务进行中（is_busy）或最近有写入时跳过。返回是否执行了清理
+    pub fn maybe_auto_vacuum(&self, is_busy: bool) -> Result<bool> {
+        self.maybe_auto_vacuum_with(is_busy, AUTO_VACUUM_THRESHOLD, AUTO_VACUUM_MIN_IDLE, |conn| conn.execute_batch("VACUUM"))
+    }
+
+    fn maybe_auto_vacuum_with<F>(&self, is_busy: bool, threshold: i64, min_idle: Duration, vacuum: F) -> Result<bool>
+    where
+        F: FnOnce(&Connection) -> Result<()>,
+    {
+        if is_busy || self.idle_for() < min_idle || self.deleted_rows_since_vacuum()? < threshold {
+            return Ok(false);
+        }
+
+        let conn = self.writer();
+        vacuum(&conn)?;
+        conn.execute(
+            "INSERT OR REPLACE INTO database_metadata (key, value, updated_at) VALUES (?1, '0', ?2)",
+            params![DELETED_ROWS_KEY, Utc::now().to_rfc3339()],
+        )?;
+        log::info!("✓ 删除数据较多，已自动执行数据库真空操作");
+        Ok(true)
+    }
+
     pub fn toggle_star(&self, id: &str) -> Result<bool> {
-        let current_star: bool = self.conn.query_row(
+        let conn = self.writer();
+        let current_star: bool = conn.query_row(
             "SELECT is_starred FROM transcription_records WHERE id = ?1",
             [id],
             |row| row.get(0)
         )?;
 
         let new_star = !current_star;
-        self.conn.execute(
+        conn.execute(
             "UPDATE transcription_records SET is_starred = ?1, updated_at = ?2 WHERE id = ?3",
             params![new_star, Utc::now().to_rfc3339(), id],
         )?;
@@ -245,24 +621,327 @@ impl StorageService {
     }
 
     pub fn update_record_name(&self, id: &str, name: &str) -> Result<()> {
-        self.conn.execute(
+        let conn = self.writer();
+        conn.execute(
             "UPDATE transcription_records SET name = ?1, updated_at = ?2 WHERE id = ?3",
             params![name, Utc::now().to_rfc3339(), id],
         )?;
         Ok(())
     }
 
-    fn row_to_record(&self, row: &rusqlite::Row) -> rusqlite::Result<TranscriptionRecord> {
+    // ========== 标签管理 ==========
+
+    /// 列出所有标签及使用次数，按使用频率降序（用于自动补全）
+    pub fn list_all_tags(&self) -> Result<Vec<TagUsage>> {
+        let conn = self.reader()?;
+        let mut stmt = conn.prepare(
+            "SELECT tag.value, COUNT(*) AS usage
+             FROM transcription_records r, json_each(r.tags) AS tag
+             WHERE TRIM(tag.value) != ''
+             GROUP BY tag.value
+             ORDER BY usage DESC, tag.value ASC"
+        )?;
+        let tags = stmt.query_map([], |row| Ok(TagUsage {
+            tag: row.get(0)?,
+            count: row.get(1)?,
+        }))?;
+        tags.collect()
+    }
+
+    /// 重命名标签并同步到所有记录；新标签已存在时合并去重。返回受影响的记录数
+    pub fn rename_tag(&self, old_tag: &str, new_tag: &str) -> Result<usize> {
+        let new_tag = new_tag.trim();
+        if new_tag.is_empty() || old_tag == new_tag {
+            return Ok(0);
+        }
+
+        let conn = self.writer();
+        let tx = conn.unchecked_transaction()?;
+        let affected: Vec<(String, String)> = {
+            let mut stmt = tx.prepare(
+                "SELECT id, tags FROM transcription_records
+                 WHERE EXISTS (SELECT 1 FROM json_each(transcription_records.tags) WHERE value = ?1)"
+            )?;
+            let rows = stmt.query_map([old_tag], |row| Ok((row.get(0)?, row.get(1)?)))?;
+            rows.collect::<Result<Vec<_>>>()?
+        };
+
+        for (id, tags_json) in &affected {
+            let tags: Vec<String> = serde_json::from_str(tags_json).unwrap_or_default();
+            let mut renamed: Vec<String> = Vec::with_capacity(tags.len());
+            for tag in tags {
+                let tag = if tag == old_tag { new_tag.to_string() } else { tag };
+                if !renamed.contains(&tag) {
+                    renamed.push(tag);
+                }
+            }
+            tx.execute(
+                "UPDATE transcription_records SET tags = ?1, updated_at = ?2 WHERE id = ?3",
+                params![serde_json::to_string(&renamed).unwrap_or_default(), Utc::now().to_rfc3339(), id],
+            )?;
+        }
+
+        tx.commit()?;
+        Ok(affected.len())
+    }
+
+    // ========== 分类管理 ==========
+
+    /// 列出所有分类及记录数
+    pub fn list_categories(&self) -> Result<Vec<CategoryUsage>> {
+        let conn = self.reader()?;
+        let mut stmt = conn.prepare(
+            "SELECT category, COUNT(*) AS usage FROM transcription_records
+             WHERE category IS NOT NULL AND TRIM(category) != ''
+             GROUP BY category
+             ORDER BY usage DESC, category ASC"
+        )?;
+        let categories = stmt.query_map([], |row| Ok(CategoryUsage {
+            name: row.get(0)?,
+            count: row.get(1)?,
+        }))?;
+        categories.collect()
+    }
+
+    /// 重命名分类，返回受影响的记录数；new_name 由调用方校验非空
+    pub fn rename_category(&self, old_name: &str, new_name: &str) -> Result<usize> {
+        self.reassign_category(old_name, Some(new_name.trim()))
+    }
+
+    /// 删除分类，其记录移动到 reassign_to（为 None 时清空分类）。返回受影响的记录数
+    pub fn delete_category(&self, name: &str, reassign_to: Option<&str>) -> Result<usize> {
+        let target = reassign_to.map(|t| t.trim()).filter(|t| !t.is_empty());
+        self.reassign_category(name, target)
+    }
+
+    fn reassign_category(&self, from: &str, to: Option<&str>) -> Result<usize> {
+        let conn = self.writer();
+        let tx = conn.unchecked_transaction()?;
+        let affected = tx.execute(
+            "UPDATE transcription_records SET category = ?1, updated_at = ?2 WHERE category = ?3",
+            params![to, Utc::now().to_rfc3339(), from],
+        )?;
+        tx.commit()?;
+        Ok(affected)
+    }
+
+    // ========== 说话人名称 ==========
+
+    /// 为记录中的说话人设置显示名称，空名称表示恢复默认
+    pub fn set_speaker_name(&self, record_id: &str, speaker_id: &str, display_name: &str) -> Result<()> {
+        let conn = self.writer();
+        if display_name.trim().is_empty() {
+            conn.execute(
+                "DELETE FROM speaker_names WHERE record_id = ?1 AND speaker_id = ?2",
+                params![record_id, speaker_id],
+            )?;
+        } else {
+            conn.execute(
+                "INSERT OR REPLACE INTO speaker_names (record_id, speaker_id, display_name) VALUES (?1, ?2, ?3)",
+                params![record_id, speaker_id, display_name.trim()],
+            )?;
+        }
+        Ok(())
+    }
+
+    pub fn get_speaker_names(&self, record_id: &str) -> Result<HashMap<String, String>> {
+        let conn = self.reader()?;
+        let mut stmt = conn.prepare(
+            "SELECT speaker_id, display_name FROM speaker_names WHERE record_id = ?1"
+        )?;
+        let rows = stmt.query_map([record_id], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?;
+        rows.collect()
+    }
+
+    // ========== 波形峰值缓存 ==========
+
+    /// 读取缓存的波形峰值；按 buckets 分别缓存，未计算过或音频文件已变化时返回 None
+    pub fn get_waveform_peaks(&self, record_id: &str, buckets: usize, source: AudioStamp) -> Result<Option<Vec<PeakPair>>> {
+        let conn = self.reader()?;
+        let blob: Option<Vec<u8>> = conn.query_row(
+            "SELECT peaks FROM waveform_peaks
+             WHERE record_id = ?1 AND buckets = ?2 AND source_size = ?3 AND source_modified = ?4",
+            params![record_id, buckets as i64, source.size, source.modified],
+            |row| row.get(0),
+        ).optional()?;
+        Ok(blob.map(|blob| waveform::decode_peaks(&blob)))
+    }
+
+    pub fn save_waveform_peaks(&self, record_id: &str, source: AudioStamp, peaks: &[PeakPair]) -> Result<()> {
+        let conn = self.writer();
+        conn.execute(
+            "INSERT OR REPLACE INTO waveform_peaks (record_id, buckets, source_size, source_modified, peaks)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![record_id, peaks.len() as i64, source.size, source.modified, waveform::encode_peaks(peaks)],
+        )?;
+        Ok(())
+    }
+
+    // ========== 录音会话 ==========
+
+    /// 把记录登记为录音会话中的第 part 段（超过单段时长上限后自动拆分的录音）
+    pub fn link_session_part(&self, session_id: &str, record_id: &str, part: u32) -> Result<()> {
+        let conn = self.writer();
+        conn.execute(
+            "INSERT OR REPLACE INTO recording_sessions (record_id, session_id, part) VALUES (?1, ?2, ?3)",
+            params![record_id, session_id, part],
+        )?;
+        Ok(())
+    }
+
+    /// 与该记录属于同一录音会话的全部记录ID，按分段顺序返回；不属于任何会话时返回空
+    pub fn get_session_records(&self, record_id: &str) -> Result<Vec<String>> {
+        let conn = self.reader()?;
+        let mut stmt = conn.prepare(
+            "SELECT record_id FROM recording_sessions
+             WHERE session_id = (SELECT session_id FROM recording_sessions WHERE record_id = ?1)
+             ORDER BY part"
+        )?;
+        let rows = stmt.query_map([record_id], |row| row.get(0))?;
+        rows.collect()
+    }
+
+    // ========== 说话人分离汇总 ==========
+
+    pub fn save_diarization_summary(&self, record_id: &str, summary: &DiarizationSummary) -> Result<()> {
+        let conn = self.writer();
+        conn.execute(
+            "INSERT OR REPLACE INTO diarization_summaries (record_id, summary) VALUES (?1, ?2)",
+            params![record_id, serde_json::to_string(summary).unwrap_or_default()],
+        )?;
+        Ok(())
+    }
+
+    /// 清理没有对应记录的汇总（录音停止后未保存记录时留下）；启动时没有进行中的录音，可以安全删除
+    fn prune_orphan_diarization_summaries(&self) -> Result<()> {
+        let conn = self.writer();
+        conn.execute(
+            "DELETE FROM diarization_summaries WHERE record_id NOT IN (SELECT id FROM transcription_records)",
+            [],
+        )?;
+        Ok(())
+    }
+
+    pub fn get_diarization_summary(&self, record_id: &str) -> Result<Option<DiarizationSummary>> {
+        let conn = self.reader()?;
+        let summary: Option<String> = conn.query_row(
+            "SELECT summary FROM diarization_summaries WHERE record_id = ?1",
+            [record_id],
+            |row| row.get(0),
+        ).optional()?;
+        Ok(summary.and_then(|summary| serde_json::from_str(&summary).ok()))
+    }
+
+    // ========== 应用设置 ==========
+
+    pub fn get_setting(&self, key: &str) -> Result<Option<String>> {
+        Self::read_setting(&self.reader()?, key)
+    }
+
+    pub fn set_setting(&self, key: &str, value: &str) -> Result<()> {
+        Self::write_setting(&self.writer(), key, value)
+    }
+
+    fn read_setting(conn: &Connection, key: &str) -> Result<Option<String>> {
+        match conn.query_row(
+            "SELECT value FROM app_settings WHERE key = ?1",
+            [key],
+            |row| row.get::<_, String>(0)
+        ) {
+            Ok(value) => Ok(Some(value)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn write_setting(conn: &Connection, key: &str, value: &str) -> Result<()> {
+        conn.execute(
+            "INSERT OR REPLACE INTO app_settings (key, value, updated_at) VALUES (?1, ?2, ?3)",
+            params![key, value, Utc::now().to_rfc3339()],
+        )?;
+        Ok(())
+    }
+
+    /// 读取上次使用的配置；不存在或格式已过时则返回空配置
+    pub fn get_last_used_config(&self) -> Result<LastUsedConfig> {
+        Ok(self.get_setting(LAST_USED_CONFIG_KEY)?
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default())
+    }
+
+    pub fn set_last_used_config(&self, config: &LastUsedConfig) -> Result<()> {
+        let json = serde_json::to_string(config)
+            .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+        self.set_setting(LAST_USED_CONFIG_KEY, &json)
+    }
+
+    fn input_gains(conn: &Connection) -> Result<HashMap<String, f32>> {
+        Ok(Self::read_setting(conn, INPUT_GAINS_KEY)?
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default())
+    }
+
+    /// 读取输入设备保存的增益倍数
+    pub fn get_input_gain(&self, device_id: &str) -> Result<Option<f32>> {
+        Ok(Self::input_gains(&self.reader()?)?.get(device_id).copied())
+    }
+
+    pub fn set_input_gain(&self, device_id: &str, gain: f32) -> Result<()> {
+        // 读取与写回在同一个写连接上完成，避免并发设置不同设备时互相覆盖
+        let conn = self.writer();
+        let mut gains = Self::input_gains(&conn)?;
+        gains.insert(device_id.to_string(), gain);
+        let json = serde_json::to_string(&gains)
+            .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+        Self::write_setting(&conn, INPUT_GAINS_KEY, &json)
+    }
+
+    pub fn get_library_stats(&self) -> Result<LibraryStats> {
+        let conn = self.reader()?;
+        let mut stats = conn.query_row(
+            "SELECT COUNT(*),
+                    COALESCE(SUM(CASE WHEN status = 'completed' THEN 1 ELSE 0 END), 0),
+                    COALESCE(SUM(CASE WHEN is_starred THEN 1 ELSE 0 END), 0),
+                    COALESCE(SUM(duration), 0),
+                    COALESCE(SUM(processing_time), 0),
+                    SUM(accuracy * COALESCE(duration, 1)) / SUM(CASE WHEN accuracy IS NOT NULL THEN COALESCE(duration, 1) END),
+                    AVG(realtime_factor)
+             FROM transcription_records",
+            [],
+            |row| Ok(LibraryStats {
+                total_records: row.get(0)?,
+                completed_records: row.get(1)?,
+                starred_records: row.get(2)?,
+                total_duration: row.get(3)?,
+                total_processing_time: row.get(4)?,
+                average_accuracy: row.get(5)?,
+                average_realtime_factor: row.get(6)?,
+                realtime_factor_by_model: Vec::new(),
+            }),
+        )?;
+
+        let mut stmt = conn.prepare(
+            "SELECT model_name, COUNT(*), AVG(realtime_factor)
+             FROM transcription_records
+             WHERE model_name IS NOT NULL AND realtime_factor IS NOT NULL
+             GROUP BY model_name
+             ORDER BY model_name"
+        )?;
+        let rows = stmt.query_map([], |row| Ok(ModelSpeed {
+            model_name: row.get(0)?,
+            record_count: row.get(1)?,
+            average_realtime_factor: row.get(2)?,
+        }))?;
+        stats.realtime_factor_by_model = rows.collect::<Result<Vec<_>>>()?;
+        Ok(stats)
+    }
+
+    fn row_to_record(conn: &Connection, row: &rusqlite::Row) -> rusqlite::Result<TranscriptionRecord> {
         let tags_json: String = row.get("tags")?;
         let tags: Vec<String> = serde_json::from_str(&tags_json).unwrap_or_default();
 
         let config_json: String = row.get("config")?;
-        let config: TranscriptionConfig = serde_json::from_str(&config_json)
-            .unwrap_or(TranscriptionConfig {
-                language: "auto".to_string(),
-                mode: "normal".to_string(),
-                audio_enhancement: false,
-            });
+        let config: TranscriptionConfig = serde_json::from_str(&config_json).unwrap_or_default();
 
         let created_at_str: String = row.get("created_at")?;
         let updated_at_str: String = row.get("updated_at")?;
@@ -278,8 +957,13 @@ impl StorageService {
         let result = match (row.get::<_, Option<String>>("full_text")?, 
                            row.get::<_, Option<f64>>("processing_time")?) {
             (Some(text), Some(processing_time)) => {
-                let segments: Option<Vec<TranscriptionSegment>> = row.get::<_, Option<String>>("segments")?
-                    .and_then(|s| serde_json::from_str(&s).ok());
+                // 优先读取分段表；尚未迁移的旧数据回退到 JSON 字段
+                let record_id: String = row.get("id")?;
+                let segments = match read_segments(conn, &record_id)? {
+                    Some(segments) => Some(segments),
+                    None => row.get::<_, Option<String>>("segments")?
+                        .and_then(|s| serde_json::from_str(&s).ok()),
+                };
 
                 Some(TranscriptionResult {
                     text,
@@ -308,6 +992,10 @@ impl StorageService {
             is_starred: row.get("is_starred")?,
             config,
             result,
+            model_name: row.get("model_name")?,
+            realtime_factor: row.get("realtime_factor")?,
+            sources: row.get::<_, Option<String>>("sources")?
+                .and_then(|s| serde_json::from_str(&s).ok()),
         })
     }
 
@@ -316,7 +1004,8 @@ impl StorageService {
     /// 初始化内置提示词
     fn init_built_in_prompts(&self) -> Result<()> {
         // 检查是否已经有新的内置提示词，如果有就跳过初始化
-        let count: Result<i32, _> = self.conn.query_row(
+        let conn = self.writer();
+        let count: Result<i32, _> = conn.query_row(
             "SELECT COUNT(*) FROM prompt_templates WHERE is_built_in = 1 AND id LIKE 'builtin_%'",
             [],
             |row| row.get::<_, i32>(0)
@@ -330,7 +1019,7 @@ impl StorageService {
         }
         
         // 删除所有旧的内置提示词，为新的让路
-        self.conn.execute(
+        conn.execute(
             "DELETE FROM prompt_templates WHERE is_built_in = 1",
             [],
         )?;
@@ -593,7 +1282,8 @@ impl StorageService {
             },
         ];
 
-        // 插入新的内置提示词
+        // 插入新的内置提示词（save_prompt_template 会再次获取写连接）
+        drop(conn);
         for prompt in built_in_prompts {
             self.save_prompt_template(&prompt)?;
         }
@@ -604,11 +1294,13 @@ impl StorageService {
 
     /// 保存提示词模板
     pub fn save_prompt_template(&self, prompt: &PromptTemplate) -> Result<()> {
-        self.conn.execute(
+        let conn = self.writer();
+        conn.execute(
             "INSERT OR REPLACE INTO prompt_templates (
                 id, name, content, category, language, is_built_in, description,
-                tags, created_at, updated_at, usage_count, is_active
-            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+                tags, created_at, updated_at, usage_count, is_active, last_used_at
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12,
+                (SELECT last_used_at FROM prompt_templates WHERE id = ?1))",
             params![
                 prompt.id,
                 prompt.name,
@@ -629,7 +1321,8 @@ impl StorageService {
 
     /// 获取所有提示词模板
     pub fn get_prompt_templates(&self) -> Result<Vec<PromptTemplate>> {
-        let mut stmt = self.conn.prepare(
+        let conn = self.reader()?;
+        let mut stmt = conn.prepare(
             "SELECT * FROM prompt_templates ORDER BY is_built_in DESC, usage_count DESC, created_at DESC"
         )?;
 
@@ -662,7 +1355,8 @@ impl StorageService {
 
         query.push_str(" ORDER BY is_built_in DESC, created_at DESC");
 
-        let mut stmt = self.conn.prepare(&query)?;
+        let conn = self.reader()?;
+        let mut stmt = conn.prepare(&query)?;
         let prompt_iter = stmt.query_map(rusqlite::params_from_iter(params.iter()), |row| {
             self.row_to_prompt_template(row)
         })?;
@@ -677,7 +1371,8 @@ impl StorageService {
 
     /// 获取单个提示词模板
     pub fn get_prompt_template(&self, id: &str) -> Result<Option<PromptTemplate>> {
-        let mut stmt = self.conn.prepare(
+        let conn = self.reader()?;
+        let mut stmt = conn.prepare(
             "SELECT * FROM prompt_templates WHERE id = ?1"
         )?;
 
@@ -694,26 +1389,53 @@ impl StorageService {
 
     /// 删除提示词模板（仅限自定义）
     pub fn delete_prompt_template(&self, id: &str) -> Result<()> {
-        self.conn.execute(
+        let conn = self.writer();
+        conn.execute(
             "DELETE FROM prompt_templates WHERE id = ?1 AND is_built_in = 0",
             [id],
         )?;
         Ok(())
     }
 
-    /// 更新提示词使用次数
+    /// 更新提示词使用次数和最近使用时间
     pub fn increment_prompt_usage(&self, id: &str) -> Result<()> {
-        self.conn.execute(
-            "UPDATE prompt_templates SET usage_count = usage_count + 1, updated_at = ?1 WHERE id = ?2",
-            params![Utc::now().to_rfc3339(), id],
+        let now = Utc::now().to_rfc3339();
+        let conn = self.writer();
+        conn.execute(
+            "UPDATE prompt_templates SET usage_count = usage_count + 1, updated_at = ?1, last_used_at = ?1 WHERE id = ?2",
+            params![now, id],
         )?;
         Ok(())
     }
 
+    /// 提示词使用统计：按使用次数降序，次数相同时最近使用的靠前
+    pub fn get_prompt_usage_stats(&self) -> Result<Vec<PromptUsageStats>> {
+        let conn = self.reader()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, name, category, usage_count, last_used_at FROM prompt_templates
+             WHERE is_active = 1
+             ORDER BY usage_count DESC, last_used_at IS NULL, last_used_at DESC, name ASC"
+        )?;
+        let stats = stmt.query_map([], |row| {
+            let last_used_at: Option<String> = row.get(4)?;
+            Ok(PromptUsageStats {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                category: row.get(2)?,
+                usage_count: row.get(3)?,
+                last_used_at: last_used_at
+                    .and_then(|s| DateTime::parse_from_rfc3339(&s).ok())
+                    .map(|dt| dt.with_timezone(&Utc)),
+            })
+        })?;
+        stats.collect()
+    }
+
     /// 搜索提示词
     pub fn search_prompt_templates(&self, query: &str) -> Result<Vec<PromptTemplate>> {
         let search_pattern = format!("%{}%", query.to_lowercase());
-        let mut stmt = self.conn.prepare(
+        let conn = self.reader()?;
+        let mut stmt = conn.prepare(
             "SELECT * FROM prompt_templates 
              WHERE is_active = 1 AND (
                  LOWER(name) LIKE ?1 OR 
@@ -766,4 +1488,512 @@ impl StorageService {
             is_active: row.get("is_active")?,
         })
     }
-}
\ No newline at end of file
+}
+/// 写入记录的分段（覆盖原有分段）
+pub(crate) fn write_segments(conn: &Connection, record_id: &str, segments: &[TranscriptionSegment]) -> Result<()> {
+    conn.execute("DELETE FROM transcription_segments WHERE record_id = ?1", [record_id])?;
+    insert_segments(&mut conn.prepare_cached(INSERT_SEGMENT_SQL)?, record_id, segments)
+}
+
+pub(crate) const INSERT_SEGMENT_SQL: &str = "INSERT INTO transcription_segments (
+        record_id, idx, segment_id, start_time, end_time, text, speaker, confidence, no_speech_prob, romanization
+    ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)";
+
+/// 用已准备好的 INSERT_SEGMENT_SQL 语句追加写入分段，批量写入多条记录时共用同一条语句
+pub(crate) fn insert_segments(stmt: &mut rusqlite::Statement, record_id: &str, segments: &[TranscriptionSegment]) -> Result<()> {
+    for (idx, segment) in segments.iter().enumerate() {
+        stmt.execute(params![
+            record_id,
+            idx as i64,
+            segment.id,
+            segment.start_time,
+            segment.end_time,
+            segment.text,
+            segment.speaker,
+            segment.confidence,
+            segment.no_speech_prob,
+            segment.romanization,
+        ])?;
+    }
+    Ok(())
+}
+
+fn row_to_segment(row: &rusqlite::Row) -> Result<TranscriptionSegment> {
+    Ok(TranscriptionSegment {
+        id: row.get("segment_id")?,
+        start_time: row.get("start_time")?,
+        end_time: row.get("end_time")?,
+        text: row.get("text")?,
+        speaker: row.get("speaker")?,
+        confidence: row.get("confidence")?,
+        no_speech_prob: row.get("no_speech_prob")?,
+        romanization: row.get("romanization")?,
+    })
+}
+
+/// 读取记录的分段，按原顺序返回；没有分段时返回 None
+pub(crate) fn read_segments(conn: &Connection, record_id: &str) -> Result<Option<Vec<TranscriptionSegment>>> {
+    let mut stmt = conn.prepare(
+        "SELECT * FROM transcription_segments WHERE record_id = ?1 ORDER BY idx"
+    )?;
+    let segments = stmt.query_map([record_id], row_to_segment)?
+        .collect::<Result<Vec<_>>>()?;
+    Ok(if segments.is_empty() { None } else { Some(segments) })
+}
+
+/// 测试辅助：临时目录中的存储服务与示例记录
+#[cfg(test)]
+pub(crate) mod test_support {
+    use super::*;
+    use std::path::PathBuf;
+
+    pub fn temp_storage(name: &str) -> (StorageService, PathBuf) {
+        let dir = std::env::temp_dir().join(format!("steno_storage_test_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let db_manager = DatabaseManager::with_paths(dir.join("steno.db"), dir.join("backups")).unwrap();
+        (StorageService::with_manager(&db_manager).unwrap(), dir)
+    }
+
+    pub fn sample_record(id: &str) -> TranscriptionRecord {
+        TranscriptionRecord {
+            id: id.to_string(),
+            name: format!("记录 {}", id),
+            original_file_name: format!("{}.wav", id),
+            file_path: format!("/tmp/{}.wav", id),
+            file_size: 1024,
+            duration: Some(60.0),
+            status: "pending".to_string(),
+            progress: 0.0,
+            error_message: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            tags: Vec::new(),
+            category: None,
+            is_starred: false,
+            config: TranscriptionConfig {
+                language: "zh".to_string(),
+                mode: "standard".to_string(),
+                audio_enhancement: false,
+                decoding: None,
+                temperature_fallback: TemperatureFallback::default(),
+                segmentation: SegmentationConfig::default(),
+                suppression: SuppressionConfig::default(),
+                romanization: false,
+            },
+            result: None,
+            model_name: None,
+            realtime_factor: None,
+            sources: None,
+        }
+    }
+
+    /// 没有说话人与置信度的分段，需要时用结构体更新语法覆盖
+    pub fn sample_segment(id: &str, start_time: f64, end_time: f64, text: &str) -> TranscriptionSegment {
+        TranscriptionSegment {
+            id: id.to_string(),
+            start_time,
+            end_time,
+            text: text.to_string(),
+            speaker: None,
+            confidence: None,
+            no_speech_prob: None,
+            romanization: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::test_support::{sample_record, sample_segment, temp_storage};
+    use std::cell::Cell;
+
+    #[test]
+    fn test_auto_vacuum_after_threshold() {
+        let (storage, dir) = temp_storage("auto_vacuum");
+        for i in 0..4 {
+            storage.save_record(&sample_record(&format!("record_{}", i))).unwrap();
+        }
+
+        let vacuum_calls = Cell::new(0);
+        let mock_vacuum = |_: &Connection| {
+            vacuum_calls.set(vacuum_calls.get() + 1);
+            Ok(())
+        };
+
+        storage.delete_record("record_0").unwrap();
+        assert_eq!(storage.deleted_rows_since_vacuum().unwrap(), 1);
+        assert!(!storage.maybe_auto_vacuum_with(false, 3, Duration::ZERO, mock_vacuum).unwrap());
+
+        storage.delete_record("record_1").unwrap();
+        storage.delete_record("record_2").unwrap();
+        assert_eq!(storage.deleted_rows_since_vacuum().unwrap(), 3);
+
+        // 转录进行中不执行
+        assert!(!storage.maybe_auto_vacuum_with(true, 3, Duration::ZERO, mock_vacuum).unwrap());
+        // 刚有过写入（删除本身也是写入）时不执行
+        assert!(!storage.maybe_auto_vacuum_with(false, 3, Duration::from_secs(600), mock_vacuum).unwrap());
+        assert_eq!(vacuum_calls.get(), 0);
+
+        std::thread::sleep(Duration::from_millis(20));
+        assert!(storage.maybe_auto_vacuum_with(false, 3, Duration::from_millis(10), mock_vacuum).unwrap());
+        assert_eq!(vacuum_calls.get(), 1);
+        assert_eq!(storage.deleted_rows_since_vacuum().unwrap(), 0);
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    fn segments_record(id: &str) -> TranscriptionRecord {
+        let mut record = sample_record(id);
+        let segments = [(0.0, 2.0), (2.0, 5.0), (5.0, 9.0), (12.0, 15.0)].iter().enumerate()
+            .map(|(i, &(start_time, end_time))| sample_segment(&format!("seg_{}", i), start_time, end_time, &format!("第{}段", i)))
+            .collect();
+        record.result = Some(TranscriptionResult {
+            text: "全文".to_string(),
+            processing_time: 1.0,
+            accuracy: None,
+            segments: Some(segments),
+        });
+        record
+    }
+
+    fn ids(segments: &[TranscriptionSegment]) -> Vec<&str> {
+        segments.iter().map(|s| s.id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_segments_in_range_inclusive_boundaries() {
+        let (storage, dir) = temp_storage("segments_range");
+        storage.save_record(&segments_record("record_1")).unwrap();
+
+        // 跨越起点的段也要返回
+        assert_eq!(ids(&storage.get_segments_in_range("record_1", 3.0, 6.0).unwrap()), vec!["seg_1", "seg_2"]);
+        // 边界相接视为重叠
+        assert_eq!(ids(&storage.get_segments_in_range("record_1", 5.0, 5.0).unwrap()), vec!["seg_1", "seg_2"]);
+        assert_eq!(ids(&storage.get_segments_in_range("record_1", 9.0, 12.0).unwrap()), vec!["seg_2", "seg_3"]);
+        assert_eq!(ids(&storage.get_segments_in_range("record_1", 0.0, 100.0).unwrap()).len(), 4);
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_segment_at_time() {
+        let (storage, dir) = temp_storage("segment_at_time");
+        storage.save_record(&segments_record("record_1")).unwrap();
+        let at = |t: f64| storage.segment_at_time("record_1", t).unwrap().map(|s| s.id);
+
+        // 命中段内
+        assert_eq!(at(1.0).as_deref(), Some("seg_0"));
+        assert_eq!(at(7.5).as_deref(), Some("seg_2"));
+        // 边界：相接处属于后一段，首尾端点属于所在段
+        assert_eq!(at(2.0).as_deref(), Some("seg_1"));
+        assert_eq!(at(0.0).as_deref(), Some("seg_0"));
+        assert_eq!(at(15.0).as_deref(), Some("seg_3"));
+        // 空隙中取最近的段，距离相同取前一段
+        assert_eq!(at(9.8).as_deref(), Some("seg_2"));
+        assert_eq!(at(11.5).as_deref(), Some("seg_3"));
+        assert_eq!(at(10.5).as_deref(), Some("seg_2"));
+        // 录音首尾之外
+        assert_eq!(at(-1.0).as_deref(), Some("seg_0"));
+        assert_eq!(at(30.0).as_deref(), Some("seg_3"));
+        assert_eq!(storage.segment_at_time("missing", 1.0).unwrap(), None);
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    fn tagged_record(id: &str, tags: &[&str]) -> TranscriptionRecord {
+        let mut record = sample_record(id);
+        record.tags = tags.iter().map(|t| t.to_string()).collect();
+        record
+    }
+
+    #[test]
+    fn test_tag_frequency_counts() {
+        let (storage, dir) = temp_storage("tag_counts");
+        storage.save_record(&tagged_record("r1", &["会议", "产品"])).unwrap();
+        storage.save_record(&tagged_record("r2", &["会议"])).unwrap();
+        storage.save_record(&tagged_record("r3", &["会议", "访谈", "产品"])).unwrap();
+        storage.save_record(&tagged_record("r4", &[])).unwrap();
+
+        let tags = storage.list_all_tags().unwrap();
+        let counts: Vec<(&str, i64)> = tags.iter().map(|t| (t.tag.as_str(), t.count)).collect();
+        assert_eq!(counts, vec![("会议", 3), ("产品", 2), ("访谈", 1)]);
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_rename_tag_propagates() {
+        let (storage, dir) = temp_storage("tag_rename");
+        storage.save_record(&tagged_record("r1", &["meeting", "产品"])).unwrap();
+        storage.save_record(&tagged_record("r2", &["Meeting", "meeting"])).unwrap();
+        storage.save_record(&tagged_record("r3", &["访谈"])).unwrap();
+
+        assert_eq!(storage.rename_tag("meeting", "Meeting").unwrap(), 2);
+        assert_eq!(storage.get_record("r1").unwrap().unwrap().tags, vec!["Meeting", "产品"]);
+        // 与已有标签合并，不产生重复
+        assert_eq!(storage.get_record("r2").unwrap().unwrap().tags, vec!["Meeting"]);
+        assert_eq!(storage.get_record("r3").unwrap().unwrap().tags, vec!["访谈"]);
+        assert!(storage.list_all_tags().unwrap().iter().all(|t| t.tag != "meeting"));
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    fn categorized_record(id: &str, category: Option<&str>) -> TranscriptionRecord {
+        let mut record = sample_record(id);
+        record.category = category.map(|c| c.to_string());
+        record
+    }
+
+    #[test]
+    fn test_rename_category_propagates() {
+        let (storage, dir) = temp_storage("category_rename");
+        storage.save_record(&categorized_record("r1", Some("会议"))).unwrap();
+        storage.save_record(&categorized_record("r2", Some("会议"))).unwrap();
+        storage.save_record(&categorized_record("r3", Some("访谈"))).unwrap();
+        storage.save_record(&categorized_record("r4", None)).unwrap();
+
+        assert_eq!(storage.rename_category("会议", "周会").unwrap(), 2);
+        assert_eq!(storage.get_record("r1").unwrap().unwrap().category.as_deref(), Some("周会"));
+        assert_eq!(
+            storage.list_categories().unwrap(),
+            vec![
+                CategoryUsage { name: "周会".to_string(), count: 2 },
+                CategoryUsage { name: "访谈".to_string(), count: 1 },
+            ]
+        );
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_delete_category_reassigns_records() {
+        let (storage, dir) = temp_storage("category_delete");
+        storage.save_record(&categorized_record("r1", Some("会议"))).unwrap();
+        storage.save_record(&categorized_record("r2", Some("访谈"))).unwrap();
+        storage.save_record(&categorized_record("r3", Some("临时"))).unwrap();
+
+        assert_eq!(storage.delete_category("会议", Some("访谈")).unwrap(), 1);
+        assert_eq!(storage.get_record("r1").unwrap().unwrap().category.as_deref(), Some("访谈"));
+
+        assert_eq!(storage.delete_category("临时", None).unwrap(), 1);
+        assert_eq!(storage.get_record("r3").unwrap().unwrap().category, None);
+        assert_eq!(
+            storage.list_categories().unwrap(),
+            vec![CategoryUsage { name: "访谈".to_string(), count: 2 }]
+        );
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    fn custom_prompt(id: &str) -> PromptTemplate {
+        PromptTemplate {
+            id: id.to_string(),
+            name: format!("模板 {}", id),
+            content: "会议记录".to_string(),
+            category: "custom".to_string(),
+            language: "zh".to_string(),
+            is_built_in: false,
+            description: None,
+            tags: Vec::new(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            usage_count: 0,
+            is_active: true,
+        }
+    }
+
+    #[test]
+    fn test_prompt_usage_updates_count_and_timestamp() {
+        let (storage, dir) = temp_storage("prompt_usage");
+        storage.save_prompt_template(&custom_prompt("p_used")).unwrap();
+        storage.save_prompt_template(&custom_prompt("p_popular")).unwrap();
+
+        let before = Utc::now();
+        storage.increment_prompt_usage("p_used").unwrap();
+        for _ in 0..3 {
+            storage.increment_prompt_usage("p_popular").unwrap();
+        }
+
+        let stats = storage.get_prompt_usage_stats().unwrap();
+        let popular_index = stats.iter().position(|s| s.id == "p_popular").unwrap();
+        let used_index = stats.iter().position(|s| s.id == "p_used").unwrap();
+        assert!(popular_index < used_index);
+
+        let popular = &stats[popular_index];
+        assert_eq!(popular.usage_count, 3);
+        assert!(popular.last_used_at.unwrap() >= before - chrono::Duration::seconds(1));
+        assert_eq!(stats[used_index].usage_count, 1);
+
+        // 重新保存模板不会清除最近使用时间
+        storage.save_prompt_template(&custom_prompt("p_used")).unwrap();
+        let stats = storage.get_prompt_usage_stats().unwrap();
+        assert!(stats.iter().find(|s| s.id == "p_used").unwrap().last_used_at.is_some());
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_segments_in_empty_range() {
+        let (storage, dir) = temp_storage("segments_empty");
+        storage.save_record(&segments_record("record_1")).unwrap();
+
+        assert!(storage.get_segments_in_range("record_1", 9.5, 11.5).unwrap().is_empty());
+        assert!(storage.get_segments_in_range("record_1", 6.0, 3.0).unwrap().is_empty());
+        assert!(storage.get_segments_in_range("missing", 0.0, 100.0).unwrap().is_empty());
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_realtime_factor_by_model() {
+        let (storage, dir) = temp_storage("realtime_factor");
+        let runs = [("r1", "ggml-base", 8.0), ("r2", "ggml-base", 4.0), ("r3", "ggml-large-v3", 1.5)];
+        for (id, model, xrt) in runs {
+            storage.save_record(&sample_record(id)).unwrap();
+            storage.update_record_performance(id, Some(model), Some(xrt)).unwrap();
+        }
+        // 未记录速度的记录不参与统计
+        storage.save_record(&sample_record("r4")).unwrap();
+
+        assert_eq!(storage.get_record("r1").unwrap().unwrap().realtime_factor, Some(8.0));
+        let stats = storage.get_library_stats().unwrap();
+        assert_eq!(stats.realtime_factor_by_model, vec![
+            ModelSpeed { model_name: "ggml-base".to_string(), record_count: 2, average_realtime_factor: 6.0 },
+            ModelSpeed { model_name: "ggml-large-v3".to_string(), record_count: 1, average_realtime_factor: 1.5 },
+        ]);
+        assert!((stats.average_realtime_factor.unwrap() - 13.5 / 3.0).abs() < 1e-9);
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_last_used_config_round_trip() {
+        let (storage, dir) = temp_storage("last_config");
+        assert!(storage.get_last_used_config().unwrap().transcription.is_none());
+
+        let mut realtime = RealtimeConfig::default();
+        realtime.language = "en".to_string();
+        realtime.mode = "streaming".to_string();
+        let config = LastUsedConfig {
+            transcription: Some(TranscriptionConfig {
+                language: "zh".to_string(),
+                mode: "accurate".to_string(),
+                audio_enhancement: true,
+                decoding: Some(DecodingStrategy::Beam { beam_size: 5 }),
+                temperature_fallback: TemperatureFallback { temperature_inc: 0.1, max_fallback_attempts: 3 },
+                segmentation: SegmentationConfig { max_len: 80, split_on_word: true },
+                suppression: SuppressionConfig::default(),
+                romanization: true,
+            }),
+            realtime: Some(realtime),
+            prompt_template_id: Some("meeting".to_string()),
+        };
+        storage.set_last_used_config(&config).unwrap();
+
+        let loaded = storage.get_last_used_config().unwrap();
+        assert_eq!(loaded.prompt_template_id.as_deref(), Some("meeting"));
+        let realtime = loaded.realtime.as_ref().unwrap();
+        assert_eq!((realtime.language.as_str(), realtime.mode.as_str()), ("en", "streaming"));
+
+        // 前端传入的参数优先，缺省时沿用上次的值
+        let resolved = loaded.resolve_transcription(None, Some("fast".to_string()));
+        assert_eq!((resolved.language.as_str(), resolved.mode.as_str()), ("zh", "fast"));
+        assert!(resolved.audio_enhancement);
+        assert_eq!(resolved.decoding, Some(DecodingStrategy::Beam { beam_size: 5 }));
+        assert_eq!(resolved.temperature_fallback.max_fallback_attempts, 3);
+
+        // 无法解析的旧数据回退为空配置
+        storage.set_setting("last_used_config", "not json").unwrap();
+        assert!(storage.get_last_used_config().unwrap().realtime.is_none());
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_input_gain_per_device() {
+        let (storage, dir) = temp_storage("input_gain");
+        assert_eq!(storage.get_input_gain("input_0").unwrap(), None);
+
+        storage.set_input_gain("input_0", 2.5).unwrap();
+        storage.set_input_gain("default", 1.5).unwrap();
+        storage.set_input_gain("input_0", 3.0).unwrap();
+        assert_eq!(storage.get_input_gain("input_0").unwrap(), Some(3.0));
+        assert_eq!(storage.get_input_gain("default").unwrap(), Some(1.5));
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_diarization_summary_round_trip() {
+        use crate::realtime_speaker_diarization::{summarize_timeline, SpeakerTimeRange};
+
+        let (storage, dir) = temp_storage("diarization_summary");
+        let summary = summarize_timeline(&[
+            SpeakerTimeRange { speaker: "说话人A".to_string(), start_time: 0.0, end_time: 4.0 },
+            SpeakerTimeRange { speaker: "说话人B".to_string(), start_time: 4.0, end_time: 6.0 },
+        ]);
+        // 记录保存之前就可以写入
+        storage.save_diarization_summary("recording_1", &summary).unwrap();
+        assert_eq!(storage.get_diarization_summary("recording_1").unwrap(), Some(summary.clone()));
+        assert_eq!(storage.get_diarization_summary("recording_2").unwrap(), None);
+
+        storage.save_record(&sample_record("recording_1")).unwrap();
+        storage.delete_record("recording_1").unwrap();
+        assert_eq!(storage.get_diarization_summary("recording_1").unwrap(), None);
+
+        // 录音停止后没有保存记录的汇总在下次启动时清理
+        storage.save_record(&sample_record("recording_3")).unwrap();
+        storage.save_diarization_summary("recording_3", &summary).unwrap();
+        storage.save_diarization_summary("recording_4", &summary).unwrap();
+        drop(storage);
+        let db_manager = DatabaseManager::with_paths(dir.join("steno.db"), dir.join("backups")).unwrap();
+        let storage = StorageService::with_manager(&db_manager).unwrap();
+        assert_eq!(storage.get_diarization_summary("recording_3").unwrap(), Some(summary));
+        assert_eq!(storage.get_diarization_summary("recording_4").unwrap(), None);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_concurrent_reads_and_writes_stay_consistent() {
+        let (storage, dir) = temp_storage("concurrent");
+        storage.save_record(&sample_record("shared")).unwrap();
+
+        // 线程数多于读连接池上限，借不到连接的读操作需要等待归还而不是死锁
+        std::thread::scope(|scope| {
+            for worker in 0..MAX_READERS * 2 {
+                let storage = &storage;
+                scope.spawn(move || {
+                    for i in 0..10 {
+                        storage.save_record(&sample_record(&format!("worker_{}_{}", worker, i))).unwrap();
+                        storage.toggle_star("shared").unwrap();
+                        storage.set_input_gain(&format!("device_{}", worker), i as f32).unwrap();
+                        assert!(storage.get_record("shared").unwrap().is_some());
+                        assert!(!storage.get_all_records().unwrap().is_empty());
+                    }
+                });
+            }
+        });
+
+        assert_eq!(storage.get_all_records().unwrap().len(), 1 + MAX_READERS * 2 * 10);
+        // 每个线程切换偶数次，读改写没有丢失更新时最终应恢复为未收藏
+        assert!(!storage.get_record("shared").unwrap().unwrap().is_starred);
+        for worker in 0..MAX_READERS * 2 {
+            assert_eq!(storage.get_input_gain(&format!("device_{}", worker)).unwrap(), Some(9.0));
+        }
+        {
+            let pool = storage.readers.state.lock().unwrap();
+            assert!(pool.opened <= MAX_READERS);
+            assert_eq!(pool.idle.len(), pool.opened);
+        }
+
+        // 事务仍然整体提交
+        storage.save_record(&tagged_record("tagged", &["旧"])).unwrap();
+        assert_eq!(storage.rename_tag("旧", "新").unwrap(), 1);
+        assert_eq!(storage.get_record("tagged").unwrap().unwrap().tags, vec!["新".to_string()]);
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
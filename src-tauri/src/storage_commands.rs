@@ -1,46 +1,68 @@
-use crate::storage::{StorageService, TranscriptionRecord, TranscriptionResult, PromptTemplate};
-use std::sync::Mutex;
+use crate::storage::{StorageService, LastUsedConfig, TranscriptionRecord, TranscriptionResult, TranscriptionSegment, PromptTemplate, LibraryStats, TagUsage, CategoryUsage, PromptUsageStats};
+use crate::database_manager::DatabaseManager;
+use crate::errors::StenoError;
+use crate::metrics::{self, EvaluationReport};
+use crate::realtime_speaker_diarization::DiarizationSummary;
+use crate::text_processing::normalize_for_index;
+use crate::transcript_export::{render_combined, render_transcript, CombinedTimestamps, ExportFormat, ExportOptions, ExportSection, RecordMetadata, SpeakerNames};
+use std::sync::RwLock;
 use tauri::{AppHandle, State};
 
-pub struct StorageState(pub Mutex<Option<StorageService>>);
+/// StorageService 内部自行协调读写连接，这里只在初始化时加写锁，命令处理共享读锁即可并发执行
+pub struct StorageState(pub RwLock<Option<StorageService>>);
 
 impl StorageState {
     pub fn new() -> Self {
-        Self(Mutex::new(None))
+        Self(RwLock::new(None))
     }
 
     pub fn init(&self, app_handle: &AppHandle) -> Result<(), String> {
         let storage = StorageService::new(app_handle)
             .map_err(|e| format!("Failed to initialize storage: {}", e))?;
         
-        let mut state = self.0.lock().unwrap();
+        let mut state = self.0.write().unwrap();
         *state = Some(storage);
         Ok(())
     }
 
-    pub fn with_storage<F, R>(&self, f: F) -> Result<R, String>
+    /// 先关闭所有连接再执行 `f`（如用备份覆盖数据库文件），随后重新初始化存储（含版本迁移）。
+    /// 无论 `f` 成功与否都会重新打开，保证后续命令可用
+    pub fn reopen_after<F, R>(&self, db_manager: &DatabaseManager, f: F) -> Result<R, String>
+    where
+        F: FnOnce() -> Result<R, String>,
+    {
+        let mut state = self.0.write().unwrap_or_else(|e| e.into_inner());
+        *state = None;
+        let result = f();
+        let storage = StorageService::with_manager(db_manager)
+            .map_err(|e| format!("Failed to reopen storage: {}", e))?;
+        *state = Some(storage);
+        result
+    }
+
+    pub fn with_storage<F, R>(&self, f: F) -> Result<R, StenoError>
     where
         F: FnOnce(&StorageService) -> rusqlite::Result<R>,
     {
-        let state = self.0.lock().unwrap();
+        let state = self.0.read().unwrap();
         match state.as_ref() {
-            Some(storage) => f(storage).map_err(|e| format!("Storage error: {}", e)),
-            None => Err("Storage not initialized. Please ensure the application has fully started.".to_string()),
+            Some(storage) => f(storage).map_err(StenoError::from),
+            None => Err(StenoError::StorageUnavailable("Storage not initialized. Please ensure the application has fully started.".to_string())),
         }
     }
     
     /// 可靠的存储访问 - 统一跨平台自动初始化逻辑
-    pub fn with_storage_reliable<F, R>(&self, app_handle: &AppHandle, mut f: F) -> Result<R, String>
+    pub fn with_storage_reliable<F, R>(&self, app_handle: &AppHandle, mut f: F) -> Result<R, StenoError>
     where
         F: FnMut(&StorageService) -> rusqlite::Result<R>,
     {
         // 第一次尝试：检查是否已初始化
         {
-            let state = self.0.lock().unwrap();
+            let state = self.0.read().unwrap();
             if let Some(storage) = state.as_ref() {
                 match f(storage) {
                     Ok(result) => return Ok(result),
-                    Err(e) => return Err(format!("Storage error: {}", e)),
+                    Err(e) => return Err(e.into()),
                 }
             }
         }
@@ -57,21 +79,21 @@ impl StorageState {
                     println!("✅ 存储服务自动初始化成功 (尝试 {})", attempt);
                     
                     // 第二次尝试执行操作
-                    let state = self.0.lock().unwrap();
+                    let state = self.0.read().unwrap();
                     if let Some(storage) = state.as_ref() {
                         match f(storage) {
                             Ok(result) => return Ok(result),
-                            Err(e) => return Err(format!("Storage error: {}", e)),
+                            Err(e) => return Err(e.into()),
                         }
                     } else {
-                        return Err("Storage still not initialized after successful init".to_string());
+                        return Err(StenoError::StorageUnavailable("Storage still not initialized after successful init".to_string()));
                     }
                 },
                 Err(e) => {
                     if attempt == 3 {
                         log::error!("❌ 存储服务初始化最终失败: {}", e);
                         eprintln!("❌ 存储服务初始化最终失败: {}", e);
-                        return Err(format!("Storage initialization failed after {} attempts: {}", attempt, e));
+                        return Err(StenoError::StorageUnavailable(format!("Storage initialization failed after {} attempts: {}", attempt, e)));
                     } else {
                         log::warn!("⚠️ 存储服务初始化失败 (尝试 {}/3): {}", attempt, e);
                         // 短暂等待后重试
@@ -81,12 +103,12 @@ impl StorageState {
             }
         }
         
-        Err("Storage initialization failed after all retry attempts".to_string())
+        Err(StenoError::StorageUnavailable("Storage initialization failed after all retry attempts".to_string()))
     }
     
     /// 带重试机制的初始化
     fn init_with_retry(&self, app_handle: &AppHandle, attempt: u32) -> Result<(), String> {
-        let mut state = self.0.lock().unwrap();
+        let mut state = self.0.write().unwrap();
         
         // 如果已经初始化了，直接返回成功
         if state.is_some() {
@@ -105,7 +127,7 @@ impl StorageState {
     }
     
     /// 安全的存储访问，如果未初始化则尝试自动初始化（保持向后兼容）
-    pub fn with_storage_auto_init<F, R>(&self, app_handle: &AppHandle, f: F) -> Result<R, String>
+    pub fn with_storage_auto_init<F, R>(&self, app_handle: &AppHandle, f: F) -> Result<R, StenoError>
     where
         F: FnMut(&StorageService) -> rusqlite::Result<R>,
     {
@@ -118,15 +140,15 @@ impl StorageState {
 pub async fn init_storage(
     app_handle: AppHandle,
     storage_state: State<'_, StorageState>,
-) -> Result<(), String> {
-    storage_state.init(&app_handle)
+) -> Result<(), StenoError> {
+    storage_state.init(&app_handle).map_err(StenoError::StorageUnavailable)
 }
 
 #[tauri::command]
 pub async fn save_transcription_record(
     record: TranscriptionRecord,
     storage_state: State<'_, StorageState>,
-) -> Result<(), String> {
+) -> Result<(), StenoError> {
     storage_state.with_storage(|storage| storage.save_record(&record))
 }
 
@@ -134,7 +156,7 @@ pub async fn save_transcription_record(
 pub async fn get_transcription_record(
     id: String,
     storage_state: State<'_, StorageState>,
-) -> Result<Option<TranscriptionRecord>, String> {
+) -> Result<Option<TranscriptionRecord>, StenoError> {
     storage_state.with_storage(|storage| storage.get_record(&id))
 }
 
@@ -142,7 +164,7 @@ pub async fn get_transcription_record(
 pub async fn get_all_transcription_records(
     app_handle: AppHandle,
     storage_state: State<'_, StorageState>,
-) -> Result<Vec<TranscriptionRecord>, String> {
+) -> Result<Vec<TranscriptionRecord>, StenoError> {
     storage_state.with_storage_reliable(&app_handle, |storage| storage.get_all_records())
 }
 
@@ -153,26 +175,33 @@ pub async fn update_transcription_status(
     progress: f64,
     error: Option<String>,
     storage_state: State<'_, StorageState>,
-) -> Result<(), String> {
+) -> Result<(), StenoError> {
     storage_state.with_storage(|storage| {
         storage.update_record_status(&id, &status, progress, error.as_deref())
     })
 }
 
+/// 保存转录结果；开启自动命名时返回按内容生成的新名称
 #[tauri::command]
 pub async fn update_transcription_result(
     id: String,
-    result: TranscriptionResult,
+    mut result: TranscriptionResult,
     storage_state: State<'_, StorageState>,
-) -> Result<(), String> {
-    storage_state.with_storage(|storage| storage.update_record_result(&id, &result))
+) -> Result<Option<String>, StenoError> {
+    storage_state.with_storage(|storage| {
+        if let (Some(segments), Some(record)) = (result.segments.as_mut(), storage.get_record(&id)?) {
+            crate::romanization::annotate_segments(segments, &record.config);
+        }
+        storage.update_record_result(&id, &result)?;
+        crate::record_naming::apply_auto_name(storage, &id, &result.text)
+    })
 }
 
 #[tauri::command]
 pub async fn delete_transcription_record(
     id: String,
     storage_state: State<'_, StorageState>,
-) -> Result<(), String> {
+) -> Result<(), StenoError> {
     storage_state.with_storage(|storage| storage.delete_record(&id))
 }
 
@@ -180,7 +209,7 @@ pub async fn delete_transcription_record(
 pub async fn toggle_transcription_star(
     id: String,
     storage_state: State<'_, StorageState>,
-) -> Result<bool, String> {
+) -> Result<bool, StenoError> {
     storage_state.with_storage(|storage| storage.toggle_star(&id))
 }
 
@@ -189,16 +218,209 @@ pub async fn update_transcription_name(
     id: String,
     name: String,
     storage_state: State<'_, StorageState>,
-) -> Result<(), String> {
+) -> Result<(), StenoError> {
     storage_state.with_storage(|storage| storage.update_record_name(&id, &name))
 }
 
+#[tauri::command]
+pub async fn get_library_stats(
+    app_handle: AppHandle,
+    storage_state: State<'_, StorageState>,
+) -> Result<LibraryStats, StenoError> {
+    storage_state.with_storage_reliable(&app_handle, |storage| storage.get_library_stats())
+}
+
+#[tauri::command]
+pub async fn list_all_tags(
+    storage_state: State<'_, StorageState>,
+) -> Result<Vec<TagUsage>, StenoError> {
+    storage_state.with_storage(|storage| storage.list_all_tags())
+}
+
+#[tauri::command]
+pub async fn rename_tag(
+    old_tag: String,
+    new_tag: String,
+    storage_state: State<'_, StorageState>,
+) -> Result<usize, StenoError> {
+    storage_state.with_storage(|storage| storage.rename_tag(&old_tag, &new_tag))
+}
+
+#[tauri::command]
+pub async fn list_categories(
+    storage_state: State<'_, StorageState>,
+) -> Result<Vec<CategoryUsage>, StenoError> {
+    storage_state.with_storage(|storage| storage.list_categories())
+}
+
+/// 去掉首尾空白后的分类名称，为空时报参数错误
+fn category_name(name: &str) -> Result<&str, StenoError> {
+    match name.trim() {
+        "" => Err(StenoError::InvalidArgument("分类名称不能为空".to_string())),
+        name => Ok(name),
+    }
+}
+
+#[tauri::command]
+pub async fn rename_category(
+    old_name: String,
+    new_name: String,
+    storage_state: State<'_, StorageState>,
+) -> Result<usize, StenoError> {
+    let new_name = category_name(&new_name)?;
+    storage_state.with_storage(|storage| storage.rename_category(&old_name, new_name))
+}
+
+#[tauri::command]
+pub async fn delete_category(
+    name: String,
+    reassign_to: Option<String>,
+    storage_state: State<'_, StorageState>,
+) -> Result<usize, StenoError> {
+    storage_state.with_storage(|storage| storage.delete_category(&name, reassign_to.as_deref()))
+}
+
+#[tauri::command]
+pub async fn get_segments_in_range(
+    record_id: String,
+    start: f64,
+    end: f64,
+    storage_state: State<'_, StorageState>,
+) -> Result<Vec<TranscriptionSegment>, StenoError> {
+    storage_state.with_storage(|storage| storage.get_segments_in_range(&record_id, start, end))
+}
+
+/// 播放位置 t_secs 对应的分段，供点击播放时高亮当前段
+#[tauri::command]
+pub async fn segment_at_time(
+    record_id: String,
+    t_secs: f64,
+    storage_state: State<'_, StorageState>,
+) -> Result<Option<TranscriptionSegment>, StenoError> {
+    storage_state.with_storage(|storage| storage.segment_at_time(&record_id, t_secs))
+}
+
+/// 自动拆分的长录音中与该记录同属一个会话的全部记录ID（按先后顺序）
+#[tauri::command]
+pub async fn get_recording_session(
+    record_id: String,
+    storage_state: State<'_, StorageState>,
+) -> Result<Vec<String>, StenoError> {
+    storage_state.with_storage(|storage| storage.get_session_records(&record_id))
+}
+
+/// 实时录音结束时保存的说话人分离汇总；未启用说话人分离或没有识别到说话人时为空
+#[tauri::command]
+pub async fn get_diarization_summary(
+    record_id: String,
+    storage_state: State<'_, StorageState>,
+) -> Result<Option<DiarizationSummary>, StenoError> {
+    storage_state.with_storage(|storage| storage.get_diarization_summary(&record_id))
+}
+
+#[tauri::command]
+pub async fn set_speaker_name(
+    record_id: String,
+    speaker_id: String,
+    display_name: String,
+    storage_state: State<'_, StorageState>,
+) -> Result<(), StenoError> {
+    storage_state.with_storage(|storage| storage.set_speaker_name(&record_id, &speaker_id, &display_name))
+}
+
+/// 导出转录记录；提供 output_path 时同时写入文件，返回导出内容
+#[tauri::command]
+pub async fn export_transcription_record(
+    id: String,
+    format: String,
+    options: Option<ExportOptions>,
+    output_path: Option<String>,
+    storage_state: State<'_, StorageState>,
+) -> Result<String, StenoError> {
+    let format = ExportFormat::parse(&format).map_err(StenoError::InvalidArgument)?;
+    let options = options.unwrap_or_default();
+
+    let (record, persisted_names) = storage_state.with_storage(|storage| {
+        Ok((storage.get_record(&id)?, storage.get_speaker_names(&id)?))
+    })?;
+    let record = record.ok_or_else(|| StenoError::NotFound(format!("记录不存在: {}", id)))?;
+    let metadata = RecordMetadata::from_record(&record);
+    let result = record.result.ok_or_else(|| StenoError::NotFound("该记录还没有转录结果".to_string()))?;
+    let segments = result.segments.unwrap_or_default();
+
+    let names = SpeakerNames::new(persisted_names, &segments);
+    let content = render_transcript(&result.text, &segments, format, &options, &names, &metadata);
+
+    if let Some(path) = output_path {
+        std::fs::write(&path, &content).map_err(|e| StenoError::Internal(format!("写入导出文件失败: {}", e)))?;
+    }
+    Ok(content)
+}
+
+/// 按 ids 的顺序将多条记录导出为一份文档，每条记录前带标题
+#[tauri::command]
+pub async fn export_records_combined(
+    ids: Vec<String>,
+    format: String,
+    options: Option<ExportOptions>,
+    timestamps: Option<CombinedTimestamps>,
+    output_path: Option<String>,
+    storage_state: State<'_, StorageState>,
+) -> Result<String, StenoError> {
+    let format = ExportFormat::parse(&format).map_err(StenoError::InvalidArgument)?;
+    if ids.is_empty() {
+        return Err(StenoError::InvalidArgument("没有要导出的记录".to_string()));
+    }
+
+    let loaded = storage_state.with_storage(|storage| {
+        ids.iter()
+            .map(|id| Ok((storage.get_record(id)?, storage.get_speaker_names(id)?)))
+            .collect::<rusqlite::Result<Vec<_>>>()
+    })?;
+    let mut sections = Vec::with_capacity(ids.len());
+    for (id, (record, persisted_names)) in ids.iter().zip(loaded) {
+        let record = record.ok_or_else(|| StenoError::NotFound(format!("记录不存在: {}", id)))?;
+        let metadata = RecordMetadata::from_record(&record);
+        let result = record.result.ok_or_else(|| StenoError::NotFound(format!("记录 {} 还没有转录结果", record.name)))?;
+        let segments = result.segments.unwrap_or_default();
+        sections.push(ExportSection {
+            full_text: result.text,
+            names: SpeakerNames::new(persisted_names, &segments),
+            segments,
+            metadata,
+            duration: record.duration,
+        });
+    }
+    let content = render_combined(&sections, format, &options.unwrap_or_default(), timestamps.unwrap_or_default());
+
+    if let Some(path) = output_path {
+        std::fs::write(&path, &content).map_err(|e| StenoError::Internal(format!("写入导出文件失败: {}", e)))?;
+    }
+    Ok(content)
+}
+
+/// 以用户提供的参考文本评估记录的识别质量
+#[tauri::command]
+pub async fn evaluate_record(
+    record_id: String,
+    reference_text: String,
+    storage_state: State<'_, StorageState>,
+) -> Result<EvaluationReport, StenoError> {
+    if reference_text.trim().is_empty() {
+        return Err(StenoError::InvalidArgument("参考文本不能为空".to_string()));
+    }
+    let record = storage_state.with_storage(|storage| storage.get_record(&record_id))?
+        .ok_or_else(|| StenoError::NotFound(format!("记录不存在: {}", record_id)))?;
+    let result = record.result.ok_or_else(|| StenoError::NotFound("该记录还没有转录结果".to_string()))?;
+    Ok(metrics::evaluate(&result.text, &reference_text))
+}
+
 #[tauri::command]
 pub async fn search_transcription_records(
     query: String,
     category: Option<String>,
     storage_state: State<'_, StorageState>,
-) -> Result<Vec<TranscriptionRecord>, String> {
+) -> Result<Vec<TranscriptionRecord>, StenoError> {
     storage_state.with_storage(|storage| {
         let mut records = storage.get_all_records()?;
         
@@ -225,13 +447,15 @@ pub async fn search_transcription_records(
         
         // 按搜索关键词过滤
         if !query.trim().is_empty() {
-            let query_lower = query.to_lowercase();
+            // 查询与被检索文本使用相同的归一化，全半角、大小写、繁简差异不影响匹配
+            let normalized_query = normalize_for_index(&query, true);
+            let matches = |text: &str| normalize_for_index(text, true).contains(&normalized_query);
             records.retain(|r| {
-                r.name.to_lowercase().contains(&query_lower) ||
-                r.original_file_name.to_lowercase().contains(&query_lower) ||
-                r.tags.iter().any(|tag| tag.to_lowercase().contains(&query_lower)) ||
+                matches(&r.name) ||
+                matches(&r.original_file_name) ||
+                r.tags.iter().any(|tag| matches(tag)) ||
                 r.result.as_ref()
-                    .map(|res| res.text.to_lowercase().contains(&query_lower))
+                    .map(|res| matches(&res.text))
                     .unwrap_or(false)
             });
         }
@@ -245,7 +469,7 @@ pub async fn search_transcription_records(
 #[tauri::command]
 pub async fn get_prompt_templates(
     storage_state: State<'_, StorageState>,
-) -> Result<Vec<PromptTemplate>, String> {
+) -> Result<Vec<PromptTemplate>, StenoError> {
     storage_state.with_storage(|storage| storage.get_prompt_templates())
 }
 
@@ -254,7 +478,7 @@ pub async fn get_prompts_by_filter(
     category: Option<String>,
     language: Option<String>,
     storage_state: State<'_, StorageState>,
-) -> Result<Vec<PromptTemplate>, String> {
+) -> Result<Vec<PromptTemplate>, StenoError> {
     storage_state.with_storage(|storage| {
         storage.get_prompts_by_filter(
             category.as_deref(),
@@ -267,7 +491,7 @@ pub async fn get_prompts_by_filter(
 pub async fn save_prompt_template(
     prompt: PromptTemplate,
     storage_state: State<'_, StorageState>,
-) -> Result<(), String> {
+) -> Result<(), StenoError> {
     storage_state.with_storage(|storage| storage.save_prompt_template(&prompt))
 }
 
@@ -275,7 +499,7 @@ pub async fn save_prompt_template(
 pub async fn get_prompt_template(
     id: String,
     storage_state: State<'_, StorageState>,
-) -> Result<Option<PromptTemplate>, String> {
+) -> Result<Option<PromptTemplate>, StenoError> {
     storage_state.with_storage(|storage| storage.get_prompt_template(&id))
 }
 
@@ -283,7 +507,7 @@ pub async fn get_prompt_template(
 pub async fn delete_prompt_template(
     id: String,
     storage_state: State<'_, StorageState>,
-) -> Result<(), String> {
+) -> Result<(), StenoError> {
     storage_state.with_storage(|storage| storage.delete_prompt_template(&id))
 }
 
@@ -291,15 +515,50 @@ pub async fn delete_prompt_template(
 pub async fn search_prompt_templates(
     query: String,
     storage_state: State<'_, StorageState>,
-) -> Result<Vec<PromptTemplate>, String> {
+) -> Result<Vec<PromptTemplate>, StenoError> {
     storage_state.with_storage(|storage| storage.search_prompt_templates(&query))
 }
 
+#[tauri::command]
+pub async fn get_prompt_usage_stats(
+    storage_state: State<'_, StorageState>,
+) -> Result<Vec<PromptUsageStats>, StenoError> {
+    storage_state.with_storage(|storage| storage.get_prompt_usage_stats())
+}
+
 #[tauri::command]
 pub async fn increment_prompt_usage(
     id: String,
     storage_state: State<'_, StorageState>,
-) -> Result<(), String> {
+) -> Result<(), StenoError> {
     storage_state.with_storage(|storage| storage.increment_prompt_usage(&id))
 }
 
+
+// ========== 上次使用的配置 ==========
+
+#[tauri::command]
+pub async fn get_last_config(
+    storage_state: State<'_, StorageState>,
+) -> Result<LastUsedConfig, StenoError> {
+    storage_state.with_storage(|storage| storage.get_last_used_config())
+}
+
+#[tauri::command]
+pub async fn set_last_config(
+    config: LastUsedConfig,
+    storage_state: State<'_, StorageState>,
+) -> Result<(), StenoError> {
+    storage_state.with_storage(|storage| storage.set_last_used_config(&config))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_empty_category_name_is_invalid_argument() {
+        assert_eq!(category_name("  周会 ").unwrap(), "周会");
+        assert_eq!(category_name("  ").unwrap_err().code(), "invalid_argument");
+    }
+}
@@ -0,0 +1,393 @@
+// text_processing.rs - 转录文本后处理工具
+use std::collections::HashMap;
+use std::sync::Mutex;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use unicode_normalization::UnicodeNormalization;
+
+use crate::prompt_builder::is_cjk_char;
+use crate::whisper_full_params;
+
+/// 重复（幻觉循环）检测配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RepetitionConfig {
+    pub enabled: bool,
+    pub min_repeats: usize,             // 多词/多字短语连续重复达到该次数才折叠
+    pub min_single_unit_repeats: usize, // 单个词/字需要更高的次数，避免误伤"no no no"这类正常重复
+    pub max_ngram: usize,               // 英文等按词切分时的最大短语长度
+    pub max_ngram_cjk: usize,           // CJK按字切分时的最大短语长度
+}
+
+impl Default for RepetitionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            min_repeats: 3,
+            min_single_unit_repeats: 5,
+            max_ngram: 8,
+            max_ngram_cjk: 16,
+        }
+    }
+}
+
+/// 非语音标注（如 "[音乐]"、"(applause)"）过滤配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SuppressionConfig {
+    pub enabled: bool,
+    pub suppress_non_speech_tokens: bool, // 解码时即抑制 Whisper 的非语音 token
+    pub patterns: Vec<String>,            // 从识别结果中删除的正则
+}
+
+impl Default for SuppressionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            suppress_non_speech_tokens: true,
+            patterns: vec![
+                // 方括号内几乎都是字幕式标注
+                r"\[[^\]]*\]".to_string(),
+                r"【[^】]*】".to_string(),
+                // 圆括号只匹配常见的非语音标注，保留正常的括号内容
+                r"(?i)[(（]\s*(music|applause|laughter|laughs|silence|noise|inaudible|音乐|掌声|笑声|静音|噪音)\s*[)）]".to_string(),
+                r"[♪♫]+".to_string(),
+            ],
+        }
+    }
+}
+
+impl SuppressionConfig {
+    pub fn apply(&self, params: &mut whisper_full_params) {
+        params.suppress_nst = self.enabled && self.suppress_non_speech_tokens;
+    }
+}
+
+lazy_static::lazy_static! {
+    // 非语音过滤正则按模式文本缓存，实时识别每个窗口都会调用，不重复编译；无效的模式缓存为 None
+    static ref SUPPRESSION_REGEXES: Mutex<HashMap<String, Option<Regex>>> = Mutex::new(HashMap::new());
+}
+
+/// 删除匹配的非语音标注并整理多余空白；无效的正则记录警告后跳过
+pub fn strip_non_speech(text: &str, config: &SuppressionConfig) -> String {
+    if !config.enabled || config.patterns.is_empty() {
+        return text.to_string();
+    }
+
+    let mut result = text.to_string();
+    let mut regexes = SUPPRESSION_REGEXES.lock().unwrap_or_else(|e| e.into_inner());
+    for pattern in &config.patterns {
+        let regex = regexes.entry(pattern.clone()).or_insert_with(|| {
+            Regex::new(pattern)
+                .map_err(|e| log::warn!("⚠️ 跳过无效的非语音过滤正则 {}: {}", pattern, e))
+                .ok()
+        });
+        if let Some(regex) = regex {
+            result = regex.replace_all(&result, "").to_string();
+        }
+    }
+    if result == text {
+        return result;
+    }
+    result.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// 判断文本是否应按字符（CJK）切分
+fn use_char_units(text: &str, language: &str) -> bool {
+    match language {
+        "zh" | "ja" | "ko" => true,
+        "en" => false,
+        _ => text.chars().any(is_cjk_char),
+    }
+}
+
+/// 比较用的归一化形式：忽略大小写与标点
+fn normalize_unit(token: &str) -> String {
+    token.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(|c| c.to_lowercase())
+        .collect()
+}
+
+/// 判断短语本身是否由更短的单元重复构成（交给更短的n-gram处理）
+fn is_periodic(unit: &[String]) -> bool {
+    let n = unit.len();
+    (1..n).filter(|d| n % d == 0).any(|d| {
+        unit.chunks(d).all(|chunk| chunk == &unit[..d])
+    })
+}
+
+/// 统计从 start 开始、长度为 n 的单元连续重复的次数
+fn count_repeats(units: &[String], start: usize, n: usize) -> usize {
+    let unit = &units[start..start + n];
+    // 全是标点或空白的单元不参与折叠
+    if unit.iter().all(|u| u.is_empty()) {
+        return 1;
+    }
+
+    let mut count = 1;
+    let mut pos = start + n;
+    while pos + n <= units.len() && units[pos..pos + n] == *unit {
+        count += 1;
+        pos += n;
+    }
+    count
+}
+
+/// 检测并折叠连续重复的n-gram（如 "thank you thank you thank you"），只保留第一次出现
+pub fn collapse_repetitions(text: &str, language: &str, config: &RepetitionConfig) -> String {
+    if !config.enabled || text.trim().is_empty() {
+        return text.to_string();
+    }
+
+    let char_mode = use_char_units(text, language);
+    let tokens: Vec<String> = if char_mode {
+        text.chars().map(|c| c.to_string()).collect()
+    } else {
+        text.split_whitespace().map(|w| w.to_string()).collect()
+    };
+    let units: Vec<String> = tokens.iter().map(|t| normalize_unit(t)).collect();
+    let max_ngram = if char_mode { config.max_ngram_cjk } else { config.max_ngram };
+
+    let mut kept: Vec<&str> = Vec::with_capacity(tokens.len());
+    let mut i = 0;
+    let mut collapsed = false;
+
+    while i < tokens.len() {
+        let mut advanced = false;
+
+        // 优先匹配最长的重复短语
+        for n in (1..=max_ngram.min(tokens.len() - i)).rev() {
+            let threshold = if n == 1 { config.min_single_unit_repeats } else { config.min_repeats };
+            if (tokens.len() - i) / n < threshold {
+                continue;
+            }
+
+            if is_periodic(&units[i..i + n]) {
+                continue;
+            }
+
+            let repeats = count_repeats(&units, i, n);
+            if repeats >= threshold {
+                kept.extend(tokens[i..i + n].iter().map(|t| t.as_str()));
+                i += repeats * n;
+                advanced = true;
+                collapsed = true;
+                break;
+            }
+        }
+
+        if !advanced {
+            kept.push(&tokens[i]);
+            i += 1;
+        }
+    }
+
+    if !collapsed {
+        return text.to_string();
+    }
+
+    if char_mode {
+        kept.concat()
+    } else {
+        kept.join(" ")
+    }
+}
+
+/// 常用繁体字到简体字的对照（繁简成对排列），用于检索时繁简互通
+const TRADITIONAL_TO_SIMPLIFIED: &[&str] = &[
+    "這这個个們们來来說说會会時时對对過过還还為为國国學学後后發发經经",
+    "動动問问關关開开無无點点與与電电話话號号當当樣样從从種种長长現现",
+    "實实體体頭头見见應应進进東东書书車车門门兩两邊边間间聽听覺觉讓让",
+    "處处麼么氣气錄录語语識识別别轉转寫写聲声碼码於于歲岁萬万億亿網网",
+    "絡络線线讀读視视頻频數数據据庫库設设業业務务買买賣卖錢钱貨货價价",
+    "產产參参議议醫医藥药報报紙纸雜杂誌志記记憶忆專专幾几標标準准確确",
+    "認认團团隊队運运營营總总統统軍军歷历變变題题幫帮員员請请謝谢嗎吗",
+    "輸输壓压環环節节雲云愛爱裡里裏里臺台灣湾華华區区縣县鄉乡義义導导",
+    "師师飛飞機机場场廣广誤误計计劃划畫画錯错讚赞獲获鐘钟鍾钟歡欢樂乐",
+    "熱热際际試试驗验檢检測测詞词譯译調调課课",
+];
+
+/// 多段文本拼接为连续文本时的分隔方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TextJoinStyle {
+    #[default]
+    Space,     // 段间加空格
+    Newline,   // 每段一行
+    Paragraph, // 段间空一行，每段自成一段落
+}
+
+impl TextJoinStyle {
+    /// left 与 right 之间的分隔；按空格拼接时中文、日文等不以空格分词的语言不加空格
+    fn separator(&self, language: &str, left: &str, right: &str) -> &'static str {
+        match self {
+            TextJoinStyle::Space if is_auto_language(language) && is_cjk_boundary(left, right) => "",
+            TextJoinStyle::Space if is_unspaced_language(language) => "",
+            TextJoinStyle::Space => " ",
+            TextJoinStyle::Newline => "\n",
+            TextJoinStyle::Paragraph => "\n\n",
+        }
+    }
+}
+
+fn is_auto_language(language: &str) -> bool {
+    matches!(language.trim(), "" | "auto")
+}
+
+/// 文字不以空格分词的语言（中文、粤语、日文），接受 zh-CN 这类带地区的写法
+fn is_unspaced_language(language: &str) -> bool {
+    let code = language.trim().split(['-', '_']).next().unwrap_or("").to_lowercase();
+    matches!(code.as_str(), "zh" | "yue" | "ja")
+}
+
+/// 两段文本的衔接处是否有一侧是不以空格分词的 CJK 字符，此时不应插入空格
+pub(crate) fn is_cjk_boundary(left: &str, right: &str) -> bool {
+    left.trim_end().chars().last().is_some_and(is_unspaced_char)
+        || right.trim_start().chars().next().is_some_and(is_unspaced_char)
+}
+
+/// 韩文音节虽属 CJK 字符范围，但韩文以空格分词，衔接处仍需要空格
+fn is_unspaced_char(c: char) -> bool {
+    is_cjk_char(c) && !matches!(c as u32, 0xAC00..=0xD7AF)
+}
+
+/// 按 style 拼接多段文本；language 为记录或会话的语言，自动检测（auto）时按衔接处的字符判断是否加空格
+pub fn join_segments<'a>(texts: impl IntoIterator<Item = &'a str>, style: TextJoinStyle, language: &str) -> String {
+    let mut joined = String::new();
+    for (i, text) in texts.into_iter().enumerate() {
+        if i > 0 {
+            joined.push_str(style.separator(language, &joined, text));
+        }
+        joined.push_str(text);
+    }
+    joined
+}
+
+lazy_static::lazy_static! {
+    static ref VARIANT_MAP: HashMap<char, char> = TRADITIONAL_TO_SIMPLIFIED.iter()
+        .flat_map(|line| {
+            let chars: Vec<char> = line.chars().collect();
+            chars.chunks(2).map(|pair| (pair[0], pair[1])).collect::<Vec<_>>()
+        })
+        .collect();
+}
+
+/// 检索与比较用的归一化文本：NFKC（全角/半角等兼容字符统一）、大小写折叠，可选繁体折叠为简体。
+/// 只用于建立索引和比较，展示仍使用原文
+pub fn normalize_for_index(text: &str, fold_chinese_variants: bool) -> String {
+    text.nfkc()
+        .flat_map(char::to_lowercase)
+        .map(|c| if fold_chinese_variants { VARIANT_MAP.get(&c).copied().unwrap_or(c) } else { c })
+        .collect()
+}
+
+/// 与 normalize_for_index 相同的归一化，逐字符进行并记录每个输出字符来自原文的第几个字符，用于把匹配位置换算回原文
+pub fn normalize_with_offsets(text: &str, fold_chinese_variants: bool) -> Vec<(char, usize)> {
+    text.chars()
+        .enumerate()
+        .flat_map(|(index, c)| {
+            std::iter::once(c)
+                .nfkc()
+                .flat_map(char::to_lowercase)
+                .map(move |c| if fold_chinese_variants { VARIANT_MAP.get(&c).copied().unwrap_or(c) } else { c })
+                .map(move |c| (c, index))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_join_styles() {
+        let texts = ["第一段", "第二段", "third"];
+        assert_eq!(join_segments(texts, TextJoinStyle::Space, "en"), "第一段 第二段 third");
+        assert_eq!(join_segments(texts, TextJoinStyle::Newline, "zh"), "第一段\n第二段\nthird");
+        assert_eq!(join_segments(texts, TextJoinStyle::Paragraph, "zh"), "第一段\n\n第二段\n\nthird");
+        assert_eq!(join_segments(["only"], TextJoinStyle::Paragraph, "en"), "only");
+        assert_eq!(join_segments([], TextJoinStyle::Newline, "en"), "");
+        assert_eq!(serde_json::from_str::<TextJoinStyle>("\"paragraph\"").unwrap(), TextJoinStyle::Paragraph);
+    }
+
+    #[test]
+    fn test_space_join_follows_language() {
+        // 中文、日文段间不加空格，英文等以空格分词的语言加空格
+        assert_eq!(join_segments(["大家好", "今天讨论预算", "GPU 集群"], TextJoinStyle::Space, "zh"), "大家好今天讨论预算GPU 集群");
+        assert_eq!(join_segments(["こんにちは", "今日は"], TextJoinStyle::Space, "ja"), "こんにちは今日は");
+        assert_eq!(join_segments(["大家好", "开会"], TextJoinStyle::Space, "zh-CN"), "大家好开会");
+        assert_eq!(join_segments(["Hello everyone.", "Let's start"], TextJoinStyle::Space, "en"), "Hello everyone. Let's start");
+        assert_eq!(join_segments(["안녕하세요", "반갑습니다"], TextJoinStyle::Space, "ko"), "안녕하세요 반갑습니다");
+
+        // 自动检测语言时按衔接处的字符判断
+        assert_eq!(join_segments(["大家好", "Steno", "很好用"], TextJoinStyle::Space, "auto"), "大家好Steno很好用");
+        assert_eq!(join_segments(["Hello", "world"], TextJoinStyle::Space, "auto"), "Hello world");
+        assert!(is_cjk_boundary("好 ", " Steno") && !is_cjk_boundary("Steno", "works"));
+        assert_eq!(join_segments(["안녕하세요", "반갑습니다"], TextJoinStyle::Space, "auto"), "안녕하세요 반갑습니다");
+        assert!(!is_cjk_boundary("안녕하세요", "Steno"));
+    }
+
+    #[test]
+    fn test_collapse_hallucination_loops() {
+        let config = RepetitionConfig::default();
+        assert_eq!(collapse_repetitions("thank you thank you thank you", "en", &config), "thank you");
+        assert_eq!(
+            collapse_repetitions("Okay. Thank you. Thank you. Thank you. Thank you.", "en", &config),
+            "Okay. Thank you."
+        );
+        assert_eq!(collapse_repetitions("谢谢观看谢谢观看谢谢观看谢谢观看", "zh", &config), "谢谢观看");
+        assert_eq!(collapse_repetitions("字幕由社区提供。字幕由社区提供。字幕由社区提供。", "auto", &config), "字幕由社区提供。");
+        assert_eq!(collapse_repetitions("the the the the the the end", "en", &config), "the end");
+    }
+
+    #[test]
+    fn test_keep_legitimate_repetition() {
+        let config = RepetitionConfig::default();
+        assert_eq!(collapse_repetitions("no no no, that's wrong", "en", &config), "no no no, that's wrong");
+        assert_eq!(collapse_repetitions("very very good", "en", &config), "very very good");
+        assert_eq!(collapse_repetitions("我们一起学习，一起进步", "zh", &config), "我们一起学习，一起进步");
+        assert_eq!(collapse_repetitions("谢谢谢谢", "zh", &config), "谢谢谢谢");
+
+        let disabled = RepetitionConfig { enabled: false, ..Default::default() };
+        assert_eq!(collapse_repetitions("thank you thank you thank you", "en", &disabled), "thank you thank you thank you");
+    }
+
+    #[test]
+    fn test_strip_non_speech_annotations() {
+        let config = SuppressionConfig::default();
+        assert_eq!(strip_non_speech("[音乐] 大家好，欢迎收听", &config), "大家好，欢迎收听");
+        assert_eq!(strip_non_speech("Thank you (applause) everyone ♪♪", &config), "Thank you everyone");
+        assert_eq!(strip_non_speech("【掌声】谢谢（笑声）", &config), "谢谢");
+        assert_eq!(strip_non_speech("[BLANK_AUDIO]", &config), "");
+
+        // 正常的括号内容保留
+        assert_eq!(strip_non_speech("GDP (gross domestic product) grew", &config), "GDP (gross domestic product) grew");
+        assert_eq!(strip_non_speech("我们（公司）明年会扩张", &config), "我们（公司）明年会扩张");
+
+        let custom = SuppressionConfig {
+            patterns: vec![r"(?i)\*\s*cough\s*\*".to_string(), "(".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(strip_non_speech("well *cough* okay", &custom), "well okay");
+
+        let disabled = SuppressionConfig { enabled: false, ..Default::default() };
+        assert_eq!(strip_non_speech("[音乐] 你好", &disabled), "[音乐] 你好");
+    }
+
+    #[test]
+    fn test_normalize_width_and_case() {
+        // 全角字母、数字、标点和全角空格折叠为半角
+        assert_eq!(normalize_for_index("ＷＨＩＳＰＥＲ　１２３！", false), "whisper 123!");
+        assert_eq!(normalize_for_index("Hello World", false), normalize_for_index("hELLO wORLD", false));
+        // 兼容字符（如合字、圈码）按 NFKC 展开
+        assert_eq!(normalize_for_index("ﬁle ①", false), "file 1");
+    }
+
+    #[test]
+    fn test_normalize_chinese_variants() {
+        assert_eq!(normalize_for_index("語音識別", true), "语音识别");
+        assert_eq!(normalize_for_index("語音識別", false), "語音識別");
+        assert!(TRADITIONAL_TO_SIMPLIFIED.iter().all(|line| line.chars().count() % 2 == 0));
+    }
+}
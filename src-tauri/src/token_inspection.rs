@@ -0,0 +1,188 @@
+// token_inspection.rs - 按段导出 Whisper 的原始 token、概率与时间戳，供研究分析使用
+use std::collections::HashMap;
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+use std::sync::Mutex;
+
+use serde::Serialize;
+use tauri::{Manager, State};
+
+use crate::errors::StenoError;
+use crate::storage_commands::StorageState;
+use crate::whisper_input::{self, WHISPER_SAMPLE_RATE};
+use crate::{
+    load_and_convert_audio, whisper_context, whisper_full, whisper_full_default_params,
+    whisper_full_get_token_data, whisper_full_get_token_id, whisper_full_get_token_text,
+    whisper_full_n_segments, whisper_full_n_tokens, whisper_sampling_strategy_WHISPER_SAMPLING_GREEDY,
+    whisper_token_eot, WhisperContextState,
+};
+
+const SAMPLE_RATE: f64 = WHISPER_SAMPLE_RATE as f64;
+
+lazy_static::lazy_static! {
+    // (记录ID, 段序号) -> 该段最近一次解码的 token；结果取决于当前模型，切换模型时清空
+    static ref TOKEN_CACHE: Mutex<HashMap<(String, usize), Vec<TokenInfo>>> = Mutex::new(HashMap::new());
+}
+
+/// 模型重新加载后调用，丢弃旧模型解码出的 token
+pub fn clear_token_cache() {
+    TOKEN_CACHE.lock().unwrap_or_else(|e| e.into_inner()).clear();
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TokenInfo {
+    pub text: String,
+    pub probability: f32,
+    pub start_time: f64, // 相对整段录音的秒数
+    pub end_time: f64,
+}
+
+/// 读取最近一次 whisper_full 的全部文本 token（忽略特殊 token），时间戳加上 offset 换算为录音时间
+unsafe fn extract_tokens(ctx: *mut whisper_context, offset: f64) -> Vec<TokenInfo> {
+    let eot = whisper_token_eot(ctx);
+    let mut tokens = Vec::new();
+    for i_segment in 0..whisper_full_n_segments(ctx) {
+        for i_token in 0..whisper_full_n_tokens(ctx, i_segment) {
+            if whisper_full_get_token_id(ctx, i_segment, i_token) >= eot {
+                continue;
+            }
+            let data = whisper_full_get_token_data(ctx, i_segment, i_token);
+            let text_ptr = whisper_full_get_token_text(ctx, i_segment, i_token);
+            let text = if text_ptr.is_null() {
+                String::new()
+            } else {
+                CStr::from_ptr(text_ptr as *const c_char).to_string_lossy().to_string()
+            };
+            // Whisper 时间戳单位为10ms
+            tokens.push(TokenInfo {
+                text,
+                probability: data.p,
+                start_time: offset + data.t0.max(0) as f64 / 100.0,
+                end_time: offset + data.t1.max(0) as f64 / 100.0,
+            });
+        }
+    }
+    tokens
+}
+
+/// 对一段音频重新解码并开启 token 级时间戳
+pub fn transcribe_tokens(
+    audio: &[f32],
+    sample_rate: u32,
+    offset: f64,
+    language: &str,
+    whisper_state: &WhisperContextState,
+) -> Result<Vec<TokenInfo>, String> {
+    let ctx = whisper_state.ctx.lock().unwrap();
+    if ctx.is_null() {
+        return Err("Whisper 上下文未初始化".to_string());
+    }
+
+    let mut params = unsafe { whisper_full_default_params(whisper_sampling_strategy_WHISPER_SAMPLING_GREEDY) };
+    params.token_timestamps = true;
+    params.print_progress = false;
+    params.print_realtime = false;
+    let lang_cstring = match language {
+        "" | "auto" => None,
+        code => CString::new(code).ok(),
+    };
+    params.language = lang_cstring.as_ref().map_or(std::ptr::null(), |lang| lang.as_ptr());
+
+    let mut audio_copy = whisper_input::ensure_whisper_rate(audio, sample_rate).into_owned();
+    let result = unsafe { whisper_full(*ctx, params, audio_copy.as_mut_ptr(), audio_copy.len() as i32) };
+    if result != 0 {
+        return Err(format!("Whisper 识别失败，错误码: {}", result));
+    }
+    Ok(unsafe { extract_tokens(*ctx, offset) })
+}
+
+/// 段在 16kHz 音频中的采样范围，越界部分截断
+fn segment_sample_range(start_time: f64, end_time: f64, total_samples: usize) -> (usize, usize) {
+    let start = ((start_time.max(0.0) * SAMPLE_RATE) as usize).min(total_samples);
+    let end = ((end_time.max(0.0) * SAMPLE_RATE) as usize).clamp(start, total_samples);
+    (start, end)
+}
+
+/// 返回记录中某段的 token 数据；未缓存时从保存的音频中截取该段重新解码
+#[tauri::command]
+pub async fn get_segment_tokens(
+    record_id: String,
+    segment_idx: usize,
+    app_handle: tauri::AppHandle,
+    storage_state: State<'_, StorageState>,
+) -> Result<Vec<TokenInfo>, StenoError> {
+    let cache_key = (record_id.clone(), segment_idx);
+    if let Some(tokens) = TOKEN_CACHE.lock().unwrap().get(&cache_key) {
+        return Ok(tokens.clone());
+    }
+
+    let record = storage_state.with_storage(|storage| storage.get_record(&record_id))?
+        .ok_or_else(|| StenoError::NotFound(format!("记录不存在: {}", record_id)))?;
+    let segments = record.result.as_ref()
+        .and_then(|result| result.segments.as_ref())
+        .ok_or_else(|| StenoError::NotFound("该记录没有分段结果".to_string()))?;
+    let segment = segments.get(segment_idx)
+        .ok_or_else(|| StenoError::InvalidArgument(format!("段序号 {} 超出范围（共 {} 段）", segment_idx, segments.len())))?;
+    let (start_time, end_time) = (segment.start_time, segment.end_time);
+    let language = record.config.language.clone();
+    let file_path = record.file_path.clone();
+
+    let tokens = tauri::async_runtime::spawn_blocking(move || {
+        let (audio, _, _) = load_and_convert_audio(&file_path)?;
+        let (start, end) = segment_sample_range(start_time, end_time, audio.len());
+        if start == end {
+            return Err("该段在音频中没有数据".to_string());
+        }
+        let whisper_state = app_handle.state::<WhisperContextState>();
+        transcribe_tokens(&audio[start..end], WHISPER_SAMPLE_RATE, start as f64 / SAMPLE_RATE, &language, &whisper_state)
+    })
+    .await
+    .map_err(|e| StenoError::Internal(format!("token 解码任务异常: {}", e)))?
+    .map_err(StenoError::Internal)?;
+
+    TOKEN_CACHE.lock().unwrap().insert(cache_key, tokens.clone());
+    Ok(tokens)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_model_switch_clears_cached_tokens() {
+        let token = TokenInfo { text: "你好".to_string(), probability: 0.9, start_time: 0.0, end_time: 0.5 };
+        TOKEN_CACHE.lock().unwrap().insert(("record_cache_test".to_string(), 0), vec![token]);
+        clear_token_cache();
+        assert!(!TOKEN_CACHE.lock().unwrap().contains_key(&("record_cache_test".to_string(), 0)));
+    }
+
+    #[test]
+    fn test_segment_sample_range_clamps() {
+        assert_eq!(segment_sample_range(1.0, 2.5, 160000), (16000, 40000));
+        assert_eq!(segment_sample_range(9.0, 12.0, 160000), (144000, 160000));
+        assert_eq!(segment_sample_range(11.0, 12.0, 160000), (160000, 160000));
+        assert_eq!(segment_sample_range(2.0, 1.0, 160000), (32000, 32000));
+    }
+
+    /// 需要真实模型与语音样本：STENO_TEST_MODEL 指向 ggml 模型，STENO_TEST_AUDIO 指向一段有人声的音频
+    #[cfg(feature = "whisper-integration-tests")]
+    #[test]
+    fn test_token_probabilities_and_times() {
+        let model = std::env::var("STENO_TEST_MODEL").expect("需要设置 STENO_TEST_MODEL");
+        let audio_path = std::env::var("STENO_TEST_AUDIO").expect("需要设置 STENO_TEST_AUDIO");
+        let whisper_state = WhisperContextState::new(&model).unwrap();
+        let (audio, _, _) = load_and_convert_audio(&audio_path).unwrap();
+        let (start, end) = segment_sample_range(0.0, 10.0, audio.len());
+
+        let offset = start as f64 / SAMPLE_RATE;
+        let tokens = transcribe_tokens(&audio[start..end], WHISPER_SAMPLE_RATE, offset, "auto", &whisper_state).unwrap();
+        assert!(!tokens.is_empty());
+        for token in &tokens {
+            assert!((0.0..=1.0).contains(&token.probability), "概率越界: {:?}", token);
+            assert!(token.start_time >= offset && token.start_time <= token.end_time, "时间无效: {:?}", token);
+        }
+        for pair in tokens.windows(2) {
+            assert!(pair[0].start_time <= pair[1].start_time, "时间不单调: {:?}", pair);
+        }
+    }
+}
@@ -0,0 +1,645 @@
+// transcript_export.rs - 转录结果导出（TXT / SRT / VTT / CSV / Markdown / 按说话人分组的TXT）
+use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+
+use crate::storage::{TranscriptionRecord, TranscriptionSegment};
+use crate::text_processing::is_cjk_boundary;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportFormat {
+    Txt,
+    Srt,
+    Vtt,
+    DiarizedTxt,
+    Csv,
+    Markdown,
+}
+
+impl ExportFormat {
+    pub fn parse(format: &str) -> Result<Self, String> {
+        match format.to_lowercase().as_str() {
+            "txt" => Ok(Self::Txt),
+            "srt" => Ok(Self::Srt),
+            "vtt" => Ok(Self::Vtt),
+            "diarized_txt" => Ok(Self::DiarizedTxt),
+            "csv" => Ok(Self::Csv),
+            "markdown" | "md" => Ok(Self::Markdown),
+            other => Err(format!("不支持的导出格式: {}", other)),
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Txt | Self::DiarizedTxt => "txt",
+            Self::Srt => "srt",
+            Self::Vtt => "vtt",
+            Self::Csv => "csv",
+            Self::Markdown => "md",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExportOptions {
+    pub include_speakers: bool,  // 在每段前加上说话人名称
+    pub group_by_speaker: bool,  // 合并同一说话人的连续段
+    pub include_romanization: bool, // TXT/SRT/VTT 在原文下一行附上拼音（分段有注音时）
+}
+
+/// 导出文件头部使用的记录信息（Markdown front-matter）
+#[derive(Debug, Clone, Default)]
+pub struct RecordMetadata {
+    pub title: String,
+    pub fields: Vec<(&'static str, String)>,
+}
+
+impl RecordMetadata {
+    pub fn from_record(record: &TranscriptionRecord) -> Self {
+        let mut fields = vec![
+            ("source", record.original_file_name.clone()),
+            ("created_at", record.created_at.to_rfc3339()),
+        ];
+        if let Some(duration) = record.duration {
+            fields.push(("duration", format_timestamp(duration, '.')));
+        }
+        fields.push(("language", record.config.language.clone()));
+        if let Some(category) = record.category.as_ref().filter(|c| !c.is_empty()) {
+            fields.push(("category", category.clone()));
+        }
+        if !record.tags.is_empty() {
+            fields.push(("tags", record.tags.join(", ")));
+        }
+        Self { title: record.name.clone(), fields }
+    }
+}
+
+/// 说话人ID到显示名称的映射；没有保存名称时按出现顺序命名为 Speaker A/B/C...
+pub struct SpeakerNames {
+    names: HashMap<String, String>,
+    order: Vec<String>,
+}
+
+impl SpeakerNames {
+    pub fn new(persisted: HashMap<String, String>, segments: &[TranscriptionSegment]) -> Self {
+        let mut order: Vec<String> = Vec::new();
+        for speaker in segments.iter().filter_map(|s| s.speaker.as_ref()) {
+            if !order.contains(speaker) {
+                order.push(speaker.clone());
+            }
+        }
+        Self { names: persisted, order }
+    }
+
+    pub fn resolve(&self, speaker_id: &str) -> String {
+        if let Some(name) = self.names.get(speaker_id).filter(|n| !n.trim().is_empty()) {
+            return name.clone();
+        }
+        match self.order.iter().position(|s| s == speaker_id) {
+            Some(index) => format!("Speaker {}", speaker_letter(index)),
+            None => speaker_id.to_string(),
+        }
+    }
+}
+
+fn speaker_letter(index: usize) -> String {
+    let letter = (b'A' + (index % 26) as u8) as char;
+    if index < 26 {
+        letter.to_string()
+    } else {
+        format!("{}{}", letter, index / 26)
+    }
+}
+
+/// 合并后的一段发言
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpeakerTurn {
+    pub speaker: Option<String>,
+    pub start_time: f64,
+    pub end_time: f64,
+    pub text: String,
+    pub romanization: Option<String>,
+}
+
+/// 拼接文本：CJK字符之间不加空格
+pub(crate) fn join_text(left: &str, right: &str) -> String {
+    let left = left.trim_end();
+    let right = right.trim_start();
+    if left.is_empty() {
+        return right.to_string();
+    }
+    if right.is_empty() {
+        return left.to_string();
+    }
+    if is_cjk_boundary(left, right) {
+        format!("{}{}", left, right)
+    } else {
+        format!("{} {}", left, right)
+    }
+}
+
+/// 合并同一说话人的连续段
+pub fn merge_speaker_turns(segments: &[TranscriptionSegment]) -> Vec<SpeakerTurn> {
+    let mut turns: Vec<SpeakerTurn> = Vec::new();
+    for segment in segments {
+        if segment.text.trim().is_empty() {
+            continue;
+        }
+        match turns.last_mut() {
+            Some(turn) if turn.speaker == segment.speaker => {
+                turn.text = join_text(&turn.text, &segment.text);
+                turn.end_time = turn.end_time.max(segment.end_time);
+                if let Some(romanization) = &segment.romanization {
+                    turn.romanization = Some(match turn.romanization.take() {
+                        Some(previous) => format!("{} {}", previous, romanization),
+                        None => romanization.clone(),
+                    });
+                }
+            }
+            _ => turns.push(SpeakerTurn {
+                speaker: segment.speaker.clone(),
+                start_time: segment.start_time,
+                end_time: segment.end_time,
+                text: segment.text.trim().to_string(),
+                romanization: segment.romanization.clone(),
+            }),
+        }
+    }
+    turns
+}
+
+fn segments_as_turns(segments: &[TranscriptionSegment], options: &ExportOptions) -> Vec<SpeakerTurn> {
+    if options.group_by_speaker {
+        return merge_speaker_turns(segments);
+    }
+    segments.iter()
+        .filter(|s| !s.text.trim().is_empty())
+        .map(|s| SpeakerTurn {
+            speaker: s.speaker.clone(),
+            start_time: s.start_time,
+            end_time: s.end_time,
+            text: s.text.trim().to_string(),
+            romanization: s.romanization.clone(),
+        })
+        .collect()
+}
+
+/// 选项开启且该段有注音时，在文本下一行附上拼音
+fn with_romanization(text: String, turn: &SpeakerTurn, options: &ExportOptions) -> String {
+    match turn.romanization.as_deref().filter(|_| options.include_romanization) {
+        Some(romanization) => format!("{}\n{}", text, romanization),
+        None => text,
+    }
+}
+
+fn format_timestamp(seconds: f64, millis_separator: char) -> String {
+    let total_ms = (seconds.max(0.0) * 1000.0).round() as u64;
+    format!(
+        "{:02}:{:02}:{:02}{}{:03}",
+        total_ms / 3_600_000,
+        (total_ms / 60_000) % 60,
+        (total_ms / 1000) % 60,
+        millis_separator,
+        total_ms % 1000
+    )
+}
+
+fn speaker_label(turn: &SpeakerTurn, names: &SpeakerNames) -> Option<String> {
+    turn.speaker.as_deref().map(|id| names.resolve(id))
+}
+
+/// 按格式渲染导出内容；没有分段信息时TXT导出完整文本
+pub fn render_transcript(
+    full_text: &str,
+    segments: &[TranscriptionSegment],
+    format: ExportFormat,
+    options: &ExportOptions,
+    names: &SpeakerNames,
+    metadata: &RecordMetadata,
+) -> String {
+    match format {
+        ExportFormat::Txt => {
+            if segments.is_empty() || !(options.include_speakers || options.include_romanization) {
+                return full_text.trim().to_string();
+            }
+            segments_as_turns(segments, options).iter()
+                .map(|turn| {
+                    let text = match speaker_label(turn, names).filter(|_| options.include_speakers) {
+                        Some(label) => format!("{}: {}", label, turn.text),
+                        None => turn.text.clone(),
+                    };
+                    with_romanization(text, turn, options)
+                })
+                .collect::<Vec<_>>()
+                .join("\n")
+        }
+        ExportFormat::DiarizedTxt => {
+            if segments.is_empty() {
+                return full_text.trim().to_string();
+            }
+            merge_speaker_turns(segments).iter()
+                .map(|turn| match speaker_label(turn, names) {
+                    Some(label) => format!("{}: {}", label, turn.text),
+                    None => turn.text.clone(),
+                })
+                .collect::<Vec<_>>()
+                .join("\n")
+        }
+        ExportFormat::Srt => render_srt_cues(&segments_as_turns(segments, options), names, options, 1),
+        ExportFormat::Vtt => {
+            let mut output = String::from("WEBVTT\n\n");
+            for turn in segments_as_turns(segments, options) {
+                // WebVTT 使用 <v 名称> 标注说话人
+                let text = match speaker_label(&turn, names).filter(|_| options.include_speakers) {
+                    Some(label) => format!("<v {}>{}", label, turn.text),
+                    None => turn.text.clone(),
+                };
+                let text = with_romanization(text, &turn, options);
+                output.push_str(&format!(
+                    "{} --> {}\n{}\n\n",
+                    format_timestamp(turn.start_time, '.'),
+                    format_timestamp(turn.end_time, '.'),
+                    text
+                ));
+            }
+            output
+        }
+        ExportFormat::Csv => render_csv(segments, names),
+        ExportFormat::Markdown => render_markdown(full_text, segments, names, metadata),
+    }
+}
+
+/// SRT 字幕块，序号从 first_index 开始
+fn render_srt_cues(turns: &[SpeakerTurn], names: &SpeakerNames, options: &ExportOptions, first_index: usize) -> String {
+    let mut output = String::new();
+    for (index, turn) in turns.iter().enumerate() {
+        let text = match speaker_label(turn, names).filter(|_| options.include_speakers) {
+            Some(label) => format!("[{}] {}", label, turn.text),
+            None => turn.text.clone(),
+        };
+        let text = with_romanization(text, turn, options);
+        output.push_str(&format!(
+            "{}\n{} --> {}\n{}\n\n",
+            first_index + index,
+            format_timestamp(turn.start_time, ','),
+            format_timestamp(turn.end_time, ','),
+            text
+        ));
+    }
+    output
+}
+
+fn format_anchor_time(seconds: f64) -> String {
+    let total = seconds.max(0.0) as u64;
+    format!("{:02}:{:02}", total / 60, total % 60)
+}
+
+/// YAML front-matter：值统一使用JSON字符串转义（同时是合法的YAML双引号字符串）
+fn render_front_matter(metadata: &RecordMetadata) -> String {
+    let quote = |value: &str| serde_json::to_string(value).unwrap_or_else(|_| "\"\"".to_string());
+    let mut header = String::from("---\n");
+    header.push_str(&format!("title: {}\n", quote(&metadata.title)));
+    for (key, value) in &metadata.fields {
+        header.push_str(&format!("{}: {}\n", key, quote(value)));
+    }
+    header.push_str("---\n");
+    header
+}
+
+/// Markdown：front-matter 加标题，同一说话人的连续段合并为一个引用块，时间锚点可点击跳转
+fn render_markdown(
+    full_text: &str,
+    segments: &[TranscriptionSegment],
+    names: &SpeakerNames,
+    metadata: &RecordMetadata,
+) -> String {
+    let mut output = render_front_matter(metadata);
+    if !metadata.title.is_empty() {
+        output.push_str(&format!("\n# {}\n", metadata.title));
+    }
+    output.push_str(&render_markdown_body(full_text, segments, names));
+    output
+}
+
+/// Markdown 正文：没有分段时输出完整文本
+fn render_markdown_body(full_text: &str, segments: &[TranscriptionSegment], names: &SpeakerNames) -> String {
+    if segments.is_empty() {
+        return format!("\n{}\n", full_text.trim());
+    }
+
+    let mut output = String::new();
+    for turn in merge_speaker_turns(segments) {
+        let anchor = format!("[{}](#t={})", format_anchor_time(turn.start_time), turn.start_time.max(0.0) as u64);
+        match speaker_label(&turn, names) {
+            Some(label) => output.push_str(&format!("\n> {} **{}**\n>\n", anchor, label)),
+            None => output.push_str(&format!("\n> {}\n>\n", anchor)),
+        }
+        for line in turn.text.lines() {
+            output.push_str(&format!("> {}\n", line));
+        }
+    }
+    output
+}
+
+/// CSV：每段一行 start,end,speaker,confidence,text，转义交给 csv crate 处理
+fn render_csv(segments: &[TranscriptionSegment], names: &SpeakerNames) -> String {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    let _ = writer.write_record(["start", "end", "speaker", "confidence", "text"]);
+    write_csv_rows(&mut writer, None, segments, names);
+    finish_csv(writer)
+}
+
+/// 提供 record 时在每行前加上记录标题一列（合并导出使用）
+fn write_csv_rows(writer: &mut csv::Writer<Vec<u8>>, record: Option<&str>, segments: &[TranscriptionSegment], names: &SpeakerNames) {
+    for segment in segments.iter().filter(|s| !s.text.trim().is_empty()) {
+        let mut row: Vec<String> = record.map(str::to_string).into_iter().collect();
+        row.extend([
+            format_timestamp(segment.start_time, '.'),
+            format_timestamp(segment.end_time, '.'),
+            segment.speaker.as_deref().map(|id| names.resolve(id)).unwrap_or_default(),
+            segment.confidence.map(|c| format!("{:.3}", c)).unwrap_or_default(),
+            segment.text.trim().to_string(),
+        ]);
+        let _ = writer.write_record(&row);
+    }
+}
+
+fn finish_csv(writer: csv::Writer<Vec<u8>>) -> String {
+    writer.into_inner()
+        .ok()
+        .and_then(|bytes| String::from_utf8(bytes).ok())
+        .unwrap_or_default()
+}
+
+/// 合并导出的时间戳
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CombinedTimestamps {
+    #[default]
+    PerRecord,  // 每条记录从0开始
+    Cumulative, // 按导出顺序首尾相接
+}
+
+/// 合并导出中的一条记录
+pub struct ExportSection {
+    pub full_text: String,
+    pub segments: Vec<TranscriptionSegment>,
+    pub names: SpeakerNames,
+    pub metadata: RecordMetadata,
+    pub duration: Option<f64>,
+}
+
+impl ExportSection {
+    /// 累计时间戳时该记录占用的时长；记录时长未知时取最后一段的结束时间
+    fn span(&self) -> f64 {
+        self.duration.unwrap_or_else(|| self.segments.iter().map(|s| s.end_time).fold(0.0, f64::max))
+    }
+}
+
+/// 按给定顺序拼接多条记录，每条记录前插入标题；SRT 没有注释语法，只连续编号
+pub fn render_combined(
+    sections: &[ExportSection],
+    format: ExportFormat,
+    options: &ExportOptions,
+    timestamps: CombinedTimestamps,
+) -> String {
+    let mut output = String::new();
+    let mut csv_writer = csv::Writer::from_writer(Vec::new());
+    match format {
+        ExportFormat::Vtt => output.push_str("WEBVTT\n\n"),
+        ExportFormat::Csv => {
+            let _ = csv_writer.write_record(["record", "start", "end", "speaker", "confidence", "text"]);
+        }
+        _ => {}
+    }
+
+    let mut offset = 0.0;
+    let mut srt_index = 1;
+    for (i, section) in sections.iter().enumerate() {
+        let segments: Vec<TranscriptionSegment> = section.segments.iter()
+            .map(|segment| TranscriptionSegment {
+                start_time: segment.start_time + offset,
+                end_time: segment.end_time + offset,
+                ..segment.clone()
+            })
+            .collect();
+        let title = &section.metadata.title;
+
+        match format {
+            ExportFormat::Txt | ExportFormat::DiarizedTxt => {
+                if i > 0 {
+                    output.push_str("\n\n");
+                }
+                output.push_str(&format!("=== {} ===\n", title));
+                output.push_str(&render_transcript(&section.full_text, &segments, format, options, &section.names, &section.metadata));
+            }
+            ExportFormat::Srt => {
+                let turns = segments_as_turns(&segments, options);
+                output.push_str(&render_srt_cues(&turns, &section.names, options, srt_index));
+                srt_index += turns.len();
+            }
+            ExportFormat::Vtt => {
+                // NOTE 块中不能出现 "-->"
+                output.push_str(&format!("NOTE {}\n\n", title.replace("-->", "->")));
+                let cues = render_transcript(&section.full_text, &segments, format, options, &section.names, &section.metadata);
+                output.push_str(cues.strip_prefix("WEBVTT\n\n").unwrap_or(&cues));
+            }
+            ExportFormat::Markdown => {
+                if i > 0 {
+                    output.push('\n');
+                }
+                output.push_str(&format!("# {}\n", title));
+                output.push_str(&render_markdown_body(&section.full_text, &segments, &section.names));
+            }
+            ExportFormat::Csv => write_csv_rows(&mut csv_writer, Some(title), &segments, &section.names),
+        }
+
+        if timestamps == CombinedTimestamps::Cumulative {
+            offset += section.span();
+        }
+    }
+
+    if format == ExportFormat::Csv {
+        return finish_csv(csv_writer);
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::test_support::sample_segment;
+
+    fn segment(start_time: f64, end_time: f64, speaker: Option<&str>, text: &str) -> TranscriptionSegment {
+        TranscriptionSegment {
+            speaker: speaker.map(|s| s.to_string()),
+            ..sample_segment(&format!("seg_{}", start_time), start_time, end_time, text)
+        }
+    }
+
+    #[test]
+    fn test_group_consecutive_turns() {
+        let segments = vec![
+            segment(0.0, 2.0, Some("spk_0"), "大家好，"),
+            segment(2.0, 4.0, Some("spk_0"), "今天开会。"),
+            segment(4.0, 6.0, Some("spk_1"), "Hello"),
+            segment(6.0, 7.0, Some("spk_1"), "everyone."),
+            segment(7.0, 9.0, Some("spk_0"), "开始吧。"),
+        ];
+
+        let turns = merge_speaker_turns(&segments);
+        assert_eq!(turns.len(), 3);
+        assert_eq!(turns[0].text, "大家好，今天开会。");
+        assert_eq!((turns[0].start_time, turns[0].end_time), (0.0, 4.0));
+        assert_eq!(turns[1].text, "Hello everyone.");
+
+        let names = SpeakerNames::new(HashMap::new(), &segments);
+        let output = render_transcript("", &segments, ExportFormat::DiarizedTxt, &ExportOptions::default(), &names, &RecordMetadata::default());
+        assert_eq!(output, "Speaker A: 大家好，今天开会。\nSpeaker B: Hello everyone.\nSpeaker A: 开始吧。");
+    }
+
+    #[test]
+    fn test_speaker_name_resolution() {
+        let segments = vec![
+            segment(0.0, 1.5, Some("spk_0"), "你好"),
+            segment(1.5, 3.0, Some("spk_1"), "您好"),
+        ];
+        let persisted = HashMap::from([("spk_1".to_string(), "王经理".to_string())]);
+        let names = SpeakerNames::new(persisted, &segments);
+        assert_eq!(names.resolve("spk_0"), "Speaker A");
+        assert_eq!(names.resolve("spk_1"), "王经理");
+
+        let options = ExportOptions { include_speakers: true, ..ExportOptions::default() };
+        let srt = render_transcript("", &segments, ExportFormat::Srt, &options, &names, &RecordMetadata::default());
+        assert_eq!(srt, "1\n00:00:00,000 --> 00:00:01,500\n[Speaker A] 你好\n\n2\n00:00:01,500 --> 00:00:03,000\n[王经理] 您好\n\n");
+
+        let vtt = render_transcript("", &segments, ExportFormat::Vtt, &options, &names, &RecordMetadata::default());
+        assert!(vtt.starts_with("WEBVTT\n\n00:00:00.000 --> 00:00:01.500\n<v Speaker A>你好"));
+
+        // 未开启说话人选项时TXT保持原样
+        let txt = render_transcript("你好您好", &segments, ExportFormat::Txt, &ExportOptions::default(), &names, &RecordMetadata::default());
+        assert_eq!(txt, "你好您好");
+    }
+
+    #[test]
+    fn test_romanization_export() {
+        let mut segments = vec![
+            segment(0.0, 1.5, Some("spk_0"), "你好"),
+            segment(1.5, 3.0, Some("spk_0"), "开会"),
+            segment(3.0, 4.0, Some("spk_1"), "OK"),
+        ];
+        segments[0].romanization = Some("nǐ hǎo".to_string());
+        segments[1].romanization = Some("kāi huì".to_string());
+        let names = SpeakerNames::new(HashMap::new(), &segments);
+        let options = ExportOptions { include_romanization: true, ..ExportOptions::default() };
+
+        let srt = render_transcript("", &segments, ExportFormat::Srt, &options, &names, &RecordMetadata::default());
+        assert!(srt.starts_with("1\n00:00:00,000 --> 00:00:01,500\n你好\nnǐ hǎo\n\n2\n"));
+        assert!(srt.ends_with("00:00:03,000 --> 00:00:04,000\nOK\n\n"));
+
+        // 合并说话人时拼音随文本一起合并
+        let grouped = ExportOptions { include_speakers: true, group_by_speaker: true, include_romanization: true };
+        let txt = render_transcript("你好开会OK", &segments, ExportFormat::Txt, &grouped, &names, &RecordMetadata::default());
+        assert_eq!(txt, "Speaker A: 你好开会\nnǐ hǎo kāi huì\nSpeaker B: OK");
+
+        // 未开启选项时不输出拼音
+        let txt = render_transcript("你好开会OK", &segments, ExportFormat::Txt, &ExportOptions::default(), &names, &RecordMetadata::default());
+        assert_eq!(txt, "你好开会OK");
+    }
+
+    #[test]
+    fn test_csv_export_escapes_text() {
+        let mut segments = vec![
+            segment(0.0, 1.25, Some("spk_0"), "价格是1,000元"),
+            segment(1.25, 3.0, Some("spk_1"), "他说\"好的\""),
+            segment(3.0, 4.5, None, "第一行\n第二行"),
+        ];
+        segments[0].confidence = Some(0.91234);
+        let names = SpeakerNames::new(HashMap::from([("spk_1".to_string(), "李, 工程师".to_string())]), &segments);
+
+        let csv = render_transcript("", &segments, ExportFormat::Csv, &ExportOptions::default(), &names, &RecordMetadata::default());
+        assert!(csv.starts_with("start,end,speaker,confidence,text\n"));
+        assert!(csv.contains("00:00:00.000,00:00:01.250,Speaker A,0.912,\"价格是1,000元\"\n"));
+        assert!(csv.contains("00:00:01.250,00:00:03.000,\"李, 工程师\",,\"他说\"\"好的\"\"\"\n"));
+        assert!(csv.contains("00:00:03.000,00:00:04.500,,,\"第一行\n第二行\"\n"));
+        assert_eq!(ExportFormat::parse("CSV").unwrap().extension(), "csv");
+    }
+
+    #[test]
+    fn test_markdown_front_matter() {
+        let metadata = RecordMetadata {
+            title: "周会 \"Q3\" 复盘".to_string(),
+            fields: vec![("created_at", "2024-05-01T09:30:00+00:00".to_string()), ("language", "zh".to_string())],
+        };
+        let markdown = render_transcript("全文内容", &[], ExportFormat::Markdown, &ExportOptions::default(),
+            &SpeakerNames::new(HashMap::new(), &[]), &metadata);
+        assert_eq!(
+            markdown,
+            "---\ntitle: \"周会 \\\"Q3\\\" 复盘\"\ncreated_at: \"2024-05-01T09:30:00+00:00\"\nlanguage: \"zh\"\n---\n\n# 周会 \"Q3\" 复盘\n\n全文内容\n"
+        );
+    }
+
+    fn section(title: &str, duration: f64, segments: Vec<TranscriptionSegment>) -> ExportSection {
+        ExportSection {
+            full_text: segments.iter().map(|s| s.text.clone()).collect(),
+            names: SpeakerNames::new(HashMap::new(), &segments),
+            segments,
+            metadata: RecordMetadata { title: title.to_string(), fields: Vec::new() },
+            duration: Some(duration),
+        }
+    }
+
+    fn meeting_sections() -> Vec<ExportSection> {
+        vec![
+            section("上午", 60.0, vec![segment(1.0, 3.0, Some("spk_0"), "开始。"), segment(58.0, 59.5, Some("spk_1"), "休息。")]),
+            section("下午", 30.0, vec![segment(2.0, 4.0, Some("spk_0"), "继续。")]),
+        ]
+    }
+
+    #[test]
+    fn test_combined_order_and_headers() {
+        let mut sections = meeting_sections();
+        let txt = render_combined(&sections, ExportFormat::DiarizedTxt, &ExportOptions::default(), CombinedTimestamps::PerRecord);
+        assert_eq!(txt, "=== 上午 ===\nSpeaker A: 开始。\nSpeaker B: 休息。\n\n=== 下午 ===\nSpeaker A: 继续。");
+
+        sections.reverse();
+        let markdown = render_combined(&sections, ExportFormat::Markdown, &ExportOptions::default(), CombinedTimestamps::PerRecord);
+        assert_eq!(
+            markdown,
+            "# 下午\n\n> [00:02](#t=2) **Speaker A**\n>\n> 继续。\n\n# 上午\n\n> [00:01](#t=1) **Speaker A**\n>\n> 开始。\n\n> [00:58](#t=58) **Speaker B**\n>\n> 休息。\n"
+        );
+
+        let vtt = render_combined(&sections, ExportFormat::Vtt, &ExportOptions::default(), CombinedTimestamps::PerRecord);
+        assert!(vtt.starts_with("WEBVTT\n\nNOTE 下午\n\n00:00:02.000 --> 00:00:04.000\n继续。\n\nNOTE 上午\n\n"));
+        assert_eq!(vtt.matches("WEBVTT").count(), 1);
+    }
+
+    #[test]
+    fn test_combined_timestamps() {
+        let sections = meeting_sections();
+        let srt = render_combined(&sections, ExportFormat::Srt, &ExportOptions::default(), CombinedTimestamps::Cumulative);
+        assert!(srt.contains("2\n00:00:58,000 --> 00:00:59,500\n休息。"));
+        // 第二条记录的时间接在第一条记录的60秒之后，序号连续
+        assert!(srt.ends_with("3\n00:01:02,000 --> 00:01:04,000\n继续。\n\n"));
+
+        let csv = render_combined(&sections, ExportFormat::Csv, &ExportOptions::default(), CombinedTimestamps::PerRecord);
+        assert!(csv.starts_with("record,start,end,speaker,confidence,text\n上午,00:00:01.000"));
+        assert!(csv.ends_with("下午,00:00:02.000,00:00:04.000,Speaker A,,继续。\n"));
+    }
+
+    #[test]
+    fn test_markdown_groups_speaker_turns() {
+        let segments = vec![
+            segment(0.0, 2.0, Some("spk_0"), "大家好，"),
+            segment(2.0, 4.0, Some("spk_0"), "今天开会。"),
+            segment(65.0, 68.0, Some("spk_1"), "好的。"),
+        ];
+        let names = SpeakerNames::new(HashMap::new(), &segments);
+        let markdown = render_transcript("", &segments, ExportFormat::Markdown, &ExportOptions::default(), &names, &RecordMetadata::default());
+        assert_eq!(
+            markdown,
+            "---\ntitle: \"\"\n---\n\n> [00:00](#t=0) **Speaker A**\n>\n> 大家好，今天开会。\n\n> [01:05](#t=65) **Speaker B**\n>\n> 好的。\n"
+        );
+    }
+}
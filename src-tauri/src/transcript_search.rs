@@ -0,0 +1,136 @@
+// transcript_search.rs - 在单条记录的转录内容中查找关键词，返回每处匹配所在的段与字符位置，供界面跳转
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::errors::StenoError;
+use crate::storage::TranscriptionSegment;
+use crate::storage_commands::StorageState;
+use crate::text_processing::normalize_with_offsets;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchMode {
+    #[default]
+    Normalized,      // 全半角、大小写、繁简差异都忽略（与记录库检索一致）
+    CaseInsensitive, // 只忽略大小写
+    Exact,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TranscriptMatch {
+    pub segment_id: Option<String>, // 记录没有分段结果时为空，位置相对整段转录文本
+    pub start: usize, // 匹配起止位置，按字符（而不是字节）计，相对所在段的文本
+    pub end: usize,
+    pub start_time: Option<f64>, // 所在段的开始时间
+}
+
+/// 按匹配方式折叠文本，每个字符附带它在原文中的字符序号
+fn fold(text: &str, mode: MatchMode) -> Vec<(char, usize)> {
+    match mode {
+        MatchMode::Normalized => normalize_with_offsets(text, true),
+        MatchMode::CaseInsensitive => text.chars()
+            .enumerate()
+            .flat_map(|(index, c)| c.to_lowercase().map(move |c| (c, index)))
+            .collect(),
+        MatchMode::Exact => text.chars().enumerate().map(|(index, c)| (c, index)).collect(),
+    }
+}
+
+/// 文本中所有不重叠的匹配，返回原文中的 (起, 止) 字符位置
+fn match_spans(text: &str, query: &[char], mode: MatchMode) -> Vec<(usize, usize)> {
+    let folded = fold(text, mode);
+    let mut spans = Vec::new();
+    let mut i = 0;
+    while !query.is_empty() && i + query.len() <= folded.len() {
+        if folded[i..i + query.len()].iter().map(|(c, _)| c).eq(query.iter()) {
+            spans.push((folded[i].1, folded[i + query.len() - 1].1 + 1));
+            i += query.len();
+        } else {
+            i += 1;
+        }
+    }
+    spans
+}
+
+/// 逐段查找关键词；跨越两段的内容不作为匹配
+pub fn find_matches(segments: &[TranscriptionSegment], query: &str, mode: MatchMode) -> Vec<TranscriptMatch> {
+    let query: Vec<char> = fold(query.trim(), mode).into_iter().map(|(c, _)| c).collect();
+    segments.iter()
+        .flat_map(|segment| {
+            match_spans(&segment.text, &query, mode).into_iter().map(|(start, end)| TranscriptMatch {
+                segment_id: Some(segment.id.clone()),
+                start,
+                end,
+                start_time: Some(segment.start_time),
+            })
+        })
+        .collect()
+}
+
+/// 在一条记录的转录中查找 query；有分段结果时按段返回，否则在整段文本中查找
+#[tauri::command]
+pub async fn find_in_transcript(
+    record_id: String,
+    query: String,
+    mode: Option<MatchMode>,
+    storage_state: State<'_, StorageState>,
+) -> Result<Vec<TranscriptMatch>, StenoError> {
+    let record = storage_state.with_storage(|storage| storage.get_record(&record_id))?
+        .ok_or_else(|| StenoError::NotFound(format!("记录不存在: {}", record_id)))?;
+    let result = record.result.ok_or_else(|| StenoError::NotFound("该记录还没有转录结果".to_string()))?;
+    let mode = mode.unwrap_or_default();
+
+    match result.segments.filter(|segments| !segments.is_empty()) {
+        Some(segments) => Ok(find_matches(&segments, &query, mode)),
+        None => {
+            let query: Vec<char> = fold(query.trim(), mode).into_iter().map(|(c, _)| c).collect();
+            Ok(match_spans(&result.text, &query, mode).into_iter()
+                .map(|(start, end)| TranscriptMatch { segment_id: None, start, end, start_time: None })
+                .collect())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::test_support::sample_segment;
+
+    fn segment(id: &str, start_time: f64, text: &str) -> TranscriptionSegment {
+        sample_segment(id, start_time, start_time + 2.0, text)
+    }
+
+    fn spans(matches: &[TranscriptMatch]) -> Vec<(Option<&str>, usize, usize)> {
+        matches.iter().map(|m| (m.segment_id.as_deref(), m.start, m.end)).collect()
+    }
+
+    #[test]
+    fn test_multiple_matches_across_segments() {
+        let segments = vec![
+            segment("s0", 0.0, "预算会议讨论预算。"),
+            segment("s1", 2.0, "没有相关内容"),
+            segment("s2", 4.0, "下季度预算待定"),
+        ];
+        let matches = find_matches(&segments, "预算", MatchMode::Normalized);
+        assert_eq!(spans(&matches), vec![(Some("s0"), 0, 2), (Some("s0"), 6, 8), (Some("s2"), 3, 5)]);
+        assert_eq!(matches[2].start_time, Some(4.0));
+
+        // 匹配不重叠，空查询没有结果
+        assert_eq!(spans(&find_matches(&[segment("s0", 0.0, "aaaa")], "aa", MatchMode::Exact)), vec![(Some("s0"), 0, 2), (Some("s0"), 2, 4)]);
+        assert!(find_matches(&segments, "  ", MatchMode::Normalized).is_empty());
+    }
+
+    #[test]
+    fn test_full_and_half_width_normalization() {
+        let segments = vec![segment("s0", 0.0, "使用ＧＰＴ－４和Steno"), segment("s1", 2.0, "gpt-4 很快")];
+
+        let normalized = find_matches(&segments, "gpt-4", MatchMode::Normalized);
+        assert_eq!(spans(&normalized), vec![(Some("s0"), 2, 7), (Some("s1"), 0, 5)]);
+        assert_eq!(spans(&find_matches(&segments, "ＳＴＥＮＯ", MatchMode::Normalized)), vec![(Some("s0"), 8, 13)]);
+
+        // 只忽略大小写时全角字符不匹配，精确匹配区分大小写
+        assert_eq!(spans(&find_matches(&segments, "GPT-4", MatchMode::CaseInsensitive)), vec![(Some("s1"), 0, 5)]);
+        assert_eq!(spans(&find_matches(&segments, "steno", MatchMode::Exact)), vec![]);
+        assert_eq!(spans(&find_matches(&segments, "Steno", MatchMode::Exact)), vec![(Some("s0"), 8, 13)]);
+    }
+}
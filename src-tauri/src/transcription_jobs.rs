@@ -0,0 +1,139 @@
+// transcription_jobs.rs - 进行中的单文件转录任务登记与取消
+use std::collections::HashMap;
+use std::os::raw::c_void;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use crate::storage::StorageService;
+use crate::whisper_full_params;
+
+pub const STATUS_CANCELLED: &str = "cancelled";
+
+/// 取消令牌：通过 Whisper 的 abort 回调中断正在进行的推理
+#[derive(Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+
+    /// 将 abort 回调挂到 Whisper 参数上；令牌必须在 whisper_full 返回前保持有效
+    pub fn install(&self, params: &mut whisper_full_params) {
+        params.abort_callback = Some(whisper_abort_callback);
+        params.abort_callback_user_data = Arc::as_ptr(&self.0) as *mut c_void;
+    }
+}
+
+unsafe extern "C" fn whisper_abort_callback(user_data: *mut c_void) -> bool {
+    if user_data.is_null() {
+        return false;
+    }
+    (*(user_data as *const AtomicBool)).load(Ordering::SeqCst)
+}
+
+/// 按记录ID登记的进行中转录任务
+#[derive(Default)]
+pub struct TranscriptionJobRegistry {
+    jobs: Mutex<HashMap<String, CancellationToken>>,
+}
+
+impl TranscriptionJobRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 登记新任务；同一记录已有任务在运行时返回错误
+    pub fn register(&self, record_id: &str) -> Result<CancellationToken, String> {
+        let mut jobs = self.jobs.lock().unwrap();
+        if jobs.contains_key(record_id) {
+            return Err(format!("记录 {} 已有转录任务在进行中", record_id));
+        }
+        let token = CancellationToken::new();
+        jobs.insert(record_id.to_string(), token.clone());
+        Ok(token)
+    }
+
+    /// 任务结束（无论成功、失败或取消）后移除登记
+    pub fn finish(&self, record_id: &str) {
+        self.jobs.lock().unwrap().remove(record_id);
+    }
+
+    /// 取消指定记录的任务，返回是否找到该任务
+    pub fn cancel(&self, record_id: &str) -> bool {
+        match self.jobs.lock().unwrap().get(record_id) {
+            Some(token) => {
+                token.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn cancel_all(&self) {
+        for token in self.jobs.lock().unwrap().values() {
+            token.cancel();
+        }
+    }
+
+    pub fn is_active(&self, record_id: &str) -> bool {
+        self.jobs.lock().unwrap().contains_key(record_id)
+    }
+
+    pub fn has_active_jobs(&self) -> bool {
+        !self.jobs.lock().unwrap().is_empty()
+    }
+}
+
+/// 取消任务并将记录标记为已取消（部分识别结果不会写入记录）
+pub fn cancel_job(registry: &TranscriptionJobRegistry, storage: &StorageService, record_id: &str) -> Result<(), String> {
+    if !registry.cancel(record_id) {
+        return Err(format!("记录 {} 没有正在进行的转录任务", record_id));
+    }
+    storage
+        .update_record_status(record_id, STATUS_CANCELLED, 0.0, None)
+        .map_err(|e| format!("更新记录状态失败: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::test_support::{sample_record, temp_storage};
+
+    #[test]
+    fn test_cancel_aborts_job_and_updates_status() {
+        let (storage, dir) = temp_storage("cancel_job");
+        storage.save_record(&sample_record("record_1")).unwrap();
+        storage.update_record_status("record_1", "processing", 60.0, None).unwrap();
+
+        let registry = TranscriptionJobRegistry::new();
+        let token = registry.register("record_1").unwrap();
+        assert!(registry.register("record_1").is_err());
+
+        // abort 回调在取消前返回 false，取消后返回 true
+        let user_data = Arc::as_ptr(&token.0) as *mut c_void;
+        assert!(!unsafe { whisper_abort_callback(user_data) });
+
+        cancel_job(&registry, &storage, "record_1").unwrap();
+        assert!(unsafe { whisper_abort_callback(user_data) });
+        assert!(token.is_cancelled());
+
+        let record = storage.get_record("record_1").unwrap().unwrap();
+        assert_eq!(record.status, STATUS_CANCELLED);
+        assert!(record.result.is_none());
+
+        registry.finish("record_1");
+        assert!(!registry.is_active("record_1"));
+        assert!(cancel_job(&registry, &storage, "record_1").is_err());
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,229 @@
+// transcription_progress.rs - 单文件转录的真实进度跟踪（基于 Whisper 进度回调）
+use std::os::raw::{c_int, c_void};
+use std::time::{Duration, Instant};
+
+use crate::{whisper_context, whisper_full_params, whisper_state};
+
+/// Whisper 推理在整体进度中占用的区间（之前是音频转换，之后是后处理）
+pub const INFERENCE_PROGRESS_START: f64 = 50.0;
+pub const INFERENCE_PROGRESS_END: f64 = 95.0;
+
+/// 节流：进度至少前进1%且距上次上报至少250ms才会再次上报
+const MIN_PROGRESS_STEP: f64 = 1.0;
+const MIN_REPORT_INTERVAL: Duration = Duration::from_millis(250);
+
+/// 实时倍率（xRT）：音频时长 / 处理耗时，大于1表示快于实时
+pub fn realtime_factor(audio_seconds: f64, processing_seconds: f64) -> Option<f64> {
+    (audio_seconds > 0.0 && processing_seconds > 0.0).then(|| audio_seconds / processing_seconds)
+}
+
+/// 剩余时间估算中吞吐量的平滑系数，越小越平稳
+const ETA_SMOOTHING: f64 = 0.3;
+
+/// 进度上报：(整体进度 0-100, 已处理音频秒数, 音频总秒数, 预计剩余秒数)
+pub type ProgressSink = Box<dyn FnMut(f64, f64, f64, Option<f64>) + Send>;
+
+/// 按吞吐量（已处理音频秒数 / 推理耗时）估算剩余时间；吞吐量做指数平滑，避免开始阶段估算大幅跳动
+pub struct EtaEstimator {
+    started_at: Option<Instant>,
+    throughput: Option<f64>,
+}
+
+impl EtaEstimator {
+    pub fn new() -> Self {
+        Self {
+            started_at: None,
+            throughput: None,
+        }
+    }
+
+    /// 从第一次调用开始计时，之后的调用不改变起点
+    pub fn start(&mut self, now: Instant) {
+        self.started_at.get_or_insert(now);
+    }
+
+    pub fn update(&mut self, processed: f64, total: f64, now: Instant) -> Option<f64> {
+        let elapsed = now.duration_since(*self.started_at.get_or_insert(now)).as_secs_f64();
+        if elapsed <= 0.0 || processed <= 0.0 {
+            return None;
+        }
+
+        let current = processed / elapsed;
+        let throughput = match self.throughput {
+            Some(previous) => previous + ETA_SMOOTHING * (current - previous),
+            None => current,
+        };
+        self.throughput = Some(throughput);
+        Some((total - processed).max(0.0) / throughput)
+    }
+}
+
+/// 按已处理音频时长 / 总时长计算进度，支持分段识别时逐段累加
+pub struct ProgressTracker {
+    total_duration: f64,
+    completed_duration: f64, // 已完成段的累计时长
+    chunk_duration: f64,     // 当前正在识别的段时长
+    last_reported: f64,
+    last_report_at: Option<Instant>,
+    eta: EtaEstimator,
+    sink: ProgressSink,
+}
+
+impl ProgressTracker {
+    pub fn new(total_duration: f64, sink: ProgressSink) -> Self {
+        Self {
+            total_duration: total_duration.max(0.0),
+            completed_duration: 0.0,
+            chunk_duration: 0.0,
+            last_reported: INFERENCE_PROGRESS_START,
+            last_report_at: None,
+            eta: EtaEstimator::new(),
+            sink,
+        }
+    }
+
+    /// 分段识别时总时长为各段时长之和，而不是原始音频长度
+    pub fn set_total_duration(&mut self, total_duration: f64) {
+        self.total_duration = total_duration.max(0.0);
+    }
+
+    /// 开始识别下一段音频，上一段视为已完成
+    pub fn begin_chunk(&mut self, chunk_duration: f64) {
+        // 从第一段开始推理时计时，不计入之前的音频预处理
+        self.eta.start(Instant::now());
+        self.completed_duration += self.chunk_duration;
+        self.chunk_duration = chunk_duration.max(0.0);
+    }
+
+    /// Whisper 报告当前段的完成百分比（0-100）
+    pub fn on_whisper_progress(&mut self, percent: i32) {
+        self.on_whisper_progress_at(percent, Instant::now());
+    }
+
+    fn on_whisper_progress_at(&mut self, percent: i32, now: Instant) {
+        let fraction = percent.clamp(0, 100) as f64 / 100.0;
+        let processed = (self.completed_duration + self.chunk_duration * fraction).min(self.total_duration);
+        let overall = if self.total_duration > 0.0 {
+            INFERENCE_PROGRESS_START
+                + (INFERENCE_PROGRESS_END - INFERENCE_PROGRESS_START) * processed / self.total_duration
+        } else {
+            INFERENCE_PROGRESS_START
+        };
+
+        // 进度只增不减（分段识别时 Whisper 会为每段重新从0开始计数）
+        if overall <= self.last_reported {
+            return;
+        }
+
+        let reached_end = overall >= INFERENCE_PROGRESS_END;
+        let throttled = overall - self.last_reported < MIN_PROGRESS_STEP
+            || self.last_report_at.is_some_and(|at| now.duration_since(at) < MIN_REPORT_INTERVAL);
+        if throttled && !reached_end {
+            return;
+        }
+
+        self.last_reported = overall;
+        self.last_report_at = Some(now);
+        let eta = self.eta.update(processed, self.total_duration, now);
+        (self.sink)(overall, processed, self.total_duration, eta);
+    }
+
+    /// 将进度回调挂到 Whisper 参数上；tracker 必须在 whisper_full 返回前保持有效
+    pub fn install(&mut self, params: &mut whisper_full_params) {
+        params.progress_callback = Some(whisper_progress_callback);
+        params.progress_callback_user_data = self as *mut ProgressTracker as *mut c_void;
+    }
+}
+
+unsafe extern "C" fn whisper_progress_callback(
+    _ctx: *mut whisper_context,
+    _state: *mut whisper_state,
+    progress: c_int,
+    user_data: *mut c_void,
+) {
+    if user_data.is_null() {
+        return;
+    }
+    let tracker = &mut *(user_data as *mut ProgressTracker);
+    tracker.on_whisper_progress(progress as i32);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    fn recording_tracker(total_duration: f64) -> (ProgressTracker, Arc<Mutex<Vec<f64>>>) {
+        let reported = Arc::new(Mutex::new(Vec::new()));
+        let sink_reported = reported.clone();
+        let tracker = ProgressTracker::new(total_duration, Box::new(move |progress, _, _, _| {
+            sink_reported.lock().unwrap().push(progress);
+        }));
+        (tracker, reported)
+    }
+
+    #[test]
+    fn test_eta_decreases_with_steady_throughput() {
+        let mut eta = EtaEstimator::new();
+        let start = Instant::now();
+        eta.start(start);
+        assert_eq!(eta.update(0.0, 100.0, start), None);
+
+        // 稳定的2倍速处理：每秒处理2秒音频
+        let estimates: Vec<f64> = (1..=10)
+            .map(|second| {
+                let now = start + Duration::from_secs(second * 5);
+                eta.update(second as f64 * 10.0, 100.0, now).unwrap()
+            })
+            .collect();
+        assert!(estimates.windows(2).all(|w| w[1] < w[0]), "{:?}", estimates);
+        assert!((estimates[0] - 45.0).abs() < 1e-9);
+        assert_eq!(*estimates.last().unwrap(), 0.0);
+    }
+
+    #[test]
+    fn test_realtime_factor() {
+        assert_eq!(realtime_factor(60.0, 15.0), Some(4.0));
+        assert_eq!(realtime_factor(10.0, 20.0), Some(0.5));
+        assert_eq!(realtime_factor(0.0, 5.0), None);
+        assert_eq!(realtime_factor(10.0, 0.0), None);
+    }
+
+    #[test]
+    fn test_progress_callback_updates_monotonically() {
+        let (mut tracker, reported) = recording_tracker(20.0);
+        let user_data = &mut tracker as *mut ProgressTracker as *mut c_void;
+
+        // 两段各10秒，第二段 Whisper 进度从0重新开始
+        tracker.begin_chunk(10.0);
+        for percent in [0, 10, 50, 30, 100] {
+            unsafe { whisper_progress_callback(std::ptr::null_mut(), std::ptr::null_mut(), percent, user_data) };
+            std::thread::sleep(MIN_REPORT_INTERVAL);
+        }
+        tracker.begin_chunk(10.0);
+        for percent in [0, 60, 100] {
+            unsafe { whisper_progress_callback(std::ptr::null_mut(), std::ptr::null_mut(), percent, user_data) };
+            std::thread::sleep(MIN_REPORT_INTERVAL);
+        }
+
+        let reported = reported.lock().unwrap();
+        assert!(reported.windows(2).all(|w| w[1] > w[0]));
+        assert_eq!(*reported.last().unwrap(), INFERENCE_PROGRESS_END);
+        assert!(reported.iter().all(|p| (INFERENCE_PROGRESS_START..=INFERENCE_PROGRESS_END).contains(p)));
+    }
+
+    #[test]
+    fn test_progress_updates_are_throttled() {
+        let (mut tracker, reported) = recording_tracker(100.0);
+        tracker.begin_chunk(100.0);
+
+        let start = Instant::now();
+        for percent in 0..=100 {
+            tracker.on_whisper_progress_at(percent, start);
+        }
+
+        // 同一时刻的连续回调只上报第一次和最终完成
+        assert_eq!(reported.lock().unwrap().len(), 2);
+        assert_eq!(*reported.lock().unwrap().last().unwrap(), INFERENCE_PROGRESS_END);
+    }
+}
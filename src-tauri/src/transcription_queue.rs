@@ -0,0 +1,272 @@
+// transcription_queue.rs - 批量转录队列：保存在 app_settings 中，应用重启后恢复未完成的任务
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use tauri::State;
+
+use crate::errors::StenoError;
+use crate::storage::StorageService;
+use crate::storage_commands::StorageState;
+
+const QUEUE_SETTINGS_KEY: &str = "transcription_queue";
+const QUEUE_BACKUP_SETTINGS_KEY: &str = "transcription_queue_corrupt"; // 最近一次无法解析的队列原文
+
+/// 队列整体读出、修改再写回；存储层允许命令并发执行，这里串行化以免互相覆盖
+static QUEUE_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QueueJobKind {
+    #[default]
+    File,
+    LongAudio,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QueueJobStatus {
+    #[default]
+    Pending,
+    Processing,
+    Completed,
+    Failed,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QueuedJob {
+    pub id: String,
+    pub file_path: String,
+    #[serde(default)]
+    pub record_id: Option<String>, // 关联的转录记录，用于判断重启前是否已经完成
+    #[serde(default)]
+    pub kind: QueueJobKind,
+    #[serde(default)]
+    pub status: QueueJobStatus,
+    #[serde(default)]
+    pub language: Option<String>,
+    #[serde(default)]
+    pub mode: Option<String>,
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TranscriptionQueue {
+    pub jobs: Vec<QueuedJob>,
+    next_id: u64,
+}
+
+impl TranscriptionQueue {
+    /// 读取保存的队列；不存在时返回空队列。内容无法解析时先把原始 JSON 备份到
+    /// `transcription_queue_corrupt` 再返回空队列，避免下次保存时直接覆盖丢失
+    pub fn load(storage: &StorageService) -> rusqlite::Result<Self> {
+        let Some(json) = storage.get_setting(QUEUE_SETTINGS_KEY)? else {
+            return Ok(Self::default());
+        };
+        match serde_json::from_str(&json) {
+            Ok(queue) => Ok(queue),
+            Err(e) => {
+                storage.set_setting(QUEUE_BACKUP_SETTINGS_KEY, &json)?;
+                log::warn!("转录队列数据无法解析 ({})，原内容已备份到 {}", e, QUEUE_BACKUP_SETTINGS_KEY);
+                Ok(Self::default())
+            }
+        }
+    }
+
+    pub fn save(&self, storage: &StorageService) -> rusqlite::Result<()> {
+        let json = serde_json::to_string(self)
+            .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+        storage.set_setting(QUEUE_SETTINGS_KEY, &json)
+    }
+
+    pub fn enqueue(&mut self, file_path: &str, record_id: Option<String>, kind: QueueJobKind, language: Option<String>, mode: Option<String>) -> QueuedJob {
+        self.next_id += 1;
+        let job = QueuedJob {
+            id: format!("job_{}", self.next_id),
+            file_path: file_path.to_string(),
+            record_id,
+            kind,
+            status: QueueJobStatus::Pending,
+            language,
+            mode,
+            error: None,
+        };
+        self.jobs.push(job.clone());
+        job
+    }
+
+    /// 取出下一个待处理任务并标记为处理中
+    pub fn take_next(&mut self) -> Option<QueuedJob> {
+        let job = self.jobs.iter_mut().find(|job| job.status == QueueJobStatus::Pending)?;
+        job.status = QueueJobStatus::Processing;
+        Some(job.clone())
+    }
+
+    pub fn set_status(&mut self, job_id: &str, status: QueueJobStatus, error: Option<String>) -> bool {
+        match self.jobs.iter_mut().find(|job| job.id == job_id) {
+            Some(job) => {
+                job.status = status;
+                job.error = error;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn clear_finished(&mut self) {
+        self.jobs.retain(|job| !matches!(job.status, QueueJobStatus::Completed | QueueJobStatus::Failed));
+    }
+
+    /// 重启后整理队列：关联记录已完成的任务直接标记完成，中断的任务重新排队。
+    /// 长音频任务的分段进度只在内存中，同样从头开始；返回重新排队的任务数
+    pub fn restore(&mut self, record_completed: impl Fn(&str) -> bool) -> usize {
+        let mut requeued = 0;
+        for job in &mut self.jobs {
+            if matches!(job.status, QueueJobStatus::Completed | QueueJobStatus::Failed) {
+                continue;
+            }
+            if job.record_id.as_deref().is_some_and(&record_completed) {
+                job.status = QueueJobStatus::Completed;
+                continue;
+            }
+            if job.status == QueueJobStatus::Processing {
+                job.status = QueueJobStatus::Pending;
+                requeued += 1;
+            }
+        }
+        requeued
+    }
+}
+
+/// 启动时调用，恢复上次退出前未完成的队列
+pub fn restore_on_startup(storage: &StorageService) -> rusqlite::Result<usize> {
+    let mut queue = TranscriptionQueue::load(storage)?;
+    let requeued = queue.restore(|record_id| {
+        matches!(storage.get_record(record_id), Ok(Some(record)) if record.status == "completed")
+    });
+    queue.save(storage)?;
+    Ok(requeued)
+}
+
+fn update_queue<R>(storage_state: &StorageState, f: impl FnOnce(&mut TranscriptionQueue) -> R) -> Result<R, StenoError> {
+    let _guard = QUEUE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    storage_state.with_storage(|storage| {
+        let mut queue = TranscriptionQueue::load(storage)?;
+        let result = f(&mut queue);
+        queue.save(storage)?;
+        Ok(result)
+    })
+}
+
+#[tauri::command]
+pub async fn enqueue_transcription(
+    file_path: String,
+    record_id: Option<String>,
+    kind: Option<QueueJobKind>,
+    language: Option<String>,
+    mode: Option<String>,
+    storage_state: State<'_, StorageState>,
+) -> Result<QueuedJob, StenoError> {
+    update_queue(&storage_state, |queue| {
+        queue.enqueue(&file_path, record_id, kind.unwrap_or_default(), language, mode)
+    })
+}
+
+#[tauri::command]
+pub async fn get_transcription_queue(
+    storage_state: State<'_, StorageState>,
+) -> Result<Vec<QueuedJob>, StenoError> {
+    Ok(storage_state.with_storage(TranscriptionQueue::load)?.jobs)
+}
+
+#[tauri::command]
+pub async fn take_next_queued_transcription(
+    storage_state: State<'_, StorageState>,
+) -> Result<Option<QueuedJob>, StenoError> {
+    update_queue(&storage_state, |queue| queue.take_next())
+}
+
+#[tauri::command]
+pub async fn update_queued_transcription(
+    job_id: String,
+    status: QueueJobStatus,
+    error: Option<String>,
+    storage_state: State<'_, StorageState>,
+) -> Result<(), StenoError> {
+    if update_queue(&storage_state, |queue| queue.set_status(&job_id, status, error))? {
+        Ok(())
+    } else {
+        Err(StenoError::NotFound(format!("队列任务不存在: {}", job_id)))
+    }
+}
+
+#[tauri::command]
+pub async fn clear_finished_transcriptions(
+    storage_state: State<'_, StorageState>,
+) -> Result<(), StenoError> {
+    update_queue(&storage_state, |queue| queue.clear_finished())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::test_support::{sample_record, temp_storage};
+
+    #[test]
+    fn test_restart_with_partially_processed_queue() {
+        let (storage, dir) = temp_storage("queue_restart");
+        let mut record = sample_record("record_b");
+        record.status = "processing".to_string();
+        storage.save_record(&record).unwrap();
+        let mut record = sample_record("record_c");
+        record.status = "processing".to_string();
+        storage.save_record(&record).unwrap();
+
+        let mut queue = TranscriptionQueue::default();
+        let a = queue.enqueue("/tmp/a.wav", Some("record_a".to_string()), QueueJobKind::File, None, None);
+        let b = queue.enqueue("/tmp/b.wav", Some("record_b".to_string()), QueueJobKind::File, Some("zh".to_string()), None);
+        let c = queue.enqueue("/tmp/c.wav", Some("record_c".to_string()), QueueJobKind::LongAudio, None, None);
+        let d = queue.enqueue("/tmp/d.wav", None, QueueJobKind::File, None, None);
+
+        // a 已完成，b 处理中时应用退出；c 的记录在退出前已写入完成状态，但队列未来得及更新
+        assert_eq!(queue.take_next().unwrap().id, a.id);
+        queue.set_status(&a.id, QueueJobStatus::Completed, None);
+        assert_eq!(queue.take_next().unwrap().id, b.id);
+        queue.save(&storage).unwrap();
+        storage.update_record_status("record_c", "completed", 100.0, None).unwrap();
+
+        // 模拟重启
+        assert_eq!(restore_on_startup(&storage).unwrap(), 1);
+        let mut restored = TranscriptionQueue::load(&storage).unwrap();
+        let status = |queue: &TranscriptionQueue, id: &str| queue.jobs.iter().find(|job| job.id == id).unwrap().status;
+        assert_eq!(status(&restored, &a.id), QueueJobStatus::Completed);
+        assert_eq!(status(&restored, &b.id), QueueJobStatus::Pending);
+        assert_eq!(status(&restored, &c.id), QueueJobStatus::Completed);
+
+        // 中断的任务从头处理，已完成的文件不再处理
+        let next = restored.take_next().unwrap();
+        assert_eq!((next.id.as_str(), next.language.as_deref()), (b.id.as_str(), Some("zh")));
+        assert_eq!(restored.take_next().unwrap().id, d.id);
+        assert!(restored.take_next().is_none());
+
+        // 重启后新任务的ID不与旧任务冲突
+        assert_ne!(restored.enqueue("/tmp/e.wav", None, QueueJobKind::File, None, None).id, a.id);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_corrupt_queue_is_backed_up() {
+        let (storage, dir) = temp_storage("queue_corrupt");
+        storage.set_setting(QUEUE_SETTINGS_KEY, "{\"jobs\": [").unwrap();
+
+        let mut queue = TranscriptionQueue::load(&storage).unwrap();
+        assert!(queue.jobs.is_empty());
+        queue.enqueue("/tmp/a.wav", None, QueueJobKind::File, None, None);
+        queue.save(&storage).unwrap();
+
+        // 保存新队列后，损坏的原始内容仍然保留在备份键中
+        assert_eq!(storage.get_setting(QUEUE_BACKUP_SETTINGS_KEY).unwrap().as_deref(), Some("{\"jobs\": ["));
+        assert_eq!(TranscriptionQueue::load(&storage).unwrap().jobs.len(), 1);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,120 @@
+// translation.rs - 实时转录结果的下游翻译
+use std::sync::{mpsc, Arc};
+use std::thread;
+use serde::{Deserialize, Serialize};
+
+/// 翻译器接口：外部翻译服务通过实现该 trait 接入
+pub trait Translator: Send + Sync {
+    fn translate(&self, text: &str, source_language: &str, target_language: &str) -> Result<String, String>;
+}
+
+/// 默认翻译器：原样返回文本
+pub struct NoopTranslator;
+
+impl Translator for NoopTranslator {
+    fn translate(&self, text: &str, _source_language: &str, _target_language: &str) -> Result<String, String> {
+        Ok(text.to_string())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TranslationResultEvent {
+    pub segment_id: String,
+    pub source_text: String,
+    pub target_text: String,
+    pub source_language: String,
+    pub target_language: String,
+    pub timestamp: u64,
+}
+
+struct TranslationJob {
+    segment_id: String,
+    text: String,
+    source_language: String,
+}
+
+/// 翻译工作线程：在独立线程中执行翻译，不阻塞音频处理路径
+pub struct TranslationWorker {
+    job_tx: mpsc::Sender<TranslationJob>,
+    target_language: String,
+}
+
+impl TranslationWorker {
+    pub fn spawn<F>(translator: Arc<dyn Translator>, target_language: String, on_result: F) -> Self
+    where
+        F: Fn(TranslationResultEvent) + Send + 'static,
+    {
+        let (job_tx, job_rx) = mpsc::channel::<TranslationJob>();
+        let worker_target = target_language.clone();
+
+        // 发送端全部释放后线程自动退出
+        thread::spawn(move || {
+            while let Ok(job) = job_rx.recv() {
+                match translator.translate(&job.text, &job.source_language, &worker_target) {
+                    Ok(target_text) => on_result(TranslationResultEvent {
+                        segment_id: job.segment_id,
+                        source_text: job.text,
+                        target_text,
+                        source_language: job.source_language,
+                        target_language: worker_target.clone(),
+                        timestamp: std::time::SystemTime::now()
+                            .duration_since(std::time::UNIX_EPOCH)
+                            .unwrap()
+                            .as_millis() as u64,
+                    }),
+                    Err(e) => log::warn!("翻译失败 ({}): {}", job.segment_id, e),
+                }
+            }
+        });
+
+        Self { job_tx, target_language }
+    }
+
+    /// 提交翻译任务，立即返回
+    pub fn submit(&self, segment_id: &str, text: &str, source_language: &str) {
+        if text.trim().is_empty() {
+            return;
+        }
+        let _ = self.job_tx.send(TranslationJob {
+            segment_id: segment_id.to_string(),
+            text: text.to_string(),
+            source_language: source_language.to_string(),
+        });
+    }
+
+    pub fn target_language(&self) -> &str {
+        &self.target_language
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    struct UppercaseTranslator;
+
+    impl Translator for UppercaseTranslator {
+        fn translate(&self, text: &str, _source_language: &str, _target_language: &str) -> Result<String, String> {
+            Ok(text.to_uppercase())
+        }
+    }
+
+    #[test]
+    fn test_translation_worker_with_mock_translator() {
+        let (result_tx, result_rx) = mpsc::channel();
+        let worker = TranslationWorker::spawn(Arc::new(UppercaseTranslator), "en".to_string(), move |event| {
+            let _ = result_tx.send(event);
+        });
+
+        worker.submit("segment_1", "hello world", "en");
+        worker.submit("segment_2", "   ", "en"); // 空文本不翻译
+
+        let event = result_rx.recv_timeout(Duration::from_secs(2)).unwrap();
+        assert_eq!(event.segment_id, "segment_1");
+        assert_eq!(event.source_text, "hello world");
+        assert_eq!(event.target_text, "HELLO WORLD");
+        assert_eq!(event.target_language, "en");
+        assert!(result_rx.recv_timeout(Duration::from_millis(200)).is_err());
+    }
+}
@@ -0,0 +1,208 @@
+// wav_input.rs - WAV 文件的显式解码：按位深把 8/16/24/32 位整数 PCM 与 32 位浮点换算到 [-1, 1]，其他编码返回明确的错误
+use std::fmt;
+use std::path::Path;
+
+use crate::errors::StenoError;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum WavLoadError {
+    /// 编码或位深不在支持范围内（如 A-law、ADPCM、64 位浮点）
+    UnsupportedEncoding(String),
+    /// 文件头损坏或数据读取失败
+    Malformed(String),
+}
+
+impl fmt::Display for WavLoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnsupportedEncoding(message) => write!(f, "不支持的 WAV 编码: {}（支持 8/16/24/32 位 PCM 与 32 位浮点）", message),
+            Self::Malformed(message) => write!(f, "WAV 文件读取失败: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for WavLoadError {}
+
+impl From<WavLoadError> for StenoError {
+    fn from(error: WavLoadError) -> Self {
+        Self::InvalidArgument(error.to_string())
+    }
+}
+
+impl From<WavLoadError> for String {
+    fn from(error: WavLoadError) -> Self {
+        error.to_string()
+    }
+}
+
+/// 解码后的单声道 WAV，采样率保持原文件的值
+pub struct WavAudio {
+    pub samples: Vec<f32>,
+    pub sample_rate: u32,
+    pub start_time: f64,      // 第一个采样点在原文件中的时间（秒），读取整个文件时为0
+    pub source_duration: f64, // 整个文件的时长（秒）
+}
+
+fn read_samples<R: std::io::Read, S: hound::Sample>(reader: &mut hound::WavReader<R>, scale: f64, limit: usize) -> Result<Vec<f32>, WavLoadError>
+where
+    S: Into<f64>,
+{
+    reader.samples::<S>()
+        .take(limit)
+        .map(|sample| sample.map(|value| (value.into() / scale) as f32))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| WavLoadError::Malformed(e.to_string()))
+}
+
+/// 读取 WAV 并混合为单声道
+pub fn read_wav_mono(path: &Path) -> Result<WavAudio, WavLoadError> {
+    read_wav_mono_range(path, None)
+}
+
+/// 读取 WAV 中 [start, end) 秒的内容并混合为单声道；先定位到起点帧，只解码窗口内的数据。
+/// range 为 None 时读取整个文件，超出文件末尾的部分截断
+pub fn read_wav_mono_range(path: &Path, range: Option<(f64, f64)>) -> Result<WavAudio, WavLoadError> {
+    let mut reader = hound::WavReader::open(path).map_err(|e| match e {
+        hound::Error::Unsupported => WavLoadError::UnsupportedEncoding("非 PCM/浮点编码".to_string()),
+        e => WavLoadError::Malformed(e.to_string()),
+    })?;
+    let spec = reader.spec();
+    if spec.channels == 0 {
+        return Err(WavLoadError::Malformed("声道数为0".to_string()));
+    }
+    let channels = spec.channels as usize;
+    let total_frames = reader.duration();
+    let rate = spec.sample_rate as f64;
+    let (start_frame, end_frame) = match range {
+        Some((start, end)) => {
+            let frame_at = |secs: f64| ((secs.max(0.0) * rate).round() as u64).min(total_frames as u64) as u32;
+            (frame_at(start), frame_at(end))
+        }
+        None => (0, total_frames),
+    };
+    if start_frame > 0 {
+        reader.seek(start_frame).map_err(|e| WavLoadError::Malformed(e.to_string()))?;
+    }
+    let limit = end_frame.saturating_sub(start_frame) as usize * channels;
+
+    let interleaved = match (spec.sample_format, spec.bits_per_sample) {
+        (hound::SampleFormat::Int, 8) => read_samples::<_, i8>(&mut reader, 128.0, limit)?,
+        (hound::SampleFormat::Int, 16) => read_samples::<_, i16>(&mut reader, 32768.0, limit)?,
+        (hound::SampleFormat::Int, 24 | 32) => read_samples::<_, i32>(&mut reader, (1i64 << (spec.bits_per_sample - 1)) as f64, limit)?,
+        (hound::SampleFormat::Float, 32) => read_samples::<_, f32>(&mut reader, 1.0, limit)?,
+        (hound::SampleFormat::Int, bits) => return Err(WavLoadError::UnsupportedEncoding(format!("{} 位整数 PCM", bits))),
+        (hound::SampleFormat::Float, bits) => return Err(WavLoadError::UnsupportedEncoding(format!("{} 位浮点", bits))),
+    };
+
+    let samples = if channels > 1 {
+        interleaved.chunks(channels)
+            .map(|frame| frame.iter().sum::<f32>() / channels as f32)
+            .collect()
+    } else {
+        interleaved
+    };
+    Ok(WavAudio {
+        samples,
+        sample_rate: spec.sample_rate,
+        start_time: start_frame as f64 / rate,
+        source_duration: total_frames as f64 / rate,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("steno_wav_input_{}_{}.wav", name, std::process::id()))
+    }
+
+    fn reference() -> Vec<f32> {
+        (0..1600).map(|n| (2.0 * std::f32::consts::PI * 440.0 * n as f32 / 16000.0).sin() * 0.5).collect()
+    }
+
+    fn write_fixture(name: &str, sample_format: hound::SampleFormat, bits_per_sample: u16, channels: u16) -> std::path::PathBuf {
+        let path = temp_path(name);
+        let spec = hound::WavSpec { channels, sample_rate: 16000, bits_per_sample, sample_format };
+        let mut writer = hound::WavWriter::create(&path, spec).unwrap();
+        for value in reference() {
+            for _ in 0..channels {
+                match (sample_format, bits_per_sample) {
+                    (hound::SampleFormat::Float, _) => writer.write_sample(value).unwrap(),
+                    (_, 8) => writer.write_sample((value * 127.0).round() as i8).unwrap(),
+                    (_, 16) => writer.write_sample((value * 32767.0).round() as i16).unwrap(),
+                    (_, bits) => writer.write_sample((value as f64 * ((1i64 << (bits - 1)) - 1) as f64).round() as i32).unwrap(),
+                }
+            }
+        }
+        writer.finalize().unwrap();
+        path
+    }
+
+    #[test]
+    fn test_each_bit_depth_decodes_to_same_waveform() {
+        let expected = reference();
+        let fixtures = [
+            ("pcm8", hound::SampleFormat::Int, 8, 1, 1.0 / 64.0),
+            ("pcm16", hound::SampleFormat::Int, 16, 1, 1e-4),
+            ("pcm24", hound::SampleFormat::Int, 24, 2, 1e-6),
+            ("pcm32", hound::SampleFormat::Int, 32, 1, 1e-6),
+            ("float32", hound::SampleFormat::Float, 32, 1, 1e-7),
+        ];
+        for (name, sample_format, bits, channels, tolerance) in fixtures {
+            let path = write_fixture(name, sample_format, bits, channels);
+            let audio = read_wav_mono(&path).unwrap();
+            let _ = std::fs::remove_file(&path);
+
+            assert_eq!(audio.sample_rate, 16000);
+            assert_eq!(audio.samples.len(), expected.len(), "{}", name);
+            let max_error = audio.samples.iter().zip(&expected).map(|(a, b)| (a - b).abs()).fold(0.0f32, f32::max);
+            assert!(max_error <= tolerance, "{}: 最大误差 {}", name, max_error);
+        }
+    }
+
+    #[test]
+    fn test_range_read_seeks_to_window() {
+        let expected = reference();
+        let path = write_fixture("range", hound::SampleFormat::Int, 16, 2);
+        let audio = read_wav_mono_range(&path, Some((0.025, 0.075))).unwrap();
+        let tail = read_wav_mono_range(&path, Some((0.09, 5.0))).unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!((audio.start_time, audio.source_duration), (0.025, 0.1));
+        assert_eq!(audio.samples.len(), 800);
+        let max_error = audio.samples.iter().zip(&expected[400..1200]).map(|(a, b)| (a - b).abs()).fold(0.0f32, f32::max);
+        assert!(max_error <= 1e-4, "最大误差 {}", max_error);
+        // 终点超出文件末尾时截断
+        assert_eq!(tail.samples.len(), 160);
+    }
+
+    #[test]
+    fn test_unsupported_encoding_is_reported() {
+        // A-law（格式码 6）的最小 WAV 文件头
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(b"RIFF");
+        bytes.extend_from_slice(&40u32.to_le_bytes());
+        bytes.extend_from_slice(b"WAVEfmt ");
+        bytes.extend_from_slice(&16u32.to_le_bytes());
+        bytes.extend_from_slice(&6u16.to_le_bytes()); // A-law
+        bytes.extend_from_slice(&1u16.to_le_bytes());
+        bytes.extend_from_slice(&8000u32.to_le_bytes());
+        bytes.extend_from_slice(&8000u32.to_le_bytes());
+        bytes.extend_from_slice(&1u16.to_le_bytes());
+        bytes.extend_from_slice(&8u16.to_le_bytes());
+        bytes.extend_from_slice(b"data");
+        bytes.extend_from_slice(&4u32.to_le_bytes());
+        bytes.extend_from_slice(&[0xD5; 4]);
+        let path = temp_path("alaw");
+        std::fs::write(&path, bytes).unwrap();
+
+        let error = read_wav_mono(&path).err().unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert!(matches!(error, WavLoadError::UnsupportedEncoding(_)), "{:?}", error);
+        assert_eq!(StenoError::from(error).code(), "invalid_argument");
+
+        let missing = read_wav_mono(Path::new("/nonexistent/steno.wav")).err().unwrap();
+        assert!(matches!(missing, WavLoadError::Malformed(_)));
+    }
+}
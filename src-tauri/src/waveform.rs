@@ -0,0 +1,141 @@
+// waveform.rs - 记录音频的波形峰值：按桶降采样为 (最小值, 最大值) 对，结果缓存在数据库中供界面反复绘制
+use serde::{Deserialize, Serialize};
+use tauri::Manager;
+
+use crate::decode_audio_to_mono_16k;
+use crate::errors::StenoError;
+use crate::storage_commands::StorageState;
+
+/// 单次请求的桶数上限，足够覆盖高分辨率屏幕的波形宽度
+const MAX_BUCKETS: usize = 20000;
+
+/// 音频文件指纹：大小与修改时间（毫秒），合并或重录后任一变化都会使缓存的峰值失效
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AudioStamp {
+    pub size: i64,
+    pub modified: i64,
+}
+
+impl AudioStamp {
+    pub fn of_file(path: &str) -> Result<Self, StenoError> {
+        let metadata = std::fs::metadata(path)
+            .map_err(|e| StenoError::NotFound(format!("无法读取音频文件 {}: {}", path, e)))?;
+        let modified = metadata.modified()
+            .ok()
+            .and_then(|time| time.duration_since(std::time::UNIX_EPOCH).ok())
+            .map_or(0, |elapsed| elapsed.as_millis() as i64);
+        Ok(Self { size: metadata.len() as i64, modified })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PeakPair {
+    pub min: f32,
+    pub max: f32,
+}
+
+/// 把样本均分到 buckets 个桶中，每桶取最小值与最大值；样本少于桶数时多出的桶为 0
+pub fn compute_peaks(samples: &[f32], buckets: usize) -> Vec<PeakPair> {
+    (0..buckets)
+        .map(|bucket| {
+            let start = bucket * samples.len() / buckets;
+            let end = (bucket + 1) * samples.len() / buckets;
+            let slice = &samples[start..end];
+            if slice.is_empty() {
+                return PeakPair { min: 0.0, max: 0.0 };
+            }
+            slice.iter().fold(PeakPair { min: f32::MAX, max: f32::MIN }, |peak, &sample| PeakPair {
+                min: peak.min.min(sample),
+                max: peak.max.max(sample),
+            })
+        })
+        .collect()
+}
+
+/// 数据库中的存储格式：每对峰值依次为 min、max 的小端 f32
+pub fn encode_peaks(peaks: &[PeakPair]) -> Vec<u8> {
+    peaks.iter()
+        .flat_map(|peak| peak.min.to_le_bytes().into_iter().chain(peak.max.to_le_bytes()))
+        .collect()
+}
+
+pub fn decode_peaks(blob: &[u8]) -> Vec<PeakPair> {
+    blob.chunks_exact(8)
+        .map(|pair| PeakPair {
+            min: f32::from_le_bytes([pair[0], pair[1], pair[2], pair[3]]),
+            max: f32::from_le_bytes([pair[4], pair[5], pair[6], pair[7]]),
+        })
+        .collect()
+}
+
+/// 返回记录音频的波形峰值；同一桶数只在第一次调用时读取音频计算，之后直接读缓存，音频文件变化后重新计算
+#[tauri::command]
+pub async fn compute_waveform_peaks(
+    record_id: String,
+    buckets: usize,
+    app_handle: tauri::AppHandle,
+) -> Result<Vec<PeakPair>, StenoError> {
+    if buckets == 0 || buckets > MAX_BUCKETS {
+        return Err(StenoError::InvalidArgument(format!("桶数必须在 1 到 {} 之间", MAX_BUCKETS)));
+    }
+    let storage_state = app_handle.state::<StorageState>();
+    let record = storage_state.with_storage(|storage| storage.get_record(&record_id))?
+        .ok_or_else(|| StenoError::NotFound(format!("记录不存在: {}", record_id)))?;
+    let source = AudioStamp::of_file(&record.file_path)?;
+    if let Some(peaks) = storage_state.with_storage(|storage| storage.get_waveform_peaks(&record_id, buckets, source))? {
+        return Ok(peaks);
+    }
+
+    let peaks = tauri::async_runtime::spawn_blocking(move || {
+        let audio = decode_audio_to_mono_16k(&record.file_path, None)?.samples;
+        Ok::<_, String>(compute_peaks(&audio, buckets))
+    })
+    .await
+    .map_err(|e| StenoError::Internal(format!("波形计算任务异常: {}", e)))?
+    .map_err(StenoError::Internal)?;
+
+    storage_state.with_storage(|storage| storage.save_waveform_peaks(&record_id, source, &peaks))?;
+    Ok(peaks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::test_support::{sample_record, temp_storage};
+
+    #[test]
+    fn test_peaks_bucket_count_and_values() {
+        let samples: Vec<f32> = (0..1000).map(|i| ((i % 10) as f32 - 5.0) / 10.0).collect();
+        let peaks = compute_peaks(&samples, 7);
+        assert_eq!(peaks.len(), 7);
+        assert!(peaks.iter().all(|peak| peak.min == -0.5 && peak.max == 0.4));
+
+        // 样本少于桶数
+        let peaks = compute_peaks(&[0.2, -0.3], 4);
+        assert_eq!(peaks.len(), 4);
+        assert_eq!(peaks.iter().filter(|peak| *peak == &PeakPair { min: 0.0, max: 0.0 }).count(), 2);
+        assert!(compute_peaks(&[], 3).iter().all(|peak| peak.min == 0.0 && peak.max == 0.0));
+    }
+
+    #[test]
+    fn test_cached_peaks_match_first_computation() {
+        let (storage, dir) = temp_storage("waveform_peaks");
+        storage.save_record(&sample_record("record_1")).unwrap();
+        let samples: Vec<f32> = (0..16000).map(|i| (i as f32 * 0.01).sin() * 0.8).collect();
+        let source = AudioStamp { size: 32000, modified: 1_700_000_000_000 };
+
+        assert_eq!(storage.get_waveform_peaks("record_1", 200, source).unwrap(), None);
+        let peaks = compute_peaks(&samples, 200);
+        storage.save_waveform_peaks("record_1", source, &peaks).unwrap();
+        assert_eq!(storage.get_waveform_peaks("record_1", 200, source).unwrap(), Some(peaks.clone()));
+        // 不同桶数分别缓存
+        assert_eq!(storage.get_waveform_peaks("record_1", 100, source).unwrap(), None);
+        // 音频被合并或重录后缓存失效
+        let rerecorded = AudioStamp { size: 64000, modified: 1_700_000_100_000 };
+        assert_eq!(storage.get_waveform_peaks("record_1", 200, rerecorded).unwrap(), None);
+
+        storage.delete_record("record_1").unwrap();
+        assert_eq!(storage.get_waveform_peaks("record_1", 200, source).unwrap(), None);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
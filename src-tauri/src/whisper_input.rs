@@ -0,0 +1,49 @@
+// whisper_input.rs - 送入 whisper_full 之前的采样率检查：Whisper 只接受 16kHz 单声道，其他采样率会得到乱码结果且没有任何报错
+use std::borrow::Cow;
+
+use crate::{fallback_resample, high_quality_resample};
+
+/// Whisper 模型要求的采样率，采集、解码、录音与时间换算统一使用该值
+pub const WHISPER_SAMPLE_RATE: u32 = 16000;
+
+/// 采样率与 Whisper 一致时原样返回；不一致时记录警告并即时重采样到 16kHz
+pub fn ensure_whisper_rate<'a>(samples: impl Into<Cow<'a, [f32]>>, sample_rate: u32) -> Cow<'a, [f32]> {
+    let samples = samples.into();
+    if sample_rate == WHISPER_SAMPLE_RATE || samples.is_empty() {
+        return samples;
+    }
+    log::warn!(
+        "⚠️ 送入识别的音频采样率为 {}Hz，与模型要求的 {}Hz 不一致，已即时重采样",
+        sample_rate, WHISPER_SAMPLE_RATE
+    );
+    let resampled = high_quality_resample(&samples, sample_rate, WHISPER_SAMPLE_RATE).unwrap_or_else(|e| {
+        log::warn!("⚠️ 高质量重采样失败，改用简单重采样: {}", e);
+        fallback_resample(&samples, sample_rate, WHISPER_SAMPLE_RATE)
+    });
+    Cow::Owned(resampled)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tone(sample_rate: u32, seconds: f32) -> Vec<f32> {
+        (0..(sample_rate as f32 * seconds) as usize)
+            .map(|n| (2.0 * std::f32::consts::PI * 440.0 * n as f32 / sample_rate as f32).sin() * 0.5)
+            .collect()
+    }
+
+    #[test]
+    fn test_mismatched_rate_is_resampled() {
+        let matching = tone(WHISPER_SAMPLE_RATE, 1.0);
+        assert!(matches!(ensure_whisper_rate(matching.as_slice(), WHISPER_SAMPLE_RATE), Cow::Borrowed(_)));
+
+        // 48kHz 的一秒音频被换算成约一秒的 16kHz 音频，而不是被当作三秒送入模型
+        let guarded = ensure_whisper_rate(tone(48000, 1.0), 48000);
+        assert!(matches!(guarded, Cow::Owned(_)));
+        let seconds = guarded.len() as f64 / WHISPER_SAMPLE_RATE as f64;
+        assert!((seconds - 1.0).abs() < 0.05, "重采样后时长 {:.3}s", seconds);
+
+        assert!(ensure_whisper_rate(Vec::<f32>::new(), 44100).is_empty());
+    }
+}
@@ -6,7 +6,9 @@ interface DownloadProgress {
   downloaded: number;
   total: number;
   speed: number;
-  status: 'downloading' | 'completed' | 'error' | 'paused';
+  status: 'downloading' | 'importing' | 'completed' | 'error' | 'paused';
+  percent: number | null;
+  eta_secs: number | null;
 }
 
 interface ModelDownloadProgressProps {
@@ -30,13 +32,13 @@ const ModelDownloadProgress: React.FC<ModelDownloadProgressProps> = ({
   };
 
   const getProgressPercentage = (): number => {
-    if (progress.total === 0) return 0;
-    return Math.round((progress.downloaded / progress.total) * 100);
+    return Math.round(progress.percent ?? 0);
   };
 
   const getStatusText = (): string => {
     switch (progress.status) {
       case 'downloading': return '下载中';
+      case 'importing': return '导入中';
       case 'completed': return '下载完成';
       case 'error': return '下载失败';
       case 'paused': return '已暂停';
@@ -47,6 +49,7 @@ const ModelDownloadProgress: React.FC<ModelDownloadProgressProps> = ({
   const getStatusColor = (): string => {
     switch (progress.status) {
       case 'downloading': return 'bg-blue-500';
+      case 'importing': return 'bg-blue-500';
       case 'completed': return 'bg-green-500';
       case 'error': return 'bg-red-500';
       case 'paused': return 'bg-yellow-500';
@@ -114,8 +117,8 @@ const ModelDownloadProgress: React.FC<ModelDownloadProgressProps> = ({
             <span>下载速度: {formatSpeed(progress.speed)}</span>
             <span>
               剩余时间: {
-                progress.speed > 0 
-                  ? Math.ceil((progress.total - progress.downloaded) / progress.speed) + 's'
+                progress.eta_secs !== null
+                  ? progress.eta_secs + 's'
                   : '计算中...'
               }
             </span>
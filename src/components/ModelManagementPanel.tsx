@@ -24,6 +24,19 @@ interface AvailableModel {
   quality: string;
 }
 
+interface CatalogEntry {
+  name: string;
+  display_name: string;
+  size: number;
+  languages: string;
+  description: string;
+  quality: string;
+  urls: string[];
+}
+
+const formatModelSize = (bytes: number): string =>
+  bytes >= 1024 ** 3 ? `${(bytes / 1024 ** 3).toFixed(2)}GB` : `${(bytes / 1024 ** 2).toFixed(1)}MB`;
+
 interface StorageInfo {
   used_space: number;
   total_space: number;
@@ -35,61 +48,14 @@ interface DownloadProgress {
   downloaded: number;
   total: number;
   speed: number;
-  status: 'downloading' | 'completed' | 'error' | 'paused';
+  status: 'downloading' | 'importing' | 'completed' | 'error' | 'paused';
+  percent: number | null;
+  eta_secs: number | null;
 }
 
 const ModelManagementPanel: React.FC = () => {
   const [installedModels, setInstalledModels] = useState<ModelInfo[]>([]);
-  const [availableModels] = useState<AvailableModel[]>([
-    {
-      name: 'ggml-large-v3',
-      size: '3.1GB',
-      url: 'https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-large-v3.bin',
-      description: 'Whisper Large V3 - 最高质量',
-      languages: 'multilingual',
-      quality: 'highest'
-    },
-    {
-      name: 'ggml-large-v2',
-      size: '3.09GB', 
-      url: 'https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-large-v2.bin',
-      description: 'Whisper Large V2 - 高质量',
-      languages: 'multilingual',
-      quality: 'high'
-    },
-    {
-      name: 'ggml-medium',
-      size: '1.53GB',
-      url: 'https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-medium.bin',
-      description: 'Whisper Medium - 良好质量',
-      languages: 'multilingual',
-      quality: 'good'
-    },
-    {
-      name: 'ggml-small',
-      size: '488MB',
-      url: 'https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-small.bin',
-      description: 'Whisper Small - 一般质量',
-      languages: 'multilingual',
-      quality: 'fair'
-    },
-    {
-      name: 'ggml-base',
-      size: '148MB',
-      url: 'https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-base.bin',
-      description: 'Whisper Base - 基础质量',
-      languages: 'multilingual',
-      quality: 'basic'
-    },
-    {
-      name: 'ggml-tiny',
-      size: '77.7MB',
-      url: 'https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-tiny.bin',
-      description: 'Whisper Tiny - 最小模型',
-      languages: 'multilingual',
-      quality: 'minimal'
-    }
-  ]);
+  const [availableModels, setAvailableModels] = useState<AvailableModel[]>([]);
   const [storageInfo, setStorageInfo] = useState<StorageInfo | null>(null);
   const [downloadProgress, setDownloadProgress] = useState<DownloadProgress | null>(null);
   const [loading, setLoading] = useState(false);
@@ -97,6 +63,7 @@ const ModelManagementPanel: React.FC = () => {
   useEffect(() => {
     loadInstalledModels();
     loadStorageInfo();
+    loadModelCatalog();
     
     const unlistenDownload = listen<DownloadProgress>('model_download_progress', (event) => {
       setDownloadProgress(event.payload);
@@ -119,6 +86,22 @@ const ModelManagementPanel: React.FC = () => {
     }
   };
 
+  const loadModelCatalog = async () => {
+    try {
+      const catalog = await invoke<{ version: number; models: CatalogEntry[] }>('get_model_catalog');
+      setAvailableModels(catalog.models.map(entry => ({
+        name: entry.name,
+        size: formatModelSize(entry.size),
+        url: entry.urls[0],
+        description: `Whisper ${entry.display_name} - ${entry.description}`,
+        languages: entry.languages,
+        quality: entry.quality,
+      })));
+    } catch (error) {
+      console.error('Failed to load model catalog:', error);
+    }
+  };
+
   const loadStorageInfo = async () => {
     try {
       const info = await invoke<StorageInfo>('get_storage_info');
@@ -70,6 +70,7 @@ const NewTranscriptionModal: React.FC<NewTranscriptionModalProps> = ({
   const [currentModel, setCurrentModel] = useState<ModelInfo | null>(null);
   const [currentPrompt, setCurrentPrompt] = useState<PromptTemplate | null>(null);
   const [showFullPrompt, setShowFullPrompt] = useState(false);
+  const [supportedLanguages, setSupportedLanguages] = useState<{ code: string; name: string }[] | null>(null);
 
   // 音频处理配置
   const [audioConfig, setAudioConfig] = useState<AudioProcessingConfig>({
@@ -105,6 +106,13 @@ const NewTranscriptionModal: React.FC<NewTranscriptionModalProps> = ({
       const activeModel = await invoke<ModelInfo | null>('get_current_model');
       setCurrentModel(activeModel);
 
+      // 获取当前模型支持的语言，失败时保留默认选项
+      try {
+        setSupportedLanguages(await invoke<{ code: string; name: string }[]>('get_supported_languages'));
+      } catch {
+        setSupportedLanguages(null);
+      }
+
       // 获取当前提示词
       const prompts = await invoke<PromptTemplate[]>('get_prompt_templates');
       const activePrompt = prompts.find(prompt => prompt.is_active);
@@ -303,7 +311,7 @@ const NewTranscriptionModal: React.FC<NewTranscriptionModalProps> = ({
     }
   ];
 
-  const languageOptions = [
+  const baseLanguageOptions = [
     { 
       value: 'auto', 
       label: '自动检测', 
@@ -323,6 +331,18 @@ const NewTranscriptionModal: React.FC<NewTranscriptionModalProps> = ({
     }
   ];
 
+  // 按当前模型支持的语言过滤，并追加模型支持的其他语言
+  const languageOptions = supportedLanguages
+    ? [
+        ...baseLanguageOptions.filter(option =>
+          option.value === 'auto' || supportedLanguages.some(lang => lang.code === option.value)
+        ),
+        ...supportedLanguages
+          .filter(lang => !baseLanguageOptions.some(option => option.value === lang.code))
+          .map(lang => ({ value: lang.code, label: lang.name, description: lang.code }))
+      ]
+    : baseLanguageOptions;
+
   const qualityOptions = [
     { 
       value: 'high_precision', 
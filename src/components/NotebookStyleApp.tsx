@@ -26,10 +26,18 @@ import { storageService } from '../utils/storage';
 import SmartTranscriptionDisplay from './SmartTranscriptionDisplay';
 import { useRealtimeRecording } from '../hooks/useRealtimeRecording';
 
+interface ModelStatus {
+  status: 'unchecked' | 'missing' | 'warming_up' | 'ready';
+  model_path?: string;
+  code?: string;
+  message?: string;
+}
+
 interface RecognitionProgress {
   stage: string;
   progress: number;
   message: string;
+  eta_secs?: number; // 预计剩余秒数
 }
 
 interface RecognitionResult {
@@ -82,10 +90,36 @@ const NotebookStyleApp: React.FC = () => {
     }
   }, [recordingError, error]);
 
+  // 模型状态：启动检查可能早于监听注册，先注册监听再查询一次当前状态
+  useEffect(() => {
+    let cancelled = false;
+    let missingShown = false;
+    const showMissing = (status: ModelStatus) => {
+      if (cancelled || missingShown || status.status !== 'missing') return;
+      missingShown = true;
+      error('模型不可用', `${status.message}，请在模型管理中下载或切换模型`, 8000);
+    };
+
+    const unlistenMissing = listen<ModelStatus>('model_missing', (event) => {
+      showMissing({ ...event.payload, status: 'missing' });
+    });
+    unlistenMissing
+      .then(() => invoke<ModelStatus>('get_model_status'))
+      .then(showMissing)
+      .catch(err => console.error('查询模型状态失败:', err));
+
+    return () => {
+      cancelled = true;
+      unlistenMissing.then(fn => fn());
+    };
+  }, [error]);
+
 
   // 录音结果处理
   const handleRecordingResult = useCallback(async (segments: any[]) => {
     if (segments.length === 0) return;
+    // 已为本次录音建立记录时由停止流程更新该记录，不再另存一条
+    if (activeRealtimeRecordId) return;
 
     try {
       // 将录音段落转换为转录记录
@@ -135,7 +169,7 @@ const NotebookStyleApp: React.FC = () => {
       console.error('保存录音结果失败:', err);
       error('保存失败', '无法保存录音结果');
     }
-  }, [recordingState.duration, recordingState.speakerCount, realtimeConfig, success, error]);
+  }, [recordingState.duration, recordingState.speakerCount, realtimeConfig, activeRealtimeRecordId, success, error]);
 
   // 处理实时录音开始
   const handleStartRealtimeRecording = useCallback(async (config: any) => {
@@ -545,11 +579,14 @@ const NotebookStyleApp: React.FC = () => {
           
           // 更新存储中的结果
           try {
-            await storageService.updateResult(record.id, {
+            const autoName = await storageService.updateResult(record.id, {
               text: result.text,
               processing_time: result.processing_time,
               accuracy: completedRecord.accuracy,
             });
+            if (autoName) {
+              setTranscriptionRecords(prev => prev.map(r => r.id === record.id ? { ...r, name: autoName } : r));
+            }
           } catch (err) {
             console.error('Failed to save result:', err);
           }
@@ -621,6 +658,7 @@ const NotebookStyleApp: React.FC = () => {
         language: config.model.language,
         mode: config.model.mode,
         initial_prompt: initial_prompt || null,
+        recordId: record.id,
       });
       
     } catch (err) {
@@ -26,6 +26,7 @@ import { PromptForm } from './PromptForm';
 import { PromptFormWizard } from './PromptFormWizard';
 import { SmartPromptWizard } from './SmartPromptWizard';
 import { DeleteConfirmModal } from './DeleteConfirmModal';
+import { getErrorMessage } from '../utils/errors';
 
 export const PromptManager: React.FC = () => {
   const [state, setState] = useState<PromptManagerState>({
@@ -105,7 +106,7 @@ export const PromptManager: React.FC = () => {
       console.error('Failed to load prompts:', error);
       setState(prev => ({ 
         ...prev, 
-        error: error as string, 
+        error: getErrorMessage(error), 
         isLoading: false 
       }));
     }
@@ -210,7 +211,7 @@ export const PromptManager: React.FC = () => {
       console.error('Failed to apply prompt:', error);
       setNotification({
         type: 'error',
-        message: `应用提示词失败: ${error}`
+        message: `应用提示词失败: ${getErrorMessage(error)}`
       });
     }
   };
@@ -271,7 +272,7 @@ export const PromptManager: React.FC = () => {
       console.error('Failed to create and apply prompt:', error);
       setNotification({
         type: 'error',
-        message: `创建失败: ${error}`
+        message: `创建失败: ${getErrorMessage(error)}`
       });
     }
   };
@@ -331,7 +332,7 @@ export const PromptManager: React.FC = () => {
       console.error('Failed to update and apply prompt:', error);
       setNotification({
         type: 'error',
-        message: `更新失败: ${error}`
+        message: `更新失败: ${getErrorMessage(error)}`
       });
     }
   };
@@ -364,7 +365,7 @@ export const PromptManager: React.FC = () => {
     } catch (error) {
       setNotification({
         type: 'error',
-        message: `删除失败: ${error}`
+        message: `删除失败: ${getErrorMessage(error)}`
       });
     } finally {
       setDeleteConfirm({
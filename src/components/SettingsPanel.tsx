@@ -10,6 +10,7 @@ import {
 } from '@heroicons/react/24/outline';
 import { invoke } from '@tauri-apps/api/core';
 import { cn } from '../utils/cn';
+import { getErrorMessage } from '../utils/errors';
 
 interface AudioDevice {
   id: string;
@@ -34,6 +35,8 @@ interface AudioTestResult {
 interface MicTestState {
   phase: string; // "monitoring", "recording", "playback", "completed"
   volume_level: number;
+  dbfs: number;
+  peak_dbfs: number;
   countdown: number;
   message: string;
 }
@@ -77,7 +80,7 @@ const SettingsPanel: React.FC<SettingsPanelProps> = ({
       
     } catch (error) {
       console.error('Failed to load audio devices:', error);
-      setTestResult('获取音频设备失败: ' + String(error));
+      setTestResult('获取音频设备失败: ' + getErrorMessage(error));
     } finally {
       setLoading(false);
     }
@@ -102,7 +105,7 @@ const SettingsPanel: React.FC<SettingsPanelProps> = ({
       setTestResult(result.message);
       
     } catch (error) {
-      setTestResult('设备测试失败: ' + String(error));
+      setTestResult('设备测试失败: ' + getErrorMessage(error));
     } finally {
       setIsTestingInput(false);
       setIsTestingOutput(false);
@@ -151,7 +154,7 @@ const SettingsPanel: React.FC<SettingsPanelProps> = ({
       setMicTestInterval(interval);
       
     } catch (error) {
-      setTestResult('麦克风测试启动失败: ' + String(error));
+      setTestResult('麦克风测试启动失败: ' + getErrorMessage(error));
       setIsTestingInput(false);
     }
   };
@@ -162,7 +165,7 @@ const SettingsPanel: React.FC<SettingsPanelProps> = ({
       await invoke('play_recorded_audio');
       setTestResult('正在播放录制的音频...');
     } catch (error) {
-      setTestResult('播放失败: ' + String(error));
+      setTestResult('播放失败: ' + getErrorMessage(error));
     }
   };
   
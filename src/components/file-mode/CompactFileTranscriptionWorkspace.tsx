@@ -1,6 +1,6 @@
 import React, { useState, useCallback, useEffect, useRef } from 'react';
 import { invoke } from "@tauri-apps/api/core";
-import { listen } from "@tauri-apps/api/event";
+import { listen, once } from "@tauri-apps/api/event";
 import { open } from '@tauri-apps/plugin-dialog';
 import {
   CloudArrowUpIcon,
@@ -28,6 +28,7 @@ interface RecognitionProgress {
   stage: string;
   progress: number;
   message: string;
+  eta_secs?: number; // 预计剩余秒数
 }
 
 interface RecognitionResult {
@@ -37,6 +38,18 @@ interface RecognitionResult {
   processing_time: number;
 }
 
+// 持久化转录队列中的任务 (对应 transcription_queue::QueuedJob)
+interface QueuedJob {
+  id: string;
+  file_path: string;
+  record_id?: string;
+  kind: 'file' | 'long_audio';
+  status: 'pending' | 'processing' | 'completed' | 'failed';
+  language?: string;
+  mode?: string;
+  error?: string;
+}
+
 interface AudioProcessingConfig {
   // 音频预处理参数 (对应 AudioEnhancementConfig)
   enable_preemphasis: boolean;
@@ -75,6 +88,7 @@ interface FileInfo {
   status: 'pending' | 'processing' | 'completed' | 'failed';
   progress: number;
   error?: string;
+  queueJobId?: string; // 批量处理时对应的持久化队列任务
   result?: {
     text: string;
     processing_time: number;
@@ -199,6 +213,28 @@ const CompactFileTranscriptionWorkspace: React.FC<CompactFileTranscriptionWorksp
     };
   }, [success, error]);
 
+  // 恢复上次退出前未处理完的批量任务，重启时已由后台重新排队
+  useEffect(() => {
+    invoke<QueuedJob[]>('get_transcription_queue')
+      .then(jobs => {
+        const restored: FileInfo[] = jobs
+          .filter(job => job.kind === 'file' && job.status === 'pending')
+          .map(job => ({
+            id: job.id,
+            name: job.file_path.split('/').pop() || job.file_path.split('\\').pop() || 'Unknown',
+            path: job.file_path,
+            size: 0,
+            status: 'pending' as const,
+            progress: 0,
+            queueJobId: job.id,
+          }));
+        if (restored.length > 0) {
+          setFileQueue(prev => [...restored, ...prev.filter(f => !restored.some(r => r.path === f.path))]);
+        }
+      })
+      .catch(err => console.error('读取转录队列失败:', err));
+  }, []);
+
   // 估算准确率
   const estimateAccuracy = (text: string): number => {
     if (!text) return 0;
@@ -321,27 +357,74 @@ const CompactFileTranscriptionWorkspace: React.FC<CompactFileTranscriptionWorksp
     setFileQueue(prev => prev.filter(f => f.id !== fileId));
   }, []);
 
-  // 批量处理
+  // 批量处理：文件先写入持久化队列，再逐个取出转录，应用中途退出后重启可以继续
   const processBatch = useCallback(async () => {
     const pendingFiles = fileQueue.filter(f => f.status === 'pending');
-    if (pendingFiles.length === 0) return;
+    if (pendingFiles.length === 0 || isProcessing) return;
 
     info("批量处理", `开始处理 ${pendingFiles.length} 个文件`);
-    
-    for (const file of pendingFiles) {
-      if (!isProcessing) {
-        await processFile(file.id);
-        await new Promise(resolve => {
-          const checkInterval = setInterval(() => {
-            if (!isProcessing) {
-              clearInterval(checkInterval);
-              resolve(void 0);
-            }
-          }, 1000);
+    setIsProcessing(true);
+    try {
+      const jobFiles = new Map<string, string>(); // 队列任务ID -> 文件ID
+      for (const file of pendingFiles) {
+        const jobId = file.queueJobId ?? (await invoke<QueuedJob>('enqueue_transcription', {
+          filePath: file.path,
+          kind: 'file',
+          language: modelConfig.language,
+          mode: modelConfig.mode,
+        })).id;
+        jobFiles.set(jobId, file.id);
+      }
+      setFileQueue(prev => prev.map(f => {
+        const entry = [...jobFiles].find(([, fileId]) => fileId === f.id);
+        return entry ? { ...f, queueJobId: entry[0] } : f;
+      }));
+
+      const whisperPrompt = localStorage.getItem('whisperPrompt') || '';
+      let job: QueuedJob | null;
+      while ((job = await invoke<QueuedJob | null>('take_next_queued_transcription'))) {
+        const jobId = job.id;
+        const fileId = jobFiles.get(jobId);
+        if (fileId) {
+          setFileQueue(prev => prev.map(f =>
+            f.id === fileId ? { ...f, status: 'processing' as const, progress: 0 } : f
+          ));
+        }
+
+        // 先注册一次性监听再启动识别，避免错过完成事件
+        const completed = new Promise<RecognitionResult>(resolve => {
+          once<RecognitionResult>('recognition_complete', event => resolve(event.payload));
+        });
+        let result: RecognitionResult;
+        try {
+          await invoke("recognize_file_async", {
+            path: job.file_path,
+            language: job.language ?? modelConfig.language,
+            mode: job.mode ?? modelConfig.mode,
+            initial_prompt: whisperPrompt || null,
+          });
+          result = await completed;
+        } catch (err) {
+          const errorMsg = err as string;
+          result = { success: false, error: errorMsg, processing_time: 0 };
+          setFileQueue(prev => prev.map(f =>
+            f.id === fileId ? { ...f, status: 'failed' as const, error: errorMsg } : f
+          ));
+        }
+
+        await invoke('update_queued_transcription', {
+          jobId,
+          status: result.success ? 'completed' : 'failed',
+          error: result.success ? null : (result.error ?? null),
         });
       }
+      await invoke('clear_finished_transcriptions');
+    } catch (err) {
+      error("批量处理失败", err as string);
+    } finally {
+      setIsProcessing(false);
     }
-  }, [fileQueue, isProcessing, processFile, info]);
+  }, [fileQueue, isProcessing, modelConfig, info, error]);
 
   // 根据模式自动调整参数
   useEffect(() => {
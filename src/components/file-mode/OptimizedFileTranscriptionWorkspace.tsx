@@ -29,6 +29,7 @@ interface RecognitionProgress {
   stage: string;
   progress: number;
   message: string;
+  eta_secs?: number; // 预计剩余秒数
 }
 
 interface RecognitionResult {
@@ -1,4 +1,4 @@
-import React, { useState, useEffect } from 'react';
+import React, { useState, useEffect, useRef } from 'react';
 import {
   MicrophoneIcon,
   StopIcon,
@@ -38,14 +38,40 @@ interface RealtimeConfig {
   noise_reduction: boolean;
   auto_save: boolean;
   save_interval: number;
+  auto_stop_after_silence_secs?: number;
+  decoding?: DecodingStrategy;
+  vad_backend?: 'energy' | 'webrtc';
+  input_gain?: number; // 缺省时使用当前设备保存的增益
+  denoise_backend?: 'none' | 'noise_gate' | 'rnnoise';
+  segmentation?: { max_len: number; split_on_word: boolean }; // max_len 为 0 时不限制段长度
+  suppression?: { enabled: boolean; suppress_non_speech_tokens: boolean; patterns: string[] }; // 非语音标注过滤
+  stall_timeout_secs?: number; // 超过该时长没有音频数据时发送 recording_stalled
+  normalize?: boolean; // 识别前标准化音量，默认开启
+  pre_emphasis?: number | null; // 预加重系数，缺省不做预加重
+  initial_prompt?: string | null; // 提示词模板，与热词一起作为识别提示
+  hotwords?: string[];
 }
 
+type DecodingStrategy =
+  | { type: 'greedy'; best_of: number }
+  | { type: 'beam'; beam_size: number };
+
 interface RecognitionResult {
   text: string;
   confidence: number;
   is_temporary: boolean;
   speaker?: string;
   timestamp: number;
+  start_time: number; // 相对录音开始的秒数
+  end_time: number;
+}
+
+interface RecordingRolledOverEvent {
+  session_id: string;
+  part: number; // 新一段的序号，从1开始
+  previous_record_id: string;
+  record_id: string;
+  previous_file_path: string | null;
 }
 
 interface AudioLevelUpdate {
@@ -68,6 +94,8 @@ const RecordingWorkspace: React.FC<RecordingWorkspaceProps> = ({
   const [currentTranscript, setCurrentTranscript] = useState('');
   const [isProcessing, setIsProcessing] = useState(false);
   const [forceStopRequested, setForceStopRequested] = useState(false);
+  // 后端自动保存使用的记录ID，前端不再另建记录
+  const recordIdRef = useRef<string | null>(null);
 
   // 录音计时器
   useEffect(() => {
@@ -89,6 +117,8 @@ const RecordingWorkspace: React.FC<RecordingWorkspaceProps> = ({
     let unsubscribeRecognition: (() => void) | undefined;
     let unsubscribeError: (() => void) | undefined;
     let unsubscribeStop: (() => void) | undefined;
+    let unsubscribeStalled: (() => void) | undefined;
+    let unsubscribeRolledOver: (() => void) | undefined;
 
     const setupEventListeners = async () => {
       try {
@@ -135,6 +165,18 @@ const RecordingWorkspace: React.FC<RecordingWorkspaceProps> = ({
           setForceStopRequested(false);
         });
 
+        // 监听采集卡住事件（音频线程可能已退出）
+        unsubscribeStalled = await listen<{ stalled_secs: number }>('recording_stalled', (event) => {
+          console.warn(`No audio received for ${event.payload.stalled_secs.toFixed(1)}s`);
+        });
+
+        // 监听单段时长上限触发的自动拆分：之后的转录保存到新一段的记录
+        unsubscribeRolledOver = await listen<RecordingRolledOverEvent>('recording_rolled_over', (event) => {
+          const { part, previous_record_id, record_id } = event.payload;
+          console.log(`Recording rolled over to part ${part}: ${previous_record_id} -> ${record_id}`);
+          recordIdRef.current = record_id;
+        });
+
       } catch (error) {
         console.error('Failed to setup event listeners:', error);
       }
@@ -147,6 +189,8 @@ const RecordingWorkspace: React.FC<RecordingWorkspaceProps> = ({
       if (unsubscribeRecognition) unsubscribeRecognition();
       if (unsubscribeError) unsubscribeError();
       if (unsubscribeStop) unsubscribeStop();
+      if (unsubscribeStalled) unsubscribeStalled();
+      if (unsubscribeRolledOver) unsubscribeRolledOver();
     };
   }, []);
 
@@ -172,11 +216,12 @@ const RecordingWorkspace: React.FC<RecordingWorkspaceProps> = ({
         speaker_diarization: true, // 说话人识别
         noise_reduction: true, // 噪音降低
         auto_save: true, // 自动保存
-        save_interval: 5 // 5分钟保存一次
+        save_interval: 5, // 5分钟保存一次
+        initial_prompt: localStorage.getItem('whisperPrompt') || null, // 与文件转录使用同一提示词
       };
 
-      // 启动实时录音
-      await invoke('start_realtime_recording', { config });
+      // 启动实时录音，转录记录由后端以返回的ID自动保存
+      recordIdRef.current = await invoke<string>('start_realtime_recording', { config });
       
       setIsRecording(true);
       setIsPaused(false);
@@ -1,6 +1,7 @@
 import { useState, useEffect, useCallback, useRef } from 'react';
 import { invoke } from '@tauri-apps/api/core';
 import { listen, UnlistenFn } from '@tauri-apps/api/event';
+import { getErrorMessage } from '../utils/errors';
 
 // 新的优化配置接口
 export interface OptimalRealtimeConfig {
@@ -26,7 +27,9 @@ export interface TranscriptionResultEvent {
 
 // 音频级别事件
 export interface AudioLevelEvent {
-  level: number;
+  level: number; // 0..1
+  dbfs: number; // 平滑后的电平
+  peak_dbfs: number;
   speechProbability: number;
   timestamp: number;
 }
@@ -337,7 +340,7 @@ export const useOptimalRealtimeRecording = (): UseOptimalRealtimeRecordingReturn
       
     } catch (error) {
       console.error('开始优化录音失败:', error);
-      const errorMessage = error instanceof Error ? error.message : String(error);
+      const errorMessage = getErrorMessage(error);
       setError(`启动优化录音失败: ${errorMessage}`);
       setRecordingState(prev => ({ ...prev, status: 'idle' }));
       cleanup();
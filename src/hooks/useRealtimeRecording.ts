@@ -1,6 +1,7 @@
 import { useState, useEffect, useCallback, useRef } from 'react';
 import { invoke } from '@tauri-apps/api/core';
 import { listen, UnlistenFn } from '@tauri-apps/api/event';
+import { getErrorMessage } from '../utils/errors';
 
 export interface RealtimeConfig {
   language: 'zh' | 'en' | 'auto';
@@ -20,7 +21,9 @@ export interface RecognitionResult {
 }
 
 export interface AudioLevelUpdate {
-  level: number;
+  level: number; // 0..1
+  dbfs: number; // 平滑后的电平
+  peak_dbfs: number;
   timestamp: number;
 }
 
@@ -289,7 +292,7 @@ export const useRealtimeRecording = (): UseRealtimeRecordingReturn => {
     } catch (error) {
       console.error('开始录音失败:', error);
       console.error('错误详情:', JSON.stringify(error, null, 2));
-      const errorMessage = error instanceof Error ? error.message : String(error);
+      const errorMessage = getErrorMessage(error);
       setError(`启动录音失败: ${errorMessage}`);
       setRecordingState(prev => ({ ...prev, status: 'idle' }));
       cleanup();
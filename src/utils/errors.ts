@@ -0,0 +1,22 @@
+// 后端命令返回的结构化错误：{ code, message }
+export interface StenoError {
+  code: string;
+  message: string;
+}
+
+export const isStenoError = (error: unknown): error is StenoError =>
+  typeof error === 'object' &&
+  error !== null &&
+  typeof (error as StenoError).code === 'string' &&
+  typeof (error as StenoError).message === 'string';
+
+// 兼容尚未迁移、仍返回字符串的命令
+export const getErrorMessage = (error: unknown): string => {
+  if (isStenoError(error)) {
+    return error.message;
+  }
+  if (error instanceof Error) {
+    return error.message;
+  }
+  return String(error);
+};
@@ -120,9 +120,10 @@ class StorageService {
       speaker?: string;
       confidence?: number;
     }>;
-  }): Promise<void> {
+  }): Promise<string | null> {
     await this.init();
-    await invoke('update_transcription_result', { id, result });
+    // 开启自动命名时返回按内容生成的新名称
+    return await invoke<string | null>('update_transcription_result', { id, result });
   }
 
   async deleteRecord(id: string): Promise<void> {